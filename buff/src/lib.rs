@@ -3,9 +3,17 @@ use std::fmt;
 use tracing::error;
 
 mod impls;
+mod varint;
+
+pub use varint::{varint_size, MAX_VARINT_SIZE};
 
 /// Represents a type that may be serialized to bytes and deserialized from
 /// bytes.
+///
+/// The provided implementations use fixed-width integers in big-endian byte
+/// order (and IEEE 754 floats, also in big-endian byte order), regardless of
+/// the platform, so that the serialized bytes are portable. There are no
+/// implementations for platform-dependent types, such as `usize`.
 pub trait AsBytes: Sized {
     /// The serialized representation.
     type Repr;
@@ -17,11 +25,35 @@ pub trait AsBytes: Sized {
     fn deserialize(src: Self::Repr) -> Self;
 }
 
+/// The error returned by the fallible (`try_*`) buffer methods if there are not
+/// enough remaining bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError {
+    /// The number of bytes which were requested.
+    pub requested: usize,
+    /// The number of bytes which remained in the buffer.
+    pub remaining: usize,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "not enough capacity for {} more bytes ({} remaining)",
+            self.requested, self.remaining
+        )
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
 /// A fixed-size buffer (buff, aka. buf fixed).
 ///
 /// # Panics
 ///
-/// All `put_*` methods panic if there is not enough capacity.
+/// All `read_*` and `write_*` methods panic if there is not enough capacity.
+/// See the fallible `try_*` variants, which return an [`OverflowError`]
+/// instead.
 pub struct Buff<'a> {
     inner: &'a mut [u8],
     offset: usize,
@@ -83,6 +115,16 @@ impl<'a> Buff<'a> {
         T::deserialize(buf)
     }
 
+    /// Reads the type represented by [`AsBytes`], or fails if there are not
+    /// enough remaining bytes (in which case the offset isn't changed).
+    pub fn try_read<const S: usize, T>(&mut self) -> Result<T, OverflowError>
+    where
+        T: AsBytes<Repr = [u8; S]>,
+    {
+        let bytes = self.try_slice_to(S)?;
+        Ok(T::deserialize(bytes.try_into().expect("slice of length S")))
+    }
+
     /// Reads exactly the amount of bytes necessary to fill the given slice.
     pub fn read_slice(&mut self, dest: &mut [u8]) {
         dest.copy_from_slice(self.slice_to(dest.len()));
@@ -98,12 +140,30 @@ impl<'a> Buff<'a> {
         self.write_slice(data.as_ref());
     }
 
+    /// Writes the type represented by [`AsBytes`], or fails if there is not
+    /// enough capacity (in which case nothing is written).
+    pub fn try_write<T>(&mut self, src: T) -> Result<(), OverflowError>
+    where
+        T: AsBytes,
+        T::Repr: AsRef<[u8]>,
+    {
+        let data = src.serialize();
+        self.try_write_slice(data.as_ref())
+    }
+
     /// Writes the byte sequence into the buffer, starting at the current
     /// length.
     pub fn write_slice(&mut self, src: &[u8]) {
         self.slice_to(src.len()).copy_from_slice(src);
     }
 
+    /// Writes the byte sequence into the buffer, or fails if there is not
+    /// enough capacity (in which case nothing is written).
+    pub fn try_write_slice(&mut self, src: &[u8]) -> Result<(), OverflowError> {
+        self.try_slice_to(src.len())?.copy_from_slice(src);
+        Ok(())
+    }
+
     /// Writes `count` times the given byte.
     pub fn write_bytes(&mut self, count: usize, val: u8) {
         self.slice_to(count).fill(val);
@@ -153,14 +213,166 @@ impl Buff<'_> {
     ///
     /// This method also increments `self.len` by `count`.
     fn slice_to(&mut self, count: usize) -> &mut [u8] {
+        if !self.can_accommodate(count) {
+            error!(buff = ?self, "not enough capacity for {count} more bytes");
+            panic!("not enough capacity for {count} more bytes");
+        }
+        self.try_slice_to(count).expect("checked capacity")
+    }
+
+    /// Fallible version of [`Buff::slice_to`], which doesn't change the offset
+    /// on failure.
+    fn try_slice_to(&mut self, count: usize) -> Result<&mut [u8], OverflowError> {
         let lo = self.offset;
         let hi = lo + count;
         if hi > self.capacity() {
-            error!(buff = ?self, "not enough capacity for {count} more bytes");
-            panic!("not enough capacity for {count} more bytes");
+            return Err(OverflowError {
+                requested: count,
+                remaining: self.remaining(),
+            });
+        }
+        self.offset = hi;
+        Ok(&mut self.inner[lo..hi])
+    }
+}
+
+/// A read-only fixed-size buffer, which reads from a shared slice. Hence, data
+/// may be read (e.g., deserialized) without copying it first.
+///
+/// # Panics
+///
+/// All `read_*` methods panic if there are not enough remaining bytes.
+pub struct BuffRead<'a> {
+    inner: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> BuffRead<'a> {
+    /// Creates a new read-only buffer, `BuffRead`.
+    pub fn new(inner: &'a [u8]) -> BuffRead<'a> {
+        BuffRead { inner, offset: 0 }
+    }
+
+    /// Returns the underlying buffer.
+    pub fn get(&self) -> &'a [u8] {
+        self.inner
+    }
+
+    /// Returns the buffer capacity.
+    pub fn capacity(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns the remaining available bytes in the buffer.
+    pub fn remaining(&self) -> usize {
+        self.capacity() - self.offset
+    }
+
+    /// Returns the current offset.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Changes the underlying cursor offset position.
+    pub fn seek(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    /// Advances the cursor by the given delta.
+    pub fn seek_advance(&mut self, delta: usize) {
+        self.offset += delta;
+    }
+
+    /// Checks if the buffer has `n` more bytes.
+    pub fn can_accommodate(&self, n: usize) -> bool {
+        self.offset() + n <= self.capacity()
+    }
+
+    /// Reads the type represented by [`AsBytes`].
+    pub fn read<const S: usize, T>(&mut self) -> T
+    where
+        T: AsBytes<Repr = [u8; S]>,
+    {
+        let bytes = self.read_bytes(S);
+        T::deserialize(bytes.try_into().expect("slice of length S"))
+    }
+
+    /// Reads the type represented by [`AsBytes`], or fails if there are not
+    /// enough remaining bytes (in which case the offset isn't changed).
+    pub fn try_read<const S: usize, T>(&mut self) -> Result<T, OverflowError>
+    where
+        T: AsBytes<Repr = [u8; S]>,
+    {
+        let bytes = self.try_read_bytes(S)?;
+        Ok(T::deserialize(bytes.try_into().expect("slice of length S")))
+    }
+
+    /// Reads exactly the amount of bytes necessary to fill the given slice.
+    pub fn read_slice(&mut self, dest: &mut [u8]) {
+        dest.copy_from_slice(self.read_bytes(dest.len()));
+    }
+
+    /// Reads the next `count` bytes, without copying them.
+    pub fn read_bytes(&mut self, count: usize) -> &'a [u8] {
+        match self.try_read_bytes(count) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                error!(buff = ?self, "not enough capacity for {count} more bytes");
+                panic!("not enough capacity for {count} more bytes");
+            }
+        }
+    }
+
+    /// Fallible version of [`BuffRead::read_bytes`], which doesn't change the
+    /// offset on failure.
+    pub fn try_read_bytes(&mut self, count: usize) -> Result<&'a [u8], OverflowError> {
+        let lo = self.offset;
+        let hi = lo + count;
+        if hi > self.capacity() {
+            return Err(OverflowError {
+                requested: count,
+                remaining: self.remaining(),
+            });
         }
         self.offset = hi;
-        &mut self.inner[lo..hi]
+        Ok(&self.inner[lo..hi])
+    }
+
+    /// Creates a scope used to compute the byte delta.
+    pub fn delta<F, R>(&mut self, scope: F) -> (usize, R)
+    where
+        F: Fn(&mut Self) -> R,
+    {
+        let start = self.offset;
+        let ret = scope(self);
+        let delta = self.offset - start;
+        (delta, ret)
+    }
+
+    /// Creates a scope in which exactly `count` bytes must be read. See
+    /// [`Buff::scoped_exact`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scope didn't read `count` bytes.
+    pub fn scoped_exact<F, R>(&mut self, count: usize, scope: F) -> R
+    where
+        F: Fn(&mut Self) -> R,
+    {
+        let (delta, ret) = self.delta(scope);
+        assert_eq!(delta, count);
+        ret
+    }
+}
+
+impl fmt::Debug for BuffRead<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BuffRead")
+            .field("len", &self.offset)
+            .field("remaining", &self.remaining())
+            .field("capacity", &self.capacity())
+            .field("inner", &"<bytes>")
+            .finish()
     }
 }
 
@@ -179,6 +391,31 @@ impl fmt::Debug for Buff<'_> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_portable_byte_order() {
+        let mut orig_buf = [0_u8; 27];
+        let mut buf = Buff::new(&mut orig_buf);
+        buf.write(-2_i16);
+        buf.write(0x0102_0304_u32);
+        buf.write(-1_i64 << 8);
+        buf.write(1.5_f64);
+        buf.write(true);
+        buf.write(0xAB_u8);
+        assert_eq!(
+            buf.get(),
+            b"\xFF\xFE\x01\x02\x03\x04\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x00\
+              \x3F\xF8\x00\x00\x00\x00\x00\x00\x01\xAB\x00\x00\x00"
+        );
+
+        buf.seek(0);
+        assert_eq!(buf.read::<2, i16>(), -2);
+        assert_eq!(buf.read::<4, u32>(), 0x0102_0304);
+        assert_eq!(buf.read::<8, i64>(), -1 << 8);
+        assert_eq!(buf.read::<8, f64>(), 1.5);
+        assert!(buf.read::<1, bool>());
+        assert_eq!(buf.read::<1, u8>(), 0xAB);
+    }
+
     #[test]
     fn test_write() {
         let mut orig_buf = [0_u8; 10];
@@ -243,6 +480,73 @@ mod tests {
         let _: u8 = buf.read(); // BAM!
     }
 
+    #[test]
+    fn test_read_shared() {
+        let orig_buf = *b"\x01\xAB\xCD\xEF\x03\x9C\x03\x03\x01\x02";
+        let mut buf = BuffRead::new(&orig_buf);
+
+        let val: i32 = buf.read();
+        assert_eq!(val, 0x01ABCDEF_i32);
+        let val: u16 = buf.read();
+        assert_eq!(val, 0x39C_u16);
+        assert_eq!(buf.remaining(), 4);
+
+        let bytes = buf.read_bytes(3);
+        assert_eq!(bytes, b"\x03\x03\x01");
+        // The bytes are borrowed from the underlying slice.
+        assert!(std::ptr::eq(bytes, &orig_buf[6..9]));
+
+        buf.seek(4);
+        let mut dest = [0_u8; 2];
+        buf.read_slice(&mut dest);
+        assert_eq!(&dest, b"\x03\x9C");
+    }
+
+    #[test]
+    #[should_panic(expected = "not enough capacity for 2 more bytes")]
+    fn test_overflow_read_shared() {
+        let orig_buf = [1, 2, 3];
+        let mut buf = BuffRead::new(&orig_buf);
+
+        let _: u16 = buf.read();
+        let _: u16 = buf.read(); // BAM!
+    }
+
+    #[test]
+    fn test_try_read_write() {
+        let mut orig_buf = [0_u8; 5];
+        let mut buf = Buff::new(&mut orig_buf);
+
+        buf.try_write(0x0102_0304_u32).unwrap();
+        let error = buf.try_write(0x0506_u16).unwrap_err();
+        assert_eq!(
+            error,
+            OverflowError {
+                requested: 2,
+                remaining: 1
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "not enough capacity for 2 more bytes (1 remaining)"
+        );
+        // Failed writes don't change the buffer.
+        assert_eq!(buf.offset(), 4);
+        assert!(buf.try_write_slice(b"\x05\x06").is_err());
+        buf.try_write_slice(b"\x05").unwrap();
+        assert_eq!(buf.get(), b"\x01\x02\x03\x04\x05");
+
+        buf.seek(3);
+        assert!(buf.try_read::<4, u32>().is_err());
+        assert_eq!(buf.offset(), 3);
+        assert_eq!(buf.try_read::<2, u16>(), Ok(0x0405));
+
+        let mut buf = BuffRead::new(&orig_buf);
+        assert_eq!(buf.try_read::<4, u32>(), Ok(0x0102_0304));
+        assert!(buf.try_read::<2, u16>().is_err());
+        assert_eq!(buf.try_read_bytes(1), Ok(&b"\x05"[..]));
+    }
+
     #[test]
     fn test_seek() {
         let mut orig_buf = [1, 2, 3, 4];
@@ -0,0 +1,187 @@
+//! Variable-length integers, encoded as unsigned LEB128, and length-prefixed
+//! byte sequences.
+//!
+//! Each byte stores 7 bits of the integer, starting from the least significant
+//! ones, and has its most significant bit set if more bytes follow. Hence,
+//! integers smaller than 128 take a single byte.
+
+use crate::{Buff, BuffRead, OverflowError};
+
+/// The maximum size of an encoded `u64`.
+pub const MAX_VARINT_SIZE: usize = 10;
+
+/// Returns the size of the given integer, once encoded as a varint.
+pub const fn varint_size(value: u64) -> usize {
+    let bits = u64::BITS - value.leading_zeros();
+    if bits == 0 {
+        1
+    } else {
+        bits.div_ceil(7) as usize
+    }
+}
+
+/// Encodes the given integer, returning the used prefix of the array.
+fn encode(mut value: u64, bytes: &mut [u8; MAX_VARINT_SIZE]) -> &[u8] {
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes[len] = byte;
+            return &bytes[..=len];
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+impl Buff<'_> {
+    /// Writes the given integer as a varint.
+    pub fn write_varint(&mut self, value: u64) {
+        self.write_slice(encode(value, &mut [0; MAX_VARINT_SIZE]));
+    }
+
+    /// Writes the given integer as a varint, or fails if there is not enough
+    /// capacity (in which case nothing is written).
+    pub fn try_write_varint(&mut self, value: u64) -> Result<(), OverflowError> {
+        self.try_write_slice(encode(value, &mut [0; MAX_VARINT_SIZE]))
+    }
+
+    /// Writes `len` as a varint, followed by the scope, which must write
+    /// exactly `len` bytes. Fails, without writing anything, if there is not
+    /// enough capacity for both. See [`BuffRead::read_prefixed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scope didn't write `len` bytes.
+    pub fn try_write_prefixed<F, R, E>(&mut self, len: usize, scope: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Self) -> Result<R, E>,
+        E: From<OverflowError>,
+    {
+        let size = varint_size(len as u64) + len;
+        if !self.can_accommodate(size) {
+            return Err(OverflowError {
+                requested: size,
+                remaining: self.remaining(),
+            }
+            .into());
+        }
+        self.try_write_varint(len as u64)?;
+        let start = self.offset();
+        let ret = scope(self)?;
+        assert_eq!(self.offset() - start, len, "prefixed scope length mismatch");
+        Ok(ret)
+    }
+}
+
+impl<'a> BuffRead<'a> {
+    /// Reads a varint. Returns `None` if it is malformed, i.e., if it doesn't
+    /// fit an `u64` or if the buffer ends before it does.
+    pub fn read_varint(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for i in 0..MAX_VARINT_SIZE {
+            let byte: u8 = self.try_read().ok()?;
+            if i == MAX_VARINT_SIZE - 1 && byte > 1 {
+                return None;
+            }
+            value |= u64::from(byte & 0x7F) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// Reads a varint length, returning a buffer over the bytes which follow
+    /// it (which are skipped). Returns `None` if the length is malformed or if
+    /// there are not enough remaining bytes. See [`Buff::try_write_prefixed`].
+    pub fn read_prefixed(&mut self) -> Option<BuffRead<'a>> {
+        let len = usize::try_from(self.read_varint()?).ok()?;
+        let bytes = self.try_read_bytes(len).ok()?;
+        Some(BuffRead::new(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint() {
+        const CASES: &[(u64, &[u8])] = &[
+            (0, b"\x00"),
+            (1, b"\x01"),
+            (127, b"\x7F"),
+            (128, b"\x80\x01"),
+            (300, b"\xAC\x02"),
+            (16_383, b"\xFF\x7F"),
+            (16_384, b"\x80\x80\x01"),
+            (u64::MAX, b"\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x01"),
+        ];
+        for &(value, encoded) in CASES {
+            assert_eq!(varint_size(value), encoded.len(), "{value}");
+
+            let mut bytes = [0; MAX_VARINT_SIZE];
+            let mut buf = Buff::new(&mut bytes);
+            buf.write_varint(value);
+            assert_eq!(&buf.get()[..buf.offset()], encoded, "{value}");
+
+            let mut buf = BuffRead::new(encoded);
+            assert_eq!(buf.read_varint(), Some(value));
+            assert_eq!(buf.remaining(), 0);
+        }
+    }
+
+    #[test]
+    fn test_malformed_varint() {
+        // Truncated.
+        assert_eq!(BuffRead::new(b"\x80\x80").read_varint(), None);
+        // Doesn't fit an `u64`.
+        let too_big = b"\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x02";
+        assert_eq!(BuffRead::new(too_big).read_varint(), None);
+        let too_long = [0x80; MAX_VARINT_SIZE + 1];
+        assert_eq!(BuffRead::new(&too_long).read_varint(), None);
+
+        let mut bytes = [0; 1];
+        let mut buf = Buff::new(&mut bytes);
+        assert!(buf.try_write_varint(128).is_err());
+        assert_eq!(buf.offset(), 0);
+    }
+
+    #[test]
+    fn test_prefixed() {
+        let mut bytes = [0; 8];
+        let mut buf = Buff::new(&mut bytes);
+        buf.try_write_prefixed(3, |buf| buf.try_write_slice(b"abc"))
+            .unwrap();
+        // Nothing is written if the whole sequence doesn't fit.
+        let result = buf.try_write_prefixed(4, |buf| buf.try_write_slice(b"defg"));
+        assert_eq!(
+            result,
+            Err(OverflowError {
+                requested: 5,
+                remaining: 4
+            })
+        );
+        buf.try_write_prefixed(2, |buf| buf.try_write_slice(b"de"))
+            .unwrap();
+        assert_eq!(&bytes[..7], b"\x03abc\x02de");
+
+        let mut buf = BuffRead::new(&bytes);
+        assert_eq!(buf.read_prefixed().unwrap().get(), b"abc");
+        let mut inner = buf.read_prefixed().unwrap();
+        assert_eq!(inner.read::<1, u8>(), b'd');
+        assert_eq!(buf.offset(), 7);
+        // The length exceeds the remaining bytes.
+        assert!(BuffRead::new(b"\x03ab").read_prefixed().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "prefixed scope length mismatch")]
+    fn test_prefixed_mismatch() {
+        let mut bytes = [0; 8];
+        let mut buf = Buff::new(&mut bytes);
+        let _ = buf.try_write_prefixed(3, |buf| buf.try_write_slice(b"ab"));
+    }
+}
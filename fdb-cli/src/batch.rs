@@ -0,0 +1,91 @@
+//! The non-interactive mode (i.e., `fdb-cli exec`), which executes a script of
+//! SQL statements, from a file (`--file <path>`) or from the command line
+//! (`-c <sql>`).
+//!
+//! Each statement's output is printed to the stdout, in the chosen
+//! [`OutputFormat`] (by default, a single line of JSON per statement).
+//!
+//! The whole script is parsed before any statement is executed. Execution stops
+//! at the first failing statement, whose error is printed to the stderr.
+//!
+//! The statements are executed within a single session, hence scripts may use
+//! transactions (e.g., `BEGIN` and `COMMIT`). A transaction which is still
+//! running once the execution stops is rolled back.
+
+use std::io::{self, Write};
+
+use fdb::{sql::parser::parse_script, Db};
+
+use crate::output::OutputFormat;
+
+/// The exit code of a script whose statements all succeeded.
+pub const EXIT_SUCCESS: i32 = 0;
+/// The exit code of a script which couldn't be read or parsed, or whose
+/// execution failed.
+pub const EXIT_FAILURE: i32 = 1;
+/// The exit code of invalid command line arguments.
+pub const EXIT_USAGE: i32 = 2;
+
+/// Where the script is read from.
+pub enum Script {
+    File(String),
+    Command(String),
+}
+
+impl Script {
+    /// Parses the arguments of the `exec` command.
+    pub fn from_args(args: &[String]) -> Result<Script, String> {
+        match args {
+            [flag, path] if flag == "--file" || flag == "-f" => Ok(Script::File(path.clone())),
+            [flag, sql] if flag == "-c" => Ok(Script::Command(sql.clone())),
+            [flag] => Err(format!("missing value for `{flag}`")),
+            _ => Err("expected either `--file <path>` or `-c <sql>`".into()),
+        }
+    }
+
+    fn read(self) -> io::Result<String> {
+        match self {
+            Script::File(path) => std::fs::read_to_string(path),
+            Script::Command(sql) => Ok(sql),
+        }
+    }
+}
+
+/// Executes the given script, returning the process exit code.
+pub async fn run(db: &Db, script: Script, format: OutputFormat) -> i32 {
+    let src = match script.read() {
+        Ok(src) => src,
+        Err(error) => {
+            eprintln!("error: can't read script: {error}");
+            return EXIT_FAILURE;
+        }
+    };
+    let statements = match parse_script(&src) {
+        Ok(statements) => statements,
+        Err(error) => {
+            eprintln!("error: {error}");
+            return EXIT_FAILURE;
+        }
+    };
+
+    let mut session = db.session();
+    let mut stdout = io::stdout();
+    for (i, statement) in statements.into_iter().enumerate() {
+        match session.execute_statement(statement).await {
+            Ok(output) => {
+                if format.write_output(&mut stdout, &output).is_err() {
+                    return EXIT_FAILURE;
+                }
+            }
+            Err(error) => {
+                let _ = stdout.flush();
+                eprintln!("error: statement {}: {error}", i + 1);
+                return EXIT_FAILURE;
+            }
+        }
+    }
+    if stdout.flush().is_err() {
+        return EXIT_FAILURE;
+    }
+    EXIT_SUCCESS
+}
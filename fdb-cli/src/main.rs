@@ -8,31 +8,165 @@ use std::{
 use fdb::{
     catalog::{
         column::Column,
-        object::{Object, ObjectType},
-        page::{HeapPage, SpecificPage},
+        object::Object,
         table_schema::TableSchema,
         ty::{PrimitiveTypeId, TypeId},
     },
     error::DbResult,
     exec::{query, value::Value, values::Values},
-    Db,
+    tools, Db,
 };
 use tracing::instrument;
 
+mod batch;
+mod output;
+
+use output::OutputFormat;
+
+const USAGE: &str = "usage: fdb-cli [--format table|csv|json] \
+    [exec (--file <path> | -c <sql>) | check | salvage <src> <dst>]";
+
+/// A non-interactive command.
+enum Command {
+    Exec(batch::Script),
+    Check,
+    /// Salvages the source database into the destination one.
+    Salvage(String, String),
+}
+
 #[tokio::main]
 async fn main() -> DbResult<()> {
     setup_tracing();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (format, args) = match &args[..] {
+        [flag, format, args @ ..] if flag == "--format" => match format.parse::<OutputFormat>() {
+            Ok(format) => (Some(format), args),
+            Err(error) => usage_error(&error),
+        },
+        [flag] if flag == "--format" => usage_error("missing value for `--format`"),
+        args => (None, args),
+    };
+    let command = match args.split_first() {
+        None => None,
+        Some((command, args)) if command == "exec" => match batch::Script::from_args(args) {
+            Ok(script) => Some(Command::Exec(script)),
+            Err(error) => usage_error(&error),
+        },
+        Some((command, [])) if command == "check" => Some(Command::Check),
+        Some((command, _)) if command == "check" => usage_error("`check` takes no arguments"),
+        Some((command, [src, dst])) if command == "salvage" => {
+            Some(Command::Salvage(src.clone(), dst.clone()))
+        }
+        Some((command, _)) if command == "salvage" => {
+            usage_error("`salvage` takes a source and a destination path")
+        }
+        Some((command, _)) => usage_error(&format!("unknown command `{command}`")),
+    };
+
+    // The salvage works on the given databases, instead of the default one.
+    if let Some(Command::Salvage(src, dst)) = &command {
+        let code = salvage(Path::new(src), Path::new(dst)).await?;
+        std::process::exit(code);
+    }
+
     let (db, first_access) = Db::open(Path::new("ignore/my-db")).await?;
     if first_access {
         define_test_catalog(&db).await?;
     }
 
+    // Scripts are meant to be read by other programs, hence JSON by default.
+    let code = match command {
+        Some(Command::Exec(script)) => {
+            batch::run(&db, script, format.unwrap_or(OutputFormat::Json)).await
+        }
+        Some(Command::Check) => check(&db).await?,
+        Some(Command::Salvage(..)) => unreachable!(),
+        None => {
+            interactive(&db, format.unwrap_or(OutputFormat::Table)).await?;
+            batch::EXIT_SUCCESS
+        }
+    };
+
+    db.close().await?;
+    std::process::exit(code);
+}
+
+/// Prints the given error along with the usage, exiting.
+fn usage_error(error: &str) -> ! {
+    eprintln!("error: {error}\n{USAGE}");
+    std::process::exit(batch::EXIT_USAGE);
+}
+
+/// Checks the database integrity, printing the problems found. Returns the
+/// process exit code.
+async fn check(db: &Db) -> DbResult<i32> {
+    let report = db.check_integrity().await?;
+    for problem in &report.problems {
+        println!("{problem}");
+    }
+    println!(
+        "checked {} of {} pages: {} problem(s)",
+        report.pages_checked,
+        report.page_count,
+        report.problems.len()
+    );
+    Ok(if report.is_ok() {
+        batch::EXIT_SUCCESS
+    } else {
+        batch::EXIT_FAILURE
+    })
+}
+
+/// Salvages the database at the given source path into a new database at the
+/// given destination path, printing the salvaged records. Returns the process
+/// exit code, which signals a failure if anything was lost.
+async fn salvage(src: &Path, dst: &Path) -> DbResult<i32> {
+    let db = tools::open_read_only(src).await?;
+    let report = db.salvage_to(dst).await?;
+    for (table, salvage) in &report.tables {
+        println!(
+            "{table}: {} record(s) salvaged, {} lost, {} damaged page(s){}",
+            salvage.records,
+            salvage.lost_records,
+            salvage.damaged_pages,
+            if salvage.complete { "" } else { ", incomplete" },
+        );
+    }
+    println!("{} index(es) rebuilt", report.indexes);
+    for object in &report.skipped_objects {
+        println!("skipped `{object}`");
+    }
+    if !report.schema.is_lossless() {
+        println!("the schema is damaged: some objects may be lost");
+    }
+    Ok(if report.is_lossless() {
+        batch::EXIT_SUCCESS
+    } else {
+        batch::EXIT_FAILURE
+    })
+}
+
+/// Runs the interactive prompt loop, until the `quit` command.
+///
+/// The `sql` statements run in a session kept across commands, so that they
+/// may span a transaction (see `BEGIN`). Meanwhile, the other commands are
+/// refused, since they would wait for the transaction to end.
+async fn interactive(db: &Db, format: OutputFormat) -> DbResult<()> {
+    let mut stdout = io::stdout();
+    let mut session = db.session();
     loop {
-        let table = Object::find(&db, "chess_matches").await?.try_into_table()?;
+        let table = Object::find(db, "chess_matches").await?.try_into_table()?;
 
-        println!("Pick a command: `insert`, `select`, `delete`, `update` or `quit`.");
-        match &*input::<String>("cmd> ") {
+        println!("Pick a command: `insert`, `select`, `delete`, `update`, `sql`, `import`, `export` or `quit`.");
+        let cmd = input::<String>("cmd> ");
+        if session.in_transaction() && !matches!(&*cmd, "sql" | "quit") {
+            println!(
+                "a transaction is running; end it through `sql` (`COMMIT` or `ROLLBACK`) first."
+            );
+            continue;
+        }
+        match &*cmd {
             "insert" => {
                 let id: i32 = input("id (int)> ");
                 let name: String = input("name (text)> ");
@@ -55,17 +189,17 @@ async fn main() -> DbResult<()> {
             "select" => {
                 let select_query = query::table::Select::new(&table);
 
-                println!("{}", "-".repeat(50));
+                let mut rows = Vec::new();
                 db.execute(select_query, |row| {
-                    let id = row.get("id").unwrap();
-                    let name = row.get("name").unwrap();
-                    let age = row.get("age").unwrap();
-                    println!("{id:<4} | {name:<20} | {age:<4}");
+                    rows.push(row);
                     Ok::<_, ()>(())
                 })
                 .await?
                 .unwrap();
-                println!("{}", "-".repeat(50));
+                let columns: Vec<_> = (table.schema.columns.iter())
+                    .map(|column| column.name.clone())
+                    .collect();
+                format.write_rows(&mut stdout, &columns, &rows).unwrap();
             }
             "delete" => {
                 let id: i32 = input("id (int)> ");
@@ -95,13 +229,61 @@ async fn main() -> DbResult<()> {
                 let del = query::table::Update::new(&table, &pred, &updater);
                 db.execute(del, |_| Ok::<_, ()>(())).await?.unwrap();
             }
-            "quit" => break,
+            "sql" => {
+                let sql: String = input("sql> ");
+                match session.execute(&sql).await {
+                    Ok(output) => format.write_output(&mut stdout, &output).unwrap(),
+                    Err(error) => println!("error: {error}"),
+                }
+            }
+            "import" => {
+                let path: String = input("path (csv)> ");
+                let mut count = 0;
+                let import = query::table::ImportCsv::new(&table, path);
+                match db
+                    .execute(import, |()| {
+                        count += 1;
+                        Ok::<_, ()>(())
+                    })
+                    .await
+                {
+                    Ok(result) => {
+                        result.unwrap();
+                        println!("ok ({count} rows)");
+                    }
+                    Err(error) => println!("error: {error}"),
+                }
+            }
+            "export" => {
+                let path: String = input("path (csv)> ");
+                let mut count = 0;
+                let export = query::table::ExportCsv::new(query::table::Select::new(&table), path);
+                match db
+                    .execute(export, |()| {
+                        count += 1;
+                        Ok::<_, ()>(())
+                    })
+                    .await
+                {
+                    Ok(result) => {
+                        result.unwrap();
+                        println!("ok ({count} rows)");
+                    }
+                    Err(error) => println!("error: {error}"),
+                }
+            }
+            "quit" => {
+                if session.in_transaction() {
+                    println!("rolling back the running transaction");
+                    session.execute("ROLLBACK").await?;
+                }
+                break;
+            }
             _ => {
                 println!("invalid option; try again.");
             }
         }
     }
-
     Ok(())
 }
 
@@ -141,28 +323,11 @@ fn input<T: FromStr>(prompt: &str) -> T {
     }
 }
 
-// TODO: While this database doesn't support user-defined tables (aka. `CREATE
-// TABLE`), during bootstrap, one allocates a specific catalog to use for
-// testing purposes.
+/// Defines the `chess_matches` table, used by the interactive commands.
 #[instrument(level = "debug", skip_all)]
 pub async fn define_test_catalog(db: &Db) -> DbResult<()> {
-    let test_page_guard = db.pager().alloc(HeapPage::new_seq_first).await?;
-    let test_page = test_page_guard.write().await;
-
-    let object = Object {
-        ty: ObjectType::Table(get_chess_matches_schema()),
-        // TODO: The page allocation should be encapsulated in the create object
-        // implementation.
-        page_id: test_page.id(),
-        name: "chess_matches".into(),
-    };
-
-    let query = query::object::Create::new(&object);
+    let query = query::object::CreateTable::new("chess_matches", get_chess_matches_schema());
     db.execute(query, |_| Ok::<(), ()>(())).await?.unwrap();
-
-    test_page.flush();
-    db.pager().flush_all().await?;
-
     Ok(())
 }
 
@@ -172,14 +337,23 @@ fn get_chess_matches_schema() -> TableSchema {
             Column {
                 ty: TypeId::Primitive(PrimitiveTypeId::Int),
                 name: "id".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
             },
             Column {
                 ty: TypeId::Primitive(PrimitiveTypeId::Text),
                 name: "name".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
             },
             Column {
                 ty: TypeId::Primitive(PrimitiveTypeId::Int),
                 name: "age".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
             },
         ],
     }
@@ -0,0 +1,177 @@
+//! Rendering of the statement results, in one of the [`OutputFormat`]s.
+
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+use fdb::{
+    exec::{
+        format::{BlobFormat, TimestampFormat, ValueFormat},
+        value::Value,
+        values::Values,
+    },
+    sql::planner::SqlOutput,
+};
+use serde_json::{json, Value as Json};
+
+/// The format of the printed results.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// An aligned table, for humans.
+    Table,
+    /// A header line with the column names, followed by a line for each row.
+    /// Statements which yield no rows print nothing.
+    Csv,
+    /// A single line of JSON for each statement:
+    ///
+    /// - `{"columns": [...], "rows": [[...], ...]}` for a `SELECT`;
+    /// - `{"affected": <count>}` for an `INSERT`, `UPDATE` or `DELETE`;
+    /// - `{"done": true}` for other statements (e.g., a `CREATE TABLE`).
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "unknown format `{s}` (expected `table`, `csv` or `json`)"
+            )),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Writes the given statement output.
+    pub fn write_output(&self, out: &mut impl Write, output: &SqlOutput) -> io::Result<()> {
+        match output {
+            SqlOutput::Rows { columns, rows } => self.write_rows(out, columns, rows),
+            SqlOutput::Affected(count) => match self {
+                OutputFormat::Table => writeln!(out, "ok ({count} rows)"),
+                OutputFormat::Csv => Ok(()),
+                OutputFormat::Json => writeln!(out, "{}", json!({ "affected": count })),
+            },
+            SqlOutput::Done => match self {
+                OutputFormat::Table => writeln!(out, "ok"),
+                OutputFormat::Csv => Ok(()),
+                OutputFormat::Json => writeln!(out, "{}", json!({ "done": true })),
+            },
+        }
+    }
+
+    /// Writes the given rows, projecting the given columns (in order). Missing
+    /// values are written as empty (or `null`, in JSON).
+    pub fn write_rows(
+        &self,
+        out: &mut impl Write,
+        columns: &[String],
+        rows: &[Values],
+    ) -> io::Result<()> {
+        match self {
+            OutputFormat::Table => write_table(out, columns, rows),
+            OutputFormat::Csv => write_csv(out, columns, rows),
+            OutputFormat::Json => {
+                let rows: Vec<Json> = (rows.iter())
+                    .map(|row| {
+                        (columns.iter())
+                            .map(|column| row.get(column).map_or(Json::Null, Value::to_json))
+                            .collect()
+                    })
+                    .collect();
+                writeln!(out, "{}", json!({ "columns": columns, "rows": rows }))
+            }
+        }
+    }
+}
+
+/// Writes an aligned table, whose column widths fit the header and all values.
+/// Numbers are right-aligned.
+fn write_table(out: &mut impl Write, columns: &[String], rows: &[Values]) -> io::Result<()> {
+    let format = ValueFormat::default();
+    let cells: Vec<Vec<(String, bool)>> = (rows.iter())
+        .map(|row| {
+            (columns.iter())
+                .map(|column| match row.get(column) {
+                    Some(value) => (format.format(value), is_numeric(value)),
+                    None => (String::new(), false),
+                })
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = (columns.iter().enumerate())
+        .map(|(i, column)| {
+            (cells.iter())
+                .map(|row| row[i].0.chars().count())
+                .fold(column.chars().count(), usize::max)
+        })
+        .collect();
+
+    let header: Vec<_> = (columns.iter().zip(&widths))
+        .map(|(column, width)| format!("{column:<width$}"))
+        .collect();
+    writeln!(out, "{}", header.join(" | ").trim_end())?;
+    let rule: Vec<_> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    writeln!(out, "{}", rule.join("-+-"))?;
+    for row in &cells {
+        let row: Vec<_> = (row.iter().zip(&widths))
+            .map(|((cell, numeric), width)| {
+                if *numeric {
+                    format!("{cell:>width$}")
+                } else {
+                    format!("{cell:<width$}")
+                }
+            })
+            .collect();
+        writeln!(out, "{}", row.join(" | ").trim_end())?;
+    }
+    let plural = if rows.len() == 1 { "" } else { "s" };
+    writeln!(out, "({} row{plural})", rows.len())
+}
+
+fn is_numeric(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Byte(_)
+            | Value::ShortInt(_)
+            | Value::Int(_)
+            | Value::BigInt(_)
+            | Value::Float(_)
+            | Value::Decimal(_)
+    )
+}
+
+/// Writes the rows as CSV, in the format read by the database's CSV imports
+/// (i.e., timestamps as milliseconds and blobs in hexadecimal).
+fn write_csv(out: &mut impl Write, columns: &[String], rows: &[Values]) -> io::Result<()> {
+    let format = ValueFormat {
+        timestamp: TimestampFormat::Raw,
+        blob: BlobFormat::Hex,
+    };
+    let header: Vec<_> = columns.iter().map(|column| quote(column)).collect();
+    writeln!(out, "{}", header.join(","))?;
+    for row in rows {
+        let fields: Vec<_> = (columns.iter())
+            .map(|column| match row.get(column) {
+                Some(Value::Text(text)) => quote(text),
+                Some(value) => quote(&format.format(value)),
+                None => String::new(),
+            })
+            .collect();
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+/// Quotes the given CSV field if it contains commas, quotes or line breaks.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
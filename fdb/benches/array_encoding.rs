@@ -0,0 +1,73 @@
+//! Array value encoding benchmarks.
+//!
+//! Run with `cargo bench -p fdb --bench array_encoding`. Since no benchmark
+//! harness is used, each case is simply timed over a fixed number of rounds.
+
+use std::{hint::black_box, time::Instant};
+
+use fdb::{
+    catalog::ty::PrimitiveTypeId,
+    exec::value::Value,
+    util::io::{DeserializeCtx, Serialize, Size},
+};
+
+const LEN: usize = 4096;
+const ROUNDS: u32 = 200;
+
+fn main() {
+    // A simple LCG, to avoid depending on a random number generator crate.
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+        seed >> 33
+    };
+
+    let sorted: Vec<_> = (0..LEN as i64).map(|i| 1_000_000 + i * 3).collect();
+    let small: Vec<_> = (0..LEN).map(|_| (next() % 1000) as i64).collect();
+    let random: Vec<_> = (0..LEN).map(|_| next() as i64 * next() as i64).collect();
+
+    bench("sorted bigint", PrimitiveTypeId::BigInt, &sorted);
+    bench("small random bigint", PrimitiveTypeId::BigInt, &small);
+    bench("full random bigint", PrimitiveTypeId::BigInt, &random);
+    bench("sorted int", PrimitiveTypeId::Int, &sorted);
+    bench("small random int", PrimitiveTypeId::Int, &small);
+}
+
+fn bench(name: &str, element_type: PrimitiveTypeId, ints: &[i64]) {
+    let elements = ints
+        .iter()
+        .map(|&int| match element_type {
+            PrimitiveTypeId::Int => Value::Int(int as i32),
+            _ => Value::BigInt(int),
+        })
+        .collect();
+    let value = Value::Array(element_type, elements);
+    let width = match element_type {
+        PrimitiveTypeId::Int => 4,
+        _ => 8,
+    };
+    let type_id = value.type_id();
+
+    let size = value.size() as usize;
+    let full_width = 2 + ints.len() * width;
+    let mut bytes = vec![0; size];
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut buf = buff::Buff::new(&mut bytes);
+        black_box(&value).serialize(&mut buf).unwrap();
+    }
+    let encode = start.elapsed() / ROUNDS;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut buf = buff::BuffRead::new(&bytes);
+        black_box(Value::deserialize(&mut buf, &type_id).unwrap());
+    }
+    let decode = start.elapsed() / ROUNDS;
+
+    println!(
+        "{name:<20} {size:>6} bytes ({:>5.1}% of {full_width}), encode {encode:>10?}, decode {decode:>10?}",
+        size as f64 * 100.0 / full_width as f64,
+    );
+}
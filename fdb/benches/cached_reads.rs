@@ -0,0 +1,71 @@
+//! Index lookup benchmarks over a fully cached database.
+//!
+//! Run with `cargo bench -p fdb --bench cached_reads`. Since no benchmark
+//! harness is used, the lookups are simply timed over a fixed number of rounds.
+
+use std::{collections::HashMap, hint::black_box, path::Path, time::Instant};
+
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::DbResult,
+    exec::{operations::index::BTree, query, value::Value, values::Values},
+    Db,
+};
+
+const ROWS: i32 = 5000;
+const ROUNDS: u32 = 20;
+
+#[tokio::main]
+async fn main() -> DbResult<()> {
+    let path = Path::new("ignore/cached-reads-bench.db");
+    tokio::fs::create_dir_all("ignore").await?;
+    let _ = tokio::fs::remove_file(path).await;
+    let (db, _) = Db::open(path).await?;
+
+    let schema = TableSchema {
+        columns: vec![Column {
+            ty: TypeId::Primitive(PrimitiveTypeId::Int),
+            name: "id".into(),
+            max_len: None,
+            default: None,
+            not_null: false,
+        }],
+    };
+    let create = query::object::CreateTable::new("bench", schema);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let table = Object::find(&db, "bench").await?.try_into_table()?;
+    for id in 0..ROWS {
+        let values = Values::from(HashMap::from([("id".into(), Value::Int(id))]));
+        let insert = query::table::Insert::new(&table, values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    let create = query::index::Create::new("bench_by_id", "bench", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let index = Object::find(&db, "bench_by_id").await?.try_into_index()?;
+    let tree = BTree::new(index.page_id);
+
+    let (hits, misses) = db.pager().cache_stats();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for id in 0..ROWS {
+            black_box(tree.search(db.pager(), &Value::Int(id)).await?);
+        }
+    }
+    let lookup = start.elapsed() / (ROUNDS * ROWS as u32);
+    let (new_hits, new_misses) = db.pager().cache_stats();
+
+    println!(
+        "index lookup {lookup:>10?} ({} page reads, {} misses)",
+        new_hits - hits,
+        new_misses - misses,
+    );
+
+    drop(db);
+    tokio::fs::remove_file(path).await?;
+    Ok(())
+}
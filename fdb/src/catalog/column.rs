@@ -1,11 +1,25 @@
 use crate::{
     catalog::ty::TypeId,
-    error::DbResult,
-    util::io::{Deserialize, Serialize, Size, VarString},
+    error::{DbResult, Error},
+    exec::value::Value,
+    util::io::{Deserialize, DeserializeCtx, Serialize, Size, VarString},
 };
 
+/// The type tag flag which signals that the column's attributes byte (see
+/// [`ATTR_MAX_LEN`], [`ATTR_DEFAULT`] and [`ATTR_NOT_NULL`]) follows the type.
+/// Type tags never use their most significant bit (see [`TypeId`]).
+const ATTRS_FLAG: u8 = 0b1000_0000;
+
+/// The attribute which signals that the column's maximum length follows.
+const ATTR_MAX_LEN: u8 = 0b001;
+/// The attribute which signals that the column's default value follows, after
+/// the maximum length (if any).
+const ATTR_DEFAULT: u8 = 0b010;
+/// The attribute which signals that the column is declared as `NOT NULL`.
+const ATTR_NOT_NULL: u8 = 0b100;
+
 /// A column definition.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Column {
     /// The column value type.
     pub ty: TypeId,
@@ -13,30 +27,94 @@ pub struct Column {
     ///
     /// The column name may have at most 64 bytes.
     pub name: String,
+    /// The maximum length (in bytes) of the column values, which is enforced
+    /// when records are inserted or updated. Only text and blob columns may be
+    /// bounded.
+    pub max_len: Option<u16>,
+    /// The value assigned to the column when it is omitted by an insert, which
+    /// must be of the column type. Without it, the zero value of the type (see
+    /// [`Value::default_for_type`]) is assigned, unless the column is declared
+    /// as `NOT NULL`.
+    pub default: Option<Value>,
+    /// Whether the column is declared as `NOT NULL`, in which case inserts
+    /// which omit it fail (see [`Error::MissingValue`]), unless it has a
+    /// default value. Since values are never null, this only forbids omitting
+    /// the column.
+    pub not_null: bool,
+}
+
+impl Column {
+    /// Returns the column's attributes byte. See [`ATTRS_FLAG`].
+    fn attrs(&self) -> u8 {
+        let mut attrs = 0;
+        if self.max_len.is_some() {
+            attrs |= ATTR_MAX_LEN;
+        }
+        if self.default.is_some() {
+            attrs |= ATTR_DEFAULT;
+        }
+        if self.not_null {
+            attrs |= ATTR_NOT_NULL;
+        }
+        attrs
+    }
 }
 
 impl Size for Column {
     fn size(&self) -> u32 {
-        self.ty.size() + VarString::from(self.name.as_str()).size()
+        let attrs_size = if self.attrs() != 0 { 1 } else { 0 };
+        let max_len_size = if self.max_len.is_some() { 2 } else { 0 };
+        let default_size = self.default.as_ref().map_or(0, Value::size);
+        self.ty.size()
+            + attrs_size
+            + max_len_size
+            + default_size
+            + VarString::from(self.name.as_str()).size()
     }
 }
 
 impl Serialize for Column {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
-        self.ty.serialize(buf)?;
+        let attrs = self.attrs();
+        let flags = if attrs != 0 { ATTRS_FLAG } else { 0 };
+        self.ty.serialize_with_flags(flags, buf)?;
+        if attrs != 0 {
+            buf.write(attrs);
+        }
+        if let Some(max_len) = self.max_len {
+            buf.write(max_len);
+        }
+        if let Some(default) = &self.default {
+            default.serialize(buf)?;
+        }
         VarString::from(self.name.as_str()).serialize(buf)?;
         Ok(())
     }
 }
 
 impl Deserialize<'_> for Column {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
+        let tag: u8 = buf.read();
+        let ty = TypeId::deserialize_tagged(tag & !ATTRS_FLAG, buf)?;
+        let attrs: u8 = if tag & ATTRS_FLAG != 0 { buf.read() } else { 0 };
+        if attrs & !(ATTR_MAX_LEN | ATTR_DEFAULT | ATTR_NOT_NULL) != 0 {
+            return Err(Error::CorruptedTypeTag);
+        }
+        let max_len = (attrs & ATTR_MAX_LEN != 0).then(|| buf.read());
+        let default = if attrs & ATTR_DEFAULT != 0 {
+            Some(Value::deserialize(buf, &ty)?)
+        } else {
+            None
+        };
         Ok(Column {
-            ty: TypeId::deserialize(buf)?,
+            ty,
             name: VarString::deserialize(buf)?.into(),
+            max_len,
+            default,
+            not_null: attrs & ATTR_NOT_NULL != 0,
         })
     }
 }
@@ -0,0 +1,91 @@
+use tracing::error;
+
+use crate::{
+    catalog::table_schema::TableSchema,
+    error::{DbResult, Error},
+    util::io::{Deserialize, Serialize, Size, VarString},
+};
+
+/// An external table schema. External tables are read-only tables whose rows
+/// are stored in a file outside of the database.
+#[derive(Debug, Clone)]
+pub struct ExternalTableSchema {
+    /// The path of the file, as given at creation (i.e., relative paths are
+    /// resolved from the working directory of the process which reads it).
+    pub path: String,
+    /// The file format.
+    pub format: ExternalFormat,
+    /// The declared schema of the file rows.
+    pub schema: TableSchema,
+}
+
+/// The format of an external table file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExternalFormat {
+    /// Comma-separated values, with a header line naming the columns.
+    Csv = 0,
+    /// One JSON object per line.
+    Jsonl = 1,
+    /// A sealed table segment. See [`crate::io::segment`].
+    Segment = 2,
+}
+
+impl ExternalFormat {
+    /// Returns the canonical format name.
+    pub fn name(self) -> &'static str {
+        match self {
+            ExternalFormat::Csv => "csv",
+            ExternalFormat::Jsonl => "jsonl",
+            ExternalFormat::Segment => "segment",
+        }
+    }
+
+    /// Returns the format with the given (case-insensitive) name, if any.
+    pub fn from_name(name: &str) -> Option<ExternalFormat> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(ExternalFormat::Csv),
+            "jsonl" => Some(ExternalFormat::Jsonl),
+            "segment" => Some(ExternalFormat::Segment),
+            _ => None,
+        }
+    }
+}
+
+impl Size for ExternalTableSchema {
+    fn size(&self) -> u32 {
+        VarString::from(self.path.as_str()).size() + 1 + self.schema.size()
+    }
+}
+
+impl Serialize for ExternalTableSchema {
+    fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        VarString::from(self.path.as_str()).serialize(buf)?;
+        buf.write(self.format as u8);
+        self.schema.serialize(buf)?;
+        Ok(())
+    }
+}
+
+impl Deserialize<'_> for ExternalTableSchema {
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
+    where
+        Self: Sized,
+    {
+        let path = VarString::deserialize(buf)?.into();
+        let format = match buf.read::<1, u8>() {
+            0 => ExternalFormat::Csv,
+            1 => ExternalFormat::Jsonl,
+            2 => ExternalFormat::Segment,
+            unexpected => {
+                error!(?unexpected, "invalid `ExternalFormat` discriminant");
+                return Err(Error::CorruptedTypeTag);
+            }
+        };
+        Ok(ExternalTableSchema {
+            path,
+            format,
+            schema: TableSchema::deserialize(buf)?,
+        })
+    }
+}
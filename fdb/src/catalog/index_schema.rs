@@ -0,0 +1,39 @@
+use crate::{
+    error::DbResult,
+    util::io::{Deserialize, Serialize, Size, VarString},
+};
+
+/// An index schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSchema {
+    /// The name of the indexed table.
+    pub table: String,
+    /// The name of the indexed column, i.e., the index key.
+    pub column: String,
+}
+
+impl Size for IndexSchema {
+    fn size(&self) -> u32 {
+        VarString::from(self.table.as_str()).size() + VarString::from(self.column.as_str()).size()
+    }
+}
+
+impl Serialize for IndexSchema {
+    fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        VarString::from(self.table.as_str()).serialize(buf)?;
+        VarString::from(self.column.as_str()).serialize(buf)?;
+        Ok(())
+    }
+}
+
+impl Deserialize<'_> for IndexSchema {
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(IndexSchema {
+            table: VarString::deserialize(buf)?.into(),
+            column: VarString::deserialize(buf)?.into(),
+        })
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    catalog::{page::PageId, table_schema::TableSchema},
+    catalog::{
+        external_schema::ExternalTableSchema, index_schema::IndexSchema, page::PageId,
+        statistics::TableStatistics, table_schema::TableSchema,
+    },
     error::{DbResult, Error},
     util::io::{Deserialize, Serialize, Size, VarString},
 };
@@ -12,6 +15,9 @@ pub struct Object {
     /// The object's type (e.g. a table, an index, etc).
     pub ty: ObjectType,
     /// The ID of the first page that stores the actual records.
+    ///
+    /// External tables have no pages, hence their objects point to the first
+    /// database page.
     pub page_id: PageId,
     /// The object name (e.g. the table name as per the user's definition).
     ///
@@ -36,7 +42,7 @@ impl Serialize for Object {
 }
 
 impl Deserialize<'_> for Object {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -51,14 +57,20 @@ impl Deserialize<'_> for Object {
 #[derive(Debug, Clone)]
 pub enum ObjectType {
     Table(TableSchema),
-    Index,
+    Index(IndexSchema),
+    External(ExternalTableSchema),
+    /// The statistics of the table whose heap sequence starts at the object's
+    /// page. See [`crate::exec::statistics`].
+    Statistics(TableStatistics),
 }
 
 impl Size for ObjectType {
     fn size(&self) -> u32 {
         1 + match self {
             ObjectType::Table(schema) => schema.size(),
-            ObjectType::Index => 0,
+            ObjectType::Index(schema) => schema.size(),
+            ObjectType::External(schema) => schema.size(),
+            ObjectType::Statistics(statistics) => statistics.size(),
         }
     }
 }
@@ -66,15 +78,18 @@ impl Size for ObjectType {
 impl Serialize for ObjectType {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
         buf.write(self.discriminant());
-        if let ObjectType::Table(schema) = self {
-            schema.serialize(buf)?;
+        match self {
+            ObjectType::Table(schema) => schema.serialize(buf)?,
+            ObjectType::Index(schema) => schema.serialize(buf)?,
+            ObjectType::External(schema) => schema.serialize(buf)?,
+            ObjectType::Statistics(statistics) => statistics.serialize(buf)?,
         }
         Ok(())
     }
 }
 
 impl Deserialize<'_> for ObjectType {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -84,7 +99,18 @@ impl Deserialize<'_> for ObjectType {
                 let schema = TableSchema::deserialize(buf)?;
                 Ok(ObjectType::Table(schema))
             }
-            0xB => Ok(ObjectType::Index),
+            0xB => {
+                let schema = IndexSchema::deserialize(buf)?;
+                Ok(ObjectType::Index(schema))
+            }
+            0xC => {
+                let schema = ExternalTableSchema::deserialize(buf)?;
+                Ok(ObjectType::External(schema))
+            }
+            0xD => {
+                let statistics = TableStatistics::deserialize(buf)?;
+                Ok(ObjectType::Statistics(statistics))
+            }
             _ => Err(Error::CorruptedObjectTypeTag),
         }
     }
@@ -95,7 +121,9 @@ impl ObjectType {
     pub const fn discriminant(&self) -> u8 {
         match self {
             ObjectType::Table(_) => 0xA,
-            ObjectType::Index => 0xB,
+            ObjectType::Index(_) => 0xB,
+            ObjectType::External(_) => 0xC,
+            ObjectType::Statistics(_) => 0xD,
         }
     }
 
@@ -103,19 +131,36 @@ impl ObjectType {
     pub const fn _name(&self) -> &'static str {
         match self {
             ObjectType::Table(_) => "table",
-            ObjectType::Index => "index",
+            ObjectType::Index(_) => "index",
+            ObjectType::External(_) => "external table",
+            ObjectType::Statistics(_) => "statistics",
         }
     }
 }
 
 /// A table object type.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TableObject {
     pub schema: TableSchema,
     pub page_id: PageId,
     pub name: String,
 }
 
+/// An index object type.
+#[derive(Debug)]
+pub struct IndexObject {
+    pub schema: IndexSchema,
+    pub page_id: PageId,
+    pub name: String,
+}
+
+/// An external table object type.
+#[derive(Debug, Clone)]
+pub struct ExternalTableObject {
+    pub schema: ExternalTableSchema,
+    pub name: String,
+}
+
 impl Object {
     /// Returns the underlying [`TableObject`] or fails.
     pub fn try_into_table(self) -> DbResult<TableObject> {
@@ -132,4 +177,35 @@ impl Object {
             )))
         }
     }
+
+    /// Returns the underlying [`ExternalTableObject`] or fails.
+    pub fn try_into_external_table(self) -> DbResult<ExternalTableObject> {
+        if let ObjectType::External(schema) = self.ty {
+            Ok(ExternalTableObject {
+                schema,
+                name: self.name,
+            })
+        } else {
+            Err(Error::Cast(format!(
+                "object `{}` is not an external table",
+                self.name
+            )))
+        }
+    }
+
+    /// Returns the underlying [`IndexObject`] or fails.
+    pub fn try_into_index(self) -> DbResult<IndexObject> {
+        if let ObjectType::Index(schema) = self.ty {
+            Ok(IndexObject {
+                schema,
+                page_id: self.page_id,
+                name: self.name,
+            })
+        } else {
+            Err(Error::Cast(format!(
+                "object `{}` is not an index",
+                self.name
+            )))
+        }
+    }
 }
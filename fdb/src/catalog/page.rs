@@ -22,6 +22,10 @@ pub use heap::*;
 mod b_tree;
 pub use b_tree::*;
 
+/// The free list page definition.
+mod free_list;
+pub use free_list::*;
+
 /// An in-memory page.
 ///
 /// Since the database engine can interpret the "raw page" sequence of bytes,
@@ -32,11 +36,12 @@ pub use b_tree::*;
 ///
 /// All "usable" page implementations of the database may be wrapped in this
 /// enum.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Page {
     First(FirstPage),
     Heap(HeapPage),
     BTree(BTreePage),
+    FreeList(FreeListPage),
 }
 
 impl Page {
@@ -46,6 +51,7 @@ impl Page {
             Page::First(inner) => inner.id(),
             Page::Heap(inner) => inner.id(),
             Page::BTree(inner) => inner.id(),
+            Page::FreeList(inner) => inner.id(),
         }
     }
 
@@ -56,6 +62,7 @@ impl Page {
             Page::First(_) => FirstPage::ty(),
             Page::Heap(_) => HeapPage::ty(),
             Page::BTree(_) => BTreePage::ty(),
+            Page::FreeList(_) => FreeListPage::ty(),
         }
     }
 
@@ -85,6 +92,7 @@ impl Size for Page {
             Page::First(inner) => inner.size(),
             Page::Heap(inner) => inner.size(),
             Page::BTree(inner) => inner.size(),
+            Page::FreeList(inner) => inner.size(),
         }
     }
 }
@@ -95,12 +103,13 @@ impl Serialize for Page {
             Page::First(inner) => inner.serialize(buf),
             Page::Heap(inner) => inner.serialize(buf),
             Page::BTree(inner) => inner.serialize(buf),
+            Page::FreeList(inner) => inner.serialize(buf),
         }
     }
 }
 
 impl Deserialize<'_> for Page {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -113,6 +122,7 @@ impl Deserialize<'_> for Page {
             PageType::First => Page::First(FirstPage::deserialize(buf)?),
             PageType::Heap => Page::Heap(HeapPage::deserialize(buf)?),
             PageType::BTree => Page::BTree(BTreePage::deserialize(buf)?),
+            PageType::FreeList => Page::FreeList(FreeListPage::deserialize(buf)?),
         })
     }
 }
@@ -130,6 +140,8 @@ pub enum PageType {
     Heap = 0x01,
     /// See [`BTreePage`].
     BTree = 0x02,
+    /// See [`FreeListPage`].
+    FreeList = 0x03,
 }
 
 impl Size for PageType {
@@ -140,13 +152,13 @@ impl Size for PageType {
 
 impl Serialize for PageType {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
-        buf.write(*self as u8);
+        buf.try_write(*self as u8)?;
         Ok(())
     }
 }
 
 impl Deserialize<'_> for PageType {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -154,6 +166,8 @@ impl Deserialize<'_> for PageType {
         match tag {
             0x66 => Ok(PageType::First),
             0x01 => Ok(PageType::Heap),
+            0x02 => Ok(PageType::BTree),
+            0x03 => Ok(PageType::FreeList),
             unexpected => {
                 error!(?unexpected, "invalid `PageType` type discriminant");
                 Err(Error::CorruptedTypeTag)
@@ -175,7 +189,7 @@ impl PageType {
 /// first page at index 1. This allows using the 0-value to encode NULL pages,
 /// i.e., a reference to a page that doesn't exist. Indeed, this same approach
 /// is used by DBMSs such as SQLite.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
 pub struct PageId(NonZeroU32);
 
@@ -238,7 +252,7 @@ impl Serialize for PageId {
 
 impl Deserialize<'_> for PageId {
     /// Must not try to deserialize a null page ID.
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -255,13 +269,13 @@ impl Size for Option<PageId> {
 impl Serialize for Option<PageId> {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
         let num = self.map(PageId::get).unwrap_or(0);
-        buf.write(num);
+        buf.try_write(num)?;
         Ok(())
     }
 }
 
 impl Deserialize<'_> for Option<PageId> {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -1,14 +1,30 @@
-//! Heap pages store records in an unordered and sequential fashion.
+//! B+Tree pages store index cells in an ordered fashion.
+//!
+//! Text and blob keys are prefix-compressed: each one only stores the bytes
+//! that follow the prefix it shares with the previous key in the page. Since
+//! cells are ordered, neighboring keys often share long prefixes.
 
-use std::ops::Add;
+use std::{cmp::Ordering, ops::Add};
+
+use tracing::error;
 
 use crate::{
-    catalog::page::{Page, PageId, PageType, SpecificPage},
-    error::DbResult,
-    util::io::{Deserialize, Serialize, Size},
+    catalog::{
+        page::{Page, PageId, PageType, SpecificPage},
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::value::Value,
+    util::io::{Deserialize, DeserializeCtx, Serialize, Size, VarBytes},
 };
 
-#[derive(Debug)]
+/// The size of the header shared by all B+Tree pages.
+const BASE_HEADER_SIZE: u32 = 1 // top-level page type (btree)
+    + 1 // btree type tag
+    + 4 // page id
+    + 2; // cell_count
+
+#[derive(Debug, Clone)]
 pub enum BTreePage {
     Internal(BTreeInternalPage), // tag OxAA
     Leaf(BTreeLeafPage),         // tag 0xFF
@@ -16,14 +32,10 @@ pub enum BTreePage {
 
 impl Size for BTreePage {
     fn size(&self) -> u32 {
-        (1_u32) // top-level page type (btree)
-            .add(1) // btree type tag
-            .add(4) // page id
-            .add(2) // cell_count
-            .add(match self {
-                BTreePage::Internal(node) => 4 * node.ptrs.len() + node.keys.len(),
-                BTreePage::Leaf(node) => 4 + 4 + node.cells.len(),
-            } as u32)
+        BASE_HEADER_SIZE.add(match self {
+            BTreePage::Internal(node) => 4 * node.ptrs.len() as u32 + cells_size(&node.keys),
+            BTreePage::Leaf(node) => 4 + 4 + cells_size(&node.cells),
+        })
     }
 }
 
@@ -32,31 +44,33 @@ impl Serialize for BTreePage {
         PageType::BTree.serialize(buf)?; // top-level page type (btree)
         match self {
             BTreePage::Internal(node) => {
+                debug_assert_eq!(node.ptrs.len(), node.keys.len() + 1);
                 buf.write(0xAA_u8); // tag for internal page
                 node.id.serialize(buf)?;
-                buf.write(node.cell_count);
+                buf.try_write(node.keys.len() as u16)?;
 
                 for ptr in &node.ptrs {
                     ptr.serialize(buf)?;
                 }
-                buf.write_slice(&node.keys);
+                serialize_cells(&node.keys, buf)?;
             }
             BTreePage::Leaf(node) => {
                 buf.write(0xFF_u8); // tag for leaf page
                 node.id.serialize(buf)?;
-                buf.write(node.cell_count);
+                buf.try_write(node.cells.len() as u16)?;
 
                 node.prev.serialize(buf)?;
                 node.next.serialize(buf)?;
-                buf.write_slice(&node.cells);
+                serialize_cells(&node.cells, buf)?;
             }
         }
+        buf.pad_end_bytes(0);
         Ok(())
     }
 }
 
 impl Deserialize<'_> for BTreePage {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -69,7 +83,6 @@ impl Deserialize<'_> for BTreePage {
             // internal page
             0xAA => BTreePage::Internal(BTreeInternalPage {
                 id,
-                cell_count,
                 ptrs: {
                     // `+1` to account for the last pointer
                     let mut ptrs = Vec::with_capacity((cell_count + 1) as usize);
@@ -78,25 +91,19 @@ impl Deserialize<'_> for BTreePage {
                     }
                     ptrs
                 },
-                keys: {
-                    let mut bytes = vec![0; buf.remaining()];
-                    buf.read_slice(&mut bytes);
-                    bytes
-                },
+                keys: deserialize_cells(cell_count, buf)?,
             }),
             // leaf page
             0xFF => BTreePage::Leaf(BTreeLeafPage {
                 id,
-                cell_count,
                 prev: Option::<PageId>::deserialize(buf)?,
                 next: Option::<PageId>::deserialize(buf)?,
-                cells: {
-                    let mut bytes = vec![0; buf.remaining()];
-                    buf.read_slice(&mut bytes);
-                    bytes
-                },
+                cells: deserialize_cells(cell_count, buf)?,
             }),
-            _ => panic!("corrupted file or impl bug"),
+            unexpected => {
+                error!(?unexpected, "invalid `BTreePage` type tag");
+                return Err(Error::CorruptedTypeTag);
+            }
         })
     }
 }
@@ -116,19 +123,233 @@ impl SpecificPage for BTreePage {
     super::impl_cast_methods!(Page::BTree => BTreePage);
 }
 
-#[derive(Debug)]
+impl BTreePage {
+    /// Constructs a new empty leaf page.
+    pub fn new_leaf(_page_size: u16, page_id: PageId) -> Self {
+        BTreePage::Leaf(BTreeLeafPage {
+            id: page_id,
+            prev: None,
+            next: None,
+            cells: Vec::new(),
+        })
+    }
+
+    /// Sets the page ID.
+    pub fn set_id(&mut self, page_id: PageId) {
+        match self {
+            BTreePage::Internal(inner) => inner.id = page_id,
+            BTreePage::Leaf(inner) => inner.id = page_id,
+        }
+    }
+}
+
+/// An internal B+Tree page.
+///
+/// The `i`-th key separates the sub trees pointed by the `i`-th and `i + 1`-th
+/// pointers: it is greater than all cells of the former and less than or equal
+/// to all cells of the latter. Hence, there is always one more pointer than
+/// keys.
+///
+/// Separators need not be indexed cells. Text and blob ones may be truncated
+/// (see [`BTreeCell::separator`]) so that more of them fit in a page.
+#[derive(Debug, Clone)]
 pub struct BTreeInternalPage {
-    id: PageId,
-    cell_count: u16,
-    ptrs: Vec<PageId>,
-    keys: Vec<u8>,
+    pub id: PageId,
+    pub ptrs: Vec<PageId>,
+    pub keys: Vec<BTreeCell>,
+}
+
+impl BTreeInternalPage {
+    /// Returns the index of the pointer to the sub tree that may contain the
+    /// given cell.
+    pub fn child_index(&self, cell: &BTreeCell) -> usize {
+        self.keys.partition_point(|key| key <= cell)
+    }
 }
 
-#[derive(Debug)]
+/// A leaf B+Tree page.
+///
+/// Leaves are doubly linked so that range scans may be performed without
+/// going back through the internal pages.
+#[derive(Debug, Clone)]
 pub struct BTreeLeafPage {
-    id: PageId,
-    cell_count: u16,
-    prev: Option<PageId>,
-    next: Option<PageId>,
-    cells: Vec<u8>,
+    pub id: PageId,
+    pub prev: Option<PageId>,
+    pub next: Option<PageId>,
+    pub cells: Vec<BTreeCell>,
+}
+
+/// A B+Tree cell, i.e., an index key and a pointer to the indexed record.
+///
+/// Cells are ordered by their key and then by the record pointer. Hence, the
+/// same key may be indexed many times (for different records).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BTreeCell {
+    /// The index key.
+    pub key: Value,
+    /// The ID of the page that contains the indexed record.
+    pub page_id: PageId,
+    /// The offset of the indexed record in its page.
+    pub offset: u16,
+}
+
+impl PartialOrd for BTreeCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BTreeCell {
+    /// Keys of the same index always have the same type.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key
+            .partial_cmp(&other.key)
+            .expect("index keys must have the same type")
+            .then_with(|| self.page_id.get().cmp(&other.page_id.get()))
+            .then_with(|| self.offset.cmp(&other.offset))
+    }
+}
+
+impl BTreeCell {
+    /// Returns the shortest separator between the given cells (see
+    /// [`BTreeInternalPage`]), where `last < first`.
+    ///
+    /// If the keys are text or blobs, that's the shortest prefix of `first`'s
+    /// key which is greater than `last`'s key. Otherwise, it's `first` itself.
+    pub fn separator(last: &BTreeCell, first: &BTreeCell) -> BTreeCell {
+        debug_assert!(last < first);
+        let (Some(last_bytes), Some(first_bytes)) = (key_bytes(&last.key), key_bytes(&first.key))
+        else {
+            return first.clone();
+        };
+        let mut len = shared_len(last_bytes, first_bytes) + 1;
+        if let Value::Text(text) = &first.key {
+            while len < text.len() && !text.is_char_boundary(len) {
+                len += 1;
+            }
+        }
+        if len >= first_bytes.len() {
+            return first.clone();
+        }
+        // As the key is less than `first`'s, the record pointer doesn't matter.
+        let key = match &first.key {
+            Value::Text(text) => Value::Text(text[..len].to_owned()),
+            _ => Value::Blob(first_bytes[..len].to_vec()),
+        };
+        BTreeCell {
+            key,
+            page_id: first.page_id,
+            offset: first.offset,
+        }
+    }
+
+    /// Returns the size of the cell when stored after the given one in a page.
+    pub(crate) fn compressed_size(&self, prev: Option<&BTreeCell>) -> u32 {
+        let shared = shared_prefix(prev, self);
+        self.key.type_id().size()
+            + self.page_id.size()
+            + 2
+            + match key_bytes(&self.key) {
+                Some(bytes) => 2 + VarBytes::from(&bytes[shared..]).size(),
+                None => self.key.size(),
+            }
+    }
+}
+
+/// The size of the cell when it's the first one in a page, i.e., when its key
+/// isn't compressed.
+impl Size for BTreeCell {
+    fn size(&self) -> u32 {
+        self.compressed_size(None)
+    }
+}
+
+/// Returns the size of the given (ordered) cells when stored in a page.
+fn cells_size(cells: &[BTreeCell]) -> u32 {
+    let prevs = std::iter::once(None).chain(cells.iter().map(Some));
+    cells
+        .iter()
+        .zip(prevs)
+        .map(|(cell, prev)| cell.compressed_size(prev))
+        .sum()
+}
+
+/// Serializes the given (ordered) cells, compressing the text and blob keys.
+/// Each one is stored as its type tag, the length of the prefix it shares with
+/// the previous key, the remaining bytes and the record pointer. Other keys are
+/// stored as is.
+fn serialize_cells(cells: &[BTreeCell], buf: &mut buff::Buff<'_>) -> DbResult<()> {
+    let mut prev = None;
+    for cell in cells {
+        cell.key.type_id().serialize(buf)?;
+        match key_bytes(&cell.key) {
+            Some(bytes) => {
+                let shared = shared_prefix(prev, cell);
+                buf.try_write(shared as u16)?;
+                VarBytes::from(&bytes[shared..]).serialize(buf)?;
+            }
+            None => cell.key.serialize(buf)?,
+        }
+        cell.page_id.serialize(buf)?;
+        buf.try_write(cell.offset)?;
+        prev = Some(cell);
+    }
+    Ok(())
+}
+
+/// Deserializes the cells serialized by [`serialize_cells`].
+fn deserialize_cells(count: u16, buf: &mut buff::BuffRead<'_>) -> DbResult<Vec<BTreeCell>> {
+    let mut cells: Vec<BTreeCell> = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let ty = TypeId::deserialize(buf)?;
+        let key = match ty {
+            TypeId::Primitive(PrimitiveTypeId::Text | PrimitiveTypeId::Blob) => {
+                let shared: u16 = buf.read();
+                let suffix = VarBytes::deserialize(buf)?.0;
+                let prefix = match cells.last().map(|prev| key_bytes(&prev.key)) {
+                    Some(Some(prev)) if shared as usize <= prev.len() => &prev[..shared as usize],
+                    None if shared == 0 => &[],
+                    _ => return Err(Error::CorruptedKeyPrefix),
+                };
+                let bytes = [prefix, &suffix].concat();
+                if ty == TypeId::Primitive(PrimitiveTypeId::Text) {
+                    Value::Text(String::from_utf8(bytes).map_err(|_| Error::CorruptedUtf8)?)
+                } else {
+                    Value::Blob(bytes)
+                }
+            }
+            _ => Value::deserialize(buf, &ty)?,
+        };
+        cells.push(BTreeCell {
+            key,
+            page_id: PageId::deserialize(buf)?,
+            offset: buf.read(),
+        });
+    }
+    Ok(cells)
+}
+
+/// Returns the bytes of a text or blob key.
+fn key_bytes(key: &Value) -> Option<&[u8]> {
+    match key {
+        Value::Text(text) => Some(text.as_bytes()),
+        Value::Blob(bytes) => Some(bytes),
+        _ => None,
+    }
+}
+
+/// Returns the length of the prefix which the given cell's key shares with the
+/// previous one.
+fn shared_prefix(prev: Option<&BTreeCell>, cell: &BTreeCell) -> usize {
+    match (
+        prev.and_then(|prev| key_bytes(&prev.key)),
+        key_bytes(&cell.key),
+    ) {
+        (Some(prev), Some(bytes)) => shared_len(prev, bytes),
+        _ => 0,
+    }
+}
+
+fn shared_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
 }
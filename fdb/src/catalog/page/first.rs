@@ -1,4 +1,4 @@
-use buff::Buff;
+use buff::{Buff, BuffRead, OverflowError};
 
 use crate::{
     catalog::page::{Page, PageId, PageType, SpecificPage},
@@ -9,16 +9,71 @@ use crate::{
 /// The database header size.
 pub const HEADER_SIZE: usize = 100;
 
+/// The file format version written by this build. Files of other versions are
+/// rejected (see [`Error::IncompatibleFile`]).
+///
+/// Version 2 stores the lengths of texts and blobs (e.g., in records and index
+/// keys) as varints, rather than as 2-byte integers.
+///
+/// Version 3 stores the column attributes (i.e., the maximum length, the
+/// default value and `NOT NULL`) in an attributes byte (see [`Column`]).
+///
+/// Version 4 stores the layout version of heap pages, which is incremented when
+/// their records are moved (see [`HeapPage::reclaim`]).
+///
+/// [`Column`]: crate::catalog::column::Column
+/// [`HeapPage::reclaim`]: crate::catalog::page::HeapPage::reclaim
+pub const FILE_FORMAT_VERSION: u8 = 4;
+
+/// The byte order mark, stored in the header right after the free page count.
+///
+/// All integers are stored as fixed-width, big-endian, integers (see
+/// [`buff::AsBytes`]), regardless of the platform. The mark is stored as such,
+/// so that files written with another byte order are detected (rather than
+/// misread) when opened. Files created before the mark was introduced have
+/// zeros in its place, and are also big-endian.
+pub const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+
+/// The offset of the [`BYTE_ORDER_MARK`] in the header.
+pub const BYTE_ORDER_MARK_OFFSET: usize = 29;
+
+/// Checks whether the given byte order mark, as read from the header, denotes
+/// a file in the byte order used by this build.
+pub fn check_byte_order_mark(mark: u32) -> DbResult<()> {
+    match mark {
+        BYTE_ORDER_MARK | 0 => Ok(()),
+        mark if mark == BYTE_ORDER_MARK.swap_bytes() => {
+            Err(Error::IncompatibleFile("byte order mismatch"))
+        }
+        _ => Err(Error::CorruptedHeader("byte order mark")),
+    }
+}
+
+/// The maximum number of hot pages recorded in the first page.
+pub const MAX_HOT_PAGES: usize = 32;
+
+/// The maximum number of temporary page sequences registered in the first page.
+pub const MAX_TEMP_SEQS: usize = 64;
+
 /// The first page, which contains the database header. Currently, the database
 /// wastes `PAGE_SIZE - 100` bytes in space of the first page, for
 /// simplification's sake. In the future, this region will be used to store the
 /// first section of the database schema heap pages sequence.
 ///
 /// The first 10 bytes are reserved for the ASCII string `"fdb format"`.
-#[derive(Debug)]
+///
+/// Right after the header, the IDs of the most frequently used pages (as of the
+/// last checkpoint) are stored, so that they may be prefetched when the
+/// database is opened. Then, the IDs of the first pages of all alive temporary
+/// page sequences are stored, so that they may be purged after a crash.
+#[derive(Debug, Clone)]
 pub struct FirstPage {
     /// The database header.
     pub header: MainHeader,
+    /// The hot pages, at most [`MAX_HOT_PAGES`].
+    pub hot_page_ids: Vec<PageId>,
+    /// The temporary page sequences, at most [`MAX_TEMP_SEQS`].
+    pub temp_seq_page_ids: Vec<PageId>,
 }
 
 impl Size for FirstPage {
@@ -30,15 +85,19 @@ impl Size for FirstPage {
 impl Serialize for FirstPage {
     fn serialize(&self, buf: &mut Buff<'_>) -> DbResult<()> {
         self.header.serialize(buf)?;
+        serialize_page_ids(buf, &self.hot_page_ids, MAX_HOT_PAGES)?;
+        serialize_page_ids(buf, &self.temp_seq_page_ids, MAX_TEMP_SEQS)?;
         buf.pad_end_bytes(0);
         Ok(())
     }
 }
 
 impl Deserialize<'_> for FirstPage {
-    fn deserialize(buf: &mut Buff<'_>) -> DbResult<Self> {
+    fn deserialize(buf: &mut BuffRead<'_>) -> DbResult<Self> {
         Ok(FirstPage {
             header: MainHeader::deserialize(buf)?,
+            hot_page_ids: deserialize_page_ids(buf, MAX_HOT_PAGES)?,
+            temp_seq_page_ids: deserialize_page_ids(buf, MAX_TEMP_SEQS)?,
         })
     }
 }
@@ -59,20 +118,43 @@ impl FirstPage {
     pub fn new(page_size: u16) -> Self {
         FirstPage {
             header: MainHeader {
-                file_format_version: 1,
+                file_format_version: FILE_FORMAT_VERSION,
                 page_size,
                 page_count: 1,
                 first_free_list_page_id: None,
                 first_schema_seq_page_id: PageId::new_u32(2),
+                free_page_count: 0,
             },
+            hot_page_ids: Vec::new(),
+            temp_seq_page_ids: Vec::new(),
         }
     }
 }
 
+/// Serializes a page ID list (with at most `max` elements), prefixed by its
+/// length.
+fn serialize_page_ids(buf: &mut Buff<'_>, page_ids: &[PageId], max: usize) -> DbResult<()> {
+    debug_assert!(page_ids.len() <= max);
+    buf.try_write(page_ids.len() as u16)?;
+    for page_id in page_ids {
+        page_id.serialize(buf)?;
+    }
+    Ok(())
+}
+
+/// Deserializes a page ID list. See [`serialize_page_ids`].
+fn deserialize_page_ids(buf: &mut BuffRead<'_>, max: usize) -> DbResult<Vec<PageId>> {
+    let len: u16 = buf.read();
+    if len as usize > max {
+        return Err(Error::CorruptedHeader("page id list"));
+    }
+    (0..len).map(|_| PageId::deserialize(buf)).collect()
+}
+
 /// The database header.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MainHeader {
-    /// The file format version. Currently, such a version is defined as `0`.
+    /// The file format version. See [`FILE_FORMAT_VERSION`].
     pub file_format_version: u8,
     /// The size of the database pages.
     pub page_size: u16,
@@ -82,6 +164,8 @@ pub struct MainHeader {
     pub first_free_list_page_id: Option<PageId>,
     /// The ID of the first schema page.
     pub first_schema_seq_page_id: PageId,
+    /// The total number of pages in the free list.
+    pub free_page_count: u32,
 }
 
 impl Size for MainHeader {
@@ -92,17 +176,27 @@ impl Size for MainHeader {
 
 impl Serialize for MainHeader {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        // Checked beforehand, since the scope must always be completed.
+        if !buf.can_accommodate(HEADER_SIZE) {
+            return Err(Error::BufferOverflow(OverflowError {
+                requested: HEADER_SIZE,
+                remaining: buf.remaining(),
+            }));
+        }
         buf.scoped_exact(HEADER_SIZE, |buf| {
-            buf.write_slice(b"fdb format");
-            buf.write(self.file_format_version);
-            buf.write(self.page_size);
-            buf.write(self.page_count);
+            buf.try_write_slice(b"fdb format")?;
+            buf.try_write(self.file_format_version)?;
+            buf.try_write(self.page_size)?;
+            buf.try_write(self.page_count)?;
             self.first_free_list_page_id.serialize(buf)?;
             self.first_schema_seq_page_id.serialize(buf)?;
+            buf.try_write(self.free_page_count)?;
+            debug_assert_eq!(buf.offset(), BYTE_ORDER_MARK_OFFSET);
+            buf.try_write(BYTE_ORDER_MARK)?;
 
             let rest = HEADER_SIZE - 2 - buf.offset();
             buf.write_bytes(rest, 0);
-            buf.write_slice(br"\0");
+            buf.try_write_slice(br"\0")?;
 
             Ok::<_, Error>(())
         })
@@ -110,7 +204,7 @@ impl Serialize for MainHeader {
 }
 
 impl Deserialize<'_> for MainHeader {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -126,13 +220,22 @@ impl Deserialize<'_> for MainHeader {
                 page_count: buf.read(),
                 first_free_list_page_id: Option::<PageId>::deserialize(buf)?,
                 first_schema_seq_page_id: PageId::deserialize(buf)?,
+                free_page_count: buf.read(),
             };
+            let byte_order_mark = buf.read();
 
             buf.seek(HEADER_SIZE - 2);
             // finish header sig
             if !read_verify_eq(buf, br"\0") {
                 return Err(Error::CorruptedHeader("end"));
             }
+            check_byte_order_mark(byte_order_mark)?;
+            if header.file_format_version > FILE_FORMAT_VERSION {
+                return Err(Error::IncompatibleFile("unsupported file format version"));
+            }
+            if header.file_format_version < FILE_FORMAT_VERSION {
+                return Err(Error::IncompatibleFile("outdated file format version"));
+            }
 
             Ok(header)
         })
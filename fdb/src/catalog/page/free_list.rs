@@ -0,0 +1,72 @@
+//! Free list pages keep track of the deallocated pages of the database file.
+
+use crate::{
+    catalog::page::{Page, PageId, PageType, SpecificPage},
+    error::DbResult,
+    util::io::{Deserialize, Serialize, Size},
+};
+
+/// A page in the free list.
+///
+/// Deallocated pages are linked in a (singly-linked) list, whose head is
+/// defined in the main database header. Each deallocated page is itself
+/// rewritten as a [`FreeListPage`], hence no extra space is needed to maintain
+/// the list.
+#[derive(Debug, Clone)]
+pub struct FreeListPage {
+    /// The page ID.
+    pub id: PageId,
+    /// The next free page in the list, if any.
+    pub next_page_id: Option<PageId>,
+}
+
+impl Size for FreeListPage {
+    fn size(&self) -> u32 {
+        1 // page type
+        + 4 // page id
+        + 4 // next page id
+    }
+}
+
+impl Serialize for FreeListPage {
+    fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        PageType::FreeList.serialize(buf)?;
+        self.id.serialize(buf)?;
+        self.next_page_id.serialize(buf)?;
+        buf.pad_end_bytes(0);
+
+        Ok(())
+    }
+}
+
+impl Deserialize<'_> for FreeListPage {
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
+    where
+        Self: Sized,
+    {
+        assert_eq!(PageType::deserialize(buf)?, PageType::FreeList);
+        Ok(FreeListPage {
+            id: PageId::deserialize(buf)?,
+            next_page_id: Option::<PageId>::deserialize(buf)?,
+        })
+    }
+}
+
+impl SpecificPage for FreeListPage {
+    fn ty() -> PageType {
+        PageType::FreeList
+    }
+
+    fn id(&self) -> PageId {
+        self.id
+    }
+
+    super::impl_cast_methods!(Page::FreeList => FreeListPage);
+}
+
+impl FreeListPage {
+    /// Constructs a new free list page, which points to the given next page.
+    pub fn new(id: PageId, next_page_id: Option<PageId>) -> FreeListPage {
+        FreeListPage { id, next_page_id }
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
 };
 
 /// The first [`HeapPage`] in the sequence.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HeapPage {
     /// The page header.
     pub header: Header,
@@ -26,7 +26,7 @@ impl Size for HeapPage {
 impl Serialize for HeapPage {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
         self.header.serialize(buf)?;
-        buf.write_slice(&self.bytes);
+        buf.try_write_slice(&self.bytes)?;
         buf.pad_end_bytes(0);
 
         Ok(())
@@ -34,7 +34,7 @@ impl Serialize for HeapPage {
 }
 
 impl Deserialize<'_> for HeapPage {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -63,10 +63,26 @@ impl SpecificPage for HeapPage {
 }
 
 impl HeapPage {
-    /// Checks whether the page can accommodate `n` more bytes.
+    /// Checks whether the page can accommodate `n` more bytes. Fragment pages
+    /// never accommodate records (see [`HeapPage::is_fragment`]).
     pub fn can_accommodate(&self, n: u32) -> bool {
         // TODO(buff-trait): Use Buff API here instead.
-        self.bytes.len() >= self.header.free_offset as usize + n as usize
+        self.bytes.len() >= self.header.free_offset as usize + n as usize && !self.is_fragment()
+    }
+
+    /// Checks whether the page holds a fragment of a record that spans many
+    /// pages (see [`crate::exec::operations::heap::span`]), instead of records.
+    ///
+    /// Fragment pages are the only pages with written bytes but no records,
+    /// since the records of a page are only removed by a vacuum, which also
+    /// truncates the page.
+    pub fn is_fragment(&self) -> bool {
+        self.header.record_count == 0 && self.header.free_offset != 0
+    }
+
+    /// Returns the number of bytes that may still be written to the page.
+    pub fn free_space(&self) -> u32 {
+        (self.bytes.len() - self.header.free_offset as usize) as u32
     }
 
     /// Writes using the given closure.
@@ -100,16 +116,46 @@ impl HeapPage {
         Ok(r)
     }
 
+    /// Removes `len` bytes starting at the given offset, shifting all the
+    /// subsequent records in the page to the left. The `free_offset` marker is
+    /// decremented by `len`. NOTE THAT THIS METHOD DOESN'T ALTER THE UNDERLYING
+    /// RECORD COUNTER.
+    ///
+    /// Since this page isn't slotted (yet), moving records invalidates all
+    /// offsets that point past `offset` in this page. Hence, the layout version
+    /// is incremented (see [`Header::layout_version`]).
+    pub fn reclaim(&mut self, offset: u16, len: u16) {
+        trace!(page_id = ?self.id(), offset, len, "reclaiming bytes");
+        let free_offset = self.header.free_offset as usize;
+        let (offset, len) = (offset as usize, len as usize);
+        debug_assert!(offset + len <= free_offset);
+
+        self.bytes.copy_within(offset + len..free_offset, offset);
+        self.bytes[free_offset - len..free_offset].fill(0);
+        self.header.free_offset -= len as u16;
+        self.header.bump_layout_version();
+    }
+
+    /// Truncates the records section at the given offset, zeroing the bytes
+    /// after it. The `free_offset` marker is set to `offset` and the layout
+    /// version is incremented, as the records are assumed to have been moved.
+    /// NOTE THAT THIS METHOD DOESN'T ALTER THE UNDERLYING RECORD COUNTER.
+    pub fn truncate(&mut self, offset: u16) {
+        trace!(page_id = ?self.id(), offset, "truncating page");
+        let free_offset = self.header.free_offset as usize;
+        debug_assert!(offset as usize <= free_offset);
+        self.bytes[offset as usize..free_offset].fill(0);
+        self.header.free_offset = offset;
+        self.header.bump_layout_version();
+    }
+
     /// Reads at the given offset.
     pub fn read_at<F, R>(&self, offset: u16, f: F) -> DbResult<R>
     where
-        F: for<'a> FnOnce(&mut buff::Buff<'a>) -> DbResult<R>,
+        F: for<'a> FnOnce(&mut buff::BuffRead<'a>) -> DbResult<R>,
     {
         trace!(page_id = ?self.id(), "reading from buffer");
-        // TODO: HACK: One must be able to create a buf from a shared slice.
-        // TODO(buff-trait): Fix.
-        let mut cloned_buf = self.bytes[offset as usize..].to_owned();
-        let mut buf = buff::Buff::new(&mut cloned_buf);
+        let mut buf = buff::BuffRead::new(&self.bytes[offset as usize..]);
         f(&mut buf)
     }
 
@@ -123,6 +169,23 @@ impl HeapPage {
         self.header.free_offset
     }
 
+    /// Deserializes a heap page which may be corrupted (e.g., whose checksum
+    /// doesn't match), from its contents. Unlike [`Deserialize`], which assumes
+    /// a valid page, malformed headers (e.g., null page IDs) fail instead of
+    /// panicking, and the free offset is checked against the page size.
+    pub fn deserialize_tolerant(payload: &[u8]) -> DbResult<HeapPage> {
+        let mut buf = buff::BuffRead::new(payload);
+        if PageType::deserialize(&mut buf)? != PageType::Heap {
+            return Err(Error::CorruptedPage("not a heap page"));
+        }
+        let header = Header::deserialize_tolerant(&mut buf)?;
+        let bytes = payload[buf.offset()..].to_vec();
+        if header.free_offset as usize > bytes.len() {
+            return Err(Error::CorruptedPage("free offset is out of bounds"));
+        }
+        Ok(HeapPage { header, bytes })
+    }
+
     /// Constructs the first page of a heap page sequence.
     pub fn new_seq_first(page_size: u16, page_id: PageId) -> Self {
         let header = Header {
@@ -131,10 +194,12 @@ impl HeapPage {
                 last_page_id: page_id,
                 page_count: 1,
                 record_count: 0,
+                activity: ActivityCounters::default(),
             }),
             next_page_id: None,
             record_count: 0,
             free_offset: 0,
+            layout_version: 0,
         };
         let bytes = vec![0; page_size as usize - header.size() as usize];
 
@@ -149,6 +214,7 @@ impl HeapPage {
             next_page_id: Some(page_id),
             record_count: 0,
             free_offset: 0,
+            layout_version: 0,
         };
         let bytes = vec![0; page_size as usize - header.size() as usize];
 
@@ -157,7 +223,7 @@ impl HeapPage {
 }
 
 /// The [`HeapPage`] header. Not to be confused with [`SeqHeader`].
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Header {
     // Do not forget:
     // page_type: TypeId,
@@ -171,6 +237,10 @@ pub struct Header {
     pub record_count: u16,
     /// Offset of the free bytes section.
     pub free_offset: u16,
+    /// The version of the records' offsets, incremented whenever records are
+    /// moved within the page, so that stale
+    /// [`RecordId`](crate::exec::query::table::RecordId)s are detected.
+    pub layout_version: u16,
 }
 
 impl Size for Header {
@@ -181,6 +251,7 @@ impl Size for Header {
             + self.next_page_id.size()
             + 2
             + 2
+            + 2
     }
 }
 
@@ -190,14 +261,15 @@ impl Serialize for Header {
         self.id.serialize(buf)?;
         self.seq_header.serialize(buf)?;
         self.next_page_id.serialize(buf)?;
-        buf.write(self.record_count);
-        buf.write(self.free_offset);
+        buf.try_write(self.record_count)?;
+        buf.try_write(self.free_offset)?;
+        buf.try_write(self.layout_version)?;
         Ok(())
     }
 }
 
 impl Deserialize<'_> for Header {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -207,26 +279,118 @@ impl Deserialize<'_> for Header {
             next_page_id: Option::<PageId>::deserialize(buf)?,
             record_count: buf.read(),
             free_offset: buf.read(),
+            layout_version: buf.read(),
+        })
+    }
+}
+
+impl Header {
+    /// Increments the layout version, after records were moved.
+    fn bump_layout_version(&mut self) {
+        self.layout_version = self.layout_version.wrapping_add(1);
+    }
+
+    /// Same as [`Deserialize::deserialize`], but malformed headers fail instead
+    /// of panicking. See [`HeapPage::deserialize_tolerant`].
+    fn deserialize_tolerant(buf: &mut buff::BuffRead<'_>) -> DbResult<Header> {
+        let page_id = |buf: &mut buff::BuffRead<'_>| {
+            Option::<PageId>::deserialize(buf)?.ok_or(Error::CorruptedPage("null page id"))
+        };
+        let id = page_id(buf)?;
+        let discriminant: u8 = buf.read();
+        let seq_header = match discriminant {
+            0xAA => None,
+            0xFF => Some(SeqHeader {
+                last_page_id: page_id(buf)?,
+                page_count: buf.read(),
+                record_count: buf.read(),
+                activity: ActivityCounters::deserialize(buf)?,
+            }),
+            _ => return Err(Error::CorruptedPage("invalid sequence header tag")),
+        };
+        Ok(Header {
+            id,
+            seq_header,
+            next_page_id: Option::<PageId>::deserialize(buf)?,
+            record_count: buf.read(),
+            free_offset: buf.read(),
+            layout_version: buf.read(),
         })
     }
 }
 
 /// The [`HeapPage`] sequence header.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SeqHeader {
     /// The ID of the last page in this sequence.
     pub last_page_id: PageId,
     /// The number of pages in this sequence.
     pub page_count: u32,
-    /// The number of records in this sequence.
+    /// The number of records in this sequence (including the deleted ones).
     pub record_count: u64,
+    /// The activity counters of this sequence.
+    pub activity: ActivityCounters,
+}
+
+/// The activity counters of a heap sequence. See [`crate::exec::activity`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActivityCounters {
+    /// The number of inserted records.
+    pub inserts: u64,
+    /// The number of updated records.
+    pub updates: u64,
+    /// The number of deleted records.
+    pub deletes: u64,
+    /// The number of dead records (i.e., deleted or relocated by an update)
+    /// since the last vacuum.
+    pub dead_rows: u64,
+}
+
+impl ActivityCounters {
+    /// Adds the given counters to these ones.
+    pub fn add(&mut self, other: &ActivityCounters) {
+        self.inserts += other.inserts;
+        self.updates += other.updates;
+        self.deletes += other.deletes;
+        self.dead_rows += other.dead_rows;
+    }
+}
+
+impl Size for ActivityCounters {
+    fn size(&self) -> u32 {
+        4 * 8
+    }
+}
+
+impl Serialize for ActivityCounters {
+    fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        buf.try_write(self.inserts)?;
+        buf.try_write(self.updates)?;
+        buf.try_write(self.deletes)?;
+        buf.try_write(self.dead_rows)?;
+        Ok(())
+    }
+}
+
+impl Deserialize<'_> for ActivityCounters {
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(ActivityCounters {
+            inserts: buf.read(),
+            updates: buf.read(),
+            deletes: buf.read(),
+            dead_rows: buf.read(),
+        })
+    }
 }
 
 impl Size for Option<SeqHeader> {
     fn size(&self) -> u32 {
         1 + self
             .as_ref()
-            .map(|header| header.last_page_id.size() + 4 + 8)
+            .map(|header| header.last_page_id.size() + 4 + 8 + header.activity.size())
             .unwrap_or(1)
     }
 }
@@ -234,19 +398,20 @@ impl Size for Option<SeqHeader> {
 impl Serialize for Option<SeqHeader> {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
         let Some(header) = self else {
-            buf.write(0xAA_u8);
+            buf.try_write(0xAA_u8)?;
             return Ok(());
         };
-        buf.write(0xFF_u8);
+        buf.try_write(0xFF_u8)?;
         header.last_page_id.serialize(buf)?;
-        buf.write(header.page_count);
-        buf.write(header.record_count);
+        buf.try_write(header.page_count)?;
+        buf.try_write(header.record_count)?;
+        header.activity.serialize(buf)?;
         Ok(())
     }
 }
 
 impl Deserialize<'_> for Option<SeqHeader> {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -257,6 +422,7 @@ impl Deserialize<'_> for Option<SeqHeader> {
                 last_page_id: PageId::deserialize(buf)?,
                 page_count: buf.read(),
                 record_count: buf.read(),
+                activity: ActivityCounters::deserialize(buf)?,
             })),
             unexpected => {
                 error!(?unexpected, "invalid `SeqHeader` type discriminant");
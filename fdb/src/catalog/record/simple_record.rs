@@ -3,17 +3,21 @@ use std::{
     cmp::Ordering,
     fmt::{self, Debug},
     ops::Add,
+    panic::{self, AssertUnwindSafe},
 };
 
+use buff::BuffRead;
+
 use crate::{
     catalog::{page::PageId, table_schema::TableSchema},
-    error::DbResult,
+    error::{DbResult, Error},
     exec::operations::PhysicalState,
     util::io::{Deserialize, DeserializeCtx, Serialize, SerializeCtx, Size},
 };
 
 /// A simple database record. May store arbitrary bytes which are to be
 /// interpreted in a higher-level layer.
+#[derive(Clone)]
 pub struct SimpleRecord<'d, D>
 where
     D: Clone,
@@ -30,6 +34,11 @@ where
     total_size: u16,
     /// Whether the record is logically deleted.
     is_deleted: bool,
+    /// Whether the record spans many pages. Such records may only be written
+    /// through [`span`](crate::exec::operations::heap::span).
+    ///
+    /// This value is not serialized.
+    spanned: bool,
     /// The record's bytes. Notice that the size of this section is stored as a
     /// 2-byte number.
     // TODO(buff-trait): Use a slice here.
@@ -53,6 +62,7 @@ where
             offset,
             total_size: 0, // <---- One updates this below.
             is_deleted: false,
+            spanned: false,
             data,
             pad_size: 0,
         };
@@ -70,6 +80,16 @@ where
         self.is_deleted = true;
     }
 
+    /// Checks whether the record spans many pages.
+    pub fn is_spanned(&self) -> bool {
+        self.spanned
+    }
+
+    /// Marks the record as spanning many pages.
+    pub(crate) fn set_spanned(&mut self) {
+        self.spanned = true;
+    }
+
     /// Returns the record's [`PageId`].
     pub fn page_id(&self) -> PageId {
         self.page_id
@@ -106,7 +126,12 @@ where
     /// returned in the result's error variant.
     ///
     /// Notice that updates don't change the current record's `total_size`.
+    /// Records that span many pages are never updated in place, i.e., case `3`
+    /// always applies to them.
     pub fn try_update(&mut self, new_data: Cow<'d, D>) -> Result<(), Cow<'d, D>> {
+        if self.spanned {
+            return Err(new_data);
+        }
         let total_size = self.available_data_size();
         let new_size = new_data.size();
 
@@ -125,10 +150,73 @@ where
         }
     }
 
+    /// Returns the size of the padding section.
+    pub fn pad_size(&self) -> u16 {
+        self.pad_size
+    }
+
+    /// Drops the padding section, shrinking the record's `total_size`
+    /// accordingly. Returns the amount of bytes that were reclaimed.
+    ///
+    /// Callers must also reclaim such bytes in the underlying page (see
+    /// [`HeapPage::reclaim`](crate::catalog::page::HeapPage::reclaim)).
+    /// Otherwise, the next record would be read at the wrong offset.
+    pub fn trim_padding(&mut self) -> u16 {
+        let reclaimed = self.pad_size;
+        self.pad_size = 0;
+        self.total_size -= reclaimed;
+        reclaimed
+    }
+
     /// Returns the available size for the `data` section.
     fn available_data_size(&self) -> u32 {
         self.size() - 2 - 1
     }
+
+    /// Deserializes a record which may be corrupted (e.g., from a page whose
+    /// checksum doesn't match), decoding its data with the given closure.
+    ///
+    /// Unlike the [`DeserializeCtx`] implementations, which assume a valid
+    /// record, the record is never read beyond its stated size, and malformed
+    /// records fail with [`Error::CorruptedRecord`] instead of panicking. Since
+    /// the value decoders panic on truncated input, such panics are caught.
+    /// Spanned records (see [`span`]) must be assembled beforehand.
+    ///
+    /// [`span`]: crate::exec::operations::heap::span
+    pub fn deserialize_tolerant<F>(
+        buf: &mut BuffRead<'_>,
+        location: PhysicalState,
+        decode: F,
+    ) -> DbResult<Self>
+    where
+        F: FnOnce(&mut BuffRead<'_>) -> DbResult<D>,
+    {
+        let total_size: u16 = buf.try_read()?;
+        let bytes = buf.try_read_bytes((total_size as usize).saturating_sub(2))?;
+        let Some((&flags, data_bytes)) = bytes.split_first() else {
+            return Err(Error::CorruptedRecord("invalid record size"));
+        };
+        let is_deleted = match flags {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::CorruptedRecord("invalid flags")),
+        };
+        let data = panic::catch_unwind(AssertUnwindSafe(|| decode(&mut BuffRead::new(data_bytes))))
+            .map_err(|_| Error::CorruptedRecord("malformed data"))??;
+        let pad_size = (data_bytes.len() as u32)
+            .checked_sub(data.size())
+            .ok_or(Error::CorruptedRecord("data overruns the record"))?;
+
+        Ok(SimpleRecord {
+            page_id: location.page_id,
+            offset: location.offset,
+            total_size,
+            is_deleted,
+            spanned: false,
+            data: Cow::Owned(data),
+            pad_size: pad_size as u16,
+        })
+    }
 }
 
 impl<D> Size for SimpleRecord<'_, D>
@@ -149,6 +237,7 @@ where
     D: SerializeCtx<TableSchema> + Clone,
 {
     fn serialize(&self, buf: &mut buff::Buff<'_>, ctx: &TableRecordCtx<'_>) -> DbResult<()> {
+        debug_assert!(!self.spanned, "spanned records can't be written in place");
         buf.write(self.total_size);
         buf.write(self.is_deleted);
         self.data.serialize(buf, ctx.schema)?;
@@ -162,7 +251,7 @@ impl<D> DeserializeCtx<'_, TableRecordCtx<'_>> for SimpleRecord<'_, D>
 where
     D: for<'a> DeserializeCtx<'a, TableSchema> + Size + Clone,
 {
-    fn deserialize(buf: &mut buff::Buff<'_>, ctx: &TableRecordCtx<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>, ctx: &TableRecordCtx<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -187,6 +276,7 @@ where
             offset: ctx.offset,
             total_size,
             is_deleted,
+            spanned: false,
             data: Cow::Owned(data),
             pad_size,
         })
@@ -212,7 +302,7 @@ impl<D> DeserializeCtx<'_, SimpleCtx> for SimpleRecord<'_, D>
 where
     D: for<'a> Deserialize<'a> + Size + Clone,
 {
-    fn deserialize(buf: &mut buff::Buff<'_>, ctx: &SimpleCtx) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>, ctx: &SimpleCtx) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -237,6 +327,7 @@ where
             offset: ctx.offset,
             total_size,
             is_deleted,
+            spanned: false,
             data: Cow::Owned(data),
             pad_size,
         })
@@ -292,6 +383,7 @@ where
             .field("offset", &self.offset)
             .field("total_size", &self.total_size)
             .field("is_deleted", &self.is_deleted)
+            .field("spanned", &self.spanned)
             .field("data", &self.data)
             .field("pad_size", &self.pad_size)
             .finish()
@@ -0,0 +1,158 @@
+use crate::{
+    catalog::ty::TypeId,
+    error::DbResult,
+    exec::value::Value,
+    util::io::{Deserialize, DeserializeCtx, Serialize, Size, VarString},
+};
+
+/// The statistics of a table, stored in the database schema (see
+/// [`ObjectType::Statistics`]) and maintained by the
+/// [`StatisticsTracker`](crate::exec::statistics::StatisticsTracker).
+///
+/// [`ObjectType::Statistics`]: crate::catalog::object::ObjectType::Statistics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableStatistics {
+    /// The number of live records.
+    pub row_count: u64,
+    /// The number of pages of the table's heap sequence.
+    pub page_count: u32,
+    /// The total size (in bytes) of the live records' data.
+    pub total_size: u64,
+    /// The statistics of the indexed columns.
+    pub columns: Vec<ColumnStatistics>,
+}
+
+impl TableStatistics {
+    /// Returns the average size (in bytes) of the live records' data.
+    pub fn avg_record_size(&self) -> f64 {
+        if self.row_count == 0 {
+            0.0
+        } else {
+            self.total_size as f64 / self.row_count as f64
+        }
+    }
+
+    /// Returns the statistics of the given column, if it is tracked.
+    pub fn column(&self, column: &str) -> Option<&ColumnStatistics> {
+        self.columns.iter().find(|stats| stats.column == column)
+    }
+}
+
+impl Size for TableStatistics {
+    fn size(&self) -> u32 {
+        8 + 4 + 8 + 2 + self.columns.iter().map(Size::size).sum::<u32>()
+    }
+}
+
+impl Serialize for TableStatistics {
+    fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        buf.write(self.row_count);
+        buf.write(self.page_count);
+        buf.write(self.total_size);
+        buf.write(self.columns.len() as u16);
+        for column in &self.columns {
+            column.serialize(buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl Deserialize<'_> for TableStatistics {
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
+    where
+        Self: Sized,
+    {
+        let row_count = buf.read();
+        let page_count = buf.read();
+        let total_size = buf.read();
+        let len: u16 = buf.read();
+        let columns = (0..len)
+            .map(|_| ColumnStatistics::deserialize(buf))
+            .collect::<DbResult<_>>()?;
+        Ok(TableStatistics {
+            row_count,
+            page_count,
+            total_size,
+            columns,
+        })
+    }
+}
+
+/// The statistics of a column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    /// The column name.
+    pub column: String,
+    /// The type of the column.
+    pub ty: TypeId,
+    /// The minimum value. Deleting records doesn't shrink the range, hence
+    /// it's only guaranteed to be a lower bound.
+    pub min: Value,
+    /// The maximum value. Likewise, it's only guaranteed to be an upper bound.
+    pub max: Value,
+}
+
+impl ColumnStatistics {
+    /// Creates the statistics of a column whose only value is the given one.
+    pub fn new(column: impl Into<String>, value: &Value) -> ColumnStatistics {
+        ColumnStatistics {
+            column: column.into(),
+            ty: value.type_id(),
+            min: value.clone(),
+            max: value.clone(),
+        }
+    }
+
+    /// Widens the range to include the given value.
+    pub fn widen(&mut self, value: &Value) {
+        if value < &self.min {
+            self.min = value.clone();
+        }
+        if value > &self.max {
+            self.max = value.clone();
+        }
+    }
+
+    /// Widens the range to include the given one.
+    pub fn merge(&mut self, other: &ColumnStatistics) {
+        self.widen(&other.min);
+        self.widen(&other.max);
+    }
+}
+
+impl Size for ColumnStatistics {
+    fn size(&self) -> u32 {
+        VarString::from(self.column.as_str()).size()
+            + self.ty.size()
+            + self.min.size()
+            + self.max.size()
+    }
+}
+
+impl Serialize for ColumnStatistics {
+    fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        VarString::from(self.column.as_str()).serialize(buf)?;
+        self.ty.serialize(buf)?;
+        self.min.serialize(buf)?;
+        self.max.serialize(buf)?;
+        Ok(())
+    }
+}
+
+impl Deserialize<'_> for ColumnStatistics {
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
+    where
+        Self: Sized,
+    {
+        let column = VarString::deserialize(buf)?.into();
+        let ty = TypeId::deserialize(buf)?;
+        let min = Value::deserialize(buf, &ty)?;
+        let max = Value::deserialize(buf, &ty)?;
+        Ok(ColumnStatistics {
+            column,
+            ty,
+            min,
+            max,
+        })
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
 };
 
 /// A table schema.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TableSchema {
     /// The table columns.
     ///
@@ -14,6 +14,13 @@ pub struct TableSchema {
     pub columns: Vec<Column>,
 }
 
+impl TableSchema {
+    /// Returns the ordinal (i.e., the position) of the given column.
+    pub fn ordinal(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.name == name)
+    }
+}
+
 impl Size for TableSchema {
     fn size(&self) -> u32 {
         VarList::from(self.columns.as_slice()).size()
@@ -28,7 +35,7 @@ impl Serialize for TableSchema {
 }
 
 impl Deserialize<'_> for TableSchema {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
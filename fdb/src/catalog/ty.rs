@@ -1,6 +1,14 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ptr,
+    sync::Mutex,
+};
+
 use tracing::error;
 
 use crate::{
+    catalog::table_schema::TableSchema,
     error::{DbResult, Error},
     util::io::{Deserialize, Serialize, Size},
 };
@@ -22,56 +30,156 @@ pub enum TypeId {
     /// represent an array of short integers, one would use `0001_0010`. To
     /// represent a simple (i.e., primitive) short integer, `0000_0010`.
     Array(PrimitiveTypeId),
+    /// Multi-dimensional array type, i.e., an array of arrays (of one less
+    /// dimension), with the given number of dimensions (from 2 up to
+    /// [`MAX_ARRAY_DIMS`]). The number of dimensions is encoded in place of the
+    /// "array type", e.g., a two-dimensional array of short integers is
+    /// represented by `0010_0010`.
+    NestedArray(PrimitiveTypeId, u8),
+    /// Composite type, whose values are sets of named fields (see
+    /// [`CompositeType`]). Serialized as the `0000_1111` tag, followed by the
+    /// fields' schema. Arrays of composite values aren't supported.
+    Composite(CompositeType),
 }
 
+/// The type tag of [`TypeId::Composite`], whose 4 least significant bits don't
+/// encode any primitive type.
+const COMPOSITE_TAG: u8 = 0b0000_1111;
+
+/// The maximum number of dimensions of array types. The most significant bit
+/// of the type tag is left unused, so that it may be used as a flag (e.g., by
+/// the column definitions).
+pub const MAX_ARRAY_DIMS: u8 = 7;
+
 impl Size for TypeId {
     fn size(&self) -> u32 {
-        1
+        1 + match self {
+            TypeId::Composite(composite) => composite.fields().size(),
+            ty => ty.primitive().map_or(0, PrimitiveTypeId::params_size),
+        }
     }
 }
 
 impl Serialize for TypeId {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        self.serialize_with_flags(0, buf)
+    }
+}
+
+impl Deserialize<'_> for TypeId {
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
+    where
+        Self: Sized,
+    {
+        let tag = buf.read();
+        Self::deserialize_tagged(tag, buf)
+    }
+}
+
+impl TypeId {
+    /// Returns the type tag (see [`TypeId::Array`]).
+    fn to_u8(self) -> u8 {
         let (hi_discriminant, lo_discriminant) = match self {
             TypeId::Primitive(primitive) => (0, primitive.to_u8()),
             TypeId::Array(primitive) => (1, primitive.to_u8()),
+            TypeId::NestedArray(primitive, dims) => (dims, primitive.to_u8()),
+            TypeId::Composite(_) => return COMPOSITE_TAG,
         };
         // Those parentheses are necessary. <:
-        let discriminant: u8 = (hi_discriminant << 4) + lo_discriminant;
-        buf.write(discriminant);
+        (hi_discriminant << 4) + lo_discriminant
+    }
+
+    /// Serializes the type tag, combined with the given flags (which must only
+    /// use the tag's unused most significant bit), followed by the type
+    /// parameters, if any (see [`PrimitiveTypeId::Decimal`] and
+    /// [`TypeId::Composite`]).
+    pub(crate) fn serialize_with_flags(self, flags: u8, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        buf.try_write(self.to_u8() | flags)?;
+        match self {
+            TypeId::Composite(composite) => composite.fields().serialize(buf)?,
+            ty => ty
+                .primitive()
+                .expect("non-composite type")
+                .serialize_params(buf)?,
+        }
         Ok(())
     }
-}
 
-impl Deserialize<'_> for TypeId {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
-    where
-        Self: Sized,
-    {
-        let tag: u8 = buf.read();
+    /// Parses the given type tag (see [`TypeId::Array`]), reading the type
+    /// parameters which follow it, if any (see [`PrimitiveTypeId::Decimal`]).
+    pub(crate) fn deserialize_tagged(tag: u8, buf: &mut buff::BuffRead<'_>) -> DbResult<Self> {
+        if tag == COMPOSITE_TAG {
+            let fields = TableSchema::deserialize(buf)?;
+            return Ok(Self::Composite(CompositeType::new(fields)));
+        }
 
         let hi_discriminant = tag >> 4; // 4 most significant bits
         let lo_discriminant = tag & 0xF; // 4 least significant bits
 
-        let primitive_type = PrimitiveTypeId::try_from_u8(lo_discriminant)?;
+        let primitive_type = PrimitiveTypeId::deserialize_tagged(lo_discriminant, buf)?;
 
         match hi_discriminant {
             0 => Ok(Self::Primitive(primitive_type)),
             1 => Ok(Self::Array(primitive_type)),
+            2..=MAX_ARRAY_DIMS => Ok(Self::NestedArray(primitive_type, hi_discriminant)),
             unexpected => {
                 error!(?unexpected, "invalid `TypeId` type discriminant");
                 Err(Error::CorruptedTypeTag)
             }
         }
     }
-}
 
-impl TypeId {
     /// Returns the canonical type name.
     pub fn name(self) -> &'static str {
         match self {
             TypeId::Primitive(primitive) => primitive.name(),
-            TypeId::Array(_) => "array",
+            TypeId::Array(_) | TypeId::NestedArray(..) => "array",
+            TypeId::Composite(_) => "struct",
+        }
+    }
+
+    /// Returns the number of dimensions of the type, which is zero for
+    /// primitive and composite types.
+    pub fn dims(self) -> u8 {
+        match self {
+            TypeId::Primitive(_) | TypeId::Composite(_) => 0,
+            TypeId::Array(_) => 1,
+            TypeId::NestedArray(_, dims) => dims,
+        }
+    }
+
+    /// Returns the element type of array types, or `None` for other types.
+    pub fn element_type(self) -> Option<TypeId> {
+        match self {
+            TypeId::Primitive(_) | TypeId::Composite(_) => None,
+            TypeId::Array(primitive) => Some(TypeId::Primitive(primitive)),
+            TypeId::NestedArray(primitive, 2) => Some(TypeId::Array(primitive)),
+            TypeId::NestedArray(primitive, dims) => Some(TypeId::NestedArray(primitive, dims - 1)),
+        }
+    }
+
+    /// Returns the type of the arrays whose elements are of this type, or
+    /// `None` if it would have more than [`MAX_ARRAY_DIMS`] dimensions or if
+    /// this is a composite type.
+    pub fn array_of(self) -> Option<TypeId> {
+        match self {
+            TypeId::Primitive(primitive) => Some(TypeId::Array(primitive)),
+            TypeId::Array(primitive) => Some(TypeId::NestedArray(primitive, 2)),
+            TypeId::NestedArray(primitive, dims) if dims < MAX_ARRAY_DIMS => {
+                Some(TypeId::NestedArray(primitive, dims + 1))
+            }
+            TypeId::NestedArray(..) | TypeId::Composite(_) => None,
+        }
+    }
+
+    /// Returns the primitive type, or the (innermost) element type of array
+    /// types. Returns `None` for composite types.
+    pub fn primitive(self) -> Option<PrimitiveTypeId> {
+        match self {
+            TypeId::Primitive(primitive)
+            | TypeId::Array(primitive)
+            | TypeId::NestedArray(primitive, _) => Some(primitive),
+            TypeId::Composite(_) => None,
         }
     }
 
@@ -86,8 +194,63 @@ impl TypeId {
     }
 }
 
+/// The fields of a composite type, i.e., a schema whose (schematized) rows are
+/// the values of the type. Fields may be of any type, including other
+/// composite types.
+///
+/// Composite types are interned (see [`CompositeType::new`]), so that type ids
+/// remain cheap to copy and compare. Hence, two composite types are equal if
+/// (and only if) their fields are equal.
+#[derive(Copy, Clone)]
+pub struct CompositeType(&'static TableSchema);
+
+impl CompositeType {
+    /// Returns the composite type of the given fields.
+    ///
+    /// Interned types are never freed, which is fine since there are only as
+    /// many of them as distinct composite column definitions.
+    pub fn new(fields: TableSchema) -> CompositeType {
+        static TYPES: Mutex<Vec<&'static TableSchema>> = Mutex::new(Vec::new());
+
+        let mut types = TYPES.lock().unwrap();
+        if let Some(interned) = types.iter().find(|interned| ***interned == fields) {
+            return CompositeType(interned);
+        }
+        let interned: &'static TableSchema = Box::leak(Box::new(fields));
+        types.push(interned);
+        CompositeType(interned)
+    }
+
+    /// Returns the fields' schema.
+    pub fn fields(self) -> &'static TableSchema {
+        self.0
+    }
+}
+
+impl PartialEq for CompositeType {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for CompositeType {}
+
+impl Hash for CompositeType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(self.0, state);
+    }
+}
+
+impl fmt::Debug for CompositeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.columns.iter().map(|field| (&field.name, field.ty)))
+            .finish()
+    }
+}
+
 /// `fdb` possible primitive (i.e., non-composite) value types.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum PrimitiveTypeId {
     Bool = 0,
@@ -98,27 +261,45 @@ pub enum PrimitiveTypeId {
     Timestamp = 5,
     Text = 6,
     Blob = 7,
+    /// A 64-bit floating point number.
+    Float = 8,
+    /// A fixed-point number, stored as a 64-bit integer of which the given
+    /// number of (least significant) decimal digits are fractional. The scale
+    /// is part of the type (and thus stored in the column definitions), and
+    /// is serialized in a byte which follows the type tag.
+    Decimal(u8) = 9,
+    /// A calendar date, stored as a 32-bit number of days since the Unix epoch.
+    /// See [`Date`](crate::exec::time::Date).
+    Date = 10,
+    /// A time of day, stored as a 32-bit number of milliseconds since midnight.
+    /// See [`Time`](crate::exec::time::Time).
+    Time = 11,
 }
 
+/// The maximum scale of [`PrimitiveTypeId::Decimal`] types, so that at least
+/// one integer digit fits in the underlying 64-bit integer.
+pub const MAX_DECIMAL_SCALE: u8 = 18;
+
 impl Size for PrimitiveTypeId {
     fn size(&self) -> u32 {
-        1
+        1 + self.params_size()
     }
 }
 
 impl Serialize for PrimitiveTypeId {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
-        buf.write(self.to_u8());
-        Ok(())
+        buf.try_write(self.to_u8())?;
+        self.serialize_params(buf)
     }
 }
 
 impl Deserialize<'_> for PrimitiveTypeId {
-    fn deserialize(buf: &mut buff::Buff<'_>) -> DbResult<Self>
+    fn deserialize(buf: &mut buff::BuffRead<'_>) -> DbResult<Self>
     where
         Self: Sized,
     {
-        Self::try_from_u8(buf.read())
+        let tag = buf.read();
+        Self::deserialize_tagged(tag, buf)
     }
 }
 
@@ -134,16 +315,69 @@ impl PrimitiveTypeId {
             PrimitiveTypeId::Timestamp => "timestamp",
             PrimitiveTypeId::Text => "text",
             PrimitiveTypeId::Blob => "blob",
+            PrimitiveTypeId::Float => "float",
+            PrimitiveTypeId::Decimal(_) => "decimal",
+            PrimitiveTypeId::Date => "date",
+            PrimitiveTypeId::Time => "time",
         }
     }
 
-    /// Serialized representation.
+    /// Returns the type with the given (case-insensitive) canonical name, if
+    /// any. See [`PrimitiveTypeId::name`]. Decimal types have a zero scale.
+    pub fn from_name(name: &str) -> Option<PrimitiveTypeId> {
+        match name.to_lowercase().as_str() {
+            "bool" => Some(PrimitiveTypeId::Bool),
+            "byte" => Some(PrimitiveTypeId::Byte),
+            "shortint" => Some(PrimitiveTypeId::ShortInt),
+            "int" => Some(PrimitiveTypeId::Int),
+            "bigint" => Some(PrimitiveTypeId::BigInt),
+            "timestamp" => Some(PrimitiveTypeId::Timestamp),
+            "text" => Some(PrimitiveTypeId::Text),
+            "blob" => Some(PrimitiveTypeId::Blob),
+            "float" => Some(PrimitiveTypeId::Float),
+            "decimal" => Some(PrimitiveTypeId::Decimal(0)),
+            "date" => Some(PrimitiveTypeId::Date),
+            "time" => Some(PrimitiveTypeId::Time),
+            _ => None,
+        }
+    }
+
+    /// Serialized representation, without the type parameters.
     fn to_u8(self) -> u8 {
-        self as u8
+        match self {
+            PrimitiveTypeId::Bool => 0,
+            PrimitiveTypeId::Byte => 1,
+            PrimitiveTypeId::ShortInt => 2,
+            PrimitiveTypeId::Int => 3,
+            PrimitiveTypeId::BigInt => 4,
+            PrimitiveTypeId::Timestamp => 5,
+            PrimitiveTypeId::Text => 6,
+            PrimitiveTypeId::Blob => 7,
+            PrimitiveTypeId::Float => 8,
+            PrimitiveTypeId::Decimal(_) => 9,
+            PrimitiveTypeId::Date => 10,
+            PrimitiveTypeId::Time => 11,
+        }
+    }
+
+    /// The size of the serialized type parameters.
+    fn params_size(self) -> u32 {
+        match self {
+            PrimitiveTypeId::Decimal(_) => 1,
+            _ => 0,
+        }
     }
 
-    /// Deserialize the type id from the given byte.
-    fn try_from_u8(serialized: u8) -> DbResult<Self> {
+    fn serialize_params(self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
+        if let PrimitiveTypeId::Decimal(scale) = self {
+            buf.try_write(scale)?;
+        }
+        Ok(())
+    }
+
+    /// Deserialize the type id from the given byte, reading the type
+    /// parameters which follow it, if any.
+    fn deserialize_tagged(serialized: u8, buf: &mut buff::BuffRead<'_>) -> DbResult<Self> {
         match serialized {
             0 => Ok(PrimitiveTypeId::Bool),
             1 => Ok(PrimitiveTypeId::Byte),
@@ -153,6 +387,17 @@ impl PrimitiveTypeId {
             5 => Ok(PrimitiveTypeId::Timestamp),
             6 => Ok(PrimitiveTypeId::Text),
             7 => Ok(PrimitiveTypeId::Blob),
+            8 => Ok(PrimitiveTypeId::Float),
+            9 => {
+                let scale: u8 = buf.read();
+                if scale > MAX_DECIMAL_SCALE {
+                    error!(?scale, "invalid decimal scale");
+                    return Err(Error::CorruptedTypeTag);
+                }
+                Ok(PrimitiveTypeId::Decimal(scale))
+            }
+            10 => Ok(PrimitiveTypeId::Date),
+            11 => Ok(PrimitiveTypeId::Time),
             unexpected => {
                 error!(?unexpected, "invalid `PrimitiveTypeId` type discriminant");
                 Err(Error::CorruptedTypeTag)
@@ -164,6 +409,7 @@ impl PrimitiveTypeId {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::catalog::column::Column;
 
     #[test]
     fn test_type_id_representation() {
@@ -172,6 +418,8 @@ mod tests {
             (0b0000_0010, TypeId::Primitive(PrimitiveTypeId::ShortInt)),
             (0b0001_0110, TypeId::Array(PrimitiveTypeId::Text)),
             (0b0000_0110, TypeId::Primitive(PrimitiveTypeId::Text)),
+            (0b0010_0011, TypeId::NestedArray(PrimitiveTypeId::Int, 2)),
+            (0b0111_0111, TypeId::NestedArray(PrimitiveTypeId::Blob, 7)),
         ];
 
         let mut buf = [0_u8; 1];
@@ -186,12 +434,87 @@ mod tests {
                 "invalid serialization for case `{case:?}`"
             );
 
-            buf.seek(0);
-            let deserialized_type_id = TypeId::deserialize(buf).expect("should deserialize");
+            let deserialized_type_id = TypeId::deserialize(&mut buff::BuffRead::new(buf.get()))
+                .expect("should deserialize");
             assert_eq!(
                 deserialized_type_id, type_id,
                 "invalid deserialization for case `{case:?}`"
             );
         }
     }
+
+    #[test]
+    fn test_array_dims() {
+        let int = TypeId::Primitive(PrimitiveTypeId::Int);
+        let mut ty = int;
+        for dims in 1..=MAX_ARRAY_DIMS {
+            let array = ty.array_of().expect("should nest");
+            assert_eq!(array.dims(), dims);
+            assert_eq!(array.element_type(), Some(ty));
+            assert_eq!(array.primitive(), Some(PrimitiveTypeId::Int));
+            ty = array;
+        }
+        assert_eq!(ty.array_of(), None);
+        assert_eq!(int.element_type(), None);
+    }
+
+    #[test]
+    fn test_composite_type_id_representation() {
+        let field = |name: &str, ty| Column {
+            ty,
+            name: name.into(),
+            max_len: None,
+            default: None,
+            not_null: false,
+        };
+        let fields = TableSchema {
+            columns: vec![
+                field("a", TypeId::Primitive(PrimitiveTypeId::Byte)),
+                field("b", TypeId::Array(PrimitiveTypeId::Decimal(2))),
+            ],
+        };
+        let type_id = TypeId::Composite(CompositeType::new(fields.clone()));
+        // Composite types with the same fields are equal.
+        assert_eq!(type_id, TypeId::Composite(CompositeType::new(fields)));
+
+        let mut buf = [0_u8; 32];
+        let buf = &mut buff::Buff::new(&mut buf);
+        type_id.serialize(buf).expect("should serialize");
+        assert_eq!(buf.offset() as u32, type_id.size());
+        assert_eq!(buf.get()[0], COMPOSITE_TAG);
+
+        assert_eq!(
+            TypeId::deserialize(&mut buff::BuffRead::new(buf.get())).expect("should deserialize"),
+            type_id
+        );
+
+        let nested = TypeId::Composite(CompositeType::new(TableSchema {
+            columns: vec![field("inner", type_id)],
+        }));
+        assert_ne!(nested, type_id);
+        assert_eq!(nested.name(), "struct");
+        assert_eq!(nested.array_of(), None);
+        assert_eq!(nested.primitive(), None);
+    }
+
+    #[test]
+    fn test_decimal_type_id_representation() {
+        let mut buf = [0_u8; 2];
+        let buf = &mut buff::Buff::new(&mut buf);
+
+        let type_id = TypeId::Array(PrimitiveTypeId::Decimal(2));
+        assert_eq!(type_id.size(), 2);
+        type_id.serialize(buf).expect("should serialize");
+        assert_eq!(buf.get(), [0b0001_1001, 2]);
+
+        assert_eq!(
+            TypeId::deserialize(&mut buff::BuffRead::new(buf.get())).expect("should deserialize"),
+            type_id
+        );
+
+        buf.seek(0);
+        buf.write(0b0000_1001_u8);
+        buf.write(MAX_DECIMAL_SCALE + 1);
+        assert!(TypeId::deserialize(&mut buff::BuffRead::new(buf.get())).is_err());
+    }
 }
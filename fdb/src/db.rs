@@ -1,38 +1,398 @@
-use std::path::Path;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as SyncMutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{broadcast, RwLock};
+use tracing::{info, warn};
 
 use crate::{
-    error::DbResult,
-    exec::query::Query,
-    io::{bootstrap, disk_manager::DiskManager, pager::Pager},
+    catalog::{
+        object::Object,
+        page::{FirstPage, PageId},
+        statistics::TableStatistics,
+    },
+    error::{DbResult, Error},
+    exec::{
+        activity::{self, ActivityTracker, TableActivity, VacuumThreshold},
+        catalog_cache::CatalogCache,
+        decode_cache::DecodeCache,
+        integrity::{self, IntegrityReport},
+        kv::Kv,
+        locking::{LockManager, DEFAULT_LOCK_TIMEOUT},
+        notify::{Change, ChangeNotifier},
+        query::Query,
+        salvage::{self, SalvageReport},
+        statistics::{self, StatisticsTracker},
+        transaction::{self, Transaction},
+    },
+    io::{
+        alloc::AllocState,
+        bootstrap,
+        disk_manager::{DiskManager, SegmentLayout, SyncMode},
+        flusher::Flusher,
+        group_commit::GroupCommitStats,
+        metrics::MetricsSnapshot,
+        pager::{FlushPolicy, Pager, DEFAULT_CACHE_CAPACITY},
+        storage::{MemoryBackend, StorageBackend},
+        temp::{self, TempFiles},
+        txn,
+    },
+    sql::{self, planner::SqlOutput, result_cache::ResultCache},
 };
 
 /// A `fdb` database instance.
 pub struct Db {
-    pager: Pager,
+    pager: Arc<Pager>,
+    /// The statement-level latch. See [`Db::execute`].
+    statement_latch: Arc<RwLock<()>>,
+    /// Writes the dirty pages once the flush policy's delay elapses, unless
+    /// the database is read-only. See [`flusher`](crate::io::flusher).
+    _flusher: Option<Flusher>,
+    /// The table activity tracker. See [`Db::activity`].
+    activity: ActivityTracker,
+    /// The table statistics tracker. See [`Db::table_statistics`].
+    statistics: StatisticsTracker,
+    /// The table change subscriptions. See [`Db::subscribe`].
+    notifier: ChangeNotifier,
+    /// The decoded record cache, if enabled. See [`Db::decode_cache`].
+    decode_cache: Option<DecodeCache>,
+    /// The query result cache, if enabled. See [`Db::result_cache`].
+    result_cache: Option<ResultCache>,
+    /// The record locks. See [`Db::locks`].
+    locks: Arc<LockManager>,
+    /// The instant of the last [`Db::checkpoint`], if any.
+    last_checkpoint: SyncMutex<Option<Instant>>,
+    /// The recovery performed when the database was opened.
+    recovery: RecoveryState,
+    /// The environment the database was opened in. See [`Db::environment`].
+    environment: DbEnvironment,
+    /// Whether the database was opened in read-only mode.
+    read_only: bool,
+    /// The catalog version. See [`Db::catalog_version`].
+    catalog_version: AtomicU64,
+    /// The catalog object cache. See [`Db::catalog_cache`].
+    catalog_cache: CatalogCache,
+    /// The temporary files. See [`Db::temp_files`].
+    temp_files: TempFiles,
 }
 
 impl Db {
     /// Opens a database "connection" and returns the instance. This method also
     /// bootstraps the database on the first access.
     ///
-    /// On first access, `true` is returned as the second tuple element.
+    /// On first access, `true` is returned as the second tuple element. The
+    /// database file is created atomically (see [`bootstrap::create_file`]),
+    /// so that, out of many concurrent first accesses to the same path, only
+    /// one of them sees `true`.
     pub async fn open(path: &Path) -> DbResult<(Self, bool)> {
-        const DEFAULT_PAGE_SIZE: u16 = 4 * 1024;
-        Self::open_with_page_size(path, DEFAULT_PAGE_SIZE).await
+        Self::open_with_options(path, DbOptions::default()).await
     }
 
     /// Same as [`Db::open`], but allows for setting a different page size.
     pub async fn open_with_page_size(path: &Path, page_size: u16) -> DbResult<(Self, bool)> {
-        let disk_manager = DiskManager::new(Path::new(path), page_size).await?;
-        let mut pager = Pager::new(disk_manager);
+        let options = DbOptions {
+            page_size,
+            ..DbOptions::default()
+        };
+        Self::open_with_options(path, options).await
+    }
+
+    /// Same as [`Db::open`], but allows for setting the [`DbOptions`].
+    ///
+    /// Fails with [`Error::PageSizeMismatch`] if the database file was created
+    /// with another page size. In read-only mode, the database file must exist.
+    ///
+    /// If [`DbOptions::max_segment_size`] is set, the pages are stored in
+    /// segment files named after the given path instead (see
+    /// [`SegmentLayout`]). Notice that, unlike database files, new segmented
+    /// databases are bootstrapped after their first segment is created, hence
+    /// not atomically.
+    pub async fn open_with_options(path: &Path, options: DbOptions) -> DbResult<(Self, bool)> {
+        options.validate()?;
+        if let Some(max_segment_size) = options.max_segment_size {
+            let layout = SegmentLayout::new(options.page_size, max_segment_size)?;
+            let disk_manager = DiskManager::new_segmented(path, layout, options.read_only).await?;
+            info!(?path, ?layout, "opening segmented database");
+            let temp_files = TempFiles::for_path(path, options.temp_dir.as_deref());
+            return Self::open_disk_manager(disk_manager, temp_files, options, false).await;
+        }
+        let (disk_manager, created) = if options.read_only {
+            let disk_manager = DiskManager::new_read_only(path, options.page_size).await?;
+            (disk_manager, false)
+        } else {
+            let created = bootstrap::create_file(path, options.page_size).await?;
+            (DiskManager::new(path, options.page_size).await?, created)
+        };
+        info!(?path, created, "opening database");
+        let temp_files = TempFiles::for_path(path, options.temp_dir.as_deref());
+        Self::open_disk_manager(disk_manager, temp_files, options, created).await
+    }
+
+    /// Same as [`Db::open_with_options`], but the pages are stored in the given
+    /// backend instead of a file. See [`storage`](crate::io::storage).
+    pub async fn open_with_backend(
+        backend: impl StorageBackend + 'static,
+        options: DbOptions,
+    ) -> DbResult<(Self, bool)> {
+        options.validate()?;
+        let disk_manager = DiskManager::with_backend(Box::new(backend), options.page_size);
+        let temp_files = TempFiles::anonymous(options.temp_dir.as_deref());
+        Self::open_disk_manager(disk_manager, temp_files, options, false).await
+    }
+
+    /// Opens a new, empty, database whose pages are kept in memory (see
+    /// [`MemoryBackend`]), hence lost once it's dropped.
+    pub async fn open_in_memory() -> DbResult<Self> {
+        Self::open_in_memory_with_options(DbOptions::default()).await
+    }
+
+    /// Same as [`Db::open_in_memory`], but allows for setting the
+    /// [`DbOptions`]. Fails with [`Error::ReadOnly`] in read-only mode, since
+    /// the database would always be empty.
+    pub async fn open_in_memory_with_options(options: DbOptions) -> DbResult<Self> {
+        let (db, _) = Self::open_with_backend(MemoryBackend::new(), options).await?;
+        Ok(db)
+    }
 
-        let is_new = bootstrap::boot_first_page(&mut pager).await?;
-        Ok((Db { pager }, is_new))
+    /// Opens the database over the given disk manager, whose storage was just
+    /// created (and bootstrapped) if `created` is `true`.
+    async fn open_disk_manager(
+        mut disk_manager: DiskManager,
+        temp_files: TempFiles,
+        options: DbOptions,
+        created: bool,
+    ) -> DbResult<(Self, bool)> {
+        disk_manager.set_sync_mode(options.sync_mode);
+        match disk_manager.read_header_page_size().await? {
+            Some(actual) if actual != options.page_size => {
+                return Err(Error::PageSizeMismatch {
+                    expected: options.page_size,
+                    actual,
+                });
+            }
+            None if options.read_only => return Err(Error::ReadOnly),
+            _ => (),
+        }
+
+        let mut pager = Pager::with_cache_capacity(disk_manager, options.cache_capacity);
+        pager.set_flush_policy(options.flush_policy);
+        pager.set_group_commit_delay(options.group_commit_delay);
+        pager
+            .io_scheduler()
+            .set_background_rate(options.background_io_rate);
+
+        // Empty storages (e.g., new backends) are bootstrapped in place.
+        let is_new = bootstrap::boot_first_page(&mut pager).await? || created;
+        let recovery = if is_new {
+            RecoveryState::Created
+        } else if options.read_only {
+            // Leftovers (if any) are purged by the next read-write open.
+            pager.warmup().await?;
+            RecoveryState::Clean
+        } else {
+            let purged = temp::purge(&pager).await?;
+            pager.warmup().await?;
+            match purged {
+                0 => RecoveryState::Clean,
+                purged_temp_sequences => RecoveryState::Recovered {
+                    purged_temp_sequences,
+                },
+            }
+        };
+        if !options.read_only {
+            // Temporary files are never reused, hence the ones left behind by a
+            // crash may always be purged.
+            temp_files.purge()?;
+        }
+        let format_version = pager
+            .read_with(PageId::FIRST, |page: &FirstPage| {
+                page.header.file_format_version
+            })
+            .await?;
+        let environment = DbEnvironment {
+            version: env!("CARGO_PKG_VERSION"),
+            page_size: options.page_size,
+            file_size: pager.file_size().await?,
+            format_version,
+            recovery,
+            wal_segments: None,
+            cache_capacity: options.cache_capacity,
+            decode_cache_capacity: options.decode_cache_capacity,
+            result_cache_capacity: options.result_cache_capacity,
+            read_only: options.read_only,
+            sync_mode: options.sync_mode,
+            flush_policy: options.flush_policy,
+            group_commit_delay: options.group_commit_delay,
+            features: COMPILED_FEATURES,
+        };
+        info!("opened database\n{environment}");
+
+        let pager = Arc::new(pager);
+        let statement_latch = Arc::new(RwLock::new(()));
+        let flusher = (!options.read_only)
+            .then(|| Flusher::spawn(Arc::clone(&pager), Arc::clone(&statement_latch)));
+        let db = Db {
+            pager,
+            statement_latch,
+            _flusher: flusher,
+            activity: ActivityTracker::default(),
+            statistics: StatisticsTracker::default(),
+            notifier: ChangeNotifier::default(),
+            decode_cache: (options.decode_cache_capacity > 0)
+                .then(|| DecodeCache::new(options.decode_cache_capacity)),
+            result_cache: (options.result_cache_capacity > 0)
+                .then(|| ResultCache::new(options.result_cache_capacity)),
+            locks: Arc::new(LockManager::with_timeout(options.lock_timeout)),
+            last_checkpoint: SyncMutex::new(None),
+            recovery,
+            environment,
+            read_only: options.read_only,
+            catalog_version: AtomicU64::new(0),
+            catalog_cache: CatalogCache::new(),
+            temp_files,
+        };
+        Ok((db, is_new))
     }
 
     /// Executes the given query, passing the callback closure for each yielded
     /// element.
+    ///
+    /// Queries other than the read-only ones (see [`Query::READ_ONLY`]) are
+    /// executed in isolation, under a statement-level latch, as write
+    /// transactions. Read-only queries read a snapshot of the database, taken
+    /// once the running write transaction (if any) commits, hence they never
+    /// observe a partially applied statement (e.g., an update that spans
+    /// multiple pages), nor the statements which commit while they run. Unlike
+    /// write transactions, they don't wait for each other, and write
+    /// transactions don't wait for them. See [`txn`](crate::io::txn).
+    ///
+    /// In read-only mode, all other queries fail with [`Error::ReadOnly`].
+    ///
+    /// Within an explicit transaction (see [`Db::begin`]), queries are executed
+    /// under its latch instead, and observe its uncommitted changes.
+    ///
+    /// With group commit enabled (see [`DbOptions::with_group_commit`]), the
+    /// pages written by other queries are synchronized once the latch is
+    /// released, along with the pages of concurrent queries.
+    ///
+    /// The table changes of other queries are published to the subscribers
+    /// (see [`Db::subscribe`]) once the query succeeds, even if the callback
+    /// stops it early. They are discarded if the query fails.
+    ///
+    /// # Deadlock
+    ///
+    /// The latch is held until a write query is exhausted, so its callback must
+    /// not execute other statements.
     pub async fn execute<Q, F, E>(&self, mut query: Q, mut f: F) -> DbResult<Result<(), E>>
+    where
+        Q: Query,
+        F: for<'a> FnMut(Q::Item<'a>) -> Result<(), E>,
+    {
+        if !Q::READ_ONLY && self.read_only {
+            return Err(Error::ReadOnly);
+        }
+
+        if transaction::is_active() {
+            return self.execute_in_transaction(&mut query, &mut f).await;
+        }
+
+        if Q::READ_ONLY {
+            // The latch is only held while the snapshot is taken, so that no
+            // write transaction is running.
+            let snapshot = {
+                let _read_guard = self.statement_latch.read().await;
+                self.pager.txns().snapshot()
+            };
+            let _foreground = self.pager.io_scheduler().foreground();
+            return txn::with_snapshot(snapshot, self.run(&mut query, &mut f)).await;
+        }
+
+        let result = {
+            let _write_guard = self.statement_latch.write().await;
+            let _txn = self.pager.txns().begin();
+
+            // Marked after acquiring the latch, since background tasks defer
+            // their I/O while holding it.
+            let _foreground = self.pager.io_scheduler().foreground();
+            let result = self.run(&mut query, &mut f).await;
+            // Still under the latch, so that only this query's changes are
+            // pending.
+            match result {
+                Ok(_) => self.notifier.publish(),
+                Err(_) => self.notifier.discard(),
+            }
+            result
+        };
+        // Outside of the latch, so that the syncs of concurrent statements may
+        // be batched. See `group_commit`.
+        self.pager.commit().await?;
+        result
+    }
+
+    /// Executes the given query within the running explicit transaction, which
+    /// holds the latch, under an implicit savepoint. See [`Transaction`].
+    async fn execute_in_transaction<Q, F, E>(
+        &self,
+        query: &mut Q,
+        f: &mut F,
+    ) -> DbResult<Result<(), E>>
+    where
+        Q: Query,
+        F: for<'a> FnMut(Q::Item<'a>) -> Result<(), E>,
+    {
+        let _foreground = self.pager.io_scheduler().foreground();
+        // Read-only queries see the transaction's changes, hence they need no
+        // snapshot (nor a savepoint).
+        if Q::READ_ONLY {
+            return self.run(query, f).await;
+        }
+        let depth = self.pager.savepoint().await?;
+        let changes = self.notifier.mark();
+        let result = {
+            let _txn = self.pager.txns().begin();
+            self.run(query, f).await
+        };
+        // The write transaction ended, so that the restored pages aren't
+        // journaled again.
+        match result {
+            Ok(_) => self.pager.release(depth),
+            Err(_) => {
+                self.pager.rollback_to(depth).await?;
+                self.notifier.discard_since(changes);
+                self.bump_catalog_version();
+            }
+        }
+        result
+    }
+
+    /// Begins an explicit transaction. See [`Transaction`].
+    ///
+    /// In read-only mode, fails with [`Error::ReadOnly`].
+    ///
+    /// # Deadlock
+    ///
+    /// The statement latch is held until the transaction ends, hence the other
+    /// statements (and methods such as [`Db::checkpoint`]) must not be awaited
+    /// by its owner meanwhile, except through the transaction.
+    pub async fn begin(&self) -> DbResult<Transaction<'_>> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        if transaction::is_active() {
+            return Err(Error::ExecError("a transaction is already running".into()));
+        }
+        let latch = Arc::clone(&self.statement_latch).write_owned().await;
+        Transaction::begin(self, latch).await
+    }
+
+    /// Exhausts the given query. See [`Db::execute`].
+    async fn run<Q, F, E>(&self, query: &mut Q, f: &mut F) -> DbResult<Result<(), E>>
     where
         Q: Query,
         F: for<'a> FnMut(Q::Item<'a>) -> Result<(), E>,
@@ -42,19 +402,726 @@ impl Db {
                 return Ok(error);
             }
         }
+        // Within an explicit transaction, the deltas are kept in memory, as the
+        // pages they would be persisted to may be rolled back.
+        if !Q::READ_ONLY && self.activity.should_persist() && !self.pager.txns().is_journaling() {
+            self.statistics.persist(self).await?;
+            self.activity.persist(&self.pager).await?;
+        }
         Ok(Ok(()))
     }
 
+    /// Same as [`Db::execute`], but also returns the metrics counters
+    /// increments (see [`MetricsSnapshot`]) during the query execution.
+    ///
+    /// The counters are shared by the whole database, hence the returned
+    /// increments include the work of the statements and background tasks that
+    /// ran concurrently (e.g., other read-only queries).
+    pub async fn execute_with_metrics<Q, F, E>(
+        &self,
+        query: Q,
+        f: F,
+    ) -> DbResult<(Result<(), E>, MetricsSnapshot)>
+    where
+        Q: Query,
+        F: for<'a> FnMut(Q::Item<'a>) -> Result<(), E>,
+    {
+        let before = self.pager.metrics().snapshot();
+        let result = self.execute(query, f).await?;
+        let metrics = self.pager.metrics().snapshot().since(&before);
+        Ok((result, metrics))
+    }
+
+    /// Records the currently hot pages so that they are prefetched the next
+    /// time the database is opened. See [`Pager::checkpoint`].
+    ///
+    /// The table activity and statistics deltas are also persisted (see
+    /// [`ActivityTracker`] and [`StatisticsTracker`]), and all dirty pages are
+    /// written, regardless of the [`FlushPolicy`].
+    pub async fn checkpoint(&self) -> DbResult<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        let _guard = self.statement_latch.write().await;
+        let _foreground = self.pager.io_scheduler().foreground();
+        self.statistics.persist(self).await?;
+        self.activity.persist(&self.pager).await?;
+        self.pager.checkpoint().await?;
+        *self.last_checkpoint.lock().unwrap() = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Closes the database, after the running statements finish. Like
+    /// [`Db::checkpoint`], the table activity and statistics deltas are
+    /// persisted and all dirty pages are written (regardless of the
+    /// [`FlushPolicy`]); the database file is then synchronized to the storage
+    /// device, regardless of the configured [`SyncMode`].
+    ///
+    /// Dropping the database instead loses the pages whose writes were
+    /// deferred by the flush policy. The file is released once the pages being
+    /// prefetched in the background (if any) are loaded, and once all clones
+    /// of [`Db::pager`] are dropped.
+    pub async fn close(self) -> DbResult<()> {
+        if !self.read_only {
+            self.checkpoint().await?;
+            self.pager.sync().await?;
+        }
+        info!("closed database");
+        Ok(())
+    }
+
+    /// Writes all pending pages and synchronizes the database file to the
+    /// storage device, regardless of the configured [`SyncMode`]. Once this
+    /// method returns, all previously executed statements are durable.
+    pub async fn sync(&self) -> DbResult<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        let _guard = self.statement_latch.write().await;
+        self.pager.sync().await
+    }
+
+    /// Writes a consistent copy of the database into a new file at the given
+    /// path, which must not exist yet, while the database remains open.
+    /// Returns the size of the copy, in bytes.
+    ///
+    /// Write statements are paused (at a statement boundary) while the pages
+    /// are copied, so the copy holds exactly the statements that finished
+    /// before it started. Read-only queries keep running. The copy may be
+    /// opened as any other database file; leftover temporary tables (if any)
+    /// are purged when it's opened.
+    pub async fn backup_to(&self, path: &Path) -> DbResult<u64> {
+        let _guard = self.statement_latch.write().await;
+        let _foreground = self.pager.io_scheduler().foreground();
+        if !self.read_only {
+            self.statistics.persist(self).await?;
+            self.activity.persist(&self.pager).await?;
+        }
+        let page_count = self.pager.backup_to(path).await?;
+        info!(?path, page_count, "backed up database");
+        Ok(page_count as u64 * self.page_size() as u64)
+    }
+
+    /// Sets the maximum size (in bytes) of the database file, which is rounded
+    /// down to a multiple of the page size. Statements that would grow the file
+    /// beyond it fail with [`Error::DatabaseFull`]. `None` removes the limit.
+    ///
+    /// The limit isn't persisted, hence it must be set each time the database
+    /// is opened.
+    ///
+    /// [`Error::DatabaseFull`]: crate::error::Error::DatabaseFull
+    pub fn set_max_size(&self, max_size: Option<u64>) {
+        let max_page_count = max_size
+            .map(|max_size| u32::try_from(max_size / self.page_size() as u64).unwrap_or(u32::MAX));
+        self.pager.set_max_page_count(max_page_count);
+    }
+
+    /// Returns the current database usage statistics.
+    pub async fn stats(&self) -> DbResult<DbStats> {
+        let AllocState {
+            page_count,
+            free_page_count,
+            ..
+        } = self.pager.alloc_state().await?;
+        let page_size = self.page_size();
+        Ok(DbStats {
+            page_size,
+            page_count,
+            free_page_count,
+            size: page_count as u64 * page_size as u64,
+            max_size: self
+                .pager
+                .max_page_count()
+                .map(|max| max as u64 * page_size as u64),
+            metrics: self.pager.metrics().snapshot(),
+        })
+    }
+
+    /// Returns a snapshot of the database health, suitable for exposing through
+    /// an application's health endpoint.
+    pub async fn health(&self) -> DbResult<DbHealth> {
+        let stats = self.stats().await?;
+        let (cache_hits, cache_misses) = self.pager.cache_stats();
+        Ok(DbHealth {
+            size: stats.size,
+            pending_writes: self.pager.pending_write_count(),
+            wal_backlog: None,
+            cache_hits,
+            cache_misses,
+            last_checkpoint_age: self
+                .last_checkpoint
+                .lock()
+                .unwrap()
+                .map(|instant| instant.elapsed()),
+            recovery: self.recovery,
+            group_commit: self.pager.group_commit_stats(),
+        })
+    }
+
+    /// Checks the structural integrity of the database, visiting all of its
+    /// pages. See [`integrity`] for the performed checks.
+    ///
+    /// Like [`Db::backup_to`], write statements are paused while the check
+    /// runs. Problems are returned in the report; errors are only returned if
+    /// the check couldn't run at all (e.g., if the first page is unreadable).
+    pub async fn check_integrity(&self) -> DbResult<IntegrityReport> {
+        let _guard = self.statement_latch.write().await;
+        let _foreground = self.pager.io_scheduler().foreground();
+        if !self.read_only {
+            self.pager.flush_all().await?;
+        }
+        integrity::check(self).await
+    }
+
+    /// Salvages the database into a new database file at the given path,
+    /// copying every decodable record of each table and rebuilding the indexes.
+    /// Unreadable or corrupted pages are skipped. See [`salvage`] for details.
+    ///
+    /// Like [`Db::check_integrity`], write statements are paused while the
+    /// salvage runs. Fails if the given path already exists.
+    pub async fn salvage_to(&self, path: &Path) -> DbResult<SalvageReport> {
+        let _guard = self.statement_latch.write().await;
+        let _foreground = self.pager.io_scheduler().foreground();
+        if !self.read_only {
+            self.pager.flush_all().await?;
+        }
+        salvage::salvage(self, path).await
+    }
+
+    /// Returns the environment facts gathered when the database was opened,
+    /// e.g., to be included in bug reports.
+    pub fn environment(&self) -> &DbEnvironment {
+        &self.environment
+    }
+
+    /// Checks whether the database is readable, by reading its first page.
+    ///
+    /// Unlike [`Db::health`], this doesn't wait for running statements.
+    pub async fn ping(&self) -> DbResult<()> {
+        self.pager
+            .read_with(PageId::FIRST, |_: &FirstPage| ())
+            .await
+    }
+
+    /// Returns the names and activity of the tables for which a vacuum is
+    /// recommended according to the given threshold.
+    pub async fn vacuum_candidates(
+        &self,
+        threshold: &VacuumThreshold,
+    ) -> DbResult<Vec<(String, TableActivity)>> {
+        let mut tables = activity::tables(self).await?;
+        tables.retain(|(_, activity)| activity.needs_vacuum(threshold));
+        Ok(tables)
+    }
+
+    /// Parses and executes the given SQL statement.
+    pub async fn execute_sql(&self, sql: &str) -> DbResult<SqlOutput> {
+        let statement = sql::parser::parse(sql)?;
+        sql::planner::execute(self, statement).await
+    }
+
+    /// Exports the database schema as a script of SQL `CREATE` statements. See
+    /// [`sql::script`].
+    pub async fn schema_script(&self) -> DbResult<String> {
+        sql::script::export(self).await
+    }
+
+    /// Applies the given schema script (see [`Db::schema_script`]) to this
+    /// database, which must have no objects. Returns the number of created
+    /// objects.
+    pub async fn load_schema_script(&self, script: &str) -> DbResult<usize> {
+        sql::script::load(self, script).await
+    }
+
+    /// Creates a new SQL session, which caches the statements it executes. See
+    /// [`Session`](sql::session::Session).
+    pub fn session(&self) -> sql::session::Session<'_> {
+        sql::session::Session::new(self)
+    }
+
+    /// Returns a handle to the given key-value namespace, which is created by
+    /// its first write. See [`kv`](crate::exec::kv).
+    pub fn kv(&self, namespace: impl Into<String>) -> Kv<'_> {
+        Kv::new(self, namespace)
+    }
+
     /// Returns a reference to the database pager.
     ///
     /// This method is not stable and in the future will be removed in favor of
     /// a SQL interface.
-    pub fn pager(&self) -> &Pager {
+    pub fn pager(&self) -> &Arc<Pager> {
         &self.pager
     }
 
+    /// Returns the table activity tracker.
+    pub fn activity(&self) -> &ActivityTracker {
+        &self.activity
+    }
+
+    /// Returns the statistics of the given table, or `None` if it has none
+    /// (i.e., it was created by an older version and never analyzed). See
+    /// [`statistics`](crate::exec::statistics).
+    pub async fn table_statistics(&self, table: &str) -> DbResult<Option<TableStatistics>> {
+        let table = Object::find(self, table).await?.try_into_table()?;
+        statistics::table_statistics(self, &table).await
+    }
+
+    /// Returns the table statistics tracker.
+    pub(crate) fn statistics(&self) -> &StatisticsTracker {
+        &self.statistics
+    }
+
+    /// Subscribes to the changes (inserts, updates and deletes) of the records
+    /// of the given table, which need not exist yet. See
+    /// [`notify`](crate::exec::notify).
+    pub fn subscribe(&self, table: &str) -> broadcast::Receiver<Change> {
+        self.notifier.subscribe(table)
+    }
+
+    /// Returns the table change notifier.
+    pub(crate) fn notifier(&self) -> &ChangeNotifier {
+        &self.notifier
+    }
+
+    /// Returns the decoded record cache, if it is enabled (see
+    /// [`DbOptions::with_decode_cache_capacity`]).
+    pub fn decode_cache(&self) -> Option<&DecodeCache> {
+        self.decode_cache.as_ref()
+    }
+
+    /// Returns the query result cache, if it is enabled (see
+    /// [`DbOptions::with_result_cache_capacity`]).
+    pub fn result_cache(&self) -> Option<&ResultCache> {
+        self.result_cache.as_ref()
+    }
+
+    /// Returns the temporary files manager, e.g., for operators which spill to
+    /// the disk.
+    pub fn temp_files(&self) -> &TempFiles {
+        &self.temp_files
+    }
+
+    /// Returns the record lock table. See [`locking`](crate::exec::locking).
+    pub fn locks(&self) -> &Arc<LockManager> {
+        &self.locks
+    }
+
+    /// Invalidates the cached results (if any) of the given table, which is
+    /// being written.
+    pub(crate) fn invalidate_results(&self, table: &str) {
+        if let Some(cache) = &self.result_cache {
+            cache.invalidate_table(table);
+        }
+    }
+
     /// Returns the database's page size.
     pub fn page_size(&self) -> u16 {
         self.pager.page_size()
     }
+
+    /// Checks whether the database was opened in read-only mode.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Returns the catalog version, which is incremented each time an object
+    /// (e.g., a table or an index) is created or dropped (or the catalog is
+    /// refreshed, see [`Db::refresh_catalog`]). It starts at zero when the
+    /// database is opened.
+    ///
+    /// Plans which resolved objects under a given version must not be used
+    /// once the version changes. See [`Prepared`](sql::planner::Prepared).
+    pub fn catalog_version(&self) -> u64 {
+        self.catalog_version.load(Ordering::Acquire)
+    }
+
+    /// Increments the catalog version. See [`Db::catalog_version`].
+    pub(crate) fn bump_catalog_version(&self) {
+        self.catalog_version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Returns the catalog object cache, which is used to look objects up by
+    /// their names. See [`catalog_cache`](crate::exec::catalog_cache).
+    pub fn catalog_cache(&self) -> &CatalogCache {
+        &self.catalog_cache
+    }
+
+    /// Invalidates the cached catalog objects and the prepared statements (by
+    /// bumping the catalog version), which must be done if the catalog was
+    /// changed outside of this instance.
+    pub fn refresh_catalog(&self) {
+        info!("refreshing catalog");
+        self.bump_catalog_version();
+    }
+}
+
+impl Drop for Db {
+    fn drop(&mut self) {
+        // The background flusher (if any) is aborted, hence these pages are
+        // lost.
+        let pending_writes = self.pager.pending_write_count();
+        if pending_writes > 0 {
+            warn!(
+                pending_writes,
+                "database dropped with unwritten pages; use `Db::close` to write them"
+            );
+        }
+    }
+}
+
+/// The options used to open a database. See [`Db::open_with_options`].
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn f() -> fdb::error::DbResult<()> {
+/// use fdb::{io::disk_manager::SyncMode, DbOptions};
+///
+/// let (db, _) = DbOptions::new()
+///     .with_page_size(8 * 1024)
+///     .with_sync_mode(SyncMode::Normal)
+///     .open("my-db".as_ref())
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbOptions {
+    /// The page size. Must match the page size of an existing database file.
+    pub page_size: u16,
+    /// The maximum number of pages in the page cache.
+    pub cache_capacity: u64,
+    /// Whether the database is opened in read-only mode, in which only
+    /// read-only queries (see [`Query::READ_ONLY`]) may be executed.
+    pub read_only: bool,
+    /// When the written pages are synchronized to the storage device.
+    pub sync_mode: SyncMode,
+    /// When the pages written by the statements are written to the disk. See
+    /// [`Pager::flush`].
+    pub flush_policy: FlushPolicy,
+    /// The maximum number of pages per second read or written by background
+    /// tasks. `None` means that there is no limit. See [`IoScheduler`].
+    ///
+    /// [`IoScheduler`]: crate::io::scheduler::IoScheduler
+    pub background_io_rate: Option<u32>,
+    /// The maximum number of records in the decoded record cache. Zero (the
+    /// default) disables the cache. See [`decode_cache`].
+    ///
+    /// [`decode_cache`]: crate::exec::decode_cache
+    pub decode_cache_capacity: u64,
+    /// The maximum number of results in the query result cache. Zero (the
+    /// default) disables the cache. See [`result_cache`].
+    ///
+    /// [`result_cache`]: crate::sql::result_cache
+    pub result_cache_capacity: u64,
+    /// The maximum time a committing statement waits for others to join its
+    /// sync, in [`SyncMode::Normal`]. `None` (the default) disables group
+    /// commit. See [`group_commit`].
+    ///
+    /// [`group_commit`]: crate::io::group_commit
+    pub group_commit_delay: Option<Duration>,
+    /// The maximum size of each segment file, if the pages are striped across
+    /// many files. `None` (the default) stores them in a single file. Must
+    /// match the segment size of an existing segmented database. See
+    /// [`SegmentLayout`].
+    pub max_segment_size: Option<u64>,
+    /// The directory of the temporary files (see [`TempFiles`]). `None` (the
+    /// default) means the directory of the database file or, for databases not
+    /// stored in a file, the system's temporary directory.
+    pub temp_dir: Option<PathBuf>,
+    /// The maximum time a record lock request waits before failing with
+    /// [`Error::Deadlock`]. See [`locking`](crate::exec::locking).
+    pub lock_timeout: Duration,
+}
+
+/// The minimum page size.
+pub const MIN_PAGE_SIZE: u16 = 128;
+
+impl DbOptions {
+    /// Returns the default options.
+    pub fn new() -> DbOptions {
+        DbOptions::default()
+    }
+
+    /// Sets the page size.
+    pub fn with_page_size(mut self, page_size: u16) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Sets the maximum number of pages in the page cache.
+    pub fn with_cache_capacity(mut self, cache_capacity: u64) -> Self {
+        self.cache_capacity = cache_capacity;
+        self
+    }
+
+    /// Sets the read-only mode.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Sets the sync mode.
+    pub fn with_sync_mode(mut self, sync_mode: SyncMode) -> Self {
+        self.sync_mode = sync_mode;
+        self
+    }
+
+    /// Sets the flush policy.
+    pub fn with_flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
+    /// Sets the maximum number of pages per second read or written by
+    /// background tasks.
+    pub fn with_background_io_rate(mut self, background_io_rate: Option<u32>) -> Self {
+        self.background_io_rate = background_io_rate;
+        self
+    }
+
+    /// Sets the maximum number of records in the decoded record cache, which is
+    /// disabled if zero.
+    pub fn with_decode_cache_capacity(mut self, decode_cache_capacity: u64) -> Self {
+        self.decode_cache_capacity = decode_cache_capacity;
+        self
+    }
+
+    /// Sets the maximum number of results in the query result cache, which is
+    /// disabled if zero.
+    pub fn with_result_cache_capacity(mut self, result_cache_capacity: u64) -> Self {
+        self.result_cache_capacity = result_cache_capacity;
+        self
+    }
+
+    /// Enables group commit, in which the syncs of concurrently committing
+    /// statements are batched, waiting for at most the given delay. Only takes
+    /// effect in [`SyncMode::Normal`].
+    pub fn with_group_commit(mut self, max_delay: Duration) -> Self {
+        self.group_commit_delay = Some(max_delay);
+        self
+    }
+
+    /// Stripes the pages across segment files of (at most) the given size.
+    pub fn with_max_segment_size(mut self, max_segment_size: u64) -> Self {
+        self.max_segment_size = Some(max_segment_size);
+        self
+    }
+
+    /// Sets the directory of the temporary files.
+    pub fn with_temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(temp_dir.into());
+        self
+    }
+
+    /// Sets the lock timeout.
+    pub fn with_lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.lock_timeout = lock_timeout;
+        self
+    }
+
+    /// Opens the database using these options. See [`Db::open_with_options`].
+    pub async fn open(self, path: &Path) -> DbResult<(Db, bool)> {
+        Db::open_with_options(path, self).await
+    }
+
+    /// Opens an in-memory database using these options. See
+    /// [`Db::open_in_memory_with_options`].
+    pub async fn open_in_memory(self) -> DbResult<Db> {
+        Db::open_in_memory_with_options(self).await
+    }
+
+    fn validate(&self) -> DbResult<()> {
+        if self.page_size < MIN_PAGE_SIZE {
+            return Err(Error::ExecError(format!(
+                "page size must be at least {MIN_PAGE_SIZE} bytes"
+            )));
+        }
+        if self.cache_capacity == 0 {
+            return Err(Error::ExecError("cache capacity must be positive".into()));
+        }
+        if self.background_io_rate == Some(0) {
+            return Err(Error::ExecError(
+                "background I/O rate must be positive".into(),
+            ));
+        }
+        if let Some(max_segment_size) = self.max_segment_size {
+            SegmentLayout::new(self.page_size, max_segment_size)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for DbOptions {
+    fn default() -> Self {
+        DbOptions {
+            page_size: 4 * 1024,
+            cache_capacity: DEFAULT_CACHE_CAPACITY,
+            read_only: false,
+            sync_mode: SyncMode::default(),
+            flush_policy: FlushPolicy::default(),
+            background_io_rate: None,
+            decode_cache_capacity: 0,
+            result_cache_capacity: 0,
+            group_commit_delay: None,
+            max_segment_size: None,
+            temp_dir: None,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+        }
+    }
+}
+
+/// Database usage statistics. See [`Db::stats`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DbStats {
+    /// The page size.
+    pub page_size: u16,
+    /// The number of pages in the database file.
+    pub page_count: u32,
+    /// The number of pages in the free list, which may be reused without
+    /// growing the file.
+    pub free_page_count: u32,
+    /// The size of the database file, in bytes.
+    pub size: u64,
+    /// The maximum size of the database file, in bytes, if any.
+    pub max_size: Option<u64>,
+    /// The execution counters since the database was opened.
+    pub metrics: MetricsSnapshot,
+}
+
+impl DbStats {
+    /// Returns the number of bytes that may still be used, counting the free
+    /// pages as available. `None` if there is no maximum size.
+    pub fn available(&self) -> Option<u64> {
+        let free = self.free_page_count as u64 * self.page_size as u64;
+        self.max_size
+            .map(|max_size| max_size.saturating_sub(self.size) + free)
+    }
+}
+
+/// A database health snapshot. See [`Db::health`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DbHealth {
+    /// The size of the database file, in bytes.
+    pub size: u64,
+    /// The number of page writes not yet written to the disk. See
+    /// [`Pager::pending_write_count`].
+    pub pending_writes: u32,
+    /// The number of log records not yet applied to the database file. Always
+    /// `None`, as `fdb` has no write-ahead log yet.
+    pub wal_backlog: Option<u64>,
+    /// The number of page loads served by the page cache since the database
+    /// was opened.
+    pub cache_hits: u64,
+    /// The number of page loads which had to read from the disk since the
+    /// database was opened.
+    pub cache_misses: u64,
+    /// The time elapsed since the last [`Db::checkpoint`]. `None` if no
+    /// checkpoint happened since the database was opened.
+    pub last_checkpoint_age: Option<Duration>,
+    /// The recovery performed when the database was opened.
+    pub recovery: RecoveryState,
+    /// The group commit statistics, whose batching factor is the average
+    /// number of commits per sync. See [`Pager::group_commit_stats`].
+    pub group_commit: GroupCommitStats,
+}
+
+impl DbHealth {
+    /// Returns the fraction of page loads served by the page cache. `None` if
+    /// no page was loaded.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let total = self.cache_hits + self.cache_misses;
+        (total > 0).then(|| self.cache_hits as f64 / total as f64)
+    }
+}
+
+/// The features `fdb` was compiled with. See [`DbEnvironment`].
+pub const COMPILED_FEATURES: &[&str] = &[
+    #[cfg(debug_assertions)]
+    "debug-assertions",
+];
+
+/// The environment facts which affect the correctness and the performance of a
+/// database, gathered when it was opened. See [`Db::environment`].
+///
+/// It is displayed as a plain-text report, one fact per line.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DbEnvironment {
+    /// The `fdb` version.
+    pub version: &'static str,
+    /// The page size.
+    pub page_size: u16,
+    /// The size of the database file when it was opened, in bytes.
+    pub file_size: u64,
+    /// The file format version recorded in the database header.
+    pub format_version: u8,
+    /// The recovery performed when the database was opened.
+    pub recovery: RecoveryState,
+    /// The number of write-ahead log segments found. Always `None`, as `fdb`
+    /// has no write-ahead log yet.
+    pub wal_segments: Option<u32>,
+    /// The maximum number of pages in the page cache.
+    pub cache_capacity: u64,
+    /// The maximum number of records in the decoded record cache.
+    pub decode_cache_capacity: u64,
+    /// The maximum number of results in the query result cache.
+    pub result_cache_capacity: u64,
+    /// Whether the database was opened in read-only mode.
+    pub read_only: bool,
+    /// The sync mode.
+    pub sync_mode: SyncMode,
+    /// The flush policy.
+    pub flush_policy: FlushPolicy,
+    /// The group commit delay, if enabled. See
+    /// [`DbOptions::with_group_commit`].
+    pub group_commit_delay: Option<Duration>,
+    /// The compile-time features. See [`COMPILED_FEATURES`].
+    pub features: &'static [&'static str],
+}
+
+impl DbEnvironment {
+    /// Checks whether the database was cleanly shut down the last time it was
+    /// closed. Always `true` for a newly created database.
+    pub fn clean_shutdown(&self) -> bool {
+        !matches!(self.recovery, RecoveryState::Recovered { .. })
+    }
+}
+
+impl fmt::Display for DbEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "fdb version: {}", self.version)?;
+        writeln!(f, "page size: {}", self.page_size)?;
+        writeln!(f, "file size: {}", self.file_size)?;
+        writeln!(f, "format version: {}", self.format_version)?;
+        writeln!(f, "recovery: {:?}", self.recovery)?;
+        match self.wal_segments {
+            Some(wal_segments) => writeln!(f, "wal segments: {wal_segments}")?,
+            None => writeln!(f, "wal segments: none")?,
+        }
+        writeln!(f, "cache capacity: {}", self.cache_capacity)?;
+        writeln!(f, "decode cache capacity: {}", self.decode_cache_capacity)?;
+        writeln!(f, "result cache capacity: {}", self.result_cache_capacity)?;
+        writeln!(f, "read only: {}", self.read_only)?;
+        writeln!(f, "sync mode: {:?}", self.sync_mode)?;
+        writeln!(f, "flush policy: {:?}", self.flush_policy)?;
+        match self.group_commit_delay {
+            Some(delay) => writeln!(f, "group commit delay: {delay:?}")?,
+            None => writeln!(f, "group commit delay: disabled")?,
+        }
+        write!(f, "features: {}", self.features.join(", "))
+    }
+}
+
+/// The recovery performed when the database was opened. See [`DbHealth`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RecoveryState {
+    /// The database file was created (and bootstrapped).
+    Created,
+    /// The database file was cleanly closed; nothing was recovered.
+    Clean,
+    /// Leftovers from an unclean shutdown were cleaned up.
+    Recovered {
+        /// The number of purged temporary sequences. See [`crate::io::temp`].
+        purged_temp_sequences: usize,
+    },
 }
@@ -1,4 +1,4 @@
-use std::{io, sync::Arc};
+use std::{fmt, io, sync::Arc};
 
 use crate::catalog::page::PageId;
 
@@ -14,10 +14,32 @@ pub enum Error {
     #[error("incomplete page ({0:?})")]
     ReadIncompletePage(PageId),
 
+    /// The page's checksum didn't match its contents (see
+    /// [`crate::util::checksum`]).
+    #[error("page checksum mismatch ({0:?})")]
+    ChecksumMismatch(PageId),
+
     /// Corrupted header.
     #[error("corrupted header: {0}")]
     CorruptedHeader(&'static str),
 
+    /// Corrupted page, found by a tolerant read (e.g., while salvaging a
+    /// damaged database).
+    #[error("corrupted page: {0}")]
+    CorruptedPage(&'static str),
+
+    /// Corrupted record, found by a tolerant read (see
+    /// [`SimpleRecord::deserialize_tolerant`]).
+    ///
+    /// [`SimpleRecord::deserialize_tolerant`]: crate::catalog::record::simple_record::SimpleRecord::deserialize_tolerant
+    #[error("corrupted record: {0}")]
+    CorruptedRecord(&'static str),
+
+    /// The database file is valid, but can't be opened by this build (e.g., it
+    /// was written in an unsupported file format version).
+    #[error("incompatible database file: {0}")]
+    IncompatibleFile(&'static str),
+
     /// Invalid object type tag.
     #[error("corrupted object type tag")]
     CorruptedObjectTypeTag,
@@ -30,17 +52,168 @@ pub enum Error {
     #[error("utf-8 error while decoding string")]
     CorruptedUtf8,
 
+    /// Invalid packed integer sequence (see [`crate::util::packing`]).
+    #[error("corrupted packed integers")]
+    CorruptedPacking,
+
+    /// Invalid variable-length integer (see [`buff::varint_size`]).
+    #[error("corrupted varint")]
+    CorruptedVarint,
+
+    /// Invalid prefix-compressed index key (see
+    /// [`crate::catalog::page::BTreePage`]).
+    #[error("corrupted index key prefix")]
+    CorruptedKeyPrefix,
+
+    /// A text or blob value exceeded its column's maximum length (see
+    /// [`Column::max_len`]).
+    ///
+    /// [`Column::max_len`]: crate::catalog::column::Column::max_len
+    #[error("value of column `{column}` is too long ({len} bytes, maximum is {max_len})")]
+    ValueTooLong {
+        column: String,
+        len: usize,
+        max_len: u16,
+    },
+
+    /// An insert omitted a column declared as `NOT NULL` which has no default
+    /// value (see [`Column::not_null`]).
+    ///
+    /// [`Column::not_null`]: crate::catalog::column::Column::not_null
+    #[error("missing value for column `{0}`, which is declared as NOT NULL")]
+    MissingValue(String),
+
+    /// A value (e.g., a page) didn't fit the buffer it was serialized into.
+    #[error("serialization overflow: {0}")]
+    BufferOverflow(#[from] buff::OverflowError),
+
     /// Casting error.
     #[error("cast error: {0}")]
     Cast(String),
 
+    /// SQL syntax error.
+    #[error("syntax error: {0}")]
+    Syntax(String),
+
     /// Generic error.
     #[error("execution error: {0}")]
     ExecError(String),
 
+    /// The database reached its maximum size (see [`Db::set_max_size`]).
+    ///
+    /// [`Db::set_max_size`]: crate::Db::set_max_size
+    #[error("database is full (maximum size is {max_size} bytes)")]
+    DatabaseFull { max_size: u64 },
+
+    /// The database file's page size differs from the requested one (see
+    /// [`DbOptions::page_size`]).
+    ///
+    /// [`DbOptions::page_size`]: crate::DbOptions::page_size
+    #[error("file page size is {actual}; expected {expected}")]
+    PageSizeMismatch { expected: u16, actual: u16 },
+
+    /// The database was opened in read-only mode (see
+    /// [`DbOptions::read_only`]), but the operation would write to it.
+    ///
+    /// [`DbOptions::read_only`]: crate::DbOptions::read_only
+    #[error("database is read-only")]
+    ReadOnly,
+
+    /// Waiting for a record lock would deadlock (or timed out), hence the
+    /// statement was aborted. See [`locking`](crate::exec::locking).
+    #[error("deadlock detected while waiting for a record lock")]
+    Deadlock,
+
     /// An generic IO error.
     #[error("io error: {0}")]
     Io(Arc<io::Error>),
+
+    /// An error wrapped with information about where it occurred. See
+    /// [`ResultExt`].
+    #[error("{context}: {source}")]
+    Context {
+        context: ErrorContext,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Returns the underlying error, i.e., without any context.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// Returns the contexts of the error, from the outermost to the innermost.
+    pub fn contexts(&self) -> impl Iterator<Item = &ErrorContext> {
+        let mut current = self;
+        std::iter::from_fn(move || match current {
+            Error::Context { context, source } => {
+                current = source;
+                Some(context)
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Where an error occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorContext {
+    /// The page with the given ID.
+    Page(PageId),
+    /// The record at the given page and offset.
+    Record { page_id: PageId, offset: u16 },
+    /// The database object (e.g., a table) with the given name.
+    Object(String),
+    /// The given operation.
+    Operation(&'static str),
+    /// The line (starting at 1) of the given external file.
+    Line { path: String, line: u64 },
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorContext::Page(page_id) => write!(f, "page {}", page_id.get()),
+            ErrorContext::Record { page_id, offset } => {
+                write!(f, "record at page {}, offset {offset}", page_id.get())
+            }
+            ErrorContext::Object(name) => write!(f, "object `{name}`"),
+            ErrorContext::Operation(operation) => write!(f, "{operation}"),
+            ErrorContext::Line { path, line } => write!(f, "{path}, line {line}"),
+        }
+    }
+}
+
+/// Extension trait to attach an [`ErrorContext`] to errors.
+pub trait ResultExt<T> {
+    /// Wraps the error (if any) with the given context.
+    fn context(self, context: ErrorContext) -> DbResult<T>;
+
+    /// Wraps the error (if any) with the context returned by the given
+    /// closure, which is only called in the error case.
+    fn with_context<F>(self, f: F) -> DbResult<T>
+    where
+        F: FnOnce() -> ErrorContext;
+}
+
+impl<T> ResultExt<T> for DbResult<T> {
+    fn context(self, context: ErrorContext) -> DbResult<T> {
+        self.with_context(|| context)
+    }
+
+    fn with_context<F>(self, f: F) -> DbResult<T>
+    where
+        F: FnOnce() -> ErrorContext,
+    {
+        self.map_err(|source| Error::Context {
+            context: f(),
+            source: Box::new(source),
+        })
+    }
 }
 
 impl From<io::Error> for Error {
@@ -0,0 +1,272 @@
+//! Table activity tracking.
+//!
+//! Table queries record their activity (inserts, updates, deletes and dead
+//! records) in the database's [`ActivityTracker`]. The counters are kept in
+//! memory as deltas, which are periodically added to the counters persisted in
+//! each table's sequence header (see [`ActivityCounters`]). Hence, a crash may
+//! lose the most recent deltas, but never corrupts the counters.
+//!
+//! The dead record count is used to recommend vacuums for heavily churned
+//! tables (see [`VacuumThreshold`]), which may be performed in the background
+//! (see [`auto_vacuum`](crate::exec::auto_vacuum)).
+//!
+//! The tracker also records the lookups served by each index (see
+//! [`IndexUsage`]), so that unused indexes may be found. Such usage is only
+//! kept in memory, hence it only accounts for the lookups since the database
+//! was opened.
+
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        object::{IndexObject, Object, ObjectType, TableObject},
+        page::{ActivityCounters, HeapPage, PageId},
+    },
+    error::DbResult,
+    exec::{operations::index::BTree, query, util::macros::seq_h},
+    io::pager::Pager,
+    Db,
+};
+
+/// The number of recorded changes after which the deltas are persisted.
+pub const PERSIST_INTERVAL: u64 = 1024;
+
+/// The in-memory activity deltas of the database tables, keyed by the ID of
+/// their first page.
+#[derive(Debug, Default)]
+pub struct ActivityTracker {
+    deltas: Mutex<HashMap<PageId, ActivityCounters>>,
+    pending: AtomicU64,
+    index_usage: Mutex<HashMap<PageId, IndexUsage>>,
+}
+
+impl ActivityTracker {
+    /// Records activity over the table whose sequence starts at the given page.
+    pub(crate) fn record(&self, page_id: PageId, f: impl FnOnce(&mut ActivityCounters)) {
+        let mut deltas = self.deltas.lock().unwrap();
+        f(deltas.entry(page_id).or_default());
+        self.pending.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Discards the deltas of the table whose sequence starts at the given
+    /// page. Must be called before the sequence is released.
+    pub(crate) fn forget(&self, page_id: PageId) {
+        self.deltas.lock().unwrap().remove(&page_id);
+    }
+
+    /// Records a lookup served by the index whose root is at the given page.
+    pub(crate) fn record_index_lookup(&self, root: PageId) {
+        let mut usage = self.index_usage.lock().unwrap();
+        let usage = usage.entry(root).or_default();
+        usage.lookups += 1;
+        usage.last_used = Some(SystemTime::now());
+    }
+
+    /// Discards the usage of the index whose root is at the given page. Must
+    /// be called before the index is released.
+    pub(crate) fn forget_index(&self, root: PageId) {
+        self.index_usage.lock().unwrap().remove(&root);
+    }
+
+    /// Returns the usage of the index whose root is at the given page.
+    pub fn index_usage(&self, root: PageId) -> IndexUsage {
+        let usage = self.index_usage.lock().unwrap();
+        usage.get(&root).copied().unwrap_or_default()
+    }
+
+    /// Checks whether enough changes were recorded since the last persistence.
+    pub(crate) fn should_persist(&self) -> bool {
+        self.pending.load(Ordering::Relaxed) >= PERSIST_INTERVAL
+    }
+
+    /// Adds the in-memory deltas to the persisted counters.
+    ///
+    /// # Deadlock
+    ///
+    /// This method acquires a write latch to the first page of each table with
+    /// pending deltas.
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn persist(&self, pager: &Pager) -> DbResult<()> {
+        let deltas = mem::take(&mut *self.deltas.lock().unwrap());
+        self.pending.store(0, Ordering::Relaxed);
+        debug!(tables = deltas.len(), "persisting activity deltas");
+        for (page_id, delta) in deltas {
+            let guard = pager.get::<HeapPage>(page_id).await?;
+            let mut page = guard.write().await;
+            seq_h!(mut page).activity.add(&delta);
+            page.flush();
+        }
+        pager.flush().await
+    }
+
+    /// Returns the activity of the given table.
+    pub async fn table_activity(
+        &self,
+        pager: &Pager,
+        table: &TableObject,
+    ) -> DbResult<TableActivity> {
+        let (record_count, mut counters) = pager
+            .read_with(table.page_id, |page: &HeapPage| {
+                let seq_header = seq_h!(page);
+                (seq_header.record_count, seq_header.activity)
+            })
+            .await?;
+        if let Some(delta) = self.deltas.lock().unwrap().get(&table.page_id) {
+            counters.add(delta);
+        }
+        Ok(TableActivity {
+            inserts: counters.inserts,
+            updates: counters.updates,
+            deletes: counters.deletes,
+            live_rows: record_count.saturating_sub(counters.dead_rows),
+            dead_rows: counters.dead_rows,
+        })
+    }
+}
+
+/// The activity of a table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TableActivity {
+    /// The number of inserted records.
+    pub inserts: u64,
+    /// The number of updated records.
+    pub updates: u64,
+    /// The number of deleted records.
+    pub deletes: u64,
+    /// The number of live records.
+    pub live_rows: u64,
+    /// The number of dead records since the last vacuum.
+    pub dead_rows: u64,
+}
+
+impl TableActivity {
+    /// Returns the ratio of dead records among all stored records.
+    pub fn dead_ratio(&self) -> f64 {
+        let total = self.live_rows + self.dead_rows;
+        if total == 0 {
+            0.0
+        } else {
+            self.dead_rows as f64 / total as f64
+        }
+    }
+
+    /// Checks whether a vacuum is recommended according to the given threshold.
+    pub fn needs_vacuum(&self, threshold: &VacuumThreshold) -> bool {
+        self.dead_rows > threshold.min_dead_rows
+            && self.dead_rows as f64 > threshold.scale_factor * self.live_rows as f64
+    }
+}
+
+/// The usage of an index since the database was opened.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexUsage {
+    /// The number of lookups (i.e., index scans) served by the index.
+    pub lookups: u64,
+    /// When the index last served a lookup.
+    pub last_used: Option<SystemTime>,
+}
+
+/// The statistics of an index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexStats {
+    /// The index name.
+    pub name: String,
+    /// The name of the indexed table.
+    pub table: String,
+    /// The name of the indexed column.
+    pub column: String,
+    /// The number of levels of the index tree.
+    pub height: u32,
+    /// The number of pages of the index tree.
+    pub page_count: u32,
+    /// The number of indexed records.
+    pub entries: u64,
+    /// The index usage.
+    pub usage: IndexUsage,
+}
+
+/// The threshold above which a table should be vacuumed: a table needs a
+/// vacuum once it has more than `min_dead_rows` dead records, and more than
+/// `scale_factor` dead records per live record.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VacuumThreshold {
+    pub min_dead_rows: u64,
+    pub scale_factor: f64,
+}
+
+impl Default for VacuumThreshold {
+    fn default() -> Self {
+        VacuumThreshold {
+            min_dead_rows: 50,
+            scale_factor: 0.2,
+        }
+    }
+}
+
+/// Returns the activity of all tables in the database schema, in the schema
+/// order.
+pub async fn tables(db: &Db) -> DbResult<Vec<(String, TableActivity)>> {
+    let mut tables = Vec::new();
+    db.execute(query::object::Select::new(), |object: Object| {
+        if let ObjectType::Table(schema) = object.ty {
+            tables.push(TableObject {
+                schema,
+                page_id: object.page_id,
+                name: object.name,
+            });
+        }
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+
+    let mut activities = Vec::with_capacity(tables.len());
+    for table in tables {
+        let activity = db.activity().table_activity(db.pager(), &table).await?;
+        activities.push((table.name, activity));
+    }
+    Ok(activities)
+}
+
+/// Returns the statistics of all indexes in the database schema, in the schema
+/// order. All index pages are visited.
+pub async fn indexes(db: &Db) -> DbResult<Vec<IndexStats>> {
+    let mut indexes = Vec::new();
+    db.execute(query::object::Select::new(), |object: Object| {
+        if let ObjectType::Index(schema) = object.ty {
+            indexes.push(IndexObject {
+                schema,
+                page_id: object.page_id,
+                name: object.name,
+            });
+        }
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+
+    let mut stats = Vec::with_capacity(indexes.len());
+    for index in indexes {
+        let tree_stats = BTree::new(index.page_id).stats(db.pager()).await?;
+        stats.push(IndexStats {
+            name: index.name,
+            table: index.schema.table,
+            column: index.schema.column,
+            height: tree_stats.height,
+            page_count: tree_stats.page_count,
+            entries: tree_stats.entries,
+            usage: db.activity().index_usage(index.page_id),
+        });
+    }
+    Ok(stats)
+}
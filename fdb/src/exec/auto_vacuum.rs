@@ -0,0 +1,147 @@
+//! Auto-vacuum background job.
+//!
+//! The job periodically looks for tables whose dead records exceed the
+//! configured [`VacuumThreshold`] and vacuums them incrementally: at most
+//! `page_budget` pages are vacuumed per round, so that foreground statements
+//! aren't blocked for long (each round holds the statement latch, as any
+//! other write statement). As with any vacuum, the
+//! [`RecordId`](crate::exec::query::table::RecordId)s of the vacuumed pages
+//! become stale at any time.
+//!
+//! The job runs as a background task, hence its page I/O is throttled by the
+//! [`IoScheduler`](crate::io::scheduler::IoScheduler).
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::Notify, task::JoinHandle};
+use tracing::{debug, error, instrument};
+
+use crate::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{
+        activity::VacuumThreshold,
+        query::table::{Vacuum, VacuumStats},
+    },
+    io::scheduler::background,
+    Db,
+};
+
+/// The auto-vacuum configuration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AutoVacuumConfig {
+    /// The interval between two vacuum rounds.
+    pub interval: Duration,
+    /// The maximum number of pages vacuumed per round, i.e., the I/O budget.
+    pub page_budget: u32,
+    /// The threshold above which a table is vacuumed.
+    pub threshold: VacuumThreshold,
+}
+
+impl Default for AutoVacuumConfig {
+    fn default() -> Self {
+        AutoVacuumConfig {
+            interval: Duration::from_secs(1),
+            page_budget: 16,
+            threshold: VacuumThreshold::default(),
+        }
+    }
+}
+
+/// A handle to a running auto-vacuum job. The job is aborted if the handle is
+/// dropped; [`AutoVacuum::stop`] waits for the current round to finish.
+pub struct AutoVacuum {
+    stop: Arc<Notify>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl AutoVacuum {
+    /// Spawns a new auto-vacuum job over the given database. Must be called
+    /// within a Tokio runtime.
+    pub fn spawn(db: Arc<Db>, config: AutoVacuumConfig) -> AutoVacuum {
+        let stop = Arc::new(Notify::new());
+        let handle = tokio::spawn(background(run(db, config, Arc::clone(&stop))));
+        AutoVacuum {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stops the job, waiting for the current round (if any) to finish.
+    pub async fn stop(mut self) {
+        self.stop.notify_one();
+        if let Some(handle) = self.handle.take() {
+            // The job never panics, as errors are only logged.
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Drop for AutoVacuum {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.handle {
+            handle.abort();
+        }
+    }
+}
+
+/// The position of an unfinished incremental vacuum.
+struct Cursor {
+    table: String,
+    position: u32,
+}
+
+async fn run(db: Arc<Db>, config: AutoVacuumConfig, stop: Arc<Notify>) {
+    let mut cursor = None;
+    loop {
+        tokio::select! {
+            _ = stop.notified() => break,
+            _ = tokio::time::sleep(config.interval) => {}
+        }
+        if let Err(error) = round(&db, &config, &mut cursor).await {
+            error!(?error, "auto-vacuum round failed");
+            cursor = None;
+        }
+    }
+    debug!("auto-vacuum stopped");
+}
+
+/// Performs a vacuum round, resuming the unfinished vacuum (if any) or starting
+/// a new one over the first table that exceeds the threshold.
+#[instrument(name = "AutoVacuumRound", level = "debug", skip_all)]
+async fn round(db: &Db, config: &AutoVacuumConfig, cursor: &mut Option<Cursor>) -> DbResult<()> {
+    let Cursor { table, position } = match cursor.take() {
+        Some(cursor) => cursor,
+        None => {
+            let candidates = db.vacuum_candidates(&config.threshold).await?;
+            let Some((table, activity)) = candidates.into_iter().next() else {
+                return Ok(());
+            };
+            debug!(table, ?activity, "starting vacuum");
+            Cursor { table, position: 0 }
+        }
+    };
+    // The table may have been dropped since the last round.
+    let table = match Object::find(db, &table).await {
+        Ok(object) => object.try_into_table()?,
+        Err(_) => return Ok(()),
+    };
+
+    let vacuum = Vacuum::new(&table)
+        .with_start(position)
+        .with_page_budget(config.page_budget);
+    let mut stats = VacuumStats::default();
+    db.execute(vacuum, |round_stats| {
+        stats = round_stats;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    debug!(table = table.name, ?stats, "vacuumed pages");
+
+    *cursor = stats.resume_at.map(|position| Cursor {
+        table: table.name,
+        position,
+    });
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Catalog cache.
+//!
+//! Looking an object up by its name (see [`Object::find`]) scans the schema
+//! heap sequence, which would otherwise be done by every statement. Hence, the
+//! objects found are kept in the database's [`CatalogCache`], keyed by their
+//! names.
+//!
+//! The cached objects are tagged with the catalog version (see
+//! [`Db::catalog_version`]) under which they were read. Creating or dropping an
+//! object bumps the version, hence invalidating the whole cache; so does
+//! [`Db::refresh_catalog`], which must be called if the catalog is changed
+//! outside of this instance.
+//!
+//! [`Db::catalog_version`]: crate::Db::catalog_version
+//! [`Db::refresh_catalog`]: crate::Db::refresh_catalog
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex as SyncMutex,
+    },
+};
+
+use tracing::trace;
+
+use crate::catalog::object::Object;
+
+/// A cache of catalog objects, keyed by their names.
+#[derive(Default)]
+pub struct CatalogCache {
+    /// The catalog version of the cached objects, along with the objects.
+    entries: SyncMutex<(u64, HashMap<String, Object>)>,
+    /// The number of lookups served by the cache.
+    hits: AtomicU64,
+    /// The number of lookups which missed the cache, including the ones whose
+    /// object was stale.
+    misses: AtomicU64,
+}
+
+impl CatalogCache {
+    /// Constructs a new, empty, cache.
+    pub fn new() -> CatalogCache {
+        CatalogCache::default()
+    }
+
+    /// Returns the cached object with the given name, if it was read under the
+    /// given catalog version. Objects of other versions are dropped.
+    pub(crate) fn get(&self, name: &str, catalog_version: u64) -> Option<Object> {
+        let mut entries = self.entries.lock().unwrap();
+        let (version, objects) = &mut *entries;
+        if *version != catalog_version {
+            trace!(version, catalog_version, "dropping stale catalog objects");
+            *version = catalog_version;
+            objects.clear();
+        }
+        let cached = objects.get(name).cloned();
+        let counter = match cached {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    /// Caches the given object, which was read under the given catalog
+    /// version. Ignored if the version is no longer current.
+    pub(crate) fn insert(&self, catalog_version: u64, object: &Object) {
+        let mut entries = self.entries.lock().unwrap();
+        let (version, objects) = &mut *entries;
+        if *version == catalog_version {
+            objects.insert(object.name.clone(), object.clone());
+        }
+    }
+
+    /// Returns the number of cached objects.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().1.len()
+    }
+
+    /// Checks whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of lookups served by the cache and the number of
+    /// lookups which had to scan the catalog, respectively, since the database
+    /// was opened.
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
@@ -0,0 +1,92 @@
+//! Decoded record cache.
+//!
+//! The records read through their IDs (e.g., by [`GetById`] or by index scans)
+//! may be kept decoded in the database's [`DecodeCache`], so that repeatedly
+//! reading the same few records (e.g., of small lookup tables) doesn't
+//! deserialize the same bytes over and over.
+//!
+//! Each cached record is tagged with the version of its page (see
+//! [`Pager::page_version`]) at the time it was read. Any write to the page
+//! bumps its version, hence invalidating all of its cached records. Spanned
+//! records (see [`span`]) are never cached.
+//!
+//! The cache is disabled by default. See
+//! [`DbOptions::with_decode_cache_capacity`].
+//!
+//! [`GetById`]: crate::exec::query::table::GetById
+//! [`Pager::page_version`]: crate::io::pager::Pager::page_version
+//! [`span`]: crate::exec::operations::heap::span
+//! [`DbOptions::with_decode_cache_capacity`]: crate::DbOptions::with_decode_cache_capacity
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use moka::sync::Cache;
+use tracing::trace;
+
+use crate::{
+    catalog::record::simple_record::SimpleRecord,
+    exec::{query::table::RecordId, values::SchematizedValues},
+};
+
+type Record = SimpleRecord<'static, SchematizedValues>;
+
+/// A bounded cache of decoded records, keyed by their IDs.
+pub struct DecodeCache {
+    inner: Cache<RecordId, (u64, Record)>,
+    /// The number of lookups served by the cache.
+    hits: AtomicU64,
+    /// The number of lookups which missed the cache, including the ones whose
+    /// record was stale.
+    misses: AtomicU64,
+}
+
+impl DecodeCache {
+    /// Constructs a new cache, which holds at most the given number of
+    /// records.
+    pub fn new(capacity: u64) -> DecodeCache {
+        DecodeCache {
+            inner: Cache::new(capacity),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the cached record with the given ID, if it was read while its
+    /// page was at the given version.
+    pub(crate) fn get(&self, id: RecordId, page_version: u64) -> Option<Record> {
+        let cached = match self.inner.get(&id) {
+            Some((version, record)) if version == page_version => Some(record),
+            Some(_) => {
+                trace!(%id, "stale decoded record");
+                self.inner.invalidate(&id);
+                None
+            }
+            None => None,
+        };
+        let counter = match cached {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    /// Caches the given record, which was read while its page was at the given
+    /// version. Spanned records are ignored.
+    pub(crate) fn insert(&self, id: RecordId, page_version: u64, record: &Record) {
+        if record.is_spanned() {
+            return;
+        }
+        self.inner.insert(id, (page_version, record.clone()));
+    }
+
+    /// Returns the number of lookups served by the cache and the number of
+    /// lookups which had to decode the record, respectively, since the database
+    /// was opened.
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
@@ -0,0 +1,646 @@
+//! Row expressions.
+//!
+//! Unlike opaque Rust closures, expressions may be inspected by the executors,
+//! e.g., to look the matching records up through an index (see
+//! [`Expr::key_range`]) instead of scanning the whole table.
+
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fmt,
+    ops::{self, Bound},
+};
+
+use crate::{
+    catalog::{
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{
+        value::{Decimal, Value},
+        values::Values,
+    },
+};
+
+/// An expression over the values of a row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Column(String),
+    Literal(Value),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Cmp(Box<Expr>, CmpOp, Box<Expr>),
+    /// An array element access. Indices start at 1, and out of bounds
+    /// accesses yield no value (thus, never match).
+    Index(Box<Expr>, Box<Expr>),
+    /// A composite field access, e.g., `address.city`.
+    Field(Box<Expr>, String),
+}
+
+/// A comparison operator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Neq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl CmpOp {
+    /// Returns the operator with swapped operands, e.g., `a < b` is `b > a`.
+    pub fn flip(self) -> CmpOp {
+        match self {
+            CmpOp::Lt => CmpOp::Gt,
+            CmpOp::Lte => CmpOp::Gte,
+            CmpOp::Gt => CmpOp::Lt,
+            CmpOp::Gte => CmpOp::Lte,
+            op => op,
+        }
+    }
+
+    /// Checks whether the given ordering satisfies the operator.
+    pub fn test(self, ord: Ordering) -> bool {
+        match self {
+            CmpOp::Eq => ord == Ordering::Equal,
+            CmpOp::Neq => ord != Ordering::Equal,
+            CmpOp::Lt => ord == Ordering::Less,
+            CmpOp::Lte => ord != Ordering::Greater,
+            CmpOp::Gt => ord == Ordering::Greater,
+            CmpOp::Gte => ord != Ordering::Less,
+        }
+    }
+}
+
+/// Constructs a column reference.
+pub fn col(name: impl Into<String>) -> Expr {
+    Expr::Column(name.into())
+}
+
+/// Constructs a literal.
+pub fn lit(value: Value) -> Expr {
+    Expr::Literal(value)
+}
+
+impl Expr {
+    pub fn eq(self, rhs: Expr) -> Expr {
+        self.cmp(CmpOp::Eq, rhs)
+    }
+
+    pub fn neq(self, rhs: Expr) -> Expr {
+        self.cmp(CmpOp::Neq, rhs)
+    }
+
+    pub fn lt(self, rhs: Expr) -> Expr {
+        self.cmp(CmpOp::Lt, rhs)
+    }
+
+    pub fn lte(self, rhs: Expr) -> Expr {
+        self.cmp(CmpOp::Lte, rhs)
+    }
+
+    pub fn gt(self, rhs: Expr) -> Expr {
+        self.cmp(CmpOp::Gt, rhs)
+    }
+
+    pub fn gte(self, rhs: Expr) -> Expr {
+        self.cmp(CmpOp::Gte, rhs)
+    }
+
+    pub fn and(self, rhs: Expr) -> Expr {
+        Expr::And(Box::new(self), Box::new(rhs))
+    }
+
+    pub fn or(self, rhs: Expr) -> Expr {
+        Expr::Or(Box::new(self), Box::new(rhs))
+    }
+
+    /// Accesses the array element at the given (1-based) index.
+    pub fn index(self, index: Expr) -> Expr {
+        Expr::Index(Box::new(self), Box::new(index))
+    }
+
+    /// Accesses the given field of a composite value.
+    pub fn field(self, name: impl Into<String>) -> Expr {
+        Expr::Field(Box::new(self), name.into())
+    }
+
+    fn cmp(self, op: CmpOp, rhs: Expr) -> Expr {
+        Expr::Cmp(Box::new(self), op, Box::new(rhs))
+    }
+
+    /// Type-checks the expression against the given schema, returning its
+    /// type. Numeric values (see [`compare`]) are comparable with each other.
+    pub fn check(&self, schema: &TableSchema) -> DbResult<TypeId> {
+        const BOOL: TypeId = TypeId::Primitive(PrimitiveTypeId::Bool);
+        let expect_bool = |expr: &Expr| -> DbResult<()> {
+            match expr.check(schema)? {
+                BOOL => Ok(()),
+                ty => Err(Error::ExecError(format!(
+                    "expected a boolean expression, but got `{}` in `{expr}`",
+                    ty.name()
+                ))),
+            }
+        };
+        match self {
+            Expr::Column(name) => schema
+                .columns
+                .iter()
+                .find(|column| column.name == *name)
+                .map(|column| column.ty)
+                .ok_or_else(|| Error::ExecError(format!("column `{name}` does not exist"))),
+            Expr::Literal(value) => Ok(value.type_id()),
+            Expr::Not(inner) => {
+                expect_bool(inner)?;
+                Ok(BOOL)
+            }
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+                expect_bool(lhs)?;
+                expect_bool(rhs)?;
+                Ok(BOOL)
+            }
+            Expr::Cmp(lhs, _, rhs) => {
+                let (lhs_ty, rhs_ty) = (lhs.check(schema)?, rhs.check(schema)?);
+                let comparable = match (lhs_ty, rhs_ty) {
+                    (TypeId::Primitive(a), TypeId::Primitive(b)) => {
+                        a == b || (is_numeric(a) && is_numeric(b))
+                    }
+                    _ => false,
+                };
+                if !comparable {
+                    return Err(Error::Cast(format!(
+                        "can't compare `{}` with `{}` in `{self}`",
+                        lhs_ty.name(),
+                        rhs_ty.name()
+                    )));
+                }
+                Ok(BOOL)
+            }
+            Expr::Index(array, index) => {
+                let (array_ty, index_ty) = (array.check(schema)?, index.check(schema)?);
+                match (array_ty.element_type(), index_ty) {
+                    (Some(element_ty), TypeId::Primitive(ty)) if is_integer(ty) => Ok(element_ty),
+                    _ => Err(Error::Cast(format!(
+                        "can't index `{}` with `{}` in `{self}`",
+                        array_ty.name(),
+                        index_ty.name()
+                    ))),
+                }
+            }
+            Expr::Field(inner, name) => match inner.check(schema)? {
+                TypeId::Composite(ty) => (ty.fields().columns.iter())
+                    .find(|field| field.name == *name)
+                    .map(|field| field.ty)
+                    .ok_or_else(|| {
+                        Error::ExecError(format!("field `{name}` does not exist in `{self}`"))
+                    }),
+                ty => Err(Error::Cast(format!(
+                    "can't access field `{name}` of `{}` in `{self}`",
+                    ty.name()
+                ))),
+            },
+        }
+    }
+
+    /// Type-checks the expression as a predicate, i.e., a boolean expression.
+    pub fn check_predicate(&self, schema: &TableSchema) -> DbResult<()> {
+        match self.check(schema)? {
+            TypeId::Primitive(PrimitiveTypeId::Bool) => Ok(()),
+            ty => Err(Error::ExecError(format!(
+                "predicate must be a boolean expression, but `{self}` is `{}`",
+                ty.name()
+            ))),
+        }
+    }
+
+    /// Evaluates the expression over the given values. Returns `None` if some
+    /// referenced column is missing or if the operand types mismatch (which
+    /// can't happen for type-checked expressions; see [`Expr::check`]).
+    pub fn eval<'v>(&'v self, values: &'v Values) -> Option<Cow<'v, Value>> {
+        Some(match self {
+            Expr::Column(name) => Cow::Borrowed(values.get(name)?),
+            Expr::Literal(value) => Cow::Borrowed(value),
+            Expr::Not(inner) => Cow::Owned(Value::Bool(!inner.eval_bool(values)?)),
+            Expr::And(lhs, rhs) => Cow::Owned(Value::Bool(
+                lhs.eval_bool(values)? && rhs.eval_bool(values)?,
+            )),
+            Expr::Or(lhs, rhs) => Cow::Owned(Value::Bool(
+                lhs.eval_bool(values)? || rhs.eval_bool(values)?,
+            )),
+            Expr::Cmp(lhs, op, rhs) => {
+                let ord = compare(&*lhs.eval(values)?, &*rhs.eval(values)?)?;
+                Cow::Owned(Value::Bool(op.test(ord)))
+            }
+            Expr::Index(array, index) => {
+                let index = as_i64(&*index.eval(values)?)?;
+                let index = usize::try_from(index.checked_sub(1)?).ok()?;
+                match array.eval(values)? {
+                    Cow::Borrowed(array) => {
+                        Cow::Borrowed(array.try_cast_array_ref().ok()?.get(index)?)
+                    }
+                    Cow::Owned(array) => {
+                        Cow::Owned(array.try_cast_array_ref().ok()?.get(index)?.clone())
+                    }
+                }
+            }
+            Expr::Field(inner, name) => match inner.eval(values)? {
+                Cow::Borrowed(value) => Cow::Borrowed(value.field(name)?),
+                Cow::Owned(value) => Cow::Owned(value.field(name)?.clone()),
+            },
+        })
+    }
+
+    /// Checks whether the given values match the (boolean) expression.
+    pub fn matches(&self, values: &Values) -> bool {
+        self.eval_bool(values) == Some(true)
+    }
+
+    fn eval_bool(&self, values: &Values) -> Option<bool> {
+        self.eval(values)?.try_cast_bool_ref().ok().copied()
+    }
+
+    /// Extracts a key range from the expression, i.e., a column and the bounds
+    /// which all matching rows satisfy, so that they may be looked up through
+    /// an index over that column. The expression must still be applied to the
+    /// rows within the range.
+    ///
+    /// Ranges are extracted from comparisons between a column and a literal (in
+    /// any order) and from conjunctions (in which case the left-most range is
+    /// used). The literal is converted to the column type; if the conversion
+    /// isn't lossless, no range is extracted.
+    pub fn key_range(&self, schema: &TableSchema) -> Option<(String, Bound<Value>, Bound<Value>)> {
+        let (column, op, value) = match self {
+            Expr::And(lhs, rhs) => return lhs.key_range(schema).or_else(|| rhs.key_range(schema)),
+            Expr::Cmp(lhs, op, rhs) => match (&**lhs, &**rhs) {
+                (Expr::Column(column), Expr::Literal(value)) => (column, *op, value),
+                (Expr::Literal(value), Expr::Column(column)) => (column, op.flip(), value),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let column_ty = schema.columns.iter().find(|c| c.name == *column)?.ty;
+        let value = cast(value, column_ty)?;
+        let (start, end) = match op {
+            CmpOp::Eq => (Bound::Included(value.clone()), Bound::Included(value)),
+            CmpOp::Lt => (Bound::Unbounded, Bound::Excluded(value)),
+            CmpOp::Lte => (Bound::Unbounded, Bound::Included(value)),
+            CmpOp::Gt => (Bound::Excluded(value), Bound::Unbounded),
+            CmpOp::Gte => (Bound::Included(value), Bound::Unbounded),
+            CmpOp::Neq => return None,
+        };
+        Some((column.clone(), start, end))
+    }
+}
+
+/// A row predicate, either an opaque closure or an [`Expr`].
+#[derive(Copy, Clone)]
+pub(crate) enum Predicate<'a> {
+    Fn(&'a (dyn Sync + for<'v> Fn(&'v Values) -> bool)),
+    Expr(&'a Expr),
+}
+
+impl Predicate<'_> {
+    /// Type-checks the predicate, if it is an expression.
+    pub(crate) fn check(self, schema: &TableSchema) -> DbResult<()> {
+        match self {
+            Predicate::Fn(_) => Ok(()),
+            Predicate::Expr(expr) => expr.check_predicate(schema),
+        }
+    }
+
+    pub(crate) fn matches(self, values: &Values) -> bool {
+        match self {
+            Predicate::Fn(pred) => pred(values),
+            Predicate::Expr(expr) => expr.matches(values),
+        }
+    }
+
+    /// Describes the predicate. Closures can't be described.
+    pub(crate) fn describe(self) -> String {
+        match self {
+            Predicate::Fn(_) => "<closure>".to_owned(),
+            Predicate::Expr(expr) => expr.to_string(),
+        }
+    }
+}
+
+impl ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Column(name) => f.write_str(name),
+            Expr::Literal(Value::Text(text)) => write!(f, "'{text}'"),
+            Expr::Literal(value) => value.fmt(f),
+            Expr::Not(inner) => write!(f, "NOT ({inner})"),
+            Expr::And(lhs, rhs) => write!(f, "({lhs}) AND ({rhs})"),
+            Expr::Or(lhs, rhs) => write!(f, "({lhs}) OR ({rhs})"),
+            Expr::Index(array, index) => write!(f, "{array}[{index}]"),
+            Expr::Field(inner, name) => write!(f, "{inner}.{name}"),
+            Expr::Cmp(lhs, op, rhs) => {
+                let op = match op {
+                    CmpOp::Eq => "=",
+                    CmpOp::Neq => "<>",
+                    CmpOp::Lt => "<",
+                    CmpOp::Lte => "<=",
+                    CmpOp::Gt => ">",
+                    CmpOp::Gte => ">=",
+                };
+                write!(f, "{lhs} {op} {rhs}")
+            }
+        }
+    }
+}
+
+/// Compares two values. Numeric values (i.e., integers of different widths,
+/// decimals of different scales and floats) are comparable. Comparisons which
+/// involve floats are approximate, and NaNs aren't comparable. Dates and times
+/// are only comparable with values of the same type.
+pub fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+        (Value::Blob(a), Value::Blob(b)) => Some(a.cmp(b)),
+        (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+        (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
+        (Value::Float(_), _) | (_, Value::Float(_)) => as_f64(a)?.partial_cmp(&as_f64(b)?),
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            Some(as_decimal(a)?.cmp_numeric(&as_decimal(b)?))
+        }
+        (a, b) => Some(as_i64(a)?.cmp(&as_i64(b)?)),
+    }
+}
+
+/// Returns the value of an integer value, regardless of its width.
+pub fn as_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Byte(inner) => Some(*inner as i64),
+        Value::ShortInt(inner) => Some(*inner as i64),
+        Value::Int(inner) => Some(*inner as i64),
+        Value::BigInt(inner) | Value::Timestamp(inner) => Some(*inner),
+        _ => None,
+    }
+}
+
+/// Returns the value of a decimal or integer value, as a decimal.
+fn as_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Decimal(decimal) => Some(*decimal),
+        value => Some(Decimal {
+            unscaled: as_i64(value)?,
+            scale: 0,
+        }),
+    }
+}
+
+/// Returns the (approximate) value of a numeric value, as a float.
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float(float) => Some(float.0),
+        Value::Decimal(decimal) => Some(decimal.to_f64()),
+        value => Some(as_i64(value)? as f64),
+    }
+}
+
+pub(crate) fn is_integer(ty: PrimitiveTypeId) -> bool {
+    matches!(
+        ty,
+        PrimitiveTypeId::Byte
+            | PrimitiveTypeId::ShortInt
+            | PrimitiveTypeId::Int
+            | PrimitiveTypeId::BigInt
+            | PrimitiveTypeId::Timestamp
+    )
+}
+
+fn is_numeric(ty: PrimitiveTypeId) -> bool {
+    is_integer(ty) || matches!(ty, PrimitiveTypeId::Float | PrimitiveTypeId::Decimal(_))
+}
+
+/// Converts the given value into the given type, if lossless.
+pub(crate) fn cast(value: &Value, ty: TypeId) -> Option<Value> {
+    if value.type_id() == ty {
+        return Some(value.clone());
+    }
+    let TypeId::Primitive(primitive) = ty else {
+        return None;
+    };
+    if let PrimitiveTypeId::Decimal(scale) = primitive {
+        return Some(Value::Decimal(as_decimal(value)?.rescale(scale)?));
+    }
+    let int = as_i64(value)?;
+    Some(match primitive {
+        PrimitiveTypeId::Byte => Value::Byte(int.try_into().ok()?),
+        PrimitiveTypeId::ShortInt => Value::ShortInt(int.try_into().ok()?),
+        PrimitiveTypeId::Int => Value::Int(int.try_into().ok()?),
+        PrimitiveTypeId::BigInt => Value::BigInt(int),
+        PrimitiveTypeId::Timestamp => Value::Timestamp(int),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::{column::Column, ty::CompositeType};
+
+    fn schema() -> TableSchema {
+        let column = |name: &str, ty| Column {
+            ty: TypeId::Primitive(ty),
+            name: name.into(),
+            max_len: None,
+            default: None,
+            not_null: false,
+        };
+        TableSchema {
+            columns: vec![
+                column("id", PrimitiveTypeId::Int),
+                column("name", PrimitiveTypeId::Text),
+                column("active", PrimitiveTypeId::Bool),
+            ],
+        }
+    }
+
+    fn values(id: i32, name: &str, active: bool) -> Values {
+        let mut values = Values::new();
+        values.set("id".into(), Value::Int(id));
+        values.set("name".into(), Value::Text(name.into()));
+        values.set("active".into(), Value::Bool(active));
+        values
+    }
+
+    #[test]
+    fn test_eval() {
+        let expr = col("id").gt(lit(Value::BigInt(1))).and(
+            col("name")
+                .neq(lit(Value::Text("b".into())))
+                .or(col("active")),
+        );
+        assert!(expr.check(&schema()).is_ok());
+        assert!(expr.matches(&values(2, "a", false)));
+        assert!(expr.matches(&values(2, "b", true)));
+        assert!(!expr.matches(&values(2, "b", false)));
+        assert!(!expr.matches(&values(1, "a", true)));
+        assert!((!col("active")).matches(&values(1, "a", false)));
+        // Missing columns never match.
+        assert!(!col("missing").matches(&values(1, "a", true)));
+    }
+
+    #[test]
+    fn test_check() {
+        let schema = schema();
+        assert!(col("missing").check(&schema).is_err());
+        assert!(col("id").and(col("active")).check(&schema).is_err());
+        assert!(col("id")
+            .eq(lit(Value::Text("1".into())))
+            .check(&schema)
+            .is_err());
+        assert!((!col("id")).check(&schema).is_err());
+        assert_eq!(
+            col("id").lte(lit(Value::Byte(1))).check(&schema).unwrap(),
+            TypeId::Primitive(PrimitiveTypeId::Bool)
+        );
+        let decimal = Value::Decimal(Decimal {
+            unscaled: 15,
+            scale: 1,
+        });
+        assert!(col("id").lt(lit(decimal)).check(&schema).is_ok());
+    }
+
+    #[test]
+    fn test_index() {
+        let schema = TableSchema {
+            columns: vec![Column {
+                ty: TypeId::NestedArray(PrimitiveTypeId::Int, 2),
+                name: "grid".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
+            }],
+        };
+        let row = |grid: Vec<Vec<i32>>| {
+            let grid = grid.into_iter().map(|row| {
+                Value::Array(
+                    PrimitiveTypeId::Int,
+                    row.into_iter().map(Value::Int).collect(),
+                )
+            });
+            let mut values = Values::new();
+            values.set(
+                "grid".into(),
+                Value::NestedArray(PrimitiveTypeId::Int, 2, grid.collect()),
+            );
+            values
+        };
+
+        let expr = col("grid")
+            .index(lit(Value::Int(2)))
+            .index(lit(Value::Byte(1)));
+        assert_eq!(expr.to_string(), "grid[2][1]");
+        assert!(expr.clone().gt(lit(Value::Int(3))).check(&schema).is_ok());
+        assert_eq!(
+            col("grid")
+                .index(lit(Value::Int(1)))
+                .check(&schema)
+                .unwrap(),
+            TypeId::Array(PrimitiveTypeId::Int)
+        );
+        let matches = expr.gt(lit(Value::Int(3)));
+        assert!(matches.matches(&row(vec![vec![1], vec![4, 0]])));
+        assert!(!matches.matches(&row(vec![vec![5], vec![3]])));
+        // Out of bounds accesses never match.
+        assert!(!matches.matches(&row(vec![vec![5]])));
+        assert!(!matches.matches(&row(vec![vec![5], vec![]])));
+
+        let not_an_array = col("grid")
+            .index(lit(Value::Int(1)))
+            .index(lit(Value::Int(1)));
+        assert!(not_an_array
+            .index(lit(Value::Int(1)))
+            .check(&schema)
+            .is_err());
+        let not_an_index = col("grid").index(lit(Value::Text("1".into())));
+        assert!(not_an_index.check(&schema).is_err());
+    }
+
+    #[test]
+    fn test_field() {
+        let address = CompositeType::new(schema());
+        let schema = TableSchema {
+            columns: vec![Column {
+                ty: TypeId::Composite(address),
+                name: "address".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
+            }],
+        };
+        let row = |id: i32| {
+            let fields = values(id, "a", true)
+                .try_into_schematized(address.fields())
+                .unwrap();
+            let mut values = Values::new();
+            values.set("address".into(), Value::Composite(address, fields));
+            values
+        };
+
+        let expr = col("address").field("id").gte(lit(Value::Int(2)));
+        assert_eq!(expr.to_string(), "address.id >= 2");
+        assert!(expr.check(&schema).is_ok());
+        assert!(expr.matches(&row(2)));
+        assert!(!expr.matches(&row(1)));
+        assert!(col("address")
+            .field("active")
+            .and(col("address").field("id").eq(lit(Value::Int(3))))
+            .matches(&row(3)));
+
+        assert!(col("address").field("missing").check(&schema).is_err());
+        let not_composite = col("address").field("id").field("id");
+        assert!(not_composite.check(&schema).is_err());
+        assert!(col("address")
+            .eq(lit(Value::Int(1)))
+            .check(&schema)
+            .is_err());
+    }
+
+    #[test]
+    fn test_key_range() {
+        let schema = schema();
+        let range = lit(Value::BigInt(5))
+            .gt(col("id"))
+            .and(col("name").eq(lit(Value::Text("a".into()))))
+            .key_range(&schema);
+        assert_eq!(
+            range,
+            Some((
+                "id".into(),
+                Bound::Unbounded,
+                Bound::Excluded(Value::Int(5))
+            ))
+        );
+        let range = col("active").and(col("name").gte(lit(Value::Text("a".into()))));
+        assert_eq!(
+            range.key_range(&schema),
+            Some((
+                "name".into(),
+                Bound::Included(Value::Text("a".into())),
+                Bound::Unbounded
+            ))
+        );
+        // Lossy conversions, disjunctions and inequalities have no range.
+        let out_of_range = col("id").eq(lit(Value::BigInt(i64::MAX)));
+        assert_eq!(out_of_range.key_range(&schema), None);
+        let or = col("id").eq(lit(Value::Int(1))).or(col("active"));
+        assert_eq!(or.key_range(&schema), None);
+        assert_eq!(col("id").neq(lit(Value::Int(1))).key_range(&schema), None);
+    }
+}
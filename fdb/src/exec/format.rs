@@ -0,0 +1,241 @@
+//! Value display formatting.
+//!
+//! Unlike the [`Value`]'s `Display` implementation (which is meant for logs),
+//! the formats defined here render the whole value in an interpretable way,
+//! to be used by renderers and exporters. Numbers are always formatted in a
+//! locale-independent way (i.e., no digit grouping and `-` as the sign).
+
+use std::fmt::{self, Write};
+
+use crate::exec::{time, value::Value};
+
+/// The value formatting options.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValueFormat {
+    pub timestamp: TimestampFormat,
+    pub blob: BlobFormat,
+}
+
+/// The timestamp format. Timestamps are milliseconds since the Unix epoch.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// The underlying number of milliseconds, e.g., `1678795200000`.
+    Raw,
+    /// An ISO-8601 date and time in UTC, e.g., `2023-03-14T12:00:00.000Z`.
+    #[default]
+    Iso8601,
+}
+
+/// The blob format.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum BlobFormat {
+    /// The blob length only, e.g., `<bytes (2)>`.
+    Summary,
+    /// The (uppercase) hexadecimal representation, e.g., `CAFE`.
+    #[default]
+    Hex,
+    /// The (padded) standard base64 representation, e.g., `yv4=`.
+    Base64,
+}
+
+impl ValueFormat {
+    /// Returns a [`Display`](fmt::Display) implementation which formats the
+    /// given value using these options.
+    pub fn display<'a>(&'a self, value: &'a Value) -> Formatted<'a> {
+        Formatted {
+            format: self,
+            value,
+        }
+    }
+
+    /// Formats the given value into a string.
+    pub fn format(&self, value: &Value) -> String {
+        self.display(value).to_string()
+    }
+
+    fn fmt_value(&self, value: &Value, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match value {
+            Value::Timestamp(millis) => match self.timestamp {
+                TimestampFormat::Raw => fmt::Display::fmt(millis, f),
+                TimestampFormat::Iso8601 => time::fmt_timestamp(*millis, f),
+            },
+            Value::Blob(bytes) => match self.blob {
+                BlobFormat::Summary => write!(f, "<bytes ({})>", bytes.len()),
+                BlobFormat::Hex => bytes.iter().try_for_each(|byte| write!(f, "{byte:02X}")),
+                BlobFormat::Base64 => fmt_base64(bytes, f),
+            },
+            // Array elements are formatted as in the SQL array literals.
+            Value::Array(_, elements) | Value::NestedArray(_, _, elements) => {
+                f.write_char('[')?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    self.fmt_nested(element, f)?;
+                }
+                f.write_char(']')
+            }
+            // Likewise, composite values are formatted as in the SQL struct
+            // literals, e.g., `{city: 'Lisbon', zip: 1000}`.
+            Value::Composite(ty, fields) => {
+                f.write_char('{')?;
+                for (i, (field, value)) in ty
+                    .fields()
+                    .columns
+                    .iter()
+                    .zip(fields.as_slice())
+                    .enumerate()
+                {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: ", field.name)?;
+                    self.fmt_nested(value, f)?;
+                }
+                f.write_char('}')
+            }
+            value => fmt::Display::fmt(value, f),
+        }
+    }
+
+    /// Formats a value nested in an array or composite value, whose texts are
+    /// quoted.
+    fn fmt_nested(&self, value: &Value, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Value::Text(text) = value {
+            write!(f, "'{}'", text.replace('\'', "''"))
+        } else {
+            self.fmt_value(value, f)
+        }
+    }
+}
+
+/// A value formatted using some [`ValueFormat`]. See [`ValueFormat::display`].
+pub struct Formatted<'a> {
+    format: &'a ValueFormat,
+    value: &'a Value,
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.format.fmt_value(self.value, f)
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn fmt_base64(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (n >> (18 - 6 * i)) & 0x3F;
+                f.write_char(BASE64_ALPHABET[index as usize] as char)?;
+            } else {
+                f.write_char('=')?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes the (padded) standard base64 representation of some bytes, as
+/// formatted by [`BlobFormat::Base64`]. Returns `None` if the text is malformed.
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    for (i, chunk) in text.chunks(4).enumerate() {
+        let is_last = (i + 1) * 4 == text.len();
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !is_last) {
+            return None;
+        }
+        let mut n = 0_u32;
+        for (j, &c) in chunk[..4 - padding].iter().enumerate() {
+            let index = BASE64_ALPHABET.iter().position(|&a| a == c)?;
+            n |= (index as u32) << (18 - 6 * j);
+        }
+        bytes.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::ty::PrimitiveTypeId;
+
+    #[test]
+    fn test_format_timestamp() {
+        let format = ValueFormat::default();
+        let iso = |millis| format.format(&Value::Timestamp(millis));
+        assert_eq!(iso(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso(1_678_795_200_123), "2023-03-14T12:00:00.123Z");
+        assert_eq!(iso(951_782_400_000), "2000-02-29T00:00:00.000Z");
+        assert_eq!(iso(-1), "1969-12-31T23:59:59.999Z");
+
+        let format = ValueFormat {
+            timestamp: TimestampFormat::Raw,
+            ..ValueFormat::default()
+        };
+        assert_eq!(format.format(&Value::Timestamp(-1)), "-1");
+    }
+
+    #[test]
+    fn test_format_blob() {
+        let with = |blob| ValueFormat {
+            blob,
+            ..ValueFormat::default()
+        };
+        let bytes = |bytes: &[u8]| Value::Blob(bytes.to_vec());
+        assert_eq!(
+            with(BlobFormat::Hex).format(&bytes(b"\xCA\xFE\x01")),
+            "CAFE01"
+        );
+        assert_eq!(
+            with(BlobFormat::Summary).format(&bytes(b"ab")),
+            "<bytes (2)>"
+        );
+        let base64 = with(BlobFormat::Base64);
+        for (input, expected) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+            (b"\xCA\xFE", "yv4="),
+        ] {
+            assert_eq!(base64.format(&bytes(input)), expected);
+            assert_eq!(decode_base64(expected).as_deref(), Some(input));
+        }
+        for malformed in ["Zg=", "Z===", "Zg==Zg==", "Zm9*"] {
+            assert_eq!(decode_base64(malformed), None);
+        }
+    }
+
+    #[test]
+    fn test_format_other() {
+        let format = ValueFormat::default();
+        assert_eq!(format.format(&Value::Int(-1_000_000)), "-1000000");
+        assert_eq!(format.format(&Value::Text("it's".into())), "it's");
+        let texts = Value::Array(
+            PrimitiveTypeId::Text,
+            vec![Value::Text("a".into()), Value::Text("it's".into())],
+        );
+        assert_eq!(format.format(&texts), "['a', 'it''s']");
+        let timestamps = Value::Array(PrimitiveTypeId::Timestamp, vec![Value::Timestamp(0)]);
+        assert_eq!(format.format(&timestamps), "[1970-01-01T00:00:00.000Z]");
+        let nested = Value::NestedArray(
+            PrimitiveTypeId::Text,
+            2,
+            vec![texts, Value::Array(PrimitiveTypeId::Text, vec![])],
+        );
+        assert_eq!(format.format(&nested), "[['a', 'it''s'], []]");
+    }
+}
@@ -0,0 +1,477 @@
+//! Database integrity checks. See [`Db::check_integrity`].
+//!
+//! The check walks every structure of the database, starting from the first
+//! page: the database schema, the heap sequence of each table, the B+Tree of
+//! each index, the temporary sequences and the free list. Each page must be
+//! reachable from exactly one of them, its checksum must match, and it must
+//! have the type expected by the structure and hold its own ID. Heap sequences
+//! must be as long as their headers state and hold as many records. Pages which
+//! aren't reachable at all are reported as orphaned, since they are never
+//! reused.
+//!
+//! Problems are collected in an [`IntegrityReport`], instead of failing the
+//! check, so that all of them are reported at once.
+
+use std::fmt;
+
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    catalog::{
+        object::ObjectType,
+        page::{
+            BTreeLeafPage, BTreePage, FirstPage, FreeListPage, HeapPage, PageId, PageType,
+            SpecificPage,
+        },
+    },
+    error::{DbResult, Error},
+    exec::query::{self, Query},
+    io::pager::Pager,
+    Db,
+};
+
+/// The result of an integrity check. See [`Db::check_integrity`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// The number of pages in the database file, as per its header.
+    pub page_count: u32,
+    /// The number of pages reachable from some structure.
+    pub pages_checked: u32,
+    /// The problems found, in the order they were found.
+    pub problems: Vec<IntegrityProblem>,
+}
+
+impl IntegrityReport {
+    /// Checks whether no problem was found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A problem found by an integrity check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntegrityProblem {
+    /// The page the problem was found at. Problems of a whole heap sequence are
+    /// reported at its first page, and problems of the free list at the first
+    /// page of the database.
+    pub page_id: PageId,
+    /// The structure which the page is part of, if any.
+    pub structure: Option<Structure>,
+    pub kind: ProblemKind,
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "page {}", self.page_id.get())?;
+        if let Some(structure) = &self.structure {
+            write!(f, " ({structure})")?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// A database structure, whose pages are visited by an integrity check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Structure {
+    /// The first page of the database.
+    Header,
+    /// The heap sequence which stores the database schema.
+    Schema,
+    /// The heap sequence of the given table.
+    Table(String),
+    /// The B+Tree of the given index.
+    Index(String),
+    /// A temporary heap sequence. See [`temp`](crate::io::temp).
+    Temp,
+    /// The list of deallocated pages.
+    FreeList,
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Structure::Header => f.write_str("header"),
+            Structure::Schema => f.write_str("schema"),
+            Structure::Table(name) => write!(f, "table `{name}`"),
+            Structure::Index(name) => write!(f, "index `{name}`"),
+            Structure::Temp => f.write_str("temporary sequence"),
+            Structure::FreeList => f.write_str("free list"),
+        }
+    }
+}
+
+/// The kind of an [`IntegrityProblem`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// The page checksum doesn't match its contents.
+    ChecksumMismatch,
+    /// The page couldn't be read or decoded.
+    Unreadable(String),
+    /// The page is referenced, but it is beyond the database page count.
+    OutOfBounds,
+    /// The page type isn't the one expected by its structure.
+    UnexpectedType {
+        expected: PageType,
+        actual: PageType,
+    },
+    /// The page holds the ID of another page, e.g., due to a misdirected write.
+    UnexpectedId(PageId),
+    /// The page is also referenced by the given structure (or twice by the
+    /// same one).
+    SharedPage(Structure),
+    /// The first page of a heap sequence has no sequence header.
+    MissingSeqHeader,
+    /// The heap sequence ends (i.e., a page has no next page) before reaching
+    /// the page count of its header.
+    BrokenChain { expected: u32, actual: u32 },
+    /// The last page of the heap sequence isn't the one of its header.
+    LastPageMismatch { expected: PageId, actual: PageId },
+    /// The heap sequence pages don't hold as many records as its header
+    /// states.
+    RecordCountMismatch { expected: u64, actual: u64 },
+    /// The B+Tree leaves aren't all at the same depth.
+    UnbalancedTree,
+    /// The leaf sibling pointers don't point to the adjacent leaves.
+    BrokenSiblingLink,
+    /// The free list length isn't the free page count of the header.
+    FreePageCountMismatch { expected: u32, actual: u32 },
+    /// The objects of the database schema couldn't be read, hence the tables
+    /// and indexes weren't checked.
+    UnreadableSchema(String),
+    /// The page isn't reachable from any structure.
+    Orphaned,
+}
+
+impl fmt::Display for ProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProblemKind::ChecksumMismatch => f.write_str("checksum mismatch"),
+            ProblemKind::Unreadable(error) => write!(f, "unreadable page: {error}"),
+            ProblemKind::OutOfBounds => f.write_str("referenced page is out of bounds"),
+            ProblemKind::UnexpectedType { expected, actual } => {
+                write!(f, "expected a {expected:?} page, found a {actual:?} page")
+            }
+            ProblemKind::UnexpectedId(id) => write!(f, "page holds the ID {}", id.get()),
+            ProblemKind::SharedPage(other) => write!(f, "page is also part of {other}"),
+            ProblemKind::MissingSeqHeader => f.write_str("missing sequence header"),
+            ProblemKind::BrokenChain { expected, actual } => {
+                write!(f, "sequence has {actual} of its {expected} pages")
+            }
+            ProblemKind::LastPageMismatch { expected, actual } => write!(
+                f,
+                "sequence ends at page {}, instead of {}",
+                actual.get(),
+                expected.get()
+            ),
+            ProblemKind::RecordCountMismatch { expected, actual } => {
+                write!(f, "sequence has {actual} records, instead of {expected}")
+            }
+            ProblemKind::UnbalancedTree => f.write_str("leaves are at different depths"),
+            ProblemKind::BrokenSiblingLink => f.write_str("broken leaf sibling link"),
+            ProblemKind::FreePageCountMismatch { expected, actual } => {
+                write!(f, "free list has {actual} pages, instead of {expected}")
+            }
+            ProblemKind::UnreadableSchema(error) => write!(f, "unreadable schema: {error}"),
+            ProblemKind::Orphaned => f.write_str("orphaned page"),
+        }
+    }
+}
+
+/// Checks the integrity of the given database.
+///
+/// Pages are read from the disk, hence callers must write the dirty pages
+/// beforehand and guarantee that no page is written while the check runs
+/// (e.g., by holding the statement latch, see [`Db::check_integrity`]).
+#[instrument(level = "debug", skip_all)]
+pub async fn check(db: &Db) -> DbResult<IntegrityReport> {
+    let pager = db.pager();
+    // The first page is required to locate everything else.
+    let first_page = pager
+        .read_from_disk(PageId::FIRST)
+        .await?
+        .cast::<FirstPage>();
+    let header = &first_page.header;
+
+    let mut checker = Checker {
+        pager,
+        owners: vec![None; header.page_count as usize],
+        structures: Vec::new(),
+        problems: Vec::new(),
+    };
+    let s = checker.structure(Structure::Header);
+    checker.claim(PageId::FIRST, s);
+
+    let s = checker.structure(Structure::Schema);
+    checker
+        .check_heap_seq(header.first_schema_seq_page_id, s)
+        .await;
+
+    let mut objects = Vec::new();
+    let mut select = query::object::Select::new();
+    loop {
+        match select.next(db).await {
+            Ok(Some(object)) => objects.push(object),
+            Ok(None) => break,
+            Err(error) => {
+                checker.problems.push(IntegrityProblem {
+                    page_id: header.first_schema_seq_page_id,
+                    structure: Some(Structure::Schema),
+                    kind: ProblemKind::UnreadableSchema(error.to_string()),
+                });
+                break;
+            }
+        }
+    }
+    for object in objects {
+        match object.ty {
+            ObjectType::Table(_) => {
+                let s = checker.structure(Structure::Table(object.name));
+                checker.check_heap_seq(object.page_id, s).await;
+            }
+            ObjectType::Index(_) => {
+                let s = checker.structure(Structure::Index(object.name));
+                checker.check_btree(object.page_id, s).await;
+            }
+            // External tables have no pages and statistics are kept along with
+            // their table's sequence.
+            ObjectType::External(_) | ObjectType::Statistics(_) => (),
+        }
+    }
+
+    for &page_id in &first_page.temp_seq_page_ids {
+        let s = checker.structure(Structure::Temp);
+        checker.check_heap_seq(page_id, s).await;
+    }
+
+    let s = checker.structure(Structure::FreeList);
+    checker
+        .check_free_list(header.first_free_list_page_id, header.free_page_count, s)
+        .await;
+
+    let mut pages_checked = 0;
+    for (i, owner) in checker.owners.iter().enumerate() {
+        if owner.is_some() {
+            pages_checked += 1;
+        } else {
+            checker.problems.push(IntegrityProblem {
+                page_id: PageId::new_u32(i as u32 + 1),
+                structure: None,
+                kind: ProblemKind::Orphaned,
+            });
+        }
+    }
+
+    let report = IntegrityReport {
+        page_count: header.page_count,
+        pages_checked,
+        problems: checker.problems,
+    };
+    if report.is_ok() {
+        debug!(pages_checked, "no integrity problems found");
+    } else {
+        warn!(problems = report.problems.len(), "integrity problems found");
+    }
+    Ok(report)
+}
+
+struct Checker<'a> {
+    pager: &'a Pager,
+    /// The index (in `structures`) of the structure which each page is part
+    /// of, indexed by the page number minus one.
+    owners: Vec<Option<usize>>,
+    structures: Vec<Structure>,
+    problems: Vec<IntegrityProblem>,
+}
+
+impl Checker<'_> {
+    /// Registers the given structure, returning its index.
+    fn structure(&mut self, structure: Structure) -> usize {
+        self.structures.push(structure);
+        self.structures.len() - 1
+    }
+
+    fn report(&mut self, page_id: PageId, s: usize, kind: ProblemKind) {
+        self.problems.push(IntegrityProblem {
+            page_id,
+            structure: Some(self.structures[s].clone()),
+            kind,
+        });
+    }
+
+    /// Marks the given page as part of the given structure. Fails (reporting
+    /// the problem) if it is out of bounds or already part of a structure, in
+    /// which case it must not be visited (e.g., so that cycles end).
+    fn claim(&mut self, page_id: PageId, s: usize) -> bool {
+        let Some(owner) = self.owners.get_mut(page_id.get() as usize - 1) else {
+            self.report(page_id, s, ProblemKind::OutOfBounds);
+            return false;
+        };
+        if let Some(other) = *owner {
+            let other = self.structures[other].clone();
+            self.report(page_id, s, ProblemKind::SharedPage(other));
+            return false;
+        }
+        *owner = Some(s);
+        true
+    }
+
+    /// Reads the given page from the disk, checking its type and ID. Returns
+    /// `None` (reporting the problem) if it fails.
+    async fn read<S: SpecificPage>(&mut self, page_id: PageId, s: usize) -> Option<S> {
+        let page = match self.pager.read_from_disk(page_id).await {
+            Ok(page) => page,
+            Err(Error::ChecksumMismatch(_)) => {
+                self.report(page_id, s, ProblemKind::ChecksumMismatch);
+                return None;
+            }
+            Err(error) => {
+                self.report(page_id, s, ProblemKind::Unreadable(error.to_string()));
+                return None;
+            }
+        };
+        if page.ty() != S::ty() {
+            let kind = ProblemKind::UnexpectedType {
+                expected: S::ty(),
+                actual: page.ty(),
+            };
+            self.report(page_id, s, kind);
+            return None;
+        }
+        if page.id() != page_id {
+            self.report(page_id, s, ProblemKind::UnexpectedId(page.id()));
+            return None;
+        }
+        Some(page.cast())
+    }
+
+    /// Checks the heap sequence which starts at the given page.
+    async fn check_heap_seq(&mut self, first_page_id: PageId, s: usize) {
+        if !self.claim(first_page_id, s) {
+            return;
+        }
+        let Some(mut page) = self.read::<HeapPage>(first_page_id, s).await else {
+            return;
+        };
+        let Some(seq_header) = page.header.seq_header.clone() else {
+            self.report(first_page_id, s, ProblemKind::MissingSeqHeader);
+            return;
+        };
+
+        // As in `heap::page_ids`, the sequence is traversed using the page
+        // count, since the last page's `next_page_id` may not be null.
+        let mut page_id = first_page_id;
+        let mut record_count = 0;
+        for n in 1..=seq_header.page_count {
+            record_count += page.header.record_count as u64;
+            if n == seq_header.page_count {
+                break;
+            }
+            let Some(next_page_id) = page.header.next_page_id else {
+                let kind = ProblemKind::BrokenChain {
+                    expected: seq_header.page_count,
+                    actual: n,
+                };
+                self.report(first_page_id, s, kind);
+                return;
+            };
+            if !self.claim(next_page_id, s) {
+                return;
+            }
+            page_id = next_page_id;
+            page = match self.read::<HeapPage>(page_id, s).await {
+                Some(page) => page,
+                None => return,
+            };
+        }
+
+        if page_id != seq_header.last_page_id {
+            let kind = ProblemKind::LastPageMismatch {
+                expected: seq_header.last_page_id,
+                actual: page_id,
+            };
+            self.report(first_page_id, s, kind);
+        }
+        if record_count != seq_header.record_count {
+            let kind = ProblemKind::RecordCountMismatch {
+                expected: seq_header.record_count,
+                actual: record_count,
+            };
+            self.report(first_page_id, s, kind);
+        }
+    }
+
+    /// Checks the B+Tree whose root is at the given page. As in
+    /// [`BTree::stats`], the tree is visited level by level.
+    ///
+    /// [`BTree::stats`]: crate::exec::operations::index::BTree::stats
+    async fn check_btree(&mut self, root: PageId, s: usize) {
+        if !self.claim(root, s) {
+            return;
+        }
+        // Sibling links are only checked if the whole level was read.
+        let mut complete = true;
+        let mut level = vec![root];
+        while !level.is_empty() {
+            let mut next_level = Vec::new();
+            let mut leaves = Vec::new();
+            for page_id in level {
+                match self.read::<BTreePage>(page_id, s).await {
+                    Some(BTreePage::Internal(node)) => {
+                        for ptr in node.ptrs {
+                            if self.claim(ptr, s) {
+                                next_level.push(ptr);
+                            } else {
+                                complete = false;
+                            }
+                        }
+                    }
+                    Some(BTreePage::Leaf(leaf)) => leaves.push(leaf),
+                    None => complete = false,
+                }
+            }
+            if !leaves.is_empty() && !next_level.is_empty() {
+                self.report(root, s, ProblemKind::UnbalancedTree);
+                complete = false;
+            }
+            if complete {
+                self.check_sibling_links(&leaves, s);
+            }
+            level = next_level;
+        }
+    }
+
+    /// Checks that each of the given leaves (of a whole level, in order) points
+    /// to the adjacent ones.
+    fn check_sibling_links(&mut self, leaves: &[BTreeLeafPage], s: usize) {
+        for (i, leaf) in leaves.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|i| leaves[i].id);
+            let next = leaves.get(i + 1).map(|leaf| leaf.id);
+            if leaf.prev != prev || leaf.next != next {
+                self.report(leaf.id, s, ProblemKind::BrokenSiblingLink);
+            }
+        }
+    }
+
+    /// Checks the free list which starts at the given page, whose length
+    /// should be the given one.
+    async fn check_free_list(&mut self, mut next: Option<PageId>, expected: u32, s: usize) {
+        let mut count = 0;
+        while let Some(page_id) = next {
+            if !self.claim(page_id, s) {
+                return;
+            }
+            let Some(page) = self.read::<FreeListPage>(page_id, s).await else {
+                return;
+            };
+            count += 1;
+            next = page.next_page_id;
+        }
+        if count != expected {
+            let kind = ProblemKind::FreePageCountMismatch {
+                expected,
+                actual: count,
+            };
+            self.report(PageId::FIRST, s, kind);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Embedded key-value storage.
+//!
+//! Each key-value namespace (see [`Db::kv`]) is stored in a dedicated table,
+//! with a `key` and a `value` blob column, along with an index over the keys.
+//! Both are created by the first write to the namespace. Hence, applications
+//! may store plain key-value pairs without defining any schema, while the
+//! pairs go through the same record and index machinery as the records of any
+//! other table.
+//!
+//! Each operation is executed as a single statement (see [`Db::execute`]), so
+//! that, for instance, a [`Kv::put`] never races with another one for the same
+//! key.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    mem,
+    ops::Bound,
+};
+
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        column::Column,
+        object::{Object, TableObject},
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{
+        operations::index::BTree,
+        query::{self, describe_range, Plan, Query},
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+/// The prefix of the names of the namespace tables.
+pub const TABLE_PREFIX: &str = "__kv_";
+
+/// The key column of the namespace tables.
+const KEY: &str = "key";
+
+/// The value column of the namespace tables.
+const VALUE: &str = "value";
+
+/// A handle to a key-value namespace. See [`Db::kv`].
+pub struct Kv<'a> {
+    db: &'a Db,
+    namespace: String,
+    table: String,
+    index: String,
+}
+
+impl<'a> Kv<'a> {
+    /// Creates a new handle to the given namespace.
+    pub(crate) fn new(db: &'a Db, namespace: impl Into<String>) -> Kv<'a> {
+        let namespace = namespace.into();
+        let table = format!("{TABLE_PREFIX}{namespace}");
+        let index = format!("{table}_{KEY}");
+        Kv {
+            db,
+            namespace,
+            table,
+            index,
+        }
+    }
+
+    /// Returns the namespace name.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Returns the value of the given key, if any.
+    pub async fn get(&self, key: &[u8]) -> DbResult<Option<Vec<u8>>> {
+        let bound = Value::Blob(key.to_vec());
+        let mut value = None;
+        let scan = Scan::new(self, Bound::Included(bound.clone()), Bound::Included(bound));
+        self.db
+            .execute(scan, |(_, v)| {
+                value = Some(v);
+                Ok::<_, ()>(())
+            })
+            .await?
+            .unwrap();
+        Ok(value)
+    }
+
+    /// Sets the value of the given key, replacing the previous one (if any).
+    /// The namespace is created if it doesn't exist yet.
+    pub async fn put(&self, key: &[u8], value: &[u8]) -> DbResult<()> {
+        let put = Put {
+            kv: self,
+            key: key.to_vec(),
+            value: value.to_vec(),
+        };
+        self.db.execute(put, |_| Ok::<_, ()>(())).await?.unwrap();
+        Ok(())
+    }
+
+    /// Deletes the given key. Returns whether it existed.
+    pub async fn delete(&self, key: &[u8]) -> DbResult<bool> {
+        let delete = Delete {
+            kv: self,
+            key: key.to_vec(),
+            done: false,
+        };
+        let mut deleted = false;
+        self.db
+            .execute(delete, |()| {
+                deleted = true;
+                Ok::<_, ()>(())
+            })
+            .await?
+            .unwrap();
+        Ok(deleted)
+    }
+
+    /// Returns the pairs whose keys start with the given prefix, in key order.
+    /// An empty prefix returns all pairs.
+    pub async fn scan_prefix(&self, prefix: &[u8]) -> DbResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        let start = Bound::Included(Value::Blob(prefix.to_vec()));
+        let scan = Scan::new(self, start, prefix_end(prefix));
+        let mut pairs = Vec::new();
+        self.db
+            .execute(scan, |pair| {
+                pairs.push(pair);
+                Ok::<_, ()>(())
+            })
+            .await?
+            .unwrap();
+        Ok(pairs)
+    }
+
+    /// Returns the namespace table and its key index, if the namespace exists.
+    async fn find(&self, db: &Db) -> DbResult<Option<(TableObject, BTree)>> {
+        let table = match Object::find(db, &self.table).await {
+            Ok(object) => object.try_into_table()?,
+            // `find` fails with an execution error if there is no such object.
+            Err(Error::ExecError(_)) => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let index = Object::find(db, &self.index).await?.try_into_index()?;
+        Ok(Some((table, BTree::new(index.page_id))))
+    }
+
+    /// Returns the namespace table and its key index, creating them if the
+    /// namespace doesn't exist yet.
+    async fn find_or_create(&self, db: &Db) -> DbResult<(TableObject, BTree)> {
+        if let Some(found) = self.find(db).await? {
+            return Ok(found);
+        }
+        debug!(namespace = self.namespace, "creating key-value namespace");
+        let column = |name: &str| Column {
+            ty: TypeId::Primitive(PrimitiveTypeId::Blob),
+            name: name.into(),
+            max_len: None,
+            default: None,
+            not_null: false,
+        };
+        let schema = TableSchema {
+            columns: vec![column(KEY), column(VALUE)],
+        };
+        query::object::CreateTable::new(&self.table, schema)
+            .next(db)
+            .await?;
+        query::index::Create::new(&self.index, &self.table, KEY)
+            .next(db)
+            .await?;
+        Ok(self.find(db).await?.expect("namespace was created"))
+    }
+}
+
+/// Returns the (exclusive) upper bound of the keys which start with the given
+/// prefix, i.e., the prefix with its last non-maximum byte incremented.
+fn prefix_end(prefix: &[u8]) -> Bound<Value> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Bound::Excluded(Value::Blob(end));
+        }
+    }
+    Bound::Unbounded
+}
+
+/// Extracts the key and the value of the given namespace record values.
+fn pair(mut values: Values) -> (Vec<u8>, Vec<u8>) {
+    let mut take = |name| match values.remove(name) {
+        Some(Value::Blob(bytes)) => bytes,
+        value => unreachable!("expected blob column, got {value:?}"),
+    };
+    (take(KEY), take(VALUE))
+}
+
+/// Yields the pairs whose keys are within the given bounds, in key order.
+struct Scan<'k> {
+    kv: &'k Kv<'k>,
+    start: Bound<Value>,
+    end: Bound<Value>,
+    pairs: Option<VecDeque<(Vec<u8>, Vec<u8>)>>,
+}
+
+impl<'k> Scan<'k> {
+    fn new(kv: &'k Kv<'k>, start: Bound<Value>, end: Bound<Value>) -> Scan<'k> {
+        Scan {
+            kv,
+            start,
+            end,
+            pairs: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Query for Scan<'_> {
+    type Item<'a> = (Vec<u8>, Vec<u8>);
+
+    const READ_ONLY: bool = true;
+
+    #[instrument(name = "KvScan", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.pairs.is_none() {
+            let mut pairs = VecDeque::new();
+            if let Some((table, tree)) = self.kv.find(db).await? {
+                let start = self.start.clone();
+                let end = self.end.clone();
+                let mut scan = query::table::IndexScan::new(&table, tree, start, end);
+                while let Some(record) = scan.next(db).await? {
+                    if record.is_deleted() {
+                        continue;
+                    }
+                    let values = record.into_data().into_owned().into_values(&table.schema);
+                    pairs.push_back(pair(values));
+                }
+            }
+            self.pairs = Some(pairs);
+        }
+        Ok(self.pairs.as_mut().expect("loaded above").pop_front())
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("KvScan")
+            .with("namespace", &self.kv.namespace)
+            .with("range", describe_range(&self.start, &self.end)))
+    }
+}
+
+/// Sets the value of a key. Yields nothing.
+struct Put<'k> {
+    kv: &'k Kv<'k>,
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+#[async_trait]
+impl Query for Put<'_> {
+    type Item<'a> = ();
+
+    #[instrument(name = "KvPut", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let (table, tree) = self.kv.find_or_create(db).await?;
+
+        let key = Value::Blob(self.key.clone());
+        let bound = || Bound::Included(key.clone());
+        let mut scan = query::table::IndexScan::new(&table, tree, bound(), bound());
+        let mut existing = None;
+        while let Some(record) = scan.next(db).await? {
+            if !record.is_deleted() {
+                existing = Some(
+                    query::table::RecordId::current(db, record.page_id(), record.offset()).await?,
+                );
+                break;
+            }
+        }
+
+        let value = Value::Blob(self.value.clone());
+        match existing {
+            Some(id) => {
+                let updater = move |values: &mut Values| values.set(VALUE.into(), value.clone());
+                query::table::UpdateById::new(&table, id, &updater)
+                    .next(db)
+                    .await?;
+            }
+            None => {
+                let values =
+                    Values::from(HashMap::from([(KEY.into(), key), (VALUE.into(), value)]));
+                query::table::Insert::new(&table, values).next(db).await?;
+            }
+        }
+        Ok(None)
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("KvPut").with("namespace", &self.kv.namespace))
+    }
+}
+
+/// Deletes a key. Yields once if it existed.
+struct Delete<'k> {
+    kv: &'k Kv<'k>,
+    key: Vec<u8>,
+    done: bool,
+}
+
+#[async_trait]
+impl Query for Delete<'_> {
+    type Item<'a> = ();
+
+    #[instrument(name = "KvDelete", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        let Some((table, tree)) = self.kv.find(db).await? else {
+            return Ok(None);
+        };
+        let key = Value::Blob(mem::take(&mut self.key));
+        let bound = || Bound::Included(key.clone());
+        let mut scan = query::table::IndexScan::new(&table, tree, bound(), bound());
+        while let Some(record) = scan.next(db).await? {
+            if record.is_deleted() {
+                continue;
+            }
+            let id = query::table::RecordId::current(db, record.page_id(), record.offset()).await?;
+            return query::table::DeleteById::new(&table, id).next(db).await;
+        }
+        Ok(None)
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("KvDelete").with("namespace", &self.kv.namespace))
+    }
+}
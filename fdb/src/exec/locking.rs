@@ -0,0 +1,262 @@
+//! Record locks.
+//!
+//! Queries which modify records (e.g., [`Update`] and [`Delete`]) take an
+//! exclusive lock on each record before writing it, through their own
+//! [`LockSet`], and hold it until they are dropped (i.e., until the statement
+//! finishes). Hence, two statements which modify the same record serialize on
+//! it, rather than on the latches of the pages they happen to share. Shared
+//! locks may be held by many owners at once, but not along with an exclusive
+//! one.
+//!
+//! Write statements are still executed one at a time (see [`Db::execute`]),
+//! hence their locks only conflict with the ones held by other owners, e.g.,
+//! created through [`Db::locks`].
+//!
+//! Locks are keyed by the records' locations (see [`RecordId`]), hence a
+//! relocated record is locked under its new location.
+//!
+//! An owner waiting for a lock waits for its holders, which are tracked in a
+//! wait-for graph. If a request would close a cycle in the graph, it fails with
+//! [`Error::Deadlock`] instead of waiting, which aborts the requesting
+//! statement.
+//!
+//! However, write statements wait while holding the statement latch, and the
+//! owners waiting for the latch (e.g., one which executes a statement before
+//! releasing its locks) aren't in the graph. Hence, requests also fail with
+//! [`Error::Deadlock`] once they waited for longer than the lock timeout (see
+//! [`DbOptions::lock_timeout`]).
+//!
+//! [`Db::execute`]: crate::Db::execute
+//! [`DbOptions::lock_timeout`]: crate::DbOptions::lock_timeout
+//! [`Db::locks`]: crate::Db::locks
+//! [`Update`]: crate::exec::query::table::Update
+//! [`Delete`]: crate::exec::query::table::Delete
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as SyncMutex,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::Notify,
+    time::{self, Instant},
+};
+use tracing::{debug, trace};
+
+use crate::{
+    error::{DbResult, Error},
+    exec::query::table::RecordId,
+};
+
+/// The mode of a record lock.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LockMode {
+    /// May be held by many owners at once.
+    Shared,
+    /// May only be held by a single owner.
+    Exclusive,
+}
+
+/// The default lock timeout. See [`DbOptions::lock_timeout`].
+///
+/// [`DbOptions::lock_timeout`]: crate::DbOptions::lock_timeout
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The record lock table.
+#[derive(Debug)]
+pub struct LockManager {
+    state: SyncMutex<State>,
+    /// Notified whenever locks are released.
+    released: Notify,
+    next_owner: AtomicU64,
+    /// The maximum time a lock request waits.
+    timeout: Duration,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    locks: HashMap<RecordId, Lock>,
+    /// The owners each waiting owner waits for.
+    waits_for: HashMap<u64, HashSet<u64>>,
+}
+
+#[derive(Debug)]
+struct Lock {
+    mode: LockMode,
+    holders: HashSet<u64>,
+}
+
+impl State {
+    /// Returns the owners (other than the given one) which prevent it from
+    /// taking the given lock, which is granted if there are none.
+    fn try_grant(&mut self, owner: u64, id: RecordId, mode: LockMode) -> HashSet<u64> {
+        let Some(lock) = self.locks.get_mut(&id) else {
+            let holders = HashSet::from([owner]);
+            self.locks.insert(id, Lock { mode, holders });
+            return HashSet::new();
+        };
+        let others: HashSet<u64> = lock
+            .holders
+            .iter()
+            .copied()
+            .filter(|h| *h != owner)
+            .collect();
+        let compatible = match (lock.mode, mode) {
+            (LockMode::Shared, LockMode::Shared) => true,
+            // Held exclusively by the owner, or upgraded if it's the only
+            // holder.
+            _ => others.is_empty(),
+        };
+        if !compatible {
+            return others;
+        }
+        lock.holders.insert(owner);
+        if mode == LockMode::Exclusive {
+            lock.mode = LockMode::Exclusive;
+        }
+        HashSet::new()
+    }
+
+    /// Checks whether the given owner is reachable from the given ones in the
+    /// wait-for graph.
+    fn reaches(&self, from: &HashSet<u64>, owner: u64) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<u64> = from.iter().copied().collect();
+        while let Some(current) = stack.pop() {
+            if current == owner {
+                return true;
+            }
+            if visited.insert(current) {
+                stack.extend(self.waits_for.get(&current).into_iter().flatten());
+            }
+        }
+        false
+    }
+}
+
+impl Default for LockManager {
+    fn default() -> Self {
+        LockManager::new()
+    }
+}
+
+impl LockManager {
+    /// Constructs a new, empty, lock table, with the default timeout.
+    pub fn new() -> LockManager {
+        LockManager::with_timeout(DEFAULT_LOCK_TIMEOUT)
+    }
+
+    /// Constructs a new, empty, lock table, whose requests fail with
+    /// [`Error::Deadlock`] once they waited for the given time.
+    pub fn with_timeout(timeout: Duration) -> LockManager {
+        LockManager {
+            state: SyncMutex::default(),
+            released: Notify::new(),
+            next_owner: AtomicU64::new(0),
+            timeout,
+        }
+    }
+
+    /// Creates a new lock owner, whose locks are released once it's dropped.
+    pub fn owner(self: &Arc<Self>) -> LockSet {
+        LockSet {
+            owner: self.next_owner.fetch_add(1, Ordering::Relaxed),
+            manager: Arc::clone(self),
+            held: Vec::new(),
+        }
+    }
+
+    /// Returns the number of locked records.
+    pub fn locked_count(&self) -> usize {
+        self.state.lock().unwrap().locks.len()
+    }
+
+    /// Returns the number of owners waiting for a lock.
+    pub fn waiting_count(&self) -> usize {
+        self.state.lock().unwrap().waits_for.len()
+    }
+}
+
+/// The record locks held by a single owner (e.g., a statement). They are
+/// released once it's dropped.
+#[derive(Debug)]
+pub struct LockSet {
+    owner: u64,
+    manager: Arc<LockManager>,
+    held: Vec<RecordId>,
+}
+
+impl LockSet {
+    /// Locks the given record in the given mode, waiting for the conflicting
+    /// holders (if any) to release it. Returns whether it had to wait, in
+    /// which case the record may have been changed in the meantime.
+    ///
+    /// Fails with [`Error::Deadlock`] if waiting would deadlock, or once it
+    /// waited for longer than the lock timeout.
+    pub async fn lock(&mut self, id: RecordId, mode: LockMode) -> DbResult<bool> {
+        let manager = &*self.manager;
+        let deadline = Instant::now() + manager.timeout;
+        let mut waited = false;
+        loop {
+            // Created before the state is checked, so that a release in between
+            // isn't missed.
+            let released = manager.released.notified();
+            {
+                let mut state = manager.state.lock().unwrap();
+                let blockers = state.try_grant(self.owner, id, mode);
+                if blockers.is_empty() {
+                    state.waits_for.remove(&self.owner);
+                    if !self.held.contains(&id) {
+                        self.held.push(id);
+                    }
+                    trace!(owner = self.owner, %id, ?mode, "granted record lock");
+                    return Ok(waited);
+                }
+                if state.reaches(&blockers, self.owner) {
+                    state.waits_for.remove(&self.owner);
+                    debug!(owner = self.owner, %id, "deadlock detected");
+                    return Err(Error::Deadlock);
+                }
+                trace!(owner = self.owner, %id, ?blockers, "waiting for record lock");
+                state.waits_for.insert(self.owner, blockers);
+            }
+            if time::timeout_at(deadline, released).await.is_err() {
+                manager.state.lock().unwrap().waits_for.remove(&self.owner);
+                debug!(owner = self.owner, %id, "timed out waiting for record lock");
+                return Err(Error::Deadlock);
+            }
+            waited = true;
+        }
+    }
+
+    /// Returns the number of records locked by this owner.
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Checks whether this owner holds no locks.
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+}
+
+impl Drop for LockSet {
+    fn drop(&mut self) {
+        let mut state = self.manager.state.lock().unwrap();
+        for id in &self.held {
+            if let Some(lock) = state.locks.get_mut(id) {
+                lock.holders.remove(&self.owner);
+                if lock.holders.is_empty() {
+                    state.locks.remove(id);
+                }
+            }
+        }
+        state.waits_for.remove(&self.owner);
+        drop(state);
+        self.manager.released.notify_waiters();
+    }
+}
@@ -0,0 +1,111 @@
+//! Table change notifications.
+//!
+//! Table queries record their changes (see [`Change`]) in the database's
+//! [`ChangeNotifier`]. The changes of a statement are held back until it
+//! succeeds, and then published to the subscribers of each changed table (see
+//! [`Db::subscribe`](crate::Db::subscribe)). The changes of failed statements
+//! are discarded. Within an explicit transaction, they are held back until it
+//! commits. Changes are only recorded for tables with subscribers.
+//!
+//! Notifications are delivered through bounded broadcast channels: a subscriber
+//! which falls more than [`CHANNEL_CAPACITY`] notifications behind misses the
+//! oldest ones (see [`RecvError::Lagged`]).
+//!
+//! [`RecvError::Lagged`]: tokio::sync::broadcast::error::RecvError::Lagged
+
+use std::{collections::HashMap, mem, sync::Mutex};
+
+use tokio::sync::broadcast;
+use tracing::trace;
+
+use crate::exec::query::table::RecordId;
+
+/// The number of notifications buffered for each table.
+pub const CHANNEL_CAPACITY: usize = 1024;
+
+/// A change to a table record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// The name of the changed table.
+    pub table: String,
+    pub kind: ChangeKind,
+    /// The ID of the changed record. For updates which relocate the record,
+    /// it is the ID of the new record.
+    pub record_id: RecordId,
+}
+
+/// The kind of a [`Change`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// The table subscriptions and the changes of the running statement.
+#[derive(Debug, Default)]
+pub struct ChangeNotifier {
+    channels: Mutex<HashMap<String, broadcast::Sender<Change>>>,
+    pending: Mutex<Vec<Change>>,
+}
+
+impl ChangeNotifier {
+    /// Subscribes to the changes of the given table.
+    pub fn subscribe(&self, table: &str) -> broadcast::Receiver<Change> {
+        let mut channels = self.channels.lock().unwrap();
+        match channels.get(table) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(CHANNEL_CAPACITY);
+                channels.insert(table.to_owned(), sender);
+                receiver
+            }
+        }
+    }
+
+    /// Records a change to the given table, if it has subscribers.
+    pub(crate) fn record(&self, table: &str, kind: ChangeKind, record_id: RecordId) {
+        let channels = self.channels.lock().unwrap();
+        if channels.get(table).is_none_or(|s| s.receiver_count() == 0) {
+            return;
+        }
+        self.pending.lock().unwrap().push(Change {
+            table: table.to_owned(),
+            kind,
+            record_id,
+        });
+    }
+
+    /// Publishes the recorded changes.
+    pub(crate) fn publish(&self) {
+        let pending = mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return;
+        }
+        trace!(count = pending.len(), "publishing changes");
+        let mut channels = self.channels.lock().unwrap();
+        for change in pending {
+            if let Some(sender) = channels.get(&change.table) {
+                // Fails if all receivers were dropped in the meantime.
+                let _ = sender.send(change);
+            }
+        }
+        channels.retain(|_, sender| sender.receiver_count() > 0);
+    }
+
+    /// Discards the recorded changes.
+    pub(crate) fn discard(&self) {
+        self.pending.lock().unwrap().clear();
+    }
+
+    /// Returns the number of recorded changes, to discard the ones recorded
+    /// afterwards (see [`ChangeNotifier::discard_since`]).
+    pub(crate) fn mark(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Discards the changes recorded since the given mark.
+    pub(crate) fn discard_since(&self, mark: usize) {
+        self.pending.lock().unwrap().truncate(mark);
+    }
+}
@@ -1,3 +1,5 @@
+use tracing::trace;
+
 use crate::{
     catalog::object::Object,
     error::{DbResult, Error},
@@ -7,10 +9,21 @@ use crate::{
 
 impl Object {
     /// Tries to find the given object from the database.
+    ///
+    /// The objects found are cached, so that subsequent lookups don't scan the
+    /// catalog. See [`catalog_cache`](crate::exec::catalog_cache).
     pub async fn find(db: &Db, name: &str) -> DbResult<Self> {
+        // The version is read before scanning, so that an object read during a
+        // concurrent catalog change isn't cached under the new version.
+        let catalog_version = db.catalog_version();
+        if let Some(object) = db.catalog_cache().get(name, catalog_version) {
+            trace!(name, "found cached object");
+            return Ok(object);
+        }
         let mut query = query::object::Select::new();
         while let Some(object) = query.next(db).await? {
             if object.name == name {
+                db.catalog_cache().insert(catalog_version, &object);
                 return Ok(object);
             }
         }
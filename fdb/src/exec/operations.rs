@@ -1,10 +1,24 @@
 use crate::catalog::page::PageId;
 
 pub mod heap {
+    mod append;
+    pub use append::*;
+
     mod seq_scan;
     pub use seq_scan::*;
+
+    mod release;
+    pub use release::*;
+
+    pub mod span;
 }
 
+pub mod index;
+
+pub mod merge;
+
+pub mod tape;
+
 #[derive(Copy, Clone, Debug)]
 pub struct PhysicalState {
     pub page_id: PageId,
@@ -0,0 +1,119 @@
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::page::{HeapPage, PageId, SpecificPage},
+    error::DbResult,
+    exec::{
+        operations::{heap::span, PhysicalState},
+        util::macros::seq_h,
+    },
+    io::pager::Pager,
+};
+
+/// Appends a record to the heap sequence that starts at the given page,
+/// returning its location.
+///
+/// The record is written by the given closure, which must write exactly `size`
+/// bytes. It goes to the last page of the sequence or, if such page can't
+/// accommodate it, to a new page. Records that don't fit in an empty page span
+/// many pages (see [`span`]).
+///
+/// Callers are responsible for flushing the pager.
+#[instrument(level = "debug", skip_all)]
+pub async fn append<F>(
+    pager: &Pager,
+    first_page_id: PageId,
+    size: u32,
+    serialize: F,
+) -> DbResult<PhysicalState>
+where
+    F: for<'a> Fn(&mut buff::Buff<'a>) -> DbResult<()>,
+{
+    debug!(?first_page_id, "getting page");
+    let guard = pager.get::<HeapPage>(first_page_id).await?;
+    let mut page = guard.write().await;
+    let last_page_id = seq_h!(mut page).last_page_id;
+
+    let written = if last_page_id != first_page_id {
+        // If there are more than one page in the heap sequence, one must
+        // write into the last page in the sequence.
+        debug!(?last_page_id, "getting last page");
+        let last_guard = pager.get::<HeapPage>(last_page_id).await?;
+        let mut last = last_guard.write().await;
+
+        let written = write(pager, &mut last, size, &serialize).await?;
+        last.flush();
+        written
+    } else {
+        // Otherwise, one is in the first page.
+        write(pager, &mut page, size, &serialize).await?
+    };
+
+    seq_h!(mut page).record_count += 1;
+    seq_h!(mut page).last_page_id = written.last_page_id;
+    seq_h!(mut page).page_count += written.new_page_count;
+
+    page.flush();
+
+    Ok(written.location)
+}
+
+/// Writes a record to the given (last) page, allocating a new page if it can't
+/// accommodate the record. See [`append`].
+async fn write<F>(
+    pager: &Pager,
+    page: &mut HeapPage,
+    size: u32,
+    serialize: &F,
+) -> DbResult<span::Written>
+where
+    F: for<'a> Fn(&mut buff::Buff<'a>) -> DbResult<()>,
+{
+    if page.can_accommodate(size) {
+        debug!("fit right in");
+        let location = PhysicalState {
+            page_id: page.id(),
+            offset: page.offset(),
+        };
+        page.write(|buf| serialize(buf))?;
+        page.header.record_count += 1;
+
+        return Ok(span::Written {
+            location,
+            last_page_id: page.id(),
+            new_page_count: 0,
+        });
+    }
+
+    let capacity = HeapPage::new_seq_node(pager.usable_size(), PageId::FIRST).free_space();
+    if size > capacity {
+        let mut payload = vec![0; size as usize];
+        serialize(&mut buff::Buff::new(&mut payload))?;
+        return span::write(pager, page, &payload).await;
+    }
+
+    // If the given page can't accommodate the given record, one must allocate a
+    // new page.
+    debug!("allocating new page to append");
+    let new_page_guard = pager.alloc(HeapPage::new_seq_node).await?;
+    let mut new_page = new_page_guard.write().await;
+    let new_page_id = new_page.id();
+
+    let offset = new_page.offset();
+    new_page.write(|buf| serialize(buf))?;
+    new_page.header.record_count += 1;
+
+    // Links the new page.
+    page.header.next_page_id = Some(new_page_id);
+
+    new_page.flush();
+
+    Ok(span::Written {
+        location: PhysicalState {
+            page_id: new_page_id,
+            offset,
+        },
+        last_page_id: new_page_id,
+        new_page_count: 1,
+    })
+}
@@ -0,0 +1,48 @@
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::page::{HeapPage, PageId},
+    error::DbResult,
+    exec::util::macros::seq_h,
+    io::pager::Pager,
+};
+
+/// Returns the IDs of the pages of the heap sequence that starts at the given
+/// page, in sequence order.
+pub async fn page_ids(pager: &Pager, first_page_id: PageId) -> DbResult<Vec<PageId>> {
+    let page_count = pager
+        .read_with(first_page_id, |page: &HeapPage| seq_h!(page).page_count)
+        .await?;
+
+    // Notice that the sequence is traversed using the page count, since the
+    // last page's `next_page_id` is not guaranteed to be null.
+    let mut page_ids = Vec::with_capacity(page_count as usize);
+    let mut page_id = first_page_id;
+    for _ in 0..page_count {
+        page_ids.push(page_id);
+        if let Some(next) = pager
+            .read_with(page_id, |page: &HeapPage| page.header.next_page_id)
+            .await?
+        {
+            page_id = next;
+        }
+    }
+    Ok(page_ids)
+}
+
+/// Moves all pages of the heap sequence that starts at the given page to the
+/// free list. Returns the number of released pages.
+///
+/// Callers must guarantee that the sequence is no longer referenced (e.g., by
+/// the database schema) and that there are no alive guards to its pages.
+#[instrument(level = "debug", skip_all)]
+pub async fn release(pager: &Pager, first_page_id: PageId) -> DbResult<u32> {
+    let page_ids = page_ids(pager, first_page_id).await?;
+    let page_count = page_ids.len() as u32;
+
+    debug!(?first_page_id, page_count, "releasing heap sequence pages");
+    for page_id in page_ids {
+        pager.dealloc(page_id).await?;
+    }
+    Ok(page_count)
+}
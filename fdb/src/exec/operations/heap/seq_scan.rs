@@ -4,14 +4,21 @@ use tracing::{instrument, trace};
 
 use crate::{
     catalog::page::{HeapPage, PageId, SpecificPage},
-    error::DbResult,
-    exec::{operations::PhysicalState, util::macros::get_or_insert_with},
-    util::io::Size,
+    error::{DbResult, ErrorContext, ResultExt},
+    exec::{
+        operations::{
+            heap::span::{self, Spannable},
+            PhysicalState,
+        },
+        sample::PageSample,
+        util::macros::get_or_insert_with,
+    },
     Db,
 };
 
 pub struct SeqScan<T> {
     first_page_id: PageId,
+    sample: Option<PageSample>,
     state: Option<State>,
     _type: PhantomData<T>,
 }
@@ -29,24 +36,30 @@ impl<T> SeqScan<T> {
     pub fn new(first_page_id: PageId) -> Self {
         SeqScan {
             first_page_id,
+            sample: None,
             state: None,
             _type: PhantomData,
         }
     }
 
+    /// Only scans the pages included in the given sample. The records of the
+    /// other pages are skipped without being deserialized.
+    pub fn with_sample(mut self, sample: PageSample) -> Self {
+        self.sample = Some(sample);
+        self
+    }
+
     /// Returns the current element and advances the underlying iterator.
     pub async fn next<De>(&mut self, db: &Db, deserializer: De) -> DbResult<Option<T>>
     where
-        De: Fn(&mut buff::Buff, PhysicalState) -> DbResult<T>,
-        T: Size,
+        De: Fn(&mut buff::BuffRead, PhysicalState) -> DbResult<T>,
+        T: Spannable,
     {
-        let (state, maybe_record) = self.load(db, deserializer).await?;
-        if let Some(record) = &maybe_record {
-            state.offset += record.size() as u16;
-            state.rem_total -= 1;
-            state.rem_page -= 1;
-        }
-        Ok(maybe_record)
+        let maybe_loaded = self.load(db, deserializer).await?;
+        Ok(maybe_loaded.map(|(state, record, size)| {
+            state.advance(1, size);
+            record
+        }))
     }
 
     /// Returns the current element without advancing the underlying iterator.
@@ -55,26 +68,138 @@ impl<T> SeqScan<T> {
     /// underlying database pager.
     pub async fn peek<De>(&mut self, db: &Db, deserializer: De) -> DbResult<Option<T>>
     where
-        De: Fn(&mut buff::Buff, PhysicalState) -> DbResult<T>,
+        De: Fn(&mut buff::BuffRead, PhysicalState) -> DbResult<T>,
+        T: Spannable,
     {
-        self.load(db, deserializer)
-            .await
-            .map(|(_, maybe_record)| maybe_record)
+        let maybe_loaded = self.load(db, deserializer).await?;
+        Ok(maybe_loaded.map(|(_, record, _)| record))
     }
 
-    /// Load record implementation. Though it changes the state on page
-    /// switches, it doesn't advance the record counters when a record is
-    /// deserialized.
+    /// Returns (at most) the next `n` elements of the current page and advances
+    /// the underlying iterator past them. Unlike calling [`SeqScan::next`] `n`
+    /// times, the page is read (and its contents copied) only once.
+    ///
+    /// Batches never cross page boundaries, hence they may be shorter than `n`
+    /// even if the sequence isn't exhausted. Spanned records, whose fragments
+    /// must be assembled from other pages, are returned in batches of their
+    /// own. An empty batch means that the sequence is exhausted (unless `n` is
+    /// zero).
     #[instrument(level = "debug", skip_all)]
-    async fn load<De>(&mut self, db: &Db, deserializer: De) -> DbResult<(&mut State, Option<T>)>
+    pub async fn next_batch<De>(&mut self, db: &Db, n: usize, deserializer: De) -> DbResult<Vec<T>>
+    where
+        De: Fn(&mut buff::BuffRead, PhysicalState) -> DbResult<T>,
+        T: Spannable,
+    {
+        let Some(state) = self.position(db).await? else {
+            return Ok(Vec::new());
+        };
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+
+        let page_id = state.page_id;
+        let start = state.offset;
+        let count = n.min(usize::from(state.rem_page));
+        let (batch, end) = db
+            .pager()
+            .read_with(page_id, |page: &HeapPage| {
+                page.read_at(start, |buf| {
+                    let mut batch = Vec::with_capacity(count);
+                    let mut offset = start;
+                    while batch.len() < count && !span::is_head(page, offset) {
+                        buf.seek(usize::from(offset - start));
+                        let state = PhysicalState { page_id, offset };
+                        let record = deserializer(buf, state)
+                            .context(ErrorContext::Record { page_id, offset })?;
+                        offset += record.size() as u16;
+                        batch.push(record);
+                    }
+                    Ok((batch, offset))
+                })
+            })
+            .await??;
+
+        if batch.is_empty() {
+            trace!("reading spanned record on its own");
+            let state = PhysicalState {
+                page_id,
+                offset: start,
+            };
+            let (record, size) =
+                span::read(db, state, deserializer)
+                    .await
+                    .context(ErrorContext::Record {
+                        page_id,
+                        offset: start,
+                    })?;
+            self.advance(1, size);
+            return Ok(vec![record]);
+        }
+        trace!(len = batch.len(), "deserialized batch");
+        self.advance(batch.len() as u16, end - start);
+        Ok(batch)
+    }
+
+    /// Moves the cursor `delta` bytes back in the current page. Used when the
+    /// region behind the cursor has been shrunk (see [`HeapPage::reclaim`]).
+    pub fn rewind(&mut self, delta: u16) {
+        if let Some(state) = &mut self.state {
+            // If the current page was exhausted, the next `position` resets
+            // the offset; there is nothing to rewind in such a case.
+            if state.rem_page != 0 {
+                state.offset -= delta;
+            }
+        }
+    }
+
+    /// Advances the iterator past the given number of records of the current
+    /// page, which take the given number of bytes.
+    fn advance(&mut self, count: u16, size: u16) {
+        let state = self.state.as_mut().expect("positioned");
+        state.advance(count, size);
+    }
+
+    /// Load record implementation. Doesn't advance the record counters when a
+    /// record is deserialized. Records are returned along with the state and
+    /// their size in the page (see [`span::read`]).
+    async fn load<De>(
+        &mut self,
+        db: &Db,
+        deserializer: De,
+    ) -> DbResult<Option<(&mut State, T, u16)>>
     where
-        De: Fn(&mut buff::Buff, PhysicalState) -> DbResult<T>,
+        De: Fn(&mut buff::BuffRead, PhysicalState) -> DbResult<T>,
+        T: Spannable,
     {
+        let Some(state) = self.position(db).await? else {
+            return Ok(None);
+        };
+
+        trace!("deserializing record using provided deserializer");
+        let physical_state = PhysicalState {
+            page_id: state.page_id,
+            offset: state.offset,
+        };
+        let (record, size) =
+            span::read(db, physical_state, deserializer)
+                .await
+                .context(ErrorContext::Record {
+                    page_id: physical_state.page_id,
+                    offset: physical_state.offset,
+                })?;
+        Ok(Some((state, record, size)))
+    }
+
+    /// Moves the cursor to the next record, switching pages if needed. Returns
+    /// `None` once the sequence is exhausted.
+    #[instrument(level = "debug", skip_all)]
+    async fn position(&mut self, db: &Db) -> DbResult<Option<&mut State>> {
         let state = get_or_insert_with!(&mut self.state, || {
             let first_page_id = self.first_page_id;
             trace!(?first_page_id, "loading first page of sequence");
 
-            db.pager()
+            let state = db
+                .pager()
                 .read_with(first_page_id, |page: &HeapPage| {
                     let seq_header = page.header.seq_header.as_ref().expect("first seq page");
 
@@ -86,15 +211,29 @@ impl<T> SeqScan<T> {
                         offset: page.first_offset(),
                     }
                 })
-                .await?
+                .await?;
+            state.read_ahead(db);
+            state
         });
 
-        if state.rem_total == 0 {
-            trace!("no more entries in sequence, done");
-            return Ok((state, None));
-        }
-
-        if state.rem_page == 0 {
+        // Notice that a page may be empty (e.g., after a vacuum).
+        loop {
+            if state.rem_total == 0 {
+                trace!("no more entries in sequence, done");
+                return Ok(None);
+            }
+            if state.rem_page != 0 {
+                if self
+                    .sample
+                    .is_none_or(|sample| sample.includes(state.page_id))
+                {
+                    return Ok(Some(state));
+                }
+                trace!(page_id = ?state.page_id, "skipping page out of sample");
+                state.rem_total -= u64::from(state.rem_page);
+                state.rem_page = 0;
+                continue;
+            }
             let next_page_id = state.next_page_id.expect("must have +1");
             trace!(?next_page_id, "loading next page of sequence");
             db.pager()
@@ -105,22 +244,26 @@ impl<T> SeqScan<T> {
                     state.offset = page.first_offset();
                 })
                 .await?;
+            state.read_ahead(db);
         }
+    }
+}
 
-        trace!("deserializing record using provided deserializer");
-        let physical_state = PhysicalState {
-            page_id: state.page_id,
-            offset: state.offset,
-        };
-        let record = db
-            .pager()
-            .read_with(state.page_id, |page: &HeapPage| {
-                page.read_at(state.offset, |buf| {
-                    // Deserializes the record:
-                    deserializer(buf, physical_state)
-                })
-            })
-            .await??;
-        Ok((state, Some(record)))
+impl State {
+    fn advance(&mut self, count: u16, size: u16) {
+        self.offset += size;
+        self.rem_total -= u64::from(count);
+        self.rem_page -= count;
+    }
+
+    /// Prefetches the next page of the sequence (see [`Pager::prefetch`]) while
+    /// the current one is read, unless the current page is the last one with
+    /// records.
+    ///
+    /// [`Pager::prefetch`]: crate::io::pager::Pager::prefetch
+    fn read_ahead(&self, db: &Db) {
+        if self.rem_total > u64::from(self.rem_page) {
+            db.pager().prefetch(self.next_page_id);
+        }
     }
 }
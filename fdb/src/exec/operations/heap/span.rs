@@ -0,0 +1,301 @@
+//! Records that span many heap pages.
+//!
+//! A record which doesn't fit in an empty heap page is split into fragments.
+//! The first one, the *head*, is stored in the heap page like any other record
+//! (hence, it is the one addressed by the record ID), and it is laid out as:
+//!
+//! ```text
+//! +-----------+-----------+-------------+-------------------+
+//! | head size | flags     | payload len | payload fragment  |
+//! | (u16)     | (u8)      | (u16)       | (head size - 5)   |
+//! +-----------+-----------+-------------+-------------------+
+//! ```
+//!
+//! Where `flags` holds the [`SPANNED`] bit (and the [`DELETED`] bit, if the
+//! record was deleted) and the payload is the whole record, serialized as if it
+//! wasn't spanned. The two first fields of the head overlap the ones of a
+//! regular record, so that sequential scans may skip it.
+//!
+//! The head takes all the free space of its page and the following fragments
+//! are stored in *fragment pages*, which directly follow the head page in the
+//! heap sequence. Fragment pages hold no records and are laid out as a `u16`
+//! fragment length, followed by the fragment itself. See
+//! [`HeapPage::is_fragment`].
+
+use tracing::{debug, trace};
+
+use crate::{
+    catalog::{
+        page::{HeapPage, PageId, SpecificPage},
+        record::simple_record::SimpleRecord,
+    },
+    error::{DbResult, Error},
+    exec::operations::PhysicalState,
+    io::pager::Pager,
+    util::io::Size,
+    Db,
+};
+
+/// The flags bit which signals that the record was deleted. Regular records
+/// store the deleted flag as a boolean in the same byte.
+pub const DELETED: u8 = 0b01;
+
+/// The flags bit which signals that the record spans many pages.
+pub const SPANNED: u8 = 0b10;
+
+/// The maximum size of a (serialized) record.
+pub const MAX_RECORD_SIZE: u32 = u16::MAX as u32;
+
+/// The size of the head fields that precede its payload fragment.
+pub const HEAD_HEADER_SIZE: u16 = 2 + 1 + 2;
+
+/// The size of the fragment page fields that precede its fragment.
+const FRAGMENT_HEADER_SIZE: u16 = 2;
+
+/// The minimum payload fragment stored in a head. If the page has less free
+/// space, the head is written to a new page.
+const MIN_HEAD_FRAGMENT: u16 = 16;
+
+/// A record which may be read from a spanned record.
+pub trait Spannable: Size {
+    /// Marks the record as spanned, i.e., as a record which may only be written
+    /// through this module.
+    fn set_spanned(&mut self);
+}
+
+impl<D: Size + Clone> Spannable for SimpleRecord<'_, D> {
+    fn set_spanned(&mut self) {
+        SimpleRecord::set_spanned(self);
+    }
+}
+
+/// A parsed head.
+pub(crate) struct Head {
+    /// The size of the head in its page.
+    pub size: u16,
+    pub is_deleted: bool,
+    /// The payload fragment in the head.
+    payload: Vec<u8>,
+    payload_len: u16,
+    /// The first fragment page.
+    next_page_id: Option<PageId>,
+}
+
+/// Checks whether the record at the given offset is spanned, i.e., whether it
+/// is a head.
+pub(crate) fn is_head(page: &HeapPage, offset: u16) -> bool {
+    page.bytes[offset as usize + 2] & SPANNED != 0
+}
+
+/// Parses the head at the given offset, if the record is spanned.
+pub(crate) fn parse_head(page: &HeapPage, offset: u16) -> DbResult<Option<Head>> {
+    if !is_head(page, offset) {
+        return Ok(None);
+    }
+    let flags = page.bytes[offset as usize + 2];
+    let head = page.read_at(offset, |buf| {
+        let size: u16 = buf.read();
+        buf.seek_advance(1);
+        let payload_len: u16 = buf.read();
+        let payload = buf.read_bytes((size - HEAD_HEADER_SIZE) as usize).to_vec();
+        Ok(Head {
+            size,
+            is_deleted: flags & DELETED != 0,
+            payload,
+            payload_len,
+            next_page_id: page.header.next_page_id,
+        })
+    })?;
+    Ok(Some(head))
+}
+
+/// Reads the fragment pages of the given head, returning the whole record
+/// payload and the IDs of the fragment pages.
+pub(crate) async fn assemble(db: &Db, head: Head) -> DbResult<(Vec<u8>, Vec<PageId>)> {
+    let Head {
+        is_deleted,
+        mut payload,
+        payload_len,
+        mut next_page_id,
+        ..
+    } = head;
+    let mut fragment_page_ids = Vec::new();
+    while payload.len() < payload_len as usize {
+        let page_id = next_page_id
+            .ok_or_else(|| Error::ExecError("corrupted record: missing fragment page".into()))?;
+        next_page_id = db
+            .pager()
+            .read_with(page_id, |page: &HeapPage| {
+                if !page.is_fragment() {
+                    return Err(Error::ExecError(format!(
+                        "corrupted record: page {} isn't a fragment page",
+                        page_id.get()
+                    )));
+                }
+                page.read_at(page.first_offset(), |buf| {
+                    let len: u16 = buf.read();
+                    payload.extend_from_slice(buf.read_bytes(len as usize));
+                    Ok(())
+                })?;
+                Ok(page.header.next_page_id)
+            })
+            .await??;
+        fragment_page_ids.push(page_id);
+    }
+    if payload.len() != payload_len as usize {
+        return Err(Error::ExecError("corrupted record: payload overrun".into()));
+    }
+    // The payload keeps the flags as they were when the record was written.
+    payload[2] = is_deleted as u8;
+    trace!(
+        len = payload.len(),
+        fragments = fragment_page_ids.len(),
+        "assembled record"
+    );
+    Ok((payload, fragment_page_ids))
+}
+
+/// Reads the record at the given location (which must be within the page's
+/// records region), assembling it if it is spanned. Returns the record and its
+/// size in the page, which is the size of its head if it is spanned.
+pub(crate) async fn read<T, De>(
+    db: &Db,
+    state: PhysicalState,
+    deserializer: De,
+) -> DbResult<(T, u16)>
+where
+    T: Spannable,
+    De: Fn(&mut buff::BuffRead, PhysicalState) -> DbResult<T>,
+{
+    let read = db
+        .pager()
+        .read_with(state.page_id, |page: &HeapPage| {
+            let read = match parse_head(page, state.offset)? {
+                Some(head) => Err(head),
+                None => {
+                    let record = page.read_at(state.offset, |buf| deserializer(buf, state))?;
+                    let size = record.size() as u16;
+                    Ok((record, size))
+                }
+            };
+            Ok::<_, Error>(read)
+        })
+        .await??;
+    match read {
+        Ok(read) => Ok(read),
+        Err(head) => {
+            let size = head.size;
+            let (payload, _) = assemble(db, head).await?;
+            let mut record = deserializer(&mut buff::BuffRead::new(&payload), state)?;
+            record.set_spanned();
+            Ok((record, size))
+        }
+    }
+}
+
+/// Marks the (spanned) record at the given offset as deleted.
+pub(crate) fn set_deleted(page: &mut HeapPage, offset: u16) {
+    page.bytes[offset as usize + 2] |= DELETED;
+}
+
+/// The result of a spanned write. See [`write`].
+pub(crate) struct Written {
+    /// The location of the head.
+    pub location: PhysicalState,
+    /// The last page written to, which is the new last page of the sequence.
+    pub last_page_id: PageId,
+    /// The number of allocated pages.
+    pub new_page_count: u32,
+}
+
+/// Writes the given serialized record, splitting it into a head and fragment
+/// pages. The head is written to the given (last) page, if it has enough free
+/// space, or to a new page otherwise.
+pub(crate) async fn write(pager: &Pager, page: &mut HeapPage, payload: &[u8]) -> DbResult<Written> {
+    if payload.len() as u32 > MAX_RECORD_SIZE {
+        return Err(Error::ExecError(format!(
+            "record size ({}) exceeds the maximum record size",
+            payload.len()
+        )));
+    }
+    let payload_len = payload.len() as u16;
+    debug!(payload_len, "writing spanned record");
+
+    // The pages written to, in sequence order. The first one is only written
+    // to if it holds the head.
+    let mut guards = Vec::new();
+    let mut pages: Vec<&mut HeapPage> = vec![page];
+    let head_in_page = pages[0].free_space() >= (HEAD_HEADER_SIZE + MIN_HEAD_FRAGMENT) as u32
+        && !pages[0].is_fragment();
+
+    let mut rest = payload;
+    let mut count = (!head_in_page) as u32;
+    {
+        let capacity =
+            HeapPage::new_seq_node(pager.usable_size(), PageId::FIRST).free_space() as usize;
+        let head_capacity = if head_in_page {
+            pages[0].free_space() as usize - HEAD_HEADER_SIZE as usize
+        } else {
+            capacity - HEAD_HEADER_SIZE as usize
+        };
+        let fragment_capacity = capacity - FRAGMENT_HEADER_SIZE as usize;
+        let mut remaining = payload.len().saturating_sub(head_capacity);
+        while remaining > 0 {
+            count += 1;
+            remaining = remaining.saturating_sub(fragment_capacity);
+        }
+    }
+    for guard in pager.alloc_many(count, HeapPage::new_seq_node).await? {
+        guards.push(guard);
+    }
+    let mut new_pages = Vec::with_capacity(guards.len());
+    for guard in &guards {
+        new_pages.push(guard.write().await);
+    }
+    pages.extend(new_pages.iter_mut().map(|page| &mut **page));
+    for i in 1..pages.len() {
+        let next_page_id = pages[i].id();
+        pages[i - 1].header.next_page_id = Some(next_page_id);
+    }
+
+    let head_index = (!head_in_page) as usize;
+    let head = &mut pages[head_index];
+    let location = PhysicalState {
+        page_id: head.id(),
+        offset: head.offset(),
+    };
+    let head_len = (head.free_space() as usize - HEAD_HEADER_SIZE as usize).min(rest.len());
+    let size = HEAD_HEADER_SIZE + head_len as u16;
+    head.write(|buf| {
+        buf.write(size);
+        buf.write(SPANNED);
+        buf.write(payload_len);
+        buf.write_slice(&rest[..head_len]);
+        Ok(())
+    })?;
+    head.header.record_count += 1;
+    rest = &rest[head_len..];
+
+    for fragment_page in &mut pages[head_index + 1..] {
+        let len =
+            (fragment_page.free_space() as usize - FRAGMENT_HEADER_SIZE as usize).min(rest.len());
+        fragment_page.write(|buf| {
+            buf.write(len as u16);
+            buf.write_slice(&rest[..len]);
+            Ok(())
+        })?;
+        rest = &rest[len..];
+    }
+    debug_assert!(rest.is_empty());
+
+    let last_page_id = pages.last().expect("non empty").id();
+    drop(pages);
+    for page in new_pages {
+        page.flush();
+    }
+    Ok(Written {
+        location,
+        last_page_id,
+        new_page_count: count,
+    })
+}
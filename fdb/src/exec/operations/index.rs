@@ -0,0 +1,713 @@
+//! B+Tree index operations.
+
+use std::ops::Bound;
+
+use tracing::{debug, instrument, trace};
+
+use crate::{
+    catalog::page::{BTreeCell, BTreeInternalPage, BTreeLeafPage, BTreePage, PageId, SpecificPage},
+    error::{DbResult, Error},
+    exec::value::Value,
+    io::pager::Pager,
+    util::io::Size,
+};
+
+/// A B+Tree index over [`BTreePage`]s.
+///
+/// The root page never changes: when it splits, its contents are moved to two
+/// new pages; when it is left with a single child, the child contents are moved
+/// back into it. Hence, the root page ID may be used to identify the index
+/// (e.g., in the database schema).
+///
+/// Nodes are split when they don't fit in a page and are merged with (or
+/// borrow cells from) a sibling when they are less than half full.
+///
+/// # Concurrency
+///
+/// Operations latch one page at a time. Hence, callers must guarantee that
+/// modifications are not executed concurrently with other operations over the
+/// same index (e.g., using the statement-level latch of [`Db::execute`]).
+///
+/// [`Db::execute`]: crate::Db::execute
+#[derive(Copy, Clone, Debug)]
+pub struct BTree {
+    root: PageId,
+}
+
+impl BTree {
+    /// Constructs a handle to the index whose root is at the given page.
+    pub fn new(root: PageId) -> BTree {
+        BTree { root }
+    }
+
+    /// Allocates the root page of a new empty index.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn create(pager: &Pager) -> DbResult<BTree> {
+        let root = alloc_id(pager).await?;
+        debug!(?root, "created index");
+        Ok(BTree { root })
+    }
+
+    /// Returns the ID of the root page.
+    pub fn root(&self) -> PageId {
+        self.root
+    }
+
+    /// Builds the (empty) index bottom-up from the given cells, which must be
+    /// sorted and unique. Nodes of each level are allocated in a single batch.
+    ///
+    /// This is much cheaper than inserting each cell, since no node is ever
+    /// split.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn bulk_load(&self, pager: &Pager, cells: Vec<BTreeCell>) -> DbResult<()> {
+        debug_assert!(cells.windows(2).all(|w| w[0] < w[1]));
+        for cell in &cells {
+            check(pager, cell)?;
+        }
+        match load(pager, self.root).await? {
+            BTreePage::Leaf(leaf) if leaf.cells.is_empty() => (),
+            _ => return Err(Error::ExecError("index is not empty".into())),
+        }
+
+        let capacity = pager.usable_size() as u32;
+
+        // Leaves.
+        let sizes = chunk_sizes(&cells, 0);
+        let chunks = pack(&sizes, LEAF_HEADER_SIZE, capacity, 1);
+        if chunks.len() == 1 {
+            return store(pager, leaf_page(self.root, None, None, cells)).await;
+        }
+        let ids = alloc_ids(pager, chunks.len()).await?;
+        let mut level = Vec::with_capacity(chunks.len());
+        let mut cells = cells.into_iter();
+        let mut last: Option<BTreeCell> = None;
+        for (i, len) in chunks.into_iter().enumerate() {
+            let cells: Vec<_> = cells.by_ref().take(len).collect();
+            let separator = match &last {
+                Some(last) => BTreeCell::separator(last, &cells[0]),
+                None => cells[0].clone(),
+            };
+            last = cells.last().cloned();
+            level.push((separator, ids[i]));
+            let prev = i.checked_sub(1).map(|i| ids[i]);
+            let next = ids.get(i + 1).copied();
+            store(pager, leaf_page(ids[i], prev, next, cells)).await?;
+        }
+        debug!(leaves = level.len(), "stored leaves");
+
+        // Internal levels. Each child pointer is accounted along with its
+        // separator key. As the first separator of each node is moved up to the
+        // parent, accounting it (uncompressed) makes up for the second one not
+        // being compressed.
+        loop {
+            let keys: Vec<_> = level.iter().map(|(cell, _)| cell.clone()).collect();
+            let sizes = chunk_sizes(&keys, 4);
+            let chunks = pack(&sizes, INTERNAL_HEADER_SIZE, capacity, 2);
+            let ids = if chunks.len() == 1 {
+                vec![self.root]
+            } else {
+                alloc_ids(pager, chunks.len()).await?
+            };
+
+            let mut next_level = Vec::with_capacity(chunks.len());
+            let mut children = level.into_iter();
+            for (len, id) in chunks.into_iter().zip(ids) {
+                let (mut keys, ptrs): (Vec<_>, Vec<_>) = children.by_ref().take(len).unzip();
+                next_level.push((keys.remove(0), id));
+                store(
+                    pager,
+                    BTreePage::Internal(BTreeInternalPage { id, ptrs, keys }),
+                )
+                .await?;
+            }
+
+            if next_level.len() == 1 {
+                return Ok(());
+            }
+            level = next_level;
+        }
+    }
+
+    /// Inserts the given cell, splitting the nodes that overflow.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn insert(&self, pager: &Pager, cell: BTreeCell) -> DbResult<()> {
+        check(pager, &cell)?;
+
+        let (mut path, mut leaf) = self.find_leaf(pager, &cell).await?;
+        match leaf.cells.binary_search(&cell) {
+            Ok(_) => return Err(Error::ExecError("record is already indexed".into())),
+            Err(pos) => leaf.cells.insert(pos, cell),
+        }
+
+        let mut node = BTreePage::Leaf(leaf);
+        loop {
+            if fits(pager, &node) {
+                return store(pager, node).await;
+            }
+            if node.id() == self.root {
+                return self.split_root(pager, node).await;
+            }
+
+            let (left, separator, right) = split(pager, node).await?;
+            let right_id = right.id();
+            store(pager, left).await?;
+            store(pager, right).await?;
+
+            let (mut parent, index) = path.pop().expect("non-root node must have a parent");
+            parent.keys.insert(index, separator);
+            parent.ptrs.insert(index + 1, right_id);
+            node = BTreePage::Internal(parent);
+        }
+    }
+
+    /// Removes the given cell, merging or rebalancing the nodes that underflow.
+    /// Returns `false` if the cell wasn't indexed.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn delete(&self, pager: &Pager, cell: &BTreeCell) -> DbResult<bool> {
+        let (mut path, mut leaf) = self.find_leaf(pager, cell).await?;
+        let Ok(pos) = leaf.cells.binary_search(cell) else {
+            return Ok(false);
+        };
+        leaf.cells.remove(pos);
+
+        let min_size = pager.usable_size() as u32 / 2;
+        let mut node = BTreePage::Leaf(leaf);
+        loop {
+            if node.id() == self.root {
+                self.store_root(pager, node).await?;
+                return Ok(true);
+            }
+            if node.size() >= min_size {
+                store(pager, node).await?;
+                return Ok(true);
+            }
+
+            let (mut parent, index) = path.pop().expect("non-root node must have a parent");
+            debug_assert!(parent.ptrs.len() > 1);
+            let (left_index, right_index) = if index + 1 < parent.ptrs.len() {
+                (index, index + 1)
+            } else {
+                (index - 1, index)
+            };
+            let (left, right) = if left_index == index {
+                (node, load(pager, parent.ptrs[right_index]).await?)
+            } else {
+                (load(pager, parent.ptrs[left_index]).await?, node)
+            };
+            let separator = parent.keys[left_index].clone();
+
+            match merge(pager, left, separator, right).await? {
+                Merge::Merged(merged) => {
+                    trace!(id = ?merged.id(), "merged nodes");
+                    parent.keys.remove(left_index);
+                    let right_id = parent.ptrs.remove(right_index);
+                    store(pager, merged).await?;
+                    pager.dealloc(right_id).await?;
+                }
+                Merge::Redistributed(left, separator, right) => {
+                    trace!(id = ?left.id(), "redistributed nodes");
+                    parent.keys[left_index] = separator;
+                    store(pager, left).await?;
+                    store(pager, right).await?;
+                }
+            }
+            node = BTreePage::Internal(parent);
+        }
+    }
+
+    /// Returns all cells with the given key.
+    pub async fn search(&self, pager: &Pager, key: &Value) -> DbResult<Vec<BTreeCell>> {
+        self.range(pager, Bound::Included(key), Bound::Included(key))
+            .await
+    }
+
+    /// Returns all cells whose keys are within the given bounds, in order.
+    ///
+    /// Callers must ensure that the given keys have the same type as the
+    /// indexed ones.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn range(
+        &self,
+        pager: &Pager,
+        start: Bound<&Value>,
+        end: Bound<&Value>,
+    ) -> DbResult<Vec<BTreeCell>> {
+        // Pages are inspected in place (rather than loaded into an owned copy),
+        // since lookups over cached pages are dominated by such copies.
+        let mut page_id = self.root;
+        let mut in_leaves = false;
+        let mut cells = Vec::new();
+        loop {
+            let next = pager
+                .read_with(page_id, |page: &BTreePage| match page {
+                    BTreePage::Internal(_) if in_leaves => Err(not_a_leaf(page_id)),
+                    BTreePage::Internal(node) => {
+                        let index = match start {
+                            Bound::Included(key) => {
+                                node.keys.partition_point(|cell| cell.key < *key)
+                            }
+                            Bound::Excluded(key) => {
+                                node.keys.partition_point(|cell| cell.key <= *key)
+                            }
+                            Bound::Unbounded => 0,
+                        };
+                        Ok(Some(node.ptrs[index]))
+                    }
+                    BTreePage::Leaf(leaf) => {
+                        in_leaves = true;
+                        for cell in &leaf.cells {
+                            let after_start = match start {
+                                Bound::Included(key) => cell.key >= *key,
+                                Bound::Excluded(key) => cell.key > *key,
+                                Bound::Unbounded => true,
+                            };
+                            if !after_start {
+                                continue;
+                            }
+                            let before_end = match end {
+                                Bound::Included(key) => cell.key <= *key,
+                                Bound::Excluded(key) => cell.key < *key,
+                                Bound::Unbounded => true,
+                            };
+                            if !before_end {
+                                return Ok(None);
+                            }
+                            cells.push(cell.clone());
+                        }
+                        Ok(leaf.next)
+                    }
+                })
+                .await??;
+            match next {
+                Some(next) => page_id = next,
+                None => return Ok(cells),
+            }
+        }
+    }
+
+    /// Returns the structural statistics of the index. All of its pages are
+    /// visited.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn stats(&self, pager: &Pager) -> DbResult<BTreeStats> {
+        let mut stats = BTreeStats {
+            height: 0,
+            page_count: 0,
+            entries: 0,
+        };
+        // All leaves are at the same depth, so the tree is visited level by
+        // level.
+        let mut level = vec![self.root];
+        while !level.is_empty() {
+            stats.height += 1;
+            stats.page_count += level.len() as u32;
+            let mut next_level = Vec::new();
+            for page_id in level {
+                pager
+                    .read_with(page_id, |page: &BTreePage| match page {
+                        BTreePage::Internal(node) => next_level.extend(&node.ptrs),
+                        BTreePage::Leaf(leaf) => stats.entries += leaf.cells.len() as u64,
+                    })
+                    .await?;
+            }
+            level = next_level;
+        }
+        Ok(stats)
+    }
+
+    /// Returns the height of the index, i.e., the number of pages read by each
+    /// lookup to reach a leaf.
+    pub async fn height(&self, pager: &Pager) -> DbResult<u32> {
+        let mut height = 1;
+        let mut page_id = self.root;
+        while let Some(child) = pager
+            .read_with(page_id, |page: &BTreePage| match page {
+                BTreePage::Internal(node) => Some(node.ptrs[0]),
+                BTreePage::Leaf(_) => None,
+            })
+            .await?
+        {
+            height += 1;
+            page_id = child;
+        }
+        Ok(height)
+    }
+
+    /// Deallocates all pages of the index, including the root. Returns the
+    /// number of deallocated pages.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn release(&self, pager: &Pager) -> DbResult<u32> {
+        let mut count = 0;
+        let mut stack = vec![self.root];
+        while let Some(page_id) = stack.pop() {
+            if let BTreePage::Internal(node) = load(pager, page_id).await? {
+                stack.extend(node.ptrs);
+            }
+            pager.dealloc(page_id).await?;
+            count += 1;
+        }
+        debug!(count, "released index pages");
+        Ok(count)
+    }
+
+    /// Finds the leaf that may contain the given cell, also returning the path
+    /// (internal nodes and the index of the followed pointer) to it.
+    async fn find_leaf(
+        &self,
+        pager: &Pager,
+        cell: &BTreeCell,
+    ) -> DbResult<(Vec<(BTreeInternalPage, usize)>, BTreeLeafPage)> {
+        let mut path = Vec::new();
+        let mut page_id = self.root;
+        loop {
+            match load(pager, page_id).await? {
+                BTreePage::Internal(node) => {
+                    let index = node.child_index(cell);
+                    page_id = node.ptrs[index];
+                    path.push((node, index));
+                }
+                BTreePage::Leaf(leaf) => return Ok((path, leaf)),
+            }
+        }
+    }
+
+    /// Splits the (overflowing) root. Its contents are moved to two new pages,
+    /// which become the root's only children.
+    async fn split_root(&self, pager: &Pager, mut node: BTreePage) -> DbResult<()> {
+        debug!("splitting root");
+        node.set_id(alloc_id(pager).await?);
+        let (left, separator, right) = split(pager, node).await?;
+        let root = BTreePage::Internal(BTreeInternalPage {
+            id: self.root,
+            ptrs: vec![left.id(), right.id()],
+            keys: vec![separator],
+        });
+        store(pager, left).await?;
+        store(pager, right).await?;
+        store(pager, root).await
+    }
+
+    /// Stores the root. If it is left with a single child, the child contents
+    /// are moved into the root, decreasing the tree height.
+    async fn store_root(&self, pager: &Pager, node: BTreePage) -> DbResult<()> {
+        match node {
+            BTreePage::Internal(node) if node.keys.is_empty() => {
+                debug!("collapsing root");
+                let child_id = node.ptrs[0];
+                let mut child = load(pager, child_id).await?;
+                child.set_id(self.root);
+                store(pager, child).await?;
+                pager.dealloc(child_id).await
+            }
+            node => store(pager, node).await,
+        }
+    }
+}
+
+/// The structural statistics of a [`BTree`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BTreeStats {
+    /// The number of levels, including the root and the leaves.
+    pub height: u32,
+    /// The number of pages.
+    pub page_count: u32,
+    /// The number of indexed cells.
+    pub entries: u64,
+}
+
+/// Checks whether the given cell may be indexed, i.e., whether it isn't too
+/// large.
+pub fn check(pager: &Pager, cell: &BTreeCell) -> DbResult<()> {
+    let max_size = max_cell_size(pager.usable_size());
+    if cell.size() > max_size {
+        return Err(Error::ExecError(format!(
+            "index cell size ({}) exceeds the maximum ({max_size})",
+            cell.size()
+        )));
+    }
+    Ok(())
+}
+
+/// The result of a [`merge`].
+enum Merge {
+    Merged(BTreePage),
+    Redistributed(BTreePage, BTreeCell, BTreePage),
+}
+
+/// Splits the given node in two, allocating a page for the right one. Returns
+/// the nodes and the separator key, which must be inserted in the parent.
+async fn split(pager: &Pager, node: BTreePage) -> DbResult<(BTreePage, BTreeCell, BTreePage)> {
+    let right_id = alloc_id(pager).await?;
+    trace!(left = ?node.id(), right = ?right_id, "splitting node");
+
+    match node {
+        BTreePage::Leaf(mut left) => {
+            let at = split_point(&left.cells, 1);
+            let cells = left.cells.split_off(at);
+            if let Some(next) = left.next {
+                set_leaf_prev(pager, next, Some(right_id)).await?;
+            }
+            let right = BTreeLeafPage {
+                id: right_id,
+                prev: Some(left.id),
+                next: left.next,
+                cells,
+            };
+            left.next = Some(right_id);
+            let separator = BTreeCell::separator(left.cells.last().unwrap(), &right.cells[0]);
+            Ok((BTreePage::Leaf(left), separator, BTreePage::Leaf(right)))
+        }
+        BTreePage::Internal(mut left) => {
+            let at = split_point(&left.keys, 2);
+            let mut keys = left.keys.split_off(at);
+            let separator = keys.remove(0);
+            let ptrs = left.ptrs.split_off(at + 1);
+            let right = BTreeInternalPage {
+                id: right_id,
+                ptrs,
+                keys,
+            };
+            Ok((
+                BTreePage::Internal(left),
+                separator,
+                BTreePage::Internal(right),
+            ))
+        }
+    }
+}
+
+/// Merges the given sibling nodes into the left one if they fit in a single
+/// page. Otherwise, evenly redistributes their cells.
+async fn merge(
+    pager: &Pager,
+    left: BTreePage,
+    separator: BTreeCell,
+    right: BTreePage,
+) -> DbResult<Merge> {
+    match (left, right) {
+        (BTreePage::Leaf(mut left), BTreePage::Leaf(mut right)) => {
+            let mut cells = std::mem::take(&mut left.cells);
+            cells.append(&mut right.cells);
+
+            let merged = BTreePage::Leaf(BTreeLeafPage {
+                id: left.id,
+                prev: left.prev,
+                next: right.next,
+                cells,
+            });
+            if fits(pager, &merged) {
+                if let Some(next) = right.next {
+                    set_leaf_prev(pager, next, Some(left.id)).await?;
+                }
+                return Ok(Merge::Merged(merged));
+            }
+
+            let BTreePage::Leaf(BTreeLeafPage { mut cells, .. }) = merged else {
+                unreachable!();
+            };
+            let at = split_point(&cells, 1);
+            right.cells = cells.split_off(at);
+            left.cells = cells;
+            let separator = BTreeCell::separator(left.cells.last().unwrap(), &right.cells[0]);
+            Ok(Merge::Redistributed(
+                BTreePage::Leaf(left),
+                separator,
+                BTreePage::Leaf(right),
+            ))
+        }
+        (BTreePage::Internal(mut left), BTreePage::Internal(mut right)) => {
+            let mut keys = std::mem::take(&mut left.keys);
+            keys.push(separator);
+            keys.append(&mut right.keys);
+            let mut ptrs = std::mem::take(&mut left.ptrs);
+            ptrs.append(&mut right.ptrs);
+
+            let merged = BTreePage::Internal(BTreeInternalPage {
+                id: left.id,
+                ptrs,
+                keys,
+            });
+            if fits(pager, &merged) {
+                return Ok(Merge::Merged(merged));
+            }
+
+            let BTreePage::Internal(BTreeInternalPage {
+                mut ptrs, mut keys, ..
+            }) = merged
+            else {
+                unreachable!();
+            };
+            let at = split_point(&keys, 2);
+            right.keys = keys.split_off(at);
+            let separator = right.keys.remove(0);
+            right.ptrs = ptrs.split_off(at + 1);
+            left.keys = keys;
+            left.ptrs = ptrs;
+            Ok(Merge::Redistributed(
+                BTreePage::Internal(left),
+                separator,
+                BTreePage::Internal(right),
+            ))
+        }
+        _ => unreachable!("siblings must be at the same level"),
+    }
+}
+
+/// Returns the index that splits the given cells in two halves of
+/// (approximately) the same size in bytes. At least `min` cells are left in
+/// each side.
+fn split_point(cells: &[BTreeCell], min: usize) -> usize {
+    debug_assert!(cells.len() >= 2 * min);
+    let sizes: Vec<_> = chunk_sizes(cells, 0)
+        .into_iter()
+        .map(|(_, size)| size)
+        .collect();
+    let total: u32 = sizes.iter().sum();
+    let mut acc = 0;
+    let at = sizes
+        .iter()
+        .position(|size| {
+            acc += size;
+            acc >= total / 2
+        })
+        .unwrap_or(cells.len());
+    at.clamp(min, cells.len() - min)
+}
+
+/// The size of the leaf header (see [`BTreePage`]).
+const LEAF_HEADER_SIZE: u32 = 16;
+
+/// The size of the internal node header, also accounting for the pointer that
+/// has no associated key.
+const INTERNAL_HEADER_SIZE: u32 = 8 + 4;
+
+/// Returns the sizes of the given (ordered) cells plus `extra` bytes, both
+/// when the cell is the first one in a page and when it follows the previous
+/// cell (i.e., when its key is compressed). See [`pack`].
+fn chunk_sizes(cells: &[BTreeCell], extra: u32) -> Vec<(u32, u32)> {
+    let prevs = std::iter::once(None).chain(cells.iter().map(Some));
+    cells
+        .iter()
+        .zip(prevs)
+        .map(|(cell, prev)| (extra + cell.size(), extra + cell.compressed_size(prev)))
+        .collect()
+}
+
+/// Groups the given items (in order) in chunks that fit the given capacity,
+/// returning the length of each chunk. Each item has two sizes: one for when it
+/// is the first item of its chunk and another for when it isn't.
+///
+/// Chunks are filled up, except for the last two, which are evenly balanced if
+/// the last one is less than half full. At least `min` items are put in each
+/// chunk.
+fn pack(sizes: &[(u32, u32)], header: u32, capacity: u32, min: usize) -> Vec<usize> {
+    let mut chunks = Vec::new();
+    let (mut len, mut acc) = (0, header);
+    for &(first_size, size) in sizes {
+        if len >= min && acc + size > capacity {
+            chunks.push(len);
+            (len, acc) = (0, header);
+        }
+        acc += if len == 0 { first_size } else { size };
+        len += 1;
+    }
+    chunks.push(len);
+
+    let tail_len = chunks.iter().rev().take(2).sum::<usize>();
+    if chunks.len() > 1 && tail_len >= 2 * min && (acc < capacity / 2 || len < min) {
+        let tail = &sizes[sizes.len() - tail_len..];
+        let total: u32 = tail.iter().map(|(_, size)| size).sum();
+        let mut acc = 0;
+        let at = tail
+            .iter()
+            .position(|(_, size)| {
+                acc += size;
+                acc >= total / 2
+            })
+            .unwrap_or(tail.len())
+            .clamp(min, tail.len() - min);
+        let chunk_size = |chunk: &[(u32, u32)]| {
+            let rest: u32 = chunk[1..].iter().map(|(_, size)| size).sum();
+            header + chunk[0].0 + rest
+        };
+        if chunk_size(&tail[..at]) <= capacity && chunk_size(&tail[at..]) <= capacity {
+            chunks.truncate(chunks.len() - 2);
+            chunks.push(at);
+            chunks.push(tail.len() - at);
+        }
+    }
+    chunks
+}
+
+fn leaf_page(
+    id: PageId,
+    prev: Option<PageId>,
+    next: Option<PageId>,
+    cells: Vec<BTreeCell>,
+) -> BTreePage {
+    BTreePage::Leaf(BTreeLeafPage {
+        id,
+        prev,
+        next,
+        cells,
+    })
+}
+
+/// Returns the maximum cell size for the given page size. It guarantees that
+/// nodes always have enough cells to be split.
+fn max_cell_size(page_size: u16) -> u32 {
+    (page_size as u32 - 16) / 4
+}
+
+fn fits(pager: &Pager, page: &BTreePage) -> bool {
+    page.size() <= pager.usable_size() as u32
+}
+
+async fn alloc_id(pager: &Pager) -> DbResult<PageId> {
+    let guard = pager.alloc(BTreePage::new_leaf).await?;
+    let page = guard.read().await;
+    let page_id = page.id();
+    page.release();
+    Ok(page_id)
+}
+
+async fn alloc_ids(pager: &Pager, n: usize) -> DbResult<Vec<PageId>> {
+    let guards = pager.alloc_many(n as u32, BTreePage::new_leaf).await?;
+    let mut ids = Vec::with_capacity(n);
+    for guard in guards {
+        let page = guard.read().await;
+        ids.push(page.id());
+        page.release();
+    }
+    Ok(ids)
+}
+
+async fn load(pager: &Pager, page_id: PageId) -> DbResult<BTreePage> {
+    pager
+        .read_with(page_id, |page: &BTreePage| page.clone())
+        .await
+}
+
+async fn load_leaf(pager: &Pager, page_id: PageId) -> DbResult<BTreeLeafPage> {
+    match load(pager, page_id).await? {
+        BTreePage::Leaf(leaf) => Ok(leaf),
+        BTreePage::Internal(_) => Err(not_a_leaf(page_id)),
+    }
+}
+
+fn not_a_leaf(page_id: PageId) -> Error {
+    Error::ExecError(format!("page {} is not a leaf", page_id.get()))
+}
+
+async fn store(pager: &Pager, page: BTreePage) -> DbResult<()> {
+    let guard = pager.get::<BTreePage>(page.id()).await?;
+    let mut guard = guard.write().await;
+    *guard = page;
+    guard.flush();
+    Ok(())
+}
+
+async fn set_leaf_prev(pager: &Pager, page_id: PageId, prev: Option<PageId>) -> DbResult<()> {
+    let mut leaf = load_leaf(pager, page_id).await?;
+    leaf.prev = prev;
+    store(pager, BTreePage::Leaf(leaf)).await
+}
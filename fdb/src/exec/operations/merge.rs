@@ -0,0 +1,147 @@
+//! K-way merge of sorted record sources.
+
+use std::cmp::Ordering;
+
+use async_trait::async_trait;
+use tracing::{instrument, trace};
+
+use crate::{
+    error::DbResult,
+    exec::query::{Plan, Query},
+    Db,
+};
+
+/// Merges `K` sources, each one already sorted as per the given comparator,
+/// into a single sorted stream.
+///
+/// Sources are any [`Query`] that yields owned records, e.g., sort tapes, index
+/// range scans or scans over sorted temporary tables. Records that compare as
+/// equal are yielded in the order of their sources, hence the merge is stable.
+pub struct KWayMerge<S, T, C> {
+    sources: Vec<S>,
+    /// The current (i.e., smallest not yet yielded) record of each source.
+    heads: Vec<Option<T>>,
+    /// A binary min-heap of source indexes, ordered by their heads. Exhausted
+    /// sources are not present.
+    heap: Vec<usize>,
+    cmp: C,
+    primed: bool,
+}
+
+#[async_trait]
+impl<S, T, C> Query for KWayMerge<S, T, C>
+where
+    S: for<'a> Query<Item<'a> = T> + Send,
+    T: Send,
+    C: Fn(&T, &T) -> Ordering + Send + Sync,
+{
+    type Item<'a> = T;
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    #[instrument(name = "KWayMerge", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if !self.primed {
+            self.prime(db).await?;
+        }
+
+        let Some(&source) = self.heap.first() else {
+            trace!("all sources exhausted");
+            return Ok(None);
+        };
+        let record = self.heads[source]
+            .take()
+            .expect("heap only has live sources");
+
+        // Refills the head of the source that has just been consumed.
+        match self.sources[source].next(db).await? {
+            Some(next) => {
+                self.heads[source] = Some(next);
+                self.sift_down(0);
+            }
+            None => {
+                trace!(source, "source exhausted");
+                let last = self.heap.pop().expect("non empty");
+                if !self.heap.is_empty() {
+                    self.heap[0] = last;
+                    self.sift_down(0);
+                }
+            }
+        }
+
+        Ok(Some(record))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let mut plan = Plan::new("KWayMerge").with("k", self.sources.len());
+        for source in &mut self.sources {
+            plan = plan.with_child(source.describe(db).await?);
+        }
+        Ok(plan)
+    }
+}
+
+impl<S, T, C> KWayMerge<S, T, C>
+where
+    S: for<'a> Query<Item<'a> = T>,
+    C: Fn(&T, &T) -> Ordering,
+{
+    /// Constructs a new merge over the given sorted sources.
+    pub fn new(sources: Vec<S>, cmp: C) -> KWayMerge<S, T, C> {
+        KWayMerge {
+            heads: Vec::with_capacity(sources.len()),
+            heap: Vec::with_capacity(sources.len()),
+            sources,
+            cmp,
+            primed: false,
+        }
+    }
+
+    /// Returns the number of merged sources (i.e., the `K`).
+    pub fn k(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Loads the first record of each source and builds the heap.
+    async fn prime(&mut self, db: &Db) -> DbResult<()> {
+        for (i, source) in self.sources.iter_mut().enumerate() {
+            let head = source.next(db).await?;
+            if head.is_some() {
+                self.heap.push(i);
+            }
+            self.heads.push(head);
+        }
+        for i in (0..self.heap.len() / 2).rev() {
+            self.sift_down(i);
+        }
+        self.primed = true;
+        Ok(())
+    }
+
+    /// Checks whether the heap node `a` must come before the node `b`.
+    fn before(&self, a: usize, b: usize) -> bool {
+        let (sa, sb) = (self.heap[a], self.heap[b]);
+        let ha = self.heads[sa].as_ref().expect("live source");
+        let hb = self.heads[sb].as_ref().expect("live source");
+        // Ties are broken by the source index to keep the merge stable.
+        (self.cmp)(ha, hb).then(sa.cmp(&sb)) == Ordering::Less
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let (l, r) = (2 * i + 1, 2 * i + 2);
+            let mut min = i;
+            if l < self.heap.len() && self.before(l, min) {
+                min = l;
+            }
+            if r < self.heap.len() && self.before(r, min) {
+                min = r;
+            }
+            if min == i {
+                return;
+            }
+            self.heap.swap(i, min);
+            i = min;
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Tapes, i.e., temporary record sequences which are written once and then
+//! read sequentially, such as the sorted runs of an external sort or the
+//! partitions of operators which spill to the disk.
+
+use async_trait::async_trait;
+use tracing::{instrument, trace};
+
+use crate::{
+    catalog::table_schema::TableSchema,
+    error::DbResult,
+    exec::{
+        operations::heap,
+        query::{
+            table::{mk_deserializer, BulkInsert, Record, TempTable},
+            Plan, Query,
+        },
+        values::SchematizedValues,
+    },
+    Db,
+};
+
+type Row = SchematizedValues;
+
+/// A tape, whose records are kept in a temporary heap page sequence (see
+/// [`TempTable`]), hence managed by the pager as any other page.
+///
+/// Records are appended through a [`TapeWriter`] and read back, in the same
+/// order, through [`TapeReader`]s. Tapes must be released through
+/// [`Tape::destroy`]; otherwise, their pages are only purged by the next
+/// database open.
+#[derive(Debug)]
+pub struct Tape {
+    table: TempTable,
+}
+
+impl Tape {
+    /// Creates a new, empty, tape, whose records follow the given schema. The
+    /// name is only used for diagnostics.
+    #[instrument(name = "TapeCreate", level = "debug", skip_all)]
+    pub async fn create(db: &Db, name: &str, schema: TableSchema) -> DbResult<Tape> {
+        let table = TempTable::create(db, name, schema).await?;
+        Ok(Tape { table })
+    }
+
+    /// Returns the schema of the records.
+    pub fn schema(&self) -> &TableSchema {
+        &self.table.table().schema
+    }
+
+    /// Returns a writer which appends to the tape, in batches of (at most)
+    /// `batch_size` records.
+    pub fn writer(&self, batch_size: usize) -> TapeWriter<'_> {
+        TapeWriter {
+            tape: self,
+            batch: Vec::with_capacity(batch_size.max(1)),
+            batch_size: batch_size.max(1),
+            len: 0,
+        }
+    }
+
+    /// Appends all the given records to the tape, at once.
+    pub async fn write_all(&self, db: &Db, rows: Vec<Row>) -> DbResult<()> {
+        let mut writer = self.writer(rows.len());
+        for row in rows {
+            writer.push(db, row).await?;
+        }
+        writer.finish(db).await
+    }
+
+    /// Returns a reader over the tape, from its first record. The reader
+    /// doesn't borrow the tape, so that it may be owned by other queries
+    /// (e.g., a [`KWayMerge`]), but must not outlive it.
+    ///
+    /// [`KWayMerge`]: crate::exec::operations::merge::KWayMerge
+    pub fn reader(&self) -> TapeReader {
+        TapeReader {
+            schema: self.schema().clone(),
+            seq_scan: heap::SeqScan::new(self.table.table().page_id),
+        }
+    }
+
+    /// Releases the tape pages.
+    pub async fn destroy(self, db: &Db) -> DbResult<()> {
+        self.table.destroy(db).await
+    }
+}
+
+/// A sequential writer over a [`Tape`], which buffers the records and appends
+/// them in batches. See [`Tape::writer`].
+///
+/// Buffered records are only written once the batch is full or once the
+/// writer is finished, hence [`TapeWriter::finish`] must be called.
+pub struct TapeWriter<'a> {
+    tape: &'a Tape,
+    batch: Vec<Row>,
+    batch_size: usize,
+    len: usize,
+}
+
+impl TapeWriter<'_> {
+    /// Appends the given record, writing the current batch if it is full.
+    pub async fn push(&mut self, db: &Db, row: Row) -> DbResult<()> {
+        self.batch.push(row);
+        self.len += 1;
+        if self.batch.len() == self.batch_size {
+            self.flush(db).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of appended records.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks whether no record was appended.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes the remaining buffered records.
+    pub async fn finish(mut self, db: &Db) -> DbResult<()> {
+        self.flush(db).await?;
+        trace!(len = self.len, "wrote tape");
+        Ok(())
+    }
+
+    async fn flush(&mut self, db: &Db) -> DbResult<()> {
+        let schema = self.tape.schema();
+        let rows = self.batch.drain(..).map(|row| row.into_values(schema));
+        BulkInsert::new(self.tape.table.table(), rows)
+            .next(db)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A sequential reader over a [`Tape`]. See [`Tape::reader`].
+pub struct TapeReader {
+    schema: TableSchema,
+    seq_scan: heap::SeqScan<Record>,
+}
+
+#[async_trait]
+impl Query for TapeReader {
+    type Item<'a> = Row;
+
+    const READ_ONLY: bool = true;
+
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_record = self
+            .seq_scan
+            .next(db, mk_deserializer(&self.schema))
+            .await?;
+        // Tape records are never deleted.
+        Ok(maybe_record.map(|record| record.into_data().into_owned()))
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TapeReader"))
+    }
+}
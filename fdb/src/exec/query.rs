@@ -2,18 +2,51 @@ use async_trait::async_trait;
 
 use crate::{error::DbResult, Db};
 
+mod source;
+pub use source::*;
+
+mod limit;
+pub use limit::*;
+
+mod filter;
+pub use filter::*;
+
+mod pipeline;
+pub use pipeline::*;
+
+mod explain;
+pub use explain::*;
+
 pub mod object {
     mod create;
     pub use create::*;
 
     mod select;
     pub use select::*;
+
+    mod create_table;
+    pub use create_table::*;
+
+    mod create_external_table;
+    pub use create_external_table::*;
+
+    mod drop_table;
+    pub use drop_table::*;
+
+    mod seal_table;
+    pub use seal_table::*;
 }
 
 pub mod table {
     mod insert;
     pub use insert::*;
 
+    mod bulk_insert;
+    pub use bulk_insert::*;
+
+    mod insert_from;
+    pub use insert_from::*;
+
     mod select;
     pub use select::*;
 
@@ -23,10 +56,69 @@ pub mod table {
     mod update;
     pub use update::*;
 
+    mod cte;
+    pub use cte::*;
+
+    mod temp;
+    pub use temp::*;
+
+    mod sort;
+    pub use sort::*;
+
+    mod distinct;
+    pub use distinct::*;
+
+    mod join;
+    pub use join::*;
+
+    mod merge_join;
+    pub use merge_join::*;
+
+    mod record_id;
+    pub use record_id::*;
+
+    mod by_id;
+    pub use by_id::*;
+
+    mod vacuum;
+    pub use vacuum::*;
+
+    mod analyze;
+    pub use analyze::*;
+
+    mod unnest;
+    pub use unnest::*;
+
+    mod aggregate;
+    pub use aggregate::*;
+
+    mod external_scan;
+    pub use external_scan::*;
+
+    mod parallel_scan;
+    pub use parallel_scan::*;
+
+    mod csv;
+    pub use csv::*;
+
+    mod jsonl;
+    pub use jsonl::*;
+
     // Private-implementation queries.
 
     mod seq_scan;
-    use seq_scan::*;
+    pub(crate) use seq_scan::*;
+
+    mod index_scan;
+    pub(crate) use index_scan::*;
+
+    mod indexes;
+    pub(crate) use indexes::*;
+}
+
+pub mod index {
+    mod create;
+    pub use create::*;
 }
 
 /// Query execution trait. It is implemented for all database operations.
@@ -38,6 +130,17 @@ pub mod table {
 pub trait Query {
     type Item<'a>;
 
+    /// Whether the query only reads from the database.
+    ///
+    /// Read-only statements may be executed concurrently, while all others are
+    /// executed in isolation. See [`Db::execute`].
+    const READ_ONLY: bool = false;
+
     /// Produces the next value in the stream.
     async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>>;
+
+    /// Describes what the query does (e.g., its access path), without
+    /// executing it. Must be called before the query is advanced. See
+    /// [`Explain`].
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan>;
 }
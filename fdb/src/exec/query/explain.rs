@@ -0,0 +1,170 @@
+use std::{fmt, ops::Bound};
+
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{
+    catalog::{object::TableObject, page::HeapPage},
+    error::DbResult,
+    exec::{query::Query, util::macros::seq_h, value::Value},
+    Db,
+};
+
+/// A query that, instead of executing the given query, yields a description
+/// of what it would do (see [`Query::describe`]), once.
+///
+/// The access paths are chosen as the query would choose them, hence the
+/// description reflects the current database state (e.g., the defined indexes
+/// and the table sizes).
+pub struct Explain<Q> {
+    query: Q,
+    done: bool,
+}
+
+#[async_trait]
+impl<Q: Query + Send> Query for Explain<Q> {
+    type Item<'a> = Plan;
+
+    const READ_ONLY: bool = true;
+
+    #[instrument(name = "Explain", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        self.query.describe(db).await.map(Some)
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("Explain").with_child(self.query.describe(db).await?))
+    }
+}
+
+impl<Q> Explain<Q> {
+    /// Creates a new explain executor over the given query.
+    pub fn new(query: Q) -> Explain<Q> {
+        Self { query, done: false }
+    }
+
+    /// Returns the underlying query, which may still be executed.
+    pub fn into_inner(self) -> Q {
+        self.query
+    }
+}
+
+/// The description of an executor: its name, its properties (e.g., the scan
+/// type or the sort strategy) and the executors it consumes, if any.
+///
+/// It is displayed as an indented tree, one executor per line:
+///
+/// ```text
+/// TableSort (keys: name asc, strategy: external, run_size: 4096, runs: 3, ...)
+///   -> TableSelect (table: users, access: index scan, index: users_id, ...)
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plan {
+    /// The executor name.
+    pub name: String,
+    /// The executor properties, in insertion order.
+    pub properties: Vec<(String, String)>,
+    /// The plans of the consumed executors.
+    pub children: Vec<Plan>,
+}
+
+impl Plan {
+    /// Creates a new plan with no properties and no children.
+    pub fn new(name: impl Into<String>) -> Plan {
+        Plan {
+            name: name.into(),
+            properties: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Adds the given property.
+    pub fn with(mut self, key: impl Into<String>, value: impl ToString) -> Plan {
+        self.properties.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Adds the given property, if it is set.
+    pub fn with_opt(self, key: impl Into<String>, value: Option<impl ToString>) -> Plan {
+        match value {
+            Some(value) => self.with(key, value),
+            None => self,
+        }
+    }
+
+    /// Adds the given child plan.
+    pub fn with_child(mut self, child: Plan) -> Plan {
+        self.children.push(child);
+        self
+    }
+
+    /// Returns the value of the given property, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        (self.properties.iter())
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the first plan (in depth-first order, starting from this one)
+    /// with the given name, if any.
+    pub fn find(&self, name: &str) -> Option<&Plan> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(name))
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        if depth > 0 {
+            write!(f, "\n{:width$}-> ", "", width = 2 * depth)?;
+        }
+        f.write_str(&self.name)?;
+        if !self.properties.is_empty() {
+            f.write_str(" (")?;
+            for (i, (key, value)) in self.properties.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{key}: {value}")?;
+            }
+            f.write_str(")")?;
+        }
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+/// Returns the number of pages of the given table's heap sequence, i.e., the
+/// number of pages read by a sequential scan.
+pub(crate) async fn table_page_count(db: &Db, table: &TableObject) -> DbResult<u32> {
+    (db.pager())
+        .read_with(table.page_id, |page: &HeapPage| seq_h!(page).page_count)
+        .await
+}
+
+/// Describes the given key range in the interval notation, e.g., `[1, 5)`.
+pub(crate) fn describe_range(start: &Bound<Value>, end: &Bound<Value>) -> String {
+    let start = match start {
+        Bound::Included(value) => format!("[{value}"),
+        Bound::Excluded(value) => format!("({value}"),
+        Bound::Unbounded => "(-inf".to_owned(),
+    };
+    let end = match end {
+        Bound::Included(value) => format!("{value}]"),
+        Bound::Excluded(value) => format!("{value})"),
+        Bound::Unbounded => "+inf)".to_owned(),
+    };
+    format!("{start}, {end}")
+}
@@ -0,0 +1,120 @@
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{
+    catalog::table_schema::TableSchema,
+    error::DbResult,
+    exec::{
+        expr::{Expr, Predicate},
+        query::{Plan, Query, RecordSource, SourceEstimate},
+        values::{SchematizedValues, Values},
+    },
+    Db,
+};
+
+/// A query over a [`RecordSource`], which only yields the records that match
+/// the given predicate.
+///
+/// Unlike [`Select::with_filter`](super::table::Select::with_filter), the
+/// predicate is evaluated over each record of the source, hence it may follow
+/// any other operator (e.g., an [`Unnest`](super::table::Unnest)).
+pub struct Filter<'a, S> {
+    source: S,
+    pred: Predicate<'a>,
+    checked: bool,
+}
+
+#[async_trait]
+impl<S: RecordSource> Query for Filter<'_, S> {
+    type Item<'a> = Values;
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    #[instrument(name = "Filter", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_record = RecordSource::next(self, db).await?;
+        let schema = RecordSource::schema(self);
+        Ok(maybe_record.map(|record| record.into_values(schema)))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        RecordSource::describe(self, db).await
+    }
+}
+
+#[async_trait]
+impl<S: RecordSource> RecordSource for Filter<'_, S> {
+    fn schema(&self) -> &TableSchema {
+        self.source.schema()
+    }
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    async fn next(&mut self, db: &Db) -> DbResult<Option<SchematizedValues>> {
+        self.check()?;
+        while let Some(record) = self.source.next(db).await? {
+            if self.pred.matches(&record.to_values(self.source.schema())) {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<SchematizedValues>> {
+        self.check()?;
+        while let Some(record) = self.source.peek(db).await? {
+            if self.pred.matches(&record.to_values(self.source.schema())) {
+                return Ok(Some(record));
+            }
+            self.source.next(db).await?;
+        }
+        Ok(None)
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("Filter")
+            .with("predicate", self.pred.describe())
+            .with_child(self.source.describe(db).await?))
+    }
+
+    /// The source's estimate, as an upper bound of the matching records.
+    async fn estimate(&mut self, db: &Db) -> DbResult<Option<SourceEstimate>> {
+        self.source.estimate(db).await
+    }
+}
+
+impl<'a, S: RecordSource> Filter<'a, S> {
+    /// Creates a new filter, which yields the records for which the given
+    /// closure returns `true`.
+    pub fn new(source: S, pred: &'a (dyn Sync + for<'v> Fn(&'v Values) -> bool)) -> Filter<'a, S> {
+        Self::with_predicate(source, Predicate::Fn(pred))
+    }
+
+    /// Creates a new filter, which yields the records that match the given
+    /// expression. The expression is type-checked against the source schema
+    /// before the first record is read.
+    pub fn with_expr(source: S, expr: &'a Expr) -> Filter<'a, S> {
+        Self::with_predicate(source, Predicate::Expr(expr))
+    }
+
+    fn with_predicate(source: S, pred: Predicate<'a>) -> Filter<'a, S> {
+        Self {
+            source,
+            pred,
+            checked: false,
+        }
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    fn check(&mut self) -> DbResult<()> {
+        if !self.checked {
+            self.pred.check(self.source.schema())?;
+            self.checked = true;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    catalog::{
+        index_schema::IndexSchema,
+        object::{Object, ObjectType, TableObject},
+        page::BTreeCell,
+        statistics::ColumnStatistics,
+    },
+    error::{DbResult, Error},
+    exec::{
+        operations::index::BTree,
+        query::{self, Plan, Query},
+        statistics,
+    },
+    io::temp,
+    Db,
+};
+
+/// A create index query.
+///
+/// Builds a B+Tree index over the given column of an existing table and
+/// registers the index object in the database schema.
+///
+/// While it's built, the index is registered as temporary (see [`temp`]), so
+/// that its pages are purged if the database crashes meanwhile. They are
+/// released if the build fails.
+pub struct Create {
+    name: String,
+    schema: IndexSchema,
+}
+
+#[async_trait]
+impl Query for Create {
+    type Item<'a> = ();
+
+    #[instrument(name = "IndexCreate", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let mut table = None;
+        let mut select = query::object::Select::new();
+        while let Some(object) = select.next(db).await? {
+            if object.name == self.name {
+                return Err(Error::ExecError(format!(
+                    "object `{}` already exists",
+                    self.name
+                )));
+            }
+            if object.name == self.schema.table {
+                table = Some(object);
+            }
+        }
+        let table = table
+            .ok_or_else(|| {
+                Error::ExecError(format!("object `{}` does not exist", self.schema.table))
+            })?
+            .try_into_table()?;
+        if !table
+            .schema
+            .columns
+            .iter()
+            .any(|column| column.name == self.schema.column)
+        {
+            return Err(Error::ExecError(format!(
+                "column `{}` does not exist in table `{}`",
+                self.schema.column, table.name
+            )));
+        }
+
+        let tree = BTree::create(db.pager()).await?;
+        if let Err(error) = temp::register(db.pager(), tree.root()).await {
+            tree.release(db.pager()).await?;
+            return Err(error);
+        }
+        let built = self.build(db, &table, &tree).await;
+        // XX: The unregistration and the creation of the object are not atomic.
+        // If the database crashes in between, the index pages are leaked.
+        temp::unregister(db.pager(), tree.root()).await?;
+        let object = Object {
+            ty: ObjectType::Index(self.schema.clone()),
+            page_id: tree.root(),
+            name: self.name.clone(),
+        };
+        let created = match built {
+            Ok(()) => query::object::Create::new(&object).next(db).await,
+            Err(error) => Err(error),
+        };
+        if let Err(error) = created {
+            if let Err(error) = tree.release(db.pager()).await {
+                warn!(?error, "failed to release index pages");
+            }
+            return Err(error);
+        }
+
+        Ok(None)
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("IndexCreate")
+            .with("index", &self.name)
+            .with("table", &self.schema.table)
+            .with("column", &self.schema.column)
+            .with("build", "seq scan, bulk load"))
+    }
+}
+
+impl Create {
+    /// Creates a new create index executor.
+    pub fn new(
+        name: impl Into<String>,
+        table: impl Into<String>,
+        column: impl Into<String>,
+    ) -> Create {
+        Self {
+            name: name.into(),
+            schema: IndexSchema {
+                table: table.into(),
+                column: column.into(),
+            },
+        }
+    }
+
+    /// Loads the cells of the given table's records into the given (empty)
+    /// tree.
+    async fn build(&self, db: &Db, table: &TableObject, tree: &BTree) -> DbResult<()> {
+        let mut cells = Vec::new();
+        let mut seq_scan = query::table::SeqScan::new(table);
+        while let Some(record) = seq_scan.next(db).await? {
+            if record.is_deleted() {
+                continue;
+            }
+            let key = record
+                .as_data()
+                .get(&table.schema, &self.schema.column)
+                .expect("column must exist")
+                .clone();
+            cells.push(BTreeCell {
+                key,
+                page_id: record.page_id(),
+                offset: record.offset(),
+            });
+        }
+        cells.sort_unstable();
+        let count = cells.len();
+        if let (Some(min), Some(max)) = (cells.first(), cells.last()) {
+            let mut column = ColumnStatistics::new(&self.schema.column, &min.key);
+            column.widen(&max.key);
+            statistics::track_column(db, table, column).await?;
+        }
+        tree.bulk_load(db.pager(), cells).await?;
+        debug!(count, root = ?tree.root(), "built index");
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{
+    catalog::table_schema::TableSchema,
+    error::DbResult,
+    exec::{
+        query::{Plan, Query, RecordSource, SourceEstimate},
+        values::SchematizedValues,
+    },
+    Db,
+};
+
+/// A query that skips the first `offset` elements of the underlying query and
+/// then yields at most `count` elements.
+///
+/// The underlying query is no longer advanced once `count` elements were
+/// yielded, hence a limited scan stops walking the heap sequence early.
+///
+/// It is also a [`RecordSource`] if the underlying query is.
+pub struct Limit<Q> {
+    inner: Q,
+    count: usize,
+    offset: usize,
+}
+
+#[async_trait]
+impl<Q: Query + Send> Query for Limit<Q> {
+    type Item<'a> = Q::Item<'a>;
+
+    const READ_ONLY: bool = Q::READ_ONLY;
+
+    #[instrument(name = "Limit", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.count == 0 {
+            return Ok(None);
+        }
+        while self.offset > 0 {
+            if self.inner.next(db).await?.is_none() {
+                self.count = 0;
+                return Ok(None);
+            }
+            self.offset -= 1;
+        }
+        self.count -= 1;
+        self.inner.next(db).await
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let inner = self.inner.describe(db).await?;
+        Ok(self.plan(inner))
+    }
+}
+
+#[async_trait]
+impl<S: RecordSource> RecordSource for Limit<S> {
+    fn schema(&self) -> &TableSchema {
+        self.inner.schema()
+    }
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    async fn next(&mut self, db: &Db) -> DbResult<Option<SchematizedValues>> {
+        if !self.skip(db).await? {
+            return Ok(None);
+        }
+        self.count -= 1;
+        self.inner.next(db).await
+    }
+
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<SchematizedValues>> {
+        if !self.skip(db).await? {
+            return Ok(None);
+        }
+        self.inner.peek(db).await
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let inner = RecordSource::describe(&mut self.inner, db).await?;
+        Ok(self.plan(inner))
+    }
+
+    /// The inner source's estimate, without the skipped records and bounded
+    /// by the remaining count.
+    async fn estimate(&mut self, db: &Db) -> DbResult<Option<SourceEstimate>> {
+        let estimate = self.inner.estimate(db).await?;
+        Ok(estimate.map(|estimate| SourceEstimate {
+            records: (estimate.records.saturating_sub(self.offset as u64)).min(self.count as u64),
+            ..estimate
+        }))
+    }
+}
+
+impl<Q> Limit<Q> {
+    /// Creates a new limit over the given query, which yields at most `count`
+    /// elements.
+    pub fn new(inner: Q, count: usize) -> Limit<Q> {
+        Self {
+            inner,
+            count,
+            offset: 0,
+        }
+    }
+
+    /// Sets the number of elements skipped before the first yielded one.
+    pub fn with_offset(mut self, offset: usize) -> Limit<Q> {
+        self.offset = offset;
+        self
+    }
+
+    /// Returns the underlying query.
+    pub fn into_inner(self) -> Q {
+        self.inner
+    }
+
+    /// Describes the limit over the given description of the underlying query.
+    fn plan(&self, inner: Plan) -> Plan {
+        Plan::new("Limit")
+            .with("count", self.count)
+            .with("offset", self.offset)
+            .with_child(inner)
+    }
+}
+
+impl<S: RecordSource> Limit<S> {
+    /// Skips the first `offset` records, returning whether more records may be
+    /// yielded.
+    async fn skip(&mut self, db: &Db) -> DbResult<bool> {
+        if self.count == 0 {
+            return Ok(false);
+        }
+        while self.offset > 0 {
+            if self.inner.next(db).await?.is_none() {
+                self.count = 0;
+                return Ok(false);
+            }
+            self.offset -= 1;
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,73 @@
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use super::create_table::{check_unique, validate};
+use crate::{
+    catalog::{
+        external_schema::ExternalTableSchema,
+        object::{Object, ObjectType},
+        page::PageId,
+    },
+    error::{DbResult, Error},
+    exec::query::{self, Plan, Query},
+    Db,
+};
+
+/// A create external table query.
+///
+/// Registers the external table object in the database schema. The file isn't
+/// accessed until the table is scanned (see [`ExternalScan`]), so it may be
+/// created (or replaced) afterwards.
+///
+/// [`ExternalScan`]: crate::exec::query::table::ExternalScan
+pub struct CreateExternalTable {
+    name: String,
+    schema: ExternalTableSchema,
+}
+
+#[async_trait]
+impl Query for CreateExternalTable {
+    type Item<'a> = ();
+
+    #[instrument(name = "ObjectCreateExternalTable", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        validate(&self.name, &self.schema.schema)?;
+        if self.schema.path.is_empty() {
+            return Err(Error::ExecError(format!(
+                "external table `{}` must have a path",
+                self.name
+            )));
+        }
+        check_unique(db, &self.name).await?;
+
+        debug!(
+            name = self.name,
+            path = self.schema.path,
+            "creating external table"
+        );
+        let object = Object {
+            ty: ObjectType::External(self.schema.clone()),
+            page_id: PageId::FIRST,
+            name: self.name.clone(),
+        };
+        query::object::Create::new(&object).next(db).await?;
+
+        Ok(None)
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("ObjectCreateExternalTable")
+            .with("table", &self.name)
+            .with("path", &self.schema.path))
+    }
+}
+
+impl CreateExternalTable {
+    /// Creates a new create external table executor.
+    pub fn new(name: impl Into<String>, schema: ExternalTableSchema) -> CreateExternalTable {
+        Self {
+            name: name.into(),
+            schema,
+        }
+    }
+}
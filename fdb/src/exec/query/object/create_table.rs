@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        object::{Object, ObjectType},
+        page::{HeapPage, SpecificPage},
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{
+        query::{self, Plan, Query},
+        statistics, values,
+    },
+    Db,
+};
+
+/// The maximum length (in bytes) of object and column names.
+const MAX_NAME_LEN: usize = 64;
+
+/// A create table query.
+///
+/// Allocates the first page of the table's heap sequence and registers the
+/// table object in the database schema.
+pub struct CreateTable {
+    name: String,
+    schema: TableSchema,
+}
+
+#[async_trait]
+impl Query for CreateTable {
+    type Item<'a> = ();
+
+    #[instrument(name = "ObjectCreateTable", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        validate(&self.name, &self.schema)?;
+        check_unique(db, &self.name).await?;
+
+        // Notice that no other guards (mainly to the first page) may be alive
+        // during the allocation.
+        let page_guard = db.pager().alloc(HeapPage::new_seq_first).await?;
+        let page = page_guard.read().await;
+        let page_id = page.id();
+        page.release();
+        debug!(?page_id, name = self.name, "allocated table first page");
+
+        let object = Object {
+            ty: ObjectType::Table(self.schema.clone()),
+            page_id,
+            name: self.name.clone(),
+        };
+        query::object::Create::new(&object).next(db).await?;
+        statistics::create(db, &object.try_into_table()?).await?;
+        db.pager().flush().await?;
+
+        Ok(None)
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("ObjectCreateTable")
+            .with("table", &self.name)
+            .with("columns", self.schema.columns.len()))
+    }
+}
+
+impl CreateTable {
+    /// Creates a new create table executor.
+    pub fn new(name: impl Into<String>, schema: TableSchema) -> CreateTable {
+        Self {
+            name: name.into(),
+            schema,
+        }
+    }
+}
+
+/// Checks the table and column definitions.
+pub(super) fn validate(name: &str, schema: &TableSchema) -> DbResult<()> {
+    check_name("table", name)?;
+    if schema.columns.is_empty() {
+        return Err(Error::ExecError(format!(
+            "table `{name}` must have at least one column"
+        )));
+    }
+    validate_columns("column", schema)
+}
+
+/// Checks the column definitions of the given schema, or the field definitions
+/// of a composite type (see [`TypeId::Composite`]).
+fn validate_columns(kind: &str, schema: &TableSchema) -> DbResult<()> {
+    for (i, column) in schema.columns.iter().enumerate() {
+        check_name(kind, &column.name)?;
+        if schema.columns[..i].iter().any(|c| c.name == column.name) {
+            return Err(Error::ExecError(format!(
+                "{kind} `{}` defined more than once",
+                column.name
+            )));
+        }
+        let is_bounded_type = matches!(
+            column.ty,
+            TypeId::Primitive(PrimitiveTypeId::Text | PrimitiveTypeId::Blob)
+        );
+        if column.max_len.is_some() && !is_bounded_type {
+            return Err(Error::ExecError(format!(
+                "{kind} `{}` of type `{}` can't have a maximum length",
+                column.name,
+                column.ty.name()
+            )));
+        }
+        if let Some(default) = &column.default {
+            if default.type_id() != column.ty {
+                return Err(Error::ExecError(format!(
+                    "default value of {kind} `{}` must be of type `{}`",
+                    column.name,
+                    column.ty.name()
+                )));
+            }
+            if let Some(max_len) = column.max_len {
+                values::check_len(&column.name, default, max_len)?;
+            }
+        }
+        // The zero values of composite types (see `Value::default_for_type`)
+        // omit all of their fields.
+        if kind == "field" && column.not_null && column.default.is_none() {
+            return Err(Error::ExecError(format!(
+                "field `{}` is declared as NOT NULL, hence it must have a default value",
+                column.name
+            )));
+        }
+        if let TypeId::Composite(ty) = column.ty {
+            if ty.fields().columns.is_empty() {
+                return Err(Error::ExecError(format!(
+                    "{kind} `{}` must have at least one field",
+                    column.name
+                )));
+            }
+            validate_columns("field", ty.fields())?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether there is no object with the given name.
+pub(super) async fn check_unique(db: &Db, name: &str) -> DbResult<()> {
+    let mut select = query::object::Select::new();
+    while let Some(object) = select.next(db).await? {
+        if object.name == name {
+            return Err(Error::ExecError(format!("object `{name}` already exists")));
+        }
+    }
+    Ok(())
+}
+
+fn check_name(kind: &str, name: &str) -> DbResult<()> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(Error::ExecError(format!(
+            "{kind} name `{name}` must have between 1 and {MAX_NAME_LEN} bytes"
+        )));
+    }
+    Ok(())
+}
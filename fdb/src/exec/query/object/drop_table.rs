@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use buff::BuffRead;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        object::{Object, ObjectType},
+        page::{HeapPage, PageId},
+        record::simple_record::{SimpleCtx, SimpleRecord},
+    },
+    error::{DbResult, Error},
+    exec::{
+        operations::{
+            heap::{self, span},
+            index::BTree,
+            PhysicalState,
+        },
+        query::{Plan, Query},
+        statistics,
+    },
+    util::io::{DeserializeCtx, Serialize},
+    Db,
+};
+
+const FIRST_SCHEMA_PAGE_ID: PageId = PageId::new_u32(2);
+
+type ObjectRecord = SimpleRecord<'static, Object>;
+
+/// A drop table query.
+///
+/// Logically deletes the table object (and the indexes defined over it) from
+/// the database schema and moves their pages to the free list. External tables
+/// are only removed from the schema; their files are left untouched.
+pub struct DropTable {
+    name: String,
+}
+
+#[async_trait]
+impl Query for DropTable {
+    type Item<'a> = ();
+
+    #[instrument(name = "ObjectDropTable", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let mut seq_scan = heap::SeqScan::<ObjectRecord>::new(FIRST_SCHEMA_PAGE_ID);
+
+        while let Some(mut record) = seq_scan.next(db, deserializer).await? {
+            let object = record.as_data();
+            if record.is_deleted() || object.name != self.name {
+                continue;
+            }
+            if matches!(object.ty, ObjectType::External(_)) {
+                mark_deleted(db, &mut record).await?;
+                db.pager().flush().await?;
+                return Ok(None);
+            }
+            if !matches!(object.ty, ObjectType::Table(_)) {
+                return Err(Error::Cast(format!(
+                    "object `{}` is not a table",
+                    self.name
+                )));
+            }
+
+            let table_page_id = object.page_id;
+            mark_deleted(db, &mut record).await?;
+            db.activity().forget(table_page_id);
+            statistics::remove(db, table_page_id).await?;
+            heap::release(db.pager(), table_page_id).await?;
+
+            drop_indexes(db, &self.name).await?;
+
+            db.pager().flush().await?;
+            return Ok(None);
+        }
+
+        Err(Error::ExecError(format!(
+            "object `{}` does not exist",
+            self.name
+        )))
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("ObjectDropTable").with("table", &self.name))
+    }
+}
+
+impl DropTable {
+    /// Creates a new drop table executor.
+    pub fn new(name: impl Into<String>) -> DropTable {
+        Self { name: name.into() }
+    }
+}
+
+fn deserializer(buf: &mut BuffRead<'_>, state: PhysicalState) -> DbResult<ObjectRecord> {
+    let ctx = SimpleCtx::from_physical(state);
+    ObjectRecord::deserialize(buf, &ctx)
+}
+
+/// Drops all indexes defined over the given table.
+async fn drop_indexes(db: &Db, table: &str) -> DbResult<()> {
+    let mut seq_scan = heap::SeqScan::<ObjectRecord>::new(FIRST_SCHEMA_PAGE_ID);
+
+    while let Some(mut record) = seq_scan.next(db, deserializer).await? {
+        let ObjectType::Index(schema) = &record.as_data().ty else {
+            continue;
+        };
+        if record.is_deleted() || schema.table != table {
+            continue;
+        }
+
+        let index_page_id = record.as_data().page_id;
+        mark_deleted(db, &mut record).await?;
+        db.activity().forget_index(index_page_id);
+        BTree::new(index_page_id).release(db.pager()).await?;
+    }
+    Ok(())
+}
+
+/// Marks the given object record as deleted.
+async fn mark_deleted(db: &Db, record: &mut ObjectRecord) -> DbResult<()> {
+    let page_id = record.page_id();
+    let offset = record.offset();
+    debug!(
+        ?page_id,
+        offset,
+        name = record.as_data().name,
+        "marking object as deleted"
+    );
+    let guard = db.pager().get::<HeapPage>(page_id).await?;
+    let mut page = guard.write().await;
+
+    record.set_deleted();
+    if record.is_spanned() {
+        span::set_deleted(&mut page, offset);
+    } else {
+        page.write_at(offset, |buf| record.serialize(buf))?;
+    }
+    page.flush();
+    db.bump_catalog_version();
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        external_schema::{ExternalFormat, ExternalTableSchema},
+        object::{Object, ObjectType, TableObject},
+        page::PageId,
+    },
+    error::DbResult,
+    exec::query::{self, Plan, Query, RecordSource},
+    io::segment::Segment,
+    Db,
+};
+
+/// A seal table query, which turns a table into an immutable segment.
+///
+/// The live rows of the table are compacted into a new segment file (see
+/// [`Segment`]), which must not exist. Then, the table (along with its indexes)
+/// is dropped and replaced by an external table of the same name, which is
+/// served from the segment file. Yields the number of sealed rows once.
+///
+/// Since segments are self-describing, the file may also be attached to other
+/// databases (see [`AttachSegment`]).
+pub struct SealTable<'a> {
+    table: &'a TableObject,
+    path: String,
+    done: bool,
+}
+
+#[async_trait]
+impl Query for SealTable<'_> {
+    type Item<'a> = u64;
+
+    #[instrument(name = "ObjectSealTable", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        let mut select = query::table::Select::new(self.table);
+        let mut rows = Vec::new();
+        while let Some(row) = RecordSource::next(&mut select, db).await? {
+            rows.push(row);
+        }
+        let schema = &self.table.schema;
+        // The file is written before the catalog is changed, so that a failed
+        // write leaves the table untouched.
+        let bytes = Segment::encode(schema, &rows)?;
+        Segment::write_new(&self.path, &bytes).await?;
+        debug!(name = self.table.name, path = self.path, "sealed table");
+
+        query::object::DropTable::new(&self.table.name)
+            .next(db)
+            .await?;
+        let object = Object {
+            ty: ObjectType::External(ExternalTableSchema {
+                path: self.path.clone(),
+                format: ExternalFormat::Segment,
+                schema: schema.clone(),
+            }),
+            page_id: PageId::FIRST,
+            name: self.table.name.clone(),
+        };
+        query::object::Create::new(&object).next(db).await?;
+
+        Ok(Some(rows.len() as u64))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let mut select = query::table::Select::new(self.table);
+        Ok(Plan::new("ObjectSealTable")
+            .with("table", &self.table.name)
+            .with("path", &self.path)
+            .with_child(RecordSource::describe(&mut select, db).await?))
+    }
+}
+
+impl<'a> SealTable<'a> {
+    /// Creates a new seal table executor, which writes the segment file at the
+    /// given path.
+    pub fn new(table: &'a TableObject, path: impl Into<String>) -> SealTable<'a> {
+        Self {
+            table,
+            path: path.into(),
+            done: false,
+        }
+    }
+}
+
+/// An attach segment query, which registers a segment file (see [`Segment`])
+/// as a read-only external table, whose schema is the segment's one.
+///
+/// The file is verified once attached, and each time it's scanned.
+pub struct AttachSegment {
+    name: String,
+    path: String,
+}
+
+#[async_trait]
+impl Query for AttachSegment {
+    type Item<'a> = ();
+
+    #[instrument(name = "ObjectAttachSegment", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let segment = Segment::read(&self.path).await?;
+        debug!(
+            name = self.name,
+            path = self.path,
+            rows = segment.rows.len(),
+            "attaching segment"
+        );
+        let schema = ExternalTableSchema {
+            path: self.path.clone(),
+            format: ExternalFormat::Segment,
+            schema: segment.schema,
+        };
+        query::object::CreateExternalTable::new(&self.name, schema)
+            .next(db)
+            .await
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("ObjectAttachSegment")
+            .with("table", &self.name)
+            .with("path", &self.path))
+    }
+}
+
+impl AttachSegment {
+    /// Creates a new attach segment executor.
+    pub fn new(name: impl Into<String>, path: impl Into<String>) -> AttachSegment {
+        Self {
+            name: name.into(),
+            path: path.into(),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use buff::Buff;
+use buff::BuffRead;
 use tracing::instrument;
 
 use crate::{
@@ -8,10 +8,10 @@ use crate::{
         page::PageId,
         record::simple_record::{SimpleCtx, SimpleRecord},
     },
-    error::DbResult,
+    error::{DbResult, ErrorContext, ResultExt},
     exec::{
         operations::{heap, PhysicalState},
-        query::Query,
+        query::{Plan, Query},
     },
     util::io::DeserializeCtx,
     Db,
@@ -30,10 +30,17 @@ pub struct Select {
 impl Query for Select {
     type Item<'a> = Object;
 
+    const READ_ONLY: bool = true;
+
     #[instrument(name = "ObjectSelect", level = "debug", skip_all)]
     async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
         loop {
-            return match self.seq_scan.next(db, deserializer).await? {
+            let maybe_record = self
+                .seq_scan
+                .next(db, deserializer)
+                .await
+                .context(ErrorContext::Operation("scanning the database schema"))?;
+            return match maybe_record {
                 Some(record) => {
                     if record.is_deleted() {
                         continue;
@@ -44,6 +51,10 @@ impl Query for Select {
             };
         }
     }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("ObjectSelect").with("access", "seq scan"))
+    }
 }
 
 impl Select {
@@ -60,7 +71,7 @@ impl Default for Select {
     }
 }
 
-fn deserializer(buf: &mut Buff<'_>, state: PhysicalState) -> DbResult<ObjectRecord> {
+fn deserializer(buf: &mut BuffRead<'_>, state: PhysicalState) -> DbResult<ObjectRecord> {
     let ctx = SimpleCtx::from_physical(state);
     ObjectRecord::deserialize(buf, &ctx)
 }
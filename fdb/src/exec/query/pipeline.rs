@@ -0,0 +1,64 @@
+use crate::{
+    error::DbResult,
+    exec::{
+        expr::Expr,
+        query::{
+            table::{Distinct, Sort, SortKey, Unnest},
+            Filter, Limit, RecordSource,
+        },
+        values::Values,
+    },
+};
+
+/// Chains operators over a [`RecordSource`], so that query pipelines may be
+/// assembled without a dedicated executor for each combination. For example:
+///
+/// ```no_run
+/// # use fdb::{catalog::object::TableObject, exec::{expr::Expr, query::{Pipeline, table::{Select, SortKey}}}};
+/// # fn f(table: &TableObject, expr: &Expr) {
+/// let pipeline = Select::new(table)
+///     .filter_expr(expr)
+///     .sort(vec![SortKey::desc("id")])
+///     .limit(10);
+/// # }
+/// ```
+///
+/// Each step is a [`Query`](super::Query) itself, which yields the values of
+/// the records, and a [`RecordSource`], which may be chained further.
+///
+/// This trait is implemented for all record sources.
+pub trait Pipeline: RecordSource + Sized {
+    /// Only yields the records for which the given closure returns `true`. See
+    /// [`Filter::new`].
+    fn filter(self, pred: &(dyn Sync + for<'v> Fn(&'v Values) -> bool)) -> Filter<'_, Self> {
+        Filter::new(self, pred)
+    }
+
+    /// Only yields the records that match the given expression. See
+    /// [`Filter::with_expr`].
+    fn filter_expr(self, expr: &Expr) -> Filter<'_, Self> {
+        Filter::with_expr(self, expr)
+    }
+
+    /// Sorts the records by the given keys. See [`Sort`].
+    fn sort(self, keys: Vec<SortKey>) -> Sort<Self> {
+        Sort::new(self, keys)
+    }
+
+    /// Removes the duplicate records. See [`Distinct`].
+    fn distinct(self) -> Distinct<Self> {
+        Distinct::new(self)
+    }
+
+    /// Expands the given array column into rows. See [`Unnest::new`].
+    fn unnest(self, column: impl Into<String>) -> DbResult<Unnest<Self>> {
+        Unnest::new(self, column)
+    }
+
+    /// Yields at most `count` records. See [`Limit`].
+    fn limit(self, count: usize) -> Limit<Self> {
+        Limit::new(self, count)
+    }
+}
+
+impl<S: RecordSource> Pipeline for S {}
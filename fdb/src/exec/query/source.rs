@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+
+use crate::{
+    catalog::table_schema::TableSchema,
+    error::DbResult,
+    exec::{query::Plan, values::SchematizedValues},
+    Db,
+};
+
+/// A source of (live) schematized records.
+///
+/// Higher-level operators (e.g., sort, join and aggregations) should consume
+/// their inputs through this trait, instead of being hard-wired to a specific
+/// access path such as a table sequential scan.
+#[async_trait]
+pub trait RecordSource: Send {
+    /// Returns the schema of the yielded records.
+    fn schema(&self) -> &TableSchema;
+
+    /// Whether the source only reads from the database. See
+    /// [`Query::READ_ONLY`](super::Query::READ_ONLY).
+    const READ_ONLY: bool = false;
+
+    /// Produces the next record in the stream.
+    async fn next(&mut self, db: &Db) -> DbResult<Option<SchematizedValues>>;
+
+    /// Returns the next record in the stream without advancing it.
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<SchematizedValues>>;
+
+    /// Describes what the source does, without advancing it. See
+    /// [`Query::describe`](super::Query::describe).
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan>;
+
+    /// Estimates the records yielded by the source (if it wasn't advanced yet),
+    /// e.g., from the table statistics. Returns `None` if there is no estimate.
+    ///
+    /// Estimates are only used to plan the execution of the operators over the
+    /// source (e.g., [`Sort`](super::table::Sort)), which must still be correct
+    /// if they are wrong.
+    async fn estimate(&mut self, _db: &Db) -> DbResult<Option<SourceEstimate>> {
+        Ok(None)
+    }
+}
+
+/// An estimate of the records yielded by a [`RecordSource`]. See
+/// [`RecordSource::estimate`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SourceEstimate {
+    /// The (maximum) number of records.
+    pub records: u64,
+    /// The average size (in bytes) of the records' data.
+    pub avg_record_size: f64,
+}
+
+impl SourceEstimate {
+    /// Returns the estimated total size (in bytes) of the records' data.
+    pub fn total_size(&self) -> u64 {
+        (self.records as f64 * self.avg_record_size).ceil() as u64
+    }
+}
@@ -0,0 +1,581 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    mem,
+};
+
+use async_trait::async_trait;
+use tracing::{debug, instrument, trace};
+
+use crate::{
+    catalog::{
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{
+        expr::as_i64,
+        operations::tape::Tape,
+        query::{Plan, Query, RecordSource},
+        value::Value,
+        values::{SchematizedValues, Values},
+    },
+    Db,
+};
+
+/// The default maximum number of groups held in memory at once.
+pub const DEFAULT_MAX_GROUPS: usize = 4096;
+
+/// The number of partitions the spilled records of a grouping set are
+/// distributed to.
+const SPILL_PARTITIONS: usize = 8;
+
+type Row = SchematizedValues;
+
+/// An aggregate function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AggregateFn {
+    /// The number of records, as a `BigInt`.
+    Count,
+    /// The sum of an integer column, as a `BigInt`.
+    Sum(String),
+    /// The minimum value of a column.
+    Min(String),
+    /// The maximum value of a column.
+    Max(String),
+}
+
+impl AggregateFn {
+    fn column(&self) -> Option<&str> {
+        match self {
+            AggregateFn::Count => None,
+            AggregateFn::Sum(column) | AggregateFn::Min(column) | AggregateFn::Max(column) => {
+                Some(column)
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            AggregateFn::Count => "count(*)".to_owned(),
+            AggregateFn::Sum(column) => format!("sum({column})"),
+            AggregateFn::Min(column) => format!("min({column})"),
+            AggregateFn::Max(column) => format!("max({column})"),
+        }
+    }
+}
+
+/// An aggregation query over a [`RecordSource`], which yields one row per
+/// group, holding the group's columns and the named aggregates.
+///
+/// By default, records are grouped by all the `group_by` columns. Many
+/// grouping sets (i.e., subsets of the `group_by` columns) may be used instead
+/// (see [`Aggregate::with_grouping_sets`] and [`Aggregate::with_rollup`]), in
+/// which case all of them are computed in a single pass over the source.
+///
+/// As there are no NULL values, the `group_by` columns which are not in a row's
+/// grouping set are absent from the row. So are the aggregates of empty groups
+/// other than counts (which only happen for the empty grouping set over an
+/// empty source).
+///
+/// At most `max_groups` groups (of all grouping sets) are held in memory at
+/// once. Once they are exhausted, the records of the groups which don't fit are
+/// spilled to [`Tape`]s, partitioned by the hash of their groups, and each
+/// partition is aggregated (and spilled, if needed) after the source is read.
+///
+/// Rows are yielded by grouping set (in the given order) and then by the order
+/// in which their groups first appeared in the source. The rows of the spilled
+/// groups are yielded after all others, by partition.
+///
+/// Spill tapes are released once the aggregation is exhausted. If it is
+/// dropped earlier, they are purged by the next database open.
+pub struct Aggregate<S> {
+    source: S,
+    grouping: Grouping,
+    output: Option<Output>,
+}
+
+/// How the records of an [`Aggregate`] are grouped and aggregated.
+struct Grouping {
+    group_by: Vec<String>,
+    grouping_sets: Vec<Vec<usize>>,
+    aggregates: Vec<(String, AggregateFn)>,
+    max_groups: usize,
+}
+
+/// The aggregated rows which weren't yielded yet, and the spilled partitions
+/// which weren't aggregated yet.
+struct Output {
+    rows: VecDeque<Values>,
+    partitions: Vec<Partition>,
+}
+
+/// A partition of the records of a grouping set which were spilled.
+struct Partition {
+    set: usize,
+    /// The number of spills which produced the partition.
+    level: u32,
+    tape: Tape,
+}
+
+#[async_trait]
+impl<S: RecordSource> Query for Aggregate<S> {
+    type Item<'a> = Values;
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    #[instrument(name = "TableAggregate", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        // The aggregation futures are boxed, as their (nested) layouts would
+        // otherwise overflow the compiler's query depth limit.
+        if self.output.is_none() {
+            self.output = Some(Box::pin(self.run(db)).await?);
+        }
+        loop {
+            let output = self.output.as_mut().expect("computed above");
+            if let Some(row) = output.rows.pop_front() {
+                return Ok(Some(row));
+            }
+            let Some(partition) = output.partitions.pop() else {
+                return Ok(None);
+            };
+            let schema = self.source.schema();
+            let aggregated =
+                Box::pin(aggregate_partition(db, &self.grouping, schema, partition)).await?;
+            let output = self.output.as_mut().expect("computed above");
+            output.rows = aggregated.rows;
+            // The new partitions are aggregated first, which bounds the number
+            // of simultaneous tapes.
+            output.partitions.extend(aggregated.partitions);
+        }
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let aggregates = (self.grouping.aggregates.iter())
+            .map(|(name, aggregate)| format!("{name} = {}", aggregate.describe()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Ok(Plan::new("TableAggregate")
+            .with("group_by", self.grouping.group_by.join(", "))
+            .with("grouping_sets", self.grouping.grouping_sets.len())
+            .with("aggregates", aggregates)
+            .with("max_groups", self.grouping.max_groups)
+            .with_child(self.source.describe(db).await?))
+    }
+}
+
+impl<S: RecordSource> Aggregate<S> {
+    /// Creates a new aggregation executor over the given source, grouping the
+    /// records by the given columns.
+    pub fn new(
+        source: S,
+        group_by: &[&str],
+        aggregates: Vec<(String, AggregateFn)>,
+    ) -> DbResult<Aggregate<S>> {
+        let schema = source.schema();
+        for column in group_by {
+            find_column(schema, column)?;
+        }
+        for (name, aggregate) in &aggregates {
+            if group_by.contains(&name.as_str()) {
+                return Err(Error::ExecError(format!(
+                    "aggregate `{name}` conflicts with a grouping column"
+                )));
+            }
+            if let Some(column) = aggregate.column() {
+                let ty = find_column(schema, column)?;
+                if let AggregateFn::Sum(_) = aggregate {
+                    if !is_summable(ty) {
+                        return Err(Error::ExecError(format!(
+                            "can't sum column `{column}` of type `{}`",
+                            ty.name()
+                        )));
+                    }
+                }
+            }
+        }
+
+        Ok(Aggregate {
+            source,
+            grouping: Grouping {
+                group_by: group_by.iter().map(|column| column.to_string()).collect(),
+                grouping_sets: vec![(0..group_by.len()).collect()],
+                aggregates,
+                max_groups: DEFAULT_MAX_GROUPS,
+            },
+            output: None,
+        })
+    }
+
+    /// Sets the maximum number of groups held in memory at once.
+    pub fn with_max_groups(mut self, max_groups: usize) -> Self {
+        self.grouping.max_groups = max_groups.max(1);
+        self
+    }
+
+    /// Sets the grouping sets, whose columns must be `group_by` columns. The
+    /// empty set aggregates all records.
+    pub fn with_grouping_sets(mut self, grouping_sets: &[&[&str]]) -> DbResult<Self> {
+        self.grouping.grouping_sets = grouping_sets
+            .iter()
+            .map(|set| {
+                set.iter()
+                    .map(|column| {
+                        self.grouping
+                            .group_by
+                            .iter()
+                            .position(|group_by| group_by == column)
+                            .ok_or_else(|| {
+                                Error::ExecError(format!(
+                                    "column `{column}` is not a grouping column"
+                                ))
+                            })
+                    })
+                    .collect()
+            })
+            .collect::<DbResult<_>>()?;
+        Ok(self)
+    }
+
+    /// Sets the grouping sets to the `group_by` columns' prefixes, from the
+    /// longest to the empty one. E.g., `ROLLUP (a, b)` computes the `(a, b)`,
+    /// `(a)` and `()` grouping sets.
+    pub fn with_rollup(mut self) -> Self {
+        self.grouping.grouping_sets = (0..=self.grouping.group_by.len())
+            .rev()
+            .map(|len| (0..len).collect())
+            .collect();
+        self
+    }
+
+    /// Aggregates all source records, returning the rows and the spilled
+    /// partitions.
+    async fn run(&mut self, db: &Db) -> DbResult<Output> {
+        let schema = self.source.schema().clone();
+        let sets = (0..self.grouping.grouping_sets.len()).collect();
+        let mut aggregation = Aggregation::new(&self.grouping, &schema, sets, 0);
+        let mut record_count = 0;
+        while let Some(record) = self.source.next(db).await? {
+            aggregation.add(db, record).await?;
+            record_count += 1;
+        }
+        debug!(
+            record_count,
+            sets = self.grouping.grouping_sets.len(),
+            "aggregated records"
+        );
+        aggregation.finish(db).await
+    }
+}
+
+/// Aggregates the records of the given spilled partition, releasing it.
+///
+/// It doesn't borrow the [`Aggregate`], since its source isn't required to be
+/// `Sync` and the returned future must be `Send`.
+async fn aggregate_partition(
+    db: &Db,
+    grouping: &Grouping,
+    schema: &TableSchema,
+    partition: Partition,
+) -> DbResult<Output> {
+    let mut aggregation =
+        Aggregation::new(grouping, schema, vec![partition.set], partition.level + 1);
+    let mut reader = partition.tape.reader();
+    let mut record_count = 0;
+    while let Some(record) = reader.next(db).await? {
+        aggregation.add(db, record).await?;
+        record_count += 1;
+    }
+    trace!(
+        record_count,
+        level = partition.level,
+        "aggregated partition"
+    );
+    let output = aggregation.finish(db).await?;
+    partition.tape.destroy(db).await?;
+    Ok(output)
+}
+
+/// An in-memory aggregation over some of the grouping sets of an
+/// [`Aggregate`], which spills the records of the groups which don't fit.
+struct Aggregation<'a> {
+    grouping: &'a Grouping,
+    schema: &'a TableSchema,
+    /// The aggregated grouping sets (by index), along with their spills.
+    sets: Vec<(usize, GroupingSet, Option<Spill>)>,
+    /// The number of spills which produced the aggregated records.
+    level: u32,
+    group_count: usize,
+}
+
+impl<'a> Aggregation<'a> {
+    fn new(
+        grouping: &'a Grouping,
+        schema: &'a TableSchema,
+        sets: Vec<usize>,
+        level: u32,
+    ) -> Aggregation<'a> {
+        Aggregation {
+            grouping,
+            schema,
+            sets: (sets.into_iter())
+                .map(|set| (set, GroupingSet::default(), None))
+                .collect(),
+            level,
+            group_count: 0,
+        }
+    }
+
+    /// Aggregates the given record in all grouping sets, spilling it in the
+    /// ones whose group doesn't fit.
+    async fn add(&mut self, db: &Db, record: Row) -> DbResult<()> {
+        let values = &record.clone().into_values(self.schema);
+        for (set, groups, spill) in &mut self.sets {
+            let key: Vec<_> = (self.grouping.grouping_sets[*set].iter())
+                .map(|&i| column_value(values, &self.grouping.group_by[i]).clone())
+                .collect();
+            if !groups.contains(&key) && self.group_count == self.grouping.max_groups {
+                if spill.is_none() {
+                    *spill = Some(Spill::new(db, self.schema, *set, self.level).await?);
+                }
+                let spill = spill.as_mut().expect("created above");
+                spill
+                    .push(db, &key, record.clone(), self.grouping.max_groups)
+                    .await?;
+                continue;
+            }
+            let len = groups.groups.len();
+            let states = groups.group(key, &self.grouping.aggregates);
+            for ((name, aggregate), state) in self.grouping.aggregates.iter().zip(states) {
+                state.update(name, aggregate, values)?;
+            }
+            self.group_count += groups.groups.len() - len;
+        }
+        Ok(())
+    }
+
+    /// Returns the rows of the aggregated groups and the spilled partitions.
+    async fn finish(self, db: &Db) -> DbResult<Output> {
+        let mut rows = VecDeque::new();
+        let mut partitions = Vec::new();
+        for (set, mut groups, spill) in self.sets {
+            let columns = &self.grouping.grouping_sets[set];
+            // Unlike other grouping sets, the empty one always has a group.
+            if columns.is_empty() && groups.groups.is_empty() && spill.is_none() {
+                groups.group(Vec::new(), &self.grouping.aggregates);
+            }
+            for (key, states) in groups.groups {
+                let mut row = Values::new();
+                for (&i, value) in columns.iter().zip(key) {
+                    row.set(self.grouping.group_by[i].clone(), value);
+                }
+                for ((name, _), state) in self.grouping.aggregates.iter().zip(states) {
+                    if let Some(value) = state.finish() {
+                        row.set(name.clone(), value);
+                    }
+                }
+                rows.push_back(row);
+            }
+            if let Some(spill) = spill {
+                partitions.extend(spill.finish(db).await?);
+            }
+        }
+        // The partitions are aggregated as a stack (see `Aggregate::next`),
+        // hence reversed, so that they are aggregated in order.
+        partitions.reverse();
+        if !partitions.is_empty() {
+            debug!(
+                partitions = partitions.len(),
+                level = self.level,
+                "spilled records"
+            );
+        }
+        Ok(Output { rows, partitions })
+    }
+}
+
+/// The spilled records of a grouping set, partitioned by the hash of their
+/// groups. The records are buffered, and written in batches.
+struct Spill {
+    set: usize,
+    level: u32,
+    tapes: Vec<Tape>,
+    batches: Vec<Vec<Row>>,
+    lens: Vec<usize>,
+}
+
+impl Spill {
+    async fn new(db: &Db, schema: &TableSchema, set: usize, level: u32) -> DbResult<Spill> {
+        let mut tapes = Vec::with_capacity(SPILL_PARTITIONS);
+        for _ in 0..SPILL_PARTITIONS {
+            tapes.push(Tape::create(db, "aggregate_tape", schema.clone()).await?);
+        }
+        Ok(Spill {
+            set,
+            level,
+            tapes,
+            batches: vec![Vec::new(); SPILL_PARTITIONS],
+            lens: vec![0; SPILL_PARTITIONS],
+        })
+    }
+
+    /// Spills the given record, of the given group. At most `max_buffered`
+    /// records are buffered at once.
+    async fn push(
+        &mut self,
+        db: &Db,
+        key: &[Value],
+        record: Row,
+        max_buffered: usize,
+    ) -> DbResult<()> {
+        let i = partition_of(key, self.level);
+        self.batches[i].push(record);
+        self.lens[i] += 1;
+        if self.batches[i].len() >= (max_buffered / SPILL_PARTITIONS).max(1) {
+            self.tapes[i]
+                .write_all(db, mem::take(&mut self.batches[i]))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered records, returning the non-empty partitions. The
+    /// empty ones are released.
+    async fn finish(self, db: &Db) -> DbResult<Vec<Partition>> {
+        let mut partitions = Vec::new();
+        let parts = self.tapes.into_iter().zip(self.batches).zip(self.lens);
+        for ((tape, batch), len) in parts {
+            if len == 0 {
+                tape.destroy(db).await?;
+                continue;
+            }
+            tape.write_all(db, batch).await?;
+            partitions.push(Partition {
+                set: self.set,
+                level: self.level,
+                tape,
+            });
+        }
+        Ok(partitions)
+    }
+}
+
+/// Returns the partition of the given group. The hash depends on the spill
+/// level, so that the groups of a partition are redistributed if it is spilled
+/// again.
+fn partition_of(key: &[Value], level: u32) -> usize {
+    let mut hasher = DefaultHasher::new();
+    level.hash(&mut hasher);
+    key.hash(&mut hasher);
+    (hasher.finish() % SPILL_PARTITIONS as u64) as usize
+}
+
+/// The groups of a grouping set, in the order of their first appearance.
+#[derive(Default)]
+struct GroupingSet {
+    groups: Vec<(Vec<Value>, Vec<State>)>,
+    positions: HashMap<Vec<Value>, usize>,
+}
+
+impl GroupingSet {
+    fn contains(&self, key: &[Value]) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// Returns the states of the given group, which is created if needed.
+    fn group(&mut self, key: Vec<Value>, aggregates: &[(String, AggregateFn)]) -> &mut [State] {
+        let position = match self.positions.get(&key) {
+            Some(&position) => position,
+            None => {
+                self.positions.insert(key.clone(), self.groups.len());
+                let states = aggregates
+                    .iter()
+                    .map(|(_, aggregate)| match aggregate {
+                        AggregateFn::Count => State::Count(0),
+                        _ => State::Empty,
+                    })
+                    .collect();
+                self.groups.push((key, states));
+                self.groups.len() - 1
+            }
+        };
+        &mut self.groups[position].1
+    }
+}
+
+/// The state of an aggregate function over a group.
+enum State {
+    Empty,
+    Count(i64),
+    Sum(i64),
+    Value(Value),
+}
+
+impl State {
+    fn update(&mut self, name: &str, aggregate: &AggregateFn, values: &Values) -> DbResult<()> {
+        match (aggregate, &mut *self) {
+            (AggregateFn::Count, State::Count(count)) => *count += 1,
+            (AggregateFn::Sum(column), state) => {
+                let value = as_i64(column_value(values, column)).expect("checked integer");
+                let sum = match state {
+                    State::Empty => Some(value),
+                    State::Sum(sum) => sum.checked_add(value),
+                    _ => unreachable!(),
+                };
+                let sum =
+                    sum.ok_or_else(|| Error::ExecError(format!("aggregate `{name}` overflowed")))?;
+                *state = State::Sum(sum);
+            }
+            (AggregateFn::Min(column) | AggregateFn::Max(column), state) => {
+                let value = column_value(values, column);
+                let replace = match state {
+                    State::Empty => true,
+                    State::Value(current) if matches!(aggregate, AggregateFn::Min(_)) => {
+                        value < current
+                    }
+                    State::Value(current) => value > current,
+                    _ => unreachable!(),
+                };
+                if replace {
+                    *state = State::Value(value.clone());
+                }
+            }
+            (AggregateFn::Count, _) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Option<Value> {
+        match self {
+            State::Empty => None,
+            State::Count(count) => Some(Value::BigInt(count)),
+            State::Sum(sum) => Some(Value::BigInt(sum)),
+            State::Value(value) => Some(value),
+        }
+    }
+}
+
+fn find_column(schema: &TableSchema, column: &str) -> DbResult<TypeId> {
+    schema
+        .columns
+        .iter()
+        .find(|c| c.name == column)
+        .map(|c| c.ty)
+        .ok_or_else(|| Error::ExecError(format!("column `{column}` does not exist")))
+}
+
+fn column_value<'a>(values: &'a Values, column: &str) -> &'a Value {
+    values.get(column).expect("schematized record")
+}
+
+fn is_summable(ty: TypeId) -> bool {
+    matches!(
+        ty,
+        TypeId::Primitive(
+            PrimitiveTypeId::Byte
+                | PrimitiveTypeId::ShortInt
+                | PrimitiveTypeId::Int
+                | PrimitiveTypeId::BigInt
+        )
+    )
+}
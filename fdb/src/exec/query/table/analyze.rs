@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        object::TableObject,
+        statistics::{ColumnStatistics, TableStatistics},
+    },
+    error::DbResult,
+    exec::{
+        query::{
+            table::{SeqScan, TableIndexes},
+            table_page_count, Plan, Query,
+        },
+        statistics,
+    },
+    util::io::Size,
+    Db,
+};
+
+/// An analyze query, which recomputes the statistics of a table (see
+/// [`statistics`]) from its records, replacing the persisted ones. Yields the
+/// new statistics once.
+///
+/// Tables without statistics (i.e., created by older versions) get them once
+/// analyzed.
+pub struct Analyze<'a> {
+    table: &'a TableObject,
+    done: bool,
+}
+
+#[async_trait]
+impl Query for Analyze<'_> {
+    type Item<'a> = TableStatistics;
+
+    #[instrument(name = "TableAnalyze", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        let indexes = TableIndexes::load(db, self.table).await?;
+        let mut stats = TableStatistics::default();
+        let mut seq_scan = SeqScan::new(self.table);
+        while let Some(record) = seq_scan.next(db).await? {
+            if record.is_deleted() {
+                continue;
+            }
+            let values = record.as_data();
+            stats.row_count += 1;
+            stats.total_size += values.size() as u64;
+            for (column, ordinal) in indexes.columns() {
+                let value = &values.as_slice()[ordinal];
+                match stats
+                    .columns
+                    .iter_mut()
+                    .find(|stats| stats.column == column)
+                {
+                    Some(stats) => stats.widen(value),
+                    None => stats.columns.push(ColumnStatistics::new(column, value)),
+                }
+            }
+        }
+        stats.page_count = table_page_count(db, self.table).await?;
+        debug!(?stats, "analyzed table");
+
+        statistics::replace(db, self.table, stats.clone()).await?;
+        db.pager().flush().await?;
+        Ok(Some(stats))
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableAnalyze").with("table", &self.table.name))
+    }
+}
+
+impl<'a> Analyze<'a> {
+    /// Creates a new analyze executor.
+    pub fn new(table: &'a TableObject) -> Analyze<'a> {
+        Self { table, done: false }
+    }
+}
@@ -0,0 +1,272 @@
+use std::borrow::Cow;
+
+use async_trait::async_trait;
+use tracing::{debug, error, instrument};
+
+use crate::{
+    catalog::{
+        object::TableObject,
+        page::{HeapPage, PageId, SpecificPage},
+        record::simple_record::{self, SimpleRecord},
+    },
+    error::{DbResult, Error},
+    exec::{
+        notify::ChangeKind,
+        operations::{heap::span, PhysicalState},
+        query::{
+            table::{RecordId, TableIndexes},
+            Plan, Query,
+        },
+        util::macros::seq_h,
+        values::{SchematizedValues, Values},
+    },
+    util::io::{SerializeCtx, Size},
+    Db,
+};
+
+/// A bulk insert query.
+///
+/// Unlike many [`Insert`](super::Insert)s, the records are sequentially written
+/// to the heap pages, all continuation pages are allocated at once (except for
+/// the ones of records that span many pages), the sequence header is updated
+/// once and a single flush is performed.
+///
+/// All records are validated before any of them is written.
+pub struct BulkInsert<'a> {
+    /// The table object.
+    table: &'a TableObject,
+    /// The values to be inserted.
+    rows: Vec<Values>,
+}
+
+#[async_trait]
+impl Query for BulkInsert<'_> {
+    type Item<'a> = ();
+
+    #[instrument(name = "TableBulkInsert", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.rows.is_empty() {
+            return Ok(None);
+        }
+        let page_id = self.table.page_id;
+        let schema = &self.table.schema;
+
+        let indexes = TableIndexes::load(db, self.table).await?;
+        let rows = std::mem::take(&mut self.rows)
+            .into_iter()
+            .map(|values| values.try_into_schematized(schema))
+            .collect::<DbResult<Vec<_>>>()?;
+        let sizes: Vec<_> = rows
+            .iter()
+            .map(|values| {
+                indexes.check(db, values)?;
+                Ok(record(PageId::FIRST, 0, values).size())
+            })
+            .collect::<DbResult<_>>()?;
+
+        let capacity = HeapPage::new_seq_node(db.pager().usable_size(), PageId::FIRST).free_space();
+        if let Some(size) = sizes.iter().find(|size| **size > span::MAX_RECORD_SIZE) {
+            error!(size, "record size exceeded maximum record size");
+            return Err(Error::ExecError(format!(
+                "record size ({size}) exceeds the maximum record size"
+            )));
+        }
+
+        db.invalidate_results(&self.table.name);
+        debug!(?page_id, "getting page");
+        let guard = db.pager().get::<HeapPage>(page_id).await?;
+        let mut page = guard.write().await;
+        let mut last_page_id = seq_h!(page).last_page_id;
+
+        // The records are written in runs of records that fit in a page, each
+        // one followed by (at most) a record that spans many pages.
+        let mut locations = Vec::with_capacity(rows.len());
+        let mut start = 0;
+        while start < rows.len() {
+            let end = (sizes[start..].iter())
+                .position(|size| *size > capacity)
+                .map_or(rows.len(), |i| start + i);
+            let run = Run {
+                rows: &rows[start..end],
+                sizes: &sizes[start..end],
+                spanned: rows.get(end),
+            };
+            let written = if last_page_id != page_id {
+                debug!(?page_id, "getting last page");
+                let last_guard = db.pager().get::<HeapPage>(last_page_id).await?;
+                let mut last = last_guard.write().await;
+
+                let written = write(db, &mut last, run, self.table).await?;
+                last.flush();
+                written
+            } else {
+                write(db, &mut page, run, self.table).await?
+            };
+            locations.extend(written.locations);
+            last_page_id = written.last_page_id;
+            seq_h!(mut page).page_count += written.new_page_count;
+            start = end + 1;
+        }
+        seq_h!(mut page).record_count += rows.len() as u64;
+        seq_h!(mut page).last_page_id = last_page_id;
+        page.flush();
+
+        for (values, location) in rows.iter().zip(&locations) {
+            indexes
+                .insert(db, values, location.page_id, location.offset)
+                .await?;
+        }
+
+        db.pager().flush().await?;
+        debug!(count = rows.len(), "inserted records");
+        db.activity()
+            .record(page_id, |activity| activity.inserts += rows.len() as u64);
+        db.statistics().record(page_id, |delta| {
+            for values in &rows {
+                delta.insert(&indexes, values);
+            }
+        });
+        for location in locations {
+            let id = RecordId::current(db, location.page_id, location.offset).await?;
+            db.notifier()
+                .record(&self.table.name, ChangeKind::Insert, id);
+        }
+
+        Ok(None)
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableBulkInsert")
+            .with("table", &self.table.name)
+            .with("rows", self.rows.len()))
+    }
+}
+
+/// A run of records to be written. See [`write`].
+struct Run<'r> {
+    /// The records which fit in a page, along with their sizes.
+    rows: &'r [SchematizedValues],
+    sizes: &'r [u32],
+    /// The record which follows them, which spans many pages.
+    spanned: Option<&'r SchematizedValues>,
+}
+
+/// The result of a [`Run`] write.
+struct Written {
+    locations: Vec<PhysicalState>,
+    last_page_id: PageId,
+    new_page_count: u32,
+}
+
+/// Writes the given run, starting at the given (last) page, returning the
+/// location of each one of its records. All the needed continuation pages of
+/// the records which fit in a page are allocated at once.
+async fn write(
+    db: &Db,
+    page: &mut HeapPage,
+    run: Run<'_>,
+    table: &TableObject,
+) -> DbResult<Written> {
+    let capacity = HeapPage::new_seq_node(db.pager().usable_size(), PageId::FIRST).free_space();
+
+    // Counts the continuation pages.
+    let mut new_page_count = 0;
+    let mut free = if page.is_fragment() {
+        0
+    } else {
+        page.free_space()
+    };
+    for &size in run.sizes {
+        if size > free {
+            new_page_count += 1;
+            free = capacity;
+        }
+        free -= size;
+    }
+
+    debug!(new_page_count, "allocating continuation pages");
+    let guards = db
+        .pager()
+        .alloc_many(new_page_count, HeapPage::new_seq_node)
+        .await?;
+    let mut new_pages = Vec::with_capacity(guards.len());
+    for guard in &guards {
+        new_pages.push(guard.write().await);
+    }
+
+    let mut locations = Vec::with_capacity(run.rows.len() + 1);
+    let mut written = {
+        let mut chain: Vec<&mut HeapPage> = std::iter::once(page)
+            .chain(new_pages.iter_mut().map(|page| &mut **page))
+            .collect();
+        let mut current = 0;
+        for (values, &size) in run.rows.iter().zip(run.sizes) {
+            if !chain[current].can_accommodate(size) {
+                // Links the next page.
+                let next_page_id = chain[current + 1].id();
+                chain[current].header.next_page_id = Some(next_page_id);
+                current += 1;
+            }
+            let page = &mut chain[current];
+            let location = PhysicalState {
+                page_id: page.id(),
+                offset: page.offset(),
+            };
+            let serde_ctx = simple_record::TableRecordCtx {
+                page_id: location.page_id,
+                offset: location.offset,
+                schema: &table.schema,
+            };
+            let record = record(location.page_id, location.offset, values);
+            page.write(|buf| record.serialize(buf, &serde_ctx))?;
+            page.header.record_count += 1;
+            locations.push(location);
+        }
+
+        let last = &mut chain[current];
+        let mut written = Written {
+            locations: Vec::new(),
+            last_page_id: last.id(),
+            new_page_count,
+        };
+        if let Some(values) = run.spanned {
+            let record = record(last.id(), last.offset(), values);
+            let serde_ctx = simple_record::TableRecordCtx {
+                page_id: last.id(),
+                offset: last.offset(),
+                schema: &table.schema,
+            };
+            let mut payload = vec![0; record.size() as usize];
+            record.serialize(&mut buff::Buff::new(&mut payload), &serde_ctx)?;
+            let spanned = span::write(db.pager(), last, &payload).await?;
+            locations.push(spanned.location);
+            written.last_page_id = spanned.last_page_id;
+            written.new_page_count += spanned.new_page_count;
+        }
+        written
+    };
+    written.locations = locations;
+
+    for page in new_pages {
+        page.flush();
+    }
+    Ok(written)
+}
+
+fn record<'a>(
+    page_id: PageId,
+    offset: u16,
+    values: &'a SchematizedValues,
+) -> SimpleRecord<'a, SchematizedValues> {
+    SimpleRecord::new(page_id, offset, Cow::Borrowed(values))
+}
+
+impl<'a> BulkInsert<'a> {
+    /// Creates a new bulk insert executor.
+    pub fn new(table: &'a TableObject, rows: impl IntoIterator<Item = Values>) -> BulkInsert<'a> {
+        Self {
+            table,
+            rows: rows.into_iter().collect(),
+        }
+    }
+}
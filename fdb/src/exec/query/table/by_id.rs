@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::object::TableObject,
+    error::DbResult,
+    exec::{
+        locking::LockSet,
+        query::{
+            table::{
+                delete_record, lock_record, read_record, update_record, PadPolicy, RecordId,
+                TableIndexes, Updater,
+            },
+            Plan, Query,
+        },
+        values::Values,
+    },
+    Db,
+};
+
+/// A query that fetches a single record through its [`RecordId`].
+///
+/// Yields the record's values, or nothing if the record was deleted.
+pub struct GetById<'a> {
+    table: &'a TableObject,
+    id: RecordId,
+    done: bool,
+}
+
+#[async_trait]
+impl Query for GetById<'_> {
+    type Item<'a> = Values;
+
+    const READ_ONLY: bool = true;
+
+    #[instrument(name = "TableGetById", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        let record = read_record(db, self.table, self.id).await?;
+        if record.is_deleted() {
+            debug!(id = %self.id, "record is deleted");
+            return Ok(None);
+        }
+        Ok(Some(
+            record
+                .into_data()
+                .into_owned()
+                .into_values(&self.table.schema),
+        ))
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableGetById")
+            .with("table", &self.table.name)
+            .with("id", self.id))
+    }
+}
+
+impl<'a> GetById<'a> {
+    /// Creates a new get executor.
+    pub fn new(table: &'a TableObject, id: RecordId) -> GetById<'a> {
+        Self {
+            table,
+            id,
+            done: false,
+        }
+    }
+}
+
+/// A query that deletes a single record through its [`RecordId`].
+///
+/// Yields once if the record was deleted, or nothing if it already was.
+pub struct DeleteById<'a> {
+    table: &'a TableObject,
+    id: RecordId,
+    done: bool,
+    /// The lock of the deleted record, held until the query is dropped.
+    locks: Option<LockSet>,
+}
+
+#[async_trait]
+impl Query for DeleteById<'_> {
+    type Item<'a> = ();
+
+    #[instrument(name = "TableDeleteById", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        let mut record = read_record(db, self.table, self.id).await?;
+        if !record.is_deleted() {
+            (record, _) = lock_record(db, self.table, &mut self.locks, record).await?;
+        }
+        if record.is_deleted() {
+            debug!(id = %self.id, "record is deleted");
+            return Ok(None);
+        }
+        let indexes = TableIndexes::load(db, self.table).await?;
+        delete_record(db, self.table, &indexes, record).await?;
+        db.pager().flush().await?;
+        Ok(Some(()))
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableDeleteById")
+            .with("table", &self.table.name)
+            .with("id", self.id))
+    }
+}
+
+impl<'a> DeleteById<'a> {
+    /// Creates a new delete executor.
+    pub fn new(table: &'a TableObject, id: RecordId) -> DeleteById<'a> {
+        Self {
+            table,
+            id,
+            done: false,
+            locks: None,
+        }
+    }
+}
+
+/// A query that updates a single record through its [`RecordId`].
+///
+/// Yields the record's ID after the update, which differs from the original
+/// one if the updated record didn't fit in place, or nothing if the record was
+/// deleted. The record's padding is always kept (see [`PadPolicy::Keep`]).
+pub struct UpdateById<'a> {
+    table: &'a TableObject,
+    id: RecordId,
+    updater: &'a Updater,
+    done: bool,
+    /// The lock of the updated record, held until the query is dropped.
+    locks: Option<LockSet>,
+}
+
+#[async_trait]
+impl Query for UpdateById<'_> {
+    type Item<'a> = RecordId;
+
+    #[instrument(name = "TableUpdateById", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        let mut record = read_record(db, self.table, self.id).await?;
+        if !record.is_deleted() {
+            (record, _) = lock_record(db, self.table, &mut self.locks, record).await?;
+        }
+        if record.is_deleted() {
+            debug!(id = %self.id, "record is deleted");
+            return Ok(None);
+        }
+        let indexes = TableIndexes::load(db, self.table).await?;
+        let (id, _) = update_record(
+            db,
+            self.table,
+            &indexes,
+            record,
+            self.updater,
+            PadPolicy::Keep,
+        )
+        .await?;
+        db.pager().flush().await?;
+        Ok(Some(id))
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableUpdateById")
+            .with("table", &self.table.name)
+            .with("id", self.id))
+    }
+}
+
+impl<'a> UpdateById<'a> {
+    /// Creates a new update executor.
+    pub fn new(table: &'a TableObject, id: RecordId, updater: &'a Updater) -> UpdateById<'a> {
+        Self {
+            table,
+            id,
+            updater,
+            done: false,
+            locks: None,
+        }
+    }
+}
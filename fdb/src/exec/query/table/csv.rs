@@ -0,0 +1,229 @@
+use async_trait::async_trait;
+use tokio::{
+    fs::File,
+    io::{AsyncWriteExt, BufWriter},
+};
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        external_schema::ExternalFormat, object::TableObject, table_schema::TableSchema, ty::TypeId,
+    },
+    error::{DbResult, Error, ErrorContext, ResultExt},
+    exec::{
+        format::{BlobFormat, TimestampFormat, ValueFormat},
+        query::{
+            table::{
+                external_scan::{csv_values, Reader},
+                BulkInsert,
+            },
+            Plan, Query, RecordSource,
+        },
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+/// The format of the exported values, which is the one parsed by the imports
+/// (and by the CSV external tables).
+const EXPORT_FORMAT: ValueFormat = ValueFormat {
+    timestamp: TimestampFormat::Raw,
+    blob: BlobFormat::Hex,
+};
+
+/// A query that imports the records of a CSV file into a table.
+///
+/// The file's header line names the columns of each field, which must exist in
+/// the table. Columns which aren't in the header (as well as empty fields) are
+/// set to their default values. Hence, empty texts are imported as defaults. Fields are parsed according to the column
+/// types, as in the CSV external tables (see [`ExternalScan`](super::ExternalScan)).
+///
+/// The file is fully read and parsed before the rows are written, as a single
+/// [`BulkInsert`]. Hence, a malformed record fails the whole import. Yields once
+/// for each imported row.
+pub struct ImportCsv<'a> {
+    table: &'a TableObject,
+    path: String,
+    /// The number of imported rows yet to be yielded, once imported.
+    remaining: Option<u64>,
+}
+
+#[async_trait]
+impl Query for ImportCsv<'_> {
+    type Item<'a> = ();
+
+    #[instrument(name = "TableImportCsv", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.remaining.is_none() {
+            let count = self
+                .import(db)
+                .await
+                .with_context(|| ErrorContext::Object(self.table.name.clone()))?;
+            self.remaining = Some(count);
+        }
+        let remaining = self.remaining.as_mut().expect("imported above");
+        if *remaining == 0 {
+            return Ok(None);
+        }
+        *remaining -= 1;
+        Ok(Some(()))
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableImportCsv")
+            .with("table", &self.table.name)
+            .with("path", &self.path))
+    }
+}
+
+impl<'a> ImportCsv<'a> {
+    /// Creates a new import executor, which imports the CSV file at the given
+    /// path into the given table.
+    pub fn new(table: &'a TableObject, path: impl Into<String>) -> ImportCsv<'a> {
+        Self {
+            table,
+            path: path.into(),
+            remaining: None,
+        }
+    }
+
+    /// Reads the file and inserts its rows, returning their count.
+    async fn import(&self, db: &Db) -> DbResult<u64> {
+        let schema = &self.table.schema;
+        let mut reader = Reader::open(&self.path, ExternalFormat::Csv).await?;
+        if let Some(unknown) = (reader.header.iter())
+            .find(|name| !schema.columns.iter().any(|column| column.name == **name))
+        {
+            return Err(Error::ExecError(format!(
+                "column `{unknown}` does not exist"
+            )));
+        }
+
+        let mut rows = Vec::new();
+        while let Some((line, record)) = reader.read_record(ExternalFormat::Csv).await? {
+            let values = csv_values(schema, &reader.header, &record)
+                .and_then(|mut values| {
+                    // Validates the row, so that errors point to its line.
+                    values.try_as_schematized(schema)?;
+                    Ok(values)
+                })
+                .with_context(|| ErrorContext::Line {
+                    path: self.path.clone(),
+                    line,
+                })?;
+            rows.push(values);
+        }
+
+        let count = rows.len() as u64;
+        debug!(count, path = self.path, "importing rows");
+        BulkInsert::new(self.table, rows).next(db).await?;
+        Ok(count)
+    }
+}
+
+/// A query that exports the records of a [`RecordSource`] (e.g., a
+/// [`Select`](super::Select)) into a CSV file, which is created (or
+/// truncated).
+///
+/// The header line holds the source columns. Values are written as the imports
+/// (see [`ImportCsv`]) parse them: booleans as `true` or `false`, timestamps as
+/// milliseconds since the Unix epoch, dates and times in ISO-8601 and blobs in
+/// hexadecimal. Fields are quoted if needed. Array columns aren't supported.
+///
+/// Records are streamed to the file, which is flushed once the source is
+/// exhausted. Yields once for each exported row.
+pub struct ExportCsv<S> {
+    source: S,
+    path: String,
+    writer: Option<BufWriter<File>>,
+    done: bool,
+}
+
+#[async_trait]
+impl<S: RecordSource> Query for ExportCsv<S> {
+    type Item<'a> = ();
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    #[instrument(name = "TableExportCsv", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        if self.writer.is_none() {
+            let schema = self.source.schema();
+            let unsupported =
+                (schema.columns.iter()).find(|column| !matches!(column.ty, TypeId::Primitive(_)));
+            if let Some(column) = unsupported {
+                return Err(Error::Cast(format!(
+                    "{} column `{}` isn't supported in CSV files",
+                    column.ty.name(),
+                    column.name
+                )));
+            }
+            let file = File::create(&self.path).await?;
+            debug!(path = self.path, "created export file");
+            let mut writer = BufWriter::new(file);
+            let header = (schema.columns.iter())
+                .map(|column| quote(&column.name))
+                .collect::<Vec<_>>();
+            writer.write_all(header.join(",").as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+            self.writer = Some(writer);
+        }
+        let writer = self.writer.as_mut().expect("created above");
+
+        let Some(row) = self.source.next(db).await? else {
+            writer.flush().await?;
+            self.done = true;
+            return Ok(None);
+        };
+        let line = csv_line(self.source.schema(), &row.into_values(self.source.schema()));
+        writer.write_all(line.as_bytes()).await?;
+        Ok(Some(()))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableExportCsv")
+            .with("path", &self.path)
+            .with_child(self.source.describe(db).await?))
+    }
+}
+
+impl<S: RecordSource> ExportCsv<S> {
+    /// Creates a new export executor, which exports the given source's records
+    /// into the CSV file at the given path.
+    pub fn new(source: S, path: impl Into<String>) -> ExportCsv<S> {
+        Self {
+            source,
+            path: path.into(),
+            writer: None,
+            done: false,
+        }
+    }
+}
+
+/// Formats the given values as a CSV record (including its line break), in the
+/// order of the schema columns.
+fn csv_line(schema: &TableSchema, values: &Values) -> String {
+    let fields: Vec<_> = (schema.columns.iter())
+        .map(|column| match values.get(&column.name) {
+            Some(Value::Text(text)) => quote(text),
+            Some(value) => EXPORT_FORMAT.format(value),
+            None => String::new(),
+        })
+        .collect();
+    let mut line = fields.join(",");
+    line.push('\n');
+    line
+}
+
+/// Quotes the given field if it contains commas, quotes or line breaks.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
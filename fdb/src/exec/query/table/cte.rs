@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    error::DbResult,
+    exec::{
+        query::{Plan, Query},
+        values::Values,
+    },
+    Db,
+};
+
+/// A common table expression (CTE), i.e., a named intermediate query result
+/// which is computed only once and may be referenced many times in a larger
+/// pipeline.
+///
+/// The result is materialized in memory. Hence, it should only be used over
+/// queries whose results fit in memory.
+#[derive(Debug, Clone)]
+pub struct Cte {
+    name: String,
+    rows: Arc<[Values]>,
+}
+
+impl Cte {
+    /// Executes the given query until exhaustion, materializing all of its
+    /// rows under the given name.
+    pub async fn materialize<Q>(db: &Db, name: impl Into<String>, query: Q) -> DbResult<Cte>
+    where
+        Q: for<'a> Query<Item<'a> = Values>,
+    {
+        Self::materialize_where(db, name, query, |_| true).await
+    }
+
+    /// Same as [`Cte::materialize`], but only keeps the rows that satisfy the
+    /// given predicate.
+    #[instrument(name = "TableCteMaterialize", level = "debug", skip_all)]
+    pub async fn materialize_where<Q, F>(
+        db: &Db,
+        name: impl Into<String>,
+        mut query: Q,
+        pred: F,
+    ) -> DbResult<Cte>
+    where
+        Q: for<'a> Query<Item<'a> = Values>,
+        F: Fn(&Values) -> bool,
+    {
+        let name = name.into();
+        let mut rows = Vec::new();
+        while let Some(row) = query.next(db).await? {
+            if pred(&row) {
+                rows.push(row);
+            }
+        }
+        debug!(name, len = rows.len(), "materialized cte");
+
+        Ok(Cte {
+            name,
+            rows: rows.into(),
+        })
+    }
+
+    /// Returns the CTE name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of materialized rows.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Checks whether the CTE has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Returns a new scan over the materialized rows. Many scans (over the same
+    /// CTE) may be active at the same time.
+    pub fn scan(&self) -> CteScan {
+        CteScan {
+            name: self.name.clone(),
+            rows: Arc::clone(&self.rows),
+            cursor: 0,
+        }
+    }
+}
+
+/// A scan over the rows of a [`Cte`].
+pub struct CteScan {
+    name: String,
+    rows: Arc<[Values]>,
+    cursor: usize,
+}
+
+#[async_trait]
+impl Query for CteScan {
+    type Item<'a> = Values;
+
+    const READ_ONLY: bool = true;
+
+    #[instrument(name = "TableCteScan", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, _db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let row = self.rows.get(self.cursor).cloned();
+        if row.is_some() {
+            self.cursor += 1;
+        }
+        Ok(row)
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableCteScan")
+            .with("cte", &self.name)
+            .with("rows", self.rows.len()))
+    }
+}
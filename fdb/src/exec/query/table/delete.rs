@@ -5,9 +5,17 @@ use crate::{
     catalog::{object::TableObject, page::HeapPage, record::simple_record},
     error::DbResult,
     exec::{
-        query::{table::SeqScan, Query},
+        expr::{Expr, Predicate},
+        locking::LockSet,
+        notify::ChangeKind,
+        operations::heap::span,
+        query::{
+            table::{lock_record, Record, RecordId, SeqScan, TableIndexes},
+            Plan, Query,
+        },
         values::Values,
     },
+    io::metrics::Counter,
     util::io::SerializeCtx,
     Db,
 };
@@ -19,7 +27,10 @@ pub type Pred = dyn Sync + for<'v> Fn(&'v Values) -> bool;
 pub struct Delete<'a> {
     table: &'a TableObject,
     seq_scan: SeqScan<'a>,
-    pred: &'a Pred,
+    pred: Predicate<'a>,
+    indexes: Option<TableIndexes>,
+    /// The locks of the deleted records, held until the query is dropped.
+    locks: Option<LockSet>,
 }
 
 #[async_trait]
@@ -29,46 +40,103 @@ impl Query for Delete<'_> {
 
     #[instrument(name = "TableDelete", level = "debug", skip_all)]
     async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.indexes.is_none() {
+            self.pred.check(&self.table.schema)?;
+            self.indexes = Some(TableIndexes::load(db, self.table).await?);
+        }
         loop {
-            let out = if let Some(mut record) = self.seq_scan.next(db).await? {
-                let values = record.as_data().as_values();
-
-                if record.is_deleted() || !(self.pred)(values) {
+            let out = if let Some(record) = self.seq_scan.next(db).await? {
+                let schema = &self.table.schema;
+                let skips = |record: &Record| {
+                    record.is_deleted() || !self.pred.matches(&record.as_data().to_values(schema))
+                };
+                if skips(&record) {
                     continue;
                 }
+                let (record, reread) = lock_record(db, self.table, &mut self.locks, record).await?;
+                if reread && skips(&record) {
+                    continue;
+                }
+                db.pager().metrics().incr(Counter::RecordsMatched);
 
-                let page_id = record.page_id();
-                let offset = record.offset();
-                debug!(?page_id, "allocating page for write");
-                let guard = db.pager().get::<HeapPage>(page_id).await?;
-                let mut page = guard.write().await;
-
-                let ctx = simple_record::TableRecordCtx {
-                    page_id,
-                    offset,
-                    schema: &self.table.schema,
-                };
-
-                record.set_deleted();
-                page.write_at(offset, |buf| record.serialize(buf, &ctx))?;
-
-                page.flush();
+                let indexes = self.indexes.as_ref().expect("loaded above");
+                delete_record(db, self.table, indexes, record).await?;
                 Some(())
             } else {
-                db.pager().flush_all().await?;
+                db.pager().flush().await?;
                 None
             };
             return Ok(out);
         }
     }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableDelete")
+            .with("table", &self.table.name)
+            .with("pred", self.pred.describe())
+            .with_child(self.seq_scan.describe(db).await?))
+    }
 }
 
 impl<'s> Delete<'s> {
     pub fn new(table: &'s TableObject, pred: &'s Pred) -> Delete<'s> {
+        Self::with_predicate(table, Predicate::Fn(pred))
+    }
+
+    /// Creates a delete of the records that match the given (boolean)
+    /// expression, which is type-checked against the table schema.
+    pub fn with_expr(table: &'s TableObject, expr: &'s Expr) -> Delete<'s> {
+        Self::with_predicate(table, Predicate::Expr(expr))
+    }
+
+    fn with_predicate(table: &'s TableObject, pred: Predicate<'s>) -> Delete<'s> {
         Self {
             seq_scan: SeqScan::new(table),
             table,
             pred,
+            indexes: None,
+            locks: None,
         }
     }
 }
+
+/// Marks the given record as deleted and removes it from the table indexes.
+pub(super) async fn delete_record(
+    db: &Db,
+    table: &TableObject,
+    indexes: &TableIndexes,
+    mut record: Record,
+) -> DbResult<()> {
+    let page_id = record.page_id();
+    let offset = record.offset();
+    db.invalidate_results(&table.name);
+    debug!(?page_id, "allocating page for write");
+    let guard = db.pager().get::<HeapPage>(page_id).await?;
+    let mut page = guard.write().await;
+
+    let ctx = simple_record::TableRecordCtx {
+        page_id,
+        offset,
+        schema: &table.schema,
+    };
+
+    record.set_deleted();
+    if record.is_spanned() {
+        span::set_deleted(&mut page, offset);
+    } else {
+        page.write_at(offset, |buf| record.serialize(buf, &ctx))?;
+    }
+
+    let id = RecordId::new(page_id, offset, page.header.layout_version);
+    page.flush();
+
+    db.activity().record(table.page_id, |activity| {
+        activity.deletes += 1;
+        activity.dead_rows += 1;
+    });
+    db.statistics()
+        .record(table.page_id, |delta| delta.delete(record.as_data()));
+    db.notifier().record(&table.name, ChangeKind::Delete, id);
+
+    indexes.delete(db, record.as_data(), page_id, offset).await
+}
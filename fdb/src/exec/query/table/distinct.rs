@@ -0,0 +1,207 @@
+use std::collections::{HashSet, VecDeque};
+
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::table_schema::TableSchema,
+    error::DbResult,
+    exec::{
+        query::{
+            table::{Sort, SortKey},
+            Plan, Query, RecordSource,
+        },
+        value::Value,
+        values::{SchematizedValues, Values},
+    },
+    Db,
+};
+
+/// The default maximum number of distinct records held in memory.
+pub const DEFAULT_WORK_MEM: usize = 4096;
+
+type Row = SchematizedValues;
+
+/// The values of a record, in schema order.
+type Key = Vec<Value>;
+
+/// A query over a [`RecordSource`] which eliminates its duplicate records,
+/// i.e., records whose values are all equal.
+///
+/// The source is read into a hash set until it is exhausted or until more
+/// than `work_mem` distinct records were read. In the former case, the records
+/// are yielded in the order they first appeared in the source. In the latter,
+/// the distinct records and the rest of the source are sorted by all columns,
+/// using the external [`Sort`], and the duplicates are skipped as they become
+/// adjacent. Hence, in that case, the records are yielded in ascending order.
+pub struct Distinct<S> {
+    state: State<S>,
+    schema: TableSchema,
+    work_mem: usize,
+    peeked: Option<Row>,
+}
+
+enum State<S> {
+    Initial(S),
+    Hashed(VecDeque<Row>),
+    Sorted {
+        sort: Box<Sort<Spilled<S>>>,
+        last: Option<Key>,
+    },
+    /// Only used while the state is being replaced.
+    Empty,
+}
+
+#[async_trait]
+impl<S: RecordSource> Query for Distinct<S> {
+    type Item<'a> = Values;
+
+    #[instrument(name = "TableDistinct", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_record = RecordSource::next(self, db).await?;
+        let schema = RecordSource::schema(self);
+        Ok(maybe_record.map(|record| record.into_values(schema)))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        RecordSource::describe(self, db).await
+    }
+}
+
+#[async_trait]
+impl<S: RecordSource> RecordSource for Distinct<S> {
+    fn schema(&self) -> &TableSchema {
+        &self.schema
+    }
+
+    async fn next(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        if let Some(row) = self.peeked.take() {
+            return Ok(Some(row));
+        }
+        if let State::Initial(_) = self.state {
+            self.state = self.run(db).await?;
+        }
+        match &mut self.state {
+            State::Hashed(rows) => Ok(rows.pop_front()),
+            State::Sorted { sort, last } => loop {
+                let Some(row) = RecordSource::next(sort.as_mut(), db).await? else {
+                    return Ok(None);
+                };
+                let key = row.as_slice().to_vec();
+                if last.as_ref() != Some(&key) {
+                    *last = Some(key);
+                    return Ok(Some(row));
+                }
+            },
+            State::Initial(_) | State::Empty => unreachable!(),
+        }
+    }
+
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        if self.peeked.is_none() {
+            self.peeked = RecordSource::next(self, db).await?;
+        }
+        Ok(self.peeked.clone())
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let plan = Plan::new("TableDistinct").with("work_mem", self.work_mem);
+        match &mut self.state {
+            State::Initial(source) => Ok(plan.with_child(source.describe(db).await?)),
+            State::Sorted { sort, .. } => Ok(plan
+                .with("strategy", "sort")
+                .with_child(RecordSource::describe(sort.as_mut(), db).await?)),
+            State::Hashed(_) => Ok(plan.with("strategy", "hash")),
+            State::Empty => unreachable!(),
+        }
+    }
+}
+
+impl<S: RecordSource> Distinct<S> {
+    /// Creates a new distinct executor over the given source.
+    pub fn new(source: S) -> Distinct<S> {
+        Self {
+            schema: source.schema().clone(),
+            state: State::Initial(source),
+            work_mem: DEFAULT_WORK_MEM,
+            peeked: None,
+        }
+    }
+
+    /// Sets the maximum number of distinct records held in memory, beyond
+    /// which the records are deduplicated by sorting. It is also used as the
+    /// run size of the sort (see [`Sort::with_run_size`]).
+    pub fn with_work_mem(mut self, work_mem: usize) -> Distinct<S> {
+        self.work_mem = work_mem.max(1);
+        self
+    }
+
+    /// Reads the source into a hash set, falling back to a sort if the distinct
+    /// records don't fit in `work_mem`.
+    async fn run(&mut self, db: &Db) -> DbResult<State<S>> {
+        let State::Initial(mut source) = std::mem::replace(&mut self.state, State::Empty) else {
+            unreachable!();
+        };
+        let mut seen = HashSet::new();
+        let mut rows = VecDeque::new();
+        while let Some(row) = source.next(db).await? {
+            if !seen.insert(row.as_slice().to_vec()) {
+                continue;
+            }
+            rows.push_back(row);
+            if rows.len() > self.work_mem {
+                debug!(work_mem = self.work_mem, "spilling to sort");
+                let keys = (self.schema.columns.iter())
+                    .map(|column| SortKey::asc(&column.name))
+                    .collect();
+                let spilled = Spilled {
+                    rows,
+                    source,
+                    schema: self.schema.clone(),
+                };
+                let sort = Sort::new(spilled, keys).with_run_size(self.work_mem);
+                return Ok(State::Sorted {
+                    sort: Box::new(sort),
+                    last: None,
+                });
+            }
+        }
+        debug!(len = rows.len(), "deduplicated in memory");
+        Ok(State::Hashed(rows))
+    }
+}
+
+/// The source of a spilled distinct: the distinct records read so far,
+/// followed by the rest of the original source.
+struct Spilled<S> {
+    rows: VecDeque<Row>,
+    source: S,
+    schema: TableSchema,
+}
+
+#[async_trait]
+impl<S: RecordSource> RecordSource for Spilled<S> {
+    fn schema(&self) -> &TableSchema {
+        &self.schema
+    }
+
+    async fn next(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        match self.rows.pop_front() {
+            Some(row) => Ok(Some(row)),
+            None => self.source.next(db).await,
+        }
+    }
+
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        match self.rows.front() {
+            Some(row) => Ok(Some(row.clone())),
+            None => self.source.peek(db).await,
+        }
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("Spilled")
+            .with("rows", self.rows.len())
+            .with_child(self.source.describe(db).await?))
+    }
+}
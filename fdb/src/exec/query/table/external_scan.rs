@@ -0,0 +1,415 @@
+use async_trait::async_trait;
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, BufReader},
+};
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        external_schema::ExternalFormat,
+        object::ExternalTableObject,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error, ErrorContext, ResultExt},
+    exec::{
+        query::{Plan, Query, RecordSource},
+        time::{self, Date, Time},
+        value::{Decimal, Float, Value},
+        values::{SchematizedValues, Values},
+    },
+    io::segment::Segment,
+    sql::lexer::decode_hex,
+    Db,
+};
+
+type Row = SchematizedValues;
+
+/// A scan over an external table, which streams the rows from its file.
+///
+/// Each CSV or JSONL record is converted into a row of the table's declared
+/// schema. CSV fields are matched to the columns through the header line, and
+/// JSON fields through their keys. Unknown fields are ignored, while missing
+/// ones (as well as empty CSV fields and JSON nulls) are set to the column's
+/// default value.
+///
+/// CSV fields are parsed according to the column types. Booleans are written
+/// as `true` or `false`, timestamps as milliseconds since the Unix epoch (or
+/// ISO-8601 strings, also in JSON), dates and times in ISO-8601 (see
+/// [`time`]) and blobs in hexadecimal (also in JSON strings). Arrays are only
+/// supported in JSON.
+///
+/// Segment files (see [`Segment`]) are read and verified as a whole when the
+/// scan starts, and their schema must match the table's declared schema.
+pub struct ExternalScan<'a> {
+    table: &'a ExternalTableObject,
+    reader: Option<Reader>,
+    /// The remaining rows of a segment file.
+    segment_rows: Option<std::vec::IntoIter<Row>>,
+    peeked: Option<Row>,
+}
+
+/// An open line-based file (i.e., CSV or JSONL). Also used by the imports
+/// (see [`ImportCsv`](super::ImportCsv) and [`ImportJsonl`](super::ImportJsonl)).
+pub(super) struct Reader {
+    path: String,
+    lines: BufReader<File>,
+    /// The number of read lines.
+    line: u64,
+    /// The column names of the CSV header line.
+    pub(super) header: Vec<String>,
+}
+
+#[async_trait]
+impl Query for ExternalScan<'_> {
+    type Item<'a> = Values;
+
+    const READ_ONLY: bool = true;
+
+    #[instrument(name = "TableExternalScan", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_row = RecordSource::next(self, db).await?;
+        let schema = RecordSource::schema(self);
+        Ok(maybe_row.map(|row| row.into_values(schema)))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        RecordSource::describe(self, db).await
+    }
+}
+
+#[async_trait]
+impl RecordSource for ExternalScan<'_> {
+    fn schema(&self) -> &TableSchema {
+        &self.table.schema.schema
+    }
+
+    const READ_ONLY: bool = true;
+
+    async fn next(&mut self, _db: &Db) -> DbResult<Option<Row>> {
+        match self.peeked.take() {
+            Some(row) => Ok(Some(row)),
+            None => self.read_row().await,
+        }
+    }
+
+    async fn peek(&mut self, _db: &Db) -> DbResult<Option<Row>> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_row().await?;
+        }
+        Ok(self.peeked.clone())
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        let schema = &self.table.schema;
+        Ok(Plan::new("TableExternalScan")
+            .with("table", &self.table.name)
+            .with("format", schema.format.name())
+            .with("path", &schema.path))
+    }
+}
+
+impl<'a> ExternalScan<'a> {
+    /// Creates a new scan executor over the given external table.
+    pub fn new(table: &'a ExternalTableObject) -> ExternalScan<'a> {
+        Self {
+            table,
+            reader: None,
+            segment_rows: None,
+            peeked: None,
+        }
+    }
+
+    /// Reads the next row, opening the file on the first call.
+    async fn read_row(&mut self) -> DbResult<Option<Row>> {
+        let table = self.table;
+        if table.schema.format == ExternalFormat::Segment {
+            if self.segment_rows.is_none() {
+                let rows = read_segment(table)
+                    .await
+                    .with_context(|| ErrorContext::Object(table.name.clone()))?;
+                self.segment_rows = Some(rows.into_iter());
+            }
+            return Ok(self.segment_rows.as_mut().expect("read above").next());
+        }
+        if self.reader.is_none() {
+            self.reader = Some(
+                Reader::open(&table.schema.path, table.schema.format)
+                    .await
+                    .with_context(|| ErrorContext::Object(table.name.clone()))?,
+            );
+        }
+        let reader = self.reader.as_mut().expect("opened above");
+
+        let Some((line, record)) = reader.read_record(table.schema.format).await? else {
+            return Ok(None);
+        };
+        let schema = &table.schema.schema;
+        let values = match table.schema.format {
+            ExternalFormat::Csv => csv_values(schema, &reader.header, &record),
+            ExternalFormat::Jsonl => json_values(schema, &record),
+            ExternalFormat::Segment => unreachable!("segments aren't line-based"),
+        };
+        let row = values.and_then(|values| values.try_into_schematized(schema));
+        row.map(Some).with_context(|| ErrorContext::Line {
+            path: table.schema.path.clone(),
+            line,
+        })
+    }
+}
+
+impl Reader {
+    /// Opens the file at the given path, reading its header line if it's a CSV
+    /// file.
+    pub(super) async fn open(path: &str, format: ExternalFormat) -> DbResult<Reader> {
+        let file = File::open(path).await?;
+        debug!(path, "opened external file");
+        let mut reader = Reader {
+            path: path.to_owned(),
+            lines: BufReader::new(file),
+            line: 0,
+            header: Vec::new(),
+        };
+        if format == ExternalFormat::Csv {
+            if let Some((_, header)) = reader.read_record(ExternalFormat::Csv).await? {
+                reader.header = parse_csv(&header).expect("complete record");
+            }
+        }
+        Ok(reader)
+    }
+
+    /// Reads the next non-blank record, returning its first line number. CSV
+    /// records may span many lines (within quoted fields).
+    pub(super) async fn read_record(
+        &mut self,
+        format: ExternalFormat,
+    ) -> DbResult<Option<(u64, String)>> {
+        let mut record = String::new();
+        loop {
+            let start = self.line + 1;
+            record.clear();
+            loop {
+                if self.lines.read_line(&mut record).await? == 0 {
+                    if record.is_empty() {
+                        return Ok(None);
+                    }
+                    return Err(Error::ExecError("unterminated quoted field".into())).with_context(
+                        || ErrorContext::Line {
+                            path: self.path.clone(),
+                            line: start,
+                        },
+                    );
+                }
+                self.line += 1;
+                let complete = match format {
+                    ExternalFormat::Csv => parse_csv(trim_newline(&record)).is_some(),
+                    ExternalFormat::Jsonl | ExternalFormat::Segment => true,
+                };
+                if complete {
+                    break;
+                }
+            }
+            let trimmed = trim_newline(&record);
+            if !trimmed.trim().is_empty() {
+                return Ok(Some((start, trimmed.to_owned())));
+            }
+        }
+    }
+}
+
+/// Reads the segment file of the given table, checking its schema.
+async fn read_segment(table: &ExternalTableObject) -> DbResult<Vec<Row>> {
+    let segment = Segment::read(&table.schema.path).await?;
+    if !segment.has_schema(&table.schema.schema) {
+        return Err(Error::ExecError(format!(
+            "segment `{}` doesn't match the table schema",
+            table.schema.path
+        )));
+    }
+    debug!(
+        path = table.schema.path,
+        rows = segment.rows.len(),
+        "read segment"
+    );
+    Ok(segment.rows)
+}
+
+fn trim_newline(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+/// Splits the given CSV record into its fields. Fields may be quoted, in which
+/// case they may contain commas, line breaks and escaped (i.e., doubled)
+/// quotes. Returns `None` if the record ends within a quoted field.
+pub(super) fn parse_csv(record: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+/// Converts the given CSV record into values, according to the header.
+pub(super) fn csv_values(
+    schema: &TableSchema,
+    header: &[String],
+    record: &str,
+) -> DbResult<Values> {
+    let fields = parse_csv(record).expect("complete record");
+    if fields.len() != header.len() {
+        return Err(Error::ExecError(format!(
+            "expected {} fields, but got {}",
+            header.len(),
+            fields.len()
+        )));
+    }
+    let mut values = Values::new();
+    for column in &schema.columns {
+        let Some(i) = header.iter().position(|name| *name == column.name) else {
+            continue;
+        };
+        if fields[i].is_empty() {
+            continue;
+        }
+        let TypeId::Primitive(primitive) = column.ty else {
+            return Err(Error::Cast(format!(
+                "{} column `{}` isn't supported in CSV files",
+                column.ty.name(),
+                column.name
+            )));
+        };
+        let value = parse_primitive(&fields[i], primitive)
+            .ok_or_else(|| cast_error(&fields[i], column.ty, &column.name))?;
+        values.set(column.name.clone(), value);
+    }
+    Ok(values)
+}
+
+/// Parses the textual representation of a value of the given type.
+fn parse_primitive(text: &str, primitive: PrimitiveTypeId) -> Option<Value> {
+    Some(match primitive {
+        PrimitiveTypeId::Bool => match text {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => return None,
+        },
+        PrimitiveTypeId::Byte => Value::Byte(text.parse().ok()?),
+        PrimitiveTypeId::ShortInt => Value::ShortInt(text.parse().ok()?),
+        PrimitiveTypeId::Int => Value::Int(text.parse().ok()?),
+        PrimitiveTypeId::BigInt => Value::BigInt(text.parse().ok()?),
+        PrimitiveTypeId::Timestamp => {
+            Value::Timestamp(text.parse().ok().or_else(|| time::parse_timestamp(text))?)
+        }
+        PrimitiveTypeId::Text => Value::Text(text.to_owned()),
+        PrimitiveTypeId::Blob => Value::Blob(decode_hex(text)?),
+        PrimitiveTypeId::Float => Value::Float(Float(text.parse().ok()?)),
+        PrimitiveTypeId::Decimal(scale) => Value::Decimal(Decimal::parse(text, scale)?),
+        PrimitiveTypeId::Date => Value::Date(Date::parse(text)?),
+        PrimitiveTypeId::Time => Value::Time(Time::parse(text)?),
+    })
+}
+
+/// Converts the given JSON object into values.
+fn json_values(schema: &TableSchema, record: &str) -> DbResult<Values> {
+    let json: serde_json::Value = serde_json::from_str(record)
+        .map_err(|error| Error::ExecError(format!("invalid JSON: {error}")))?;
+    let serde_json::Value::Object(object) = json else {
+        return Err(Error::ExecError("expected a JSON object".into()));
+    };
+    let mut values = Values::new();
+    for column in &schema.columns {
+        let Some(json) = object.get(&column.name).filter(|json| !json.is_null()) else {
+            continue;
+        };
+        let value = json_value(json, column.ty)
+            .ok_or_else(|| cast_error(&json.to_string(), column.ty, &column.name))?;
+        values.set(column.name.clone(), value);
+    }
+    Ok(values)
+}
+
+/// Converts the given JSON value into a value of the given type.
+fn json_value(json: &serde_json::Value, ty: TypeId) -> Option<Value> {
+    use serde_json::Value as Json;
+
+    let primitive = match ty {
+        TypeId::Primitive(primitive) => primitive,
+        // As for the records, missing (or `null`) fields take their default
+        // values, while unknown ones are ignored.
+        TypeId::Composite(ty) => {
+            let Json::Object(object) = json else {
+                return None;
+            };
+            let mut fields = Values::new();
+            for field in &ty.fields().columns {
+                let Some(json) = object.get(&field.name).filter(|json| !json.is_null()) else {
+                    continue;
+                };
+                fields.set(field.name.clone(), json_value(json, field.ty)?);
+            }
+            let fields = fields.try_into_schematized(ty.fields()).ok()?;
+            return Some(Value::Composite(ty, fields));
+        }
+        ty => {
+            let element_type = ty.element_type().expect("array type");
+            let elements = json
+                .as_array()?
+                .iter()
+                .map(|element| json_value(element, element_type))
+                .collect::<Option<_>>()?;
+            return Some(Value::array(ty, elements));
+        }
+    };
+    Some(match (json, primitive) {
+        (Json::Bool(bool), PrimitiveTypeId::Bool) => Value::Bool(*bool),
+        (Json::Number(number), PrimitiveTypeId::Byte) => {
+            Value::Byte(number.as_i64()?.try_into().ok()?)
+        }
+        (Json::Number(number), PrimitiveTypeId::ShortInt) => {
+            Value::ShortInt(number.as_i64()?.try_into().ok()?)
+        }
+        (Json::Number(number), PrimitiveTypeId::Int) => {
+            Value::Int(number.as_i64()?.try_into().ok()?)
+        }
+        (Json::Number(number), PrimitiveTypeId::BigInt) => Value::BigInt(number.as_i64()?),
+        (Json::Number(number), PrimitiveTypeId::Timestamp) => Value::Timestamp(number.as_i64()?),
+        (Json::String(text), PrimitiveTypeId::Text) => Value::Text(text.clone()),
+        (Json::String(hex), PrimitiveTypeId::Blob) => Value::Blob(decode_hex(hex)?),
+        (
+            Json::Number(_) | Json::String(_),
+            PrimitiveTypeId::Float | PrimitiveTypeId::Decimal(_),
+        )
+        | (
+            Json::String(_),
+            PrimitiveTypeId::Timestamp | PrimitiveTypeId::Date | PrimitiveTypeId::Time,
+        ) => Value::from_json(json, TypeId::Primitive(primitive))?,
+        _ => return None,
+    })
+}
+
+fn cast_error(raw: &str, ty: TypeId, column: &str) -> Error {
+    Error::Cast(format!(
+        "can't convert {raw} to `{}` for column `{column}`",
+        ty.name()
+    ))
+}
@@ -0,0 +1,94 @@
+use std::{collections::VecDeque, ops::Bound};
+
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{object::TableObject, page::BTreeCell},
+    error::DbResult,
+    exec::{
+        operations::index::BTree,
+        query::{
+            describe_range,
+            table::{read_record, Record, RecordId},
+            Plan, Query,
+        },
+        value::Value,
+    },
+    io::metrics::Counter,
+    Db,
+};
+
+/// An index scan query for tables.
+///
+/// Yields the records whose indexed key is within the given bounds, in key
+/// order.
+pub struct IndexScan<'a> {
+    table: &'a TableObject,
+    tree: BTree,
+    start: Bound<Value>,
+    end: Bound<Value>,
+    cells: Option<VecDeque<BTreeCell>>,
+}
+
+#[async_trait]
+impl Query for IndexScan<'_> {
+    type Item<'a> = Record;
+
+    const READ_ONLY: bool = true;
+
+    #[instrument(name = "TableIndexScan", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_record = self.peek(db).await?;
+        if maybe_record.is_some() {
+            self.cells.as_mut().expect("loaded by peek").pop_front();
+            db.pager().metrics().incr(Counter::RecordsScanned);
+        }
+        Ok(maybe_record)
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableIndexScan")
+            .with("table", &self.table.name)
+            .with("range", describe_range(&self.start, &self.end))
+            .with("height", self.tree.height(db.pager()).await?))
+    }
+}
+
+impl<'a> IndexScan<'a> {
+    /// Creates a new index scan executor over the given index.
+    pub fn new(
+        table: &'a TableObject,
+        tree: BTree,
+        start: Bound<Value>,
+        end: Bound<Value>,
+    ) -> IndexScan<'a> {
+        Self {
+            table,
+            tree,
+            start,
+            end,
+            cells: None,
+        }
+    }
+
+    /// Returns the current element without advancing the underlying iterator.
+    pub async fn peek(&mut self, db: &Db) -> DbResult<Option<Record>> {
+        if self.cells.is_none() {
+            let cells = self
+                .tree
+                .range(db.pager(), self.start.as_ref(), self.end.as_ref())
+                .await?;
+            debug!(count = cells.len(), "loaded index cells");
+            db.activity().record_index_lookup(self.tree.root());
+            self.cells = Some(cells.into());
+        }
+        let Some(cell) = self.cells.as_ref().and_then(VecDeque::front) else {
+            return Ok(None);
+        };
+
+        let id = RecordId::current(db, cell.page_id, cell.offset).await?;
+        let record = read_record(db, self.table, id).await?;
+        Ok(Some(record))
+    }
+}
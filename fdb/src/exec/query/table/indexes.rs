@@ -0,0 +1,192 @@
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        object::{IndexObject, TableObject},
+        page::{BTreeCell, PageId},
+    },
+    error::DbResult,
+    exec::{
+        operations::index::{self, BTree},
+        query::{self, Query},
+        values::SchematizedValues,
+    },
+    Db,
+};
+
+/// The indexes defined over a table, used to keep them in sync with the table
+/// records.
+pub(crate) struct TableIndexes {
+    /// The indexes, along with the ordinals of their columns in the table
+    /// schema.
+    indexes: Vec<(IndexObject, usize)>,
+}
+
+impl TableIndexes {
+    /// Loads the indexes defined over the given table from the database schema.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn load(db: &Db, table: &TableObject) -> DbResult<TableIndexes> {
+        let mut indexes = Vec::new();
+        let mut select = query::object::Select::new();
+        while let Some(object) = select.next(db).await? {
+            let Ok(index) = object.try_into_index() else {
+                continue;
+            };
+            if index.schema.table == table.name {
+                let ordinal = table
+                    .schema
+                    .ordinal(&index.schema.column)
+                    .expect("indexed column must exist");
+                indexes.push((index, ordinal));
+            }
+        }
+        debug!(count = indexes.len(), "loaded table indexes");
+        Ok(TableIndexes { indexes })
+    }
+
+    /// Checks whether the table has no indexes.
+    pub fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
+
+    /// Returns the indexed columns, along with their ordinals in the table
+    /// schema. Columns with many indexes are returned once for each index.
+    pub fn columns(&self) -> impl Iterator<Item = (&str, usize)> {
+        (self.indexes.iter()).map(|(index, ordinal)| (index.schema.column.as_str(), *ordinal))
+    }
+
+    /// Returns the index over the given column, if any. If there are many, the
+    /// shallowest one is returned, as its lookups read fewer pages.
+    pub async fn find(&self, db: &Db, column: &str) -> DbResult<Option<BTree>> {
+        let candidates: Vec<_> = self
+            .indexes
+            .iter()
+            .filter(|(index, _)| index.schema.column == column)
+            .map(|(index, _)| BTree::new(index.page_id))
+            .collect();
+        if candidates.len() <= 1 {
+            return Ok(candidates.first().copied());
+        }
+        let mut best = None;
+        for tree in candidates {
+            let height = tree.height(db.pager()).await?;
+            if best.is_none_or(|(_, best_height)| height < best_height) {
+                best = Some((tree, height));
+            }
+        }
+        Ok(best.map(|(tree, _)| tree))
+    }
+
+    /// Returns the index with the given name, if it is defined over the table.
+    pub fn find_by_name(&self, name: &str) -> Option<&IndexObject> {
+        (self.indexes.iter())
+            .map(|(index, _)| index)
+            .find(|index| index.name == name)
+    }
+
+    /// Returns the index whose root is the given page, if it is defined over
+    /// the table.
+    pub fn find_by_root(&self, root: PageId) -> Option<&IndexObject> {
+        (self.indexes.iter())
+            .map(|(index, _)| index)
+            .find(|index| index.page_id == root)
+    }
+
+    /// Checks whether the given record may be indexed. Must be called before
+    /// the record is written, so that it isn't left out of the indexes.
+    pub fn check(&self, db: &Db, values: &SchematizedValues) -> DbResult<()> {
+        for (_, ordinal) in &self.indexes {
+            // The location doesn't affect the cell size.
+            let cell = cell(*ordinal, values, PageId::FIRST, 0);
+            index::check(db.pager(), &cell)?;
+        }
+        Ok(())
+    }
+
+    /// Indexes the given record, which is at the given location.
+    pub async fn insert(
+        &self,
+        db: &Db,
+        values: &SchematizedValues,
+        page_id: PageId,
+        offset: u16,
+    ) -> DbResult<()> {
+        for (index, ordinal) in &self.indexes {
+            let cell = cell(*ordinal, values, page_id, offset);
+            BTree::new(index.page_id).insert(db.pager(), cell).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes the given record, which is at the given location, from the
+    /// indexes.
+    pub async fn delete(
+        &self,
+        db: &Db,
+        values: &SchematizedValues,
+        page_id: PageId,
+        offset: u16,
+    ) -> DbResult<()> {
+        for (index, ordinal) in &self.indexes {
+            let cell = cell(*ordinal, values, page_id, offset);
+            BTree::new(index.page_id).delete(db.pager(), &cell).await?;
+        }
+        Ok(())
+    }
+
+    /// Re-indexes the given record, which was moved within the given page
+    /// (e.g., by a vacuum).
+    pub async fn relocate(
+        &self,
+        db: &Db,
+        values: &SchematizedValues,
+        page_id: PageId,
+        from: u16,
+        to: u16,
+    ) -> DbResult<()> {
+        for (index, ordinal) in &self.indexes {
+            let tree = BTree::new(index.page_id);
+            tree.delete(db.pager(), &cell(*ordinal, values, page_id, from))
+                .await?;
+            tree.insert(db.pager(), cell(*ordinal, values, page_id, to))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Re-indexes the given record, which was updated in place. Only the
+    /// indexes whose key changed are modified.
+    pub async fn update(
+        &self,
+        db: &Db,
+        old: &SchematizedValues,
+        new: &SchematizedValues,
+        page_id: PageId,
+        offset: u16,
+    ) -> DbResult<()> {
+        for (index, ordinal) in &self.indexes {
+            if old.get_ordinal(*ordinal) == new.get_ordinal(*ordinal) {
+                continue;
+            }
+            let tree = BTree::new(index.page_id);
+            tree.delete(db.pager(), &cell(*ordinal, old, page_id, offset))
+                .await?;
+            tree.insert(db.pager(), cell(*ordinal, new, page_id, offset))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the index cell of the given record, whose key is the value of the
+/// column at the given ordinal.
+fn cell(ordinal: usize, values: &SchematizedValues, page_id: PageId, offset: u16) -> BTreeCell {
+    BTreeCell {
+        key: values
+            .get_ordinal(ordinal)
+            .expect("indexed column must exist")
+            .clone(),
+        page_id,
+        offset,
+    }
+}
@@ -1,22 +1,23 @@
 use std::borrow::Cow;
 
 use async_trait::async_trait;
-use tracing::{debug, error, instrument};
+use tracing::instrument;
 
 use crate::{
     catalog::{
         object::TableObject,
-        page::{HeapPage, PageId, SpecificPage},
         record::simple_record::{self, SimpleRecord},
-        table_schema::TableSchema,
     },
-    error::{DbResult, Error},
+    error::DbResult,
     exec::{
-        query::Query,
-        util::macros::seq_h,
+        notify::ChangeKind,
+        operations::heap,
+        query::{
+            table::{RecordId, TableIndexes},
+            Plan, Query,
+        },
         values::{SchematizedValues, Values},
     },
-    io::pager::Pager,
     util::io::{SerializeCtx, Size},
     Db,
 };
@@ -35,99 +36,17 @@ impl Query for Insert<'_> {
 
     #[instrument(name = "TableInsert", level = "debug", skip_all)]
     async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
-        let page_id = self.table.page_id;
-        let table_schema = &self.table.schema;
-        let schematized_values = self.values.try_as_schematized(table_schema)?;
-
-        debug!(?page_id, "getting page");
-        let guard = db.pager().get::<HeapPage>(page_id).await?;
-        let mut page = guard.write().await;
-        let last_page_id = seq_h!(mut page).last_page_id;
-
-        let maybe_new_last_page_id = if last_page_id != page_id {
-            // If there are more than one page in the heap sequence, one must
-            // write into the last page in the sequence.
-            debug!(?page_id, "getting last page");
-            let last_guard = db.pager().get::<HeapPage>(last_page_id).await?;
-            let mut last = last_guard.write().await;
-
-            let mlp = write(db.pager(), &mut last, table_schema, &schematized_values).await?;
-            last.flush();
-            mlp
-        } else {
-            // Otherwise, one is in the first page.
-            write(db.pager(), &mut page, table_schema, &schematized_values).await?
-        };
-
-        seq_h!(mut page).record_count += 1;
-
-        if let Some(last_page_id) = maybe_new_last_page_id {
-            seq_h!(mut page).last_page_id = last_page_id;
-            seq_h!(mut page).page_count += 1;
-        }
-
-        page.flush();
-
-        db.pager().flush_all().await?;
-
+        let id = self.insert(db).await?;
+        db.activity()
+            .record(self.table.page_id, |activity| activity.inserts += 1);
+        db.notifier()
+            .record(&self.table.name, ChangeKind::Insert, id);
         Ok(None)
     }
-}
-
-/// Writes the given `TableSchema` and, if allocated a new page, returns its ID.
-#[instrument(level = "debug", skip_all)]
-async fn write(
-    pager: &Pager,
-    page: &mut HeapPage,
-    schema: &TableSchema,
-    record: &SchematizedValues<'_>,
-) -> DbResult<Option<PageId>> {
-    let serde_ctx = simple_record::TableRecordCtx {
-        page_id: page.id(),
-        offset: page.offset(),
-        schema,
-    };
-    let record = SimpleRecord::<SchematizedValues>::new(
-        serde_ctx.page_id,
-        serde_ctx.offset,
-        Cow::Borrowed(record),
-    );
-    let size = record.size();
 
-    if page.can_accommodate(size) {
-        debug!("fit right in");
-        page.write(|buf| record.serialize(buf, &serde_ctx))?;
-        page.header.record_count += 1;
-
-        return Ok(None);
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableInsert").with("table", &self.table.name))
     }
-
-    // If the given page can't accommodate the given record, one must allocate a
-    // new page.
-    debug!("allocating new page to insert");
-    let new_page_guard = pager.alloc(HeapPage::new_seq_node).await?;
-    let mut new_page = new_page_guard.write().await;
-    let new_page_id = new_page.id();
-
-    // Sanity check.
-    if !new_page.can_accommodate(size) {
-        error!(size, "record size exceeded maximum page capacity");
-        new_page.flush(); // TODO: Move this page to free list.
-
-        return Err(Error::ExecError(format!(
-            "record size ({size}) exceeds the maximum page capacity"
-        )));
-    }
-
-    new_page.write(|buf| record.serialize(buf, &serde_ctx))?;
-    new_page.header.record_count += 1;
-
-    // Links the new page.
-    page.header.next_page_id = Some(new_page_id);
-
-    new_page.flush();
-
-    Ok(Some(new_page_id))
 }
 
 impl<'a> Insert<'a> {
@@ -135,4 +54,41 @@ impl<'a> Insert<'a> {
     pub fn new(table: &'a TableObject, values: Values) -> Insert<'a> {
         Self { table, values }
     }
+
+    /// Inserts the record, returning its ID.
+    pub(super) async fn insert(&mut self, db: &Db) -> DbResult<RecordId> {
+        let page_id = self.table.page_id;
+        let table_schema = &self.table.schema;
+        let schematized_values = self.values.try_as_schematized(table_schema)?;
+
+        let indexes = TableIndexes::load(db, self.table).await?;
+        indexes.check(db, &schematized_values)?;
+
+        db.invalidate_results(&self.table.name);
+        // Records don't serialize their own locations.
+        let serde_ctx = simple_record::TableRecordCtx {
+            page_id,
+            offset: 0,
+            schema: table_schema,
+        };
+        let record = SimpleRecord::<SchematizedValues>::new(
+            serde_ctx.page_id,
+            serde_ctx.offset,
+            Cow::Borrowed(&schematized_values),
+        );
+        let location = heap::append(db.pager(), page_id, record.size(), |buf| {
+            record.serialize(buf, &serde_ctx)
+        })
+        .await?;
+
+        indexes
+            .insert(db, &schematized_values, location.page_id, location.offset)
+            .await?;
+        db.statistics()
+            .record(page_id, |delta| delta.insert(&indexes, &schematized_values));
+
+        db.pager().flush().await?;
+
+        RecordId::current(db, location.page_id, location.offset).await
+    }
 }
@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{object::TableObject, table_schema::TableSchema, ty::TypeId},
+    error::{DbResult, Error},
+    exec::{
+        expr::{cast, is_integer},
+        query::{table::BulkInsert, Plan, Query, RecordSource},
+        values::Values,
+    },
+    Db,
+};
+
+/// An insert query whose rows are produced by a [`RecordSource`], e.g., a
+/// [`Select`](super::Select) over another table (or the target table itself),
+/// possibly sorted or unnested.
+///
+/// Each target column is fed by a source column (see
+/// [`InsertFrom::with_mapping`]). The mapping is validated once, before the
+/// source is read: both columns must exist and their types must match, except
+/// for integers, which may be converted to other integer types. Values out of
+/// the target type range fail the whole insert.
+///
+/// The source is fully read before the rows are written, as a single
+/// [`BulkInsert`]. Yields once for each inserted row.
+pub struct InsertFrom<'a, S> {
+    table: &'a TableObject,
+    source: S,
+    /// The `(target, source)` column pairs. If `None`, each source column feeds
+    /// the target column with the same name.
+    mapping: Option<Vec<(String, String)>>,
+    /// The number of inserted rows yet to be yielded, once inserted.
+    remaining: Option<u64>,
+}
+
+#[async_trait]
+impl<S: RecordSource> Query for InsertFrom<'_, S> {
+    type Item<'a> = ();
+
+    #[instrument(name = "TableInsertFrom", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.remaining.is_none() {
+            let count = self.insert(db).await?;
+            self.remaining = Some(count);
+        }
+        let remaining = self.remaining.as_mut().expect("inserted above");
+        if *remaining == 0 {
+            return Ok(None);
+        }
+        *remaining -= 1;
+        Ok(Some(()))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let mapping = self.mapping.as_ref().map(|mapping| {
+            (mapping.iter())
+                .map(|(target, source)| format!("{target} <- {source}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+        Ok(Plan::new("TableInsertFrom")
+            .with("table", &self.table.name)
+            .with_opt("mapping", mapping)
+            .with_child(self.source.describe(db).await?))
+    }
+}
+
+impl<'a, S: RecordSource> InsertFrom<'a, S> {
+    /// Creates a new insert executor, which inserts the rows of the given
+    /// source into the given table.
+    pub fn new(table: &'a TableObject, source: S) -> InsertFrom<'a, S> {
+        Self {
+            table,
+            source,
+            mapping: None,
+            remaining: None,
+        }
+    }
+
+    /// Sets the `(target, source)` column pairs. Target columns which aren't
+    /// mapped are set to their default values, and source columns which aren't
+    /// mapped are ignored.
+    pub fn with_mapping(mut self, mapping: &[(&str, &str)]) -> InsertFrom<'a, S> {
+        let mapping = mapping
+            .iter()
+            .map(|&(target, source)| (target.to_owned(), source.to_owned()))
+            .collect();
+        self.mapping = Some(mapping);
+        self
+    }
+
+    /// Reads the source and inserts its rows, returning their count.
+    async fn insert(&mut self, db: &Db) -> DbResult<u64> {
+        let mapping = match self.mapping.take() {
+            Some(mapping) => mapping,
+            None => (self.source.schema().columns.iter())
+                .map(|column| (column.name.clone(), column.name.clone()))
+                .collect(),
+        };
+        let mapping = check_mapping(&self.table.schema, self.source.schema(), mapping)?;
+
+        let mut rows = Vec::new();
+        while let Some(record) = RecordSource::next(&mut self.source, db).await? {
+            let record = record.into_values(self.source.schema());
+            let mut values = Values::new();
+            for (target, source, ty) in &mapping {
+                let value = record.get(source).expect("schema column");
+                let value = cast(value, *ty).ok_or_else(|| {
+                    Error::Cast(format!(
+                        "value {value:?} is out of range for column `{target}`"
+                    ))
+                })?;
+                values.set(target.clone(), value);
+            }
+            rows.push(values);
+        }
+
+        let count = rows.len() as u64;
+        debug!(count, "inserting source rows");
+        BulkInsert::new(self.table, rows).next(db).await?;
+        Ok(count)
+    }
+}
+
+/// Checks the given `(target, source)` column pairs, returning them along with
+/// the target column types.
+fn check_mapping(
+    target: &TableSchema,
+    source: &TableSchema,
+    mapping: Vec<(String, String)>,
+) -> DbResult<Vec<(String, String, TypeId)>> {
+    let column_type = |schema: &TableSchema, name: &str| {
+        (schema.columns.iter())
+            .find(|column| column.name == name)
+            .map(|column| column.ty)
+            .ok_or_else(|| Error::ExecError(format!("column `{name}` does not exist")))
+    };
+    let mut checked: Vec<(String, String, TypeId)> = Vec::with_capacity(mapping.len());
+    for (target_column, source_column) in mapping {
+        if checked
+            .iter()
+            .any(|(column, _, _)| *column == target_column)
+        {
+            return Err(Error::ExecError(format!(
+                "column `{target_column}` specified more than once"
+            )));
+        }
+        let target_ty = column_type(target, &target_column)?;
+        let source_ty = column_type(source, &source_column)?;
+        let compatible = match (source_ty, target_ty) {
+            (TypeId::Primitive(a), TypeId::Primitive(b)) => {
+                a == b || (is_integer(a) && is_integer(b))
+            }
+            (a, b) => a == b,
+        };
+        if !compatible {
+            return Err(Error::Cast(format!(
+                "can't assign column `{source_column}` of type `{}` to column `{target_column}` of type `{}`",
+                source_ty.name(),
+                target_ty.name()
+            )));
+        }
+        checked.push((target_column, source_column, target_ty));
+    }
+    Ok(checked)
+}
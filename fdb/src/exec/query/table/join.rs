@@ -0,0 +1,413 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+};
+
+use async_trait::async_trait;
+use tracing::{debug, instrument, trace};
+
+use crate::{
+    catalog::{
+        object::TableObject,
+        page::{HeapPage, PageId},
+        table_schema::TableSchema,
+    },
+    error::{DbResult, Error, ErrorContext, ResultExt},
+    exec::{
+        operations::heap,
+        query::{
+            table::{seq_scan::mk_deserializer, BulkInsert, Record, TempTable},
+            table_page_count, Plan, Query,
+        },
+        util::macros::seq_h,
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+/// The default maximum number of build records held in memory at once.
+pub const DEFAULT_BUILD_SIZE: usize = 4096;
+
+/// An (inner) equi-join between two tables.
+///
+/// The records of the smaller table (the *build* side) are loaded into an
+/// in-memory hash table, keyed by their join column, which is then probed by a
+/// linear scan over the other table (the *probe* side).
+///
+/// If the build side has more than `build_size` records, both tables are first
+/// partitioned by the hash of their join column into temporary tables, so that
+/// each build partition fits in memory and is joined with its matching probe
+/// partition. Partitions are released as soon as they are joined.
+///
+/// Each yielded row holds the columns of both tables, qualified by their
+/// prefixes (by default, the table names). See
+/// [`qualify`](crate::exec::values::qualify).
+pub struct HashJoin<'a> {
+    left: Input<'a>,
+    right: Input<'a>,
+    build_size: usize,
+    /// Whether the left table is the build side.
+    build_is_left: bool,
+    started: bool,
+    partitions: VecDeque<Partition>,
+    current: Option<Probe>,
+    pending: VecDeque<Values>,
+}
+
+/// One of the joined tables.
+pub(super) struct Input<'a> {
+    pub table: &'a TableObject,
+    pub column: String,
+    pub prefix: String,
+}
+
+impl<'a> Input<'a> {
+    /// Creates an input whose prefix is the table name.
+    pub fn new(table: &'a TableObject, column: String) -> Input<'a> {
+        Input {
+            table,
+            column,
+            prefix: table.name.clone(),
+        }
+    }
+
+    /// Describes the linear scan over the input table.
+    pub async fn describe(&self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableSeqScan")
+            .with("table", &self.table.name)
+            .with_opt(
+                "prefix",
+                (self.prefix != self.table.name).then_some(&self.prefix),
+            )
+            .with("pages", table_page_count(db, self.table).await?))
+    }
+}
+
+/// A pair of build and probe sources with the same join keys.
+struct Partition {
+    build: Scan,
+    probe: Scan,
+    /// The temporary tables which back the partition, if any.
+    tapes: Vec<TempTable>,
+}
+
+/// A loaded partition.
+struct Probe {
+    table: HashMap<Value, Vec<Values>>,
+    probe: Scan,
+    tapes: Vec<TempTable>,
+}
+
+#[async_trait]
+impl Query for HashJoin<'_> {
+    type Item<'a> = Values;
+
+    #[instrument(name = "TableHashJoin", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if !self.started {
+            self.started = true;
+            self.start(db).await?;
+        }
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Ok(Some(row));
+            }
+            let Some(current) = &mut self.current else {
+                match self.partitions.pop_front() {
+                    Some(partition) => self.current = Some(self.load(db, partition).await?),
+                    None => return Ok(None),
+                }
+                continue;
+            };
+            let Some(row) = current.probe.next(db).await? else {
+                let current = self.current.take().expect("current partition");
+                for tape in current.tapes {
+                    tape.destroy(db).await?;
+                }
+                continue;
+            };
+            let table = &self.current.as_ref().expect("current partition").table;
+            let key = row.get(&self.probe_input().column).expect("schematized");
+            if let Some(matches) = table.get(key) {
+                let rows: Vec<_> = (matches.iter())
+                    .map(|build_row| self.combine(build_row.clone(), row.clone()))
+                    .collect();
+                self.pending.extend(rows);
+            }
+        }
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        validate(&self.left, &self.right)?;
+        let left_count = record_count(db, self.left.table).await?;
+        let right_count = record_count(db, self.right.table).await?;
+        let (build, build_count) = if left_count < right_count {
+            (&self.left, left_count)
+        } else {
+            (&self.right, right_count)
+        };
+        let mut plan = Plan::new("TableHashJoin")
+            .with(
+                "on",
+                format!("{} = {}", self.left.column, self.right.column),
+            )
+            .with("build", &build.prefix)
+            .with("build_size", self.build_size);
+        plan = if build_count <= self.build_size as u64 {
+            plan.with("strategy", "in-memory")
+        } else {
+            let count = build_count.div_ceil(self.build_size as u64);
+            plan.with("strategy", "partitioned")
+                .with("partitions", count)
+        };
+        for input in [&self.left, &self.right] {
+            plan = plan.with_child(input.describe(db).await?);
+        }
+        Ok(plan)
+    }
+}
+
+impl<'a> HashJoin<'a> {
+    /// Creates a new hash join executor, which yields the combined records
+    /// whose `left_column` value equals the `right_column` value.
+    pub fn new(
+        left: &'a TableObject,
+        left_column: impl Into<String>,
+        right: &'a TableObject,
+        right_column: impl Into<String>,
+    ) -> HashJoin<'a> {
+        Self {
+            left: Input::new(left, left_column.into()),
+            right: Input::new(right, right_column.into()),
+            build_size: DEFAULT_BUILD_SIZE,
+            build_is_left: false,
+            started: false,
+            partitions: VecDeque::new(),
+            current: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Sets the prefixes of the left and right columns, which default to the
+    /// table names. Distinct prefixes are required to join a table with itself.
+    pub fn with_prefixes(
+        mut self,
+        left: impl Into<String>,
+        right: impl Into<String>,
+    ) -> HashJoin<'a> {
+        self.left.prefix = left.into();
+        self.right.prefix = right.into();
+        self
+    }
+
+    /// Sets the maximum number of build records held in memory at once.
+    pub fn with_build_size(mut self, build_size: usize) -> HashJoin<'a> {
+        self.build_size = build_size.max(1);
+        self
+    }
+
+    /// Validates the join, picks the build side and, if needed, partitions the
+    /// tables.
+    async fn start(&mut self, db: &Db) -> DbResult<()> {
+        validate(&self.left, &self.right)?;
+
+        let left_count = record_count(db, self.left.table).await?;
+        let right_count = record_count(db, self.right.table).await?;
+        self.build_is_left = left_count < right_count;
+        let build_count = left_count.min(right_count);
+
+        let (build, probe) = (self.build_input(), self.probe_input());
+        if build_count <= self.build_size as u64 {
+            debug!(build = build.table.name, build_count, "joining in memory");
+            self.partitions.push_back(Partition {
+                build: Scan::new(build.table),
+                probe: Scan::new(probe.table),
+                tapes: Vec::new(),
+            });
+            return Ok(());
+        }
+
+        let count = build_count.div_ceil(self.build_size as u64) as usize;
+        debug!(build = build.table.name, build_count, count, "partitioning");
+        let build_tapes = self.partition(db, build, count).await?;
+        let probe_tapes = self.partition(db, probe, count).await?;
+        for (build, probe) in build_tapes.into_iter().zip(probe_tapes) {
+            self.partitions.push_back(Partition {
+                build: Scan::new(build.table()),
+                probe: Scan::new(probe.table()),
+                tapes: vec![build, probe],
+            });
+        }
+        Ok(())
+    }
+
+    /// Distributes the records of the given input to `count` temporary tables,
+    /// by the hash of their join column.
+    async fn partition(
+        &self,
+        db: &Db,
+        input: &Input<'_>,
+        count: usize,
+    ) -> DbResult<Vec<TempTable>> {
+        let mut tapes = Vec::with_capacity(count);
+        for _ in 0..count {
+            tapes.push(TempTable::create(db, "join_tape", input.table.schema.clone()).await?);
+        }
+        // At most `build_size` records are buffered at once.
+        let batch_size = (self.build_size / count).max(1);
+        let mut batches = vec![Vec::new(); count];
+        let mut scan = Scan::new(input.table);
+        while let Some(row) = scan.next(db).await? {
+            let i = partition_of(row.get(&input.column).expect("schematized"), count);
+            batches[i].push(row);
+            if batches[i].len() == batch_size {
+                BulkInsert::new(tapes[i].table(), batches[i].drain(..))
+                    .next(db)
+                    .await?;
+            }
+        }
+        for (tape, batch) in tapes.iter().zip(batches) {
+            BulkInsert::new(tape.table(), batch).next(db).await?;
+        }
+        Ok(tapes)
+    }
+
+    /// Loads the build side of the given partition into a hash table.
+    async fn load(&self, db: &Db, mut partition: Partition) -> DbResult<Probe> {
+        let column = &self.build_input().column;
+        let mut table: HashMap<_, Vec<_>> = HashMap::new();
+        let mut len = 0;
+        while let Some(row) = partition.build.next(db).await? {
+            let key = row.get(column).expect("schematized").clone();
+            table.entry(key).or_default().push(row);
+            len += 1;
+        }
+        trace!(len, keys = table.len(), "loaded build partition");
+        Ok(Probe {
+            table,
+            probe: partition.probe,
+            tapes: partition.tapes,
+        })
+    }
+
+    /// Combines a build and a probe record into a qualified row.
+    fn combine(&self, build: Values, probe: Values) -> Values {
+        let (left, right) = if self.build_is_left {
+            (build, probe)
+        } else {
+            (probe, build)
+        };
+        combine((&self.left, left), (&self.right, right))
+    }
+
+    fn build_input(&self) -> &Input<'a> {
+        if self.build_is_left {
+            &self.left
+        } else {
+            &self.right
+        }
+    }
+
+    fn probe_input(&self) -> &Input<'a> {
+        if self.build_is_left {
+            &self.right
+        } else {
+            &self.left
+        }
+    }
+}
+
+/// Checks that both join columns exist and have the same type, and that the
+/// prefixes are distinct.
+pub(super) fn validate(left: &Input, right: &Input) -> DbResult<()> {
+    let mut types = Vec::with_capacity(2);
+    for input in [left, right] {
+        let columns = &input.table.schema.columns;
+        let Some(column) = columns.iter().find(|c| c.name == input.column) else {
+            return Err(Error::ExecError(format!(
+                "column `{}` does not exist in table `{}`",
+                input.column, input.table.name
+            )));
+        };
+        types.push(column.ty);
+    }
+    if types[0] != types[1] {
+        return Err(Error::ExecError(format!(
+            "can't join column `{}` of type `{}` with column `{}` of type `{}`",
+            left.column,
+            types[0].name(),
+            right.column,
+            types[1].name()
+        )));
+    }
+    if left.prefix == right.prefix {
+        return Err(Error::ExecError(format!(
+            "ambiguous join prefix `{}`, distinct prefixes must be set",
+            left.prefix
+        )));
+    }
+    Ok(())
+}
+
+/// Combines a left and a right record into a row of qualified columns.
+pub(super) fn combine((left, l): (&Input, Values), (right, r): (&Input, Values)) -> Values {
+    let mut row = l.into_qualified(&left.prefix);
+    row.extend(r.into_qualified(&right.prefix));
+    row
+}
+
+/// Returns the number of records (including the deleted ones) of the given
+/// table.
+async fn record_count(db: &Db, table: &TableObject) -> DbResult<u64> {
+    db.pager()
+        .read_with(table.page_id, |page: &HeapPage| seq_h!(page).record_count)
+        .await
+}
+
+/// Returns the partition of the given join key.
+fn partition_of(key: &Value, count: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % count as u64) as usize
+}
+
+/// A linear scan over the live records of a table. Unlike [`SeqScan`], it
+/// doesn't borrow the table, so that it may scan temporary tables owned by the
+/// join.
+///
+/// [`SeqScan`]: super::SeqScan
+struct Scan {
+    name: String,
+    schema: TableSchema,
+    seq_scan: heap::SeqScan<Record>,
+}
+
+impl Scan {
+    fn new(table: &TableObject) -> Scan {
+        let page_id: PageId = table.page_id;
+        Scan {
+            name: table.name.clone(),
+            schema: table.schema.clone(),
+            seq_scan: heap::SeqScan::new(page_id),
+        }
+    }
+
+    async fn next(&mut self, db: &Db) -> DbResult<Option<Values>> {
+        loop {
+            let maybe_record = self
+                .seq_scan
+                .next(db, mk_deserializer(&self.schema))
+                .await
+                .with_context(|| ErrorContext::Object(self.name.clone()))?;
+            match maybe_record {
+                Some(record) if record.is_deleted() => continue,
+                Some(record) => {
+                    let values = record.into_data().into_owned();
+                    return Ok(Some(values.into_values(&self.schema)));
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use async_trait::async_trait;
+use tokio::{
+    fs::File,
+    io::{AsyncWriteExt, BufWriter},
+};
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{external_schema::ExternalFormat, object::TableObject},
+    error::{DbResult, Error, ErrorContext, ResultExt},
+    exec::{
+        query::{
+            table::{external_scan::Reader, BulkInsert},
+            Plan, Query, RecordSource,
+        },
+        values::Values,
+    },
+    Db,
+};
+
+/// A query that imports the records of a JSONL (i.e., newline-delimited JSON)
+/// file into a table.
+///
+/// Each line holds a JSON object, whose fields are converted into the values
+/// of the columns of the same name (see [`Values::from_json`]). Blank lines
+/// are skipped.
+///
+/// As in [`ImportCsv`](super::ImportCsv), the file is fully read and parsed
+/// before the rows are written, as a single [`BulkInsert`]. Yields once for
+/// each imported row.
+pub struct ImportJsonl<'a> {
+    table: &'a TableObject,
+    path: String,
+    /// The number of imported rows yet to be yielded, once imported.
+    remaining: Option<u64>,
+}
+
+#[async_trait]
+impl Query for ImportJsonl<'_> {
+    type Item<'a> = ();
+
+    #[instrument(name = "TableImportJsonl", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.remaining.is_none() {
+            let count = self
+                .import(db)
+                .await
+                .with_context(|| ErrorContext::Object(self.table.name.clone()))?;
+            self.remaining = Some(count);
+        }
+        let remaining = self.remaining.as_mut().expect("imported above");
+        if *remaining == 0 {
+            return Ok(None);
+        }
+        *remaining -= 1;
+        Ok(Some(()))
+    }
+
+    async fn describe(&mut self, _db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableImportJsonl")
+            .with("table", &self.table.name)
+            .with("path", &self.path))
+    }
+}
+
+impl<'a> ImportJsonl<'a> {
+    /// Creates a new import executor, which imports the JSONL file at the
+    /// given path into the given table.
+    pub fn new(table: &'a TableObject, path: impl Into<String>) -> ImportJsonl<'a> {
+        Self {
+            table,
+            path: path.into(),
+            remaining: None,
+        }
+    }
+
+    /// Reads the file and inserts its rows, returning their count.
+    async fn import(&self, db: &Db) -> DbResult<u64> {
+        let schema = &self.table.schema;
+        let mut reader = Reader::open(&self.path, ExternalFormat::Jsonl).await?;
+
+        let mut rows = Vec::new();
+        while let Some((line, record)) = reader.read_record(ExternalFormat::Jsonl).await? {
+            let values = serde_json::from_str::<serde_json::Value>(&record)
+                .map_err(|error| Error::ExecError(format!("invalid JSON: {error}")))
+                .and_then(|json| Values::from_json(&json, schema))
+                .and_then(|mut values| {
+                    // Validates the row, so that errors point to its line.
+                    values.try_as_schematized(schema)?;
+                    Ok(values)
+                })
+                .with_context(|| ErrorContext::Line {
+                    path: self.path.clone(),
+                    line,
+                })?;
+            rows.push(values);
+        }
+
+        let count = rows.len() as u64;
+        debug!(count, path = self.path, "importing rows");
+        BulkInsert::new(self.table, rows).next(db).await?;
+        Ok(count)
+    }
+}
+
+/// A query that exports the records of a [`RecordSource`] (e.g., a
+/// [`Select`](super::Select)) into a JSONL (i.e., newline-delimited JSON) file,
+/// which is created (or truncated).
+///
+/// Each record is written as a JSON object (see [`Values::to_json`]), which
+/// is read back by the imports (see [`ImportJsonl`]).
+///
+/// Records are streamed to the file, which is flushed once the source is
+/// exhausted. Yields once for each exported row.
+pub struct ExportJsonl<S> {
+    source: S,
+    path: String,
+    writer: Option<BufWriter<File>>,
+    done: bool,
+}
+
+#[async_trait]
+impl<S: RecordSource> Query for ExportJsonl<S> {
+    type Item<'a> = ();
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    #[instrument(name = "TableExportJsonl", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        if self.writer.is_none() {
+            let file = File::create(&self.path).await?;
+            debug!(path = self.path, "created export file");
+            self.writer = Some(BufWriter::new(file));
+        }
+        let writer = self.writer.as_mut().expect("created above");
+
+        let Some(row) = self.source.next(db).await? else {
+            writer.flush().await?;
+            self.done = true;
+            return Ok(None);
+        };
+        let mut line = row.into_values(self.source.schema()).to_json().to_string();
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+        Ok(Some(()))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableExportJsonl")
+            .with("path", &self.path)
+            .with_child(self.source.describe(db).await?))
+    }
+}
+
+impl<S: RecordSource> ExportJsonl<S> {
+    /// Creates a new export executor, which exports the given source's records
+    /// into the JSONL file at the given path.
+    pub fn new(source: S, path: impl Into<String>) -> ExportJsonl<S> {
+        Self {
+            source,
+            path: path.into(),
+            writer: None,
+            done: false,
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::object::TableObject,
+    error::DbResult,
+    exec::{
+        query::{
+            table::{
+                join::{self, Input},
+                Select, Sort, SortKey, DEFAULT_RUN_SIZE,
+            },
+            Plan, Query, RecordSource,
+        },
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+/// An (inner) equi-join between two tables, which sorts both tables by their
+/// join column and merges them.
+///
+/// Unlike the [`HashJoin`](super::HashJoin), no input has to fit in memory:
+/// the tables are sorted by the external [`Sort`] (at most `run_size` records
+/// are sorted in memory at once), and the merge only holds the right records
+/// which share the current join key.
+///
+/// The rows are yielded in the ascending order of the join key and hold the
+/// columns of both tables, qualified by their prefixes (by default, the table
+/// names). See [`qualify`](crate::exec::values::qualify).
+pub struct MergeJoin<'a> {
+    left: Input<'a>,
+    right: Input<'a>,
+    run_size: usize,
+    state: State<'a>,
+    /// The right records of the current join key.
+    group: Vec<Values>,
+    group_key: Option<Value>,
+    pending: VecDeque<Values>,
+}
+
+enum State<'a> {
+    Initial,
+    Merging {
+        left: Box<Sort<Select<'a>>>,
+        right: Box<Sort<Select<'a>>>,
+    },
+    Done,
+}
+
+#[async_trait]
+impl Query for MergeJoin<'_> {
+    type Item<'a> = Values;
+
+    #[instrument(name = "TableMergeJoin", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if let State::Initial = self.state {
+            join::validate(&self.left, &self.right)?;
+            self.state = State::Merging {
+                left: Box::new(self.sort(&self.left)),
+                right: Box::new(self.sort(&self.right)),
+            };
+        }
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Ok(Some(row));
+            }
+            let State::Merging { left, right } = &mut self.state else {
+                return Ok(None);
+            };
+            let (lc, rc) = (&self.left.column, &self.right.column);
+
+            let Some(l) = RecordSource::peek(left.as_mut(), db).await? else {
+                self.finish(db).await?;
+                continue;
+            };
+            let l_key = l.get(left.schema(), lc).expect("schematized");
+            if let Some(group_key) = &self.group_key {
+                if l_key == group_key {
+                    let l = RecordSource::next(left.as_mut(), db).await?;
+                    let l = l.expect("peeked").into_values(left.schema());
+                    for r in &self.group {
+                        let row = join::combine((&self.left, l.clone()), (&self.right, r.clone()));
+                        self.pending.push_back(row);
+                    }
+                    continue;
+                }
+                self.group.clear();
+                self.group_key = None;
+            }
+
+            let Some(r) = RecordSource::peek(right.as_mut(), db).await? else {
+                self.finish(db).await?;
+                continue;
+            };
+            let r_key = r.get(right.schema(), rc).expect("schematized");
+            // Both columns have the same type.
+            match l_key.partial_cmp(r_key).expect("comparable keys") {
+                Ordering::Less => {
+                    RecordSource::next(left.as_mut(), db).await?;
+                }
+                Ordering::Greater => {
+                    RecordSource::next(right.as_mut(), db).await?;
+                }
+                Ordering::Equal => {
+                    let key = r_key.clone();
+                    while let Some(r) = RecordSource::peek(right.as_mut(), db).await? {
+                        if r.get(right.schema(), rc) != Some(&key) {
+                            break;
+                        }
+                        RecordSource::next(right.as_mut(), db).await?;
+                        self.group.push(r.into_values(right.schema()));
+                    }
+                    self.group_key = Some(key);
+                }
+            }
+        }
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        join::validate(&self.left, &self.right)?;
+        let mut plan = Plan::new("TableMergeJoin")
+            .with(
+                "on",
+                format!("{} = {}", self.left.column, self.right.column),
+            )
+            .with("run_size", self.run_size);
+        for input in [&self.left, &self.right] {
+            let mut sort = self.sort(input);
+            plan = plan.with_child(RecordSource::describe(&mut sort, db).await?);
+        }
+        Ok(plan)
+    }
+}
+
+impl<'a> MergeJoin<'a> {
+    /// Creates a new merge join executor, which yields the combined records
+    /// whose `left_column` value equals the `right_column` value.
+    pub fn new(
+        left: &'a TableObject,
+        left_column: impl Into<String>,
+        right: &'a TableObject,
+        right_column: impl Into<String>,
+    ) -> MergeJoin<'a> {
+        Self {
+            left: Input::new(left, left_column.into()),
+            right: Input::new(right, right_column.into()),
+            run_size: DEFAULT_RUN_SIZE,
+            state: State::Initial,
+            group: Vec::new(),
+            group_key: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Sets the prefixes of the left and right columns, which default to the
+    /// table names. Distinct prefixes are required to join a table with itself.
+    pub fn with_prefixes(
+        mut self,
+        left: impl Into<String>,
+        right: impl Into<String>,
+    ) -> MergeJoin<'a> {
+        self.left.prefix = left.into();
+        self.right.prefix = right.into();
+        self
+    }
+
+    /// Sets the maximum number of records sorted in memory at once, for each
+    /// table. See [`Sort::with_run_size`].
+    pub fn with_run_size(mut self, run_size: usize) -> MergeJoin<'a> {
+        self.run_size = run_size.max(1);
+        self
+    }
+
+    fn sort(&self, input: &Input<'a>) -> Sort<Select<'a>> {
+        let keys = vec![SortKey::asc(&input.column)];
+        Sort::new(Select::new(input.table), keys).with_run_size(self.run_size)
+    }
+
+    /// Releases the sorts once a table is exhausted, since the remaining
+    /// records of the other one can't match.
+    async fn finish(&mut self, db: &Db) -> DbResult<()> {
+        if let State::Merging {
+            mut left,
+            mut right,
+        } = std::mem::replace(&mut self.state, State::Done)
+        {
+            debug!("merged");
+            left.finish(db).await?;
+            right.finish(db).await?;
+        }
+        self.group.clear();
+        Ok(())
+    }
+}
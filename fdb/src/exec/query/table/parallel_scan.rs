@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        object::TableObject,
+        page::{HeapPage, PageId},
+        table_schema::TableSchema,
+    },
+    error::{DbResult, Error, ErrorContext, ResultExt},
+    exec::{
+        operations::{
+            heap::{self, span},
+            PhysicalState,
+        },
+        query::{
+            table::{mk_deserializer, Record},
+            table_page_count, Plan, Query, RecordSource,
+        },
+        values::{SchematizedValues, Values},
+    },
+    io::{metrics::Counter, txn},
+    Db,
+};
+
+type Row = SchematizedValues;
+
+/// The number of records each task may scan ahead of the consumer.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// A table sequence scan which runs in many tasks.
+///
+/// The table's page sequence is partitioned into (at most) the given number of
+/// contiguous page ranges, each one scanned by its own task. The records are
+/// yielded as the tasks produce them, hence in no particular order. The tasks
+/// are spawned on the first call to `next` (or `peek`), and run within the
+/// snapshot of the statement (see [`txn`]).
+///
+/// Since the sequence's header only keeps its first and last pages, the ranges
+/// are computed by walking the page chain before the tasks are spawned. Such
+/// walk only reads the page headers, which are then cached for the tasks.
+pub struct ParallelScan {
+    db: Arc<Db>,
+    table: Arc<TableObject>,
+    tasks: usize,
+    state: Option<State>,
+    peeked: Option<Row>,
+}
+
+struct State {
+    rx: mpsc::Receiver<DbResult<Row>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+#[async_trait]
+impl Query for ParallelScan {
+    type Item<'a> = Values;
+
+    const READ_ONLY: bool = true;
+
+    #[instrument(name = "TableParallelScan", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_row = RecordSource::next(self, db).await?;
+        Ok(maybe_row.map(|row| row.into_values(&self.table.schema)))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        RecordSource::describe(self, db).await
+    }
+}
+
+#[async_trait]
+impl RecordSource for ParallelScan {
+    fn schema(&self) -> &TableSchema {
+        &self.table.schema
+    }
+
+    const READ_ONLY: bool = true;
+
+    async fn next(&mut self, _db: &Db) -> DbResult<Option<Row>> {
+        match self.peeked.take() {
+            Some(row) => Ok(Some(row)),
+            None => self.recv().await,
+        }
+    }
+
+    async fn peek(&mut self, _db: &Db) -> DbResult<Option<Row>> {
+        if self.peeked.is_none() {
+            self.peeked = self.recv().await?;
+        }
+        Ok(self.peeked.clone())
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableParallelScan")
+            .with("table", &self.table.name)
+            .with("tasks", self.tasks)
+            .with("pages", table_page_count(db, &self.table).await?))
+    }
+}
+
+impl ParallelScan {
+    /// Creates a new scan executor over the given table, which runs in (at
+    /// most) `tasks` tasks.
+    pub fn new(db: Arc<Db>, table: &TableObject, tasks: usize) -> ParallelScan {
+        ParallelScan {
+            db,
+            table: Arc::new(table.clone()),
+            tasks: tasks.max(1),
+            state: None,
+            peeked: None,
+        }
+    }
+
+    /// Receives the next record from the tasks, spawning them on the first
+    /// call. Once all of them are done, checks whether any of them panicked.
+    async fn recv(&mut self) -> DbResult<Option<Row>> {
+        if self.state.is_none() {
+            self.state = Some(self.spawn().await?);
+        }
+        let state = self.state.as_mut().expect("spawned above");
+        match state.rx.recv().await {
+            Some(result) => result.map(Some),
+            None => {
+                for handle in state.handles.drain(..) {
+                    handle
+                        .await
+                        .map_err(|error| Error::ExecError(format!("scan task failed: {error}")))?;
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    async fn spawn(&self) -> DbResult<State> {
+        let page_ids = heap::page_ids(self.db.pager(), self.table.page_id)
+            .await
+            .with_context(|| ErrorContext::Object(self.table.name.clone()))?;
+        let range_len = page_ids.len().div_ceil(self.tasks);
+        debug!(pages = page_ids.len(), range_len, "spawning scan tasks");
+
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let snapshot = txn::current();
+        let handles = (page_ids.chunks(range_len))
+            .map(|range| {
+                let db = Arc::clone(&self.db);
+                let table = Arc::clone(&self.table);
+                let range = range.to_vec();
+                let tx = tx.clone();
+                let snapshot = snapshot.clone();
+                tokio::spawn(async move {
+                    let scan = scan(&db, &table, &range, &tx);
+                    let result = match snapshot {
+                        Some(snapshot) => txn::with_snapshot(snapshot, scan).await,
+                        None => scan.await,
+                    };
+                    if let Err(error) = result {
+                        // Fails if the scan was dropped, which doesn't need
+                        // the error anymore.
+                        let _ = tx.send(Err(error)).await;
+                    }
+                })
+            })
+            .collect();
+        Ok(State { rx, handles })
+    }
+}
+
+impl Drop for ParallelScan {
+    fn drop(&mut self) {
+        if let Some(state) = &self.state {
+            for handle in &state.handles {
+                handle.abort();
+            }
+        }
+    }
+}
+
+/// Sends the live records of the given pages to the scan. Stops early if the
+/// scan was dropped.
+async fn scan(
+    db: &Db,
+    table: &TableObject,
+    page_ids: &[PageId],
+    tx: &mpsc::Sender<DbResult<Row>>,
+) -> DbResult<()> {
+    let deserializer = mk_deserializer(&table.schema);
+    for (i, &page_id) in page_ids.iter().enumerate() {
+        db.pager().prefetch(page_ids.get(i + 1).copied());
+        // Notice that the pages holding the fragments of spanned records have
+        // no records of their own.
+        let (record_count, mut offset) = db
+            .pager()
+            .read_with(page_id, |page: &HeapPage| {
+                (page.header.record_count, page.first_offset())
+            })
+            .await?;
+        for _ in 0..record_count {
+            let state = PhysicalState { page_id, offset };
+            let (record, size): (Record, _) = span::read(db, state, &deserializer)
+                .await
+                .context(ErrorContext::Record { page_id, offset })
+                .with_context(|| ErrorContext::Object(table.name.clone()))?;
+            offset += size;
+            db.pager().metrics().incr(Counter::RecordsScanned);
+            if record.is_deleted() {
+                continue;
+            }
+            if tx.send(Ok(record.into_data().into_owned())).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
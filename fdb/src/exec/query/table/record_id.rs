@@ -0,0 +1,148 @@
+use std::fmt;
+
+use tracing::debug;
+
+use crate::{
+    catalog::{
+        object::TableObject,
+        page::{HeapPage, PageId},
+    },
+    error::{DbResult, Error, ErrorContext, ResultExt},
+    exec::{
+        locking::{LockMode, LockSet},
+        operations::{heap::span, PhysicalState},
+        query::table::seq_scan::{mk_deserializer, Record},
+    },
+    Db,
+};
+
+/// The physical address of a table record, i.e., the heap page it lives in and
+/// its offset in the page.
+///
+/// A record ID remains valid while the record isn't moved. Hence, it is stable
+/// across in-place updates, but not across updates that relocate the record
+/// (see [`UpdateById`](super::UpdateById)) or vacuums.
+///
+/// Since moving records shifts the ones after them in the page, the ID also
+/// holds the page's layout version (see [`HeapPage::reclaim`]), so that stale
+/// IDs are rejected instead of referring to another record.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecordId {
+    pub page_id: PageId,
+    pub offset: u16,
+    pub layout_version: u16,
+}
+
+impl RecordId {
+    /// Constructs a new record ID.
+    pub fn new(page_id: PageId, offset: u16, layout_version: u16) -> RecordId {
+        RecordId {
+            page_id,
+            offset,
+            layout_version,
+        }
+    }
+
+    /// Constructs the ID of the record at the given offset, as of the current
+    /// layout version of its page.
+    pub(crate) async fn current(db: &Db, page_id: PageId, offset: u16) -> DbResult<RecordId> {
+        let layout_version = db
+            .pager()
+            .read_with(page_id, |page: &HeapPage| page.header.layout_version)
+            .await?;
+        Ok(RecordId::new(page_id, offset, layout_version))
+    }
+}
+
+impl fmt::Display for RecordId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.page_id.get(), self.offset)
+    }
+}
+
+impl From<RecordId> for PhysicalState {
+    fn from(id: RecordId) -> PhysicalState {
+        PhysicalState {
+            page_id: id.page_id,
+            offset: id.offset,
+        }
+    }
+}
+
+/// Reads the record with the given ID from the given table.
+///
+/// The page must be a heap page, the offset must be within its records' region
+/// and the page's layout must not have changed since the ID was obtained,
+/// otherwise an error is returned. It is up to the caller to provide an ID
+/// obtained from the given table.
+///
+/// The record is served by the decoded record cache, if enabled and if its page
+/// wasn't written since the record was cached. See
+/// [`decode_cache`](crate::exec::decode_cache). Pages read from a snapshot's
+/// before-image (see [`txn`](crate::io::txn)) bypass the cache.
+pub(super) async fn read_record(db: &Db, table: &TableObject, id: RecordId) -> DbResult<Record> {
+    // The version is taken before reading the page, so that a concurrent write
+    // may only make the cached record stale, not wrong.
+    let page_version = db.pager().page_version(id.page_id);
+    let decode_cache = db
+        .decode_cache()
+        .filter(|_| !db.pager().is_versioned(id.page_id));
+    if let Some(record) = decode_cache.and_then(|cache| cache.get(id, page_version)) {
+        return Ok(record);
+    }
+
+    let state = PhysicalState::from(id);
+    let deserializer = mk_deserializer(&table.schema);
+    let read = async {
+        db.pager()
+            .read_with(id.page_id, |page: &HeapPage| {
+                if id.offset < page.first_offset()
+                    || id.offset >= page.offset()
+                    || page.is_fragment()
+                {
+                    return Err(Error::ExecError(format!("invalid record id {id}")));
+                }
+                if page.header.layout_version != id.layout_version {
+                    return Err(Error::ExecError(format!(
+                        "stale record id {id}: its page was compacted since"
+                    )));
+                }
+                Ok(())
+            })
+            .await??;
+        span::read(db, state, deserializer)
+            .await
+            .context(ErrorContext::Record {
+                page_id: id.page_id,
+                offset: id.offset,
+            })
+    };
+    let (record, _) = read
+        .await
+        .with_context(|| ErrorContext::Object(table.name.clone()))?;
+    if let Some(cache) = decode_cache.filter(|_| !db.pager().is_versioned(id.page_id)) {
+        cache.insert(id, page_version, &record);
+    }
+    Ok(record)
+}
+
+/// Locks the given record exclusively (see [`locking`]) through the given lock
+/// owner, which is created on first use. If the lock had to be waited for, the
+/// record is read again, since its previous holder may have changed it. Returns
+/// the record and whether it was read again.
+///
+/// [`locking`]: crate::exec::locking
+pub(super) async fn lock_record(
+    db: &Db,
+    table: &TableObject,
+    locks: &mut Option<LockSet>,
+    record: Record,
+) -> DbResult<(Record, bool)> {
+    let id = RecordId::current(db, record.page_id(), record.offset()).await?;
+    let locks = locks.get_or_insert_with(|| db.locks().owner());
+    if locks.lock(id, LockMode::Exclusive).await? {
+        debug!(%id, "reading record again after waiting for its lock");
+        return Ok((read_record(db, table, id).await?, true));
+    }
+    Ok((record, false))
+}
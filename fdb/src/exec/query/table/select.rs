@@ -1,47 +1,520 @@
+use std::{
+    collections::VecDeque,
+    ops::{Bound, RangeBounds},
+    sync::Arc,
+};
+
 use async_trait::async_trait;
-use tracing::instrument;
+use tracing::{debug, instrument};
 
 use crate::{
-    catalog::object::TableObject,
-    error::DbResult,
+    catalog::{column::Column, object::TableObject, table_schema::TableSchema},
+    error::{DbResult, Error},
     exec::{
-        query::{table::SeqScan, Query},
-        values::Values,
+        expr::Expr,
+        operations::index::BTree,
+        query::{
+            describe_range,
+            table::{IndexScan, Record, RecordId, SeqScan, TableIndexes},
+            table_page_count, Limit, Plan, Query, RecordSource, SourceEstimate,
+        },
+        sample::PageSample,
+        statistics,
+        value::Value,
+        values::{Row, SchematizedValues, Values},
     },
+    io::metrics::Counter,
     Db,
 };
 
 /// A select query.
 pub struct Select<'a> {
-    linear_scan: SeqScan<'a>,
+    table: &'a TableObject,
+    filter: Option<Filter>,
+    expr: Option<&'a Expr>,
+    columns: Option<Arc<[String]>>,
+    /// The ordinals of the projected columns, resolved on first use.
+    ordinals: Option<Vec<usize>>,
+    sample: Option<PageSample>,
+    hint: Option<IndexHint>,
+    access: Option<Access<'a>>,
+}
+
+/// An access path hint, which overrides the choice of the select's access
+/// path. See [`Select::use_index`] and [`Select::no_index_scan`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IndexHint {
+    /// Forces the given index to be used.
+    Use(String),
+    /// Forbids index scans, i.e., forces the table to be linearly scanned.
+    NoIndex,
+}
+
+/// A filter over a column's values.
+struct Filter {
+    column: String,
+    start: Bound<Value>,
+    end: Bound<Value>,
+}
+
+/// The number of records deserialized at once by linear accesses. See
+/// [`SeqScan::next_batch`].
+const BATCH_SIZE: usize = 64;
+
+/// The access path used by the select.
+enum Access<'a> {
+    /// A linear scan, along with the records of its current batch.
+    Linear(SeqScan<'a>, VecDeque<Record>),
+    Index(IndexScan<'a>),
+}
+
+impl Access<'_> {
+    async fn next(&mut self, db: &Db) -> DbResult<Option<Record>> {
+        match self {
+            Access::Linear(seq_scan, batch) => {
+                if batch.is_empty() {
+                    batch.extend(seq_scan.next_batch(db, BATCH_SIZE).await?);
+                }
+                Ok(batch.pop_front())
+            }
+            Access::Index(index_scan) => index_scan.next(db).await,
+        }
+    }
+
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<Record>> {
+        match self {
+            Access::Linear(seq_scan, batch) => {
+                if batch.is_empty() {
+                    batch.extend(seq_scan.next_batch(db, BATCH_SIZE).await?);
+                }
+                Ok(batch.front().cloned())
+            }
+            Access::Index(index_scan) => index_scan.peek(db).await,
+        }
+    }
 }
 
 #[async_trait]
 impl Query for Select<'_> {
-    // TODO: Create ordered row abstraction (so that select return data in the
-    // same order as the user requested).
+    /// The (projected) values. See [`Select::next_row`] for ordered rows.
     type Item<'a> = Values;
 
+    const READ_ONLY: bool = true;
+
     #[instrument(name = "TableSelect", level = "debug", skip_all)]
     async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_record = self.next_record(db).await?;
+        Ok(maybe_record.map(|record| self.project(record)))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        RecordSource::describe(self, db).await
+    }
+}
+
+#[async_trait]
+impl RecordSource for Select<'_> {
+    fn schema(&self) -> &TableSchema {
+        &self.table.schema
+    }
+
+    const READ_ONLY: bool = true;
+
+    async fn next(&mut self, db: &Db) -> DbResult<Option<SchematizedValues>> {
+        let maybe_record = self.next_record(db).await?;
+        Ok(maybe_record.map(|record| record.into_data().into_owned()))
+    }
+
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<SchematizedValues>> {
         loop {
-            let result = if let Some(record) = self.linear_scan.next(db).await? {
-                if record.is_deleted() {
+            let maybe_record = self.access(db).await?.peek(db).await?;
+            let result = if let Some(record) = maybe_record {
+                if self.skips(&record) {
+                    // Skips the record so that the next peek sees the
+                    // following one.
+                    self.access(db).await?.next(db).await?;
                     continue;
                 }
-                Some(record.into_data().into_owned().into_values())
+                Some(record.into_data().into_owned())
             } else {
                 None
             };
             return Ok(result);
         }
     }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let table = self.table;
+        let mut plan = Plan::new("TableSelect")
+            .with("table", &table.name)
+            .with_opt("columns", self.columns.as_ref().map(|c| c.join(", ")))
+            .with_opt("expr", self.expr);
+        match self.index(db).await? {
+            Some((tree, filter)) => {
+                let indexes = TableIndexes::load(db, table).await?;
+                let index = indexes.find_by_root(tree.root());
+                plan = plan
+                    .with("access", "index scan")
+                    .with_opt("index", index.map(|index| &index.name))
+                    .with("column", &filter.column)
+                    .with("range", describe_range(&filter.start, &filter.end))
+                    .with("height", tree.height(db.pager()).await?);
+            }
+            None => {
+                let filter = self.filter.as_ref().map(|filter| {
+                    let range = describe_range(&filter.start, &filter.end);
+                    format!("{} in {range}", filter.column)
+                });
+                plan = plan
+                    .with("access", "seq scan")
+                    .with("pages", table_page_count(db, table).await?)
+                    .with_opt("filter", filter)
+                    .with_opt("sample", self.sample.map(|sample| format!("{sample:?}")));
+            }
+        }
+        Ok(plan)
+    }
+
+    /// The table statistics' estimate, which is an upper bound if the select
+    /// is filtered or sampled.
+    async fn estimate(&mut self, db: &Db) -> DbResult<Option<SourceEstimate>> {
+        let statistics = statistics::table_statistics(db, self.table).await?;
+        Ok(statistics.map(|statistics| SourceEstimate {
+            records: statistics.row_count,
+            avg_record_size: statistics.avg_record_size(),
+        }))
+    }
 }
 
 impl<'a> Select<'a> {
     pub fn new(table: &'a TableObject) -> Select<'a> {
         Self {
-            linear_scan: SeqScan::new(table),
+            table,
+            filter: None,
+            expr: None,
+            columns: None,
+            ordinals: None,
+            sample: None,
+            hint: None,
+            access: None,
+        }
+    }
+
+    /// Creates a select that only yields the records whose value for the given
+    /// column is within the given range.
+    ///
+    /// If there is an index over the column, it is used to look the records up
+    /// (in which case they are yielded in the column order). Otherwise, the
+    /// table is linearly scanned.
+    pub fn with_filter(
+        table: &'a TableObject,
+        column: impl Into<String>,
+        range: impl RangeBounds<Value>,
+    ) -> Select<'a> {
+        Self {
+            table,
+            filter: Some(Filter {
+                column: column.into(),
+                start: range.start_bound().cloned(),
+                end: range.end_bound().cloned(),
+            }),
+            expr: None,
+            columns: None,
+            ordinals: None,
+            sample: None,
+            hint: None,
+            access: None,
+        }
+    }
+
+    /// Creates a select that only yields the records which match the given
+    /// (boolean) expression, which is type-checked against the table schema.
+    ///
+    /// If a key range may be extracted from the expression (see
+    /// [`Expr::key_range`]) and there is an index over its column, the index
+    /// is used to look the records up. Otherwise, the table is linearly
+    /// scanned.
+    pub fn with_expr(table: &'a TableObject, expr: &'a Expr) -> Select<'a> {
+        let filter = expr
+            .key_range(&table.schema)
+            .map(|(column, start, end)| Filter { column, start, end });
+        Self {
+            table,
+            filter,
+            expr: Some(expr),
+            columns: None,
+            ordinals: None,
+            sample: None,
+            hint: None,
+            access: None,
         }
     }
+
+    /// Restricts the yielded values to the given columns (a projection), which
+    /// are also the columns of the rows returned by [`Select::next_row`], in
+    /// the given order.
+    ///
+    /// By default, all columns are yielded (in the schema order).
+    pub fn with_columns(mut self, columns: &[&str]) -> Select<'a> {
+        self.columns = Some(columns.iter().map(|&column| column.to_owned()).collect());
+        self.ordinals = None;
+        self
+    }
+
+    /// Only yields the records stored in the pages included in the given
+    /// sample. On linear scans, the other pages are skipped without being
+    /// deserialized.
+    pub fn with_page_sample(mut self, sample: PageSample) -> Select<'a> {
+        self.sample = Some(sample);
+        self
+    }
+
+    /// Forces the select to look the records up through the given index, which
+    /// must be defined over the table and over the column of the filter (see
+    /// [`Select::with_filter`] and [`Select::with_expr`]). Otherwise, the
+    /// select fails.
+    pub fn use_index(mut self, index: impl Into<String>) -> Select<'a> {
+        self.hint = Some(IndexHint::Use(index.into()));
+        self
+    }
+
+    /// Forbids the select from using an index, i.e., the table is linearly
+    /// scanned even if there is an index over the column of the filter.
+    pub fn no_index_scan(mut self) -> Select<'a> {
+        self.hint = Some(IndexHint::NoIndex);
+        self
+    }
+
+    /// Sets (or, if `None`, clears) the access path hint. See
+    /// [`Select::use_index`] and [`Select::no_index_scan`].
+    pub fn with_hint(mut self, hint: Option<IndexHint>) -> Select<'a> {
+        self.hint = hint;
+        self
+    }
+
+    /// Limits the select to at most `count` records, which stops the scan once
+    /// they are yielded. See [`Limit`].
+    pub fn limit(self, count: usize) -> Limit<Select<'a>> {
+        Limit::new(self, count)
+    }
+
+    /// Returns the next record as an ordered [`Row`], whose columns follow the
+    /// projection order (see [`Select::with_columns`]) or, if there's no
+    /// projection, the schema order.
+    pub async fn next_row(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        let maybe_record = self.next_record(db).await?;
+        let table = self.table;
+        let columns = Arc::clone(self.columns.get_or_insert_with(|| {
+            let columns = &table.schema.columns;
+            columns.iter().map(|column| column.name.clone()).collect()
+        }));
+        let ordinals = self.ordinals().expect("columns are set");
+        Ok(maybe_record.map(|record| {
+            let values = record.into_data().into_owned().project(ordinals);
+            Row::new(columns, values)
+        }))
+    }
+
+    /// Returns the next record's values along with its [`RecordId`], which may
+    /// be used to address it directly (see [`GetById`], [`UpdateById`] and
+    /// [`DeleteById`]).
+    ///
+    /// [`GetById`]: super::GetById
+    /// [`UpdateById`]: super::UpdateById
+    /// [`DeleteById`]: super::DeleteById
+    pub async fn next_with_id(&mut self, db: &Db) -> DbResult<Option<(RecordId, Values)>> {
+        let Some(record) = self.next_record(db).await? else {
+            return Ok(None);
+        };
+        let id = RecordId::current(db, record.page_id(), record.offset()).await?;
+        Ok(Some((id, self.project(record))))
+    }
+
+    /// Returns the next live record which matches the filter.
+    async fn next_record(&mut self, db: &Db) -> DbResult<Option<Record>> {
+        loop {
+            let maybe_record = self.access(db).await?.next(db).await?;
+            if let Some(record) = &maybe_record {
+                if self.skips(record) {
+                    continue;
+                }
+                db.pager().metrics().incr(Counter::RecordsMatched);
+            }
+            return Ok(maybe_record);
+        }
+    }
+
+    /// Projects the record's values into the selected columns (if any).
+    fn project(&mut self, record: Record) -> Values {
+        let values = record.into_data().into_owned();
+        let table = self.table;
+        let Some(ordinals) = self.ordinals() else {
+            return values.into_values(&table.schema);
+        };
+        let projected = values.project(ordinals);
+        let columns = self.columns.as_ref().expect("columns are set");
+        let mut values = Values::new();
+        for (column, value) in columns.iter().zip(projected) {
+            values.set(column.clone(), value);
+        }
+        values
+    }
+
+    /// Returns the ordinals of the projected columns (if any), resolving them
+    /// against the table schema on the first call.
+    fn ordinals(&mut self) -> Option<&[usize]> {
+        let columns = self.columns.as_ref()?;
+        let schema = &self.table.schema;
+        let ordinals = self.ordinals.get_or_insert_with(|| {
+            columns
+                .iter()
+                .map(|column| schema.ordinal(column).expect("validated column"))
+                .collect()
+        });
+        Some(ordinals)
+    }
+
+    /// Returns the access path, choosing it on the first call.
+    async fn access(&mut self, db: &Db) -> DbResult<&mut Access<'a>> {
+        if self.access.is_none() {
+            if let Some(columns) = &self.columns {
+                self.check_columns(columns)?;
+            }
+            if let Some(expr) = self.expr {
+                expr.check_predicate(&self.table.schema)?;
+            }
+            if let Some(filter) = &self.filter {
+                self.check_filter(filter)?;
+            }
+            let access = match self.index(db).await? {
+                Some((tree, filter)) => Access::Index(IndexScan::new(
+                    self.table,
+                    tree,
+                    filter.start.clone(),
+                    filter.end.clone(),
+                )),
+                None => Access::Linear(self.seq_scan(), VecDeque::new()),
+            };
+            self.access = Some(access);
+        }
+        Ok(self.access.as_mut().unwrap())
+    }
+
+    /// Returns the index to be used (along with the filter over its column),
+    /// if any, honoring the hint. Fails if the hinted index can't be used.
+    async fn index(&self, db: &Db) -> DbResult<Option<(BTree, &Filter)>> {
+        let table = &self.table.name;
+        match &self.hint {
+            Some(IndexHint::NoIndex) => {
+                debug!("index scans forbidden by hint");
+                Ok(None)
+            }
+            Some(IndexHint::Use(name)) => {
+                let indexes = TableIndexes::load(db, self.table).await?;
+                let Some(index) = indexes.find_by_name(name) else {
+                    return Err(Error::ExecError(format!(
+                        "index `{name}` is not defined over table `{table}`"
+                    )));
+                };
+                let column = &index.schema.column;
+                match &self.filter {
+                    Some(filter) if filter.column == *column => {
+                        debug!(index = name, "using hinted index scan");
+                        Ok(Some((BTree::new(index.page_id), filter)))
+                    }
+                    _ => Err(Error::ExecError(format!(
+                        "index `{name}` can't be used, since there is no key range over column \
+                         `{column}`"
+                    ))),
+                }
+            }
+            None => {
+                let Some(filter) = &self.filter else {
+                    return Ok(None);
+                };
+                let indexes = TableIndexes::load(db, self.table).await?;
+                let tree = indexes.find(db, &filter.column).await?;
+                if tree.is_some() {
+                    debug!(column = filter.column, "using index scan");
+                }
+                Ok(tree.map(|tree| (tree, filter)))
+            }
+        }
+    }
+
+    fn seq_scan(&self) -> SeqScan<'a> {
+        let seq_scan = SeqScan::new(self.table);
+        match self.sample {
+            Some(sample) => seq_scan.with_sample(sample),
+            None => seq_scan,
+        }
+    }
+
+    /// Checks whether the projected columns exist and are not repeated.
+    fn check_columns(&self, columns: &[String]) -> DbResult<()> {
+        for (i, column) in columns.iter().enumerate() {
+            self.find_column(column)?;
+            if columns[..i].contains(column) {
+                return Err(Error::ExecError(format!(
+                    "column `{column}` specified more than once"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks whether the filter column exists and whether the bounds have the
+    /// same type as the column.
+    fn check_filter(&self, filter: &Filter) -> DbResult<()> {
+        let column = self.find_column(&filter.column)?;
+        for bound in [&filter.start, &filter.end] {
+            if let Bound::Included(value) | Bound::Excluded(value) = bound {
+                if value.type_id() != column.ty {
+                    return Err(Error::Cast(format!(
+                        "can't compare column `{}` with a value of another type",
+                        filter.column
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn find_column(&self, name: &str) -> DbResult<&Column> {
+        let columns = &self.table.schema.columns;
+        columns
+            .iter()
+            .find(|column| column.name == name)
+            .ok_or_else(|| {
+                Error::ExecError(format!(
+                    "column `{name}` does not exist in table `{}`",
+                    self.table.name
+                ))
+            })
+    }
+
+    /// Checks whether the given record must be skipped, i.e., whether it is
+    /// deleted, out of the sample or doesn't match the filter.
+    fn skips(&self, record: &Record) -> bool {
+        record.is_deleted()
+            || self
+                .sample
+                .is_some_and(|sample| !sample.includes(record.page_id()))
+            || !self.matches(record.as_data())
+    }
+
+    /// Checks whether the given values match the filter and the expression (if
+    /// any).
+    fn matches(&self, values: &SchematizedValues) -> bool {
+        let schema = &self.table.schema;
+        let in_range = self.filter.as_ref().is_none_or(|filter| {
+            values
+                .get(schema, &filter.column)
+                .is_some_and(|value| (filter.start.as_ref(), filter.end.as_ref()).contains(value))
+        });
+        in_range
+            && self
+                .expr
+                .is_none_or(|expr| expr.matches(&values.to_values(schema)))
+    }
 }
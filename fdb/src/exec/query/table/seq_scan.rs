@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use buff::Buff;
+use buff::BuffRead;
 use tracing::instrument;
 
 use crate::{
@@ -8,17 +8,19 @@ use crate::{
         record::simple_record::{SimpleRecord, TableRecordCtx},
         table_schema::TableSchema,
     },
-    error::DbResult,
+    error::{DbResult, ErrorContext, ResultExt},
     exec::{
         operations::{heap, PhysicalState},
-        query::Query,
+        query::{table_page_count, Plan, Query},
+        sample::PageSample,
         values::SchematizedValues,
     },
+    io::metrics::Counter,
     util::io::DeserializeCtx,
     Db,
 };
 
-type Record = SimpleRecord<'static, SchematizedValues<'static>>;
+pub(crate) type Record = SimpleRecord<'static, SchematizedValues>;
 
 /// A sequence scan query for tables.
 pub struct SeqScan<'a> {
@@ -30,11 +32,25 @@ pub struct SeqScan<'a> {
 impl Query for SeqScan<'_> {
     type Item<'a> = Record;
 
+    const READ_ONLY: bool = true;
+
     #[instrument(name = "TableLinearScan", level = "debug", skip_all)]
     async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
-        self.seq_scan
+        let maybe_record = self
+            .seq_scan
             .next(db, mk_deserializer(&self.table.schema))
             .await
+            .with_context(|| self.context())?;
+        if maybe_record.is_some() {
+            db.pager().metrics().incr(Counter::RecordsScanned);
+        }
+        Ok(maybe_record)
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableSeqScan")
+            .with("table", &self.table.name)
+            .with("pages", table_page_count(db, self.table).await?))
     }
 }
 
@@ -47,6 +63,31 @@ impl<'a> SeqScan<'a> {
         }
     }
 
+    /// Only scans the pages included in the given sample. See
+    /// [`heap::SeqScan::with_sample`].
+    pub fn with_sample(mut self, sample: PageSample) -> SeqScan<'a> {
+        self.seq_scan = self.seq_scan.with_sample(sample);
+        self
+    }
+
+    /// Returns (at most) the next `n` records of the current page. See
+    /// [`heap::SeqScan::next_batch`].
+    pub async fn next_batch(&mut self, db: &Db, n: usize) -> DbResult<Vec<Record>> {
+        let batch = self
+            .seq_scan
+            .next_batch(db, n, mk_deserializer(&self.table.schema))
+            .await
+            .with_context(|| self.context())?;
+        (db.pager().metrics()).add(Counter::RecordsScanned, batch.len() as u64);
+        Ok(batch)
+    }
+
+    /// Moves the underlying cursor `delta` bytes back. See
+    /// [`heap::SeqScan::rewind`].
+    pub fn rewind(&mut self, delta: u16) {
+        self.seq_scan.rewind(delta);
+    }
+
     /// Returns the current element without advancing the underlying iterator.
     ///
     /// This method doesn't perform any kind of cache, which is handled by the
@@ -55,12 +96,17 @@ impl<'a> SeqScan<'a> {
         self.seq_scan
             .peek(db, mk_deserializer(&self.table.schema))
             .await
+            .with_context(|| self.context())
+    }
+
+    fn context(&self) -> ErrorContext {
+        ErrorContext::Object(self.table.name.clone())
     }
 }
 
-fn mk_deserializer(
+pub(crate) fn mk_deserializer(
     schema: &TableSchema,
-) -> impl Fn(&mut Buff, PhysicalState) -> DbResult<Record> + '_ {
+) -> impl Fn(&mut BuffRead, PhysicalState) -> DbResult<Record> + '_ {
     |buf, state| {
         let ctx = TableRecordCtx::from_physical(state, schema);
         SimpleRecord::<SchematizedValues>::deserialize(buf, &ctx)
@@ -0,0 +1,458 @@
+use std::{cmp::Ordering, collections::VecDeque, mem};
+
+use async_trait::async_trait;
+use tracing::{debug, instrument, trace, warn};
+
+pub use crate::exec::util::cmp::{NullOrder, SortKey};
+use crate::{
+    catalog::{page::MAX_TEMP_SEQS, table_schema::TableSchema},
+    error::{DbResult, Error},
+    exec::{
+        operations::{
+            merge::KWayMerge,
+            tape::{Tape, TapeReader},
+        },
+        query::{Limit, Plan, Query, RecordSource},
+        values::{SchematizedValues, Values},
+    },
+    io::temp,
+    Db,
+};
+
+/// The default maximum number of records sorted in memory at once.
+pub const DEFAULT_RUN_SIZE: usize = 4096;
+
+/// The default maximum number of tapes merged at once.
+pub const DEFAULT_FAN_IN: usize = 8;
+
+type Row = SchematizedValues;
+
+/// A sort query over a [`RecordSource`], ordered by one or more [`SortKey`]s.
+/// The sort is stable.
+///
+/// Records are sorted in memory if the source has at most `run_size` records.
+/// Otherwise, an external merge sort is performed: sorted runs are distributed
+/// to [`Tape`]s, which are then merged (at most `fan_in` at once) until a
+/// single merge remains, whose results are streamed.
+///
+/// If the source has an estimate (see [`RecordSource::estimate`]), the strategy
+/// is pre-selected from it: the run size is derived from the memory limit (see
+/// [`Sort::with_memory_limit`]) and evened out over the expected runs, and the
+/// merges are deferred until the source is read (if there aren't too many
+/// runs), so that as few records as possible are merged more than once.
+///
+/// Tapes are temporary sequences, hence the number of simultaneous tapes
+/// (including the output of a merge) is bounded, so that along with the other
+/// temporary sequences (e.g., aggregate spills) they stay below
+/// [`MAX_TEMP_SEQS`]. If few of them are left, fewer tapes are merged at once.
+///
+/// Tapes are released once the sort is exhausted, or if it fails. If the sort
+/// is dropped earlier, they are purged by the next database open.
+pub struct Sort<S> {
+    source: S,
+    keys: Vec<SortKey>,
+    run_size: usize,
+    fan_in: usize,
+    memory_limit: Option<usize>,
+    state: State,
+    peeked: Option<Row>,
+}
+
+/// How the runs of a [`Sort`] are distributed and merged. See [`Sort::plan`].
+#[derive(Debug)]
+struct Strategy {
+    run_size: usize,
+    /// The expected number of records, if there is an estimate.
+    expected: Option<u64>,
+    /// The maximum number of tapes merged at once.
+    fan_in: usize,
+    /// The maximum number of simultaneous tapes, including the output of a
+    /// merge.
+    max_tapes: usize,
+    /// Whether the tapes are only merged once the source is read, instead of
+    /// whenever `fan_in` tapes of the same level exist.
+    deferred_merges: bool,
+}
+
+enum State {
+    Initial,
+    InMemory(VecDeque<Row>),
+    External {
+        merge: KWayMerge<TapeReader, Row, Comparator>,
+        tapes: Vec<Tape>,
+    },
+    Done,
+}
+
+type Comparator = Box<dyn Fn(&Row, &Row) -> Ordering + Send + Sync>;
+
+#[async_trait]
+impl<S: RecordSource> Query for Sort<S> {
+    type Item<'a> = Values;
+
+    #[instrument(name = "TableSort", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_record = RecordSource::next(self, db).await?;
+        let schema = RecordSource::schema(self);
+        Ok(maybe_record.map(|record| record.into_values(schema)))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        RecordSource::describe(self, db).await
+    }
+}
+
+#[async_trait]
+impl<S: RecordSource> RecordSource for Sort<S> {
+    fn schema(&self) -> &TableSchema {
+        self.source.schema()
+    }
+
+    async fn next(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        if let Some(row) = self.peeked.take() {
+            return Ok(Some(row));
+        }
+        if let State::Initial = self.state {
+            self.state = self.run(db).await?;
+        }
+        match &mut self.state {
+            State::Initial => unreachable!(),
+            State::InMemory(rows) => Ok(rows.pop_front()),
+            State::External { merge, .. } => {
+                let maybe_row = merge.next(db).await?;
+                if maybe_row.is_none() {
+                    self.finish(db).await?;
+                }
+                Ok(maybe_row)
+            }
+            State::Done => Ok(None),
+        }
+    }
+
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        if self.peeked.is_none() {
+            self.peeked = RecordSource::next(self, db).await?;
+        }
+        Ok(self.peeked.clone())
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        let keys = (self.keys.iter())
+            .map(|key| {
+                let order = if key.descending { "desc" } else { "asc" };
+                format!("{} {order}", key.column)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        // Without an estimate, whether the sort spills to tapes is only known
+        // once the source is read.
+        let strategy = self.plan(db).await?;
+        let spills = (strategy.expected).map(|expected| expected > strategy.run_size as u64);
+        let runs = (strategy.expected)
+            .filter(|_| spills == Some(true))
+            .map(|expected| expected.div_ceil(strategy.run_size as u64));
+        Ok(Plan::new("TableSort")
+            .with("keys", keys)
+            .with_opt(
+                "strategy",
+                spills.map(|spills| if spills { "external" } else { "in memory" }),
+            )
+            .with("run_size", strategy.run_size)
+            .with_opt("runs", runs)
+            .with("fan_in", strategy.fan_in)
+            .with_opt("deferred_merges", runs.map(|_| strategy.deferred_merges))
+            .with_child(self.source.describe(db).await?))
+    }
+}
+
+impl<S: RecordSource> Sort<S> {
+    /// Creates a new sort executor over the given source, ordered by the given
+    /// keys (the first key is the most significant one).
+    pub fn new(source: S, keys: Vec<SortKey>) -> Sort<S> {
+        Self {
+            source,
+            keys,
+            run_size: DEFAULT_RUN_SIZE,
+            fan_in: DEFAULT_FAN_IN,
+            memory_limit: None,
+            state: State::Initial,
+            peeked: None,
+        }
+    }
+
+    /// Sets the maximum number of records sorted in memory at once.
+    pub fn with_run_size(mut self, run_size: usize) -> Sort<S> {
+        self.run_size = run_size.max(1);
+        self
+    }
+
+    /// Sets the maximum number of tapes merged at once.
+    pub fn with_fan_in(mut self, fan_in: usize) -> Sort<S> {
+        self.fan_in = fan_in.max(2);
+        self
+    }
+
+    /// Sets the maximum size (in bytes) of the records sorted in memory at once.
+    ///
+    /// It only applies if the source has an estimate (see
+    /// [`RecordSource::estimate`]), whose average record size bounds the run
+    /// size (which is still at most the one set by [`Sort::with_run_size`]).
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Sort<S> {
+        self.memory_limit = Some(memory_limit);
+        self
+    }
+
+    /// Limits the sort to its first `count` records. See [`Limit`].
+    ///
+    /// The whole source is still sorted. Since the limited sort isn't
+    /// exhausted, its tapes (if any) are only purged by the next database
+    /// open.
+    pub fn limit(self, count: usize) -> Limit<Sort<S>> {
+        Limit::new(self, count)
+    }
+
+    /// Pre-selects the strategy of the sort from the source estimate, if any,
+    /// and from the number of temporary sequences which are left.
+    async fn plan(&mut self, db: &Db) -> DbResult<Strategy> {
+        // One sequence is always left, so that the other users may still
+        // allocate one.
+        let others = temp::seq_count(db.pager()).await?;
+        let max_tapes = MAX_TEMP_SEQS.saturating_sub(others + 1);
+        let mut strategy = Strategy {
+            run_size: self.run_size,
+            expected: None,
+            fan_in: self.fan_in.min(max_tapes.saturating_sub(1)).max(2),
+            max_tapes,
+            deferred_merges: false,
+        };
+        let Some(estimate) = self.source.estimate(db).await? else {
+            return Ok(strategy);
+        };
+        strategy.expected = Some(estimate.records);
+        if let (Some(memory_limit), true) = (self.memory_limit, estimate.avg_record_size > 0.0) {
+            let fitting = (memory_limit as f64 / estimate.avg_record_size) as usize;
+            strategy.run_size = strategy.run_size.min(fitting.max(1));
+        }
+        if estimate.records > strategy.run_size as u64 {
+            // Evens out the runs, so that the last one isn't much smaller than
+            // the others (the merges are cheaper if the tapes are balanced).
+            let runs = estimate.records.div_ceil(strategy.run_size as u64);
+            strategy.run_size = estimate.records.div_ceil(runs) as usize;
+            // The final merges (see `Sort::run`) rewrite fewer records than
+            // the eager ones, but the number of simultaneous tapes must still
+            // be bounded (the first merge needs a tape besides the runs).
+            strategy.deferred_merges =
+                runs <= (strategy.fan_in * strategy.fan_in) as u64 && runs < max_tapes as u64;
+        }
+        debug!(?estimate, ?strategy, "planned sort");
+        Ok(strategy)
+    }
+
+    /// Reads the whole source, sorting it in memory or distributing it to
+    /// sorted tapes.
+    async fn run(&mut self, db: &Db) -> DbResult<State> {
+        for key in &self.keys {
+            if !self.schema().columns.iter().any(|c| c.name == key.column) {
+                return Err(Error::ExecError(format!(
+                    "column `{}` does not exist",
+                    key.column
+                )));
+            }
+        }
+
+        let mut tapes = Vec::new();
+        let result = self.distribute(db, &mut tapes).await;
+        if result.is_err() {
+            release(db, tapes.into_iter().map(|(_, tape)| tape))
+                .await
+                .ok();
+        }
+        result
+    }
+
+    /// Distributes the source to the given tapes (tagged with their levels) and
+    /// merges them, unless it is sorted in memory. The tapes are left in the
+    /// given vector, so that they can be released if it fails.
+    async fn distribute(&mut self, db: &Db, tapes: &mut Vec<(u32, Tape)>) -> DbResult<State> {
+        let mut strategy = self.plan(db).await?;
+        let (run_size, fan_in) = (strategy.run_size, strategy.fan_in);
+        let max_tapes = strategy.max_tapes;
+        // Whether another tape can't be written, as it and the output of a
+        // merge would exceed the maximum number of tapes.
+        let full = |tapes: &[(u32, Tape)]| tapes.len() + 2 > max_tapes;
+        let capacity = strategy
+            .expected
+            .map_or(run_size, |expected| run_size.min(expected as usize + 1));
+        let cmp = comparator(self.schema(), self.keys.clone());
+        // Each tape is tagged with its level, i.e., the number of merges that
+        // produced it. Unless the merges are deferred, whenever `fan_in` tapes
+        // of the same level exist, they are merged, which bounds the number of
+        // simultaneous tapes. The last tapes are also merged, whatever their
+        // levels, if there is no room for another one.
+        loop {
+            let mut run = Vec::with_capacity(capacity);
+            while run.len() < run_size {
+                match self.source.next(db).await? {
+                    Some(row) => run.push(row),
+                    None => break,
+                }
+            }
+            // A full first run is only written to a tape if more records
+            // follow it.
+            let exhausted =
+                run.len() < run_size || (tapes.is_empty() && self.source.peek(db).await?.is_none());
+            run.sort_by(&cmp);
+
+            if exhausted && tapes.is_empty() {
+                debug!(len = run.len(), "sorted in memory");
+                return Ok(State::InMemory(run.into()));
+            }
+            if !run.is_empty() {
+                tapes.push((0, write_tape(db, self.schema(), run).await?));
+            }
+            // The estimate was wrong, hence the number of tapes is bounded
+            // again.
+            if strategy.deferred_merges && (tapes.len() >= fan_in * fan_in || full(tapes)) {
+                debug!(tapes = tapes.len(), "more runs than expected");
+                strategy.deferred_merges = false;
+            }
+            while !strategy.deferred_merges && tapes.len() >= fan_in {
+                let start = tapes.len() - fan_in;
+                let level = tapes[start..].iter().map(|(l, _)| *l).max().unwrap_or(0);
+                if tapes[start..].iter().any(|(l, _)| *l != level) && !full(tapes) {
+                    break;
+                }
+                let group = tapes.split_off(start);
+                let group = group.into_iter().map(|(_, tape)| tape).collect();
+                let merged = merge_tapes(db, self.schema(), &self.keys, run_size, group).await?;
+                tapes.push((level + 1, merged));
+            }
+            if exhausted {
+                break;
+            }
+        }
+        debug!(tapes = tapes.len(), "distributed runs to tapes");
+
+        // Merges the remaining tapes until at most `fan_in` remain. The first
+        // merge only merges as many tapes as needed for the following ones to
+        // merge `fan_in` tapes each (and for the last one to leave exactly
+        // `fan_in` tapes), so that as few records as possible are rewritten.
+        // The merged tape takes the place of its sources, so that the sort is
+        // stable.
+        let mut group_len = (tapes.len().saturating_sub(2) % (fan_in - 1)) + 2;
+        while tapes.len() > fan_in {
+            let group = tapes.drain(..group_len).map(|(_, tape)| tape).collect();
+            let merged = merge_tapes(db, self.schema(), &self.keys, run_size, group).await?;
+            tapes.insert(0, (0, merged));
+            group_len = fan_in;
+        }
+
+        let tapes: Vec<_> = mem::take(tapes).into_iter().map(|(_, tape)| tape).collect();
+        let readers = tapes.iter().map(Tape::reader).collect();
+        Ok(State::External {
+            merge: KWayMerge::new(readers, comparator(self.schema(), self.keys.clone())),
+            tapes,
+        })
+    }
+
+    /// Releases the tapes of an external sort, after which it yields nothing.
+    /// It is called once the sort is exhausted, but callers which stop reading
+    /// earlier may also call it.
+    pub(super) async fn finish(&mut self, db: &Db) -> DbResult<()> {
+        if let State::External { tapes, .. } = mem::replace(&mut self.state, State::Done) {
+            debug!(tapes = tapes.len(), "releasing tapes");
+            release(db, tapes).await?;
+        }
+        Ok(())
+    }
+}
+
+// The helpers below don't borrow the sort, since `S` isn't required to be
+// `Sync` and the returned futures must be `Send`.
+
+/// Writes the given (sorted) run to a new tape, which is released if it fails.
+async fn write_tape(db: &Db, schema: &TableSchema, run: Vec<Row>) -> DbResult<Tape> {
+    let tape = Tape::create(db, "sort_tape", schema.clone()).await?;
+    trace!(len = run.len(), "writing tape");
+    if let Err(error) = tape.write_all(db, run).await {
+        release(db, [tape]).await.ok();
+        return Err(error);
+    }
+    Ok(tape)
+}
+
+/// Merges the given tapes into a new one, releasing them (even if it fails).
+/// The merged records are written in batches of `run_size`.
+async fn merge_tapes(
+    db: &Db,
+    schema: &TableSchema,
+    keys: &[SortKey],
+    run_size: usize,
+    group: Vec<Tape>,
+) -> DbResult<Tape> {
+    let tape = match Tape::create(db, "sort_tape", schema.clone()).await {
+        Ok(tape) => tape,
+        Err(error) => {
+            release(db, group).await.ok();
+            return Err(error);
+        }
+    };
+    trace!(k = group.len(), "merging tapes");
+    let merged = write_merge(db, &tape, keys, run_size, &group).await;
+    let released = release(db, group).await;
+    if let Err(error) = merged.and(released) {
+        release(db, [tape]).await.ok();
+        return Err(error);
+    }
+    Ok(tape)
+}
+
+/// Writes the merged records of the given tapes to another one.
+async fn write_merge(
+    db: &Db,
+    tape: &Tape,
+    keys: &[SortKey],
+    run_size: usize,
+    group: &[Tape],
+) -> DbResult<()> {
+    let readers = group.iter().map(Tape::reader).collect();
+    let mut merge = KWayMerge::new(readers, comparator(tape.schema(), keys.to_vec()));
+    let mut writer = tape.writer(run_size);
+    while let Some(row) = merge.next(db).await? {
+        writer.push(db, row).await?;
+    }
+    writer.finish(db).await
+}
+
+/// Releases the given tapes, even if some of them fail to be released,
+/// returning the first error. The errors are logged as well, since the callers
+/// which already failed ignore them.
+async fn release(db: &Db, tapes: impl IntoIterator<Item = Tape>) -> DbResult<()> {
+    let mut result = Ok(());
+    for tape in tapes {
+        if let Err(error) = tape.destroy(db).await {
+            warn!(?error, "failed to release tape");
+            result = result.and(Err(error));
+        }
+    }
+    result
+}
+
+/// Returns a comparator over the given keys, whose columns are resolved to
+/// their ordinals in the given schema. See
+/// [`compare_by`](crate::exec::util::cmp::compare_by).
+fn comparator(schema: &TableSchema, keys: Vec<SortKey>) -> Comparator {
+    let ordinals: Vec<_> = keys.iter().map(|key| schema.ordinal(&key.column)).collect();
+    Box::new(move |a: &Row, b: &Row| {
+        (keys.iter().zip(&ordinals))
+            .map(|(key, ordinal)| {
+                let (a, b) = (
+                    ordinal.and_then(|i| a.get_ordinal(i)),
+                    ordinal.and_then(|i| b.get_ordinal(i)),
+                );
+                key.compare_values(a, b)
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal)
+    })
+}
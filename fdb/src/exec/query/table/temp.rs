@@ -0,0 +1,55 @@
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{object::TableObject, table_schema::TableSchema},
+    error::DbResult,
+    io::temp,
+    Db,
+};
+
+/// A temporary table.
+///
+/// Temporary tables are not registered in the database schema, but their pages
+/// are registered as temporary, so that they are purged by the next database
+/// open if [`TempTable::destroy`] isn't called (e.g., after a crash).
+///
+/// The usual table queries (such as [`Insert`] and [`Select`]) may be executed
+/// over [`TempTable::table`].
+///
+/// [`Insert`]: super::Insert
+/// [`Select`]: super::Select
+#[derive(Debug)]
+pub struct TempTable {
+    table: TableObject,
+}
+
+impl TempTable {
+    /// Creates a new empty temporary table.
+    #[instrument(name = "TableTempCreate", level = "debug", skip_all)]
+    pub async fn create(
+        db: &Db,
+        name: impl Into<String>,
+        schema: TableSchema,
+    ) -> DbResult<TempTable> {
+        let page_id = temp::alloc_seq(db.pager()).await?;
+        let table = TableObject {
+            schema,
+            page_id,
+            name: name.into(),
+        };
+        debug!(?page_id, name = table.name, "created temporary table");
+        Ok(TempTable { table })
+    }
+
+    /// Returns the underlying table object.
+    pub fn table(&self) -> &TableObject {
+        &self.table
+    }
+
+    /// Drops the temporary table, releasing its pages.
+    #[instrument(name = "TableTempDestroy", level = "debug", skip_all)]
+    pub async fn destroy(self, db: &Db) -> DbResult<()> {
+        db.activity().forget(self.table.page_id);
+        temp::release_seq(db.pager(), self.table.page_id).await
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{
+    catalog::table_schema::TableSchema,
+    error::{DbResult, Error},
+    exec::{
+        query::{Plan, Query, RecordSource},
+        value::Value,
+        values::{SchematizedValues, Values},
+    },
+    Db,
+};
+
+type Row = SchematizedValues;
+
+/// An unnest query over a [`RecordSource`], which expands an array column into
+/// rows: each source record yields one row per array element, in which the
+/// array is replaced by the element. Records with empty arrays yield no rows.
+///
+/// The yielded rows follow the source schema, except for the unnested column,
+/// whose type is the array's element type (i.e., unnesting a multi-dimensional
+/// array yields arrays of one less dimension).
+pub struct Unnest<S> {
+    source: S,
+    column: String,
+    schema: TableSchema,
+    pending: VecDeque<Row>,
+}
+
+#[async_trait]
+impl<S: RecordSource> Query for Unnest<S> {
+    type Item<'a> = Values;
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    #[instrument(name = "TableUnnest", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        let maybe_record = RecordSource::next(self, db).await?;
+        let schema = RecordSource::schema(self);
+        Ok(maybe_record.map(|record| record.into_values(schema)))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        RecordSource::describe(self, db).await
+    }
+}
+
+#[async_trait]
+impl<S: RecordSource> RecordSource for Unnest<S> {
+    fn schema(&self) -> &TableSchema {
+        &self.schema
+    }
+
+    const READ_ONLY: bool = S::READ_ONLY;
+
+    async fn next(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        self.fill(db).await?;
+        Ok(self.pending.pop_front())
+    }
+
+    async fn peek(&mut self, db: &Db) -> DbResult<Option<Row>> {
+        self.fill(db).await?;
+        Ok(self.pending.front().cloned())
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableUnnest")
+            .with("column", &self.column)
+            .with_child(self.source.describe(db).await?))
+    }
+}
+
+impl<S: RecordSource> Unnest<S> {
+    /// Creates a new unnest executor over the given source's array column.
+    pub fn new(source: S, column: impl Into<String>) -> DbResult<Unnest<S>> {
+        let column = column.into();
+        let mut schema = source.schema().clone();
+        let unnested = schema
+            .columns
+            .iter_mut()
+            .find(|c| c.name == column)
+            .ok_or_else(|| Error::ExecError(format!("column `{column}` does not exist")))?;
+        let Some(element_type) = unnested.ty.element_type() else {
+            return Err(Error::ExecError(format!(
+                "can't unnest column `{column}` of type `{}`",
+                unnested.ty.name()
+            )));
+        };
+        unnested.ty = element_type;
+
+        Ok(Self {
+            source,
+            column,
+            schema,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Expands source records until there are pending rows (or the source is
+    /// exhausted).
+    async fn fill(&mut self, db: &Db) -> DbResult<()> {
+        while self.pending.is_empty() {
+            let Some(record) = self.source.next(db).await? else {
+                break;
+            };
+            self.expand(record)?;
+        }
+        Ok(())
+    }
+
+    /// Expands the given record into the pending rows.
+    fn expand(&mut self, record: Row) -> DbResult<()> {
+        let mut values = record.into_values(self.source.schema());
+        let array = values.remove(&self.column).expect("schematized record");
+        let (Value::Array(_, elements) | Value::NestedArray(_, _, elements)) = array else {
+            unreachable!("checked array column");
+        };
+        for element in elements {
+            let mut row = values.clone();
+            row.set(self.column.clone(), element);
+            self.pending
+                .push_back(row.try_into_schematized(&self.schema)?);
+        }
+        Ok(())
+    }
+}
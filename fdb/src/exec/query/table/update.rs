@@ -7,10 +7,19 @@ use crate::{
     catalog::{object::TableObject, page::HeapPage, record::simple_record},
     error::DbResult,
     exec::{
-        query::{self, table::SeqScan, Query},
+        expr::{Expr, Predicate},
+        locking::LockSet,
+        notify::ChangeKind,
+        operations::heap::span,
+        query::{
+            self,
+            table::{lock_record, Record, RecordId, SeqScan, TableIndexes},
+            Plan, Query,
+        },
         values::Values,
     },
-    util::io::SerializeCtx,
+    io::metrics::Counter,
+    util::io::{SerializeCtx, Size},
     Db,
 };
 
@@ -20,12 +29,41 @@ pub type Pred = dyn Sync + for<'v> Fn(&'v Values) -> bool;
 /// The updater function.
 pub type Updater = dyn Sync + for<'v> Fn(&'v mut Values);
 
+/// The policy used to deal with the padding left behind by updates that shrink
+/// a record.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PadPolicy {
+    /// Keeps the padding in the record, which may later be reused by another
+    /// update. The space is only reclaimed by a vacuum.
+    #[default]
+    Keep,
+    /// Reclaims the padding right away, rewriting the page region after the
+    /// record, if the padding size is greater than the given threshold (in
+    /// bytes). The [`RecordId`]s previously obtained from the page become
+    /// stale.
+    ReclaimAbove(u16),
+}
+
+impl PadPolicy {
+    /// Checks whether a padding section of the given size should be reclaimed.
+    fn should_reclaim(self, pad_size: u16) -> bool {
+        match self {
+            PadPolicy::Keep => false,
+            PadPolicy::ReclaimAbove(threshold) => pad_size > threshold,
+        }
+    }
+}
+
 /// An update query.
 pub struct Update<'a> {
     table: &'a TableObject,
     linear_scan: SeqScan<'a>,
-    pred: &'a Pred,
+    pred: Predicate<'a>,
     updater: &'a Updater,
+    pad_policy: PadPolicy,
+    indexes: Option<TableIndexes>,
+    /// The locks of the updated records, held until the query is dropped.
+    locks: Option<LockSet>,
 }
 
 #[async_trait]
@@ -35,69 +73,181 @@ impl Query for Update<'_> {
 
     #[instrument(name = "TableUpdate", level = "debug", skip_all)]
     async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.indexes.is_none() {
+            self.pred.check(&self.table.schema)?;
+            self.indexes = Some(TableIndexes::load(db, self.table).await?);
+        }
         loop {
-            let out = if let Some(mut record) = self.linear_scan.next(db).await? {
+            let out = if let Some(record) = self.linear_scan.next(db).await? {
                 let schema = &self.table.schema;
-                let values = record.as_data().as_values();
-
-                if record.is_deleted() || !(self.pred)(values) {
+                let skips = |record: &Record| {
+                    record.is_deleted() || !self.pred.matches(&record.as_data().to_values(schema))
+                };
+                if skips(&record) {
                     continue;
                 }
-
-                let page_id = record.page_id();
-                let offset = record.offset();
-                debug!(?page_id, "allocating page for write");
-                let guard = db.pager().get::<HeapPage>(page_id).await?;
-                let mut page = guard.write().await;
-
-                // Clone the current row and modify it.
-                let mut values = record.as_data().as_values().clone();
-                (self.updater)(&mut values);
-                let schematized_values = Cow::Owned(values.try_into_schematized(schema)?);
-
-                let serde_ctx = simple_record::TableRecordCtx {
-                    page_id,
-                    offset,
-                    schema,
-                };
-
-                match record.try_update(schematized_values) {
-                    Ok(_) => {
-                        debug!("updated in place");
-                        page.write_at(offset, |buf| record.serialize(buf, &serde_ctx))?;
-                        page.flush();
-                    }
-                    Err(new_data) => {
-                        debug!("new record didn't fit; allocating new space");
-
-                        record.set_deleted();
-                        page.write_at(offset, |buf| record.serialize(buf, &serde_ctx))?;
-                        // Must flush before executing `Insert`. Otherwise, deadlock. t-t
-                        page.flush();
-
-                        let values = new_data.into_owned().into_values();
-                        let mut ins = query::table::Insert::new(self.table, values);
-                        ins.next(db).await?;
-                    }
+                let (record, reread) = lock_record(db, self.table, &mut self.locks, record).await?;
+                if reread && skips(&record) {
+                    continue;
+                }
+                db.pager().metrics().incr(Counter::RecordsMatched);
+
+                let indexes = self.indexes.as_ref().expect("loaded above");
+                let (_, reclaimed) = update_record(
+                    db,
+                    self.table,
+                    indexes,
+                    record,
+                    self.updater,
+                    self.pad_policy,
+                )
+                .await?;
+                if reclaimed != 0 {
+                    self.linear_scan.rewind(reclaimed);
                 }
-
                 Some(())
             } else {
-                db.pager().flush_all().await?;
+                db.pager().flush().await?;
                 None
             };
             return Ok(out);
         }
     }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableUpdate")
+            .with("table", &self.table.name)
+            .with("pred", self.pred.describe())
+            .with("pad_policy", format!("{:?}", self.pad_policy))
+            .with_child(self.linear_scan.describe(db).await?))
+    }
 }
 
 impl<'s> Update<'s> {
     pub fn new(table: &'s TableObject, pred: &'s Pred, updater: &'s Updater) -> Update<'s> {
+        Self::with_predicate(table, Predicate::Fn(pred), updater)
+    }
+
+    /// Creates an update of the records that match the given (boolean)
+    /// expression, which is type-checked against the table schema.
+    pub fn with_expr(table: &'s TableObject, expr: &'s Expr, updater: &'s Updater) -> Update<'s> {
+        Self::with_predicate(table, Predicate::Expr(expr), updater)
+    }
+
+    fn with_predicate(
+        table: &'s TableObject,
+        pred: Predicate<'s>,
+        updater: &'s Updater,
+    ) -> Update<'s> {
         Self {
             table,
             linear_scan: SeqScan::new(table),
             pred,
             updater,
+            pad_policy: PadPolicy::default(),
+            indexes: None,
+            locks: None,
+        }
+    }
+
+    /// Sets the [`PadPolicy`] used by this update. Notice that reclaiming the
+    /// padding moves the subsequent records in the page. Hence, the padding of
+    /// indexed tables is always kept.
+    pub fn with_pad_policy(mut self, pad_policy: PadPolicy) -> Update<'s> {
+        self.pad_policy = pad_policy;
+        self
+    }
+}
+
+/// Updates the given record (and the table indexes), returning its ID (which
+/// changes if the record had to be relocated) and the number of padding bytes
+/// reclaimed after it, according to the given [`PadPolicy`].
+pub(super) async fn update_record(
+    db: &Db,
+    table: &TableObject,
+    indexes: &TableIndexes,
+    mut record: Record,
+    updater: &Updater,
+    pad_policy: PadPolicy,
+) -> DbResult<(RecordId, u16)> {
+    let schema = &table.schema;
+    let page_id = record.page_id();
+    let offset = record.offset();
+    db.invalidate_results(&table.name);
+    debug!(?page_id, "allocating page for write");
+    let guard = db.pager().get::<HeapPage>(page_id).await?;
+    let mut page = guard.write().await;
+
+    // Clone the current row and modify it.
+    let old_values = record.as_data().clone();
+    let mut values = old_values.to_values(schema);
+    updater(&mut values);
+    let new_values = values.try_into_schematized(schema)?;
+    let schematized_values = Cow::Owned(new_values.clone());
+
+    indexes.check(db, &new_values)?;
+
+    let serde_ctx = simple_record::TableRecordCtx {
+        page_id,
+        offset,
+        schema,
+    };
+
+    match record.try_update(schematized_values) {
+        Ok(_) => {
+            debug!("updated in place");
+            // Reclaiming moves the subsequent records in the page, which would
+            // invalidate their index entries.
+            let reclaimed = if indexes.is_empty() && pad_policy.should_reclaim(record.pad_size()) {
+                record.trim_padding()
+            } else {
+                0
+            };
+            page.write_at(offset, |buf| record.serialize(buf, &serde_ctx))?;
+            if reclaimed != 0 {
+                debug!(reclaimed, "reclaiming record padding");
+                page.reclaim(offset + record.size() as u16, reclaimed);
+            }
+            let id = RecordId::new(page_id, offset, page.header.layout_version);
+            page.flush();
+
+            indexes
+                .update(db, &old_values, &new_values, page_id, offset)
+                .await?;
+            db.activity()
+                .record(table.page_id, |activity| activity.updates += 1);
+            db.statistics().record(table.page_id, |delta| {
+                delta.update(indexes, &old_values, &new_values)
+            });
+            db.notifier().record(&table.name, ChangeKind::Update, id);
+            Ok((id, reclaimed))
+        }
+        Err(new_data) => {
+            debug!("new record didn't fit; allocating new space");
+
+            record.set_deleted();
+            if record.is_spanned() {
+                span::set_deleted(&mut page, offset);
+            } else {
+                page.write_at(offset, |buf| record.serialize(buf, &serde_ctx))?;
+            }
+            // Must flush before executing `Insert`. Otherwise, deadlock. t-t
+            page.flush();
+
+            // The new record is indexed (and accounted for in the statistics)
+            // by `Insert`.
+            indexes.delete(db, &old_values, page_id, offset).await?;
+            db.statistics()
+                .record(table.page_id, |delta| delta.delete(&old_values));
+
+            let values = new_data.into_owned().into_values(schema);
+            let id = query::table::Insert::new(table, values).insert(db).await?;
+            db.activity().record(table.page_id, |activity| {
+                activity.updates += 1;
+                activity.dead_rows += 1;
+            });
+            db.notifier().record(&table.name, ChangeKind::Update, id);
+            Ok((id, 0))
         }
     }
 }
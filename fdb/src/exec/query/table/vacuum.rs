@@ -0,0 +1,324 @@
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        object::TableObject,
+        page::{HeapPage, PageId},
+        record::simple_record,
+    },
+    error::DbResult,
+    exec::{
+        operations::{heap::span, PhysicalState},
+        query::{
+            table::{seq_scan::mk_deserializer, TableIndexes},
+            table_page_count, Plan, Query,
+        },
+        util::macros::seq_h,
+        values::SchematizedValues,
+    },
+    util::io::{SerializeCtx, Size},
+    Db,
+};
+
+/// A vacuum query, which reclaims the space taken by dead records (and by the
+/// padding left behind by updates) of a table.
+///
+/// Each page is compacted in place, moving its live records to the start of
+/// the page. Pages that end up empty are released to the free list, unless
+/// they are the first or the last page of the sequence. So are the fragment
+/// pages of the removed records that span many pages (see
+/// [`span`](crate::exec::operations::heap::span)).
+///
+/// A vacuum may be incremental, i.e., limited to a few pages at a time (see
+/// [`Vacuum::with_page_budget`]), in which case it must be resumed from the
+/// position returned in [`VacuumStats::resume_at`].
+///
+/// Since records are moved, the [`RecordId`](super::RecordId)s of the
+/// vacuumed pages are invalidated, and rejected if used afterwards.
+pub struct Vacuum<'a> {
+    table: &'a TableObject,
+    start: u32,
+    page_budget: Option<u32>,
+    done: bool,
+}
+
+/// The statistics of a [`Vacuum`] run.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VacuumStats {
+    /// The number of visited pages.
+    pub pages_scanned: u32,
+    /// The number of pages released to the free list.
+    pub pages_released: u32,
+    /// The number of removed dead records.
+    pub records_removed: u64,
+    /// The number of reclaimed bytes, from dead records and record padding.
+    pub bytes_reclaimed: u64,
+    /// The position (i.e., the index of the page in the sequence) from which
+    /// the vacuum should be resumed, or `None` if the sequence was exhausted.
+    pub resume_at: Option<u32>,
+}
+
+/// The result of a page compaction.
+struct Compaction {
+    removed: u16,
+    reclaimed: u16,
+    /// The moved records: their values and their previous and new offsets.
+    moved: Vec<(SchematizedValues, u16, u16)>,
+    is_empty: bool,
+    /// The number of released fragment pages.
+    released: u32,
+    next_page_id: Option<PageId>,
+}
+
+#[async_trait]
+impl Query for Vacuum<'_> {
+    type Item<'a> = VacuumStats;
+
+    #[instrument(name = "TableVacuum", level = "debug", skip_all)]
+    async fn next<'a>(&mut self, db: &'a Db) -> DbResult<Option<Self::Item<'a>>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        // The dead record counters are decremented below, hence the pending
+        // deltas must be persisted first.
+        db.activity().persist(db.pager()).await?;
+        let indexes = TableIndexes::load(db, self.table).await?;
+        // The records are relocated, which changes their scan order.
+        db.invalidate_results(&self.table.name);
+
+        let first_page_id = self.table.page_id;
+        let (mut page_count, last_page_id) = db
+            .pager()
+            .read_with(first_page_id, |page: &HeapPage| {
+                let seq_header = seq_h!(page);
+                (seq_header.page_count, seq_header.last_page_id)
+            })
+            .await?;
+
+        // Walks to the starting position.
+        let mut prev_page_id = None;
+        let mut page_id = first_page_id;
+        let mut position = 0;
+        while position < self.start.min(page_count) {
+            prev_page_id = Some(page_id);
+            page_id = next_page_id(db, page_id).await?;
+            position += 1;
+        }
+
+        let mut stats = VacuumStats::default();
+        let has_budget = |stats: &VacuumStats| {
+            self.page_budget
+                .is_none_or(|budget| stats.pages_scanned < budget)
+        };
+        while position < page_count && has_budget(&stats) {
+            let compaction = self.compact(db, page_id, last_page_id).await?;
+            stats.pages_scanned += 1;
+            stats.pages_released += compaction.released;
+            page_count -= compaction.released;
+            stats.records_removed += compaction.removed as u64;
+            stats.bytes_reclaimed += compaction.reclaimed as u64;
+            for (values, from, to) in &compaction.moved {
+                indexes.relocate(db, values, page_id, *from, *to).await?;
+            }
+
+            let is_bound = page_id == first_page_id || page_id == last_page_id;
+            let next = compaction.next_page_id;
+            if compaction.is_empty && !is_bound {
+                let prev_page_id = prev_page_id.expect("not the first page");
+                debug!(?page_id, "releasing empty page");
+                let guard = db.pager().get::<HeapPage>(prev_page_id).await?;
+                let mut prev = guard.write().await;
+                prev.header.next_page_id = next;
+                prev.flush();
+                db.pager().dealloc(page_id).await?;
+
+                stats.pages_released += 1;
+                page_count -= 1;
+            } else {
+                prev_page_id = Some(page_id);
+                position += 1;
+            }
+            if position < page_count {
+                page_id = next.expect("must have +1");
+            }
+        }
+        stats.resume_at = (position < page_count).then_some(position);
+
+        let guard = db.pager().get::<HeapPage>(first_page_id).await?;
+        let mut page = guard.write().await;
+        let seq_header = seq_h!(mut page);
+        seq_header.record_count -= stats.records_removed;
+        seq_header.page_count -= stats.pages_released;
+        let activity = &mut seq_header.activity;
+        activity.dead_rows = activity.dead_rows.saturating_sub(stats.records_removed);
+        page.flush();
+
+        db.pager().flush().await?;
+        debug!(?stats, "vacuumed");
+        Ok(Some(stats))
+    }
+
+    async fn describe(&mut self, db: &Db) -> DbResult<Plan> {
+        Ok(Plan::new("TableVacuum")
+            .with("table", &self.table.name)
+            .with("pages", table_page_count(db, self.table).await?)
+            .with("start", self.start)
+            .with_opt("page_budget", self.page_budget))
+    }
+}
+
+impl<'a> Vacuum<'a> {
+    /// Creates a new vacuum executor over the whole table.
+    pub fn new(table: &'a TableObject) -> Vacuum<'a> {
+        Self {
+            table,
+            start: 0,
+            page_budget: None,
+            done: false,
+        }
+    }
+
+    /// Sets the position (i.e., the index of the page in the sequence) from
+    /// which the vacuum starts.
+    pub fn with_start(mut self, start: u32) -> Vacuum<'a> {
+        self.start = start;
+        self
+    }
+
+    /// Sets the maximum number of pages visited by the vacuum.
+    pub fn with_page_budget(mut self, page_budget: u32) -> Vacuum<'a> {
+        self.page_budget = Some(page_budget);
+        self
+    }
+
+    /// Compacts the given page, moving its live records to the start of the
+    /// page.
+    async fn compact(
+        &self,
+        db: &Db,
+        page_id: PageId,
+        last_page_id: PageId,
+    ) -> DbResult<Compaction> {
+        let schema = &self.table.schema;
+        let deserializer = mk_deserializer(schema);
+
+        let guard = db.pager().get::<HeapPage>(page_id).await?;
+        let mut page = guard.write().await;
+
+        let mut compaction = Compaction {
+            removed: 0,
+            reclaimed: 0,
+            moved: Vec::new(),
+            is_empty: false,
+            released: 0,
+            next_page_id: None,
+        };
+        let mut read_offset = page.first_offset();
+        let mut write_offset = page.first_offset();
+        for _ in 0..page.header.record_count {
+            let state = PhysicalState {
+                page_id,
+                offset: read_offset,
+            };
+            if let Some(head) = span::parse_head(&page, read_offset)? {
+                let size = head.size;
+                read_offset += size;
+                if head.is_deleted {
+                    compaction.removed += 1;
+                    compaction.reclaimed += size;
+                    let (_, fragment_page_ids) = span::assemble(db, head).await?;
+                    compaction.released +=
+                        release_fragments(db, &mut page, fragment_page_ids, last_page_id).await?;
+                    continue;
+                }
+                // Heads are moved as they are, since their fragments don't move.
+                if state.offset != write_offset {
+                    let range = state.offset as usize..read_offset as usize;
+                    page.bytes.copy_within(range, write_offset as usize);
+                    let (payload, _) = span::assemble(db, head).await?;
+                    let record = deserializer(&mut buff::BuffRead::new(&payload), state)?;
+                    let values = record.into_data().into_owned();
+                    compaction.moved.push((values, state.offset, write_offset));
+                }
+                write_offset += size;
+                continue;
+            }
+
+            let mut record = page.read_at(read_offset, |buf| deserializer(buf, state))?;
+            let size = record.size() as u16;
+            read_offset += size;
+
+            if record.is_deleted() {
+                compaction.removed += 1;
+                compaction.reclaimed += size;
+                continue;
+            }
+            let trimmed = record.trim_padding();
+            compaction.reclaimed += trimmed;
+            if state.offset != write_offset || trimmed != 0 {
+                let ctx = simple_record::TableRecordCtx {
+                    page_id,
+                    offset: write_offset,
+                    schema,
+                };
+                page.write_at(write_offset, |buf| record.serialize(buf, &ctx))?;
+            }
+            let new_offset = write_offset;
+            write_offset += record.size() as u16;
+            if state.offset != new_offset {
+                let values = record.into_data().into_owned();
+                compaction.moved.push((values, state.offset, new_offset));
+            }
+        }
+
+        if compaction.reclaimed != 0 {
+            page.truncate(write_offset);
+            page.header.record_count -= compaction.removed;
+        }
+        compaction.is_empty = page.header.record_count == 0 && !page.is_fragment();
+        compaction.next_page_id = page.header.next_page_id;
+        page.flush();
+        Ok(compaction)
+    }
+}
+
+/// Releases the given fragment pages, which directly follow the given page,
+/// returning the number of released pages. The last page of the sequence is
+/// kept (as an empty page) instead.
+async fn release_fragments(
+    db: &Db,
+    page: &mut HeapPage,
+    fragment_page_ids: Vec<PageId>,
+    last_page_id: PageId,
+) -> DbResult<u32> {
+    let mut released = 0;
+    for fragment_page_id in fragment_page_ids {
+        let guard = db.pager().get::<HeapPage>(fragment_page_id).await?;
+        let mut fragment = guard.write().await;
+        if fragment_page_id == last_page_id {
+            debug!(?fragment_page_id, "emptying last fragment page");
+            let first_offset = fragment.first_offset();
+            fragment.truncate(first_offset);
+            fragment.flush();
+            break;
+        }
+        debug!(?fragment_page_id, "releasing fragment page");
+        page.header.next_page_id = fragment.header.next_page_id;
+        fragment.flush();
+        db.pager().dealloc(fragment_page_id).await?;
+        released += 1;
+    }
+    Ok(released)
+}
+
+/// Returns the ID of the page that follows the given one.
+async fn next_page_id(db: &Db, page_id: PageId) -> DbResult<PageId> {
+    let next = db
+        .pager()
+        .read_with(page_id, |page: &HeapPage| page.header.next_page_id)
+        .await?;
+    Ok(next.expect("must have +1"))
+}
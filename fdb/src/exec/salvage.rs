@@ -0,0 +1,364 @@
+//! Salvaging of damaged databases. See [`Db::salvage_to`].
+//!
+//! The salvage copies every decodable record of each table into a new
+//! database, in which the indexes are then rebuilt. Unlike regular reads,
+//! pages are read regardless of their checksums (see
+//! [`Pager::read_raw_from_disk`]) and decoded tolerantly (see
+//! [`HeapPage::deserialize_tolerant`] and
+//! [`SimpleRecord::deserialize_tolerant`]), so that, e.g., a torn page still
+//! yields the records which weren't damaged.
+//!
+//! Records which can't be decoded are skipped and counted as lost. Pages which
+//! can't be decoded at all end their heap sequence, since the following pages
+//! are only reachable through them; the records of such pages aren't counted,
+//! as their number is unknown. Deleted records are never copied.
+
+use std::{collections::HashSet, path::Path};
+
+use buff::BuffRead;
+use tokio::fs;
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    catalog::{
+        object::{Object, ObjectType},
+        page::{FirstPage, HeapPage, PageId},
+        record::simple_record::SimpleRecord,
+        table_schema::TableSchema,
+    },
+    error::{DbResult, Error},
+    exec::{
+        operations::{
+            heap::span::{self, HEAD_HEADER_SIZE},
+            PhysicalState,
+        },
+        query::{self, table::BulkInsert},
+        values::{SchematizedValues, Values},
+    },
+    io::pager::Pager,
+    util::io::{Deserialize, DeserializeCtx, Size},
+    Db, DbOptions,
+};
+
+/// The result of a salvage. See [`Db::salvage_to`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SalvageReport {
+    /// The salvage of the database schema, whose records are the objects.
+    pub schema: SeqSalvage,
+    /// The salvage of each table, in the schema order.
+    pub tables: Vec<(String, SeqSalvage)>,
+    /// The number of rebuilt indexes.
+    pub indexes: u32,
+    /// The indexes and external tables which couldn't be recreated (e.g.,
+    /// indexes whose table was lost).
+    pub skipped_objects: Vec<String>,
+}
+
+impl SalvageReport {
+    /// Checks whether nothing was lost, i.e., whether all records and objects
+    /// were salvaged.
+    pub fn is_lossless(&self) -> bool {
+        self.schema.is_lossless()
+            && self.tables.iter().all(|(_, table)| table.is_lossless())
+            && self.skipped_objects.is_empty()
+    }
+}
+
+/// The salvage of a heap sequence, i.e., of a table or of the database schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeqSalvage {
+    /// The number of salvaged (live) records.
+    pub records: u64,
+    /// The number of records of the read pages which couldn't be decoded.
+    pub lost_records: u64,
+    /// The number of read pages whose checksum didn't match.
+    pub damaged_pages: u32,
+    /// Whether all pages of the sequence were read. Otherwise, the records of
+    /// the pages which follow the first undecodable one are lost.
+    pub complete: bool,
+}
+
+impl SeqSalvage {
+    fn new() -> SeqSalvage {
+        SeqSalvage {
+            records: 0,
+            lost_records: 0,
+            damaged_pages: 0,
+            complete: true,
+        }
+    }
+
+    /// Checks whether no record was lost.
+    pub fn is_lossless(&self) -> bool {
+        self.complete && self.lost_records == 0
+    }
+}
+
+/// Salvages the given database into a new database file at the given path.
+///
+/// Pages are read from the disk, hence callers must write the dirty pages
+/// beforehand and guarantee that no page is written while the salvage runs
+/// (e.g., by holding the statement latch, see [`Db::salvage_to`]).
+#[instrument(level = "debug", skip_all)]
+pub async fn salvage(src: &Db, path: &Path) -> DbResult<SalvageReport> {
+    if fs::metadata(path).await.is_ok() {
+        return Err(Error::ExecError(format!(
+            "`{}` already exists",
+            path.display()
+        )));
+    }
+    let options = DbOptions::new().with_page_size(src.page_size());
+    let (dst, _) = Db::open_with_options(path, options).await?;
+    let pager = src.pager();
+
+    let mut report = SalvageReport {
+        schema: SeqSalvage::new(),
+        tables: Vec::new(),
+        indexes: 0,
+        skipped_objects: Vec::new(),
+    };
+
+    let schema_page_id = pager
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.header.first_schema_seq_page_id
+        })
+        .await?;
+    let mut objects = Vec::new();
+    let mut scan = PageScan::new(schema_page_id);
+    let decode = |buf: &mut BuffRead<'_>| Object::deserialize(buf);
+    while let Some(page) = scan.next(pager, &mut report.schema).await {
+        for record in page_records(pager, &page, &mut report.schema, decode).await {
+            if !record.is_deleted() {
+                report.schema.records += 1;
+                objects.push(record.into_data().into_owned());
+            }
+        }
+    }
+
+    for object in &objects {
+        match &object.ty {
+            ObjectType::Table(schema) => {
+                let salvage =
+                    salvage_table(src, &dst, &object.name, schema, object.page_id).await?;
+                info!(table = object.name, ?salvage, "salvaged table");
+                report.tables.push((object.name.clone(), salvage));
+            }
+            ObjectType::External(schema) => {
+                let create = query::object::CreateExternalTable::new(&object.name, schema.clone());
+                if let Err(error) = dst.execute(create, |_| Ok::<_, ()>(())).await {
+                    warn!(table = object.name, %error, "can't recreate external table");
+                    report.skipped_objects.push(object.name.clone());
+                }
+            }
+            // Indexes are rebuilt once their tables are salvaged, and the
+            // statistics are recreated along with their tables.
+            ObjectType::Index(_) | ObjectType::Statistics(_) => (),
+        }
+    }
+    for object in objects {
+        if let ObjectType::Index(schema) = object.ty {
+            let create = query::index::Create::new(&object.name, schema.table, schema.column);
+            match dst.execute(create, |_| Ok::<_, ()>(())).await {
+                Ok(_) => report.indexes += 1,
+                Err(error) => {
+                    warn!(index = object.name, %error, "can't rebuild index");
+                    report.skipped_objects.push(object.name);
+                }
+            }
+        }
+    }
+
+    dst.close().await?;
+    Ok(report)
+}
+
+/// Creates the given table in the new database, copying the decodable records
+/// of the sequence which starts at the given page into it.
+async fn salvage_table(
+    src: &Db,
+    dst: &Db,
+    name: &str,
+    schema: &TableSchema,
+    first_page_id: PageId,
+) -> DbResult<SeqSalvage> {
+    let create = query::object::CreateTable::new(name, schema.clone());
+    dst.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let table = Object::find(dst, name).await?.try_into_table()?;
+
+    let mut salvage = SeqSalvage::new();
+    let mut scan = PageScan::new(first_page_id);
+    let decode = |buf: &mut BuffRead<'_>| SchematizedValues::deserialize(buf, schema);
+    while let Some(page) = scan.next(src.pager(), &mut salvage).await {
+        let rows: Vec<Values> = page_records(src.pager(), &page, &mut salvage, decode)
+            .await
+            .into_iter()
+            .filter(|record| !record.is_deleted())
+            .map(|record| record.into_data().into_owned().into_values(schema))
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+        salvage.records += rows.len() as u64;
+        let insert = BulkInsert::new(&table, rows);
+        dst.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    Ok(salvage)
+}
+
+/// A tolerant scan over the pages of a heap sequence.
+struct PageScan {
+    next_page_id: Option<PageId>,
+    /// The number of pages yet to be read, once known from the first page.
+    remaining: Option<u32>,
+    visited: HashSet<PageId>,
+}
+
+impl PageScan {
+    fn new(first_page_id: PageId) -> PageScan {
+        PageScan {
+            next_page_id: Some(first_page_id),
+            remaining: None,
+            visited: HashSet::new(),
+        }
+    }
+
+    /// Returns the next page of the sequence. Returns `None` once the sequence
+    /// ends, or once a page can't be decoded (or the sequence cycles), in which
+    /// case the salvage is marked as incomplete.
+    async fn next(&mut self, pager: &Pager, salvage: &mut SeqSalvage) -> Option<HeapPage> {
+        let page_id = self.next_page_id.take()?;
+        if self.remaining == Some(0) {
+            return None;
+        }
+        if !self.visited.insert(page_id) {
+            warn!(?page_id, "heap sequence cycles");
+            salvage.complete = false;
+            return None;
+        }
+        let page = match read_page(pager, page_id).await {
+            Ok((page, valid)) => {
+                if !valid {
+                    warn!(?page_id, "salvaging page with checksum mismatch");
+                    salvage.damaged_pages += 1;
+                }
+                page
+            }
+            Err(error) => {
+                warn!(?page_id, %error, "skipping undecodable page");
+                salvage.complete = false;
+                return None;
+            }
+        };
+        // As in `heap::page_ids`, the sequence is traversed using the page
+        // count, since the last page's `next_page_id` may not be null. If the
+        // first page has no sequence header, the pages are followed until the
+        // sequence ends (or cycles).
+        let remaining = self.remaining.get_or_insert_with(|| {
+            (page.header.seq_header.as_ref()).map_or(u32::MAX, |seq_header| seq_header.page_count)
+        });
+        *remaining = remaining.saturating_sub(1);
+        self.next_page_id = page.header.next_page_id;
+        Some(page)
+    }
+}
+
+/// Reads the given heap page tolerantly, also returning whether its checksum
+/// matches.
+async fn read_page(pager: &Pager, page_id: PageId) -> DbResult<(HeapPage, bool)> {
+    let (payload, valid) = pager.read_raw_from_disk(page_id).await?;
+    let page = HeapPage::deserialize_tolerant(&payload)?;
+    if page.header.id != page_id {
+        return Err(Error::CorruptedPage("unexpected page id"));
+    }
+    Ok((page, valid))
+}
+
+/// Decodes the records of the given page with the given closure, skipping (and
+/// counting) the undecodable ones. Spanned records are assembled.
+async fn page_records<D, F>(
+    pager: &Pager,
+    page: &HeapPage,
+    salvage: &mut SeqSalvage,
+    decode: F,
+) -> Vec<SimpleRecord<'static, D>>
+where
+    D: Size + Clone + 'static,
+    F: Fn(&mut BuffRead<'_>) -> DbResult<D>,
+{
+    let end = page.header.free_offset as usize;
+    let mut offset = page.first_offset() as usize;
+    let mut records = Vec::new();
+    for i in 0..page.header.record_count {
+        // The size of each record is required to find the following ones, so
+        // that the rest of the page is lost if it is malformed.
+        let size: Option<u16> = BuffRead::new(&page.bytes[offset..end]).try_read().ok();
+        let Some(size) = size
+            .map(usize::from)
+            .filter(|&size| size >= 3 && offset + size <= end)
+        else {
+            salvage.lost_records += u64::from(page.header.record_count - i);
+            break;
+        };
+
+        let location = PhysicalState {
+            page_id: page.header.id,
+            offset: offset as u16,
+        };
+        let bytes = &page.bytes[offset..offset + size];
+        let record = if bytes[2] & span::SPANNED != 0 {
+            match assemble(pager, page, bytes).await {
+                Ok(payload) => SimpleRecord::deserialize_tolerant(
+                    &mut BuffRead::new(&payload),
+                    location,
+                    &decode,
+                ),
+                Err(error) => Err(error),
+            }
+        } else {
+            SimpleRecord::deserialize_tolerant(&mut BuffRead::new(bytes), location, &decode)
+        };
+        match record {
+            Ok(record) => records.push(record),
+            Err(error) => {
+                debug!(?location, %error, "skipping undecodable record");
+                salvage.lost_records += 1;
+            }
+        }
+        offset += size;
+    }
+    records
+}
+
+/// Assembles the payload of the spanned record whose head is given, reading
+/// its fragment pages tolerantly. See [`span`].
+async fn assemble(pager: &Pager, page: &HeapPage, head: &[u8]) -> DbResult<Vec<u8>> {
+    if head.len() < HEAD_HEADER_SIZE as usize {
+        return Err(Error::CorruptedRecord("invalid head size"));
+    }
+    let flags = head[2];
+    let payload_len: u16 = BuffRead::new(&head[3..]).read();
+    let mut payload = head[HEAD_HEADER_SIZE as usize..].to_vec();
+
+    let mut next_page_id = page.header.next_page_id;
+    while payload.len() < payload_len as usize {
+        let page_id = next_page_id.ok_or(Error::CorruptedRecord("missing fragment page"))?;
+        let (page, _) = read_page(pager, page_id).await?;
+        if !page.is_fragment() {
+            return Err(Error::CorruptedRecord("missing fragment page"));
+        }
+        let mut buf = BuffRead::new(&page.bytes[..page.header.free_offset as usize]);
+        let len: u16 = buf.try_read()?;
+        if len == 0 {
+            return Err(Error::CorruptedRecord("empty fragment"));
+        }
+        payload.extend_from_slice(buf.try_read_bytes(len as usize)?);
+        next_page_id = page.header.next_page_id;
+    }
+    if payload.len() != payload_len as usize || payload.len() < 3 {
+        return Err(Error::CorruptedRecord("payload length mismatch"));
+    }
+    // As in `span::assemble`, the payload keeps the flags as they were when
+    // the record was written.
+    payload[2] = (flags & span::DELETED != 0) as u8;
+    Ok(payload)
+}
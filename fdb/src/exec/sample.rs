@@ -0,0 +1,106 @@
+//! Deterministic random sampling.
+//!
+//! Random values are drawn from a seeded [`Rng`], so that sampled reads are
+//! reproducible: the same seed over the same data yields the same sample. Table
+//! scans may also sample whole pages (see [`PageSample`]), in which case the
+//! records of the pages which aren't sampled are never deserialized.
+//!
+//! There's no floating-point type yet, so random values are integers within
+//! `0..=RANDOM_MAX`, and fractions are expressed as thresholds over them (see
+//! [`threshold`]).
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::catalog::page::PageId;
+
+/// The maximum random value. See [`Rng::next_value`].
+pub const RANDOM_MAX: i64 = i32::MAX as i64;
+
+/// The SplitMix64 increment.
+const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Returns the threshold under which random values fall with the probability
+/// of the given percentage, which is clamped to `0..=100`.
+pub fn threshold(percent: i64) -> i64 {
+    percent.clamp(0, 100) * (RANDOM_MAX + 1) / 100
+}
+
+/// A seeded pseudo-random number generator (SplitMix64).
+///
+/// Values may be drawn through a shared reference, so that the generator may
+/// be used by the predicates of the executors.
+#[derive(Debug)]
+pub struct Rng {
+    state: AtomicU64,
+}
+
+impl Rng {
+    /// Constructs a generator with the given seed.
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// Returns a random seed.
+    pub fn random_seed() -> u64 {
+        RandomState::new().build_hasher().finish()
+    }
+
+    /// Returns the next random 64-bit number.
+    pub fn next_u64(&self) -> u64 {
+        mix(self
+            .state
+            .fetch_add(GAMMA, Ordering::Relaxed)
+            .wrapping_add(GAMMA))
+    }
+
+    /// Returns the next random value, uniformly distributed within
+    /// `0..=RANDOM_MAX`.
+    pub fn next_value(&self) -> i64 {
+        to_value(self.next_u64())
+    }
+
+    /// Returns `true` with the probability of the given percentage.
+    pub fn sample(&self, percent: i64) -> bool {
+        self.next_value() < threshold(percent)
+    }
+}
+
+/// A sample of the pages of a table, each of which is included if its random
+/// value (derived from the seed and the page ID) is below the threshold.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PageSample {
+    seed: u64,
+    threshold: i64,
+}
+
+impl PageSample {
+    /// Constructs a sample of the pages whose random values are below the
+    /// given threshold. See [`threshold`].
+    pub fn new(seed: u64, threshold: i64) -> PageSample {
+        PageSample { seed, threshold }
+    }
+
+    /// Checks whether the given page is sampled.
+    pub fn includes(&self, page_id: PageId) -> bool {
+        let hash = mix(self.seed ^ (page_id.get() as u64).wrapping_mul(GAMMA));
+        to_value(hash) < self.threshold
+    }
+}
+
+/// The SplitMix64 output function.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Maps a random 64-bit number into `0..=RANDOM_MAX`.
+fn to_value(n: u64) -> i64 {
+    (n >> 33) as i64
+}
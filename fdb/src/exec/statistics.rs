@@ -0,0 +1,308 @@
+//! Table statistics.
+//!
+//! Each table has a statistics object in the database schema (see
+//! [`TableStatistics`]), which is created along with the table. Table queries
+//! record the changes to the statistics of their tables in the database's
+//! [`StatisticsTracker`]. As with the activity counters (see
+//! [`activity`](crate::exec::activity)), the changes are kept in memory as
+//! deltas, which are merged into the persisted statistics at the same time the
+//! activity deltas are persisted.
+//!
+//! Hence, the statistics are approximate: a crash may lose the most recent
+//! deltas, and deletes never shrink the ranges of the columns. An [`Analyze`]
+//! recomputes them from the table records. Tables created before statistics
+//! were introduced have no statistics object until they are analyzed.
+//!
+//! Only the ranges of the indexed columns are tracked.
+//!
+//! [`Analyze`]: crate::exec::query::table::Analyze
+
+use std::{borrow::Cow, collections::HashMap, mem, sync::Mutex};
+
+use buff::BuffRead;
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        object::{Object, ObjectType, TableObject},
+        page::{HeapPage, PageId},
+        record::simple_record::{SimpleCtx, SimpleRecord},
+        statistics::{ColumnStatistics, TableStatistics},
+    },
+    error::DbResult,
+    exec::{
+        operations::{
+            heap::{self, span},
+            PhysicalState,
+        },
+        query::table::TableIndexes,
+        util::macros::seq_h,
+        values::SchematizedValues,
+    },
+    util::io::{DeserializeCtx, Serialize, Size},
+    Db,
+};
+
+/// The prefix of the names of the statistics objects, which are followed by
+/// the name of their tables.
+pub const NAME_PREFIX: &str = "__stats_";
+
+const FIRST_SCHEMA_PAGE_ID: PageId = PageId::new_u32(2);
+
+type ObjectRecord = SimpleRecord<'static, Object>;
+
+/// The in-memory statistics deltas of the database tables, keyed by the ID of
+/// their first page.
+#[derive(Debug, Default)]
+pub struct StatisticsTracker {
+    deltas: Mutex<HashMap<PageId, StatisticsDelta>>,
+}
+
+impl StatisticsTracker {
+    /// Records changes to the table whose sequence starts at the given page.
+    pub(crate) fn record(&self, page_id: PageId, f: impl FnOnce(&mut StatisticsDelta)) {
+        let mut deltas = self.deltas.lock().unwrap();
+        f(deltas.entry(page_id).or_default());
+    }
+
+    /// Discards the deltas of the table whose sequence starts at the given
+    /// page, e.g., because the table was dropped or analyzed.
+    pub(crate) fn forget(&self, page_id: PageId) {
+        self.deltas.lock().unwrap().remove(&page_id);
+    }
+
+    /// Merges the in-memory deltas into the persisted statistics. The deltas
+    /// of tables without statistics are discarded.
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn persist(&self, db: &Db) -> DbResult<()> {
+        let deltas = mem::take(&mut *self.deltas.lock().unwrap());
+        debug!(tables = deltas.len(), "persisting statistics deltas");
+        for (page_id, delta) in deltas {
+            let Some((record, mut statistics)) = find(db, page_id).await? else {
+                continue;
+            };
+            delta.apply(&mut statistics);
+            statistics.page_count = page_count(db, page_id).await?;
+            let name = record.as_data().name.clone();
+            write(db, Some(record), page_id, name, statistics).await?;
+        }
+        db.pager().flush().await
+    }
+}
+
+/// The changes to the statistics of a table. See [`StatisticsTracker`].
+#[derive(Debug, Default)]
+pub(crate) struct StatisticsDelta {
+    rows: i64,
+    size: i64,
+    columns: Vec<ColumnStatistics>,
+}
+
+impl StatisticsDelta {
+    /// Records the insertion of the given record.
+    pub fn insert(&mut self, indexes: &TableIndexes, values: &SchematizedValues) {
+        self.rows += 1;
+        self.size += values.size() as i64;
+        self.widen(indexes, values);
+    }
+
+    /// Records the deletion of the given record.
+    pub fn delete(&mut self, values: &SchematizedValues) {
+        self.rows -= 1;
+        self.size -= values.size() as i64;
+    }
+
+    /// Records the update of the given record.
+    pub fn update(
+        &mut self,
+        indexes: &TableIndexes,
+        old: &SchematizedValues,
+        new: &SchematizedValues,
+    ) {
+        self.size += new.size() as i64 - old.size() as i64;
+        self.widen(indexes, new);
+    }
+
+    fn widen(&mut self, indexes: &TableIndexes, values: &SchematizedValues) {
+        for (column, ordinal) in indexes.columns() {
+            let value = &values.as_slice()[ordinal];
+            match self.columns.iter_mut().find(|stats| stats.column == column) {
+                Some(stats) => stats.widen(value),
+                None => self.columns.push(ColumnStatistics::new(column, value)),
+            }
+        }
+    }
+
+    /// Adds the changes to the given statistics.
+    ///
+    /// The ranges of the columns indexed over existing records are set when
+    /// the index is created (see [`track_column`]). Hence, columns without a
+    /// range were indexed while the table was empty, and their ranges are the
+    /// ones of the delta.
+    fn apply(&self, statistics: &mut TableStatistics) {
+        statistics.row_count = statistics.row_count.saturating_add_signed(self.rows);
+        statistics.total_size = statistics.total_size.saturating_add_signed(self.size);
+        for column in &self.columns {
+            match (statistics.columns.iter_mut()).find(|stats| stats.column == column.column) {
+                Some(stats) => stats.merge(column),
+                None => statistics.columns.push(column.clone()),
+            }
+        }
+    }
+}
+
+/// Returns the statistics of the given table, including the pending deltas,
+/// or `None` if the table has no statistics object.
+pub async fn table_statistics(db: &Db, table: &TableObject) -> DbResult<Option<TableStatistics>> {
+    let Some((_, mut statistics)) = find(db, table.page_id).await? else {
+        return Ok(None);
+    };
+    if let Some(delta) = db.statistics().deltas.lock().unwrap().get(&table.page_id) {
+        delta.apply(&mut statistics);
+    }
+    statistics.page_count = page_count(db, table.page_id).await?;
+    Ok(Some(statistics))
+}
+
+/// Creates the statistics object of the given (new) table.
+pub(crate) async fn create(db: &Db, table: &TableObject) -> DbResult<()> {
+    let statistics = TableStatistics {
+        page_count: 1,
+        ..TableStatistics::default()
+    };
+    let name = format!("{NAME_PREFIX}{}", table.name);
+    write(db, None, table.page_id, name, statistics).await
+}
+
+/// Replaces the statistics of the given table (creating its statistics object,
+/// if needed) and discards the pending deltas.
+pub(crate) async fn replace(
+    db: &Db,
+    table: &TableObject,
+    statistics: TableStatistics,
+) -> DbResult<()> {
+    db.statistics().forget(table.page_id);
+    let record = find(db, table.page_id).await?.map(|(record, _)| record);
+    let name = format!("{NAME_PREFIX}{}", table.name);
+    write(db, record, table.page_id, name, statistics).await
+}
+
+/// Sets the range of the given column, which was just indexed. Ignored if the
+/// table has no statistics object.
+pub(crate) async fn track_column(
+    db: &Db,
+    table: &TableObject,
+    column: ColumnStatistics,
+) -> DbResult<()> {
+    let Some((record, mut statistics)) = find(db, table.page_id).await? else {
+        return Ok(());
+    };
+    match (statistics.columns.iter_mut()).find(|stats| stats.column == column.column) {
+        Some(stats) => *stats = column,
+        None => statistics.columns.push(column),
+    }
+    let name = record.as_data().name.clone();
+    write(db, Some(record), table.page_id, name, statistics).await
+}
+
+/// Marks the statistics object of the table whose sequence starts at the given
+/// page as deleted, if there is one, and discards the pending deltas.
+pub(crate) async fn remove(db: &Db, page_id: PageId) -> DbResult<()> {
+    db.statistics().forget(page_id);
+    if let Some((mut record, _)) = find(db, page_id).await? {
+        let guard = db.pager().get::<HeapPage>(record.page_id()).await?;
+        let mut page = guard.write().await;
+        mark_deleted(&mut page, &mut record)?;
+        page.flush();
+    }
+    Ok(())
+}
+
+/// Finds the statistics object of the table whose sequence starts at the given
+/// page.
+async fn find(db: &Db, page_id: PageId) -> DbResult<Option<(ObjectRecord, TableStatistics)>> {
+    let mut seq_scan = heap::SeqScan::<ObjectRecord>::new(FIRST_SCHEMA_PAGE_ID);
+    while let Some(record) = seq_scan.next(db, deserializer).await? {
+        let object = record.as_data();
+        if record.is_deleted() || object.page_id != page_id {
+            continue;
+        }
+        if let ObjectType::Statistics(statistics) = &object.ty {
+            let statistics = statistics.clone();
+            return Ok(Some((record, statistics)));
+        }
+    }
+    Ok(None)
+}
+
+/// Writes the given statistics, in place of the given record, if it fits.
+/// Otherwise, the record is marked as deleted and a new one is appended to the
+/// database schema.
+///
+/// Statistics objects are never looked up by their names, hence the catalog
+/// version isn't bumped.
+async fn write(
+    db: &Db,
+    record: Option<ObjectRecord>,
+    page_id: PageId,
+    name: String,
+    statistics: TableStatistics,
+) -> DbResult<()> {
+    let object = Object {
+        ty: ObjectType::Statistics(statistics),
+        page_id,
+        name,
+    };
+    let object = match record {
+        Some(mut record) => {
+            let guard = db.pager().get::<HeapPage>(record.page_id()).await?;
+            let mut page = guard.write().await;
+            let offset = record.offset();
+            match record.try_update(Cow::Owned(object)) {
+                Ok(()) => {
+                    debug!(?page_id, "updated statistics in place");
+                    page.write_at(offset, |buf| record.serialize(buf))?;
+                    page.flush();
+                    return Ok(());
+                }
+                Err(object) => {
+                    mark_deleted(&mut page, &mut record)?;
+                    // Must flush before appending, since the page may be the
+                    // last one of the schema.
+                    page.flush();
+                    object.into_owned()
+                }
+            }
+        }
+        None => object,
+    };
+    // Records don't serialize their own locations.
+    let record = SimpleRecord::<Object>::new(FIRST_SCHEMA_PAGE_ID, 0, Cow::Owned(object));
+    heap::append(db.pager(), FIRST_SCHEMA_PAGE_ID, record.size(), |buf| {
+        record.serialize(buf)
+    })
+    .await?;
+    Ok(())
+}
+
+fn mark_deleted(page: &mut HeapPage, record: &mut ObjectRecord) -> DbResult<()> {
+    let offset = record.offset();
+    record.set_deleted();
+    if record.is_spanned() {
+        span::set_deleted(page, offset);
+    } else {
+        page.write_at(offset, |buf| record.serialize(buf))?;
+    }
+    Ok(())
+}
+
+async fn page_count(db: &Db, page_id: PageId) -> DbResult<u32> {
+    db.pager()
+        .read_with(page_id, |page: &HeapPage| seq_h!(page).page_count)
+        .await
+}
+
+fn deserializer(buf: &mut BuffRead<'_>, state: PhysicalState) -> DbResult<ObjectRecord> {
+    let ctx = SimpleCtx::from_physical(state);
+    ObjectRecord::deserialize(buf, &ctx)
+}
@@ -0,0 +1,283 @@
+//! Civil (i.e., calendar and wall clock) time.
+//!
+//! All values are in UTC, using the proleptic Gregorian calendar. Timestamps
+//! (see [`PrimitiveTypeId::Timestamp`]) are milliseconds since the Unix epoch,
+//! and may be split into (or built from) a [`Date`] and a [`Time`].
+//!
+//! The textual representations are the ISO-8601 ones, e.g., `2023-03-14` for
+//! dates, `12:30:00` (or `12:30:00.250`) for times and `2023-03-14T12:30:00Z`
+//! for timestamps.
+//!
+//! [`PrimitiveTypeId::Timestamp`]: crate::catalog::ty::PrimitiveTypeId::Timestamp
+
+use std::fmt;
+
+const MILLIS_PER_SECOND: u32 = 1000;
+const MILLIS_PER_MINUTE: u32 = 60 * MILLIS_PER_SECOND;
+const MILLIS_PER_HOUR: u32 = 60 * MILLIS_PER_MINUTE;
+const MILLIS_PER_DAY: u32 = 24 * MILLIS_PER_HOUR;
+
+/// A calendar date, as the number of days since the Unix epoch (`1970-01-01`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date(pub i32);
+
+impl Date {
+    /// Returns the date of the given year, month (`1..=12`) and day (`1..=31`).
+    /// Returns `None` if there's no such date.
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Date> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        let days = days_from_civil(year as i64, month, day);
+        Some(Date(days.try_into().ok()?))
+    }
+
+    /// Returns the year, month (`1..=12`) and day (`1..=31`) of the date.
+    pub fn ymd(self) -> (i32, u32, u32) {
+        let (year, month, day) = civil_from_days(self.0 as i64);
+        (year as i32, month, day)
+    }
+
+    /// Parses a date in the `YYYY-MM-DD` format.
+    pub fn parse(text: &str) -> Option<Date> {
+        let mut parts = text.split('-');
+        let year = digits(parts.next()?, 4)?;
+        let month = digits(parts.next()?, 2)?;
+        let day = digits(parts.next()?, 2)?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Date::from_ymd(year as i32, month, day)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// A time of day, as the number of milliseconds since midnight.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time(pub u32);
+
+impl Time {
+    /// Returns the time of the given hour (`0..24`), minute (`0..60`), second
+    /// (`0..60`) and millisecond (`0..1000`). Returns `None` if any of them is
+    /// out of range.
+    pub fn from_hms_milli(hour: u32, min: u32, sec: u32, milli: u32) -> Option<Time> {
+        if hour >= 24 || min >= 60 || sec >= 60 || milli >= MILLIS_PER_SECOND {
+            return None;
+        }
+        Some(Time(
+            hour * MILLIS_PER_HOUR + min * MILLIS_PER_MINUTE + sec * MILLIS_PER_SECOND + milli,
+        ))
+    }
+
+    /// Returns the hour, minute, second and millisecond of the time.
+    pub fn hms_milli(self) -> (u32, u32, u32, u32) {
+        (
+            self.0 / MILLIS_PER_HOUR,
+            self.0 / MILLIS_PER_MINUTE % 60,
+            self.0 / MILLIS_PER_SECOND % 60,
+            self.0 % MILLIS_PER_SECOND,
+        )
+    }
+
+    /// Parses a time in the `HH:MM[:SS[.fff]]` format, where the fraction of
+    /// the seconds has one to three digits.
+    pub fn parse(text: &str) -> Option<Time> {
+        let (text, fraction) = match text.split_once('.') {
+            Some((text, fraction)) => (text, Some(fraction)),
+            None => (text, None),
+        };
+        let mut parts = text.split(':');
+        let hour = digits(parts.next()?, 2)?;
+        let min = digits(parts.next()?, 2)?;
+        let sec = match parts.next() {
+            Some(sec) => Some(digits(sec, 2)?),
+            None => None,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        let milli = match (fraction, sec) {
+            (None, _) => 0,
+            // The fraction must follow the seconds.
+            (Some(fraction), Some(_)) if (1..=3).contains(&fraction.len()) => {
+                digits(fraction, fraction.len())? * 10_u32.pow(3 - fraction.len() as u32)
+            }
+            (Some(_), _) => return None,
+        };
+        Time::from_hms_milli(hour, min, sec.unwrap_or(0), milli)
+    }
+}
+
+impl fmt::Display for Time {
+    /// Formats the time as `HH:MM:SS`, followed by the milliseconds (e.g.,
+    /// `.250`), if any.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (hour, min, sec, milli) = self.hms_milli();
+        write!(f, "{hour:02}:{min:02}:{sec:02}")?;
+        if milli > 0 {
+            write!(f, ".{milli:03}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the timestamp (i.e., the milliseconds since the Unix epoch) of the
+/// given date and time.
+pub fn timestamp(date: Date, time: Time) -> i64 {
+    date.0 as i64 * MILLIS_PER_DAY as i64 + time.0 as i64
+}
+
+/// Splits the given timestamp into its date and time. See [`timestamp`].
+pub fn split_timestamp(millis: i64) -> (Date, Time) {
+    let days = millis.div_euclid(MILLIS_PER_DAY as i64);
+    let time = millis.rem_euclid(MILLIS_PER_DAY as i64);
+    // The range of `i64` milliseconds is well within the range of `i32` days.
+    (Date(days as i32), Time(time as u32))
+}
+
+/// Parses a timestamp in the `YYYY-MM-DD[THH:MM[:SS[.fff]]][Z]` format, where
+/// the date and time may also be separated by a space. Times are in UTC.
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let text = text.strip_suffix('Z').unwrap_or(text);
+    let (date, time) = match text.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Time::parse(time)?),
+        None => (text, Time::default()),
+    };
+    Some(timestamp(Date::parse(date)?, time))
+}
+
+/// Formats the given timestamp as `YYYY-MM-DDTHH:MM:SS.fffZ`.
+pub fn fmt_timestamp(millis: i64, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (date, time) = split_timestamp(millis);
+    let (hour, min, sec, milli) = time.hms_milli();
+    write!(f, "{date}T{hour:02}:{min:02}:{sec:02}.{milli:03}Z")
+}
+
+/// Parses a number of exactly the given number of ASCII digits.
+fn digits(text: &str, len: usize) -> Option<u32> {
+    if text.len() != len || !text.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Converts the number of days since the Unix epoch into a date, as in
+/// <http://howardhinnant.github.io/date_algorithms.html>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097); // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11]
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32; // [1, 31]
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32; // [1, 12]
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Converts a date into the number of days since the Unix epoch. The inverse
+/// of [`civil_from_days`].
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400); // [0, 399]
+    let mp = (month as i64 + 9) % 12; // [0, 11]
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1; // [0, 365]
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy; // [0, 146096]
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date() {
+        assert_eq!(Date::from_ymd(1970, 1, 1), Some(Date(0)));
+        assert_eq!(Date::from_ymd(2023, 3, 14), Some(Date(19_430)));
+        assert_eq!(Date::from_ymd(1969, 12, 31), Some(Date(-1)));
+        assert_eq!(Date::from_ymd(2024, 2, 29).unwrap().ymd(), (2024, 2, 29));
+        assert_eq!(Date::from_ymd(2023, 2, 29), None);
+        assert_eq!(Date::from_ymd(1900, 2, 29), None);
+        assert_eq!(
+            Date::from_ymd(2000, 2, 29).unwrap().to_string(),
+            "2000-02-29"
+        );
+        assert_eq!(Date::from_ymd(2023, 13, 1), None);
+
+        for days in [-800_000, -1, 0, 59, 19_430, 2_932_896] {
+            let (year, month, day) = Date(days).ymd();
+            assert_eq!(Date::from_ymd(year, month, day), Some(Date(days)));
+        }
+
+        assert_eq!(Date::parse("2023-03-14"), Some(Date(19_430)));
+        for invalid in [
+            "2023-3-14",
+            "2023-03-14T",
+            "23-03-14",
+            "2023-02-30",
+            "+023-03-14",
+        ] {
+            assert_eq!(Date::parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_time() {
+        let time = Time::from_hms_milli(12, 30, 5, 250).unwrap();
+        assert_eq!(time.hms_milli(), (12, 30, 5, 250));
+        assert_eq!(time.to_string(), "12:30:05.250");
+        assert_eq!(Time(0).to_string(), "00:00:00");
+        assert_eq!(Time::from_hms_milli(24, 0, 0, 0), None);
+
+        assert_eq!(Time::parse("12:30:05.250"), Some(time));
+        assert_eq!(Time::parse("12:30:05.25"), Some(time));
+        assert_eq!(Time::parse("12:30"), Time::from_hms_milli(12, 30, 0, 0));
+        for invalid in [
+            "12",
+            "12:30.5",
+            "12:60",
+            "1:30",
+            "12:30:05.",
+            "12:30:05.2500",
+        ] {
+            assert_eq!(Time::parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let millis = 1_678_797_005_250;
+        let (date, time) = split_timestamp(millis);
+        assert_eq!(date, Date::from_ymd(2023, 3, 14).unwrap());
+        assert_eq!(time, Time::from_hms_milli(12, 30, 5, 250).unwrap());
+        assert_eq!(timestamp(date, time), millis);
+        assert_eq!(split_timestamp(-1), (Date(-1), Time(MILLIS_PER_DAY - 1)));
+
+        assert_eq!(parse_timestamp("2023-03-14T12:30:05.250Z"), Some(millis));
+        assert_eq!(parse_timestamp("2023-03-14 12:30:05.25"), Some(millis));
+        assert_eq!(parse_timestamp("2023-03-14"), Some(1_678_752_000_000));
+        assert_eq!(parse_timestamp("2023-03-14T"), None);
+        assert_eq!(parse_timestamp("2023-03-14T12:30:05+01:00"), None);
+    }
+}
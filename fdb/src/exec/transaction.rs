@@ -0,0 +1,218 @@
+//! Explicit transactions.
+//!
+//! A [`Transaction`] (see [`Db::begin`]) groups many statements, which are
+//! committed or rolled back together. It holds the statement latch (see
+//! [`Db::execute`]) until it ends, hence the other statements (including the
+//! read-only ones) wait for it, while its own statements see the changes of
+//! the previous ones.
+//!
+//! The pages it modifies are journaled (see [`txn`]), so that they can be
+//! restored, and they are only written to the disk once it ends (see
+//! [`Pager::flush`]). Rollbacks also discard the held back table changes (see
+//! [`notify`](crate::exec::notify)) and invalidate the cached catalog objects.
+//! The table statistics and activity counters, which are estimates, aren't
+//! rolled back: their in-memory deltas are only persisted by the statements
+//! outside of transactions.
+//!
+//! Each statement runs under an implicit savepoint: if it fails, its changes
+//! are rolled back, leaving the transaction open. Named savepoints (see
+//! [`Transaction::savepoint`]) may be rolled back to as well.
+//!
+//! Notice that commits aren't atomic on crashes, as there is no write-ahead
+//! log: a crash while the pages of a transaction are written may leave only
+//! some of them on the disk.
+//!
+//! [`txn`]: crate::io::txn
+//! [`Pager::flush`]: crate::io::pager::Pager::flush
+
+use std::future::Future;
+
+use tokio::sync::OwnedRwLockWriteGuard;
+use tracing::{debug, error, warn};
+
+use crate::{
+    error::{DbResult, Error},
+    exec::query::Query,
+    sql::planner::SqlOutput,
+    Db,
+};
+
+tokio::task_local! {
+    /// Set within the futures run by [`Transaction::run`].
+    static ACTIVE: ();
+}
+
+/// Checks whether the current task runs the statements of an explicit
+/// transaction.
+pub fn is_active() -> bool {
+    ACTIVE.try_with(|_| ()).is_ok()
+}
+
+/// An explicit transaction. See the [module docs](self).
+///
+/// Transactions must be ended by [`Transaction::commit`] or
+/// [`Transaction::rollback`]. Dropped transactions are rolled back in the
+/// background, and the other statements wait until it's done.
+pub struct Transaction<'db> {
+    db: &'db Db,
+    /// The statement latch, held until the transaction ends.
+    latch: Option<OwnedRwLockWriteGuard<()>>,
+    /// The named savepoints, from the oldest one.
+    savepoints: Vec<Savepoint>,
+}
+
+struct Savepoint {
+    name: String,
+    /// The journal depth. See [`Pager::savepoint`].
+    ///
+    /// [`Pager::savepoint`]: crate::io::pager::Pager::savepoint
+    depth: usize,
+    /// The number of held back table changes, as of the savepoint.
+    changes: usize,
+}
+
+impl<'db> Transaction<'db> {
+    /// Begins a transaction, given the statement latch.
+    pub(crate) async fn begin(
+        db: &'db Db,
+        latch: OwnedRwLockWriteGuard<()>,
+    ) -> DbResult<Transaction<'db>> {
+        db.pager().savepoint().await?;
+        debug!("began transaction");
+        Ok(Transaction {
+            db,
+            latch: Some(latch),
+            savepoints: Vec::new(),
+        })
+    }
+
+    /// Executes the given query within the transaction. See [`Db::execute`].
+    ///
+    /// If the query fails, its changes are rolled back, but the transaction is
+    /// left open.
+    pub async fn execute<Q, F, E>(&mut self, query: Q, f: F) -> DbResult<Result<(), E>>
+    where
+        Q: Query,
+        F: for<'a> FnMut(Q::Item<'a>) -> Result<(), E>,
+    {
+        let db = self.db;
+        self.run(db.execute(query, f)).await
+    }
+
+    /// Parses and executes the given SQL statement within the transaction. See
+    /// [`Db::execute_sql`].
+    ///
+    /// Transaction statements (e.g., `SAVEPOINT`) are only supported by
+    /// sessions (see [`Session`](crate::sql::session::Session)).
+    pub async fn execute_sql(&mut self, sql: &str) -> DbResult<SqlOutput> {
+        let db = self.db;
+        self.run(db.execute_sql(sql)).await
+    }
+
+    /// Runs the given future, whose statements are executed within the
+    /// transaction.
+    pub(crate) async fn run<F: Future>(&mut self, future: F) -> F::Output {
+        ACTIVE.scope((), future).await
+    }
+
+    /// Starts a savepoint with the given name. If many savepoints have the same
+    /// name, the latest one is referred to.
+    pub async fn savepoint(&mut self, name: &str) -> DbResult<()> {
+        let depth = self.db.pager().savepoint().await?;
+        debug!(name, depth, "started savepoint");
+        self.savepoints.push(Savepoint {
+            name: name.to_owned(),
+            depth,
+            changes: self.db.notifier().mark(),
+        });
+        Ok(())
+    }
+
+    /// Rolls back the changes since the given savepoint was started. The
+    /// savepoints started after it are removed, but it's kept.
+    pub async fn rollback_to(&mut self, name: &str) -> DbResult<()> {
+        let i = self.find(name)?;
+        self.savepoints.truncate(i + 1);
+        let savepoint = &self.savepoints[i];
+        let pager = self.db.pager();
+        pager.rollback_to(savepoint.depth).await?;
+        self.db.notifier().discard_since(savepoint.changes);
+        self.db.bump_catalog_version();
+        // The savepoint is started again.
+        let depth = pager.savepoint().await?;
+        debug_assert_eq!(depth, savepoint.depth);
+        debug!(name, "rolled back to savepoint");
+        Ok(())
+    }
+
+    /// Removes the given savepoint (and the ones started after it), keeping
+    /// their changes.
+    pub fn release(&mut self, name: &str) -> DbResult<()> {
+        let i = self.find(name)?;
+        self.db.pager().release(self.savepoints[i].depth);
+        self.savepoints.truncate(i);
+        debug!(name, "released savepoint");
+        Ok(())
+    }
+
+    /// Commits the transaction, publishing its table changes. Its pages are
+    /// written according to the flush policy, as those of a statement.
+    pub async fn commit(mut self) -> DbResult<()> {
+        let latch = self.latch.take();
+        let pager = self.db.pager();
+        pager.txns().end_journal();
+        self.db.notifier().publish();
+        pager.flush().await?;
+        drop(latch);
+        debug!("committed transaction");
+        // Outside of the latch, as in `Db::execute`.
+        pager.commit().await
+    }
+
+    /// Rolls back the transaction.
+    pub async fn rollback(mut self) -> DbResult<()> {
+        let latch = self.latch.take();
+        let pager = self.db.pager();
+        self.db.notifier().discard();
+        self.db.bump_catalog_version();
+        let result = pager.rollback_to(0).await;
+        pager.txns().end_journal();
+        result?;
+        pager.flush().await?;
+        drop(latch);
+        debug!("rolled back transaction");
+        pager.commit().await
+    }
+
+    /// Returns the index of the latest savepoint with the given name.
+    fn find(&self, name: &str) -> DbResult<usize> {
+        (self.savepoints.iter())
+            .rposition(|savepoint| savepoint.name == name)
+            .ok_or_else(|| Error::ExecError(format!("savepoint `{name}` does not exist")))
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        let Some(latch) = self.latch.take() else {
+            return;
+        };
+        warn!("transaction dropped without being ended; rolling it back");
+        self.db.notifier().discard();
+        self.db.bump_catalog_version();
+        let pager = std::sync::Arc::clone(self.db.pager());
+        let rollback = async move {
+            let result = pager.rollback_to(0).await;
+            pager.txns().end_journal();
+            if let Err(error) = result {
+                error!(?error, "transaction rollback failed");
+            }
+            // The latch is only released once the pages are restored.
+            drop(latch);
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => drop(handle.spawn(rollback)),
+            Err(_) => error!("transaction dropped outside of a runtime; it can't be rolled back"),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Comparison of records by sort keys.
+
+use std::cmp::Ordering;
+
+use crate::exec::{value::Value, values::Values};
+
+/// Where a [`SortKey`] places the null (i.e., absent) values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NullOrder {
+    First,
+    Last,
+}
+
+/// A sort key, i.e., a column, its order and the placement of its nulls.
+#[derive(Clone, Debug)]
+pub struct SortKey {
+    pub column: String,
+    pub descending: bool,
+    pub nulls: NullOrder,
+}
+
+impl SortKey {
+    /// Constructs an ascending sort key, which places the nulls last.
+    pub fn asc(column: impl Into<String>) -> SortKey {
+        SortKey {
+            column: column.into(),
+            descending: false,
+            nulls: NullOrder::Last,
+        }
+    }
+
+    /// Constructs a descending sort key, which places the nulls first.
+    pub fn desc(column: impl Into<String>) -> SortKey {
+        SortKey {
+            column: column.into(),
+            descending: true,
+            nulls: NullOrder::First,
+        }
+    }
+
+    /// Places the nulls first, regardless of the order.
+    pub fn nulls_first(mut self) -> SortKey {
+        self.nulls = NullOrder::First;
+        self
+    }
+
+    /// Places the nulls last, regardless of the order.
+    pub fn nulls_last(mut self) -> SortKey {
+        self.nulls = NullOrder::Last;
+        self
+    }
+
+    /// Compares the given records by this key only.
+    pub fn compare(&self, a: &Values, b: &Values) -> Ordering {
+        self.compare_values(a.get(&self.column), b.get(&self.column))
+    }
+
+    /// Compares the given values of this key's column, where absent values
+    /// are nulls.
+    pub fn compare_values(&self, a: Option<&Value>, b: Option<&Value>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                // Values of different types are considered equal.
+                let ord = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+                if self.descending {
+                    ord.reverse()
+                } else {
+                    ord
+                }
+            }
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => match self.nulls {
+                NullOrder::First => Ordering::Less,
+                NullOrder::Last => Ordering::Greater,
+            },
+            (Some(_), None) => match self.nulls {
+                NullOrder::First => Ordering::Greater,
+                NullOrder::Last => Ordering::Less,
+            },
+        }
+    }
+}
+
+/// Compares the given records by the given keys. The first key is the most
+/// significant one; the next ones only break its ties.
+pub fn compare_by(keys: &[SortKey], a: &Values, b: &Values) -> Ordering {
+    keys.iter()
+        .map(|key| key.compare(a, b))
+        .find(|ord| ord.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::exec::value::Value;
+
+    fn values(id: Option<i32>, text: &str) -> Values {
+        let mut values = Values::from(HashMap::from([(
+            "text".to_owned(),
+            Value::Text(text.into()),
+        )]));
+        if let Some(id) = id {
+            values.set("id".into(), Value::Int(id));
+        }
+        values
+    }
+
+    fn sorted(keys: &[SortKey]) -> Vec<(Option<i32>, String)> {
+        let mut rows = [
+            values(Some(2), "b"),
+            values(None, "a"),
+            values(Some(1), "b"),
+            values(Some(2), "a"),
+            values(None, "b"),
+        ];
+        rows.sort_by(|a, b| compare_by(keys, a, b));
+        rows.iter()
+            .map(|row| {
+                let id = row.get("id").map(|id| *id.try_cast_int_ref().unwrap());
+                let text = row.get("text").unwrap().try_cast_text_ref().unwrap();
+                (id, text.to_owned())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_by() {
+        let rows = sorted(&[SortKey::asc("id"), SortKey::desc("text")]);
+        assert_eq!(
+            rows,
+            [
+                (Some(1), "b".into()),
+                (Some(2), "b".into()),
+                (Some(2), "a".into()),
+                (None, "b".into()),
+                (None, "a".into()),
+            ]
+        );
+
+        let rows = sorted(&[SortKey::desc("id"), SortKey::asc("text")]);
+        assert_eq!(
+            rows,
+            [
+                (None, "a".into()),
+                (None, "b".into()),
+                (Some(2), "a".into()),
+                (Some(2), "b".into()),
+                (Some(1), "b".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_null_order() {
+        let rows = sorted(&[SortKey::asc("id").nulls_first(), SortKey::asc("text")]);
+        assert_eq!(rows[..2], [(None, "a".into()), (None, "b".into())]);
+
+        let rows = sorted(&[SortKey::desc("id").nulls_last(), SortKey::asc("text")]);
+        assert_eq!(rows[0], (Some(2), "a".into()));
+        assert_eq!(rows[3..], [(None, "a".into()), (None, "b".into())]);
+    }
+}
@@ -1,13 +1,26 @@
-use std::{fmt, ops::Add};
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Add,
+};
 
 use crate::{
-    catalog::ty::{PrimitiveTypeId, TypeId},
+    catalog::ty::{CompositeType, PrimitiveTypeId, TypeId},
     error::{DbResult, Error},
-    util::io::{Deserialize, DeserializeCtx, Serialize, Size, VarBytes, VarString},
+    exec::{
+        format::{decode_base64, BlobFormat, ValueFormat},
+        time::{self, Date, Time},
+        values::{SchematizedValues, Values},
+    },
+    util::{
+        io::{Deserialize, DeserializeCtx, Serialize, SerializeCtx, Size, VarBytes, VarString},
+        packing,
+    },
 };
 
 /// A database value.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum Value {
     Bool(bool),
     Byte(u8),
@@ -17,7 +30,172 @@ pub enum Value {
     Timestamp(i64),
     Text(String),
     Blob(Vec<u8>),
+    Float(Float),
+    Decimal(Decimal),
+    Date(Date),
+    Time(Time),
     Array(PrimitiveTypeId, Vec<Value>), // TODO: Extract this as a type.
+    /// A multi-dimensional array (see [`TypeId::NestedArray`]), whose elements
+    /// are arrays of one less dimension.
+    NestedArray(PrimitiveTypeId, u8, Vec<Value>),
+    /// A composite value (see [`TypeId::Composite`]), whose field values are
+    /// schematized over the type's fields.
+    Composite(CompositeType, SchematizedValues),
+}
+
+/// A 64-bit floating point value.
+///
+/// Unlike [`f64`], floats are totally ordered (see [`f64::total_cmp`]), so
+/// that they may be sorted and used as index keys. Hence, `-0.0` is less than
+/// `0.0` and NaNs are greater (or, if negative, less) than all other values.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Float(pub f64);
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Float {}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl fmt::Display for Float {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// A fixed-point decimal value, i.e., `unscaled / 10^scale`.
+///
+/// The scale is part of the value type (see [`PrimitiveTypeId::Decimal`]), so
+/// only the unscaled integer is serialized. Decimals with different scales are
+/// different values (e.g., `1.0` and `1.00`), but they may be compared through
+/// [`Decimal::cmp_numeric`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    pub unscaled: i64,
+    pub scale: u8,
+}
+
+impl Decimal {
+    /// Parses a decimal number, e.g., `-12.5`, into a decimal of the given
+    /// scale. Returns `None` if the text is malformed, if it has more
+    /// fractional digits than the scale or if it's out of range.
+    pub fn parse(text: &str, scale: u8) -> Option<Decimal> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        let (int, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if int.is_empty()
+            || fraction.len() > scale as usize
+            || !(int.bytes().chain(fraction.bytes())).all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let mut unscaled: i128 = 0;
+        let padding = std::iter::repeat_n(b'0', scale as usize - fraction.len());
+        for digit in int.bytes().chain(fraction.bytes()).chain(padding) {
+            unscaled = unscaled.checked_mul(10)? + (digit - b'0') as i128;
+        }
+        let unscaled = if negative { -unscaled } else { unscaled };
+        Some(Decimal {
+            unscaled: unscaled.try_into().ok()?,
+            scale,
+        })
+    }
+
+    /// Converts the decimal into one of the given scale. Returns `None` if
+    /// digits would be lost or if it's out of range.
+    pub fn rescale(self, scale: u8) -> Option<Decimal> {
+        let unscaled = if scale >= self.scale {
+            let factor = 10_i64.checked_pow((scale - self.scale) as u32)?;
+            self.unscaled.checked_mul(factor)?
+        } else {
+            let factor = 10_i64.checked_pow((self.scale - scale) as u32)?;
+            if self.unscaled % factor != 0 {
+                return None;
+            }
+            self.unscaled / factor
+        };
+        Some(Decimal { unscaled, scale })
+    }
+
+    /// Compares the numeric values of two decimals, regardless of their scales.
+    pub fn cmp_numeric(&self, other: &Decimal) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        let widen = |d: &Decimal| d.unscaled as i128 * 10_i128.pow((scale - d.scale) as u32);
+        widen(self).cmp(&widen(other))
+    }
+
+    /// Returns the closest floating point number.
+    pub fn to_f64(self) -> f64 {
+        self.unscaled as f64 / 10_f64.powi(self.scale as i32)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.unscaled < 0 { "-" } else { "" };
+        let abs = self.unscaled.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{sign}{abs}");
+        }
+        let factor = 10_u64.pow(self.scale as u32);
+        let scale = self.scale as usize;
+        write!(f, "{sign}{}.{:0scale$}", abs / factor, abs % factor)
+    }
+}
+
+impl PartialOrd for Value {
+    /// Values are only comparable with other values of the same type.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+            (Value::Byte(a), Value::Byte(b)) => a.partial_cmp(b),
+            (Value::ShortInt(a), Value::ShortInt(b)) => a.partial_cmp(b),
+            (Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+            (Value::BigInt(a), Value::BigInt(b)) => a.partial_cmp(b),
+            (Value::Timestamp(a), Value::Timestamp(b)) => a.partial_cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.partial_cmp(b),
+            (Value::Blob(a), Value::Blob(b)) => a.partial_cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::Decimal(a), Value::Decimal(b)) if a.scale == b.scale => {
+                a.unscaled.partial_cmp(&b.unscaled)
+            }
+            (Value::Date(a), Value::Date(b)) => a.partial_cmp(b),
+            (Value::Time(a), Value::Time(b)) => a.partial_cmp(b),
+            (Value::Array(a_ty, a), Value::Array(b_ty, b)) if a_ty == b_ty => a.partial_cmp(b),
+            (Value::NestedArray(a_ty, a_dims, a), Value::NestedArray(b_ty, b_dims, b))
+                if (a_ty, a_dims) == (b_ty, b_dims) =>
+            {
+                a.partial_cmp(b)
+            }
+            // Fields are compared in order.
+            (Value::Composite(a_ty, a), Value::Composite(b_ty, b)) if a_ty == b_ty => {
+                a.as_slice().partial_cmp(b.as_slice())
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Size for Value {
@@ -29,10 +207,19 @@ impl Size for Value {
             Value::Int(_) => 4,
             Value::BigInt(_) => 8,
             Value::Timestamp(_) => 8,
-            // 2-byte length and the string bytes (encoded in UTF-8).
-            Value::Text(str) => 2 + u32::try_from(str.len()).unwrap(),
-            // 2-byte length and the bytes.
-            Value::Blob(bytes) => 2 + u32::try_from(bytes.len()).unwrap(),
+            Value::Float(_) => 8,
+            // The unscaled integer.
+            Value::Decimal(_) => 8,
+            Value::Date(_) => 4,
+            Value::Time(_) => 4,
+            // Varint length and the string bytes (encoded in UTF-8).
+            Value::Text(str) => VarString::from(str.as_str()).size(),
+            // Varint length and the bytes.
+            Value::Blob(bytes) => VarBytes::from(bytes.as_slice()).size(),
+            // 2-byte length and the packed elements.
+            Value::Array(element_type, elements) if is_packed(*element_type) => {
+                2 + packing::packed_size(&packed_elements(elements))
+            }
             // 2-byte length and the elements.
             Value::Array(element_type, elements) => elements
                 .iter()
@@ -42,6 +229,10 @@ impl Size for Value {
                 })
                 .sum::<u32>()
                 .add(2), // length
+            // 2-byte length and the element arrays.
+            Value::NestedArray(_, _, elements) => 2 + elements.iter().map(Value::size).sum::<u32>(),
+            // The fields, as a schematized row (whose values are delimited).
+            Value::Composite(_, fields) => fields.size(),
         }
     }
 }
@@ -49,28 +240,43 @@ impl Size for Value {
 impl Serialize for Value {
     fn serialize(&self, buf: &mut buff::Buff<'_>) -> DbResult<()> {
         match self {
-            Value::Bool(inner) => buf.write(*inner),
-            Value::Byte(inner) => buf.write(*inner),
-            Value::ShortInt(inner) => buf.write(*inner),
-            Value::Int(inner) => buf.write(*inner),
-            Value::BigInt(inner) => buf.write(*inner),
-            Value::Timestamp(inner) => buf.write(*inner),
+            Value::Bool(inner) => buf.try_write(*inner)?,
+            Value::Byte(inner) => buf.try_write(*inner)?,
+            Value::ShortInt(inner) => buf.try_write(*inner)?,
+            Value::Int(inner) => buf.try_write(*inner)?,
+            Value::BigInt(inner) => buf.try_write(*inner)?,
+            Value::Timestamp(inner) => buf.try_write(*inner)?,
             Value::Text(inner) => VarString::from(inner.as_str()).serialize(buf)?,
             Value::Blob(inner) => VarBytes::from(inner.as_slice()).serialize(buf)?,
-            Value::Array(_element_type, elements) => {
+            Value::Float(inner) => buf.try_write(inner.0)?,
+            Value::Decimal(inner) => buf.try_write(inner.unscaled)?,
+            Value::Date(inner) => buf.try_write(inner.0)?,
+            Value::Time(inner) => buf.try_write(inner.0)?,
+            Value::Array(element_type, elements) => {
                 let len = elements.len() as u16;
-                buf.write(len);
+                buf.try_write(len)?;
+                if is_packed(*element_type) {
+                    packing::pack(&packed_elements(elements), buf)?;
+                    return Ok(());
+                }
+                for element in elements {
+                    element.serialize(buf)?;
+                }
+            }
+            Value::NestedArray(_, _, elements) => {
+                buf.try_write(elements.len() as u16)?;
                 for element in elements {
                     element.serialize(buf)?;
                 }
             }
+            Value::Composite(ty, fields) => fields.serialize(buf, ty.fields())?,
         }
         Ok(())
     }
 }
 
 impl DeserializeCtx<'_, TypeId> for Value {
-    fn deserialize(buf: &mut buff::Buff, type_id: &TypeId) -> DbResult<Self> {
+    fn deserialize(buf: &mut buff::BuffRead, type_id: &TypeId) -> DbResult<Self> {
         let value = match type_id {
             TypeId::Primitive(primitive_type) => match primitive_type {
                 PrimitiveTypeId::Bool => Value::Bool(buf.read()),
@@ -81,20 +287,77 @@ impl DeserializeCtx<'_, TypeId> for Value {
                 PrimitiveTypeId::Timestamp => Value::Timestamp(buf.read()),
                 PrimitiveTypeId::Text => Value::Text(VarString::deserialize(buf)?.into()),
                 PrimitiveTypeId::Blob => Value::Blob(VarBytes::deserialize(buf)?.into()),
+                PrimitiveTypeId::Float => Value::Float(Float(buf.read())),
+                PrimitiveTypeId::Decimal(scale) => Value::Decimal(Decimal {
+                    unscaled: buf.read(),
+                    scale: *scale,
+                }),
+                PrimitiveTypeId::Date => Value::Date(Date(buf.read())),
+                PrimitiveTypeId::Time => Value::Time(Time(buf.read())),
             },
             TypeId::Array(element_type) => {
                 let len: u16 = buf.read();
+                if is_packed(*element_type) {
+                    return unpack_elements(buf, *element_type, len as usize);
+                }
                 let mut elements = Vec::with_capacity(len as usize);
                 for _ in 0..len {
                     elements.push(Value::deserialize(buf, &TypeId::Primitive(*element_type))?);
                 }
                 Value::Array(*element_type, elements)
             }
+            TypeId::NestedArray(..) => {
+                let len: u16 = buf.read();
+                let element_type = type_id.element_type().expect("array type");
+                let elements = (0..len)
+                    .map(|_| Value::deserialize(buf, &element_type))
+                    .collect::<DbResult<_>>()?;
+                Value::array(*type_id, elements)
+            }
+            TypeId::Composite(ty) => {
+                Value::Composite(*ty, SchematizedValues::deserialize(buf, ty.fields())?)
+            }
         };
         Ok(value)
     }
 }
 
+/// Checks whether arrays of the given element type are delta and bit packed
+/// (see [`packing`]), instead of serialized element by element.
+fn is_packed(element_type: PrimitiveTypeId) -> bool {
+    matches!(element_type, PrimitiveTypeId::Int | PrimitiveTypeId::BigInt)
+}
+
+fn packed_elements(elements: &[Value]) -> Vec<i64> {
+    elements
+        .iter()
+        .map(|value| match value {
+            Value::Int(inner) => *inner as i64,
+            Value::BigInt(inner) => *inner,
+            _ => unreachable!("array elements must be integers"),
+        })
+        .collect()
+}
+
+fn unpack_elements(
+    buf: &mut buff::BuffRead,
+    element_type: PrimitiveTypeId,
+    len: usize,
+) -> DbResult<Value> {
+    let mut ints = Vec::new();
+    packing::unpack(buf, len, &mut ints)?;
+    let elements = ints
+        .into_iter()
+        .map(|int| match element_type {
+            PrimitiveTypeId::Int => i32::try_from(int)
+                .map(Value::Int)
+                .map_err(|_| Error::CorruptedPacking),
+            _ => Ok(Value::BigInt(int)),
+        })
+        .collect::<DbResult<_>>()?;
+    Ok(Value::Array(element_type, elements))
+}
+
 impl Value {
     /// Returns the default value for the given [`TypeId`].
     pub fn default_for_type(ty: TypeId) -> Self {
@@ -108,8 +371,30 @@ impl Value {
                 PrimitiveTypeId::Timestamp => Value::Timestamp(0),
                 PrimitiveTypeId::Text => Value::Text(String::with_capacity(0)),
                 PrimitiveTypeId::Blob => Value::Blob(Vec::with_capacity(0)),
+                PrimitiveTypeId::Float => Value::Float(Float(0.0)),
+                PrimitiveTypeId::Decimal(scale) => Value::Decimal(Decimal { unscaled: 0, scale }),
+                PrimitiveTypeId::Date => Value::Date(Date::default()),
+                PrimitiveTypeId::Time => Value::Time(Time::default()),
             },
-            TypeId::Array(element_type) => Value::Array(element_type, Vec::with_capacity(0)),
+            TypeId::Composite(ty) => {
+                let fields = (Values::new().try_into_schematized(ty.fields()))
+                    .expect("default values conform to the schema");
+                Value::Composite(ty, fields)
+            }
+            ty => Value::array(ty, Vec::with_capacity(0)),
+        }
+    }
+
+    /// Returns the array of the given array type with the given elements.
+    ///
+    /// Panics if the type isn't an array type.
+    pub fn array(ty: TypeId, elements: Vec<Value>) -> Value {
+        match ty {
+            TypeId::Array(element_type) => Value::Array(element_type, elements),
+            TypeId::NestedArray(element_type, dims) => {
+                Value::NestedArray(element_type, dims, elements)
+            }
+            ty => panic!("`{}` is not an array type", ty.name()),
         }
     }
 
@@ -124,7 +409,13 @@ impl Value {
             Value::Timestamp(_) => TypeId::Primitive(PrimitiveTypeId::Timestamp),
             Value::Text(_) => TypeId::Primitive(PrimitiveTypeId::Text),
             Value::Blob(_) => TypeId::Primitive(PrimitiveTypeId::Blob),
+            Value::Float(_) => TypeId::Primitive(PrimitiveTypeId::Float),
+            Value::Decimal(decimal) => TypeId::Primitive(PrimitiveTypeId::Decimal(decimal.scale)),
+            Value::Date(_) => TypeId::Primitive(PrimitiveTypeId::Date),
+            Value::Time(_) => TypeId::Primitive(PrimitiveTypeId::Time),
             Value::Array(element_type, _) => TypeId::Array(*element_type),
+            Value::NestedArray(element_type, dims, _) => TypeId::NestedArray(*element_type, *dims),
+            Value::Composite(ty, _) => TypeId::Composite(*ty),
         }
     }
 
@@ -137,7 +428,124 @@ impl Value {
         (try_cast_timestamp_ref, Timestamp, i64),
         (try_cast_text_ref, Text, str),
         (try_cast_blob_ref, Blob, [u8]),
+        (try_cast_float_ref, Float, Float),
+        (try_cast_decimal_ref, Decimal, Decimal),
+        (try_cast_date_ref, Date, Date),
+        (try_cast_time_ref, Time, Time),
     );
+
+    /// Converts the value into JSON.
+    ///
+    /// Booleans and texts are mapped to their JSON counterparts, integers (and
+    /// timestamps, as milliseconds since the Unix epoch) and floats to numbers,
+    /// decimals to strings (so that they are exact), dates and times to their
+    /// ISO-8601 strings (see [`time`]), blobs to (padded) standard base64
+    /// strings, arrays to JSON arrays and composite values to JSON objects
+    /// (keyed by field name). Non-finite floats are mapped to
+    /// `null`. This is the inverse of [`Value::from_json`], which also accepts
+    /// timestamps as ISO-8601 strings.
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value as Json;
+
+        match self {
+            Value::Bool(bool) => Json::Bool(*bool),
+            Value::Byte(int) => Json::from(*int),
+            Value::ShortInt(int) => Json::from(*int),
+            Value::Int(int) => Json::from(*int),
+            Value::BigInt(int) | Value::Timestamp(int) => Json::from(*int),
+            Value::Text(text) => Json::String(text.clone()),
+            Value::Float(float) => Json::from(float.0),
+            Value::Decimal(decimal) => Json::String(decimal.to_string()),
+            Value::Date(date) => Json::String(date.to_string()),
+            Value::Time(time) => Json::String(time.to_string()),
+            Value::Blob(_) => {
+                let format = ValueFormat {
+                    blob: BlobFormat::Base64,
+                    ..ValueFormat::default()
+                };
+                Json::String(format.format(self))
+            }
+            Value::Array(_, elements) | Value::NestedArray(_, _, elements) => {
+                Json::Array(elements.iter().map(Value::to_json).collect())
+            }
+            Value::Composite(ty, fields) => fields.to_values(ty.fields()).to_json(),
+        }
+    }
+
+    /// Converts the given JSON into a value of the given type, as represented
+    /// by [`Value::to_json`]. Returns `None` if the JSON doesn't represent such
+    /// a value (e.g., if an integer is out of range).
+    pub fn from_json(json: &serde_json::Value, ty: TypeId) -> Option<Value> {
+        use serde_json::Value as Json;
+
+        let primitive = match ty {
+            TypeId::Primitive(primitive) => primitive,
+            // Missing fields take their default values.
+            TypeId::Composite(ty) => {
+                let fields = Values::from_json(json, ty.fields()).ok()?;
+                return Some(Value::Composite(
+                    ty,
+                    fields.try_into_schematized(ty.fields()).ok()?,
+                ));
+            }
+            ty => {
+                let element_type = ty.element_type().expect("array type");
+                let elements = (json.as_array()?.iter())
+                    .map(|element| Value::from_json(element, element_type))
+                    .collect::<Option<_>>()?;
+                return Some(Value::array(ty, elements));
+            }
+        };
+        Some(match (json, primitive) {
+            (Json::Bool(bool), PrimitiveTypeId::Bool) => Value::Bool(*bool),
+            (Json::Number(number), PrimitiveTypeId::Byte) => {
+                Value::Byte(number.as_u64()?.try_into().ok()?)
+            }
+            (Json::Number(number), PrimitiveTypeId::ShortInt) => {
+                Value::ShortInt(number.as_i64()?.try_into().ok()?)
+            }
+            (Json::Number(number), PrimitiveTypeId::Int) => {
+                Value::Int(number.as_i64()?.try_into().ok()?)
+            }
+            (Json::Number(number), PrimitiveTypeId::BigInt) => Value::BigInt(number.as_i64()?),
+            (Json::Number(number), PrimitiveTypeId::Timestamp) => {
+                Value::Timestamp(number.as_i64()?)
+            }
+            (Json::String(text), PrimitiveTypeId::Timestamp) => {
+                Value::Timestamp(time::parse_timestamp(text)?)
+            }
+            (Json::String(text), PrimitiveTypeId::Text) => Value::Text(text.clone()),
+            (Json::String(base64), PrimitiveTypeId::Blob) => Value::Blob(decode_base64(base64)?),
+            (Json::Number(number), PrimitiveTypeId::Float) => Value::Float(Float(number.as_f64()?)),
+            (Json::String(text), PrimitiveTypeId::Decimal(scale)) => {
+                Value::Decimal(Decimal::parse(text, scale)?)
+            }
+            (Json::Number(number), PrimitiveTypeId::Decimal(scale)) => {
+                Value::Decimal(Decimal::parse(&number.to_string(), scale)?)
+            }
+            (Json::String(text), PrimitiveTypeId::Date) => Value::Date(Date::parse(text)?),
+            (Json::String(text), PrimitiveTypeId::Time) => Value::Time(Time::parse(text)?),
+            _ => return None,
+        })
+    }
+
+    /// Returns the value of the given field of a composite value, or `None` if
+    /// the value isn't composite or if there's no such field.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Composite(ty, fields) => fields.get(ty.fields(), name),
+            _ => None,
+        }
+    }
+
+    /// Tries to cast the [`Value`] to its underlying array elements.
+    pub fn try_cast_array_ref(&self) -> DbResult<&[Value]> {
+        if let Value::Array(_, elements) | Value::NestedArray(_, _, elements) = &self {
+            Ok(elements)
+        } else {
+            Err(Error::ExecError("invalid type cast".into()))
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -151,9 +559,19 @@ impl fmt::Display for Value {
             Value::Timestamp(inner) => inner.fmt(f),
             Value::Text(inner) => inner.fmt(f),
             Value::Blob(inner) => write!(f, "<bytes ({})>", inner.len()),
+            Value::Float(inner) => fmt::Display::fmt(inner, f),
+            Value::Decimal(inner) => fmt::Display::fmt(inner, f),
+            Value::Date(inner) => fmt::Display::fmt(inner, f),
+            Value::Time(inner) => fmt::Display::fmt(inner, f),
             Value::Array(element_type, elements) => {
                 write!(f, "<array of {} ({})>", element_type.name(), elements.len())
             }
+            Value::NestedArray(element_type, dims, elements) => {
+                let brackets = "[]".repeat(*dims as usize - 1);
+                let len = elements.len();
+                write!(f, "<array of {}{brackets} ({len})>", element_type.name())
+            }
+            Value::Composite(_, fields) => write!(f, "<struct ({} fields)>", fields.len()),
         }
     }
 }
@@ -169,7 +587,16 @@ impl fmt::Debug for Value {
             Value::Timestamp(inner) => inner.fmt(f),
             Value::Text(inner) => inner.fmt(f),
             Value::Blob(_) => f.write_str("<blob>"),
+            Value::Float(inner) => fmt::Debug::fmt(&inner.0, f),
+            Value::Decimal(inner) => fmt::Display::fmt(inner, f),
+            Value::Date(inner) => fmt::Display::fmt(inner, f),
+            Value::Time(inner) => fmt::Display::fmt(inner, f),
             Value::Array(element_type, _) => write!(f, "<array of {}>", element_type.name()),
+            Value::NestedArray(element_type, dims, _) => {
+                let brackets = "[]".repeat(*dims as usize - 1);
+                write!(f, "<array of {}{brackets}>", element_type.name())
+            }
+            Value::Composite(..) => f.write_str("<struct>"),
         }
     }
 }
@@ -210,8 +637,9 @@ mod tests {
                 value.serialize(buf).expect("serialize");
                 assert_eq!(buf.get(), EXPECTED, "serialization didn't match");
 
-                buf.seek(0);
-                let deserialized_value = Value::deserialize(buf, &ty).expect("deserialize");
+                let deserialized_value =
+                    Value::deserialize(&mut buff::BuffRead::new(buf.get()), &ty)
+                        .expect("deserialize");
                 assert_eq!(deserialized_value, value, "deserialization didn't match");
 
                 assert_eq!(value.size(), EXPECTED_SIZE as u32);
@@ -237,14 +665,29 @@ mod tests {
         Value::Timestamp(0x_1234_5678_1234_5678)
     );
 
-    t!(text, b"\x00\x05ol\xC3\xA1!", Value::Text("olá!".into()));
+    t!(text, b"\x05ol\xC3\xA1!", Value::Text("olá!".into()));
+
+    t!(blob, b"\x09ola-mundo", Value::Blob(b"ola-mundo".to_vec()));
+
+    t!(
+        float,
+        b"\x3F\xF8\x00\x00\x00\x00\x00\x00",
+        Value::Float(Float(1.5))
+    );
 
     t!(
-        blob,
-        b"\x00\x09ola-mundo",
-        Value::Blob(b"ola-mundo".to_vec())
+        decimal,
+        b"\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x85",
+        Value::Decimal(Decimal {
+            unscaled: -123,
+            scale: 2
+        })
     );
 
+    t!(date, b"\x00\x00\x4B\xE6", Value::Date(Date(19_430)));
+
+    t!(time, b"\x02\xAE\xD1\xD2", Value::Time(Time(45_011_410)));
+
     t!(
         array,
         b"\x00\x03\xAB\xCD\xEF",
@@ -253,4 +696,132 @@ mod tests {
             vec![Value::Byte(0xAB), Value::Byte(0xCD), Value::Byte(0xEF)]
         )
     );
+
+    // Base 10, then a 3-bit block with the zigzag encoded deltas 1 and 2.
+    t!(
+        int_array,
+        b"\x00\x03\x00\x00\x00\x00\x00\x00\x00\x0A\x03\x22",
+        Value::Array(
+            PrimitiveTypeId::Int,
+            vec![Value::Int(10), Value::Int(11), Value::Int(13)]
+        )
+    );
+
+    // Each element array is serialized as a (packed) array.
+    t!(
+        nested_array,
+        b"\x00\x02\x00\x01\x00\x00\x00\x00\x00\x00\x00\x07\x00\x00",
+        Value::NestedArray(
+            PrimitiveTypeId::Int,
+            2,
+            vec![
+                Value::Array(PrimitiveTypeId::Int, vec![Value::Int(7)]),
+                Value::Array(PrimitiveTypeId::Int, vec![]),
+            ]
+        )
+    );
+
+    t!(
+        bigint_array_empty,
+        b"\x00\x00",
+        Value::Array(PrimitiveTypeId::BigInt, vec![])
+    );
+
+    #[test]
+    fn test_partial_cmp() {
+        assert!(Value::Int(1) < Value::Int(2));
+        assert!(Value::Text("a".into()) < Value::Text("b".into()));
+        assert!(Value::Bool(false) < Value::Bool(true));
+        assert_eq!(Value::Int(1).partial_cmp(&Value::BigInt(1)), None);
+        assert!(
+            Value::Array(PrimitiveTypeId::Byte, vec![Value::Byte(1)])
+                < Value::Array(PrimitiveTypeId::Byte, vec![Value::Byte(1), Value::Byte(0)])
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        for (value, json) in [
+            (Value::Bool(true), "true"),
+            (Value::Byte(255), "255"),
+            (Value::Int(-7), "-7"),
+            (Value::Timestamp(1_678_795_200_000), "1678795200000"),
+            (Value::Text("a \"b\"".into()), r#""a \"b\"""#),
+            (Value::Blob(b"\xCA\xFE".to_vec()), r#""yv4=""#),
+            (Value::Date(Date(19_430)), r#""2023-03-14""#),
+            (Value::Time(Time(45_000_250)), r#""12:30:00.250""#),
+            (
+                Value::Array(PrimitiveTypeId::ShortInt, vec![Value::ShortInt(1)]),
+                "[1]",
+            ),
+            (
+                Value::NestedArray(
+                    PrimitiveTypeId::Text,
+                    2,
+                    vec![
+                        Value::Array(PrimitiveTypeId::Text, vec![Value::Text("a".into())]),
+                        Value::Array(PrimitiveTypeId::Text, vec![]),
+                    ],
+                ),
+                r#"[["a"],[]]"#,
+            ),
+        ] {
+            assert_eq!(value.to_json().to_string(), json);
+            let parsed = serde_json::from_str(json).unwrap();
+            assert_eq!(Value::from_json(&parsed, value.type_id()), Some(value));
+        }
+
+        let json = |text| serde_json::from_str::<serde_json::Value>(text).unwrap();
+        let int = TypeId::Primitive(PrimitiveTypeId::Int);
+        assert_eq!(Value::from_json(&json("4294967296"), int), None);
+        assert_eq!(Value::from_json(&json("1.5"), int), None);
+        assert_eq!(Value::from_json(&json(r#""1""#), int), None);
+        let blob = TypeId::Primitive(PrimitiveTypeId::Blob);
+        assert_eq!(Value::from_json(&json(r#""not base64""#), blob), None);
+        let timestamp = TypeId::Primitive(PrimitiveTypeId::Timestamp);
+        assert_eq!(
+            Value::from_json(&json(r#""2023-03-14T12:00:00Z""#), timestamp),
+            Some(Value::Timestamp(1_678_795_200_000))
+        );
+    }
+
+    #[test]
+    fn test_decimal() {
+        let decimal = |unscaled, scale| Decimal { unscaled, scale };
+        assert_eq!(Decimal::parse("12.5", 2), Some(decimal(1250, 2)));
+        assert_eq!(Decimal::parse("-0.05", 2), Some(decimal(-5, 2)));
+        assert_eq!(Decimal::parse("7", 0), Some(decimal(7, 0)));
+        assert_eq!(Decimal::parse("1.234", 2), None);
+        assert_eq!(Decimal::parse(".5", 1), None);
+        assert_eq!(Decimal::parse("1e3", 0), None);
+        assert_eq!(Decimal::parse("92233720368547758.08", 2), None);
+
+        assert_eq!(decimal(1250, 2).to_string(), "12.50");
+        assert_eq!(decimal(-5, 2).to_string(), "-0.05");
+        assert_eq!(decimal(i64::MIN, 0).to_string(), i64::MIN.to_string());
+
+        assert_eq!(decimal(125, 1).rescale(3), Some(decimal(12500, 3)));
+        assert_eq!(decimal(12500, 3).rescale(1), Some(decimal(125, 1)));
+        assert_eq!(decimal(12501, 3).rescale(1), None);
+        assert_eq!(decimal(i64::MAX, 0).rescale(1), None);
+
+        assert_eq!(
+            decimal(10, 1).cmp_numeric(&decimal(100, 2)),
+            Ordering::Equal
+        );
+        assert_eq!(decimal(-1, 0).cmp_numeric(&decimal(-5, 1)), Ordering::Less);
+        assert_eq!(
+            Value::Decimal(decimal(10, 1)).partial_cmp(&Value::Decimal(decimal(100, 2))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_float_order() {
+        let mut floats = [2.5, f64::NAN, -1.0, 0.0, -0.0, f64::NEG_INFINITY].map(Float);
+        floats.sort();
+        let floats = floats.map(|float| float.0.to_string());
+        assert_eq!(floats, ["-inf", "-1", "-0", "0", "2.5", "NaN"]);
+        assert_eq!(Float(f64::NAN), Float(f64::NAN));
+    }
 }
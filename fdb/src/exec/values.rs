@@ -1,4 +1,4 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
     catalog::table_schema::TableSchema,
@@ -23,13 +23,13 @@ impl Values {
     }
 
     /// Same as [`Self::try_as_schematized`], but taking ownership.
-    pub fn try_into_schematized(
-        mut self,
-        schema: &TableSchema,
-    ) -> DbResult<SchematizedValues<'static>> {
-        let size = SchematizedValues::validate_and_apply_defaults(&mut self, schema)?;
+    pub fn try_into_schematized(mut self, schema: &TableSchema) -> DbResult<SchematizedValues> {
+        SchematizedValues::validate_and_apply_defaults(&mut self, schema)?;
+        let values = (schema.columns.iter())
+            .map(|column| self.inner.remove(&column.name).expect("applied defaults"))
+            .collect();
         // SAFETY: Checked for schema-correctness above.
-        Ok(unsafe { SchematizedValues::try_new_unchecked(Cow::Owned(self), Some(size)) })
+        Ok(unsafe { SchematizedValues::new_unchecked(values) })
     }
 
     /// Checks if the values already defined in the map met the given schema's
@@ -38,15 +38,16 @@ impl Values {
     /// It also completes the values map assigning default values for each
     /// unspecified value in the context of the provided schema.
     ///
-    /// Returns the schematized values map if all column-typing constraint are
-    /// met in the context of the provided [`TableSchema`].
-    pub fn try_as_schematized<'a>(
-        &'a mut self,
-        schema: &TableSchema,
-    ) -> DbResult<SchematizedValues<'a>> {
-        let size = SchematizedValues::validate_and_apply_defaults(self, schema)?;
+    /// Returns the schematized values (a copy of the map's values, in the
+    /// schema order) if all column-typing constraint are met in the context of
+    /// the provided [`TableSchema`].
+    pub fn try_as_schematized(&mut self, schema: &TableSchema) -> DbResult<SchematizedValues> {
+        SchematizedValues::validate_and_apply_defaults(self, schema)?;
+        let values = (schema.columns.iter())
+            .map(|column| self.inner[&column.name].clone())
+            .collect();
         // SAFETY: Checked for schema-correctness above.
-        Ok(unsafe { SchematizedValues::try_new_unchecked(Cow::Borrowed(self), Some(size)) })
+        Ok(unsafe { SchematizedValues::new_unchecked(values) })
     }
 
     /// Returns a reference to the underlying value.
@@ -58,6 +59,68 @@ impl Values {
     pub fn set(&mut self, name: String, value: Value) {
         self.inner.insert(name, value);
     }
+
+    /// Removes a value, returning it.
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        self.inner.remove(name)
+    }
+
+    /// Converts the values into a JSON object, keyed by column name. See
+    /// [`Value::to_json`].
+    pub fn to_json(&self) -> serde_json::Value {
+        let object = (self.inner.iter())
+            .map(|(name, value)| (name.clone(), value.to_json()))
+            .collect();
+        serde_json::Value::Object(object)
+    }
+
+    /// Converts the given JSON object into values of the given schema's
+    /// columns, as represented by [`Values::to_json`]. Missing or `null` fields
+    /// are left unspecified (i.e., they take the column's default value once
+    /// schematized), while unknown ones are an error.
+    pub fn from_json(json: &serde_json::Value, schema: &TableSchema) -> DbResult<Values> {
+        let serde_json::Value::Object(object) = json else {
+            return Err(Error::ExecError("expected a JSON object".into()));
+        };
+        let mut values = Values::new();
+        for (name, json) in object {
+            let Some(column) = schema.columns.iter().find(|column| column.name == *name) else {
+                return Err(Error::ExecError(format!("column `{name}` does not exist")));
+            };
+            if json.is_null() {
+                continue;
+            }
+            let value = Value::from_json(json, column.ty).ok_or_else(|| {
+                Error::Cast(format!(
+                    "can't convert {json} to `{}` for column `{name}`",
+                    column.ty.name()
+                ))
+            })?;
+            values.set(name.clone(), value);
+        }
+        Ok(values)
+    }
+
+    /// Qualifies all column names with the given prefix (usually, the table
+    /// name). See [`qualify`].
+    pub fn into_qualified(self, prefix: &str) -> Values {
+        let inner = (self.inner.into_iter())
+            .map(|(name, value)| (qualify(prefix, &name), value))
+            .collect();
+        Values { inner }
+    }
+
+    /// Moves all values of `other` into `self`. Values of repeated columns are
+    /// overwritten.
+    pub fn extend(&mut self, other: Values) {
+        self.inner.extend(other.inner);
+    }
+}
+
+/// Returns the qualified name of a column, i.e., `<prefix>.<column>`, which
+/// disambiguates the columns of rows that combine many tables (e.g., joins).
+pub fn qualify(prefix: &str, column: &str) -> String {
+    format!("{prefix}.{column}")
 }
 
 impl Default for Values {
@@ -72,77 +135,113 @@ impl From<HashMap<String, Value>> for Values {
     }
 }
 
-/// An schematized environment. See [`Values`].
-/// some schema.
+/// A schematized row, i.e., the values of all columns of some schema, ordered
+/// by their ordinals (i.e., in the schema order). See [`Values`].
 ///
-/// Only schematized [`Values`] maps may be serialized and deserialized.
+/// Only schematized rows may be serialized and deserialized. Since the column
+/// names aren't stored, columns are looked up by name through the schema (see
+/// [`SchematizedValues::get`]) or directly by ordinal (see
+/// [`SchematizedValues::get_ordinal`]).
 ///
 /// This type can only be constructed after validating the [`Values`] over a
 /// schema.
-#[derive(Debug, Clone)]
-pub struct SchematizedValues<'a> {
-    values: Cow<'a, Values>,
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SchematizedValues {
+    values: Vec<Value>,
     size: u32,
 }
 
-impl Size for SchematizedValues<'_> {
+impl Size for SchematizedValues {
     fn size(&self) -> u32 {
         self.size
     }
 }
 
-impl SerializeCtx<TableSchema> for SchematizedValues<'_> {
-    fn serialize(&self, buf: &mut buff::Buff<'_>, schema: &TableSchema) -> DbResult<()> {
-        for column in &schema.columns {
-            let value = self.values.get(&column.name).expect("is schematized");
+impl SerializeCtx<TableSchema> for SchematizedValues {
+    fn serialize(&self, buf: &mut buff::Buff<'_>, _schema: &TableSchema) -> DbResult<()> {
+        for value in &self.values {
             value.serialize(buf)?;
         }
         Ok(())
     }
 }
 
-impl DeserializeCtx<'_, TableSchema> for SchematizedValues<'_> {
+impl DeserializeCtx<'_, TableSchema> for SchematizedValues {
     fn deserialize(
-        buf: &mut buff::Buff<'_>,
+        buf: &mut buff::BuffRead<'_>,
         schema: &TableSchema,
-    ) -> DbResult<SchematizedValues<'static>>
+    ) -> DbResult<SchematizedValues>
     where
         Self: Sized,
     {
-        let mut inner = HashMap::with_capacity(schema.columns.len());
-        for column in &schema.columns {
-            let value = Value::deserialize(buf, &column.ty)?;
-            inner.insert(column.name.to_owned(), value);
-        }
+        let values = (schema.columns.iter())
+            .map(|column| Value::deserialize(buf, &column.ty))
+            .collect::<DbResult<_>>()?;
         // SAFETY: Database assumes that is just stores valid records.
-        Ok(unsafe { Self::try_new_unchecked(Cow::Owned(Values::from(inner)), None) })
+        Ok(unsafe { Self::new_unchecked(values) })
     }
 }
 
-impl SchematizedValues<'_> {
-    /// Returns a reference to the underlying [`Values`].
-    pub fn as_values(&self) -> &Values {
+impl SchematizedValues {
+    /// Returns the number of values, i.e., the number of columns of the schema.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Checks whether there are no values (i.e., the schema has no columns).
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the values, in the schema order.
+    pub fn as_slice(&self) -> &[Value] {
         &self.values
     }
 
-    /// Returns the underlying owned [`Values`].
+    /// Returns a reference to the value of the column at the given ordinal.
+    pub fn get_ordinal(&self, ordinal: usize) -> Option<&Value> {
+        self.values.get(ordinal)
+    }
+
+    /// Returns a reference to the value of the given column, which is resolved
+    /// through the given schema (which must be the one the values conform to).
+    pub fn get(&self, schema: &TableSchema, name: &str) -> Option<&Value> {
+        self.get_ordinal(schema.ordinal(name)?)
+    }
+
+    /// Returns the values of the columns at the given ordinals, in order.
     ///
-    /// This method *may* clone the underlying [`Values`] map.
-    pub fn into_values(self) -> Values {
-        self.values.into_owned()
+    /// # Panics
+    ///
+    /// Panics if some ordinal is out of bounds.
+    pub fn project(&self, ordinals: &[usize]) -> Vec<Value> {
+        ordinals.iter().map(|&i| self.values[i].clone()).collect()
+    }
+
+    /// Returns the values as a [`Values`] map, whose names are taken from the
+    /// given schema (which must be the one the values conform to).
+    pub fn to_values(&self, schema: &TableSchema) -> Values {
+        self.clone().into_values(schema)
+    }
+
+    /// Same as [`Self::to_values`], but taking ownership.
+    pub fn into_values(self, schema: &TableSchema) -> Values {
+        let names = schema.columns.iter().map(|column| column.name.clone());
+        Values::from(names.zip(self.values).collect::<HashMap<_, _>>())
+    }
+
+    /// Returns the underlying values, in the schema order.
+    pub fn into_vec(self) -> Vec<Value> {
+        self.values
     }
 
     /// Checks and modifies in place, if needed, that the given [`Values`]
     /// conforms to the provided [`TableSchema`].
-    ///
-    /// If successful, returns the size of the values, in record-format.
-    fn validate_and_apply_defaults(values: &mut Values, schema: &TableSchema) -> DbResult<u32> {
-        let mut size = 0;
+    fn validate_and_apply_defaults(values: &mut Values, schema: &TableSchema) -> DbResult<()> {
         for column in &schema.columns {
             let name = &column.name;
             match values.inner.get(name) {
                 Some(value) => {
-                    size += value.size();
                     if column.ty != value.type_id() {
                         return Err(Error::ExecError(format!(
                             "unexpected type for column `{name}`, expected of type `{}`, but got `{}`",
@@ -150,16 +249,21 @@ impl SchematizedValues<'_> {
                             value.type_id().name(),
                         )));
                     }
+                    if let Some(max_len) = column.max_len {
+                        check_len(name, value, max_len)?;
+                    }
                 }
                 None => {
-                    // TODO: Required fields in schema.
-                    let value = Value::default_for_type(column.ty);
-                    size += value.size();
+                    let value = match &column.default {
+                        Some(default) => default.clone(),
+                        None if column.not_null => return Err(Error::MissingValue(name.clone())),
+                        None => Value::default_for_type(column.ty),
+                    };
                     values.inner.insert(column.name.clone(), value);
                 }
             }
         }
-        Ok(size)
+        Ok(())
     }
 
     /// Constructs a new [`SchematizedValues`] without checking for types and
@@ -167,12 +271,92 @@ impl SchematizedValues<'_> {
     ///
     /// # Safety
     ///
-    /// Callers must ensure the given [`Values`] is schematized.
-    unsafe fn try_new_unchecked(
-        values: Cow<'_, Values>,
-        size: Option<u32>,
-    ) -> SchematizedValues<'_> {
-        let size = size.unwrap_or_else(|| values.inner.values().map(Value::size).sum());
+    /// Callers must ensure the given values are schematized, i.e., that they
+    /// conform to the schema, in order.
+    unsafe fn new_unchecked(values: Vec<Value>) -> SchematizedValues {
+        let size = values.iter().map(Value::size).sum();
         SchematizedValues { values, size }
     }
 }
+
+/// Checks whether the given text or blob value fits in the given maximum
+/// length.
+pub(crate) fn check_len(column: &str, value: &Value, max_len: u16) -> DbResult<()> {
+    let len = match value {
+        Value::Text(text) => text.len(),
+        Value::Blob(bytes) => bytes.len(),
+        _ => return Ok(()),
+    };
+    if len > max_len as usize {
+        return Err(Error::ValueTooLong {
+            column: column.to_owned(),
+            len,
+            max_len,
+        });
+    }
+    Ok(())
+}
+
+/// An ordered row, i.e., a sequence of values along with their column names.
+///
+/// Unlike [`Values`], a row preserves the order of its columns (e.g., the
+/// schema order or the order of a projection), so it may be rendered
+/// deterministically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    columns: Arc<[String]>,
+    values: Vec<Value>,
+}
+
+impl Row {
+    /// Constructs a new row from the given columns and their values, in the
+    /// same order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of columns and values differ.
+    pub fn new(columns: Arc<[String]>, values: Vec<Value>) -> Row {
+        assert_eq!(columns.len(), values.len(), "mismatched row length");
+        Row { columns, values }
+    }
+
+    /// Constructs a new row from the given columns, taking their values from
+    /// the given [`Values`] map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some column is missing from the map or is repeated.
+    pub fn project(columns: Arc<[String]>, mut values: Values) -> Row {
+        let values = columns
+            .iter()
+            .map(|column| values.remove(column).expect("projected column"))
+            .collect();
+        Row { columns, values }
+    }
+
+    /// Returns the column names, in order.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Returns the values, in the column order.
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Returns a reference to the value of the given column.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        let i = self.columns.iter().position(|column| column == name)?;
+        Some(&self.values[i])
+    }
+
+    /// Returns an iterator over the column names and their values, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.columns.iter().map(String::as_str).zip(&self.values)
+    }
+
+    /// Returns the underlying values, in the column order.
+    pub fn into_values(self) -> Vec<Value> {
+        self.values
+    }
+}
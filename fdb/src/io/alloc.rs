@@ -0,0 +1,49 @@
+//! Page allocation state.
+//!
+//! The allocation counters of the main header (i.e., the page count and the
+//! free list) are kept by the pager in an [`AllocState`], instead of being
+//! read and written under the first page latch. Hence, allocations (see
+//! [`Pager::alloc`]) don't serialize on the first page, and callers may
+//! allocate pages while holding a guard to it.
+//!
+//! Allocations (and deallocations) serialize among themselves on a dedicated
+//! latch. Once they change the counters, the first page is marked dirty, and
+//! the counters are patched into it when it's written to the disk. Hence, they
+//! are persisted along with the pages written by the same statement, as
+//! before. The counters of the cached first page are refreshed whenever its
+//! latch is free, so they may lag behind while it's held;
+//! [`Pager::alloc_state`] always returns the current ones.
+//!
+//! [`Pager::alloc`]: crate::io::pager::Pager::alloc
+//! [`Pager::alloc_state`]: crate::io::pager::Pager::alloc_state
+
+use crate::catalog::page::{MainHeader, PageId};
+
+/// The allocation counters of the main header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AllocState {
+    /// The total number of pages being used in the file.
+    pub page_count: u32,
+    /// The ID of the first free list page.
+    pub first_free_list_page_id: Option<PageId>,
+    /// The total number of pages in the free list.
+    pub free_page_count: u32,
+}
+
+impl AllocState {
+    /// Reads the counters of the given header.
+    pub fn from_header(header: &MainHeader) -> AllocState {
+        AllocState {
+            page_count: header.page_count,
+            first_free_list_page_id: header.first_free_list_page_id,
+            free_page_count: header.free_page_count,
+        }
+    }
+
+    /// Writes the counters into the given header.
+    pub fn apply(&self, header: &mut MainHeader) {
+        header.page_count = self.page_count;
+        header.first_free_list_page_id = self.first_free_list_page_id;
+        header.free_page_count = self.free_page_count;
+    }
+}
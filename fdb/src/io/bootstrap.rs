@@ -1,11 +1,67 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tokio::fs;
 use tracing::{debug, instrument};
 
 use crate::{
     catalog::page::{FirstPage, HeapPage, PageId},
     error::{DbResult, Error},
-    io::pager::Pager,
+    io::{disk_manager::DiskManager, pager::Pager},
 };
 
+/// Creates a bootstrapped database file at the given path, unless it already
+/// exists, in which case `false` is returned.
+///
+/// The creation is atomic: the file is bootstrapped under a unique temporary
+/// name (created exclusively) and then linked into the given path, which fails
+/// if another task or process created it in the meantime. Hence, concurrent
+/// first accesses agree on which one of them created the database, and no
+/// access ever observes a file which isn't bootstrapped yet.
+#[instrument(level = "debug", skip_all)]
+pub async fn create_file(path: &Path, page_size: u16) -> DbResult<bool> {
+    if fs::metadata(path).await.is_ok() {
+        return Ok(false);
+    }
+    let temp = temp_path(path);
+    let result = async {
+        let mut pager = Pager::new(DiskManager::create(&temp, page_size).await?);
+        boot_first_page(&mut pager).await?;
+        pager.flush_all().await?;
+        drop(pager);
+
+        match fs::hard_link(&temp, path).await {
+            Ok(()) => {
+                debug!(?path, "created database file");
+                Ok(true)
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                debug!(?path, "database file created concurrently");
+                Ok(false)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+    .await;
+    // Best effort; the file may not have been created.
+    let _ = fs::remove_file(&temp).await;
+    result
+}
+
+/// Returns a temporary path, unique to this process and call, next to the
+/// given one.
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(".{}-{id}.init", std::process::id()));
+    path.with_file_name(name)
+}
+
 /// Loads the first page, or bootstraps it in the case of first access.
 ///
 /// It also returns a boolean that, if true, indicates that the page was booted
@@ -16,11 +72,12 @@ pub async fn boot_first_page(pager: &mut Pager) -> DbResult<bool> {
 
     match pager.get::<FirstPage>(PageId::FIRST).await {
         Ok(guard) => {
-            let actual_page_size = guard.read().await.header.page_size;
-            if actual_page_size != page_size {
-                Err(Error::ExecError(format!(
-                    "file page size is {actual_page_size}; expected {page_size}"
-                )))
+            let actual = guard.read().await.header.page_size;
+            if actual != page_size {
+                Err(Error::PageSizeMismatch {
+                    expected: page_size,
+                    actual,
+                })
             } else {
                 Ok(false)
             }
@@ -41,9 +98,6 @@ pub async fn boot_first_page(pager: &mut Pager) -> DbResult<bool> {
 
             Ok(true)
         }
-        Err(Error::ReadIncompletePage(_)) => {
-            panic!("corrupt database file");
-        }
         Err(error) => Err(error),
     }
 }
@@ -0,0 +1,180 @@
+//! Pool of page-sized I/O buffers.
+
+use std::{
+    ops::{Deref, DerefMut},
+    slice,
+    sync::{
+        atomic::{self, AtomicU64},
+        Mutex,
+    },
+};
+
+/// The alignment of the buffers, suitable for direct I/O.
+pub const BUFFER_ALIGNMENT: usize = 512;
+
+/// The default maximum number of idle buffers kept by a [`BufferPool`].
+pub const DEFAULT_MAX_IDLE_BUFFERS: usize = 32;
+
+/// A pool of reusable page-sized buffers, which are aligned to
+/// [`BUFFER_ALIGNMENT`]. See [`BufferPool::get`].
+pub struct BufferPool {
+    /// The length of the buffers.
+    len: usize,
+    /// The buffers which aren't checked out.
+    idle: Mutex<Vec<AlignedBuf>>,
+    /// The maximum number of idle buffers. Buffers returned to a full pool are
+    /// deallocated.
+    max_idle: usize,
+    /// The number of buffers allocated since the pool was created.
+    allocated: AtomicU64,
+}
+
+impl BufferPool {
+    /// Creates a new pool of buffers of the given length.
+    pub fn new(len: usize) -> BufferPool {
+        BufferPool::with_max_idle(len, DEFAULT_MAX_IDLE_BUFFERS)
+    }
+
+    /// Creates a new pool of buffers of the given length, which keeps at most
+    /// the given number of idle buffers.
+    pub fn with_max_idle(len: usize, max_idle: usize) -> BufferPool {
+        BufferPool {
+            len,
+            idle: Mutex::default(),
+            max_idle,
+            allocated: AtomicU64::new(0),
+        }
+    }
+
+    /// Checks a buffer out of the pool, allocating a new one if there are no
+    /// idle buffers. The buffer is returned to the pool once dropped.
+    ///
+    /// The contents of the buffer are unspecified, as it may have been used.
+    pub fn get(&self) -> PooledBuffer<'_> {
+        let buf = self.idle.lock().unwrap().pop().unwrap_or_else(|| {
+            self.allocated.fetch_add(1, atomic::Ordering::Relaxed);
+            AlignedBuf::new(self.len)
+        });
+        PooledBuffer {
+            buf: Some(buf),
+            pool: self,
+        }
+    }
+
+    /// Returns the length of the buffers.
+    pub fn buffer_len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of idle buffers.
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    /// Returns the number of buffers allocated since the pool was created.
+    pub fn allocated_count(&self) -> u64 {
+        self.allocated.load(atomic::Ordering::Relaxed)
+    }
+}
+
+/// A buffer checked out of a [`BufferPool`], which is returned to it on drop.
+pub struct PooledBuffer<'a> {
+    /// Always `Some`, except while dropping.
+    buf: Option<AlignedBuf>,
+    pool: &'a BufferPool,
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.buf.as_ref().expect("not dropped").as_slice()
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.buf.as_mut().expect("not dropped").as_mut_slice()
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        let buf = self.buf.take().expect("not dropped");
+        let mut idle = self.pool.idle.lock().unwrap();
+        if idle.len() < self.pool.max_idle {
+            idle.push(buf);
+        }
+    }
+}
+
+/// An aligned block of bytes.
+#[derive(Copy, Clone)]
+#[repr(C, align(512))]
+struct Block([u8; BUFFER_ALIGNMENT]);
+
+/// A heap-allocated buffer, aligned to [`BUFFER_ALIGNMENT`].
+struct AlignedBuf {
+    blocks: Box<[Block]>,
+    len: usize,
+}
+
+impl AlignedBuf {
+    fn new(len: usize) -> AlignedBuf {
+        let blocks = vec![Block([0; BUFFER_ALIGNMENT]); len.div_ceil(BUFFER_ALIGNMENT)];
+        AlignedBuf {
+            blocks: blocks.into_boxed_slice(),
+            len,
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: Blocks are byte arrays without padding, and `len` doesn't
+        // exceed their total length.
+        unsafe { slice::from_raw_parts(self.blocks.as_ptr().cast(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: See `as_slice`.
+        unsafe { slice::from_raw_parts_mut(self.blocks.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers_are_reused() {
+        let pool = BufferPool::new(1000);
+
+        let mut buf = pool.get();
+        assert_eq!(buf.len(), 1000);
+        assert_eq!(buf.as_ptr() as usize % BUFFER_ALIGNMENT, 0);
+        buf.fill(0xAB);
+        let ptr = buf.as_ptr();
+        drop(buf);
+        assert_eq!(pool.idle_count(), 1);
+
+        let buf = pool.get();
+        assert_eq!(buf.as_ptr(), ptr);
+        assert_eq!(pool.idle_count(), 0);
+        let other = pool.get();
+        assert_ne!(other.as_ptr(), ptr);
+        assert_eq!(pool.allocated_count(), 2);
+    }
+
+    #[test]
+    fn test_max_idle() {
+        let pool = BufferPool::with_max_idle(128, 2);
+
+        let bufs: Vec<_> = (0..4).map(|_| pool.get()).collect();
+        drop(bufs);
+        assert_eq!(pool.idle_count(), 2);
+        assert_eq!(pool.allocated_count(), 4);
+        for _ in 0..10 {
+            pool.get();
+        }
+        assert_eq!(pool.allocated_count(), 4);
+    }
+}
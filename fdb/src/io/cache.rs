@@ -1,15 +1,34 @@
 use std::{
-    collections::hash_map::RandomState,
+    collections::{hash_map::RandomState, HashMap},
     future::Future,
     hash::{BuildHasher, Hash},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 use moka::future::Cache as MokaCache;
 
-/// A
+/// The pinned elements, along with their pin counts.
+type PinTable<K, V> = Arc<Mutex<HashMap<K, (Arc<V>, usize)>>>;
+
+/// A bounded cache, whose elements may be pinned (see [`Cache::pin`]).
+///
+/// Pinned elements are never evicted: if the underlying cache evicts a pinned
+/// element, it is still returned by the lookups until it is unpinned. Hence,
+/// there are never two different elements for the same key while any of them
+/// is pinned.
 pub struct Cache<K, V, S = RandomState> {
     inner: MokaCache<K, Arc<V>, S>,
+    pinned: PinTable<K, V>,
+}
+
+/// A pin over a cache element, which is unpinned on drop. See [`Cache::pin`].
+pub struct Pinned<K, V>
+where
+    K: Hash + Eq,
+{
+    key: K,
+    val: Arc<V>,
+    pinned: PinTable<K, V>,
 }
 
 impl<K, V, S> Cache<K, V, S>
@@ -24,7 +43,43 @@ where
             .max_capacity(capacity)
             .build_with_hasher(hasher);
 
-        Cache { inner }
+        Cache {
+            inner,
+            pinned: Arc::default(),
+        }
+    }
+
+    /// Pins the given element, which is kept (and returned by the lookups)
+    /// until the returned pin, and all other pins for the same key, are
+    /// dropped.
+    ///
+    /// If the key is already pinned, the pinned element is used instead of the
+    /// given one (see [`Pinned::value`]). This happens if the given element was
+    /// evicted and reloaded before being pinned.
+    pub fn pin(&self, key: K, val: Arc<V>) -> Pinned<K, V>
+    where
+        K: Clone,
+    {
+        let mut pinned = self.pinned.lock().unwrap();
+        let (val, count) = pinned.entry(key.clone()).or_insert((val, 0));
+        *count += 1;
+        Pinned {
+            key,
+            val: Arc::clone(val),
+            pinned: Arc::clone(&self.pinned),
+        }
+    }
+
+    /// Returns the number of pins for the given key.
+    pub fn pin_count(&self, key: &K) -> usize {
+        let pinned = self.pinned.lock().unwrap();
+        pinned.get(key).map_or(0, |(_, count)| *count)
+    }
+
+    /// Returns the pinned element for the given key, if any.
+    fn get_pinned(&self, key: &K) -> Option<Arc<V>> {
+        let pinned = self.pinned.lock().unwrap();
+        pinned.get(key).map(|(val, _)| Arc::clone(val))
     }
 
     /// Tries to get the element using the given key. If such an element doesn't
@@ -34,6 +89,9 @@ where
         F: Future<Output = Result<V, E>>,
         E: Clone + Send + Sync + 'static,
     {
+        if let Some(val) = self.get_pinned(&key) {
+            return Ok(val);
+        }
         self.inner
             .try_get_with(key, async { loader.await.map(Arc::new) })
             .await
@@ -46,23 +104,68 @@ where
     where
         K: std::fmt::Debug,
     {
-        if self.inner.contains_key(&key) {
+        if self.contains(&key) {
             panic!("can't insert key already registered: {key:?}");
         }
         self.inner.insert(key, val).await;
     }
 
+    /// Returns the element for the given key, inserting the given one if there
+    /// is no such element.
+    pub async fn get_or_insert(&self, key: K, val: Arc<V>) -> Arc<V> {
+        if let Some(val) = self.get_pinned(&key) {
+            return val;
+        }
+        self.inner.get_with(key, async { val }).await
+    }
+
     /// Tries to load the element using the given key.
     pub async fn get(&self, key: &K) -> Option<Arc<V>> {
-        self.inner.get(key)
+        self.peek(key)
+    }
+
+    /// Tries to get the element using the given key, without waiting. Unlike
+    /// [`Cache::get_or_load`], this doesn't coordinate with pending loaders.
+    pub fn peek(&self, key: &K) -> Option<Arc<V>> {
+        self.get_pinned(key).or_else(|| self.inner.get(key))
     }
 
-    /// Evicts the element for the given key.
+    /// Checks whether the cache contains the given key.
+    pub fn contains(&self, key: &K) -> bool {
+        self.pinned.lock().unwrap().contains_key(key) || self.inner.contains_key(key)
+    }
+
+    /// Evicts the element for the given key. A pinned element is kept until
+    /// it's unpinned.
     pub async fn evict(&self, key: &K) {
         self.inner.invalidate(key).await;
     }
 }
 
+impl<K, V> Pinned<K, V>
+where
+    K: Hash + Eq,
+{
+    /// Returns the pinned element.
+    pub fn value(&self) -> &Arc<V> {
+        &self.val
+    }
+}
+
+impl<K, V> Drop for Pinned<K, V>
+where
+    K: Hash + Eq,
+{
+    fn drop(&mut self) {
+        let mut pinned = self.pinned.lock().unwrap();
+        let (_, count) = pinned.get_mut(&self.key).expect("pinned key");
+        *count -= 1;
+        if *count == 0 {
+            pinned.remove(&self.key);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,6 +237,63 @@ mod tests {
         c.insert_new(1, Arc::new("one".into())).await; // BAM!
     }
 
+    #[tokio::test]
+    async fn test_pinned_not_evicted() {
+        let c = build_cache(4);
+
+        let v1 = c
+            .get_or_load(1, async { Ok::<_, ()>("one".into()) })
+            .await
+            .unwrap();
+        let pin = c.pin(1, Arc::clone(&v1));
+        let other_pin = c.pin(1, Arc::new("other".into()));
+        assert!(Arc::ptr_eq(other_pin.value(), &v1));
+        assert_eq!(c.pin_count(&1), 2);
+
+        c.evict(&1).await;
+        assert!(c.contains(&1));
+        let v1_2 = c
+            .get_or_load::<_, ()>(1, async {
+                panic!("shouldn't exec loader for pinned element");
+            })
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&v1_2, &v1));
+
+        drop(pin);
+        assert!(c.get(&1).await.is_some());
+        drop(other_pin);
+        assert_eq!(c.pin_count(&1), 0);
+        assert!(c.get(&1).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pinned_over_capacity() {
+        use moka::future::ConcurrentCacheExt;
+
+        let c = build_cache(1);
+
+        let v1 = c
+            .get_or_load(1, async { Ok::<_, ()>("one".into()) })
+            .await
+            .unwrap();
+        let _pin = c.pin(1, Arc::clone(&v1));
+        for key in 2..10 {
+            c.get_or_load(key, async { Ok::<_, ()>(key.to_string()) })
+                .await
+                .unwrap();
+            c.inner.sync();
+        }
+
+        let v1_2 = c
+            .get_or_load::<_, ()>(1, async {
+                panic!("shouldn't exec loader for pinned element");
+            })
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&v1_2, &v1));
+    }
+
     fn build_cache(cap: u64) -> Cache<u32, String> {
         Cache::new(cap, RandomState::default())
     }
@@ -1,37 +1,182 @@
 use std::{
-    io::{self, SeekFrom},
-    path::Path,
+    io,
+    path::{Path, PathBuf},
 };
 
-use tokio::{
-    fs::{File, OpenOptions},
-    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
-};
 use tracing::info;
 
 use crate::{
-    catalog::page::PageId,
+    catalog::page::{self, PageId},
     error::{DbResult, Error},
+    io::storage::{FileBackend, SegmentedFileBackend, StorageBackend},
 };
 
+/// The offset of the page size in the database header. See [`MainHeader`].
+///
+/// [`MainHeader`]: crate::catalog::page::MainHeader
+const HEADER_PAGE_SIZE_OFFSET: u64 = 11;
+
+/// When the written pages are synchronized to the storage device (i.e., with an
+/// `fsync`-like call), so that they survive an operating system crash or a
+/// power loss.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Never; it's up to the operating system.
+    #[default]
+    Off,
+    /// Whenever all dirty pages are written at once, e.g., at checkpoints (see
+    /// [`DiskManager::sync_all_pages`]).
+    Checkpoint,
+    /// Once each batch of pages is written (see [`DiskManager::sync_batch`]).
+    Normal,
+    /// After each page write.
+    Full,
+}
+
+/// How the pages are striped across segment files, each one holding (at most)
+/// the same number of pages. See [`SegmentedFileBackend`].
+///
+/// The segment files are named after the database path, with the (4-digit,
+/// starting at 1) segment number as an extension, e.g., `my-db.0001`,
+/// `my-db.0002`, and so on. Pages are stored in the order of their IDs, hence
+/// all segments but the last one are full.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SegmentLayout {
+    page_size: u16,
+    pages_per_segment: u64,
+}
+
+impl SegmentLayout {
+    /// Constructs the layout in which each segment holds as many pages as fit
+    /// in the given maximum segment size. Fails if not even one page fits.
+    pub fn new(page_size: u16, max_segment_size: u64) -> DbResult<SegmentLayout> {
+        let pages_per_segment = max_segment_size / u64::from(page_size);
+        if pages_per_segment == 0 {
+            return Err(Error::ExecError(format!(
+                "maximum segment size must be at least the page size ({page_size} bytes)"
+            )));
+        }
+        Ok(SegmentLayout {
+            page_size,
+            pages_per_segment,
+        })
+    }
+
+    /// Returns the page size.
+    pub fn page_size(&self) -> u16 {
+        self.page_size
+    }
+
+    /// Returns the size of a full segment, in bytes.
+    pub fn segment_size(&self) -> u64 {
+        self.pages_per_segment * u64::from(self.page_size)
+    }
+
+    /// Returns the (0-based) index of the segment which holds the given page,
+    /// along with the page offset in such segment.
+    pub fn locate(&self, page_id: PageId) -> (usize, u64) {
+        let offset = page_id.offset(self.page_size);
+        let segment_size = self.segment_size();
+        ((offset / segment_size) as usize, offset % segment_size)
+    }
+
+    /// Returns the path of the segment with the given (0-based) index, for the
+    /// database at the given path.
+    pub fn segment_path(path: &Path, index: usize) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_owned();
+        name.push(format!(".{:04}", index + 1));
+        path.with_file_name(name)
+    }
+}
+
+/// Reads and writes the database pages through a [`StorageBackend`] (by
+/// default, a [`FileBackend`]).
 pub struct DiskManager {
-    file: File,
+    backend: Box<dyn StorageBackend>,
     page_size: u16,
+    sync_mode: SyncMode,
 }
 
 impl DiskManager {
     /// Opens the file at the provided path and constructs a new disk manager
     /// instance that wraps over it.
     pub async fn new(path: &Path, page_size: u16) -> DbResult<Self> {
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            // TODO: Add `O_DIRECT` flag.
-            .open(path)
-            .await?;
+        let backend = FileBackend::open(path).await?;
+        Ok(DiskManager::with_backend(Box::new(backend), page_size))
+    }
+
+    /// Same as [`DiskManager::new`], but creates the file, failing if it
+    /// already exists.
+    pub async fn create(path: &Path, page_size: u16) -> DbResult<Self> {
+        let backend = FileBackend::create(path).await?;
+        Ok(DiskManager::with_backend(Box::new(backend), page_size))
+    }
+
+    /// Same as [`DiskManager::new`], but opens the file (which must exist) in
+    /// read-only mode. Writes fail.
+    pub async fn new_read_only(path: &Path, page_size: u16) -> DbResult<Self> {
+        let backend = FileBackend::open_read_only(path).await?;
+        Ok(DiskManager::with_backend(Box::new(backend), page_size))
+    }
 
-        Ok(DiskManager { file, page_size })
+    /// Opens the segment files of the database at the given path (see
+    /// [`SegmentLayout`]) and constructs a new disk manager instance that
+    /// wraps over them. In read-only mode, at least one segment must exist.
+    pub async fn new_segmented(
+        path: &Path,
+        layout: SegmentLayout,
+        read_only: bool,
+    ) -> DbResult<Self> {
+        let backend = SegmentedFileBackend::open(path, layout, read_only).await?;
+        Ok(DiskManager::with_backend(
+            Box::new(backend),
+            layout.page_size(),
+        ))
+    }
+
+    /// Constructs a new disk manager over the given storage backend.
+    pub fn with_backend(backend: Box<dyn StorageBackend>, page_size: u16) -> Self {
+        DiskManager {
+            backend,
+            page_size,
+            sync_mode: SyncMode::default(),
+        }
+    }
+
+    /// Returns the sync mode.
+    pub fn sync_mode(&self) -> SyncMode {
+        self.sync_mode
+    }
+
+    /// Sets the sync mode.
+    pub fn set_sync_mode(&mut self, sync_mode: SyncMode) {
+        self.sync_mode = sync_mode;
+    }
+
+    /// Returns the size of the database file, in bytes.
+    pub async fn file_size(&mut self) -> DbResult<u64> {
+        Ok(self.backend.len().await?)
+    }
+
+    /// Reads the page size recorded in the database header, without assuming
+    /// any page size. Returns `None` if the file has no header yet.
+    ///
+    /// Fails with [`Error::IncompatibleFile`] if the file was written with
+    /// another byte order (see [`page::BYTE_ORDER_MARK`]).
+    pub async fn read_header_page_size(&mut self) -> DbResult<Option<u16>> {
+        const PREFIX_SIZE: usize = page::BYTE_ORDER_MARK_OFFSET + 4;
+
+        if self.backend.len().await? < PREFIX_SIZE as u64 {
+            return Ok(None);
+        }
+        // Reads the header prefix as if it were a page, which is at offset zero
+        // regardless of the page size.
+        let mut buf = [0; PREFIX_SIZE];
+        self.backend.read_page(PageId::FIRST, &mut buf).await?;
+        let mark = &buf[page::BYTE_ORDER_MARK_OFFSET..];
+        page::check_byte_order_mark(u32::from_be_bytes([mark[0], mark[1], mark[2], mark[3]]))?;
+        let page_size = &buf[HEADER_PAGE_SIZE_OFFSET as usize..];
+        Ok(Some(u16::from_be_bytes([page_size[0], page_size[1]])))
     }
 
     /// Reads the contents of the page at the offset from the given page id,
@@ -44,17 +189,13 @@ impl DiskManager {
         info!(?page_id, "reading page from disk");
         assert_eq!(buf.len(), self.page_size as usize);
 
-        let size = self.file.metadata().await?.len();
+        let size = self.backend.len().await?;
         let offset = page_id.offset(self.page_size);
         if offset >= size {
             return Err(Error::PageOutOfBounds(page_id));
         }
 
-        self.file
-            .seek(SeekFrom::Start(page_id.offset(self.page_size)))
-            .await?;
-
-        if let Err(error) = self.file.read_exact(buf).await {
+        if let Err(error) = self.backend.read_page(page_id, buf).await {
             if error.kind() == io::ErrorKind::UnexpectedEof {
                 Err(Error::ReadIncompletePage(page_id))
             } else {
@@ -75,12 +216,37 @@ impl DiskManager {
         info!(?page_id, "writing page to disk");
         assert_eq!(buf.len(), self.page_size as usize);
 
-        self.file
-            .seek(SeekFrom::Start(page_id.offset(self.page_size)))
-            .await?;
+        self.backend.write_page(page_id, buf).await?;
+        if self.sync_mode == SyncMode::Full {
+            self.backend.sync(false).await?;
+        }
 
-        self.file.write_all(buf).await?;
+        Ok(())
+    }
+
+    /// Marks the end of a batch of page writes, synchronizing them if the sync
+    /// mode is [`SyncMode::Normal`].
+    pub async fn sync_batch(&mut self) -> DbResult<()> {
+        if self.sync_mode == SyncMode::Normal {
+            self.backend.sync(false).await?;
+        }
+        Ok(())
+    }
+
+    /// Marks the end of a write of all dirty pages, synchronizing them if the
+    /// sync mode is [`SyncMode::Checkpoint`].
+    pub async fn sync_all_pages(&mut self) -> DbResult<()> {
+        if self.sync_mode == SyncMode::Checkpoint {
+            self.backend.sync(false).await?;
+        }
+        Ok(())
+    }
 
+    /// Synchronizes the file (including its metadata) to the storage device,
+    /// regardless of the sync mode.
+    pub async fn sync(&mut self) -> DbResult<()> {
+        info!("syncing file");
+        self.backend.sync(true).await?;
         Ok(())
     }
 
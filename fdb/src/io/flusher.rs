@@ -0,0 +1,78 @@
+//! Background flusher.
+//!
+//! The maximum delay of the flush policy (see [`FlushPolicy::max_delay`]) is
+//! enforced by a background task, which writes the dirty pages once the delay
+//! since their last write elapses, even if no statement finishes meanwhile.
+//! While there is no delay, the task waits for the policy to be set again (see
+//! [`Pager::set_flush_policy`]).
+//!
+//! The pages are written under the statement latch (see [`Db::execute`]), so
+//! that the pages of a running statement are never partially written.
+//!
+//! [`FlushPolicy::max_delay`]: crate::io::pager::FlushPolicy::max_delay
+//! [`Db::execute`]: crate::Db::execute
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::RwLock, task::JoinHandle, time};
+use tracing::{error, instrument};
+
+use crate::{
+    error::DbResult,
+    io::{pager::Pager, scheduler::background},
+};
+
+/// A handle to a running flusher. The flusher is aborted if the handle is
+/// dropped.
+pub(crate) struct Flusher {
+    handle: JoinHandle<()>,
+}
+
+impl Flusher {
+    /// Spawns a new flusher over the given pager. Must be called within a Tokio
+    /// runtime.
+    pub(crate) fn spawn(pager: Arc<Pager>, statement_latch: Arc<RwLock<()>>) -> Flusher {
+        let handle = tokio::spawn(background(run(pager, statement_latch)));
+        Flusher { handle }
+    }
+}
+
+impl Drop for Flusher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Returns the maximum delay of the pager's flush policy, unless zero; zero
+/// delays are enforced as statements finish.
+fn max_delay(pager: &Pager) -> Option<Duration> {
+    (pager.flush_policy().max_delay).filter(|max_delay| !max_delay.is_zero())
+}
+
+async fn run(pager: Arc<Pager>, statement_latch: Arc<RwLock<()>>) {
+    loop {
+        let Some(max_delay) = max_delay(&pager) else {
+            pager.flush_policy_set().await;
+            continue;
+        };
+        // Statements may have written the pages in the meantime, hence the
+        // delay is counted from the last write.
+        tokio::select! {
+            _ = time::sleep_until(pager.last_flush() + max_delay) => {}
+            _ = pager.flush_policy_set() => continue,
+        }
+        if let Err(error) = flush(&pager, &statement_latch).await {
+            error!(?error, "background flush failed");
+        }
+    }
+}
+
+#[instrument(name = "BackgroundFlush", level = "debug", skip_all)]
+async fn flush(pager: &Pager, statement_latch: &RwLock<()>) -> DbResult<()> {
+    {
+        let _guard = statement_latch.write().await;
+        pager.flush().await?;
+    }
+    // Outside of the latch, as statements do. See `Db::execute`.
+    pager.commit().await
+}
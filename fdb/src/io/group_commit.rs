@@ -0,0 +1,172 @@
+//! Group commit.
+//!
+//! In [`SyncMode::Normal`], each statement synchronizes the pages it wrote once
+//! they are written to the disk. Since write statements run in isolation (see
+//! [`Db::execute`]), concurrent statements would pay for an `fsync` each.
+//!
+//! With group commit enabled (see [`DbOptions::with_group_commit`]), the sync
+//! is instead performed once the statement releases the statement latch (see
+//! [`Pager::commit`]). The first committing statement waits for up to the
+//! configured delay, so that other statements may write their pages in the
+//! meantime, and then synchronizes all written pages at once. The statements
+//! which commit while a sync is pending just wait for it. Either way, a
+//! statement only completes once its pages are synchronized.
+//!
+//! [`SyncMode::Normal`]: crate::io::disk_manager::SyncMode::Normal
+//! [`Db::execute`]: crate::Db::execute
+//! [`DbOptions::with_group_commit`]: crate::DbOptions::with_group_commit
+//! [`Pager::commit`]: crate::io::pager::Pager::commit
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex as SyncMutex,
+    },
+    time::Duration,
+};
+
+use tokio::sync::watch;
+use tracing::debug;
+
+use crate::error::{DbResult, Error};
+
+/// Coordinates the syncs of the committing statements.
+pub struct GroupCommit {
+    /// The maximum time the first committing statement waits for others to
+    /// join its sync. `None` if group commit is disabled.
+    max_delay: SyncMutex<Option<Duration>>,
+    state: SyncMutex<State>,
+    /// The last synchronized batch, along with the error of its sync, if any.
+    synced: watch::Sender<(u64, Option<Error>)>,
+    /// The number of commits which had to wait for a sync.
+    commits: AtomicU64,
+    /// The number of performed syncs.
+    syncs: AtomicU64,
+}
+
+#[derive(Default)]
+struct State {
+    /// The last batch of written (but not yet synchronized) pages.
+    written: u64,
+    /// Whether a committing statement is synchronizing the pages.
+    syncing: bool,
+}
+
+/// The group commit statistics. See [`Pager::group_commit_stats`].
+///
+/// [`Pager::group_commit_stats`]: crate::io::pager::Pager::group_commit_stats
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupCommitStats {
+    /// The number of commits which waited for a sync.
+    pub commits: u64,
+    /// The number of syncs performed on behalf of the commits.
+    pub syncs: u64,
+}
+
+impl GroupCommitStats {
+    /// Returns the average number of commits per sync. `None` if there was no
+    /// sync.
+    pub fn batching_factor(&self) -> Option<f64> {
+        (self.syncs > 0).then(|| self.commits as f64 / self.syncs as f64)
+    }
+}
+
+impl GroupCommit {
+    /// Constructs a new, disabled, coordinator.
+    pub fn new() -> GroupCommit {
+        GroupCommit {
+            max_delay: SyncMutex::new(None),
+            state: SyncMutex::default(),
+            synced: watch::channel((0, None)).0,
+            commits: AtomicU64::new(0),
+            syncs: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the maximum delay of a sync, or `None` if group commit is
+    /// disabled.
+    pub fn max_delay(&self) -> Option<Duration> {
+        *self.max_delay.lock().unwrap()
+    }
+
+    /// Sets the maximum delay of a sync, or disables group commit if `None`.
+    pub fn set_max_delay(&self, max_delay: Option<Duration>) {
+        *self.max_delay.lock().unwrap() = max_delay;
+    }
+
+    /// Records that a batch of pages was written, but not synchronized. Its
+    /// sync is deferred to the next [`GroupCommit::commit`].
+    pub(crate) fn defer(&self) {
+        self.state.lock().unwrap().written += 1;
+    }
+
+    /// Waits until all batches written so far are synchronized, performing the
+    /// given sync if no other commit is doing so.
+    pub(crate) async fn commit<F>(&self, sync: F) -> DbResult<()>
+    where
+        F: Future<Output = DbResult<()>>,
+    {
+        let target = self.state.lock().unwrap().written;
+        if self.synced.borrow().0 >= target {
+            return Ok(());
+        }
+        self.commits.fetch_add(1, Ordering::Relaxed);
+
+        let mut synced = self.synced.subscribe();
+        loop {
+            {
+                let (batch, error) = &*synced.borrow_and_update();
+                if *batch >= target {
+                    return error.clone().map_or(Ok(()), Err);
+                }
+                let mut state = self.state.lock().unwrap();
+                if !state.syncing {
+                    state.syncing = true;
+                    break;
+                }
+            }
+            // Waits for the running sync, which may not include the target
+            // batch, in which case this commit may have to sync by itself.
+            synced.changed().await.expect("sender is alive");
+        }
+
+        // This commit leads the sync, waiting for others to join it.
+        let _leader = Leader(self);
+        if let Some(max_delay) = self.max_delay().filter(|delay| !delay.is_zero()) {
+            tokio::time::sleep(max_delay).await;
+        }
+        let batch = self.state.lock().unwrap().written;
+        let result = sync.await;
+        self.syncs.fetch_add(1, Ordering::Relaxed);
+        debug!(batch, ok = result.is_ok(), "synchronized batches");
+        self.synced.send_replace((batch, result.clone().err()));
+        result
+    }
+
+    /// Returns the group commit statistics since the database was opened.
+    pub fn stats(&self) -> GroupCommitStats {
+        GroupCommitStats {
+            commits: self.commits.load(Ordering::Relaxed),
+            syncs: self.syncs.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Releases the sync lead once dropped, even if the leading commit is
+/// cancelled, waking the waiting commits so that one of them may lead the next
+/// sync.
+struct Leader<'a>(&'a GroupCommit);
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().syncing = false;
+        self.0.synced.send_modify(|_| ());
+    }
+}
+
+impl Default for GroupCommit {
+    fn default() -> Self {
+        GroupCommit::new()
+    }
+}
@@ -0,0 +1,119 @@
+//! Execution metrics.
+//!
+//! The database keeps a small registry of monotonic counters (see [`Metrics`]),
+//! which are bumped by the pager (page reads, writes and cache lookups) and by
+//! the executors (scanned and matched records). A point-in-time copy of all
+//! counters is a [`MetricsSnapshot`], exposed through [`Db::stats`]. The
+//! difference between two snapshots measures the work done in between, which
+//! is how [`Db::execute_with_metrics`] measures a single query.
+//!
+//! Counters are updated with relaxed atomics, hence a snapshot taken while
+//! statements are running may not be consistent across counters.
+//!
+//! [`Db::stats`]: crate::Db::stats
+//! [`Db::execute_with_metrics`]: crate::Db::execute_with_metrics
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A counter of the [`Metrics`] registry.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Counter {
+    /// The number of pages read from the disk.
+    PagesRead,
+    /// The number of pages written to the disk.
+    PagesWritten,
+    /// The number of page loads served by the page cache.
+    CacheHits,
+    /// The number of page loads which had to read from the disk.
+    CacheMisses,
+    /// The number of records read by table scans (sequential or index ones),
+    /// including the deleted ones.
+    RecordsScanned,
+    /// The number of scanned records which matched the filter or predicate of
+    /// the executor that consumed them.
+    RecordsMatched,
+}
+
+/// The counters registry of a database.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pages_read: AtomicU64,
+    pages_written: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    records_scanned: AtomicU64,
+    records_matched: AtomicU64,
+}
+
+impl Metrics {
+    /// Adds the given amount to the given counter.
+    pub fn add(&self, counter: Counter, amount: u64) {
+        self.counter(counter).fetch_add(amount, Ordering::Relaxed);
+    }
+
+    /// Increments the given counter.
+    pub fn incr(&self, counter: Counter) {
+        self.add(counter, 1);
+    }
+
+    /// Returns the current value of the given counter.
+    pub fn get(&self, counter: Counter) -> u64 {
+        self.counter(counter).load(Ordering::Relaxed)
+    }
+
+    /// Returns the current value of all counters.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            pages_read: self.get(Counter::PagesRead),
+            pages_written: self.get(Counter::PagesWritten),
+            cache_hits: self.get(Counter::CacheHits),
+            cache_misses: self.get(Counter::CacheMisses),
+            records_scanned: self.get(Counter::RecordsScanned),
+            records_matched: self.get(Counter::RecordsMatched),
+        }
+    }
+
+    fn counter(&self, counter: Counter) -> &AtomicU64 {
+        match counter {
+            Counter::PagesRead => &self.pages_read,
+            Counter::PagesWritten => &self.pages_written,
+            Counter::CacheHits => &self.cache_hits,
+            Counter::CacheMisses => &self.cache_misses,
+            Counter::RecordsScanned => &self.records_scanned,
+            Counter::RecordsMatched => &self.records_matched,
+        }
+    }
+}
+
+/// The values of the [`Metrics`] counters at some point in time. See
+/// [`Counter`] for their meaning.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub pages_read: u64,
+    pub pages_written: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub records_scanned: u64,
+    pub records_matched: u64,
+}
+
+impl MetricsSnapshot {
+    /// Returns the counter increments since the given (earlier) snapshot.
+    pub fn since(&self, earlier: &MetricsSnapshot) -> MetricsSnapshot {
+        MetricsSnapshot {
+            pages_read: self.pages_read.saturating_sub(earlier.pages_read),
+            pages_written: self.pages_written.saturating_sub(earlier.pages_written),
+            cache_hits: self.cache_hits.saturating_sub(earlier.cache_hits),
+            cache_misses: self.cache_misses.saturating_sub(earlier.cache_misses),
+            records_scanned: self.records_scanned.saturating_sub(earlier.records_scanned),
+            records_matched: self.records_matched.saturating_sub(earlier.records_matched),
+        }
+    }
+
+    /// Returns the fraction of page loads served by the page cache. `None` if
+    /// no page was loaded.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let total = self.cache_hits + self.cache_misses;
+        (total > 0).then(|| self.cache_hits as f64 / total as f64)
+    }
+}
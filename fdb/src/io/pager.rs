@@ -1,29 +1,55 @@
 use std::{
-    collections::hash_map::RandomState,
+    collections::{hash_map::RandomState, BTreeMap, HashMap},
     marker::PhantomData,
     ops::{Deref, DerefMut},
-    sync::Arc,
+    path::Path,
+    sync::{
+        atomic::{self, AtomicBool, AtomicU32},
+        Arc, Mutex as SyncMutex, OnceLock,
+    },
+    time::Duration,
 };
 
-use buff::Buff;
-use tokio::sync::{
-    mpsc::{self},
-    Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
+use buff::{Buff, BuffRead};
+use tokio::{
+    fs,
+    sync::{Mutex, Notify, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Instant,
 };
 use tracing::{debug, info, instrument, trace};
 
 use crate::{
-    catalog::page::{FirstPage, Page, PageId, SpecificPage},
-    error::{DbResult, Error},
-    io::{cache::Cache, disk_manager::DiskManager},
-    util::io::{Deserialize, Serialize},
+    catalog::page::{FirstPage, FreeListPage, Page, PageId, SpecificPage, MAX_HOT_PAGES},
+    error::{DbResult, Error, ErrorContext, ResultExt},
+    io::{
+        alloc::AllocState,
+        buffer_pool::BufferPool,
+        cache::{Cache, Pinned},
+        disk_manager::{DiskManager, SyncMode},
+        group_commit::{GroupCommit, GroupCommitStats},
+        metrics::{Counter, Metrics},
+        scheduler::{background, is_background, IoScheduler},
+        txn::{self, Image, TxnManager},
+    },
+    util::{
+        checksum::crc32,
+        io::{Deserialize, Serialize},
+    },
 };
 
+/// The size of the checksum stored at the end of each page. See
+/// [`crate::util::checksum`].
+pub const CHECKSUM_SIZE: u16 = 4;
+
 type LockedPage = RwLock<Page>;
 
-type PageNotification = (PageId, PageRefType);
-type PageNotificationSender = mpsc::UnboundedSender<PageNotification>;
-type PageNotificationReceiver = mpsc::UnboundedReceiver<PageNotification>;
+/// The pages whose writes were scheduled (see [`PagerWriteGuard::flush`]) but
+/// not yet written to the disk, ordered by their IDs.
+type DirtyPages = Arc<SyncMutex<BTreeMap<PageId, Arc<LockedPage>>>>;
+
+/// The number of writes to each page since the database was opened. See
+/// [`Pager::page_version`].
+type PageVersions = Arc<SyncMutex<HashMap<PageId, u64>>>;
 
 pub struct Pager {
     /// The page size.
@@ -32,55 +58,308 @@ pub struct Pager {
     disk_manager: Mutex<DiskManager>,
     /// The page cache to help avoid doing unnecessary disk accesses.
     ///
-    /// XX: Deal with conflicts related to the eviction of an in-use page, which
-    /// could lead to two **different** references (and RwLocks) to the same
-    /// page. One *maybe* could use some kind of checksum verification to ensure
-    /// the serial requirements of page write sequences.
+    /// Pages are pinned while there are [`PagerGuard`]s to them, so that an
+    /// in-use page is never evicted (which could lead to two **different**
+    /// references, and locks, to the same page).
     cache: Cache<PageId, LockedPage>,
-    /// Page guard drop sender.
-    page_status_tx: PageNotificationSender,
-    /// Page guard drop receiver.
-    page_status_rx: Mutex<PageNotificationReceiver>,
+    /// The number of times each page was accessed since the database was
+    /// opened. Used to determine the hot pages at checkpoint time.
+    access_counts: SyncMutex<HashMap<PageId, u32>>,
+    /// The maximum number of pages in the database file. Zero means that there
+    /// is no limit.
+    max_page_count: AtomicU32,
+    /// The execution counters, including the page cache hits and misses. See
+    /// [`Pager::metrics`].
+    metrics: Metrics,
+    /// The dirty pages, which are inserted by the write guards. Pages are kept
+    /// alive here until flushed, so that an evicted dirty page is never read
+    /// back from the disk.
+    dirty: DirtyPages,
+    /// The page versions, which are bumped on each write. See
+    /// [`Pager::page_version`].
+    versions: PageVersions,
+    /// When the dirty pages must be written. See [`Pager::flush`].
+    flush_policy: SyncMutex<FlushPolicy>,
+    /// Notified whenever the flush policy is set. See
+    /// [`Pager::flush_policy_set`].
+    flush_policy_set: Notify,
+    /// The instant of the last write of the dirty pages.
+    last_flush: SyncMutex<Instant>,
+    /// Batches the syncs of the committing statements. See [`Pager::commit`].
+    group_commit: GroupCommit,
+    /// Versions the pages written by the write transactions. See [`txn`].
+    txns: Arc<TxnManager>,
+    /// Schedules the page I/O of the background tasks.
+    io_scheduler: IoScheduler,
+    /// The page-sized buffers used to read and write the pages.
+    buffers: BufferPool,
+    /// Serializes the allocations and deallocations. See [`alloc`].
+    ///
+    /// [`alloc`]: crate::io::alloc
+    alloc_latch: Mutex<()>,
+    /// The allocation counters, known once the first page is loaded.
+    alloc_state: SyncMutex<Option<AllocState>>,
+    /// Whether the counters of the cached first page lag behind
+    /// `alloc_state`.
+    alloc_stale: AtomicBool,
 }
 
+/// The default number of pages in the page cache.
+pub const DEFAULT_CACHE_CAPACITY: u64 = 8192;
+
 impl Pager {
     /// Constructs a new pager.
     pub fn new(disk_manager: DiskManager) -> Pager {
-        let page_size = disk_manager.page_size();
+        Pager::with_cache_capacity(disk_manager, DEFAULT_CACHE_CAPACITY)
+    }
 
-        let (page_status_tx, rx) = mpsc::unbounded_channel::<PageNotification>();
-        let page_status_rx = Mutex::new(rx);
+    /// Constructs a new pager, whose page cache holds at most the given number
+    /// of pages.
+    pub fn with_cache_capacity(disk_manager: DiskManager, cache_capacity: u64) -> Pager {
+        let page_size = disk_manager.page_size();
         let disk_manager = Mutex::new(disk_manager);
 
         Pager {
             page_size,
-            cache: Cache::new(8192, RandomState::default()),
+            cache: Cache::new(cache_capacity, RandomState::default()),
             disk_manager,
-            page_status_tx,
-            page_status_rx,
+            access_counts: SyncMutex::default(),
+            max_page_count: AtomicU32::new(0),
+            metrics: Metrics::default(),
+            dirty: DirtyPages::default(),
+            versions: PageVersions::default(),
+            flush_policy: SyncMutex::default(),
+            flush_policy_set: Notify::new(),
+            last_flush: SyncMutex::new(Instant::now()),
+            group_commit: GroupCommit::new(),
+            txns: Arc::default(),
+            io_scheduler: IoScheduler::default(),
+            buffers: BufferPool::new(page_size as usize),
+            alloc_latch: Mutex::new(()),
+            alloc_state: SyncMutex::new(None),
+            alloc_stale: AtomicBool::new(false),
         }
     }
 
+    /// Returns the I/O scheduler, which schedules the page I/O of the
+    /// background tasks. See [`IoScheduler`].
+    pub fn io_scheduler(&self) -> &IoScheduler {
+        &self.io_scheduler
+    }
+
+    /// Returns the transaction manager, which versions the pages written by the
+    /// write transactions. See [`txn`].
+    pub fn txns(&self) -> &Arc<TxnManager> {
+        &self.txns
+    }
+
+    /// Checks whether the given page is read from a before-image within the
+    /// current task's snapshot (if any). See [`txn`].
+    pub fn is_versioned(&self, page_id: PageId) -> bool {
+        txn::current_snapshot().is_some_and(|snapshot| self.txns.image(page_id, snapshot).is_some())
+    }
+
+    /// Returns the pool of the buffers used to read and write the pages.
+    pub fn buffer_pool(&self) -> &BufferPool {
+        &self.buffers
+    }
+
     /// Returns the database's page size.
     pub fn page_size(&self) -> u16 {
         self.page_size
     }
 
+    /// Returns the size of the database file, in bytes.
+    pub async fn file_size(&self) -> DbResult<u64> {
+        self.disk_manager.lock().await.file_size().await
+    }
+
+    /// Returns the number of bytes available to the page contents, i.e., the
+    /// page size without the trailing checksum (see [`CHECKSUM_SIZE`]).
+    pub fn usable_size(&self) -> u16 {
+        self.page_size - CHECKSUM_SIZE
+    }
+
+    /// Returns the maximum number of pages in the database file, if any.
+    pub fn max_page_count(&self) -> Option<u32> {
+        match self.max_page_count.load(atomic::Ordering::Relaxed) {
+            0 => None,
+            max => Some(max),
+        }
+    }
+
+    /// Sets the maximum number of pages in the database file. Allocations that
+    /// would grow the file beyond it fail with [`Error::DatabaseFull`]. Pages
+    /// in the free list may always be reused.
+    ///
+    /// Pages beyond the limit (if the file is already larger) are kept.
+    pub fn set_max_page_count(&self, max_page_count: Option<u32>) {
+        let max_page_count = max_page_count.map_or(0, |max| max.max(1));
+        self.max_page_count
+            .store(max_page_count, atomic::Ordering::Relaxed);
+    }
+
+    /// Returns the current flush policy.
+    pub fn flush_policy(&self) -> FlushPolicy {
+        *self.flush_policy.lock().unwrap()
+    }
+
+    /// Sets the flush policy, which determines when the pages written by the
+    /// executed statements are written to the disk. See [`Pager::flush`].
+    pub fn set_flush_policy(&self, flush_policy: FlushPolicy) {
+        *self.flush_policy.lock().unwrap() = flush_policy;
+        self.flush_policy_set.notify_one();
+    }
+
+    /// Waits until the flush policy is set (see [`Pager::set_flush_policy`]),
+    /// or returns right away if it was set since the last call. Only the
+    /// background flusher may wait, since a single waiter is woken.
+    pub(crate) async fn flush_policy_set(&self) {
+        self.flush_policy_set.notified().await;
+    }
+
+    /// Returns the instant of the last write of the dirty pages.
+    pub(crate) fn last_flush(&self) -> Instant {
+        *self.last_flush.lock().unwrap()
+    }
+
     /// Returns a [`PagerGuard`] for the given page ID. This guard may be used
     /// to lock the page for a write or for a read.
+    ///
+    /// Pages in the page cache are returned synchronously (see
+    /// [`Pager::get_cached`]); otherwise, the page is loaded from the disk.
     pub async fn get<S: SpecificPage>(&self, page_id: PageId) -> DbResult<PagerGuard<S>> {
-        let inner = self
+        if let Some(guard) = self.get_cached(page_id) {
+            return Ok(guard);
+        }
+
+        *self
+            .access_counts
+            .lock()
+            .unwrap()
+            .entry(page_id)
+            .or_default() += 1;
+
+        let inner = self.load(page_id).await?;
+        Ok(self.guard(page_id, inner))
+    }
+
+    /// Returns a [`PagerGuard`] for the given page ID if the page is currently
+    /// in the page cache. Unlike [`Pager::get`], this never waits.
+    pub fn get_cached<S: SpecificPage>(&self, page_id: PageId) -> Option<PagerGuard<S>> {
+        let inner = self.cache.peek(&page_id)?;
+        self.metrics.incr(Counter::CacheHits);
+        *self
+            .access_counts
+            .lock()
+            .unwrap()
+            .entry(page_id)
+            .or_default() += 1;
+
+        Some(self.guard(page_id, inner))
+    }
+
+    /// Builds a guard for the given page, pinning it in the page cache.
+    fn guard<S: SpecificPage>(&self, page_id: PageId, inner: Arc<LockedPage>) -> PagerGuard<S> {
+        if page_id == PageId::FIRST {
+            self.refresh_first_page(&inner);
+        }
+        let pin = self.cache.pin(page_id, inner);
+        PagerGuard {
+            page_id,
+            inner: Arc::clone(pin.value()),
+            _pin: pin,
+            dirty: Arc::clone(&self.dirty),
+            versions: Arc::clone(&self.versions),
+            txns: Arc::clone(&self.txns),
+            snapshot: txn::current_snapshot(),
+            image: OnceLock::new(),
+            _specific: PhantomData,
+        }
+    }
+
+    /// Returns the number of [`PagerGuard`]s to the given page.
+    pub fn pin_count(&self, page_id: PageId) -> usize {
+        self.cache.pin_count(&page_id)
+    }
+
+    /// Returns the cached page, loading it from the disk if needed.
+    async fn load(&self, page_id: PageId) -> DbResult<Arc<LockedPage>> {
+        // The disk contents of a dirty page are stale.
+        let dirty = self.dirty.lock().unwrap().get(&page_id).cloned();
+        if let Some(page) = dirty {
+            self.metrics.incr(Counter::CacheHits);
+            return Ok(self.cache.get_or_insert(page_id, page).await);
+        }
+
+        let mut missed = false;
+        let page = self
             .cache
             .get_or_load::<_, Error>(page_id, async {
-                let page = self.disk_read_page(page_id).await?;
+                missed = true;
+                let mut page = self.disk_read_page(page_id).await?;
+                // Counted along with the disk read, rather than once the page
+                // is cached, so that both counters agree while prefetches (see
+                // `Pager::prefetch`) are running.
+                self.metrics.incr(Counter::CacheMisses);
+                if let Page::First(first_page) = &mut page {
+                    let mut state = self.alloc_state.lock().unwrap();
+                    match *state {
+                        // The counters on the disk may lag behind.
+                        Some(state) => state.apply(&mut first_page.header),
+                        None => *state = Some(AllocState::from_header(&first_page.header)),
+                    }
+                }
                 Ok(RwLock::new(page))
             })
             .await?;
-        Ok(PagerGuard {
-            inner,
-            notifier: self.page_status_tx.clone(),
-            _specific: PhantomData,
-        })
+        if !missed {
+            self.metrics.incr(Counter::CacheHits);
+        }
+        Ok(page)
+    }
+
+    /// Returns the number of page loads served by the page cache and the number
+    /// of page loads which had to read from the disk, respectively, since the
+    /// database was opened.
+    pub fn cache_stats(&self) -> (u64, u64) {
+        (
+            self.metrics.get(Counter::CacheHits),
+            self.metrics.get(Counter::CacheMisses),
+        )
+    }
+
+    /// Returns the execution counters of the database. Besides the page
+    /// counters, which are bumped by the pager itself, it also holds the record
+    /// counters bumped by the executors.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Returns the number of dirty pages, i.e., pages whose writes were
+    /// scheduled (see [`PagerWriteGuard::flush`]) but not yet written to the
+    /// disk. Each page is counted once, regardless of how many times it was
+    /// written.
+    pub fn pending_write_count(&self) -> u32 {
+        self.dirty.lock().unwrap().len() as u32
+    }
+
+    /// Returns the version of the given page, which changes whenever the page
+    /// is written (i.e., a write guard to it is flushed, or it is allocated or
+    /// deallocated). Versions are kept in memory, hence they are only
+    /// comparable while the database is open.
+    pub fn page_version(&self, page_id: PageId) -> u64 {
+        self.versions
+            .lock()
+            .unwrap()
+            .get(&page_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Checks whether the given page is currently in the page cache.
+    pub fn is_cached(&self, page_id: PageId) -> bool {
+        self.cache.contains(&page_id)
     }
 
     /// Reads the given page, exposing its data in the given closure.
@@ -90,73 +369,381 @@ impl Pager {
         F: FnOnce(&S) -> R,
     {
         let guard = self.get::<S>(page_id).await?;
-        let page = guard.read().await;
+        let page = match guard.try_read() {
+            Some(page) => page,
+            None => guard.read().await,
+        };
         let ret = f(&*page);
         page.release();
         Ok(ret)
     }
 
-    /// Flushes all available pages.
-    // XX: Review this design, which imposes read-only queries to call
-    // `flush_all` in order to clean the used records from `in_use`. Ideally,
-    // such a map's READ entries should be removed when the guard drops.
+    /// Reads the given page from the disk, bypassing (and not populating) the
+    /// page cache, hence the page is returned as it was last written. Fails
+    /// with [`Error::ChecksumMismatch`] if the stored checksum doesn't match.
+    ///
+    /// Dirty pages are not considered, so callers that want to read the
+    /// current contents must write them first (e.g., [`Pager::flush_all`]).
+    pub async fn read_from_disk(&self, page_id: PageId) -> DbResult<Page> {
+        self.disk_read_page(page_id).await
+    }
+
+    /// Same as [`Pager::read_from_disk`], but returns the undecoded page
+    /// contents (i.e., without the checksum), along with whether the checksum
+    /// matches. Corrupted pages may still be partially decodable, e.g., to
+    /// salvage their records (see [`salvage`]).
+    ///
+    /// [`salvage`]: crate::exec::salvage
+    pub async fn read_raw_from_disk(&self, page_id: PageId) -> DbResult<(Vec<u8>, bool)> {
+        let mut buf = self.buffers.get();
+        let valid = self.disk_read_raw(page_id, &mut buf).await?;
+        Ok((buf[..self.usable_size() as usize].to_vec(), valid))
+    }
+
+    /// Writes the dirty pages to the disk if required by the flush policy (see
+    /// [`FlushPolicy`]). Statements call this method once they are done; hence,
+    /// depending on the policy, the writes of many statements may be coalesced
+    /// into a single one.
+    ///
+    /// Within an explicit transaction (see [`txn`]), the writes are postponed
+    /// until it ends, so that its pages are written together.
+    ///
+    /// Use [`Pager::flush_all`] to unconditionally write the dirty pages.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn flush(&self) -> DbResult<()> {
+        let dirty_count = self.dirty.lock().unwrap().len();
+        if self.txns.is_journaling() {
+            debug!(dirty_count, "postponed flush until the transaction ends");
+            return Ok(());
+        }
+        let policy = self.flush_policy();
+        let due = dirty_count >= policy.max_dirty_pages.max(1)
+            || policy
+                .max_delay
+                .is_some_and(|max_delay| self.last_flush.lock().unwrap().elapsed() >= max_delay);
+        if due {
+            self.write_dirty().await?;
+        } else {
+            debug!(dirty_count, "postponed flush");
+        }
+        Ok(())
+    }
+
+    /// Writes all dirty pages to the disk, synchronizing them if the sync mode
+    /// is [`SyncMode::Checkpoint`].
+    ///
+    /// [`SyncMode::Checkpoint`]: crate::io::disk_manager::SyncMode::Checkpoint
     #[instrument(level = "debug", skip_all)]
     pub async fn flush_all(&self) -> DbResult<()> {
-        // TODO: Use a buffer pool.
-        let mut buf = vec![0; self.page_size as usize];
+        self.write_dirty().await?;
+        self.commit().await?;
+        self.disk_manager.lock().await.sync_all_pages().await
+    }
+
+    /// Writes all dirty pages to the disk and synchronizes them to the storage
+    /// device, regardless of the sync mode.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn sync(&self) -> DbResult<()> {
+        self.write_dirty().await?;
+        self.disk_manager.lock().await.sync().await
+    }
+
+    /// Waits until the pages written so far are synchronized, if their sync
+    /// was deferred by group commit (see [`group_commit`]). Statements call
+    /// this method once they release the statement latch, so that the syncs
+    /// of concurrent statements may be batched.
+    ///
+    /// [`group_commit`]: crate::io::group_commit
+    #[instrument(level = "debug", skip_all)]
+    pub async fn commit(&self) -> DbResult<()> {
+        let sync = async { self.disk_manager.lock().await.sync_batch().await };
+        self.group_commit.commit(sync).await
+    }
+
+    /// Returns the maximum time a commit waits for others to join its sync, or
+    /// `None` if group commit is disabled (the default).
+    pub fn group_commit_delay(&self) -> Option<Duration> {
+        self.group_commit.max_delay()
+    }
+
+    /// Sets the maximum time a commit waits for others to join its sync, or
+    /// disables group commit if `None`. Only takes effect in
+    /// [`SyncMode::Normal`].
+    pub fn set_group_commit_delay(&self, max_delay: Option<Duration>) {
+        self.group_commit.set_max_delay(max_delay);
+    }
+
+    /// Returns the group commit statistics since the database was opened,
+    /// whose batching factor is the average number of commits per sync.
+    pub fn group_commit_stats(&self) -> GroupCommitStats {
+        self.group_commit.stats()
+    }
+
+    /// Starts a savepoint of the explicit transaction (which starts with its
+    /// first savepoint), returning its depth. The pages modified from now on
+    /// are journaled, so that they can be restored by [`Pager::rollback_to`].
+    /// See [`txn`].
+    pub(crate) async fn savepoint(&self) -> DbResult<usize> {
+        let _latch = self.alloc_latch.lock().await;
+        let alloc = self.alloc_state().await?;
+        Ok(self.txns.push_layer(alloc))
+    }
+
+    /// Releases the savepoints from the given depth, keeping their changes.
+    pub(crate) fn release(&self, depth: usize) {
+        self.txns.release_layers(depth);
+    }
+
+    /// Restores the pages modified since the savepoint at the given depth was
+    /// started, along with the allocation counters. The savepoint and the ones
+    /// after it are removed. Must be called outside of any write transaction.
+    ///
+    /// The pages allocated by growing the file since then are dropped from the
+    /// page cache, so that they can be allocated again.
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn rollback_to(&self, depth: usize) -> DbResult<()> {
+        let Some((images, alloc)) = self.txns.rollback_layers(depth) else {
+            return Ok(());
+        };
+        let _latch = self.alloc_latch.lock().await;
+        let current = self.alloc_state().await?;
+        debug!(pages = images.len(), ?alloc, "rolling back");
 
-        let mut rx = self.page_status_rx.lock().await;
+        for (page_id, image) in images {
+            if page_id.get() > alloc.page_count {
+                continue;
+            }
+            let page = self.load(page_id).await?;
+            *page.write().await = image;
+            self.dirty.lock().unwrap().insert(page_id, page);
+            bump_version(&self.versions, page_id);
+        }
+        for n in alloc.page_count + 1..=current.page_count {
+            let page_id = PageId::new_u32(n);
+            self.dirty.lock().unwrap().remove(&page_id);
+            self.cache.evict(&page_id).await;
+            bump_version(&self.versions, page_id);
+        }
+        // The counters of the restored first page may lag behind.
+        self.replace_alloc_state(alloc).await
+    }
+
+    /// Writes the dirty pages to the disk, in the order of their IDs.
+    async fn write_dirty(&self) -> DbResult<()> {
+        let mut buf = self.buffers.get();
         let mut flush_count = 0;
 
         loop {
-            let Ok((page_id, ref_type)) = rx.try_recv() else {
-                debug!("flushed {flush_count} pages");
-                return Ok(());
+            // Pages are only removed from the set once written, so that they
+            // are never read back from the disk in the meantime.
+            let Some((page_id, page_arc)) = self
+                .dirty
+                .lock()
+                .unwrap()
+                .first_key_value()
+                .map(|(page_id, page)| (*page_id, Arc::clone(page)))
+            else {
+                break;
             };
 
-            let page_arc = self.cache.get(&page_id).await.expect("page must exist");
+            {
+                // In write reads, this lock should not have any contention.
+                let page = page_arc.read().await;
+
+                // TODO: FIXME: A failure in serialization may incur in
+                // database file corruption. For example, if page A was
+                // successfully written in an INSERT sequence (A -> B -> C)
+                // but B failed during serialization, the DB becomes
+                // inconsistent since A was written, but B and C were not.
+                match &*page {
+                    Page::First(first_page) => {
+                        // The counters of the cached first page may lag behind.
+                        let mut first_page = first_page.clone();
+                        if let Some(state) = *self.alloc_state.lock().unwrap() {
+                            state.apply(&mut first_page.header);
+                        }
+                        serialize_page(&mut buf, &first_page)
+                            .context(ErrorContext::Page(page_id))?;
+                    }
+                    page => serialize_page(&mut buf, page).context(ErrorContext::Page(page_id))?,
+                }
+            }
+
+            {
+                // Write contents. The comment above also applies here.
+                self.io_scheduler.acquire().await;
+                self.disk_manager
+                    .lock()
+                    .await
+                    .write_page(page_id, &buf)
+                    .await?;
+                self.metrics.incr(Counter::PagesWritten);
+                debug!(?page_id, "flushed page to disk");
+            }
 
-            if ref_type == PageRefType::Write {
-                let mut buf = Buff::new(&mut buf);
+            self.dirty.lock().unwrap().remove(&page_id);
+            flush_count += 1;
+        }
 
-                {
-                    // In write reads, this lock should not have any contention.
-                    let page = page_arc.read().await;
+        if flush_count > 0 {
+            let mut disk_manager = self.disk_manager.lock().await;
+            if disk_manager.sync_mode() == SyncMode::Normal && self.group_commit_delay().is_some() {
+                self.group_commit.defer();
+            } else {
+                disk_manager.sync_batch().await?;
+            }
+        }
+        *self.last_flush.lock().unwrap() = Instant::now();
+        debug!("flushed {flush_count} pages");
+        Ok(())
+    }
 
-                    // TODO: FIXME: A failure in serialization may incur in
-                    // database file corruption. For example, if page A was
-                    // successfully written in an INSERT sequence (A -> B -> C)
-                    // but B failed during serialization, the DB becomes
-                    // inconsistent since A was written, but B and C were not.
-                    page.serialize(&mut buf)?;
+    /// Records the most frequently used pages (at most [`MAX_HOT_PAGES`]) in
+    /// the first page, so that they can be prefetched by [`Pager::warmup`] the
+    /// next time the database is opened. All pending pages are flushed.
+    ///
+    /// # Deadlock
+    ///
+    /// This method acquires a write latch to the first page.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn checkpoint(&self) -> DbResult<()> {
+        let hot_page_ids = {
+            let counts = self.access_counts.lock().unwrap();
+            let mut counts: Vec<_> = counts
+                .iter()
+                .filter(|(page_id, _)| **page_id != PageId::FIRST)
+                .map(|(page_id, count)| (*page_id, *count))
+                .collect();
+            // Ties are broken by the page ID to keep the list deterministic.
+            counts.sort_unstable_by_key(|(page_id, count)| (u32::MAX - count, page_id.get()));
+            counts.truncate(MAX_HOT_PAGES);
+            counts.into_iter().map(|(page_id, _)| page_id).collect()
+        };
+        debug!(?hot_page_ids, "recording hot pages");
 
-                    // `serialize` should fill the buffer.
-                    debug_assert_eq!(buf.remaining(), 0);
-                }
+        let first_page_guard = self.get::<FirstPage>(PageId::FIRST).await?;
+        let mut first_page = first_page_guard.write().await;
+        first_page.hot_page_ids = hot_page_ids;
+        first_page.flush();
 
-                {
-                    // Write contents. The comment above also applies here.
-                    self.disk_manager
-                        .lock()
-                        .await
-                        .write_page(page_id, buf.get())
-                        .await?;
-                    debug!(?page_id, "flushed page to disk");
-                }
+        self.flush_all().await
+    }
 
-                flush_count += 1;
+    /// Copies the database pages into a new file at the given path (which must
+    /// not exist yet), returning the number of copied pages. All pending pages
+    /// are written first, and the copy is synchronized to the storage device.
+    ///
+    /// The pages are copied as they are on the disk, hence callers must
+    /// guarantee that no page is written while the copy runs (e.g., by holding
+    /// the statement latch, see [`Db::backup_to`]). If the copy fails, the
+    /// partially written file is removed.
+    ///
+    /// [`Db::backup_to`]: crate::Db::backup_to
+    #[instrument(level = "debug", skip_all)]
+    pub async fn backup_to(&self, path: &Path) -> DbResult<u32> {
+        self.write_dirty().await?;
+        let page_count = self.alloc_state().await?.page_count;
+
+        let mut target = DiskManager::create(path, self.page_size).await?;
+        let result = async {
+            let mut buf = self.buffers.get();
+            for page_number in 1..=page_count {
+                let page_id = PageId::new_u32(page_number);
+                self.io_scheduler.acquire().await;
+                self.disk_manager
+                    .lock()
+                    .await
+                    .read_page(page_id, &mut buf)
+                    .await?;
+                self.metrics.incr(Counter::PagesRead);
+                target.write_page(page_id, &buf).await?;
+            }
+            target.sync().await
+        }
+        .await;
+        if result.is_err() {
+            drop(target);
+            // Best effort; the error of the copy is the relevant one.
+            let _ = fs::remove_file(path).await;
+        }
+        debug!(page_count, ?path, "copied database pages");
+        result.map(|()| page_count)
+    }
+
+    /// Prefetches the hot pages recorded by the last [`Pager::checkpoint`]
+    /// into the page cache. Returns the number of prefetched pages.
+    ///
+    /// Prefetched pages are not accounted as accesses.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn warmup(&self) -> DbResult<usize> {
+        let first_page = self.load(PageId::FIRST).await?;
+        let hot_page_ids = first_page
+            .read()
+            .await
+            .cast_ref::<FirstPage>()
+            .hot_page_ids
+            .clone();
+
+        let mut count = 0;
+        for page_id in hot_page_ids {
+            match self.load(page_id).await {
+                Ok(_) => count += 1,
+                // The hot page list is only a hint; stale entries are skipped,
+                // as well as corrupted pages, so that damaged databases may
+                // still be opened (e.g., to be salvaged).
+                Err(Error::PageOutOfBounds(_) | Error::ChecksumMismatch(_)) => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        debug!(count, "prefetched hot pages");
+        Ok(count)
+    }
+
+    /// Loads the given pages into the page cache in a separate task, so that
+    /// they are (hopefully) cached once accessed. E.g., sequence scans prefetch
+    /// the page which follows the one they are reading, hiding the latency of
+    /// its disk read. Cached pages are skipped.
+    ///
+    /// Prefetching is only a hint: failed loads are ignored, and the pages may
+    /// be evicted before being accessed. Prefetched pages are not accounted as
+    /// accesses. The loads are background I/O (see [`background`]) if the
+    /// current task is.
+    pub fn prefetch(self: &Arc<Self>, page_ids: impl IntoIterator<Item = PageId>) {
+        let page_ids: Vec<_> = (page_ids.into_iter())
+            .filter(|page_id| !self.is_cached(*page_id))
+            .collect();
+        if page_ids.is_empty() {
+            return;
+        }
+        trace!(?page_ids, "prefetching pages");
+        let pager = Arc::clone(self);
+        let task = async move {
+            for page_id in page_ids {
+                // The page may have been accessed in the meantime.
+                if pager.is_cached(page_id) {
+                    continue;
+                }
+                if let Err(error) = pager.load(page_id).await {
+                    debug!(?page_id, %error, "failed to prefetch page");
+                }
             }
+        };
+        if is_background() {
+            tokio::spawn(background(task));
+        } else {
+            tokio::spawn(task);
         }
     }
 
     /// Allocates a new page, returning a [`PagerGuard`] to it. The page is
     /// flushed.
     ///
-    /// # Deadlock
+    /// Pages in the free list are reused before growing the database file.
     ///
-    /// This method acquires a write latch to the first page. Hence, callers
-    /// must guarantee that there are no other active guards (read or write) to
-    /// the first page.
+    /// Allocations don't acquire a latch to the first page (see [`alloc`]).
+    /// Hence, callers may hold guards to it.
+    ///
+    /// [`alloc`]: crate::io::alloc
     #[instrument(level = "debug", skip_all)]
     #[must_use]
     pub async fn alloc<S, F>(&self, create: F) -> DbResult<PagerGuard<S>>
@@ -164,33 +751,209 @@ impl Pager {
         S: SpecificPage,
         F: FnOnce(u16, PageId) -> S,
     {
-        debug!(ty = ?S::ty(), "allocating page");
+        let mut create = Some(create);
+        let mut guards = self
+            .alloc_many(1, |page_size, page_id| {
+                let create = create.take().expect("called only once");
+                create(page_size, page_id)
+            })
+            .await?;
+        Ok(guards.pop().expect("allocated one page"))
+    }
 
-        let first_page_guard = self.get::<FirstPage>(PageId::new_u32(1)).await?;
-        let mut first_page = first_page_guard.write().await;
+    /// Allocates `n` new pages at once, returning a [`PagerGuard`] to each one
+    /// of them, in allocation order. The pages are flushed.
+    ///
+    /// Pages in the free list are reused before growing the database file. The
+    /// allocation counters are updated (and the first page marked as dirty) a
+    /// single time, regardless of the number of allocated pages.
+    ///
+    /// Fails with [`Error::DatabaseFull`] (without allocating any page) if the
+    /// file would grow beyond the maximum page count.
+    ///
+    /// # Deadlock
+    ///
+    /// This method acquires a write latch to each reused free list page.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn alloc_many<S, F>(&self, n: u32, mut create: F) -> DbResult<Vec<PagerGuard<S>>>
+    where
+        S: SpecificPage,
+        F: FnMut(u16, PageId) -> S,
+    {
+        debug!(ty = ?S::ty(), n, "allocating pages");
 
-        first_page.header.page_count += 1;
+        let _latch = self.alloc_latch.lock().await;
+        let mut state = self.alloc_state().await?;
 
-        let page_id = PageId::new_u32(first_page.header.page_count);
-        let init = create(self.page_size, page_id);
+        // Pages in the free list don't grow the file.
+        let grow_count = n.saturating_sub(state.free_page_count);
+        if let Some(max_page_count) = self.max_page_count() {
+            if state.page_count as u64 + grow_count as u64 > max_page_count as u64 {
+                let max_size = max_page_count as u64 * self.page_size as u64;
+                return Err(Error::DatabaseFull { max_size });
+            }
+        }
 
-        let mut buf = vec![0; self.page_size as usize];
-        self.flush_page(&mut buf, &init).await?;
+        let mut guards = Vec::with_capacity(n as usize);
+        // The counters are stored even if an allocation fails midway, since the
+        // pages allocated so far were already taken.
+        let result = async {
+            let mut buf = self.buffers.get();
 
-        debug!("flushing first page metadata...");
-        first_page.flush();
+            while guards.len() < n as usize {
+                let Some(page_id) = state.first_free_list_page_id else {
+                    break;
+                };
+                let free_guard = self.get::<FreeListPage>(page_id).await?;
+                let mut free_page = free_guard.inner.write().await;
+                self.txns.capture(page_id, &free_page);
 
-        let guard_inner = Arc::new(RwLock::new(init.into_page()));
-        self.cache
-            .insert_new(page_id, Arc::clone(&guard_inner))
-            .await;
-        debug!(?page_id, "page allocated");
+                let init = create(self.usable_size(), page_id);
+                self.flush_page(&mut buf, &init).await?;
 
-        Ok(PagerGuard {
-            inner: guard_inner,
-            notifier: self.page_status_tx.clone(),
-            _specific: PhantomData,
-        })
+                let next_page_id = free_page.cast_ref::<FreeListPage>().next_page_id;
+                state.first_free_list_page_id = next_page_id;
+                state.free_page_count -= 1;
+
+                // The page is replaced in place (i.e., behind the same lock), so
+                // that the cache doesn't hold two different references to it.
+                *free_page = init.into_page();
+                bump_version(&self.versions, page_id);
+                drop(free_page);
+                debug!(?page_id, "page allocated from free list");
+
+                guards.push(self.guard(page_id, Arc::clone(&free_guard.inner)));
+            }
+
+            while guards.len() < n as usize {
+                state.page_count += 1;
+
+                let page_id = PageId::new_u32(state.page_count);
+                let init = create(self.usable_size(), page_id);
+                self.flush_page(&mut buf, &init).await?;
+
+                let guard_inner = Arc::new(RwLock::new(init.into_page()));
+                self.cache
+                    .insert_new(page_id, Arc::clone(&guard_inner))
+                    .await;
+                bump_version(&self.versions, page_id);
+                debug!(?page_id, "page allocated");
+
+                guards.push(self.guard(page_id, guard_inner));
+            }
+            Ok::<_, Error>(())
+        }
+        .await;
+
+        self.store_alloc_state(state).await?;
+        result.map(|()| guards)
+    }
+
+    /// Deallocates the given page, pushing it onto the free list so that it may
+    /// be reused by subsequent allocations. The page is flushed.
+    ///
+    /// Callers must guarantee that the given page is no longer referenced by
+    /// any other page (e.g., a heap sequence's `next_page_id`).
+    ///
+    /// # Deadlock
+    ///
+    /// This method acquires a write latch to the given page. Hence, callers
+    /// must guarantee that there are no other active guards (read or write) to
+    /// it. Like [`Pager::alloc`], it doesn't latch the first page.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn dealloc(&self, page_id: PageId) -> DbResult<()> {
+        debug!(?page_id, "deallocating page");
+        if page_id == PageId::FIRST {
+            return Err(Error::ExecError("can't deallocate the first page".into()));
+        }
+
+        let _latch = self.alloc_latch.lock().await;
+        let mut state = self.alloc_state().await?;
+
+        let guard = self.get::<Page>(page_id).await?;
+        let mut page = guard.inner.write().await;
+        self.txns.capture(page_id, &page);
+
+        if let Page::FreeList(_) = &*page {
+            return Err(Error::ExecError(format!(
+                "page {} is already free",
+                page_id.get()
+            )));
+        }
+
+        let free_page = FreeListPage::new(page_id, state.first_free_list_page_id);
+        let mut buf = self.buffers.get();
+        self.flush_page(&mut buf, &free_page).await?;
+
+        state.first_free_list_page_id = Some(page_id);
+        state.free_page_count += 1;
+
+        // See the remarks on `alloc` about replacing the page in place.
+        *page = free_page.into_page();
+        bump_version(&self.versions, page_id);
+        drop(page);
+        debug!(?page_id, "page deallocated");
+
+        self.store_alloc_state(state).await
+    }
+
+    /// Returns the current allocation counters (see [`alloc`]), which may be
+    /// ahead of the ones of the first page while it's latched.
+    ///
+    /// [`alloc`]: crate::io::alloc
+    pub async fn alloc_state(&self) -> DbResult<AllocState> {
+        if let Some(state) = *self.alloc_state.lock().unwrap() {
+            return Ok(state);
+        }
+        // Loading the first page (which doesn't latch it) sets the counters.
+        self.load(PageId::FIRST).await?;
+        let state = *self.alloc_state.lock().unwrap();
+        Ok(state.expect("first page was loaded"))
+    }
+
+    /// Stores the given allocation counters and marks the first page as dirty,
+    /// so that they are written along with it.
+    async fn store_alloc_state(&self, state: AllocState) -> DbResult<()> {
+        if *self.alloc_state.lock().unwrap() == Some(state) {
+            return Ok(());
+        }
+        self.replace_alloc_state(state).await
+    }
+
+    /// Same as [`Pager::store_alloc_state`], even if the counters didn't
+    /// change, e.g., since the cached first page was replaced.
+    async fn replace_alloc_state(&self, state: AllocState) -> DbResult<()> {
+        *self.alloc_state.lock().unwrap() = Some(state);
+        self.alloc_stale.store(true, atomic::Ordering::SeqCst);
+        debug!(?state, "stored allocation counters");
+
+        let first_page = self.load(PageId::FIRST).await?;
+        self.dirty
+            .lock()
+            .unwrap()
+            .insert(PageId::FIRST, Arc::clone(&first_page));
+        bump_version(&self.versions, PageId::FIRST);
+        self.refresh_first_page(&first_page);
+        Ok(())
+    }
+
+    /// Patches the current allocation counters into the cached first page,
+    /// unless it's latched (in which case it's refreshed later).
+    fn refresh_first_page(&self, first_page: &LockedPage) {
+        if !self.alloc_stale.load(atomic::Ordering::SeqCst) {
+            return;
+        }
+        let Ok(mut page) = first_page.try_write() else {
+            trace!("first page is latched; postponed counters refresh");
+            return;
+        };
+        // Cleared before the counters are read, so that a concurrent store is
+        // never missed.
+        self.alloc_stale.store(false, atomic::Ordering::SeqCst);
+        self.txns.capture(PageId::FIRST, &page);
+        if let Some(state) = *self.alloc_state.lock().unwrap() {
+            state.apply(&mut page.cast_mut::<FirstPage>().header);
+        }
     }
 
     /// Writes the given page to the database.
@@ -198,28 +961,25 @@ impl Pager {
     /// Callers must ensure consistency with the main database header.
     #[instrument(level = "debug", skip_all)]
     async fn flush_page(&self, buf: &mut [u8], page: &impl SpecificPage) -> DbResult<()> {
-        let mut buf = Buff::new(buf);
-
         // TODO: FIXME: A failure in serialization may incur in
         // database file corruption. For example, if page A was
         // successfully written in an INSERT sequence (A -> B -> C)
         // but B failed during serialization, the DB becomes
         // inconsistent since A was written, but B and C were not.
-        //                      \/
-        page.serialize(&mut buf)?;
-        // `serialize` should fill the buffer.
-        debug_assert_eq!(buf.remaining(), 0);
-
         let id = page.id();
+        //                      \/
+        serialize_page(buf, page).context(ErrorContext::Page(id))?;
         debug!(?id, "will flush now");
 
+        self.io_scheduler.acquire().await;
         self.disk_manager
             .lock()
             .await
-            .write_page(id, buf.get())
+            .write_page(id, buf)
             // Same remarks from serialization applies here.
             //    \/
             .await?;
+        self.metrics.incr(Counter::PagesWritten);
 
         Ok(())
     }
@@ -233,18 +993,19 @@ impl Pager {
     where
         S: SpecificPage,
     {
-        let mut buf = vec![0; self.page_size as usize];
+        let mut buf = self.buffers.get();
         self.flush_page(&mut buf, &page).await?;
 
         let id = page.id();
-        let inner = Arc::new(RwLock::new(page.into_page()));
+        let page = page.into_page();
+        if let Page::First(first_page) = &page {
+            *self.alloc_state.lock().unwrap() = Some(AllocState::from_header(&first_page.header));
+        }
+        let inner = Arc::new(RwLock::new(page));
         self.cache.insert_new(id, Arc::clone(&inner)).await;
+        bump_version(&self.versions, id);
 
-        Ok(PagerGuard {
-            inner,
-            notifier: self.page_status_tx.clone(),
-            _specific: PhantomData,
-        })
+        Ok(self.guard(id, inner))
     }
 
     /// Clears all cache information associated with the given page ID.
@@ -259,26 +1020,66 @@ impl Pager {
 
     /// Loads the page from the disk.
     async fn disk_read_page(&self, page_id: PageId) -> DbResult<Page> {
-        // TODO: Use a buffer pool.
-        let mut buf = vec![0; self.page_size as usize];
-        let mut buf = Buff::new(&mut buf);
+        let mut buf = self.buffers.get();
+        if !self.disk_read_raw(page_id, &mut buf).await? {
+            return Err(Error::ChecksumMismatch(page_id));
+        }
+        let payload = &buf[..self.usable_size() as usize];
+        Page::deserialize(&mut BuffRead::new(payload)).context(ErrorContext::Page(page_id))
+    }
 
+    /// Reads the given page from the disk into the given (page-sized) buffer,
+    /// returning whether its checksum matches.
+    async fn disk_read_raw(&self, page_id: PageId, buf: &mut [u8]) -> DbResult<bool> {
         {
+            self.io_scheduler.acquire().await;
             let mut dm = self.disk_manager.lock().await;
-            dm.read_page(page_id, buf.get_mut()).await?;
+            dm.read_page(page_id, buf).await?;
         }
+        self.metrics.incr(Counter::PagesRead);
 
-        Page::deserialize(&mut buf)
+        let (payload, checksum) = buf.split_at(self.usable_size() as usize);
+        Ok(crc32(payload).to_be_bytes() == *checksum)
     }
 }
 
-/// A page guard over a specific page type of type `S`.
+/// Serializes the given page into the given buffer (whose length must be the
+/// page size), followed by its checksum.
+fn serialize_page(buf: &mut [u8], page: &impl Serialize) -> DbResult<()> {
+    let (payload, checksum) = buf.split_at_mut(buf.len() - CHECKSUM_SIZE as usize);
+    let mut payload_buf = Buff::new(payload);
+    page.serialize(&mut payload_buf)?;
+    // `serialize` should fill the buffer.
+    debug_assert_eq!(payload_buf.remaining(), 0);
+    checksum.copy_from_slice(&crc32(payload).to_be_bytes());
+    Ok(())
+}
+
+/// Bumps the version of the given page. See [`Pager::page_version`].
+fn bump_version(versions: &PageVersions, page_id: PageId) {
+    *versions.lock().unwrap().entry(page_id).or_default() += 1;
+}
+
+/// A page guard over a specific page type of type `S`. The page is pinned in
+/// the page cache (i.e., it may not be evicted) while the guard is alive.
+///
+/// Guards obtained within a snapshot (see [`txn`]) read the page as of the
+/// snapshot. Writes always apply to the page itself.
 pub struct PagerGuard<S>
 where
     S: SpecificPage,
 {
+    page_id: PageId,
     inner: Arc<LockedPage>,
-    notifier: PageNotificationSender,
+    /// Keeps the page in the page cache while the guard is alive.
+    _pin: Pinned<PageId, LockedPage>,
+    dirty: DirtyPages,
+    versions: PageVersions,
+    txns: Arc<TxnManager>,
+    /// The snapshot the guard was obtained within, if any.
+    snapshot: Option<u64>,
+    /// The before-image seen by the snapshot, once found.
+    image: OnceLock<Image>,
     _specific: PhantomData<S>,
 }
 
@@ -288,38 +1089,85 @@ where
 {
     /// Locks the page for reading. As the underlying lock is a `RwLock`, other
     /// read references may also exist at the same time.
+    ///
+    /// Within a snapshot, the page is read as of the snapshot (see [`txn`]).
     #[instrument(level = "trace", skip_all)]
     pub async fn read(&self) -> PagerReadGuard<'_, S> {
-        let guard = self.inner.read().await;
+        let guard = match self.image.get() {
+            Some(image) => image.read().await,
+            None => {
+                let guard = self.inner.read().await;
+                match self.find_image() {
+                    Some(image) => {
+                        drop(guard);
+                        image.read().await
+                    }
+                    None => guard,
+                }
+            }
+        };
         trace!(page_id = ?guard.id(), ty = ?S::ty(), "acquiring read guard");
         PagerReadGuard {
             guard,
-            notifier: self.notifier.clone(),
-            manually_dropped: false,
             _specific: PhantomData,
         }
     }
 
+    /// Locks the page for reading if it isn't locked for writing. Unlike
+    /// [`PagerGuard::read`], this never waits.
+    pub fn try_read(&self) -> Option<PagerReadGuard<'_, S>> {
+        let guard = match self.image.get() {
+            Some(image) => image.try_read().ok()?,
+            None => {
+                let guard = self.inner.try_read().ok()?;
+                match self.find_image() {
+                    Some(image) => {
+                        drop(guard);
+                        image.try_read().ok()?
+                    }
+                    None => guard,
+                }
+            }
+        };
+        trace!(page_id = ?guard.id(), ty = ?S::ty(), "acquiring read guard");
+        Some(PagerReadGuard {
+            guard,
+            _specific: PhantomData,
+        })
+    }
+
     /// Locks the page for writing. There may be no other references (read or
     /// write) concurrently.
+    ///
+    /// Within a write transaction, the page is versioned before it's modified
+    /// (see [`txn`]).
     #[instrument(level = "trace", skip_all)]
     pub async fn write(&self) -> PagerWriteGuard<'_, S> {
         let guard = self.inner.write().await;
         trace!(page_id = ?guard.id(), ty = ?S::ty(), "acquiring write guard");
+        self.txns.capture(self.page_id, &guard);
         PagerWriteGuard {
             guard,
-            notifier: self.notifier.clone(),
+            page: &self.inner,
+            dirty: &self.dirty,
+            versions: &self.versions,
             manually_dropped: false,
             _specific: PhantomData,
         }
     }
+
+    /// Returns the before-image seen by the guard's snapshot, if any. Must be
+    /// called under the page latch, since the before-images are captured under
+    /// it.
+    fn find_image(&self) -> Option<&Image> {
+        let image = self.txns.image(self.page_id, self.snapshot?)?;
+        Some(self.image.get_or_init(|| image))
+    }
 }
 
 /// A page read guard. Non-exclusive for other read guards.
 pub struct PagerReadGuard<'a, S> {
     guard: RwLockReadGuard<'a, Page>,
-    notifier: PageNotificationSender,
-    manually_dropped: bool,
     _specific: PhantomData<S>,
 }
 
@@ -327,12 +1175,8 @@ impl<S> PagerReadGuard<'_, S>
 where
     S: SpecificPage,
 {
-    /// Releases the page reference guard.
-    pub fn release(mut self) {
-        self.notifier
-            .send((self.guard.id(), PageRefType::Read))
-            .expect("receiver must be alive");
-        self.manually_dropped = true;
+    /// Releases the page reference guard. Same as dropping it.
+    pub fn release(self) {
         trace!(ty = ?S::ty(), "released read guard");
     }
 }
@@ -348,19 +1192,12 @@ where
     }
 }
 
-impl<S> Drop for PagerReadGuard<'_, S> {
-    fn drop(&mut self) {
-        let page_id = self.guard.id();
-        if !self.manually_dropped {
-            info!(?page_id, "did not release read pager guard");
-        }
-    }
-}
-
 /// A page write guard. Exclusive.
 pub struct PagerWriteGuard<'a, S> {
     guard: RwLockWriteGuard<'a, Page>,
-    notifier: PageNotificationSender,
+    page: &'a Arc<LockedPage>,
+    dirty: &'a DirtyPages,
+    versions: &'a PageVersions,
     manually_dropped: bool,
     _specific: PhantomData<S>,
 }
@@ -369,11 +1206,12 @@ impl<S> PagerWriteGuard<'_, S>
 where
     S: SpecificPage,
 {
-    /// Releases the page reference guard and **schedules** a flush.
+    /// Releases the page reference guard and **schedules** a flush, i.e.,
+    /// marks the page as dirty. See [`Pager::flush`].
     pub fn flush(mut self) {
-        self.notifier
-            .send((self.guard.id(), PageRefType::Write))
-            .expect("receiver must be alive");
+        let page_id = self.guard.id();
+        (self.dirty.lock().unwrap()).insert(page_id, Arc::clone(self.page));
+        bump_version(self.versions, page_id);
         self.manually_dropped = true;
         debug!(ty = ?S::ty(), "flushed write guard");
     }
@@ -409,9 +1247,35 @@ impl<S> Drop for PagerWriteGuard<'_, S> {
     }
 }
 
-/// The page reference type.
-#[derive(Debug, PartialEq, Eq)]
-enum PageRefType {
-    Read,
-    Write,
+/// Determines when the dirty pages are written to the disk by [`Pager::flush`].
+/// They are written as soon as any of the conditions is met.
+///
+/// Unless the policy is [`FlushPolicy::IMMEDIATE`] (the default), the writes of
+/// the latest statements may be lost if the database isn't checkpointed (see
+/// [`Db::checkpoint`]) before being closed.
+///
+/// [`Db::checkpoint`]: crate::Db::checkpoint
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FlushPolicy {
+    /// The number of dirty pages from which they are written. Zero is treated
+    /// as one.
+    pub max_dirty_pages: usize,
+    /// The time since the last write from which the dirty pages are written.
+    /// It is checked when a statement finishes and, unless zero, by a
+    /// background flusher task.
+    pub max_delay: Option<Duration>,
+}
+
+impl FlushPolicy {
+    /// Writes the dirty pages as soon as each statement finishes.
+    pub const IMMEDIATE: FlushPolicy = FlushPolicy {
+        max_dirty_pages: 1,
+        max_delay: None,
+    };
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        FlushPolicy::IMMEDIATE
+    }
 }
@@ -0,0 +1,174 @@
+//! Background I/O scheduler.
+//!
+//! Page I/O issued by background tasks (see [`background`]), such as the
+//! auto-vacuum job or a spawned checkpoint, competes with the foreground
+//! statements for the disk manager. The [`IoScheduler`] throttles it to the
+//! configured rate and defers it while foreground statements are running.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{self, AtomicU32, AtomicU64, AtomicUsize},
+        Mutex as SyncMutex,
+    },
+    time::Duration,
+};
+
+use tokio::{sync::Notify, time::Instant};
+use tracing::trace;
+
+tokio::task_local! {
+    /// Set within the futures run by [`background`].
+    static BACKGROUND: ();
+}
+
+/// The maximum time a background page I/O is deferred while foreground
+/// statements are running. Bounds the deferral so that background tasks aren't
+/// starved, and so that a background task which holds a page latch needed by a
+/// foreground statement doesn't block it indefinitely.
+pub const MAX_BACKGROUND_DEFERRAL: Duration = Duration::from_millis(100);
+
+/// Runs the given future as a background task, whose page I/O is scheduled by
+/// the [`IoScheduler`].
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn f(db: std::sync::Arc<fdb::Db>) {
+/// use fdb::io::scheduler::background;
+///
+/// tokio::spawn(background(async move { db.checkpoint().await }));
+/// # }
+/// ```
+pub async fn background<F: Future>(future: F) -> F::Output {
+    BACKGROUND.scope((), future).await
+}
+
+/// Checks whether the current task is running within [`background`].
+pub fn is_background() -> bool {
+    BACKGROUND.try_with(|_| ()).is_ok()
+}
+
+/// Schedules the page I/O of the background tasks. Foreground page I/O is
+/// never delayed.
+///
+/// Background page I/O is deferred while foreground statements are running
+/// (see [`IoScheduler::foreground`]), for at most [`MAX_BACKGROUND_DEFERRAL`],
+/// and then paced to the background rate (see
+/// [`IoScheduler::set_background_rate`]).
+#[derive(Debug, Default)]
+pub struct IoScheduler {
+    /// The maximum number of background page I/Os per second. Zero means that
+    /// there is no limit.
+    background_rate: AtomicU32,
+    /// The instant from which the next background page I/O may be issued.
+    next_slot: SyncMutex<Option<Instant>>,
+    /// The number of running foreground statements.
+    foreground_count: AtomicUsize,
+    /// Notified once the last running foreground statement finishes.
+    idle: Notify,
+    /// The number of background page I/Os which were deferred.
+    deferred_count: AtomicU64,
+}
+
+impl IoScheduler {
+    /// Returns the maximum number of background page I/Os per second, if any.
+    pub fn background_rate(&self) -> Option<u32> {
+        match self.background_rate.load(atomic::Ordering::Relaxed) {
+            0 => None,
+            rate => Some(rate),
+        }
+    }
+
+    /// Sets the maximum number of background page I/Os per second. `None`
+    /// removes the limit.
+    pub fn set_background_rate(&self, rate: Option<u32>) {
+        self.background_rate
+            .store(rate.unwrap_or(0), atomic::Ordering::Relaxed);
+    }
+
+    /// Marks a foreground statement as running until the returned guard is
+    /// dropped. Returns `None` within background tasks, which are never
+    /// accounted as foreground.
+    pub fn foreground(&self) -> Option<ForegroundGuard<'_>> {
+        if is_background() {
+            return None;
+        }
+        self.foreground_count.fetch_add(1, atomic::Ordering::SeqCst);
+        Some(ForegroundGuard { scheduler: self })
+    }
+
+    /// Returns the number of running foreground statements.
+    pub fn foreground_count(&self) -> usize {
+        self.foreground_count.load(atomic::Ordering::SeqCst)
+    }
+
+    /// Returns the number of background page I/Os which were deferred since
+    /// the database was opened.
+    pub fn deferred_count(&self) -> u64 {
+        self.deferred_count.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Waits until the current task may issue a page I/O. Returns immediately
+    /// outside of background tasks.
+    pub(crate) async fn acquire(&self) {
+        if !is_background() {
+            return;
+        }
+        self.wait_idle().await;
+        self.pace().await;
+    }
+
+    /// Waits for the running foreground statements to finish, for at most
+    /// [`MAX_BACKGROUND_DEFERRAL`].
+    async fn wait_idle(&self) {
+        let deadline = Instant::now() + MAX_BACKGROUND_DEFERRAL;
+        let mut deferred = false;
+        loop {
+            // Created before the check, so that a concurrent notification
+            // isn't missed.
+            let idle = self.idle.notified();
+            if self.foreground_count() == 0 {
+                return;
+            }
+            if !deferred {
+                trace!("deferring background page I/O");
+                self.deferred_count.fetch_add(1, atomic::Ordering::Relaxed);
+                deferred = true;
+            }
+            if tokio::time::timeout_at(deadline, idle).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Waits for the next background page I/O slot, according to the rate.
+    async fn pace(&self) {
+        let Some(rate) = self.background_rate() else {
+            return;
+        };
+        let interval = Duration::from_secs(1) / rate;
+        let slot = {
+            let now = Instant::now();
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = next_slot.map_or(now, |next_slot| next_slot.max(now));
+            *next_slot = Some(slot + interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// Marks a foreground statement as running. See [`IoScheduler::foreground`].
+pub struct ForegroundGuard<'a> {
+    scheduler: &'a IoScheduler,
+}
+
+impl Drop for ForegroundGuard<'_> {
+    fn drop(&mut self) {
+        let count = &self.scheduler.foreground_count;
+        if count.fetch_sub(1, atomic::Ordering::SeqCst) == 1 {
+            self.scheduler.idle.notify_waiters();
+        }
+    }
+}
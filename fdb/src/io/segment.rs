@@ -0,0 +1,142 @@
+//! Sealed table segments.
+//!
+//! A segment is a write-once file which holds the rows of a sealed table (see
+//! [`SealTable`]). Since it's self-describing, it may be attached to any
+//! database (see [`AttachSegment`]), in which it's served as a read-only
+//! external table.
+//!
+//! # Format
+//!
+//! | Field                            | Size     |
+//! | -------------------------------- | -------- |
+//! | Magic (`FDBSEG`)                 | 6        |
+//! | Format version                   | 1        |
+//! | Table schema                     | Variable |
+//! | Row count                        | 8        |
+//! | Rows, in the schema format       | Variable |
+//! | CRC-32 of all preceding bytes    | 4        |
+//!
+//! The whole file is verified before any row is read.
+//!
+//! [`SealTable`]: crate::exec::query::object::SealTable
+//! [`AttachSegment`]: crate::exec::query::object::AttachSegment
+
+use buff::{Buff, BuffRead};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::AsyncWriteExt,
+};
+use tracing::debug;
+
+use crate::{
+    catalog::table_schema::TableSchema,
+    error::{DbResult, Error},
+    exec::values::SchematizedValues,
+    util::{
+        checksum::crc32,
+        io::{read_verify_eq, Deserialize, DeserializeCtx, Serialize, SerializeCtx, Size},
+    },
+};
+
+/// The magic bytes at the start of a segment file.
+pub const SEGMENT_MAGIC: &[u8; 6] = b"FDBSEG";
+
+/// The current segment format version.
+///
+/// Version 2 stores the lengths of texts and blobs as varints.
+pub const SEGMENT_VERSION: u8 = 2;
+
+/// The size of the fixed fields of a segment file.
+const FIXED_SIZE: usize = SEGMENT_MAGIC.len() + 1 + 8 + 4;
+
+/// A decoded segment.
+#[derive(Debug)]
+pub struct Segment {
+    pub schema: TableSchema,
+    pub rows: Vec<SchematizedValues>,
+}
+
+impl Segment {
+    /// Encodes the given rows, which must be schematized over the given schema.
+    pub fn encode(schema: &TableSchema, rows: &[SchematizedValues]) -> DbResult<Vec<u8>> {
+        let size = FIXED_SIZE
+            + schema.size() as usize
+            + rows.iter().map(|row| row.size() as usize).sum::<usize>();
+        let mut bytes = vec![0; size];
+        let mut buf = Buff::new(&mut bytes);
+        buf.write_slice(SEGMENT_MAGIC);
+        buf.write(SEGMENT_VERSION);
+        schema.serialize(&mut buf)?;
+        buf.write(rows.len() as u64);
+        for row in rows {
+            row.serialize(&mut buf, schema)?;
+        }
+        let (payload, checksum) = bytes.split_at_mut(size - 4);
+        checksum.copy_from_slice(&crc32(payload).to_be_bytes());
+        Ok(bytes)
+    }
+
+    /// Verifies and decodes the given segment file contents.
+    pub fn decode(bytes: &[u8]) -> DbResult<Segment> {
+        let corrupted = |reason: &str| Error::ExecError(format!("corrupted segment: {reason}"));
+        if bytes.len() < FIXED_SIZE {
+            return Err(corrupted("file is too short"));
+        }
+        let (payload, checksum) = bytes.split_at(bytes.len() - 4);
+        if crc32(payload).to_be_bytes() != *checksum {
+            return Err(corrupted("checksum mismatch"));
+        }
+
+        let mut buf = BuffRead::new(payload);
+        if !read_verify_eq(&mut buf, SEGMENT_MAGIC) {
+            return Err(corrupted("invalid magic"));
+        }
+        let version = buf.read::<1, u8>();
+        if version != SEGMENT_VERSION {
+            return Err(Error::ExecError(format!(
+                "unsupported segment version {version}"
+            )));
+        }
+        let schema = TableSchema::deserialize(&mut buf)?;
+        let row_count = buf.read::<8, u64>();
+        let rows = (0..row_count)
+            .map(|_| SchematizedValues::deserialize(&mut buf, &schema))
+            .collect::<DbResult<_>>()?;
+        if buf.remaining() != 0 {
+            return Err(corrupted("trailing bytes"));
+        }
+        Ok(Segment { schema, rows })
+    }
+
+    /// Checks whether the segment rows are of the given schema, i.e., whether
+    /// the columns (along with their types and maximum lengths) are the same.
+    pub fn has_schema(&self, schema: &TableSchema) -> bool {
+        let (a, b) = (&self.schema.columns, &schema.columns);
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|(a, b)| a.name == b.name && a.ty == b.ty && a.max_len == b.max_len)
+    }
+
+    /// Reads, verifies and decodes the segment file at the given path.
+    pub async fn read(path: &str) -> DbResult<Segment> {
+        let bytes = fs::read(path).await?;
+        debug!(path, len = bytes.len(), "read segment file");
+        Segment::decode(&bytes)
+    }
+
+    /// Writes the given encoded segment to a new file at the given path, which
+    /// is synchronized to the storage device. Fails if the file exists, since
+    /// segments are write-once.
+    pub async fn write_new(path: &str, bytes: &[u8]) -> DbResult<()> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .await?;
+        file.write_all(bytes).await?;
+        file.sync_all().await?;
+        debug!(path, len = bytes.len(), "wrote segment file");
+        Ok(())
+    }
+}
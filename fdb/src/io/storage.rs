@@ -0,0 +1,363 @@
+//! Storage backends.
+//!
+//! The [`DiskManager`] reads and writes the database pages through a
+//! [`StorageBackend`], so that the pager (and all upper layers) may run
+//! against alternative storage. The following backends are provided:
+//!
+//! - [`FileBackend`], a local file (the default);
+//! - [`SegmentedFileBackend`], many local files of a maximum size (see
+//!   [`DbOptions::max_segment_size`]);
+//! - [`MemoryBackend`], an in-memory buffer, which is lost once dropped;
+//! - `HttpBackend` (experimental, behind the `http` feature), a read-only file
+//!   served over plain HTTP, whose pages are fetched through range requests.
+//!
+//! Backends may be plugged through [`Db::open_with_backend`]. In-memory
+//! databases may also be opened through [`Db::open_in_memory`].
+//!
+//! [`DiskManager`]: crate::io::disk_manager::DiskManager
+//! [`Db::open_with_backend`]: crate::Db::open_with_backend
+//! [`Db::open_in_memory`]: crate::Db::open_in_memory
+//! [`DbOptions::max_segment_size`]: crate::DbOptions::max_segment_size
+
+use std::{
+    collections::BTreeSet,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+
+use crate::{
+    catalog::page::PageId,
+    error::{DbResult, Error},
+    io::disk_manager::SegmentLayout,
+};
+
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "http")]
+pub use http::*;
+
+/// The future returned by the [`StorageBackend`] methods.
+pub type IoFuture<'a, T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send + 'a>>;
+
+/// A storage which holds the database pages, contiguously and in the order of
+/// their IDs.
+///
+/// The page size is given by the length of the buffers. The methods return
+/// boxed futures (see [`IoFuture`]), so that backends may be used as trait
+/// objects.
+pub trait StorageBackend: Send {
+    /// Reads the given page into the given buffer. Fails with an
+    /// [`io::ErrorKind::UnexpectedEof`] error if the storage ends before the
+    /// buffer is filled.
+    fn read_page<'a>(&'a mut self, page_id: PageId, buf: &'a mut [u8]) -> IoFuture<'a, ()>;
+
+    /// Writes the given buffer to the given page, growing the storage if
+    /// needed.
+    fn write_page<'a>(&'a mut self, page_id: PageId, buf: &'a [u8]) -> IoFuture<'a, ()>;
+
+    /// Synchronizes the written pages to the storage device, including the
+    /// storage metadata (e.g., its length) if `metadata` is `true`.
+    fn sync(&mut self, metadata: bool) -> IoFuture<'_, ()>;
+
+    /// Returns the length of the storage, in bytes.
+    fn len(&mut self) -> IoFuture<'_, u64>;
+
+    /// Checks whether the storage is empty.
+    fn is_empty(&mut self) -> IoFuture<'_, bool> {
+        Box::pin(async move { Ok(self.len().await? == 0) })
+    }
+}
+
+/// The offset of the given page in a storage with the given page size.
+fn offset(page_id: PageId, page_size: usize) -> u64 {
+    page_id.offset(page_size as u16)
+}
+
+/// A [`StorageBackend`] over a local file.
+pub struct FileBackend {
+    file: File,
+}
+
+impl FileBackend {
+    /// Opens the file at the given path, which must exist. Database files are
+    /// created through [`FileBackend::create`].
+    pub async fn open(path: &Path) -> io::Result<FileBackend> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            // TODO: Add `O_DIRECT` flag.
+            .open(path)
+            .await?;
+        Ok(FileBackend { file })
+    }
+
+    /// Creates the file at the given path, failing if it already exists.
+    pub async fn create(path: &Path) -> io::Result<FileBackend> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .await?;
+        Ok(FileBackend { file })
+    }
+
+    /// Opens the file at the given path, which must exist, in read-only mode.
+    /// Writes fail.
+    pub async fn open_read_only(path: &Path) -> io::Result<FileBackend> {
+        let file = OpenOptions::new().read(true).open(path).await?;
+        Ok(FileBackend { file })
+    }
+}
+
+impl StorageBackend for FileBackend {
+    fn read_page<'a>(&'a mut self, page_id: PageId, buf: &'a mut [u8]) -> IoFuture<'a, ()> {
+        Box::pin(async move {
+            let offset = offset(page_id, buf.len());
+            self.file.seek(io::SeekFrom::Start(offset)).await?;
+            self.file.read_exact(buf).await?;
+            Ok(())
+        })
+    }
+
+    fn write_page<'a>(&'a mut self, page_id: PageId, buf: &'a [u8]) -> IoFuture<'a, ()> {
+        Box::pin(async move {
+            let offset = offset(page_id, buf.len());
+            self.file.seek(io::SeekFrom::Start(offset)).await?;
+            self.file.write_all(buf).await?;
+            // Tokio's file writes are performed in the background; flushing
+            // waits for the write to complete, so that it isn't lost if the
+            // file is dropped right away.
+            self.file.flush().await
+        })
+    }
+
+    fn sync(&mut self, metadata: bool) -> IoFuture<'_, ()> {
+        Box::pin(async move {
+            if metadata {
+                self.file.sync_all().await
+            } else {
+                self.file.sync_data().await
+            }
+        })
+    }
+
+    fn len(&mut self) -> IoFuture<'_, u64> {
+        Box::pin(async move { Ok(self.file.metadata().await?.len()) })
+    }
+}
+
+/// A [`StorageBackend`] over many local files (segments), each one holding a
+/// contiguous range of pages. See [`SegmentLayout`].
+///
+/// Segments are created as the storage grows. Once a new segment is created,
+/// the previous one is extended to its full size (as a sparse file, if pages
+/// are written out of order).
+pub struct SegmentedFileBackend {
+    path: PathBuf,
+    layout: SegmentLayout,
+    segments: Vec<File>,
+    /// The segments written since the last sync.
+    unsynced: BTreeSet<usize>,
+    read_only: bool,
+}
+
+impl SegmentedFileBackend {
+    /// Opens the existing segments of the database at the given path. In
+    /// read-only mode, at least one segment must exist, and writes fail.
+    ///
+    /// Fails with [`Error::IncompatibleFile`] if the segments were created
+    /// with another segment size.
+    pub async fn open(
+        path: &Path,
+        layout: SegmentLayout,
+        read_only: bool,
+    ) -> DbResult<SegmentedFileBackend> {
+        let mut segments = Vec::new();
+        loop {
+            let segment_path = SegmentLayout::segment_path(path, segments.len());
+            let file = match OpenOptions::new()
+                .read(true)
+                .write(!read_only)
+                .open(&segment_path)
+                .await
+            {
+                Ok(file) => file,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => break,
+                Err(error) => return Err(error.into()),
+            };
+            segments.push(file);
+        }
+        if read_only && segments.is_empty() {
+            let path = SegmentLayout::segment_path(path, 0);
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("segment {} not found", path.display()),
+            )
+            .into());
+        }
+
+        // All segments but the last one are full.
+        let segment_size = layout.segment_size();
+        let last = segments.len().saturating_sub(1);
+        for (index, file) in segments.iter().enumerate() {
+            let len = file.metadata().await?.len();
+            if len > segment_size || (index < last && len != segment_size) {
+                return Err(Error::IncompatibleFile("segment size mismatch"));
+            }
+        }
+
+        Ok(SegmentedFileBackend {
+            path: path.to_owned(),
+            layout,
+            segments,
+            unsynced: BTreeSet::new(),
+            read_only,
+        })
+    }
+
+    /// Creates the segment with the given index (and the ones before it) if it
+    /// doesn't exist.
+    async fn create_segments(&mut self, index: usize) -> io::Result<()> {
+        if self.read_only && index >= self.segments.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "read-only storage",
+            ));
+        }
+        while self.segments.len() <= index {
+            if let Some(last) = self.segments.last() {
+                last.set_len(self.layout.segment_size()).await?;
+                self.unsynced.insert(self.segments.len() - 1);
+            }
+            let path = SegmentLayout::segment_path(&self.path, self.segments.len());
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .await?;
+            self.segments.push(file);
+        }
+        Ok(())
+    }
+}
+
+impl StorageBackend for SegmentedFileBackend {
+    fn read_page<'a>(&'a mut self, page_id: PageId, buf: &'a mut [u8]) -> IoFuture<'a, ()> {
+        Box::pin(async move {
+            let (index, offset) = self.layout.locate(page_id);
+            let Some(file) = self.segments.get_mut(index) else {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            };
+            file.seek(io::SeekFrom::Start(offset)).await?;
+            file.read_exact(buf).await?;
+            Ok(())
+        })
+    }
+
+    fn write_page<'a>(&'a mut self, page_id: PageId, buf: &'a [u8]) -> IoFuture<'a, ()> {
+        Box::pin(async move {
+            let (index, offset) = self.layout.locate(page_id);
+            self.create_segments(index).await?;
+            self.unsynced.insert(index);
+            let file = &mut self.segments[index];
+            file.seek(io::SeekFrom::Start(offset)).await?;
+            file.write_all(buf).await?;
+            // See `FileBackend::write_page`.
+            file.flush().await
+        })
+    }
+
+    fn sync(&mut self, metadata: bool) -> IoFuture<'_, ()> {
+        Box::pin(async move {
+            while let Some(index) = self.unsynced.pop_first() {
+                let file = &self.segments[index];
+                let result = if metadata {
+                    file.sync_all().await
+                } else {
+                    file.sync_data().await
+                };
+                if let Err(error) = result {
+                    self.unsynced.insert(index);
+                    return Err(error);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn len(&mut self) -> IoFuture<'_, u64> {
+        Box::pin(async move {
+            let Some(last) = self.segments.last() else {
+                return Ok(0);
+            };
+            let full = (self.segments.len() - 1) as u64 * self.layout.segment_size();
+            Ok(full + last.metadata().await?.len())
+        })
+    }
+}
+
+/// A [`StorageBackend`] over an in-memory buffer. Syncs are no-ops.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBackend {
+    data: Vec<u8>,
+}
+
+impl MemoryBackend {
+    /// Constructs a new, empty, in-memory storage.
+    pub fn new() -> MemoryBackend {
+        MemoryBackend::default()
+    }
+
+    /// Constructs a new in-memory storage with the given contents, e.g., a
+    /// copy of a database file.
+    pub fn with_contents(data: Vec<u8>) -> MemoryBackend {
+        MemoryBackend { data }
+    }
+
+    /// Returns the storage contents.
+    pub fn contents(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn read_page<'a>(&'a mut self, page_id: PageId, buf: &'a mut [u8]) -> IoFuture<'a, ()> {
+        let start = offset(page_id, buf.len()) as usize;
+        let result = match self.data.get(start..start + buf.len()) {
+            Some(page) => {
+                buf.copy_from_slice(page);
+                Ok(())
+            }
+            None => Err(io::ErrorKind::UnexpectedEof.into()),
+        };
+        Box::pin(async move { result })
+    }
+
+    fn write_page<'a>(&'a mut self, page_id: PageId, buf: &'a [u8]) -> IoFuture<'a, ()> {
+        let start = offset(page_id, buf.len()) as usize;
+        let end = start + buf.len();
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+        }
+        self.data[start..end].copy_from_slice(buf);
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn sync(&mut self, _metadata: bool) -> IoFuture<'_, ()> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn len(&mut self) -> IoFuture<'_, u64> {
+        let len = self.data.len() as u64;
+        Box::pin(async move { Ok(len) })
+    }
+}
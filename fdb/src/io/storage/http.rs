@@ -0,0 +1,137 @@
+use std::io;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use tracing::debug;
+
+use crate::{
+    catalog::page::PageId,
+    io::storage::{offset, IoFuture, StorageBackend},
+};
+
+/// An experimental, read-only, [`StorageBackend`] over a database file served
+/// over plain HTTP (i.e., without TLS). Pages are fetched through range
+/// requests, which the server must support; nothing is cached, hence it should
+/// be used with a large enough page cache.
+///
+/// Writes fail, so the database must be opened in read-only mode (see
+/// [`DbOptions::with_read_only`](crate::DbOptions::with_read_only)).
+pub struct HttpBackend {
+    /// The server address, as `host:port`.
+    addr: String,
+    host: String,
+    path: String,
+}
+
+impl HttpBackend {
+    /// Constructs a backend for the file at the given URL, which must be of
+    /// the form `http://host[:port]/path`.
+    pub fn new(url: &str) -> io::Result<HttpBackend> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("invalid url {url}"));
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (host, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        let addr = match host.contains(':') {
+            true => host.to_owned(),
+            false => format!("{host}:80"),
+        };
+        Ok(HttpBackend {
+            addr,
+            host: host.to_owned(),
+            path: path.to_owned(),
+        })
+    }
+
+    /// Sends a request with the given method and headers, returning the status
+    /// code, the content length and the response reader.
+    async fn request(
+        &self,
+        method: &str,
+        headers: &str,
+    ) -> io::Result<(u16, u64, BufReader<TcpStream>)> {
+        debug!(method, path = self.path, headers, "sending http request");
+        let mut stream = TcpStream::connect(&self.addr).await?;
+        let request = format!(
+            "{method} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n{headers}\r\n",
+            self.path, self.host
+        );
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+        let status = line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| bad_response(&line))?;
+        let mut len = None;
+        loop {
+            line.clear();
+            reader.read_line(&mut line).await?;
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    len = value.trim().parse().ok();
+                }
+            }
+        }
+        let len = len.ok_or_else(|| bad_response("missing content length"))?;
+        Ok((status, len, reader))
+    }
+}
+
+fn bad_response(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("bad http response: {}", reason.trim_end()),
+    )
+}
+
+fn read_only() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "http storage is read-only")
+}
+
+impl StorageBackend for HttpBackend {
+    fn read_page<'a>(&'a mut self, page_id: PageId, buf: &'a mut [u8]) -> IoFuture<'a, ()> {
+        Box::pin(async move {
+            let start = offset(page_id, buf.len());
+            let end = start + buf.len() as u64 - 1;
+            let range = format!("Range: bytes={start}-{end}\r\n");
+            let (status, len, mut reader) = self.request("GET", &range).await?;
+            match status {
+                206 if len == buf.len() as u64 => reader.read_exact(buf).await.map(|_| ()),
+                // The range ends beyond the file.
+                206 | 416 => Err(io::ErrorKind::UnexpectedEof.into()),
+                _ => Err(bad_response(&format!("status {status}"))),
+            }
+        })
+    }
+
+    fn write_page<'a>(&'a mut self, _page_id: PageId, _buf: &'a [u8]) -> IoFuture<'a, ()> {
+        Box::pin(async move { Err(read_only()) })
+    }
+
+    fn sync(&mut self, _metadata: bool) -> IoFuture<'_, ()> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn len(&mut self) -> IoFuture<'_, u64> {
+        Box::pin(async move {
+            match self.request("HEAD", "").await? {
+                (200, len, _) => Ok(len),
+                (status, ..) => Err(bad_response(&format!("status {status}"))),
+            }
+        })
+    }
+}
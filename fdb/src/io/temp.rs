@@ -0,0 +1,353 @@
+//! Temporary artifacts management.
+//!
+//! Temporary page sequences (e.g., temporary tables) are registered in the
+//! first page for as long as they are alive. Hence, if the database crashes
+//! before they are dropped, they can be purged by the next [`Db::open`]. Indexes
+//! are registered as well while they are built (see [`register`]).
+//!
+//! Temporary files (e.g., operator spills) are managed by [`TempFiles`]. They
+//! are named after the database, so that the ones left behind by a crash can
+//! also be purged by the next [`Db::open`].
+//!
+//! [`Db::open`]: crate::Db::open
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as SyncMutex,
+    },
+};
+
+use tokio::fs::{File, OpenOptions};
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    catalog::page::{FirstPage, HeapPage, Page, PageId, PageType, SpecificPage, MAX_TEMP_SEQS},
+    error::{DbResult, Error},
+    exec::operations::{heap, index::BTree},
+    io::pager::Pager,
+};
+
+/// Allocates a new temporary heap sequence and registers it, returning the ID
+/// of its first page.
+///
+/// # Deadlock
+///
+/// This method acquires a write latch to the first page.
+#[instrument(level = "debug", skip_all)]
+pub async fn alloc_seq(pager: &Pager) -> DbResult<PageId> {
+    let page_guard = pager.alloc(HeapPage::new_seq_first).await?;
+    let page = page_guard.read().await;
+    let page_id = page.id();
+    page.release();
+
+    if let Err(error) = register(pager, page_id).await {
+        pager.dealloc(page_id).await?;
+        return Err(error);
+    }
+    Ok(page_id)
+}
+
+/// Registers the given page as the first one of a temporary heap sequence, or
+/// as the root of a B-tree (e.g., of an index being built), which is purged
+/// after a crash until it's unregistered (see [`unregister`]).
+///
+/// # Deadlock
+///
+/// This method acquires a write latch to the first page.
+#[instrument(level = "debug", skip_all)]
+pub async fn register(pager: &Pager, page_id: PageId) -> DbResult<()> {
+    {
+        let first_page_guard = pager.get::<FirstPage>(PageId::FIRST).await?;
+        let mut first_page = first_page_guard.write().await;
+        if first_page.temp_seq_page_ids.len() == MAX_TEMP_SEQS {
+            first_page.flush();
+            return Err(Error::ExecError(format!(
+                "can't have more than {MAX_TEMP_SEQS} temporary sequences"
+            )));
+        }
+        first_page.temp_seq_page_ids.push(page_id);
+        first_page.flush();
+        debug!(?page_id, "registered temporary sequence");
+    }
+
+    write_registrations(pager).await
+}
+
+/// Unregisters the given page, registered by [`register`], without releasing
+/// its pages.
+///
+/// # Deadlock
+///
+/// This method acquires a write latch to the first page.
+#[instrument(level = "debug", skip_all)]
+pub async fn unregister(pager: &Pager, page_id: PageId) -> DbResult<()> {
+    {
+        let first_page_guard = pager.get::<FirstPage>(PageId::FIRST).await?;
+        let mut first_page = first_page_guard.write().await;
+        let Some(index) = first_page
+            .temp_seq_page_ids
+            .iter()
+            .position(|id| *id == page_id)
+        else {
+            first_page.flush();
+            return Err(Error::ExecError(format!(
+                "page {} is not a temporary sequence",
+                page_id.get()
+            )));
+        };
+        first_page.temp_seq_page_ids.swap_remove(index);
+        first_page.flush();
+        debug!(?page_id, "unregistered temporary sequence");
+    }
+
+    write_registrations(pager).await
+}
+
+/// Unregisters the given temporary heap sequence and releases its pages.
+///
+/// # Deadlock
+///
+/// This method acquires a write latch to the first page and to all pages of
+/// the given sequence.
+#[instrument(level = "debug", skip_all)]
+pub async fn release_seq(pager: &Pager, page_id: PageId) -> DbResult<()> {
+    unregister(pager, page_id).await?;
+
+    // XX: The unregistration and the release are not atomic. If the database
+    // crashes in between, the sequence pages are leaked.
+    heap::release(pager, page_id).await?;
+    write_registrations(pager).await
+}
+
+/// Writes the dirty pages, so that the registrations of the temporary
+/// sequences are on the disk. Within an explicit transaction, the pages are
+/// only written once it ends (see [`Pager::flush`]), as a crash before then
+/// leaves none of its pages (hence none of its sequences) on the disk.
+async fn write_registrations(pager: &Pager) -> DbResult<()> {
+    if pager.txns().is_journaling() {
+        debug!("postponed write until the transaction ends");
+        return Ok(());
+    }
+    pager.flush_all().await
+}
+
+/// Returns the number of registered temporary heap sequences.
+pub async fn seq_count(pager: &Pager) -> DbResult<usize> {
+    pager
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.temp_seq_page_ids.len()
+        })
+        .await
+}
+
+/// Releases all registered temporary heap sequences (and B-trees), i.e., the
+/// ones left behind by a crash. Returns the number of purged sequences.
+#[instrument(level = "debug", skip_all)]
+pub async fn purge(pager: &Pager) -> DbResult<usize> {
+    let page_ids = pager
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.temp_seq_page_ids.clone()
+        })
+        .await?;
+    if page_ids.is_empty() {
+        return Ok(0);
+    }
+
+    for &page_id in &page_ids {
+        // If the crash happened during a release, the sequence may have already
+        // been (partially) moved to the free list.
+        let ty = pager.read_with(page_id, Page::ty).await?;
+        match ty {
+            PageType::Heap => {
+                heap::release(pager, page_id).await?;
+            }
+            PageType::BTree => {
+                BTree::new(page_id).release(pager).await?;
+            }
+            _ => warn!(?page_id, "skipping already released temporary sequence"),
+        }
+    }
+
+    let first_page_guard = pager.get::<FirstPage>(PageId::FIRST).await?;
+    let mut first_page = first_page_guard.write().await;
+    first_page.temp_seq_page_ids.clear();
+    first_page.flush();
+
+    pager.flush_all().await?;
+    debug!(count = page_ids.len(), "purged temporary sequences");
+    Ok(page_ids.len())
+}
+
+/// The extension of the temporary files.
+const TEMP_FILE_EXTENSION: &str = "tmp";
+
+/// The temporary files of a database. See [`Db::temp_files`].
+///
+/// The files are created in a given directory (see [`DbOptions::temp_dir`]),
+/// named `{prefix}-{n}.tmp`, and removed once their [`TempFile`] is dropped.
+/// The ones still alive when the manager is dropped are removed as well.
+///
+/// [`Db::temp_files`]: crate::Db::temp_files
+/// [`DbOptions::temp_dir`]: crate::DbOptions::temp_dir
+#[derive(Debug)]
+pub struct TempFiles {
+    dir: PathBuf,
+    prefix: String,
+    next_id: AtomicU64,
+    live: Arc<SyncMutex<HashSet<PathBuf>>>,
+}
+
+impl TempFiles {
+    /// Constructs a new manager of the temporary files with the given prefix,
+    /// in the given directory.
+    pub fn new(dir: impl Into<PathBuf>, prefix: impl Into<String>) -> TempFiles {
+        TempFiles {
+            dir: dir.into(),
+            prefix: prefix.into(),
+            next_id: AtomicU64::new(1),
+            live: Arc::default(),
+        }
+    }
+
+    /// Constructs a new manager of the temporary files of the database at the
+    /// given path. The files are named after the database file and, unless
+    /// another directory is given, live next to it.
+    pub fn for_path(path: &Path, dir: Option<&Path>) -> TempFiles {
+        let dir = match dir {
+            Some(dir) => dir.to_owned(),
+            None => match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+                _ => PathBuf::from("."),
+            },
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        TempFiles::new(dir, name)
+    }
+
+    /// Constructs a new manager of the temporary files of a database which
+    /// isn't stored in a file, in the given directory (or in the system's
+    /// temporary directory). The files are named after the process and the
+    /// manager, hence never purged.
+    pub fn anonymous(dir: Option<&Path>) -> TempFiles {
+        static NEXT_INSTANCE: AtomicU64 = AtomicU64::new(1);
+
+        let dir = dir.map_or_else(std::env::temp_dir, Path::to_owned);
+        let instance = NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed);
+        TempFiles::new(dir, format!("fdb-{}-{instance}", std::process::id()))
+    }
+
+    /// Returns the directory of the temporary files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the number of temporary files which are alive.
+    pub fn live_count(&self) -> usize {
+        self.live.lock().unwrap().len()
+    }
+
+    /// Creates a new, empty, temporary file.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn create(&self) -> DbResult<TempFile> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let path = self
+            .dir
+            .join(format!("{}-{id}.{TEMP_FILE_EXTENSION}", self.prefix));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await?;
+        debug!(?path, "created temporary file");
+        self.live.lock().unwrap().insert(path.clone());
+        Ok(TempFile {
+            path,
+            file,
+            live: Arc::clone(&self.live),
+        })
+    }
+
+    /// Removes the temporary files with this manager's prefix which are not
+    /// alive, i.e., the ones left behind by a crash. Returns the number of
+    /// removed files.
+    #[instrument(level = "debug", skip_all)]
+    pub fn purge(&self) -> io::Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(error) => return Err(error),
+        };
+        let live = self.live.lock().unwrap();
+        let mut count = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if self.is_temp_file(&path) && !live.contains(&path) {
+                fs::remove_file(&path)?;
+                count += 1;
+            }
+        }
+        if count > 0 {
+            debug!(count, "purged temporary files");
+        }
+        Ok(count)
+    }
+
+    /// Checks whether the file at the given path is named after a temporary
+    /// file of this manager.
+    fn is_temp_file(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        (name.strip_prefix(self.prefix.as_str()))
+            .and_then(|rest| rest.strip_prefix('-'))
+            .and_then(|rest| rest.strip_suffix(TEMP_FILE_EXTENSION))
+            .and_then(|rest| rest.strip_suffix('.'))
+            .is_some_and(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in self.live.lock().unwrap().drain() {
+            if let Err(error) = fs::remove_file(&path) {
+                warn!(?path, %error, "failed to remove temporary file");
+            }
+        }
+    }
+}
+
+/// A temporary file, which is removed once dropped. See [`TempFiles`].
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    file: File,
+    live: Arc<SyncMutex<HashSet<PathBuf>>>,
+}
+
+impl TempFile {
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the underlying file, which is opened for reading and writing.
+    pub fn file(&mut self) -> &mut File {
+        &mut self.file
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        // If the manager was dropped first, the file was already removed.
+        if self.live.lock().unwrap().remove(&self.path) {
+            if let Err(error) = fs::remove_file(&self.path) {
+                warn!(path = ?self.path, %error, "failed to remove temporary file");
+            }
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Transactions and snapshot reads.
+//!
+//! Write statements run in isolation from each other (see [`Db::execute`]),
+//! each one as a write transaction, whose IDs increase in commit order.
+//! Read-only statements don't wait for them: each one reads a [`Snapshot`] of
+//! the database as of the last committed transaction, taken when it starts.
+//!
+//! Snapshots are implemented by versioning the pages. Before a write
+//! transaction first modifies a page while there are snapshots, a copy of the
+//! page (its before-image) is kept, tagged with the transaction ID. Within a
+//! snapshot, each page is read from the before-image of the first transaction
+//! which committed (or is still running) after the snapshot was taken, or from
+//! the page itself if there is none (see [`PagerGuard::read`]). Before-images
+//! are dropped once no snapshot needs them.
+//!
+//! Pages written outside of write transactions (e.g., the temporary pages of a
+//! sort) aren't versioned.
+//!
+//! Explicit transactions (see [`Transaction`]) span many write transactions.
+//! While one is open, the before-images of the pages are also kept in a
+//! rollback journal, regardless of the snapshots, in layers: one for the
+//! transaction, and one for each savepoint (including the implicit savepoint
+//! of each statement). Each layer holds the images of the pages as of its
+//! start, so that they can be restored (see [`Transaction::rollback_to`]).
+//!
+//! [`Db::execute`]: crate::Db::execute
+//! [`PagerGuard::read`]: crate::io::pager::PagerGuard::read
+//! [`Transaction`]: crate::exec::transaction::Transaction
+//! [`Transaction::rollback_to`]: crate::exec::transaction::Transaction::rollback_to
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as SyncMutex,
+    },
+};
+
+use tokio::sync::RwLock;
+use tracing::trace;
+
+use crate::{
+    catalog::page::{Page, PageId},
+    io::alloc::AllocState,
+};
+
+tokio::task_local! {
+    /// Set within the futures run by [`with_snapshot`].
+    static SNAPSHOT: Snapshot;
+}
+
+/// Runs the given future within the given snapshot, i.e., all pages it reads
+/// are read as of the snapshot.
+pub async fn with_snapshot<F: Future>(snapshot: Snapshot, future: F) -> F::Output {
+    SNAPSHOT.scope(snapshot, future).await
+}
+
+/// Returns the ID of the snapshot the current task is running within (see
+/// [`with_snapshot`]), if any.
+pub fn current_snapshot() -> Option<u64> {
+    SNAPSHOT.try_with(|snapshot| snapshot.id).ok()
+}
+
+/// Returns a handle to the snapshot the current task is running within (see
+/// [`with_snapshot`]), if any. Tasks spawned by a statement must run within it
+/// too, since task-local values aren't inherited.
+pub fn current() -> Option<Snapshot> {
+    SNAPSHOT.try_with(Snapshot::clone).ok()
+}
+
+/// A versioned copy of a page.
+pub(crate) type Image = Arc<RwLock<Page>>;
+
+/// Coordinates the write transactions and the snapshots.
+#[derive(Debug, Default)]
+pub struct TxnManager {
+    state: SyncMutex<State>,
+    /// The number of captured before-images.
+    captured: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The ID of the last committed write transaction.
+    committed: u64,
+    /// The ID of the running write transaction, if any.
+    running: Option<u64>,
+    /// The active snapshots (i.e., the IDs of the last transaction they see),
+    /// along with their counts.
+    snapshots: BTreeMap<u64, usize>,
+    /// The before-images of each page, ordered by their transaction IDs.
+    images: HashMap<PageId, Vec<(u64, Image)>>,
+    /// The rollback journal layers, from the oldest one, if an explicit
+    /// transaction is open.
+    journal: Option<Vec<Layer>>,
+}
+
+/// A rollback journal layer.
+#[derive(Debug)]
+struct Layer {
+    /// The allocation counters as of the layer start.
+    alloc: AllocState,
+    /// The images of the pages modified since the layer start, as of it.
+    images: HashMap<PageId, Page>,
+}
+
+impl State {
+    /// Drops the before-images which no snapshot needs, i.e., those of the
+    /// transactions seen by all snapshots.
+    fn collect(&mut self) {
+        let Some(&oldest) = self.snapshots.keys().next() else {
+            self.images.clear();
+            return;
+        };
+        self.images.retain(|_, images| {
+            images.retain(|(txn, _)| *txn > oldest);
+            !images.is_empty()
+        });
+    }
+}
+
+/// The transaction statistics. See [`TxnManager::stats`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxnStats {
+    /// The ID of the last committed write transaction.
+    pub committed: u64,
+    /// The number of active snapshots.
+    pub snapshots: usize,
+    /// The number of pages which currently have before-images.
+    pub versioned_pages: usize,
+    /// The number of before-images captured since the database was opened.
+    pub captured: u64,
+}
+
+impl TxnManager {
+    /// Constructs a new transaction manager.
+    pub fn new() -> TxnManager {
+        TxnManager::default()
+    }
+
+    /// Begins a write transaction, which commits once the returned guard is
+    /// dropped. Write transactions must not overlap.
+    pub(crate) fn begin(self: &Arc<Self>) -> WriteTxn {
+        let mut state = self.state.lock().unwrap();
+        debug_assert!(state.running.is_none(), "overlapping write transactions");
+        let id = state.committed + 1;
+        state.running = Some(id);
+        trace!(id, "began write transaction");
+        WriteTxn {
+            id,
+            manager: Arc::clone(self),
+        }
+    }
+
+    /// Takes a snapshot of the committed transactions, which is released once
+    /// dropped. No write transaction may be running while it's taken, since
+    /// the pages it already modified weren't versioned.
+    pub(crate) fn snapshot(self: &Arc<Self>) -> Snapshot {
+        let mut state = self.state.lock().unwrap();
+        debug_assert!(state.running.is_none(), "snapshot during write transaction");
+        let id = state.committed;
+        *state.snapshots.entry(id).or_default() += 1;
+        Snapshot {
+            id,
+            manager: Arc::clone(self),
+        }
+    }
+
+    /// Keeps the given page as a before-image of the running write transaction
+    /// (if any), unless it already has one or there are no snapshots. Within an
+    /// explicit transaction, it's also kept in the top journal layer, unless
+    /// it already has one. Must be called before the page is modified.
+    pub(crate) fn capture(&self, page_id: PageId, page: &Page) {
+        let mut state = self.state.lock().unwrap();
+        let Some(txn) = state.running else {
+            return;
+        };
+        if let Some(layer) = state.journal.as_mut().and_then(|layers| layers.last_mut()) {
+            layer.images.entry(page_id).or_insert_with(|| page.clone());
+        }
+        if state.snapshots.is_empty() {
+            return;
+        }
+        let images = state.images.entry(page_id).or_default();
+        if images.last().is_some_and(|(last, _)| *last == txn) {
+            return;
+        }
+        trace!(?page_id, txn, "captured before-image");
+        images.push((txn, Arc::new(RwLock::new(page.clone()))));
+        self.captured.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the image of the given page as seen by the given snapshot, or
+    /// `None` if the snapshot sees the page itself.
+    pub(crate) fn image(&self, page_id: PageId, snapshot: u64) -> Option<Image> {
+        let state = self.state.lock().unwrap();
+        let images = state.images.get(&page_id)?;
+        let (_, image) = images.iter().find(|(txn, _)| *txn > snapshot)?;
+        Some(Arc::clone(image))
+    }
+
+    /// Checks whether an explicit transaction is open, i.e., whether the
+    /// modified pages are journaled.
+    pub fn is_journaling(&self) -> bool {
+        self.state.lock().unwrap().journal.is_some()
+    }
+
+    /// Starts a new journal layer, given the current allocation counters,
+    /// returning its depth. The journal is started if there is none.
+    pub(crate) fn push_layer(&self, alloc: AllocState) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let layers = state.journal.get_or_insert_with(Vec::new);
+        layers.push(Layer {
+            alloc,
+            images: HashMap::new(),
+        });
+        trace!(depth = layers.len() - 1, "pushed journal layer");
+        layers.len() - 1
+    }
+
+    /// Merges the layers from the given depth into the one below it, whose
+    /// images are kept, as they are older.
+    pub(crate) fn release_layers(&self, depth: usize) {
+        let mut state = self.state.lock().unwrap();
+        let Some(layers) = state.journal.as_mut() else {
+            return;
+        };
+        if depth == 0 || depth >= layers.len() {
+            return;
+        }
+        for layer in layers.split_off(depth) {
+            let below = layers.last_mut().expect("layer below");
+            for (page_id, image) in layer.images {
+                below.images.entry(page_id).or_insert(image);
+            }
+        }
+        trace!(depth, "released journal layers");
+    }
+
+    /// Removes the layers from the given depth, returning the images of the
+    /// pages they modified (as of the start of the layer at the given depth)
+    /// and the allocation counters as of that start. The pages must then be
+    /// restored, outside of any write transaction.
+    pub(crate) fn rollback_layers(
+        &self,
+        depth: usize,
+    ) -> Option<(HashMap<PageId, Page>, AllocState)> {
+        let mut state = self.state.lock().unwrap();
+        let layers = state.journal.as_mut()?;
+        if depth >= layers.len() {
+            return None;
+        }
+        let mut removed = layers.split_off(depth);
+        let base = removed.remove(0);
+        let mut images = base.images;
+        // The older images win.
+        for layer in removed {
+            for (page_id, image) in layer.images {
+                images.entry(page_id).or_insert(image);
+            }
+        }
+        trace!(depth, pages = images.len(), "rolled back journal layers");
+        Some((images, base.alloc))
+    }
+
+    /// Discards the journal, once the explicit transaction ends.
+    pub(crate) fn end_journal(&self) {
+        self.state.lock().unwrap().journal = None;
+    }
+
+    /// Returns the transaction statistics.
+    pub fn stats(&self) -> TxnStats {
+        let state = self.state.lock().unwrap();
+        TxnStats {
+            committed: state.committed,
+            snapshots: state.snapshots.values().sum(),
+            versioned_pages: state.images.len(),
+            captured: self.captured.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A running write transaction. Commits once dropped.
+///
+/// Outside of explicit transactions, statements can't be rolled back, hence a
+/// failed statement commits whatever it already wrote.
+#[derive(Debug)]
+pub struct WriteTxn {
+    id: u64,
+    manager: Arc<TxnManager>,
+}
+
+impl WriteTxn {
+    /// Returns the transaction ID.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for WriteTxn {
+    fn drop(&mut self) {
+        let mut state = self.manager.state.lock().unwrap();
+        state.committed = self.id;
+        state.running = None;
+        state.collect();
+        trace!(id = self.id, "committed write transaction");
+    }
+}
+
+/// A snapshot of the committed write transactions. Released once dropped.
+#[derive(Debug)]
+pub struct Snapshot {
+    id: u64,
+    manager: Arc<TxnManager>,
+}
+
+impl Snapshot {
+    /// Returns the ID of the last transaction seen by the snapshot.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Clone for Snapshot {
+    /// Returns another handle to the snapshot, which is only released once all
+    /// of its handles are dropped.
+    fn clone(&self) -> Snapshot {
+        let mut state = self.manager.state.lock().unwrap();
+        *state.snapshots.entry(self.id).or_default() += 1;
+        Snapshot {
+            id: self.id,
+            manager: Arc::clone(&self.manager),
+        }
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        let mut state = self.manager.state.lock().unwrap();
+        if let Some(count) = state.snapshots.get_mut(&self.id) {
+            *count -= 1;
+            if *count == 0 {
+                state.snapshots.remove(&self.id);
+            }
+        }
+        state.collect();
+    }
+}
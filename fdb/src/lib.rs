@@ -1,5 +1,5 @@
 mod db;
-pub use db::Db;
+pub use db::{Db, DbEnvironment, DbHealth, DbOptions, DbStats, RecoveryState, COMPILED_FEATURES};
 
 pub mod error;
 
@@ -7,7 +7,10 @@ pub mod catalog {
     pub mod page;
 
     pub mod column;
+    pub mod external_schema;
+    pub mod index_schema;
     pub mod object;
+    pub mod statistics;
     pub mod table_schema;
 
     pub mod record;
@@ -17,28 +20,74 @@ pub mod catalog {
 
 pub mod io {
     pub mod disk_manager;
+    pub mod storage;
 
+    pub mod buffer_pool;
     pub mod cache;
+    pub(crate) mod flusher;
+    pub mod group_commit;
+    pub mod metrics;
 
+    pub mod alloc;
     pub mod pager;
+    pub mod scheduler;
+    pub mod segment;
 
     pub mod bootstrap;
+
+    pub mod temp;
+    pub mod txn;
 }
 
 pub mod exec {
     pub mod value;
     pub mod values;
 
+    pub mod expr;
+    pub mod format;
+    pub mod time;
+
     pub mod operations;
 
+    pub mod activity;
+    pub mod auto_vacuum;
+    pub mod catalog_cache;
+    pub mod decode_cache;
+    pub mod integrity;
+    pub mod kv;
+    pub mod locking;
+    pub mod notify;
+    pub mod salvage;
+    pub mod sample;
+    pub mod statistics;
+    pub mod transaction;
+
     pub mod object;
     pub mod query;
 
     pub mod util {
+        pub mod cmp;
         pub mod macros;
     }
 }
 
+pub mod sql {
+    pub mod ast;
+    pub mod lexer;
+    pub mod parser;
+    pub mod planner;
+    pub mod result_cache;
+    pub mod script;
+    pub mod session;
+}
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub mod tools;
+
 pub mod util {
+    pub mod checksum;
     pub mod io;
+    pub mod packing;
 }
@@ -0,0 +1,355 @@
+//! SQL abstract syntax tree.
+
+use std::fmt;
+
+use crate::{
+    catalog::{
+        column::Column,
+        external_schema::ExternalFormat,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    exec::{
+        query::table::IndexHint,
+        time::{Date, Time},
+        value::Value,
+    },
+    sql::lexer::is_keyword,
+};
+
+/// A SQL statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Statement {
+    Select(Select),
+    Insert(Insert),
+    Update(Update),
+    Delete(Delete),
+    CreateTable(CreateTable),
+    CreateIndex(CreateIndex),
+    Transaction(TransactionStatement),
+}
+
+/// A statement which controls the explicit transaction of a session. See
+/// [`Transaction`](crate::exec::transaction::Transaction).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionStatement {
+    /// `BEGIN [TRANSACTION]`.
+    Begin,
+    /// `COMMIT`.
+    Commit,
+    /// `ROLLBACK`.
+    Rollback,
+    /// `SAVEPOINT <name>`.
+    Savepoint(String),
+    /// `ROLLBACK TO [SAVEPOINT] <name>`.
+    RollbackTo(String),
+    /// `RELEASE [SAVEPOINT] <name>`.
+    Release(String),
+}
+
+/// `SELECT <columns> FROM <table> [<hint>] [WHERE <expr>]`.
+///
+/// The hint is either `USE INDEX (<index>)`, which forces the given index to
+/// be used, or `IGNORE INDEX`, which forbids index scans.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Select {
+    /// The projected columns. If `None`, all columns (i.e., `*`) are selected.
+    pub columns: Option<Vec<SelectItem>>,
+    pub table: String,
+    pub hint: Option<IndexHint>,
+    pub filter: Option<Expr>,
+}
+
+/// A projected column, optionally followed by accesses to the elements of
+/// array columns or to the fields of composite columns, e.g., `tags[1]`,
+/// `grid[2][1]` or `address.city`.
+///
+/// The [`Display`](fmt::Display) implementation yields the item as written,
+/// which is also the name of the projected column.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SelectItem {
+    pub column: String,
+    /// The accesses, from the outermost value.
+    pub path: Vec<Accessor>,
+}
+
+/// An access to a part of an array or composite value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Accessor {
+    /// An array element access, e.g., `[1]`. Indices start at 1.
+    Index(i64),
+    /// A composite field access, e.g., `.city`.
+    Field(String),
+}
+
+impl From<&str> for SelectItem {
+    fn from(column: &str) -> SelectItem {
+        SelectItem {
+            column: column.to_owned(),
+            path: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for SelectItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.column)?;
+        self.path.iter().try_for_each(|accessor| match accessor {
+            Accessor::Index(index) => write!(f, "[{index}]"),
+            Accessor::Field(field) => write!(f, ".{field}"),
+        })
+    }
+}
+
+/// `INSERT INTO <table> [(<columns>)] VALUES (<literals>) [, ...]` or
+/// `INSERT INTO <table> [(<columns>)] SELECT ...`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Insert {
+    pub table: String,
+    /// The target columns. If `None`, all columns, in schema order, are used.
+    pub columns: Option<Vec<String>>,
+    pub source: InsertSource,
+}
+
+/// The rows of an `INSERT` statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InsertSource {
+    Values(Vec<Vec<Literal>>),
+    /// The selected columns are assigned, in order, to the target columns.
+    Select(Select),
+}
+
+/// `UPDATE <table> SET <column> = <literal> [, ...] [WHERE <expr>]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Update {
+    pub table: String,
+    pub assignments: Vec<(String, Literal)>,
+    pub filter: Option<Expr>,
+}
+
+/// `DELETE FROM <table> [WHERE <expr>]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delete {
+    pub table: String,
+    pub filter: Option<Expr>,
+}
+
+/// `CREATE TABLE <table> (<column> <type> [, ...])` or
+/// `CREATE EXTERNAL TABLE <table> (<column> <type> [, ...]) FROM '<path>'
+/// FORMAT <format>`.
+///
+/// Column types are written as `<type>`, `<type>(<max_len>)`, `<type>[]` (for
+/// arrays) or `struct(<field> <type> [, ...])` (for composite values), e.g.,
+/// `text(20)`, `int[]` or `struct(city text, zip int)`.
+///
+/// The [`Display`](fmt::Display) implementation yields the canonical statement
+/// text, which parses back to the same statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreateTable {
+    pub table: String,
+    pub columns: Vec<Column>,
+    /// The file path and format, if the table is external.
+    pub external: Option<(String, ExternalFormat)>,
+}
+
+/// `CREATE INDEX <index> ON <table> (<column>)`.
+///
+/// The [`Display`](fmt::Display) implementation yields the canonical statement
+/// text, which parses back to the same statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreateIndex {
+    pub index: String,
+    pub table: String,
+    pub column: String,
+}
+
+/// A SQL expression.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Column(String),
+    Literal(Literal),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+    /// A function call, e.g., `array_contains(tags, 'a')`.
+    Call(String, Vec<Expr>),
+    /// An array element access, e.g., `tags[1]`. Indices start at 1.
+    Index(Box<Expr>, Box<Expr>),
+    /// A composite field access, e.g., `address.city`.
+    Field(Box<Expr>, String),
+}
+
+/// A binary operator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BinOp {
+    And,
+    Or,
+    Eq,
+    Neq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+/// A literal value. Literals are untyped until they are checked against a
+/// schema, e.g., an integer literal may be an `int` or a `bigint`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Literal {
+    Int(i64),
+    /// A decimal literal, e.g., `-1.25`, as written.
+    Decimal(String),
+    Str(String),
+    Bool(bool),
+    Blob(Vec<u8>),
+    /// An array literal, e.g., `[1, 2, 3]`.
+    Array(Vec<Literal>),
+    /// A composite literal, e.g., `{city: 'Lisbon', zip: 1000}`. Omitted
+    /// fields take their default values.
+    Struct(Vec<(String, Literal)>),
+    /// A date literal, e.g., `DATE '2023-03-14'`.
+    Date(Date),
+    /// A time literal, e.g., `TIME '12:30:00'`.
+    Time(Time),
+    /// A timestamp literal, e.g., `TIMESTAMP '2023-03-14T12:30:00Z'`, as
+    /// milliseconds since the Unix epoch.
+    Timestamp(i64),
+}
+
+impl fmt::Display for CreateTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.external {
+            Some(_) => "EXTERNAL TABLE",
+            None => "TABLE",
+        };
+        write!(f, "CREATE {kind} {} (", Ident(&self.table))?;
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", ColumnDef(column))?;
+        }
+        f.write_str(")")?;
+        if let Some((path, format)) = &self.external {
+            let path = path.replace('\'', "''");
+            write!(f, " FROM '{path}' FORMAT {}", format.name())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CreateIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CREATE INDEX {} ON {} ({})",
+            Ident(&self.index),
+            Ident(&self.table),
+            Ident(&self.column)
+        )
+    }
+}
+
+/// Formats a column (or composite field) definition, e.g., `name text(20)`.
+struct ColumnDef<'a>(&'a Column);
+
+impl fmt::Display for ColumnDef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let column = self.0;
+        write!(f, "{} ", Ident(&column.name))?;
+        match column.ty {
+            TypeId::Composite(ty) => {
+                f.write_str("struct(")?;
+                for (i, field) in ty.fields().columns.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", ColumnDef(field))?;
+                }
+                f.write_str(")")?;
+            }
+            ty => {
+                let primitive = ty.primitive().expect("non-composite type");
+                f.write_str(primitive.name())?;
+                if let PrimitiveTypeId::Decimal(scale) = primitive {
+                    write!(f, "({scale})")?;
+                }
+                for _ in 0..ty.dims() {
+                    f.write_str("[]")?;
+                }
+            }
+        }
+        if let Some(max_len) = column.max_len {
+            write!(f, "({max_len})")?;
+        }
+        if column.not_null {
+            f.write_str(" NOT NULL")?;
+        }
+        if let Some(default) = &column.default {
+            write!(f, " DEFAULT {}", LiteralDef(default))?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a value as a literal which is converted back into it when assigned
+/// to a column of the value's type, e.g., `'text'`, `x'CAFE'` or `[1, 2]`.
+struct LiteralDef<'a>(&'a Value);
+
+impl fmt::Display for LiteralDef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elements = match self.0 {
+            Value::Bool(true) => return f.write_str("TRUE"),
+            Value::Bool(false) => return f.write_str("FALSE"),
+            Value::Text(text) => return write!(f, "'{}'", text.replace('\'', "''")),
+            Value::Blob(bytes) => {
+                f.write_str("x'")?;
+                for byte in bytes {
+                    write!(f, "{byte:02X}")?;
+                }
+                return f.write_str("'");
+            }
+            // Strings are parsed as dates and times when assigned to such
+            // columns.
+            Value::Date(_) | Value::Time(_) => return write!(f, "'{}'", self.0),
+            Value::Array(_, elements) | Value::NestedArray(_, _, elements) => elements,
+            Value::Composite(ty, fields) => {
+                f.write_str("{")?;
+                let names = ty.fields().columns.iter().map(|field| &field.name);
+                for (i, (name, value)) in names.zip(fields.as_slice()).enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", Ident(name), LiteralDef(value))?;
+                }
+                return f.write_str("}");
+            }
+            // Numbers (including timestamps, as milliseconds).
+            value => return write!(f, "{value}"),
+        };
+        f.write_str("[")?;
+        for (i, element) in elements.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", LiteralDef(element))?;
+        }
+        f.write_str("]")
+    }
+}
+
+/// Formats an identifier, which is double-quoted if it isn't a plain word or
+/// if it is a keyword.
+struct Ident<'a>(&'a str);
+
+impl fmt::Display for Ident<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chars = self.0.chars();
+        let plain = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+            && !is_keyword(self.0);
+        if plain {
+            f.write_str(self.0)
+        } else {
+            write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! SQL tokenizer.
+
+use std::fmt;
+
+use crate::error::{DbResult, Error};
+
+/// A SQL token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    /// A keyword, always stored in uppercase.
+    Keyword(Keyword),
+    /// An identifier (e.g., a table or column name). Identifiers may be
+    /// double-quoted, e.g., `"select"`, to be used as keywords.
+    Ident(String),
+    /// An integer literal.
+    Int(i64),
+    /// A decimal literal, e.g., `-1.25`, as written.
+    Decimal(String),
+    /// A string literal, e.g., `'hello'`.
+    Str(String),
+    /// A blob literal, e.g., `x'CAFE'`.
+    Blob(Vec<u8>),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Dot,
+    Colon,
+    Semicolon,
+    Star,
+    Eq,
+    Neq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+macro_rules! keywords {
+    ($($variant:ident => $repr:literal,)*) => {
+        /// A SQL keyword.
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        pub enum Keyword {
+            $($variant,)*
+        }
+
+        impl Keyword {
+            /// Returns the keyword for the given (uppercase) word, if any.
+            fn from_upper(word: &str) -> Option<Keyword> {
+                match word {
+                    $($repr => Some(Keyword::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the canonical keyword representation.
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Keyword::$variant => $repr,)*
+                }
+            }
+        }
+    };
+}
+
+keywords! {
+    Select => "SELECT",
+    From => "FROM",
+    Where => "WHERE",
+    Insert => "INSERT",
+    Into => "INTO",
+    Values => "VALUES",
+    Update => "UPDATE",
+    Set => "SET",
+    Delete => "DELETE",
+    Create => "CREATE",
+    And => "AND",
+    Or => "OR",
+    Not => "NOT",
+    True => "TRUE",
+    False => "FALSE",
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Keyword(keyword) => f.write_str(keyword.as_str()),
+            Token::Ident(ident) => write!(f, "identifier `{ident}`"),
+            Token::Int(int) => write!(f, "integer `{int}`"),
+            Token::Decimal(decimal) => write!(f, "decimal `{decimal}`"),
+            Token::Str(str) => write!(f, "string '{str}'"),
+            Token::Blob(bytes) => write!(f, "blob ({} bytes)", bytes.len()),
+            Token::LParen => f.write_str("`(`"),
+            Token::RParen => f.write_str("`)`"),
+            Token::LBracket => f.write_str("`[`"),
+            Token::RBracket => f.write_str("`]`"),
+            Token::LBrace => f.write_str("`{`"),
+            Token::RBrace => f.write_str("`}`"),
+            Token::Comma => f.write_str("`,`"),
+            Token::Dot => f.write_str("`.`"),
+            Token::Colon => f.write_str("`:`"),
+            Token::Semicolon => f.write_str("`;`"),
+            Token::Star => f.write_str("`*`"),
+            Token::Eq => f.write_str("`=`"),
+            Token::Neq => f.write_str("`<>`"),
+            Token::Lt => f.write_str("`<`"),
+            Token::Lte => f.write_str("`<=`"),
+            Token::Gt => f.write_str("`>`"),
+            Token::Gte => f.write_str("`>=`"),
+        }
+    }
+}
+
+/// Splits the given SQL source into tokens.
+pub fn tokenize(src: &str) -> DbResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        // Comments (`-- ...`) run until the end of the line.
+        if src[start..].starts_with("--") {
+            while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            continue;
+        }
+
+        let token = match c {
+            '(' | ')' | '[' | ']' | '{' | '}' | ',' | '.' | ':' | ';' | '*' | '=' => {
+                chars.next();
+                match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    '{' => Token::LBrace,
+                    '}' => Token::RBrace,
+                    ',' => Token::Comma,
+                    '.' => Token::Dot,
+                    ':' => Token::Colon,
+                    ';' => Token::Semicolon,
+                    '*' => Token::Star,
+                    _ => Token::Eq,
+                }
+            }
+            '<' | '>' | '!' => {
+                chars.next();
+                let next = chars.peek().map(|&(_, c)| c);
+                let (token, consumed) = match (c, next) {
+                    ('<', Some('=')) => (Token::Lte, true),
+                    ('<', Some('>')) => (Token::Neq, true),
+                    ('<', _) => (Token::Lt, false),
+                    ('>', Some('=')) => (Token::Gte, true),
+                    ('>', _) => (Token::Gt, false),
+                    ('!', Some('=')) => (Token::Neq, true),
+                    _ => return Err(syntax_error(start, "expected `=` after `!`")),
+                };
+                if consumed {
+                    chars.next();
+                }
+                token
+            }
+            '\'' => {
+                chars.next();
+                Token::Str(read_quoted(&mut chars, start, '\'')?)
+            }
+            '"' => {
+                chars.next();
+                let ident = read_quoted(&mut chars, start, '"')?;
+                if ident.is_empty() {
+                    return Err(syntax_error(start, "empty quoted identifier"));
+                }
+                Token::Ident(ident)
+            }
+            'x' | 'X' if src[start + 1..].starts_with('\'') => {
+                chars.next();
+                chars.next();
+                let hex = read_quoted(&mut chars, start, '\'')?;
+                Token::Blob(decode_hex(&hex).ok_or_else(|| syntax_error(start, "invalid blob"))?)
+            }
+            '-' | '0'..='9' => {
+                chars.next();
+                let mut end = start + c.len_utf8();
+                let mut is_decimal = false;
+                while let Some(&(i, c)) = chars.peek() {
+                    // A single point, followed by a digit, makes a decimal.
+                    let is_point = c == '.'
+                        && !is_decimal
+                        && src[i + 1..].starts_with(|c: char| c.is_ascii_digit());
+                    if !(c.is_ascii_digit() || is_point) {
+                        break;
+                    }
+                    is_decimal |= is_point;
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                // Numbers are never followed by a field access (e.g., `1.2.3`).
+                if chars.peek().is_some_and(|&(_, c)| c == '.') {
+                    return Err(syntax_error(start, "invalid number literal"));
+                }
+                if is_decimal {
+                    Token::Decimal(src[start..end].to_owned())
+                } else {
+                    let int = src[start..end]
+                        .parse()
+                        .map_err(|_| syntax_error(start, "invalid integer literal"))?;
+                    Token::Int(int)
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let word = &src[start..end];
+                match Keyword::from_upper(&word.to_uppercase()) {
+                    Some(keyword) => Token::Keyword(keyword),
+                    None => Token::Ident(word.to_owned()),
+                }
+            }
+            other => {
+                return Err(syntax_error(
+                    start,
+                    &format!("unexpected character `{other}`"),
+                ))
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a sequence delimited by the given quote (whose opening quote was
+/// already consumed). Two consecutive quotes are used to escape a quote.
+fn read_quoted(
+    chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>,
+    start: usize,
+    quote: char,
+) -> DbResult<String> {
+    let mut str = String::new();
+    loop {
+        match chars.next() {
+            Some((_, c)) if c == quote => {
+                if chars.next_if(|&(_, c)| c == quote).is_some() {
+                    str.push(quote);
+                } else {
+                    return Ok(str);
+                }
+            }
+            Some((_, c)) => str.push(c),
+            None => return Err(syntax_error(start, "unterminated quoted literal")),
+        }
+    }
+}
+
+/// Checks whether the given word is a keyword, i.e., whether it must be quoted
+/// to be used as an identifier.
+pub(crate) fn is_keyword(word: &str) -> bool {
+    Keyword::from_upper(&word.to_uppercase()).is_some()
+}
+
+/// Decodes the given hexadecimal string, e.g., `CAFE`.
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [hi, lo] => u8::from_str_radix(std::str::from_utf8(&[*hi, *lo]).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+fn syntax_error(pos: usize, msg: &str) -> Error {
+    Error::Syntax(format!("{msg} (at position {pos})"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("select * FROM t where a >= -3 AND b <> 'it''s' or c != x'CAFE';")
+            .expect("should tokenize");
+        assert_eq!(
+            tokens,
+            [
+                Token::Keyword(Keyword::Select),
+                Token::Star,
+                Token::Keyword(Keyword::From),
+                Token::Ident("t".into()),
+                Token::Keyword(Keyword::Where),
+                Token::Ident("a".into()),
+                Token::Gte,
+                Token::Int(-3),
+                Token::Keyword(Keyword::And),
+                Token::Ident("b".into()),
+                Token::Neq,
+                Token::Str("it's".into()),
+                Token::Keyword(Keyword::Or),
+                Token::Ident("c".into()),
+                Token::Neq,
+                Token::Blob(vec![0xCA, 0xFE]),
+                Token::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_decimals() {
+        let tokens = tokenize("-1.25 <= 3").expect("should tokenize");
+        assert_eq!(
+            tokens,
+            [Token::Decimal("-1.25".into()), Token::Lte, Token::Int(3)]
+        );
+        assert!(tokenize("1.2.3").is_err());
+    }
+
+    #[test]
+    fn test_tokenize_brackets() {
+        let tokens = tokenize("tags[1] = [2]").expect("should tokenize");
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("tags".into()),
+                Token::LBracket,
+                Token::Int(1),
+                Token::RBracket,
+                Token::Eq,
+                Token::LBracket,
+                Token::Int(2),
+                Token::RBracket,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_fields() {
+        let tokens = tokenize("a.b = {b: 1.5}").expect("should tokenize");
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("a".into()),
+                Token::Dot,
+                Token::Ident("b".into()),
+                Token::Eq,
+                Token::LBrace,
+                Token::Ident("b".into()),
+                Token::Colon,
+                Token::Decimal("1.5".into()),
+                Token::RBrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_quoted_idents_and_comments() {
+        let tokens = tokenize("-- a comment\nselect \"from\", \"a \"\"b\"\"\" -- another\n;")
+            .expect("should tokenize");
+        assert_eq!(
+            tokens,
+            [
+                Token::Keyword(Keyword::Select),
+                Token::Ident("from".into()),
+                Token::Comma,
+                Token::Ident("a \"b\"".into()),
+                Token::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert!(tokenize("'unterminated").is_err());
+        assert!(tokenize("a ! b").is_err());
+        assert!(tokenize("x'ABC'").is_err());
+        assert!(tokenize("a # b").is_err());
+        assert!(tokenize("\"\"").is_err());
+        assert!(tokenize("\"unterminated").is_err());
+    }
+}
@@ -0,0 +1,1009 @@
+//! SQL recursive descent parser.
+
+use crate::{
+    catalog::{
+        column::Column,
+        external_schema::ExternalFormat,
+        table_schema::TableSchema,
+        ty::{CompositeType, PrimitiveTypeId, TypeId, MAX_ARRAY_DIMS, MAX_DECIMAL_SCALE},
+    },
+    error::{DbResult, Error},
+    exec::{
+        query::table::IndexHint,
+        time::{self, Date, Time},
+    },
+    sql::{
+        ast::{
+            Accessor, BinOp, CreateIndex, CreateTable, Delete, Expr, Insert, InsertSource, Literal,
+            Select, SelectItem, Statement, TransactionStatement, Update,
+        },
+        lexer::{tokenize, Keyword, Token},
+        planner,
+    },
+};
+
+/// Parses a single SQL statement. A trailing semicolon is optional.
+pub fn parse(src: &str) -> DbResult<Statement> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+    };
+    let statement = parser.statement()?;
+    parser.eat(&Token::Semicolon);
+    if let Some(token) = parser.peek() {
+        return Err(Error::Syntax(format!(
+            "unexpected {token} after end of statement"
+        )));
+    }
+    Ok(statement)
+}
+
+/// Parses a script, i.e., a sequence of SQL statements separated by semicolons.
+/// Empty statements are ignored.
+pub fn parse_script(src: &str) -> DbResult<Vec<Statement>> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+    };
+    let mut statements = Vec::new();
+    loop {
+        while parser.eat(&Token::Semicolon) {}
+        if parser.peek().is_none() {
+            return Ok(statements);
+        }
+        statements.push(parser.statement()?);
+        if let Some(token) = parser.peek() {
+            if *token != Token::Semicolon {
+                return Err(unexpected(token, "`;`"));
+            }
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn statement(&mut self) -> DbResult<Statement> {
+        if let Some(statement) = self.transaction()? {
+            return Ok(Statement::Transaction(statement));
+        }
+        match self.advance()? {
+            Token::Keyword(Keyword::Select) => self.select().map(Statement::Select),
+            Token::Keyword(Keyword::Insert) => self.insert().map(Statement::Insert),
+            Token::Keyword(Keyword::Update) => self.update().map(Statement::Update),
+            Token::Keyword(Keyword::Delete) => self.delete().map(Statement::Delete),
+            Token::Keyword(Keyword::Create) => self.create(),
+            other => Err(unexpected(&other, "a statement")),
+        }
+    }
+
+    /// Parses a transaction statement, if the next token starts one. Their
+    /// words aren't keywords, as in [`Parser::eat_word`].
+    fn transaction(&mut self) -> DbResult<Option<TransactionStatement>> {
+        let statement = if self.eat_word("BEGIN") {
+            self.eat_word("TRANSACTION");
+            TransactionStatement::Begin
+        } else if self.eat_word("COMMIT") {
+            TransactionStatement::Commit
+        } else if self.eat_word("ROLLBACK") {
+            if !self.eat_word("TO") {
+                return Ok(Some(TransactionStatement::Rollback));
+            }
+            self.eat_word("SAVEPOINT");
+            TransactionStatement::RollbackTo(self.ident()?)
+        } else if self.eat_word("SAVEPOINT") {
+            TransactionStatement::Savepoint(self.ident()?)
+        } else if self.eat_word("RELEASE") {
+            self.eat_word("SAVEPOINT");
+            TransactionStatement::Release(self.ident()?)
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(statement))
+    }
+
+    fn create(&mut self) -> DbResult<Statement> {
+        if self.eat_word("INDEX") {
+            let index = self.ident()?;
+            self.expect_word("ON")?;
+            let table = self.ident()?;
+            self.expect(Token::LParen)?;
+            let column = self.ident()?;
+            self.expect(Token::RParen)?;
+            return Ok(Statement::CreateIndex(CreateIndex {
+                index,
+                table,
+                column,
+            }));
+        }
+
+        let external = self.eat_word("EXTERNAL");
+        self.expect_word("TABLE")?;
+        let table = self.ident()?;
+        self.expect(Token::LParen)?;
+        let columns = self.list(Self::column)?;
+        self.expect(Token::RParen)?;
+        let external = if external {
+            self.expect(Token::Keyword(Keyword::From))?;
+            let path = match self.advance()? {
+                Token::Str(path) => path,
+                other => return Err(unexpected(&other, "a file path")),
+            };
+            self.expect_word("FORMAT")?;
+            let name = self.ident()?;
+            let format = ExternalFormat::from_name(&name)
+                .ok_or_else(|| Error::Syntax(format!("unknown external format `{name}`")))?;
+            Some((path, format))
+        } else {
+            None
+        };
+        Ok(Statement::CreateTable(CreateTable {
+            table,
+            columns,
+            external,
+        }))
+    }
+
+    /// Parses a column (or composite field) definition, e.g., `name text(20)`,
+    /// `tags int[]`, `grid int[][]`, `price decimal(2)` or
+    /// `address struct(city text, zip int)`, optionally followed by a
+    /// `NOT NULL` and a `DEFAULT <literal>` (in any order).
+    fn column(&mut self) -> DbResult<Column> {
+        let name = self.ident()?;
+        let ty_name = self.ident()?;
+        let mut ty = if ty_name.eq_ignore_ascii_case("struct") {
+            self.expect(Token::LParen)?;
+            let columns = self.list(Self::column)?;
+            self.expect(Token::RParen)?;
+            TypeId::Composite(CompositeType::new(TableSchema { columns }))
+        } else {
+            TypeId::Primitive(self.primitive_type(&ty_name)?)
+        };
+        while self.eat(&Token::LBracket) {
+            self.expect(Token::RBracket)?;
+            ty = ty.array_of().ok_or_else(|| {
+                Error::Syntax(match ty {
+                    TypeId::Composite(_) => "arrays of structs aren't supported".into(),
+                    _ => format!("arrays may have at most {MAX_ARRAY_DIMS} dimensions"),
+                })
+            })?;
+        }
+        let max_len = if self.eat(&Token::LParen) {
+            let max_len = match self.advance()? {
+                Token::Int(int) => u16::try_from(int)
+                    .map_err(|_| Error::Syntax(format!("invalid maximum length `{int}`")))?,
+                other => return Err(unexpected(&other, "a maximum length")),
+            };
+            self.expect(Token::RParen)?;
+            Some(max_len)
+        } else {
+            None
+        };
+        let (mut default, mut not_null) = (None, false);
+        loop {
+            if self.eat(&Token::Keyword(Keyword::Not)) {
+                self.expect_word("NULL")?;
+                not_null = true;
+            } else if self.eat_word("DEFAULT") {
+                let literal = self.literal()?;
+                default = Some(planner::coerce(literal, ty, &name)?);
+            } else {
+                break;
+            }
+        }
+        Ok(Column {
+            ty,
+            name,
+            max_len,
+            default,
+            not_null,
+        })
+    }
+
+    /// Parses the parameters (if any) of the primitive type of the given name.
+    fn primitive_type(&mut self, name: &str) -> DbResult<PrimitiveTypeId> {
+        let primitive = PrimitiveTypeId::from_name(name)
+            .ok_or_else(|| Error::Syntax(format!("unknown type `{name}`")))?;
+        if let PrimitiveTypeId::Decimal(_) = primitive {
+            self.expect(Token::LParen)?;
+            let scale = match self.advance()? {
+                Token::Int(int) => u8::try_from(int)
+                    .ok()
+                    .filter(|scale| *scale <= MAX_DECIMAL_SCALE)
+                    .ok_or_else(|| Error::Syntax(format!("invalid decimal scale `{int}`")))?,
+                other => return Err(unexpected(&other, "a decimal scale")),
+            };
+            self.expect(Token::RParen)?;
+            return Ok(PrimitiveTypeId::Decimal(scale));
+        }
+        Ok(primitive)
+    }
+
+    fn select(&mut self) -> DbResult<Select> {
+        let columns = if self.eat(&Token::Star) {
+            None
+        } else {
+            Some(self.list(Self::select_item)?)
+        };
+        self.expect(Token::Keyword(Keyword::From))?;
+        let table = self.ident()?;
+        let hint = self.hint()?;
+        let filter = self.filter()?;
+        Ok(Select {
+            columns,
+            table,
+            hint,
+            filter,
+        })
+    }
+
+    /// Parses a projected column, e.g., `name`, `tags[1]` or `address.city`.
+    fn select_item(&mut self) -> DbResult<SelectItem> {
+        let column = self.ident()?;
+        let mut path = Vec::new();
+        loop {
+            if self.eat(&Token::Dot) {
+                path.push(Accessor::Field(self.ident()?));
+            } else if self.eat(&Token::LBracket) {
+                match self.advance()? {
+                    Token::Int(index) => path.push(Accessor::Index(index)),
+                    other => return Err(unexpected(&other, "an integer index")),
+                }
+                self.expect(Token::RBracket)?;
+            } else {
+                return Ok(SelectItem { column, path });
+            }
+        }
+    }
+
+    fn hint(&mut self) -> DbResult<Option<IndexHint>> {
+        if self.eat_word("USE") {
+            self.expect_word("INDEX")?;
+            self.expect(Token::LParen)?;
+            let index = self.ident()?;
+            self.expect(Token::RParen)?;
+            Ok(Some(IndexHint::Use(index)))
+        } else if self.eat_word("IGNORE") {
+            self.expect_word("INDEX")?;
+            Ok(Some(IndexHint::NoIndex))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn insert(&mut self) -> DbResult<Insert> {
+        self.expect(Token::Keyword(Keyword::Into))?;
+        let table = self.ident()?;
+        let columns = if self.eat(&Token::LParen) {
+            let columns = self.list(Self::ident)?;
+            self.expect(Token::RParen)?;
+            Some(columns)
+        } else {
+            None
+        };
+        let source = match self.advance()? {
+            Token::Keyword(Keyword::Values) => InsertSource::Values(self.list(|p| {
+                p.expect(Token::LParen)?;
+                let row = p.list(Self::literal)?;
+                p.expect(Token::RParen)?;
+                Ok(row)
+            })?),
+            Token::Keyword(Keyword::Select) => InsertSource::Select(self.select()?),
+            other => return Err(unexpected(&other, "`VALUES` or `SELECT`")),
+        };
+        Ok(Insert {
+            table,
+            columns,
+            source,
+        })
+    }
+
+    fn update(&mut self) -> DbResult<Update> {
+        let table = self.ident()?;
+        self.expect(Token::Keyword(Keyword::Set))?;
+        let assignments = self.list(|p| {
+            let column = p.ident()?;
+            p.expect(Token::Eq)?;
+            Ok((column, p.literal()?))
+        })?;
+        let filter = self.filter()?;
+        Ok(Update {
+            table,
+            assignments,
+            filter,
+        })
+    }
+
+    fn delete(&mut self) -> DbResult<Delete> {
+        self.expect(Token::Keyword(Keyword::From))?;
+        let table = self.ident()?;
+        let filter = self.filter()?;
+        Ok(Delete { table, filter })
+    }
+
+    /// Parses an optional `WHERE` clause.
+    fn filter(&mut self) -> DbResult<Option<Expr>> {
+        if self.eat(&Token::Keyword(Keyword::Where)) {
+            self.expr().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn expr(&mut self) -> DbResult<Expr> {
+        let mut lhs = self.and_expr()?;
+        while self.eat(&Token::Keyword(Keyword::Or)) {
+            let rhs = self.and_expr()?;
+            lhs = Expr::Binary(Box::new(lhs), BinOp::Or, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn and_expr(&mut self) -> DbResult<Expr> {
+        let mut lhs = self.not_expr()?;
+        while self.eat(&Token::Keyword(Keyword::And)) {
+            let rhs = self.not_expr()?;
+            lhs = Expr::Binary(Box::new(lhs), BinOp::And, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn not_expr(&mut self) -> DbResult<Expr> {
+        if self.eat(&Token::Keyword(Keyword::Not)) {
+            Ok(Expr::Not(Box::new(self.not_expr()?)))
+        } else {
+            self.cmp_expr()
+        }
+    }
+
+    fn cmp_expr(&mut self) -> DbResult<Expr> {
+        let lhs = self.primary()?;
+        let op = match self.peek() {
+            Some(Token::Eq) => BinOp::Eq,
+            Some(Token::Neq) => BinOp::Neq,
+            Some(Token::Lt) => BinOp::Lt,
+            Some(Token::Lte) => BinOp::Lte,
+            Some(Token::Gt) => BinOp::Gt,
+            Some(Token::Gte) => BinOp::Gte,
+            _ => return Ok(lhs),
+        };
+        self.pos += 1;
+        let rhs = self.primary()?;
+        Ok(Expr::Binary(Box::new(lhs), op, Box::new(rhs)))
+    }
+
+    /// Parses a primary expression, followed by any number of element or
+    /// field accesses (e.g., `tags[1]` or `address.city`).
+    fn primary(&mut self) -> DbResult<Expr> {
+        let mut expr = self.atom()?;
+        loop {
+            if self.eat(&Token::Dot) {
+                expr = Expr::Field(Box::new(expr), self.ident()?);
+            } else if self.eat(&Token::LBracket) {
+                let index = self.expr()?;
+                self.expect(Token::RBracket)?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn atom(&mut self) -> DbResult<Expr> {
+        match self.peek() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(_)) if !self.at_typed_literal() => {
+                let ident = self.ident()?;
+                if !self.eat(&Token::LParen) {
+                    return Ok(Expr::Column(ident));
+                }
+                let args = if self.eat(&Token::RParen) {
+                    Vec::new()
+                } else {
+                    let args = self.list(Self::expr)?;
+                    self.expect(Token::RParen)?;
+                    args
+                };
+                Ok(Expr::Call(ident, args))
+            }
+            _ => self.literal().map(Expr::Literal),
+        }
+    }
+
+    fn literal(&mut self) -> DbResult<Literal> {
+        if self.at_typed_literal() {
+            return self.typed_literal();
+        }
+        match self.advance()? {
+            Token::Int(int) => Ok(Literal::Int(int)),
+            Token::Decimal(decimal) => Ok(Literal::Decimal(decimal)),
+            Token::Str(str) => Ok(Literal::Str(str)),
+            Token::Blob(bytes) => Ok(Literal::Blob(bytes)),
+            Token::Keyword(Keyword::True) => Ok(Literal::Bool(true)),
+            Token::Keyword(Keyword::False) => Ok(Literal::Bool(false)),
+            Token::LBracket => {
+                if self.eat(&Token::RBracket) {
+                    return Ok(Literal::Array(Vec::new()));
+                }
+                let elements = self.list(Self::literal)?;
+                self.expect(Token::RBracket)?;
+                Ok(Literal::Array(elements))
+            }
+            Token::LBrace => {
+                if self.eat(&Token::RBrace) {
+                    return Ok(Literal::Struct(Vec::new()));
+                }
+                let fields = self.list(|parser| {
+                    let name = parser.ident()?;
+                    parser.expect(Token::Colon)?;
+                    Ok((name, parser.literal()?))
+                })?;
+                self.expect(Token::RBrace)?;
+                Ok(Literal::Struct(fields))
+            }
+            other => Err(unexpected(&other, "a literal")),
+        }
+    }
+
+    /// Checks whether the next tokens are a typed literal, i.e., a `DATE`,
+    /// `TIME` or `TIMESTAMP` followed by a string. Those words aren't keywords,
+    /// so that they may still be used as identifiers.
+    fn at_typed_literal(&self) -> bool {
+        let (Some(Token::Ident(word)), Some(Token::Str(_))) =
+            (self.peek(), self.tokens.get(self.pos + 1))
+        else {
+            return false;
+        };
+        matches!(
+            PrimitiveTypeId::from_name(word),
+            Some(PrimitiveTypeId::Date | PrimitiveTypeId::Time | PrimitiveTypeId::Timestamp)
+        )
+    }
+
+    /// Parses a typed literal, e.g., `DATE '2023-03-14'`. See
+    /// [`Parser::at_typed_literal`].
+    fn typed_literal(&mut self) -> DbResult<Literal> {
+        let ty = self.ident()?;
+        let Token::Str(text) = self.advance()? else {
+            unreachable!("checked by `at_typed_literal`");
+        };
+        let literal = match PrimitiveTypeId::from_name(&ty) {
+            Some(PrimitiveTypeId::Date) => Date::parse(&text).map(Literal::Date),
+            Some(PrimitiveTypeId::Time) => Time::parse(&text).map(Literal::Time),
+            _ => time::parse_timestamp(&text).map(Literal::Timestamp),
+        };
+        literal
+            .ok_or_else(|| Error::Syntax(format!("invalid {} literal '{text}'", ty.to_lowercase())))
+    }
+
+    fn ident(&mut self) -> DbResult<String> {
+        match self.advance()? {
+            Token::Ident(ident) => Ok(ident),
+            other => Err(unexpected(&other, "an identifier")),
+        }
+    }
+
+    /// Parses a non-empty comma-separated list.
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> DbResult<T>) -> DbResult<Vec<T>> {
+        let mut items = vec![item(self)?];
+        while self.eat(&Token::Comma) {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> DbResult<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| Error::Syntax("unexpected end of input".into()))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// Consumes the next token if it is the given (case-insensitive) word.
+    /// Words which are only meaningful in some statements (e.g., `TABLE`)
+    /// aren't keywords, so that they may still be used as identifiers.
+    fn eat_word(&mut self, word: &str) -> bool {
+        let matches =
+            matches!(self.peek(), Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case(word));
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn expect_word(&mut self, word: &str) -> DbResult<()> {
+        if self.eat_word(word) {
+            return Ok(());
+        }
+        let actual = self.advance()?;
+        Err(unexpected(&actual, &format!("`{word}`")))
+    }
+
+    /// Consumes the next token if it is equal to the given one.
+    fn eat(&mut self, token: &Token) -> bool {
+        let matches = self.peek() == Some(token);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, token: Token) -> DbResult<()> {
+        let actual = self.advance()?;
+        if actual == token {
+            Ok(())
+        } else {
+            Err(unexpected(&actual, &token.to_string()))
+        }
+    }
+}
+
+fn unexpected(token: &Token, expected: &str) -> Error {
+    Error::Syntax(format!("unexpected {token}, expected {expected}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::value::Value;
+
+    fn col(name: &str) -> Box<Expr> {
+        Box::new(Expr::Column(name.into()))
+    }
+
+    fn int(int: i64) -> Box<Expr> {
+        Box::new(Expr::Literal(Literal::Int(int)))
+    }
+
+    #[test]
+    fn test_parse_select() {
+        let statement = parse("SELECT id, name FROM t WHERE NOT id = 1 OR id > 2 AND age <= 3;")
+            .expect("should parse");
+        assert_eq!(
+            statement,
+            Statement::Select(Select {
+                columns: Some(vec!["id".into(), "name".into()]),
+                table: "t".into(),
+                hint: None,
+                filter: Some(Expr::Binary(
+                    Box::new(Expr::Not(Box::new(Expr::Binary(
+                        col("id"),
+                        BinOp::Eq,
+                        int(1)
+                    )))),
+                    BinOp::Or,
+                    Box::new(Expr::Binary(
+                        Box::new(Expr::Binary(col("id"), BinOp::Gt, int(2))),
+                        BinOp::And,
+                        Box::new(Expr::Binary(col("age"), BinOp::Lte, int(3))),
+                    )),
+                )),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_hints() {
+        let statement = parse("SELECT * FROM t USE INDEX (t_id) WHERE id = 1").unwrap();
+        let Statement::Select(select) = statement else {
+            panic!("expected select");
+        };
+        assert_eq!(select.hint, Some(IndexHint::Use("t_id".into())));
+        assert!(select.filter.is_some());
+
+        let statement = parse("select * from t ignore index").unwrap();
+        let Statement::Select(select) = statement else {
+            panic!("expected select");
+        };
+        assert_eq!(select.hint, Some(IndexHint::NoIndex));
+
+        assert!(parse("SELECT * FROM t USE INDEX t_id").is_err());
+        assert!(parse("SELECT * FROM t IGNORE").is_err());
+    }
+
+    #[test]
+    fn test_parse_insert() {
+        let statement =
+            parse("insert into t (a, b) values (1, 'x'), (2, true)").expect("should parse");
+        assert_eq!(
+            statement,
+            Statement::Insert(Insert {
+                table: "t".into(),
+                columns: Some(vec!["a".into(), "b".into()]),
+                source: InsertSource::Values(vec![
+                    vec![Literal::Int(1), Literal::Str("x".into())],
+                    vec![Literal::Int(2), Literal::Bool(true)],
+                ]),
+            })
+        );
+
+        let statement = parse("INSERT INTO t SELECT a FROM u WHERE a = 1").expect("should parse");
+        assert_eq!(
+            statement,
+            Statement::Insert(Insert {
+                table: "t".into(),
+                columns: None,
+                source: InsertSource::Select(Select {
+                    columns: Some(vec!["a".into()]),
+                    table: "u".into(),
+                    hint: None,
+                    filter: Some(Expr::Binary(col("a"), BinOp::Eq, int(1))),
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_update_delete() {
+        let statement = parse("UPDATE t SET a = 1, b = 'y' WHERE (a = 2)").expect("should parse");
+        assert_eq!(
+            statement,
+            Statement::Update(Update {
+                table: "t".into(),
+                assignments: vec![
+                    ("a".into(), Literal::Int(1)),
+                    ("b".into(), Literal::Str("y".into()))
+                ],
+                filter: Some(Expr::Binary(col("a"), BinOp::Eq, int(2))),
+            })
+        );
+
+        let statement = parse("DELETE FROM t").expect("should parse");
+        assert_eq!(
+            statement,
+            Statement::Delete(Delete {
+                table: "t".into(),
+                filter: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_arrays() {
+        let statement =
+            parse("SELECT * FROM t WHERE array_contains(tags, 'a') AND tags[1] = array_length(t)")
+                .expect("should parse");
+        let Statement::Select(Select { filter, .. }) = statement else {
+            panic!("expected select");
+        };
+        assert_eq!(
+            filter,
+            Some(Expr::Binary(
+                Box::new(Expr::Call(
+                    "array_contains".into(),
+                    vec![*col("tags"), Expr::Literal(Literal::Str("a".into()))]
+                )),
+                BinOp::And,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Index(col("tags"), int(1))),
+                    BinOp::Eq,
+                    Box::new(Expr::Call("array_length".into(), vec![*col("t")])),
+                )),
+            ))
+        );
+
+        let statement = parse("INSERT INTO t VALUES ([1, 2], [])").expect("should parse");
+        let Statement::Insert(Insert {
+            source: InsertSource::Values(rows),
+            ..
+        }) = statement
+        else {
+            panic!("expected insert");
+        };
+        assert_eq!(
+            rows,
+            [[
+                Literal::Array(vec![Literal::Int(1), Literal::Int(2)]),
+                Literal::Array(vec![]),
+            ]]
+        );
+
+        let statement = parse("SELECT id, grid[2][1] FROM t").expect("should parse");
+        let Statement::Select(Select {
+            columns: Some(columns),
+            ..
+        }) = statement
+        else {
+            panic!("expected select");
+        };
+        let item = SelectItem {
+            column: "grid".into(),
+            path: vec![Accessor::Index(2), Accessor::Index(1)],
+        };
+        assert_eq!(columns, [SelectItem::from("id"), item.clone()]);
+        assert_eq!(item.to_string(), "grid[2][1]");
+        assert!(parse("SELECT tags['a'] FROM t").is_err());
+        assert!(parse("SELECT tags[1 FROM t").is_err());
+
+        let statement = parse("CREATE TABLE t (grid int[][])").expect("should parse");
+        let Statement::CreateTable(create) = &statement else {
+            panic!("expected create table");
+        };
+        let ty = TypeId::NestedArray(PrimitiveTypeId::Int, 2);
+        assert_eq!(create.columns[0].ty, ty);
+        assert_eq!(create.to_string(), "CREATE TABLE t (grid int[][])");
+        assert!(parse("CREATE TABLE t (a int[][][][][][][])").is_ok());
+        assert!(parse("CREATE TABLE t (a int[][][][][][][][])").is_err());
+    }
+
+    #[test]
+    fn test_parse_structs() {
+        let statement = parse("SELECT id, address.city, a.b[2].c FROM t WHERE address.zip > 1000")
+            .expect("should parse");
+        let Statement::Select(Select {
+            columns: Some(columns),
+            filter,
+            ..
+        }) = statement
+        else {
+            panic!("expected select");
+        };
+        let item = SelectItem {
+            column: "a".into(),
+            path: vec![
+                Accessor::Field("b".into()),
+                Accessor::Index(2),
+                Accessor::Field("c".into()),
+            ],
+        };
+        assert_eq!(columns[2], item);
+        assert_eq!(columns[1].to_string(), "address.city");
+        assert_eq!(item.to_string(), "a.b[2].c");
+        assert_eq!(
+            filter,
+            Some(Expr::Binary(
+                Box::new(Expr::Field(col("address"), "zip".into())),
+                BinOp::Gt,
+                int(1000),
+            ))
+        );
+
+        let statement = parse("INSERT INTO t VALUES ({city: 'Lisbon', geo: {lat: 1.5}}, {})")
+            .expect("should parse");
+        let Statement::Insert(Insert {
+            source: InsertSource::Values(rows),
+            ..
+        }) = statement
+        else {
+            panic!("expected insert");
+        };
+        assert_eq!(
+            rows,
+            [[
+                Literal::Struct(vec![
+                    ("city".into(), Literal::Str("Lisbon".into())),
+                    (
+                        "geo".into(),
+                        Literal::Struct(vec![("lat".into(), Literal::Decimal("1.5".into()))])
+                    ),
+                ]),
+                Literal::Struct(vec![]),
+            ]]
+        );
+
+        let sql = "CREATE TABLE t (id int, address struct(city text(20), geo struct(lat float)))";
+        let statement = parse(sql).expect("should parse");
+        let Statement::CreateTable(create) = &statement else {
+            panic!("expected create table");
+        };
+        let TypeId::Composite(address) = create.columns[1].ty else {
+            panic!("expected a composite type");
+        };
+        assert_eq!(address.fields().columns[0].max_len, Some(20));
+        assert_eq!(create.to_string(), sql);
+
+        for invalid in [
+            "CREATE TABLE t (a struct)",
+            "CREATE TABLE t (a struct())",
+            "CREATE TABLE t (a struct(b int)[])",
+            "INSERT INTO t VALUES ({a 1})",
+            "SELECT a. FROM t",
+            "SELECT * FROM t WHERE a.1 = 1",
+        ] {
+            assert!(parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_parse_typed_literals() {
+        let statement = parse(
+            "INSERT INTO t VALUES (DATE '2023-03-14', time '12:30:00', \
+             TIMESTAMP '2023-03-14T12:00:00Z', '2023-03-14')",
+        )
+        .expect("should parse");
+        let Statement::Insert(Insert {
+            source: InsertSource::Values(rows),
+            ..
+        }) = statement
+        else {
+            panic!("expected insert");
+        };
+        assert_eq!(
+            rows,
+            [[
+                Literal::Date(Date(19_430)),
+                Literal::Time(Time(45_000_000)),
+                Literal::Timestamp(1_678_795_200_000),
+                Literal::Str("2023-03-14".into()),
+            ]]
+        );
+
+        // Without a string, the type names are still identifiers.
+        let statement = parse("SELECT * FROM t WHERE date < DATE '2023-03-14'").unwrap();
+        let Statement::Select(Select { filter, .. }) = statement else {
+            panic!("expected select");
+        };
+        assert_eq!(
+            filter,
+            Some(Expr::Binary(
+                col("date"),
+                BinOp::Lt,
+                Box::new(Expr::Literal(Literal::Date(Date(19_430))))
+            ))
+        );
+
+        assert!(parse("SELECT * FROM t WHERE d = DATE '2023-02-30'").is_err());
+        assert!(parse("SELECT * FROM t WHERE d = TIME '24:00'").is_err());
+    }
+
+    #[test]
+    fn test_parse_create() {
+        let statement =
+            parse("create table t (id int, name text(20), tags bigint[])").expect("should parse");
+        let column = |name: &str, ty, max_len| Column {
+            ty,
+            name: name.into(),
+            max_len,
+            default: None,
+            not_null: false,
+        };
+        let create = CreateTable {
+            table: "t".into(),
+            columns: vec![
+                column("id", TypeId::Primitive(PrimitiveTypeId::Int), None),
+                column("name", TypeId::Primitive(PrimitiveTypeId::Text), Some(20)),
+                column("tags", TypeId::Array(PrimitiveTypeId::BigInt), None),
+            ],
+            external: None,
+        };
+        assert_eq!(statement, Statement::CreateTable(create.clone()));
+        assert_eq!(parse(&create.to_string()).unwrap(), statement);
+
+        let statement =
+            parse("CREATE EXTERNAL TABLE \"select\" (\"a b\" bool) FROM 'it''s.csv' FORMAT csv")
+                .expect("should parse");
+        let Statement::CreateTable(create) = &statement else {
+            panic!("expected create table");
+        };
+        assert_eq!(create.table, "select");
+        assert_eq!(
+            create.external,
+            Some(("it's.csv".into(), ExternalFormat::Csv))
+        );
+        assert_eq!(
+            create.to_string(),
+            "CREATE EXTERNAL TABLE \"select\" (\"a b\" bool) FROM 'it''s.csv' FORMAT csv"
+        );
+        assert_eq!(parse(&create.to_string()).unwrap(), statement);
+
+        let statement = parse("CREATE INDEX i ON t (id)").expect("should parse");
+        assert_eq!(
+            statement,
+            Statement::CreateIndex(CreateIndex {
+                index: "i".into(),
+                table: "t".into(),
+                column: "id".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_create_defaults() {
+        let statement = parse(
+            "CREATE TABLE t (id int NOT NULL, name text(8) DEFAULT 'it''s' NOT NULL, \
+             tags int[] DEFAULT [1, 2], at date DEFAULT '2023-03-14')",
+        )
+        .expect("should parse");
+        let Statement::CreateTable(create) = &statement else {
+            panic!("expected create table");
+        };
+        let attrs: Vec<_> = (create.columns.iter())
+            .map(|column| (column.default.clone(), column.not_null))
+            .collect();
+        assert_eq!(
+            attrs,
+            [
+                (None, true),
+                (Some(Value::Text("it's".into())), true),
+                (
+                    Some(Value::Array(
+                        PrimitiveTypeId::Int,
+                        vec![Value::Int(1), Value::Int(2)]
+                    )),
+                    false
+                ),
+                (Some(Value::Date(Date(19_430))), false),
+            ]
+        );
+        assert_eq!(parse(&create.to_string()).unwrap(), statement);
+
+        // Defaults must be assignable to the column.
+        assert!(parse("CREATE TABLE t (id int DEFAULT 'one')").is_err());
+        assert!(parse("CREATE TABLE t (id int NOT)").is_err());
+    }
+    #[test]
+    fn test_parse_transaction() {
+        let cases = [
+            ("BEGIN", TransactionStatement::Begin),
+            ("begin transaction;", TransactionStatement::Begin),
+            ("COMMIT", TransactionStatement::Commit),
+            ("ROLLBACK", TransactionStatement::Rollback),
+            ("SAVEPOINT a", TransactionStatement::Savepoint("a".into())),
+            (
+                "ROLLBACK TO a",
+                TransactionStatement::RollbackTo("a".into()),
+            ),
+            (
+                "ROLLBACK TO SAVEPOINT a",
+                TransactionStatement::RollbackTo("a".into()),
+            ),
+            (
+                "RELEASE SAVEPOINT a",
+                TransactionStatement::Release("a".into()),
+            ),
+        ];
+        for (sql, expected) in cases {
+            assert_eq!(
+                parse(sql).unwrap(),
+                Statement::Transaction(expected),
+                "{sql}"
+            );
+        }
+
+        // The words aren't reserved.
+        assert!(matches!(
+            parse("SELECT commit FROM savepoint").unwrap(),
+            Statement::Select(_)
+        ));
+        assert!(parse("SAVEPOINT").is_err());
+        assert!(parse("ROLLBACK TO").is_err());
+        assert!(parse("COMMIT a").is_err());
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let statements = parse_script("-- schema\nCREATE TABLE t (a int);;\nDELETE FROM t;")
+            .expect("should parse");
+        assert_eq!(statements.len(), 2);
+        assert!(parse_script("").expect("should parse").is_empty());
+        assert!(parse_script("DELETE FROM t DELETE FROM t").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("SELECT FROM t").is_err());
+        assert!(parse("SELECT * FROM t WHERE").is_err());
+        assert!(parse("SELECT * FROM t; SELECT * FROM t").is_err());
+        assert!(parse("INSERT INTO t VALUES ()").is_err());
+        assert!(parse("INSERT INTO t (a)").is_err());
+        assert!(parse("DROP TABLE t").is_err());
+        assert!(parse("CREATE TABLE t (a double)").is_err());
+        assert!(parse("CREATE TABLE t ()").is_err());
+        assert!(parse("CREATE EXTERNAL TABLE t (a int) FROM 'f' FORMAT xml").is_err());
+    }
+}
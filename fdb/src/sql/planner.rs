@@ -0,0 +1,1249 @@
+//! SQL planner. Translates parsed statements into the table executors.
+
+use std::{cmp::Ordering, collections::HashMap, ops::Bound, sync::Arc, time::UNIX_EPOCH};
+
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::{
+        column::Column,
+        external_schema::ExternalTableSchema,
+        object::{ExternalTableObject, Object, ObjectType, TableObject},
+        table_schema::TableSchema,
+        ty::{CompositeType, PrimitiveTypeId, TypeId, MAX_DECIMAL_SCALE},
+    },
+    error::{DbResult, Error},
+    exec::{
+        activity,
+        expr::{as_i64, cast, compare, is_integer},
+        query::{self, table::IndexHint},
+        sample::{self, PageSample, Rng, RANDOM_MAX},
+        time::{self, Date, Time},
+        transaction,
+        value::{Decimal, Float, Value},
+        values::Values,
+    },
+    sql::{
+        ast::{self, Accessor, BinOp, Expr, InsertSource, Literal, Statement},
+        result_cache::{ResultCache, ResultKey},
+    },
+    Db,
+};
+
+/// The name of the (read-only) system table which exposes the activity
+/// counters of each table.
+pub const TABLE_ACTIVITY: &str = "fdb_table_activity";
+
+/// The name of the (read-only) system table which exposes the statistics of
+/// each index.
+pub const INDEX_STATS: &str = "fdb_index_stats";
+
+/// The result of a SQL statement.
+#[derive(Debug, Clone)]
+pub enum SqlOutput {
+    /// The rows yielded by a `SELECT`, along with the projected column names,
+    /// in the order they were requested.
+    Rows {
+        columns: Vec<String>,
+        rows: Vec<Values>,
+    },
+    /// The number of rows affected by an `INSERT`, `UPDATE` or `DELETE`.
+    Affected(u64),
+    /// The completion of a statement which affects no rows, e.g., a `CREATE`.
+    Done,
+}
+
+/// A prepared statement, i.e., a parsed statement whose referenced objects were
+/// resolved and whose columns, values and filters were validated. See
+/// [`prepare`].
+///
+/// A prepared statement may be executed many times, as long as the catalog
+/// doesn't change in the meantime (see [`Db::catalog_version`]).
+///
+/// The random functions (see [`Prepared::with_seed`]) are evaluated against a
+/// generator which is seeded on each execution.
+#[derive(Debug, Clone)]
+pub struct Prepared {
+    plan: Plan,
+    /// The catalog version under which the objects were resolved.
+    catalog_version: u64,
+    /// The seed of the random functions. If absent, a random seed is used.
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+enum Plan {
+    Select(Box<SelectPlan>),
+    Insert {
+        table: TableObject,
+        rows: InsertRows,
+    },
+    Update {
+        table: TableObject,
+        filter: Filter,
+        assignments: Vec<(String, Value)>,
+    },
+    Delete {
+        table: TableObject,
+        filter: Filter,
+    },
+    CreateTable(ast::CreateTable),
+    CreateIndex(ast::CreateIndex),
+}
+
+#[derive(Debug, Clone)]
+struct SelectPlan {
+    source: SelectSource,
+    /// The schema of the source.
+    schema: TableSchema,
+    filter: Filter,
+    /// The projected columns. See [`projection`].
+    columns: Vec<ast::SelectItem>,
+    /// The statement, if its results may be cached. See [`result_cache`].
+    ///
+    /// [`result_cache`]: crate::sql::result_cache
+    statement: Option<ast::Select>,
+    /// Whether the filter uses random functions.
+    random: bool,
+}
+
+#[derive(Debug, Clone)]
+enum SelectSource {
+    /// The [`TABLE_ACTIVITY`] system table.
+    Activity,
+    /// The [`INDEX_STATS`] system table.
+    IndexStats,
+    External(ExternalTableObject),
+    Table {
+        table: TableObject,
+        /// See [`key_range`].
+        key_range: Option<(String, Bound<Value>, Bound<Value>)>,
+        /// The access path hint, if any. Hinted indexes were checked to be
+        /// usable. See [`check_index_hint`].
+        hint: Option<IndexHint>,
+        /// The threshold of the page sample, if the filter was replaced by a
+        /// page-sampling scan. See [`page_sample`].
+        sample: Option<i64>,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum InsertRows {
+    Values(Vec<Values>),
+    /// The selected columns are assigned, in order, to the given target
+    /// columns.
+    Select(Box<SelectPlan>, Vec<(String, TypeId)>),
+}
+
+/// Prepares the given statement. See [`Prepared`].
+#[instrument(level = "debug", skip_all)]
+pub async fn prepare(db: &Db, statement: Statement) -> DbResult<Prepared> {
+    // The version is read before resolving any object, so that a concurrent
+    // catalog change makes the statement stale.
+    let catalog_version = db.catalog_version();
+    let plan = match statement {
+        Statement::Select(select) => Plan::Select(Box::new(prepare_select(db, select).await?)),
+        Statement::Insert(insert) => prepare_insert(db, insert).await?,
+        Statement::Update(update) => prepare_update(db, update).await?,
+        Statement::Delete(delete) => prepare_delete(db, delete).await?,
+        // The objects are checked by the executors.
+        Statement::CreateTable(create) => Plan::CreateTable(create),
+        Statement::CreateIndex(create) => Plan::CreateIndex(create),
+        Statement::Transaction(_) => {
+            return Err(Error::ExecError(
+                "transaction statements must be executed within a session".into(),
+            ));
+        }
+    };
+    Ok(Prepared {
+        plan,
+        catalog_version,
+        seed: None,
+    })
+}
+
+/// Plans and executes the given statement.
+#[instrument(level = "debug", skip_all)]
+pub async fn execute(db: &Db, statement: Statement) -> DbResult<SqlOutput> {
+    let prepared = prepare(db, statement).await?;
+    // Boxed, as the execution future's (nested) layout would otherwise
+    // overflow the compiler's query depth limit in callers.
+    Box::pin(prepared.execute(db)).await
+}
+
+impl Prepared {
+    /// Returns the catalog version under which the statement was prepared.
+    pub fn catalog_version(&self) -> u64 {
+        self.catalog_version
+    }
+
+    /// Seeds the random functions (`random()` and `random_sample(percent)`),
+    /// so that each execution yields the same values, i.e., so that sampled
+    /// reads are reproducible (over the same data).
+    pub fn with_seed(mut self, seed: u64) -> Prepared {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Checks whether the catalog changed since the statement was prepared,
+    /// in which case it must be prepared again.
+    pub fn is_stale(&self, db: &Db) -> bool {
+        self.catalog_version != db.catalog_version()
+    }
+
+    /// Executes the statement. Fails if the statement is stale (see
+    /// [`Prepared::is_stale`]).
+    #[instrument(level = "debug", skip_all)]
+    pub async fn execute(&self, db: &Db) -> DbResult<SqlOutput> {
+        if self.is_stale(db) {
+            return Err(Error::ExecError(
+                "prepared statement is stale, since the catalog changed".into(),
+            ));
+        }
+        let seed = self.seed.unwrap_or_else(Rng::random_seed);
+        match &self.plan {
+            Plan::Select(select) => {
+                let rows = match self.result_key(db, select) {
+                    Some((cache, key)) => {
+                        if let Some(rows) = cache.get(&key) {
+                            debug!("serving cached result");
+                            Vec::clone(&rows)
+                        } else {
+                            // Read before the rows, so that a concurrent write
+                            // makes the result stale.
+                            let table_version = cache.table_version(&key.select.table);
+                            let rows = execute_select(db, select, seed).await?;
+                            cache.insert(key, table_version, Arc::new(rows.clone()));
+                            rows
+                        }
+                    }
+                    None => execute_select(db, select, seed).await?,
+                };
+                Ok(SqlOutput::Rows {
+                    columns: select.columns.iter().map(|c| c.to_string()).collect(),
+                    rows,
+                })
+            }
+            Plan::Insert { table, rows } => execute_insert(db, table, rows, seed).await,
+            Plan::Update {
+                table,
+                filter,
+                assignments,
+            } => {
+                // The executors take `'static` closures.
+                let pred = filter.clone().into_pred(Rng::new(seed));
+                let assignments = assignments.clone();
+                let updater = move |values: &mut Values| {
+                    for (column, value) in &assignments {
+                        values.set(column.clone(), value.clone());
+                    }
+                };
+                let query = query::table::Update::new(table, &pred, &updater);
+                count(db, query).await
+            }
+            Plan::Delete { table, filter } => {
+                let pred = filter.clone().into_pred(Rng::new(seed));
+                let query = query::table::Delete::new(table, &pred);
+                count(db, query).await
+            }
+            Plan::CreateTable(create) => {
+                let schema = TableSchema {
+                    columns: create.columns.clone(),
+                };
+                match &create.external {
+                    Some((path, format)) => {
+                        let schema = ExternalTableSchema {
+                            path: path.clone(),
+                            format: *format,
+                            schema,
+                        };
+                        let query = query::object::CreateExternalTable::new(&create.table, schema);
+                        count(db, query).await?;
+                    }
+                    None => {
+                        let query = query::object::CreateTable::new(&create.table, schema);
+                        count(db, query).await?;
+                    }
+                }
+                Ok(SqlOutput::Done)
+            }
+            Plan::CreateIndex(create) => {
+                let query = query::index::Create::new(&create.index, &create.table, &create.column);
+                count(db, query).await?;
+                Ok(SqlOutput::Done)
+            }
+        }
+    }
+
+    /// Returns the result cache (if enabled) and the key of the given select's
+    /// result, if it may be cached. Unseeded random functions yield different
+    /// results on each execution, hence their results aren't cached.
+    fn result_key<'db>(
+        &self,
+        db: &'db Db,
+        select: &SelectPlan,
+    ) -> Option<(&'db ResultCache, ResultKey)> {
+        // Within a transaction, the results may hold its uncommitted changes.
+        if transaction::is_active() {
+            return None;
+        }
+        let cache = db.result_cache()?;
+        let statement = select.statement.as_ref()?;
+        let seed = match select.random {
+            true => Some(self.seed?),
+            false => None,
+        };
+        let key = ResultKey {
+            select: statement.clone(),
+            seed,
+            catalog_version: self.catalog_version,
+        };
+        Some((cache, key))
+    }
+}
+
+async fn prepare_select(db: &Db, select: ast::Select) -> DbResult<SelectPlan> {
+    let statement = select.clone();
+    let (source, schema) = match select.table.as_str() {
+        TABLE_ACTIVITY => (SelectSource::Activity, activity_schema()),
+        INDEX_STATS => (SelectSource::IndexStats, index_stats_schema()),
+        name => {
+            debug!(name, "resolving table");
+            let object = Object::find(db, name).await?;
+            if let ObjectType::External(_) = object.ty {
+                let table = object.try_into_external_table()?;
+                let schema = table.schema.schema.clone();
+                (SelectSource::External(table), schema)
+            } else {
+                let table = object.try_into_table()?;
+                let key_range = select
+                    .filter
+                    .as_ref()
+                    .and_then(|filter| key_range(&table.schema, filter));
+                if let Some(IndexHint::Use(index)) = &select.hint {
+                    check_index_hint(db, &table, index, key_range.as_ref()).await?;
+                }
+                let schema = table.schema.clone();
+                let source = SelectSource::Table {
+                    table,
+                    key_range,
+                    hint: select.hint.clone(),
+                    sample: None,
+                };
+                (source, schema)
+            }
+        }
+    };
+    if select.hint.is_some() && !matches!(source, SelectSource::Table { .. }) {
+        return Err(Error::ExecError(format!(
+            "index hints can't be used over `{}`",
+            select.table
+        )));
+    }
+    // The system and external tables may change without any write.
+    let statement = matches!(source, SelectSource::Table { .. }).then_some(statement);
+    let random = select.filter.as_ref().is_some_and(uses_random);
+    let mut plan = SelectPlan {
+        source,
+        filter: Filter::new(&schema, select.filter)?,
+        columns: projection(&schema, select.columns)?,
+        schema,
+        statement,
+        random,
+    };
+    // A sampling filter is replaced by a page-sampling scan, which doesn't
+    // deserialize the records of the pages out of the sample.
+    if let SelectSource::Table {
+        key_range: None,
+        sample,
+        ..
+    } = &mut plan.source
+    {
+        if let Some(threshold) = plan.filter.0.as_ref().and_then(page_sample) {
+            debug!(threshold, "using page-sampling scan");
+            *sample = Some(threshold);
+            plan.filter = Filter(None);
+        }
+    }
+    Ok(plan)
+}
+
+/// Executes the given select, returning the filtered and projected rows. The
+/// random functions are seeded with the given seed.
+async fn execute_select(db: &Db, select: &SelectPlan, seed: u64) -> DbResult<Vec<Values>> {
+    let rng = Rng::new(seed);
+    let mut rows = Vec::new();
+    let push = |row: Values| {
+        if select.filter.matches(&row, &rng) {
+            rows.push(project(&select.columns, &row));
+        }
+        Ok::<_, ()>(())
+    };
+    match &select.source {
+        SelectSource::Activity => activity_rows(db).await?.into_iter().try_for_each(push),
+        SelectSource::IndexStats => index_stats_rows(db).await?.into_iter().try_for_each(push),
+        // External rows are streamed from the file.
+        SelectSource::External(table) => {
+            let query = query::table::ExternalScan::new(table);
+            db.execute(query, push).await?
+        }
+        SelectSource::Table {
+            table,
+            key_range,
+            hint,
+            sample,
+        } => {
+            let mut query = match key_range {
+                Some((column, start, end)) => {
+                    query::table::Select::with_filter(table, column, (start.clone(), end.clone()))
+                }
+                None => query::table::Select::new(table),
+            };
+            query = query.with_hint(hint.clone());
+            if let Some(threshold) = sample {
+                query = query.with_page_sample(PageSample::new(seed, *threshold));
+            }
+            db.execute(query, push).await?
+        }
+    }
+    .unwrap();
+    Ok(rows)
+}
+
+/// Returns the rows of the [`TABLE_ACTIVITY`] system table, which are the
+/// activity counters of each table. See [`activity::tables`].
+async fn activity_rows(db: &Db) -> DbResult<Vec<Values>> {
+    let rows = activity::tables(db)
+        .await?
+        .into_iter()
+        .map(|(name, activity)| {
+            let count = |count: u64| Value::BigInt(count as i64);
+            Values::from(HashMap::from([
+                ("table_name".into(), Value::Text(name)),
+                ("inserts".into(), count(activity.inserts)),
+                ("updates".into(), count(activity.updates)),
+                ("deletes".into(), count(activity.deletes)),
+                ("live_rows".into(), count(activity.live_rows)),
+                ("dead_rows".into(), count(activity.dead_rows)),
+            ]))
+        });
+    Ok(rows.collect())
+}
+
+/// Returns the rows of the [`INDEX_STATS`] system table, which are the
+/// statistics of each index. See [`activity::indexes`].
+///
+/// The `last_used` timestamp is zero for the indexes which weren't used since
+/// the database was opened.
+async fn index_stats_rows(db: &Db) -> DbResult<Vec<Values>> {
+    let rows = activity::indexes(db).await?.into_iter().map(|stats| {
+        let count = |count: u64| Value::BigInt(count as i64);
+        let last_used = stats
+            .usage
+            .last_used
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since_epoch| since_epoch.as_millis() as i64);
+        Values::from(HashMap::from([
+            ("index_name".into(), Value::Text(stats.name)),
+            ("table_name".into(), Value::Text(stats.table)),
+            ("column_name".into(), Value::Text(stats.column)),
+            ("height".into(), count(stats.height.into())),
+            ("page_count".into(), count(stats.page_count.into())),
+            ("entries".into(), count(stats.entries)),
+            ("lookups".into(), count(stats.usage.lookups)),
+            ("last_used".into(), Value::Timestamp(last_used)),
+        ]))
+    });
+    Ok(rows.collect())
+}
+
+/// Returns the schema of the [`TABLE_ACTIVITY`] system table.
+fn activity_schema() -> TableSchema {
+    TableSchema {
+        columns: vec![
+            column("table_name", PrimitiveTypeId::Text),
+            column("inserts", PrimitiveTypeId::BigInt),
+            column("updates", PrimitiveTypeId::BigInt),
+            column("deletes", PrimitiveTypeId::BigInt),
+            column("live_rows", PrimitiveTypeId::BigInt),
+            column("dead_rows", PrimitiveTypeId::BigInt),
+        ],
+    }
+}
+
+/// Returns the schema of the [`INDEX_STATS`] system table.
+fn index_stats_schema() -> TableSchema {
+    TableSchema {
+        columns: vec![
+            column("index_name", PrimitiveTypeId::Text),
+            column("table_name", PrimitiveTypeId::Text),
+            column("column_name", PrimitiveTypeId::Text),
+            column("height", PrimitiveTypeId::BigInt),
+            column("page_count", PrimitiveTypeId::BigInt),
+            column("entries", PrimitiveTypeId::BigInt),
+            column("lookups", PrimitiveTypeId::BigInt),
+            column("last_used", PrimitiveTypeId::Timestamp),
+        ],
+    }
+}
+
+fn column(name: &str, ty: PrimitiveTypeId) -> Column {
+    Column {
+        ty: TypeId::Primitive(ty),
+        name: name.into(),
+        max_len: None,
+        default: None,
+        not_null: false,
+    }
+}
+
+/// Validates the requested columns, defaulting to all of the schema's columns.
+fn projection(
+    schema: &TableSchema,
+    columns: Option<Vec<ast::SelectItem>>,
+) -> DbResult<Vec<ast::SelectItem>> {
+    match columns {
+        Some(columns) => {
+            for column in &columns {
+                item_type(schema, column)?;
+            }
+            Ok(columns)
+        }
+        None => Ok((schema.columns.iter())
+            .map(|c| ast::SelectItem::from(c.name.as_str()))
+            .collect()),
+    }
+}
+
+/// Returns the type of the given projected column, i.e., the type of the
+/// accessed element or field, if any.
+fn item_type(schema: &TableSchema, item: &ast::SelectItem) -> DbResult<TypeId> {
+    let mut ty = column_type(schema, &item.column)?;
+    for accessor in &item.path {
+        ty = match (accessor, ty) {
+            (Accessor::Index(_), ty) => ty.element_type().ok_or_else(|| {
+                Error::ExecError(format!(
+                    "can't index `{item}`, since `{}` values aren't arrays",
+                    ty.name()
+                ))
+            })?,
+            (Accessor::Field(name), TypeId::Composite(composite)) => field_type(composite, name)
+                .ok_or_else(|| {
+                    Error::ExecError(format!("field `{name}` of `{item}` does not exist"))
+                })?,
+            (Accessor::Field(name), ty) => {
+                return Err(Error::ExecError(format!(
+                    "can't access field `{name}` of `{item}`, since `{}` values aren't structs",
+                    ty.name()
+                )))
+            }
+        };
+    }
+    Ok(ty)
+}
+
+/// Projects the given row into the given (validated) columns. Out of bounds
+/// element accesses yield no value.
+fn project(columns: &[ast::SelectItem], row: &Values) -> Values {
+    let mut projected = Values::new();
+    for column in columns {
+        let value = row.get(&column.column).expect("validated column");
+        let accessed = (column.path.iter()).try_fold(value, |value, accessor| match accessor {
+            Accessor::Index(index) => {
+                let index = usize::try_from(index.checked_sub(1)?).ok()?;
+                value.try_cast_array_ref().ok()?.get(index)
+            }
+            Accessor::Field(name) => value.field(name),
+        });
+        if let Some(accessed) = accessed {
+            projected.set(column.to_string(), accessed.clone());
+        }
+    }
+    projected
+}
+
+async fn prepare_insert(db: &Db, insert: ast::Insert) -> DbResult<Plan> {
+    let table = find_table(db, &insert.table).await?;
+    let schema = &table.schema;
+
+    let columns = match insert.columns {
+        Some(columns) => {
+            for (i, column) in columns.iter().enumerate() {
+                column_type(schema, column)?;
+                if columns[..i].contains(column) {
+                    return Err(Error::ExecError(format!(
+                        "column `{column}` specified more than once"
+                    )));
+                }
+            }
+            columns
+        }
+        None => schema.columns.iter().map(|c| c.name.clone()).collect(),
+    };
+
+    let literal_rows = match insert.source {
+        InsertSource::Values(rows) => rows,
+        InsertSource::Select(select) => {
+            let select = prepare_select(db, select).await?;
+            let targets = check_select_targets(schema, &select, columns)?;
+            let rows = InsertRows::Select(Box::new(select), targets);
+            return Ok(Plan::Insert { table, rows });
+        }
+    };
+
+    // All rows are checked before inserting the first one.
+    let mut rows = Vec::with_capacity(literal_rows.len());
+    for row in literal_rows {
+        if row.len() != columns.len() {
+            return Err(Error::ExecError(format!(
+                "expected {} values, but got {}",
+                columns.len(),
+                row.len()
+            )));
+        }
+        let mut values = Values::new();
+        for (column, literal) in columns.iter().zip(row) {
+            let value = coerce(literal, column_type(schema, column)?, column)?;
+            values.set(column.clone(), value);
+        }
+        rows.push(values);
+    }
+    let rows = InsertRows::Values(rows);
+    Ok(Plan::Insert { table, rows })
+}
+
+/// Checks the target columns of an `INSERT INTO ... SELECT` statement, which
+/// are assigned the selected columns at the same positions, returning them
+/// along with their types.
+///
+/// The types must match, except for integers, which may be converted to other
+/// integer types (see [`convert`]).
+fn check_select_targets(
+    schema: &TableSchema,
+    select: &SelectPlan,
+    columns: Vec<String>,
+) -> DbResult<Vec<(String, TypeId)>> {
+    if select.columns.len() != columns.len() {
+        return Err(Error::ExecError(format!(
+            "expected {} columns, but got {}",
+            columns.len(),
+            select.columns.len()
+        )));
+    }
+    columns
+        .into_iter()
+        .zip(&select.columns)
+        .map(|(column, source)| {
+            let ty = column_type(schema, &column)?;
+            let source_ty = item_type(&select.schema, source)?;
+            let compatible = match (source_ty, ty) {
+                (TypeId::Primitive(a), TypeId::Primitive(b)) => {
+                    a == b || (is_integer(a) && is_integer(b))
+                }
+                (a, b) => a == b,
+            };
+            if !compatible {
+                return Err(Error::Cast(format!(
+                    "can't assign `{}` value to column `{column}` of type `{}`",
+                    source_ty.name(),
+                    ty.name()
+                )));
+            }
+            Ok((column, ty))
+        })
+        .collect()
+}
+
+async fn execute_insert(
+    db: &Db,
+    table: &TableObject,
+    rows: &InsertRows,
+    seed: u64,
+) -> DbResult<SqlOutput> {
+    let rows = match rows {
+        InsertRows::Values(rows) => rows.clone(),
+        // The selection is fully executed before the insertion, so a table may
+        // be inserted into itself.
+        InsertRows::Select(select, targets) => {
+            let rows = execute_select(db, select, seed).await?;
+            rows.into_iter()
+                .map(|row| {
+                    let mut values = Values::new();
+                    for ((column, ty), source) in targets.iter().zip(&select.columns) {
+                        let value = row.get(&source.to_string()).ok_or_else(|| {
+                            Error::ExecError(format!("`{source}` is out of bounds"))
+                        })?;
+                        values.set(column.clone(), convert(value, *ty, column)?);
+                    }
+                    Ok(values)
+                })
+                .collect::<DbResult<_>>()?
+        }
+    };
+    let count = rows.len() as u64;
+    let query = query::table::BulkInsert::new(table, rows);
+    db.execute(query, |()| Ok::<_, ()>(())).await?.unwrap();
+    Ok(SqlOutput::Affected(count))
+}
+
+/// Converts the given selected value into a value of the given type. Integers
+/// may be converted between integer types, as long as they are in range.
+fn convert(value: &Value, ty: TypeId, column: &str) -> DbResult<Value> {
+    cast(value, ty).ok_or_else(|| {
+        Error::Cast(format!(
+            "can't assign `{}` value to column `{column}` of type `{}`",
+            value.type_id().name(),
+            ty.name()
+        ))
+    })
+}
+
+async fn prepare_update(db: &Db, update: ast::Update) -> DbResult<Plan> {
+    let table = find_table(db, &update.table).await?;
+    let filter = Filter::new(&table.schema, update.filter)?;
+
+    let assignments = update
+        .assignments
+        .into_iter()
+        .map(|(column, literal)| {
+            let value = coerce(literal, column_type(&table.schema, &column)?, &column)?;
+            Ok((column, value))
+        })
+        .collect::<DbResult<Vec<_>>>()?;
+    Ok(Plan::Update {
+        table,
+        filter,
+        assignments,
+    })
+}
+
+async fn prepare_delete(db: &Db, delete: ast::Delete) -> DbResult<Plan> {
+    let table = find_table(db, &delete.table).await?;
+    let filter = Filter::new(&table.schema, delete.filter)?;
+    Ok(Plan::Delete { table, filter })
+}
+
+/// Executes the given query, counting its yielded items.
+async fn count<Q>(db: &Db, query: Q) -> DbResult<SqlOutput>
+where
+    Q: for<'a> query::Query<Item<'a> = ()>,
+{
+    let mut count = 0;
+    db.execute(query, |()| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(SqlOutput::Affected(count))
+}
+
+async fn find_table(db: &Db, name: &str) -> DbResult<TableObject> {
+    debug!(name, "resolving table");
+    Object::find(db, name).await?.try_into_table()
+}
+
+fn column_type(schema: &TableSchema, name: &str) -> DbResult<TypeId> {
+    schema
+        .columns
+        .iter()
+        .find(|column| column.name == name)
+        .map(|column| column.ty)
+        .ok_or_else(|| Error::ExecError(format!("column `{name}` does not exist")))
+}
+
+/// Returns the type of the given field of a composite type, if any.
+fn field_type(ty: CompositeType, name: &str) -> Option<TypeId> {
+    (ty.fields().columns.iter())
+        .find(|field| field.name == name)
+        .map(|field| field.ty)
+}
+
+/// Converts the given literal into a value of the given type.
+pub(crate) fn coerce(literal: Literal, ty: TypeId, column: &str) -> DbResult<Value> {
+    match (literal, ty) {
+        // Fields are named as `<column>.<field>` in the error messages.
+        (Literal::Struct(fields), TypeId::Composite(ty)) => {
+            let mut values = Values::new();
+            for (name, literal) in fields {
+                let path = format!("{column}.{name}");
+                let field_ty = field_type(ty, &name)
+                    .ok_or_else(|| Error::ExecError(format!("field `{path}` does not exist")))?;
+                if values.get(&name).is_some() {
+                    return Err(Error::ExecError(format!(
+                        "field `{path}` specified more than once"
+                    )));
+                }
+                values.set(name, coerce(literal, field_ty, &path)?);
+            }
+            Ok(Value::Composite(
+                ty,
+                values.try_into_schematized(ty.fields())?,
+            ))
+        }
+        (Literal::Array(elements), ty) if ty.dims() > 0 => {
+            let element_type = ty.element_type().expect("array types have an element type");
+            let elements = elements
+                .into_iter()
+                .map(|element| coerce(element, element_type, column))
+                .collect::<DbResult<_>>()?;
+            Ok(Value::array(ty, elements))
+        }
+        (literal, TypeId::Primitive(primitive)) => coerce_primitive(literal, primitive, column),
+        (literal, ty) => Err(Error::Cast(format!(
+            "can't assign {literal:?} to column `{column}` of type `{}`",
+            ty.name()
+        ))),
+    }
+}
+
+/// Converts the given literal into a value of the given primitive type.
+fn coerce_primitive(literal: Literal, primitive: PrimitiveTypeId, column: &str) -> DbResult<Value> {
+    let out_of_range = || Error::Cast(format!("integer out of range for column `{column}`"));
+    let value = match (literal, primitive) {
+        (Literal::Bool(bool), PrimitiveTypeId::Bool) => Value::Bool(bool),
+        (Literal::Int(int), PrimitiveTypeId::Byte) => {
+            Value::Byte(int.try_into().map_err(|_| out_of_range())?)
+        }
+        (Literal::Int(int), PrimitiveTypeId::ShortInt) => {
+            Value::ShortInt(int.try_into().map_err(|_| out_of_range())?)
+        }
+        (Literal::Int(int), PrimitiveTypeId::Int) => {
+            Value::Int(int.try_into().map_err(|_| out_of_range())?)
+        }
+        (Literal::Int(int), PrimitiveTypeId::BigInt) => Value::BigInt(int),
+        (Literal::Int(int), PrimitiveTypeId::Timestamp) => Value::Timestamp(int),
+        (Literal::Int(int), PrimitiveTypeId::Float) => Value::Float(Float(int as f64)),
+        (Literal::Decimal(text), PrimitiveTypeId::Float) => {
+            Value::Float(Float(text.parse().expect("lexed decimal")))
+        }
+        (Literal::Int(int), PrimitiveTypeId::Decimal(scale)) => {
+            let decimal = Decimal {
+                unscaled: int,
+                scale: 0,
+            };
+            Value::Decimal(decimal.rescale(scale).ok_or_else(out_of_range)?)
+        }
+        (Literal::Decimal(text), PrimitiveTypeId::Decimal(scale)) => {
+            Value::Decimal(Decimal::parse(&text, scale).ok_or_else(|| {
+                Error::Cast(format!(
+                    "can't assign {text} to column `{column}` of type `decimal({scale})`"
+                ))
+            })?)
+        }
+        (Literal::Str(str), PrimitiveTypeId::Text) => Value::Text(str),
+        (Literal::Blob(bytes), PrimitiveTypeId::Blob) => Value::Blob(bytes),
+        (Literal::Date(date), PrimitiveTypeId::Date) => Value::Date(date),
+        (Literal::Time(time), PrimitiveTypeId::Time) => Value::Time(time),
+        (Literal::Timestamp(millis), PrimitiveTypeId::Timestamp) => Value::Timestamp(millis),
+        // Strings are parsed as dates, times and timestamps when assigned to
+        // such columns, e.g., `INSERT INTO t VALUES ('2023-03-14')`.
+        (
+            Literal::Str(str),
+            PrimitiveTypeId::Date | PrimitiveTypeId::Time | PrimitiveTypeId::Timestamp,
+        ) => {
+            let value = match primitive {
+                PrimitiveTypeId::Date => Date::parse(&str).map(Value::Date),
+                PrimitiveTypeId::Time => Time::parse(&str).map(Value::Time),
+                _ => time::parse_timestamp(&str).map(Value::Timestamp),
+            };
+            value.ok_or_else(|| {
+                Error::Cast(format!(
+                    "can't assign '{str}' to column `{column}` of type `{}`",
+                    primitive.name()
+                ))
+            })?
+        }
+        (literal, _) => {
+            return Err(Error::Cast(format!(
+                "can't assign {literal:?} to column `{column}` of type `{}`",
+                primitive.name()
+            )))
+        }
+    };
+    Ok(value)
+}
+
+/// Converts the given decimal literal into an (exact) decimal value, whose
+/// scale is its number of fractional digits. Literals with too many fractional
+/// digits (or out of range) are approximated by a float.
+fn decimal_literal(text: &str) -> Value {
+    let scale = text
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len());
+    u8::try_from(scale)
+        .ok()
+        .filter(|scale| *scale <= MAX_DECIMAL_SCALE)
+        .and_then(|scale| Decimal::parse(text, scale))
+        .map_or_else(
+            || Value::Float(Float(text.parse().expect("lexed decimal"))),
+            Value::Decimal,
+        )
+}
+
+/// The type "kind" of an expression, used to type-check filters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    /// Any numeric type (i.e., integers, decimals and floats), since they're
+    /// comparable with each other (see [`compare`]).
+    Int,
+    Text,
+    Blob,
+    Date,
+    Time,
+    /// An array, of the given array type (possibly, an array of arrays).
+    Array(TypeId),
+    Struct(CompositeType),
+}
+
+impl Kind {
+    fn of(primitive: PrimitiveTypeId) -> Kind {
+        match primitive {
+            PrimitiveTypeId::Bool => Kind::Bool,
+            PrimitiveTypeId::Text => Kind::Text,
+            PrimitiveTypeId::Blob => Kind::Blob,
+            PrimitiveTypeId::Date => Kind::Date,
+            PrimitiveTypeId::Time => Kind::Time,
+            _ => Kind::Int,
+        }
+    }
+
+    fn of_type(ty: TypeId) -> Kind {
+        match ty {
+            TypeId::Primitive(primitive) => Kind::of(primitive),
+            TypeId::Composite(composite) => Kind::Struct(composite),
+            array => Kind::Array(array),
+        }
+    }
+}
+
+/// A type-checked filter. Since the filter is checked up front, its evaluation
+/// can't fail.
+#[derive(Debug, Clone)]
+struct Filter(Option<Expr>);
+
+impl Filter {
+    /// Type-checks the given filter against the schema.
+    fn new(schema: &TableSchema, filter: Option<Expr>) -> DbResult<Filter> {
+        if let Some(filter) = &filter {
+            if check(schema, filter)? != Kind::Bool {
+                return Err(Error::ExecError(
+                    "filter must be a boolean expression".into(),
+                ));
+            }
+        }
+        Ok(Filter(filter))
+    }
+
+    /// Checks whether the given values match the filter, drawing the random
+    /// values from the given generator. All values match an absent filter.
+    fn matches(&self, values: &Values, rng: &Rng) -> bool {
+        match &self.0 {
+            Some(filter) => eval(filter, values, rng) == Some(Value::Bool(true)),
+            None => true,
+        }
+    }
+
+    /// Converts the filter into a predicate closure, which owns the given
+    /// generator.
+    fn into_pred(self, rng: Rng) -> impl Fn(&Values) -> bool + Sync {
+        move |values: &Values| self.matches(values, &rng)
+    }
+}
+
+/// Checks whether the given index may be used to look the records of the given
+/// table up, i.e., whether it is defined over the table and over the column of
+/// the key range.
+async fn check_index_hint(
+    db: &Db,
+    table: &TableObject,
+    name: &str,
+    key_range: Option<&(String, Bound<Value>, Bound<Value>)>,
+) -> DbResult<()> {
+    let index = Object::find(db, name).await?.try_into_index()?;
+    if index.schema.table != table.name {
+        return Err(Error::ExecError(format!(
+            "index `{name}` is not defined over table `{}`",
+            table.name
+        )));
+    }
+    let column = &index.schema.column;
+    if key_range.is_none_or(|(key_column, ..)| key_column != column) {
+        return Err(Error::ExecError(format!(
+            "index `{name}` can't be used, since there is no key range over column `{column}`"
+        )));
+    }
+    Ok(())
+}
+
+/// Extracts the key range of a filter in the form `column <op> literal` (or
+/// `literal <op> column`), which may be used to look the records up through an
+/// index. The filter must still be applied to the yielded records.
+fn key_range(schema: &TableSchema, filter: &Expr) -> Option<(String, Bound<Value>, Bound<Value>)> {
+    let Expr::Binary(lhs, op, rhs) = filter else {
+        return None;
+    };
+    let (column, op, literal) = match (&**lhs, &**rhs) {
+        (Expr::Column(column), Expr::Literal(literal)) => (column, *op, literal),
+        (Expr::Literal(literal), Expr::Column(column)) => {
+            let op = match op {
+                BinOp::Lt => BinOp::Gt,
+                BinOp::Lte => BinOp::Gte,
+                BinOp::Gt => BinOp::Lt,
+                BinOp::Gte => BinOp::Lte,
+                op => *op,
+            };
+            (column, op, literal)
+        }
+        _ => return None,
+    };
+    let ty = column_type(schema, column).ok()?;
+    let value = coerce(literal.clone(), ty, column).ok()?;
+    let (start, end) = match op {
+        BinOp::Eq => (Bound::Included(value.clone()), Bound::Included(value)),
+        BinOp::Lt => (Bound::Unbounded, Bound::Excluded(value)),
+        BinOp::Lte => (Bound::Unbounded, Bound::Included(value)),
+        BinOp::Gt => (Bound::Excluded(value), Bound::Unbounded),
+        BinOp::Gte => (Bound::Included(value), Bound::Unbounded),
+        BinOp::Neq | BinOp::And | BinOp::Or => return None,
+    };
+    Some((column.clone(), start, end))
+}
+
+/// Extracts the sampling threshold (see [`sample::threshold`]) of a filter in
+/// the form `random_sample(percent)` or `random() < literal` (or `<=`, or the
+/// flipped forms), which may be replaced by a page sample. Page sampling
+/// selects the same fraction of the records on average, but in whole pages.
+fn page_sample(filter: &Expr) -> Option<i64> {
+    let is_random = |expr: &Expr| {
+        matches!(expr, Expr::Call(name, args) if args.is_empty()
+            && Function::resolve(name, 0).ok() == Some(Function::Random))
+    };
+    let threshold = match filter {
+        Expr::Call(name, args) => match (Function::resolve(name, 1).ok()?, args.as_slice()) {
+            (Function::RandomSample, [Expr::Literal(Literal::Int(percent))]) => {
+                sample::threshold(*percent)
+            }
+            _ => return None,
+        },
+        Expr::Binary(lhs, op, rhs) => {
+            let (op, literal) = match (&**lhs, &**rhs) {
+                (random, Expr::Literal(Literal::Int(int))) if is_random(random) => (*op, *int),
+                (Expr::Literal(Literal::Int(int)), random) if is_random(random) => match op {
+                    BinOp::Gt => (BinOp::Lt, *int),
+                    BinOp::Gte => (BinOp::Lte, *int),
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            match op {
+                BinOp::Lt => literal,
+                BinOp::Lte => literal.saturating_add(1),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(threshold.clamp(0, RANDOM_MAX + 1))
+}
+
+/// Checks whether the given expression calls any random function.
+fn uses_random(expr: &Expr) -> bool {
+    match expr {
+        Expr::Column(_) | Expr::Literal(_) => false,
+        Expr::Not(inner) | Expr::Field(inner, _) => uses_random(inner),
+        Expr::Binary(lhs, _, rhs) | Expr::Index(lhs, rhs) => uses_random(lhs) || uses_random(rhs),
+        Expr::Call(name, args) => {
+            matches!(
+                Function::resolve(name, args.len()),
+                Ok(Function::Random | Function::RandomSample)
+            ) || args.iter().any(uses_random)
+        }
+    }
+}
+
+fn check(schema: &TableSchema, expr: &Expr) -> DbResult<Kind> {
+    match expr {
+        Expr::Column(name) => Ok(Kind::of_type(column_type(schema, name)?)),
+        Expr::Literal(literal) => Ok(match literal {
+            Literal::Int(_) | Literal::Decimal(_) | Literal::Timestamp(_) => Kind::Int,
+            Literal::Str(_) => Kind::Text,
+            Literal::Bool(_) => Kind::Bool,
+            Literal::Blob(_) => Kind::Blob,
+            Literal::Date(_) => Kind::Date,
+            Literal::Time(_) => Kind::Time,
+            Literal::Array(_) => {
+                return Err(Error::ExecError(
+                    "array literals may only be assigned to columns".into(),
+                ))
+            }
+            Literal::Struct(_) => {
+                return Err(Error::ExecError(
+                    "struct literals may only be assigned to columns".into(),
+                ))
+            }
+        }),
+        Expr::Not(inner) => match check(schema, inner)? {
+            Kind::Bool => Ok(Kind::Bool),
+            other => Err(Error::ExecError(format!("can't negate {other:?}"))),
+        },
+        Expr::Binary(lhs, op, rhs) => {
+            let (lhs, rhs) = (check(schema, lhs)?, check(schema, rhs)?);
+            let ok = match op {
+                BinOp::And | BinOp::Or => lhs == Kind::Bool && rhs == Kind::Bool,
+                _ => lhs == rhs && !matches!(lhs, Kind::Array(_) | Kind::Struct(_)),
+            };
+            if ok {
+                Ok(Kind::Bool)
+            } else {
+                Err(Error::ExecError(format!(
+                    "invalid operand types for {op:?}: {lhs:?} and {rhs:?}"
+                )))
+            }
+        }
+        Expr::Call(name, args) => {
+            let function = Function::resolve(name, args.len())?;
+            let kinds = args
+                .iter()
+                .map(|arg| check(schema, arg))
+                .collect::<DbResult<Vec<_>>>()?;
+            match (function, kinds.as_slice()) {
+                (Function::ArrayContains, [Kind::Array(ty), element])
+                    if ty.element_type().map(Kind::of_type) == Some(*element) =>
+                {
+                    Ok(Kind::Bool)
+                }
+                (Function::ArrayLength, [Kind::Array(_)]) => Ok(Kind::Int),
+                (Function::Random, []) => Ok(Kind::Int),
+                (Function::RandomSample, [Kind::Int]) => Ok(Kind::Bool),
+                _ => Err(Error::ExecError(format!(
+                    "invalid argument types for `{name}`: {kinds:?}"
+                ))),
+            }
+        }
+        Expr::Index(array, index) => match (check(schema, array)?, check(schema, index)?) {
+            (Kind::Array(ty), Kind::Int) => Ok(Kind::of_type(
+                ty.element_type().expect("array types have an element type"),
+            )),
+            (array, index) => Err(Error::ExecError(format!(
+                "can't index {array:?} with {index:?}"
+            ))),
+        },
+        Expr::Field(inner, name) => match check(schema, inner)? {
+            Kind::Struct(ty) => field_type(ty, name)
+                .map(Kind::of_type)
+                .ok_or_else(|| Error::ExecError(format!("field `{name}` does not exist"))),
+            other => Err(Error::ExecError(format!(
+                "can't access field `{name}` of {other:?}"
+            ))),
+        },
+    }
+}
+
+/// A built-in function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Function {
+    /// `array_contains(array, element)`, which checks whether the array
+    /// contains the given element.
+    ArrayContains,
+    /// `array_length(array)`, which returns the number of elements of the
+    /// array.
+    ArrayLength,
+    /// `random()`, which returns a random integer within `0..=RANDOM_MAX`.
+    /// See [`Rng::next_value`].
+    Random,
+    /// `random_sample(percent)`, which is true with the probability of the
+    /// given percentage (there's no fractional type yet), e.g., true for about
+    /// one in ten rows with `random_sample(10)`.
+    RandomSample,
+}
+
+impl Function {
+    /// Resolves the function of the given (case-insensitive) name, checking
+    /// its arity.
+    fn resolve(name: &str, arity: usize) -> DbResult<Function> {
+        let (function, expected) = match name.to_lowercase().as_str() {
+            "array_contains" => (Function::ArrayContains, 2),
+            "array_length" => (Function::ArrayLength, 1),
+            "random" => (Function::Random, 0),
+            "random_sample" => (Function::RandomSample, 1),
+            _ => return Err(Error::ExecError(format!("unknown function `{name}`"))),
+        };
+        if arity != expected {
+            return Err(Error::ExecError(format!(
+                "function `{name}` expects {expected} arguments, but got {arity}"
+            )));
+        }
+        Ok(function)
+    }
+}
+
+/// Evaluates a type-checked expression, drawing the random values from the
+/// given generator.
+fn eval(expr: &Expr, values: &Values, rng: &Rng) -> Option<Value> {
+    Some(match expr {
+        Expr::Column(name) => values.get(name)?.clone(),
+        Expr::Literal(literal) => match literal {
+            Literal::Int(int) => Value::BigInt(*int),
+            Literal::Decimal(text) => decimal_literal(text),
+            Literal::Str(str) => Value::Text(str.clone()),
+            Literal::Bool(bool) => Value::Bool(*bool),
+            Literal::Blob(bytes) => Value::Blob(bytes.clone()),
+            Literal::Date(date) => Value::Date(*date),
+            Literal::Time(time) => Value::Time(*time),
+            Literal::Timestamp(millis) => Value::Timestamp(*millis),
+            Literal::Array(_) | Literal::Struct(_) => unreachable!("rejected by the type checker"),
+        },
+        Expr::Not(inner) => Value::Bool(!*eval(inner, values, rng)?.try_cast_bool_ref().ok()?),
+        Expr::Binary(lhs, BinOp::And, rhs) => Value::Bool(
+            *eval(lhs, values, rng)?.try_cast_bool_ref().ok()?
+                && *eval(rhs, values, rng)?.try_cast_bool_ref().ok()?,
+        ),
+        Expr::Binary(lhs, BinOp::Or, rhs) => Value::Bool(
+            *eval(lhs, values, rng)?.try_cast_bool_ref().ok()?
+                || *eval(rhs, values, rng)?.try_cast_bool_ref().ok()?,
+        ),
+        Expr::Binary(lhs, op, rhs) => {
+            let ord = compare(&eval(lhs, values, rng)?, &eval(rhs, values, rng)?)?;
+            Value::Bool(match op {
+                BinOp::Eq => ord == Ordering::Equal,
+                BinOp::Neq => ord != Ordering::Equal,
+                BinOp::Lt => ord == Ordering::Less,
+                BinOp::Lte => ord != Ordering::Greater,
+                BinOp::Gt => ord == Ordering::Greater,
+                BinOp::Gte => ord != Ordering::Less,
+                BinOp::And | BinOp::Or => unreachable!(),
+            })
+        }
+        Expr::Call(name, args) => {
+            let function = Function::resolve(name, args.len()).ok()?;
+            match function {
+                Function::ArrayContains => {
+                    let array = eval(&args[0], values, rng)?;
+                    let needle = eval(&args[1], values, rng)?;
+                    let found = (array.try_cast_array_ref().ok()?.iter())
+                        .any(|element| compare(element, &needle) == Some(Ordering::Equal));
+                    Value::Bool(found)
+                }
+                Function::ArrayLength => {
+                    let array = eval(&args[0], values, rng)?;
+                    Value::BigInt(array.try_cast_array_ref().ok()?.len() as i64)
+                }
+                Function::Random => Value::BigInt(rng.next_value()),
+                Function::RandomSample => {
+                    let percent = as_i64(&eval(&args[0], values, rng)?)?;
+                    Value::Bool(rng.sample(percent))
+                }
+            }
+        }
+        // Out of bounds accesses yield no value (thus, a false filter).
+        Expr::Index(array, index) => {
+            let index = as_i64(&eval(index, values, rng)?)?;
+            let index = usize::try_from(index.checked_sub(1)?).ok()?;
+            let array = eval(array, values, rng)?;
+            array.try_cast_array_ref().ok()?.get(index)?.clone()
+        }
+        Expr::Field(inner, name) => eval(inner, values, rng)?.field(name)?.clone(),
+    })
+}
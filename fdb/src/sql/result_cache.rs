@@ -0,0 +1,130 @@
+//! Query result cache.
+//!
+//! The rows yielded by SQL `SELECT` statements may be kept in the database's
+//! [`ResultCache`], so that repeated read-only statements (e.g., the lookups of
+//! a read-heavy application) are served without reading any page.
+//!
+//! The results are keyed by the normalized statement (i.e., its syntax tree,
+//! hence differences in whitespace, comments or keyword case don't matter), the
+//! seed of its random functions (see [`Prepared::with_seed`]) and the catalog
+//! version (see [`Db::catalog_version`]). Each cached result is also tagged
+//! with the write version of its table at the time it was read. Any write to
+//! the table (i.e., an insert, update or delete) bumps its version, hence
+//! invalidating all of its cached results.
+//!
+//! Only the selects of regular tables are cached, since the system tables and
+//! the external tables may change without any write. Selects which use random
+//! functions are only cached if they are seeded.
+//!
+//! Only the writes done through the same instance are tracked, hence the cache
+//! must be cleared (see [`ResultCache::clear`]) if the database is changed
+//! otherwise.
+//!
+//! The cache is disabled by default. See
+//! [`DbOptions::with_result_cache_capacity`].
+//!
+//! [`Prepared::with_seed`]: crate::sql::planner::Prepared::with_seed
+//! [`Db::catalog_version`]: crate::Db::catalog_version
+//! [`DbOptions::with_result_cache_capacity`]: crate::DbOptions::with_result_cache_capacity
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as SyncMutex,
+    },
+};
+
+use moka::sync::Cache;
+use tracing::trace;
+
+use crate::{exec::values::Values, sql::ast::Select};
+
+/// The key of a cached result.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ResultKey {
+    pub select: Select,
+    pub seed: Option<u64>,
+    pub catalog_version: u64,
+}
+
+/// A bounded cache of select results, keyed by their statements.
+pub struct ResultCache {
+    inner: Cache<ResultKey, (u64, Arc<Vec<Values>>)>,
+    /// The write version of each written table.
+    table_versions: SyncMutex<HashMap<String, u64>>,
+    /// The number of lookups served by the cache.
+    hits: AtomicU64,
+    /// The number of lookups which missed the cache, including the ones whose
+    /// result was stale.
+    misses: AtomicU64,
+}
+
+impl ResultCache {
+    /// Constructs a new cache, which holds at most the given number of
+    /// results.
+    pub fn new(capacity: u64) -> ResultCache {
+        ResultCache {
+            inner: Cache::new(capacity),
+            table_versions: SyncMutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the current write version of the given table. It must be read
+    /// before the result is, so that a concurrent write makes it stale.
+    pub(crate) fn table_version(&self, table: &str) -> u64 {
+        let versions = self.table_versions.lock().unwrap();
+        versions.get(table).copied().unwrap_or(0)
+    }
+
+    /// Bumps the write version of the given table, invalidating all of its
+    /// cached results.
+    pub(crate) fn invalidate_table(&self, table: &str) {
+        let mut versions = self.table_versions.lock().unwrap();
+        *versions.entry(table.to_owned()).or_default() += 1;
+    }
+
+    /// Returns the cached result of the given key, if it was read while its
+    /// table was at the current write version.
+    pub(crate) fn get(&self, key: &ResultKey) -> Option<Arc<Vec<Values>>> {
+        let table_version = self.table_version(&key.select.table);
+        let cached = match self.inner.get(key) {
+            Some((version, rows)) if version == table_version => Some(rows),
+            Some(_) => {
+                trace!(table = key.select.table, "stale result");
+                self.inner.invalidate(key);
+                None
+            }
+            None => None,
+        };
+        let counter = match cached {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    /// Caches the given result, which was read while its table was at the
+    /// given write version.
+    pub(crate) fn insert(&self, key: ResultKey, table_version: u64, rows: Arc<Vec<Values>>) {
+        self.inner.insert(key, (table_version, rows));
+    }
+
+    /// Removes all cached results.
+    pub fn clear(&self) {
+        self.inner.invalidate_all();
+    }
+
+    /// Returns the number of lookups served by the cache and the number of
+    /// lookups which had to execute the statement, respectively, since the
+    /// database was opened.
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
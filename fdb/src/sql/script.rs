@@ -0,0 +1,110 @@
+//! Schema scripts, i.e., portable textual definitions of the database objects.
+//!
+//! A schema script is a sequence of `CREATE` statements (see
+//! [`CreateTable`] and [`CreateIndex`]) which, applied to an empty
+//! database, recreates the objects of the database it was exported from. Unlike
+//! a copy of the database file, the script doesn't depend on the page size or
+//! on the on-disk format, and it may be reviewed and versioned as text.
+//!
+//! Only the schema is exported, not the rows. Column types and maximum lengths
+//! are the only column constraints `fdb` supports; external tables are
+//! exported with their file paths, which aren't resolved until they are read.
+
+use tracing::{debug, instrument};
+
+use crate::{
+    catalog::object::{Object, ObjectType},
+    error::{DbResult, Error},
+    exec::query,
+    sql::{
+        ast::{CreateIndex, CreateTable, Statement},
+        parser::parse_script,
+        planner,
+    },
+    Db,
+};
+
+/// Exports the schema script of the given database.
+///
+/// Tables (including external tables) are defined before the indexes, both in
+/// creation order.
+#[instrument(level = "debug", skip_all)]
+pub async fn export(db: &Db) -> DbResult<String> {
+    let mut tables = Vec::new();
+    let mut indexes = Vec::new();
+    db.execute(query::object::Select::new(), |object: Object| {
+        match object.ty {
+            ObjectType::Table(schema) => tables.push(CreateTable {
+                table: object.name,
+                columns: schema.columns,
+                external: None,
+            }),
+            ObjectType::External(schema) => tables.push(CreateTable {
+                table: object.name,
+                columns: schema.schema.columns,
+                external: Some((schema.path, schema.format)),
+            }),
+            ObjectType::Index(schema) => indexes.push(CreateIndex {
+                index: object.name,
+                table: schema.table,
+                column: schema.column,
+            }),
+            // Statistics are recomputed by an `Analyze`, if needed.
+            ObjectType::Statistics(_) => {}
+        }
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+
+    let statements =
+        (tables.iter().map(ToString::to_string)).chain(indexes.iter().map(ToString::to_string));
+    let mut script = String::new();
+    for statement in statements {
+        script.push_str(&statement);
+        script.push_str(";\n");
+    }
+    Ok(script)
+}
+
+/// Applies the given schema script to the given database, which must have no
+/// objects. Returns the number of created objects.
+///
+/// The whole script is parsed and checked to only contain `CREATE` statements
+/// before any object is created. However, the statements aren't applied
+/// atomically: if one of them fails, the objects created by the previous ones
+/// are kept.
+#[instrument(level = "debug", skip_all)]
+pub async fn load(db: &Db, script: &str) -> DbResult<usize> {
+    let statements = parse_script(script)?;
+    if let Some(statement) = statements.iter().find(|statement| {
+        !matches!(
+            statement,
+            Statement::CreateTable(_) | Statement::CreateIndex(_)
+        )
+    }) {
+        return Err(Error::ExecError(format!(
+            "schema scripts may only contain `CREATE` statements, got {statement:?}"
+        )));
+    }
+
+    let mut existing = 0;
+    db.execute(query::object::Select::new(), |_| {
+        existing += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    if existing > 0 {
+        return Err(Error::ExecError(format!(
+            "can't load a schema script into a database with {existing} objects"
+        )));
+    }
+
+    let count = statements.len();
+    for statement in statements {
+        debug!(?statement, "applying schema statement");
+        planner::execute(db, statement).await?;
+    }
+    Ok(count)
+}
@@ -0,0 +1,208 @@
+//! SQL sessions, which cache the prepared statements.
+
+use std::{collections::HashMap, future::Future};
+
+use tracing::{debug, instrument};
+
+use crate::{
+    error::{DbResult, Error},
+    exec::transaction::Transaction,
+    sql::{
+        self,
+        ast::{Statement, TransactionStatement},
+        planner::{Prepared, SqlOutput},
+    },
+    Db,
+};
+
+/// The default maximum number of statements cached by a [`Session`].
+pub const DEFAULT_STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// A SQL session, which caches the statements it executes, keyed by their
+/// text. Hence, repeated statements skip parsing, object resolution and
+/// validation (see [`Prepared`]).
+///
+/// All cached statements are invalidated once the catalog changes (see
+/// [`Db::catalog_version`]), e.g., if a table is dropped, in which case they
+/// are prepared again. Once the cache is full, the least recently
+/// used statement is evicted.
+///
+/// Sessions also execute the transaction statements (e.g., `BEGIN` and
+/// `COMMIT`), which aren't cached. While a transaction is running, the other
+/// statements are executed within it (see [`Transaction`]). Sessions dropped
+/// with a running transaction roll it back.
+pub struct Session<'a> {
+    db: &'a Db,
+    /// The running transaction, if any.
+    transaction: Option<Transaction<'a>>,
+    /// The cached statements, along with the tick of their last use.
+    statements: HashMap<String, (Prepared, u64)>,
+    capacity: usize,
+    /// The seed of the prepared statements. See [`Prepared::with_seed`].
+    seed: Option<u64>,
+    /// The catalog version of the cached statements.
+    catalog_version: u64,
+    /// Incremented on each execution. Used for the LRU eviction.
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl<'a> Session<'a> {
+    /// Creates a new session over the given database.
+    pub fn new(db: &'a Db) -> Session<'a> {
+        Session {
+            db,
+            transaction: None,
+            statements: HashMap::new(),
+            capacity: DEFAULT_STATEMENT_CACHE_CAPACITY,
+            seed: None,
+            catalog_version: db.catalog_version(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Sets the maximum number of cached statements. Zero disables the cache.
+    pub fn with_capacity(mut self, capacity: usize) -> Session<'a> {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Seeds the random functions of the executed statements, so that sampled
+    /// reads are reproducible. See [`Prepared::with_seed`].
+    pub fn with_seed(mut self, seed: u64) -> Session<'a> {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Executes the given SQL statement, preparing it if it isn't cached (or
+    /// if its cached plan is stale).
+    #[instrument(level = "debug", skip_all)]
+    pub async fn execute(&mut self, sql: &str) -> DbResult<SqlOutput> {
+        self.tick += 1;
+        let catalog_version = self.db.catalog_version();
+        if catalog_version != self.catalog_version {
+            debug!(catalog_version, "catalog changed, invalidating statements");
+            self.statements.clear();
+            self.catalog_version = catalog_version;
+        }
+        if let Some((prepared, last_used)) = self.statements.get_mut(sql) {
+            self.hits += 1;
+            *last_used = self.tick;
+            return in_transaction(&mut self.transaction, prepared.execute(self.db)).await;
+        }
+
+        self.misses += 1;
+        let statement = sql::parser::parse(sql)?;
+        if let Statement::Transaction(statement) = statement {
+            return self.execute_transaction(statement).await;
+        }
+        let prepared = self.prepare(statement).await?;
+        let output = in_transaction(&mut self.transaction, prepared.execute(self.db)).await;
+        // A statement prepared under another version would be stale.
+        if self.capacity > 0 && prepared.catalog_version() == self.catalog_version {
+            if self.statements.len() >= self.capacity {
+                self.evict();
+            }
+            self.statements
+                .insert(sql.to_owned(), (prepared, self.tick));
+        }
+        output
+    }
+
+    /// Executes the given (parsed) statement, without caching it.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn execute_statement(&mut self, statement: Statement) -> DbResult<SqlOutput> {
+        if let Statement::Transaction(statement) = statement {
+            return self.execute_transaction(statement).await;
+        }
+        let prepared = self.prepare(statement).await?;
+        // Boxed, as in `planner::execute`.
+        Box::pin(in_transaction(
+            &mut self.transaction,
+            prepared.execute(self.db),
+        ))
+        .await
+    }
+
+    /// Checks whether a transaction is running.
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    async fn prepare(&mut self, statement: Statement) -> DbResult<Prepared> {
+        let prepare = sql::planner::prepare(self.db, statement);
+        let mut prepared = in_transaction(&mut self.transaction, prepare).await?;
+        if let Some(seed) = self.seed {
+            prepared = prepared.with_seed(seed);
+        }
+        Ok(prepared)
+    }
+
+    async fn execute_transaction(
+        &mut self,
+        statement: TransactionStatement,
+    ) -> DbResult<SqlOutput> {
+        if statement == TransactionStatement::Begin {
+            if self.transaction.is_some() {
+                return Err(Error::ExecError("a transaction is already running".into()));
+            }
+            self.transaction = Some(self.db.begin().await?);
+            return Ok(SqlOutput::Done);
+        }
+        let Some(transaction) = &mut self.transaction else {
+            return Err(Error::ExecError("no transaction is running".into()));
+        };
+        match statement {
+            TransactionStatement::Begin => unreachable!(),
+            TransactionStatement::Commit => self.transaction.take().unwrap().commit().await?,
+            TransactionStatement::Rollback => self.transaction.take().unwrap().rollback().await?,
+            TransactionStatement::Savepoint(name) => transaction.savepoint(&name).await?,
+            TransactionStatement::RollbackTo(name) => transaction.rollback_to(&name).await?,
+            TransactionStatement::Release(name) => transaction.release(&name)?,
+        }
+        Ok(SqlOutput::Done)
+    }
+
+    /// Removes all cached statements.
+    pub fn clear(&mut self) {
+        self.statements.clear();
+    }
+
+    /// Returns the number of cached statements.
+    pub fn cached_count(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Returns the number of executions which used a cached statement and the
+    /// number of executions which prepared it, respectively.
+    pub fn cache_stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+
+    /// Evicts the least recently used statement.
+    fn evict(&mut self) {
+        let lru = (self.statements.iter())
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(sql, _)| sql.clone());
+        if let Some(sql) = lru {
+            debug!(sql, "evicting statement");
+            self.statements.remove(&sql);
+        }
+    }
+}
+
+/// Runs the given future within the given transaction, if any.
+async fn in_transaction<F: Future>(
+    transaction: &mut Option<Transaction<'_>>,
+    future: F,
+) -> F::Output {
+    // Boxed, as in `planner::execute`, since the execution futures are large.
+    let future = Box::pin(future);
+    match transaction {
+        Some(transaction) => transaction.run(future).await,
+        None => future.await,
+    }
+}
@@ -0,0 +1,240 @@
+//! Test-support utilities, behind the `testing` feature, e.g., for validating
+//! the crash recovery.
+//!
+//! [`FaultyBackend`] wraps a [`StorageBackend`], injecting faults into its page
+//! writes as configured by a [`FaultInjector`]: at given writes, from a given
+//! write on (simulating a crash), or randomly, as driven by a seed.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn f() -> fdb::error::DbResult<()> {
+//! use fdb::{
+//!     io::storage::FileBackend,
+//!     testing::{FaultInjector, FaultyBackend},
+//!     Db, DbOptions,
+//! };
+//!
+//! let path = "my-db".as_ref();
+//! let injector = FaultInjector::new();
+//! injector.crash_at(100);
+//! let backend = FaultyBackend::new(FileBackend::create(path).await?, injector.clone());
+//! let (db, _) = Db::open_with_backend(backend, DbOptions::new()).await?;
+//! // Run some statements, after which the database is dropped as if the
+//! // process had crashed...
+//! drop(db);
+//! let (db, _) = Db::open(path).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    io,
+    sync::{Arc, Mutex as SyncMutex},
+};
+
+use tracing::debug;
+
+use crate::{
+    catalog::page::PageId,
+    io::storage::{IoFuture, StorageBackend},
+};
+
+/// A fault injected into a page write.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The write is silently dropped.
+    Drop,
+    /// Only the given number of bytes of the page are written (a torn write),
+    /// silently.
+    Truncate(usize),
+    /// The write fails with an I/O error.
+    Fail,
+}
+
+/// A fault which was injected. See [`FaultInjector::injected`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InjectedFault {
+    /// The index of the write, counting from zero.
+    pub write: u64,
+    /// The written page.
+    pub page_id: PageId,
+    /// The injected fault.
+    pub fault: Fault,
+}
+
+/// Decides which page writes of a [`FaultyBackend`] are faulty.
+///
+/// It is a shared handle, so that a clone may be kept by the test while the
+/// backend is owned by the database. Writes are counted from zero, in the order
+/// they reach the backend; hence, the injected faults are reproducible as long
+/// as the writes are issued in the same order.
+#[derive(Clone, Debug, Default)]
+pub struct FaultInjector {
+    state: Arc<SyncMutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    writes: u64,
+    faults: BTreeMap<u64, Fault>,
+    crash_at: Option<u64>,
+    random: Option<(Rng, f64)>,
+    injected: Vec<InjectedFault>,
+}
+
+impl FaultInjector {
+    /// Constructs a new injector, which injects no faults until configured.
+    pub fn new() -> FaultInjector {
+        FaultInjector::default()
+    }
+
+    /// Constructs a new injector which faults each write with the given
+    /// probability, either dropping or truncating it. The faults are
+    /// determined by the given seed.
+    pub fn seeded(seed: u64, probability: f64) -> FaultInjector {
+        let injector = FaultInjector::new();
+        injector.state.lock().unwrap().random = Some((Rng(seed), probability));
+        injector
+    }
+
+    /// Injects the given fault into the given write (counting from zero).
+    pub fn fault_at(&self, write: u64, fault: Fault) {
+        self.state.lock().unwrap().faults.insert(write, fault);
+    }
+
+    /// Simulates a crash before the given write (counting from zero), i.e.,
+    /// drops it and all of the following ones. The database should then be
+    /// dropped, as if the process had crashed, and reopened.
+    pub fn crash_at(&self, write: u64) {
+        self.state.lock().unwrap().crash_at = Some(write);
+    }
+
+    /// Returns the number of writes so far, including the faulty ones.
+    pub fn writes(&self) -> u64 {
+        self.state.lock().unwrap().writes
+    }
+
+    /// Checks whether the simulated crash (if any) happened.
+    pub fn crashed(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .crash_at
+            .is_some_and(|crash_at| state.writes > crash_at)
+    }
+
+    /// Returns the injected faults, in order.
+    pub fn injected(&self) -> Vec<InjectedFault> {
+        self.state.lock().unwrap().injected.clone()
+    }
+
+    /// Counts a write of the given page, of the given length, returning the
+    /// fault to be injected into it, if any.
+    fn next(&self, page_id: PageId, len: usize) -> Option<Fault> {
+        let mut state = self.state.lock().unwrap();
+        let write = state.writes;
+        state.writes += 1;
+
+        let fault = if state.crash_at.is_some_and(|crash_at| write >= crash_at) {
+            Some(Fault::Drop)
+        } else if let Some(fault) = state.faults.remove(&write) {
+            Some(fault)
+        } else if let Some((rng, probability)) = &mut state.random {
+            (rng.next_f64() < *probability).then(|| {
+                if rng.next_u64() % 2 == 0 {
+                    Fault::Drop
+                } else {
+                    Fault::Truncate((rng.next_u64() % len as u64) as usize)
+                }
+            })
+        } else {
+            None
+        };
+        if let Some(fault) = fault {
+            debug!(write, ?page_id, ?fault, "injecting fault");
+            state.injected.push(InjectedFault {
+                write,
+                page_id,
+                fault,
+            });
+        }
+        fault
+    }
+}
+
+/// A [`StorageBackend`] which injects faults into the page writes of another
+/// one, as decided by a [`FaultInjector`]. Reads and syncs are never faulty.
+pub struct FaultyBackend<B> {
+    inner: B,
+    injector: FaultInjector,
+}
+
+impl<B: StorageBackend> FaultyBackend<B> {
+    /// Wraps the given backend.
+    pub fn new(inner: B, injector: FaultInjector) -> FaultyBackend<B> {
+        FaultyBackend { inner, injector }
+    }
+
+    /// Returns the wrapped backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: StorageBackend> StorageBackend for FaultyBackend<B> {
+    fn read_page<'a>(&'a mut self, page_id: PageId, buf: &'a mut [u8]) -> IoFuture<'a, ()> {
+        self.inner.read_page(page_id, buf)
+    }
+
+    fn write_page<'a>(&'a mut self, page_id: PageId, buf: &'a [u8]) -> IoFuture<'a, ()> {
+        Box::pin(async move {
+            match self.injector.next(page_id, buf.len()) {
+                None => self.inner.write_page(page_id, buf).await,
+                Some(Fault::Drop) => Ok(()),
+                Some(Fault::Truncate(len)) => {
+                    // The rest of the page keeps its previous contents, if any.
+                    let mut torn = vec![0; buf.len()];
+                    match self.inner.read_page(page_id, &mut torn).await {
+                        Err(error) if error.kind() != io::ErrorKind::UnexpectedEof => {
+                            return Err(error)
+                        }
+                        _ => (),
+                    }
+                    let len = len.min(buf.len());
+                    torn[..len].copy_from_slice(&buf[..len]);
+                    self.inner.write_page(page_id, &torn).await
+                }
+                Some(Fault::Fail) => Err(io::Error::other("injected write fault")),
+            }
+        })
+    }
+
+    fn sync(&mut self, metadata: bool) -> IoFuture<'_, ()> {
+        self.inner.sync(metadata)
+    }
+
+    fn len(&mut self) -> IoFuture<'_, u64> {
+        self.inner.len()
+    }
+}
+
+/// A small (SplitMix64) pseudo-random number generator, so that the faults
+/// are reproducible from a seed.
+#[derive(Debug, Default)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
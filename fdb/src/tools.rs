@@ -0,0 +1,139 @@
+//! Offline maintenance tools, which work on database files that aren't opened
+//! elsewhere.
+
+use std::path::Path;
+
+use tokio::fs;
+use tracing::{info, instrument};
+
+use crate::{
+    catalog::object::{Object, ObjectType, TableObject},
+    error::{DbResult, Error},
+    exec::{
+        query::{self, RecordSource},
+        values::Values,
+    },
+    Db, DbOptions,
+};
+
+/// The number of rows inserted at once by [`migrate_page_size`].
+const MIGRATION_BATCH_SIZE: usize = 1024;
+
+/// Opens the database at the given path in read-only mode, with the page size
+/// it was created with (instead of failing with [`Error::PageSizeMismatch`]).
+pub async fn open_read_only(path: &Path) -> DbResult<Db> {
+    let options = DbOptions::new().with_read_only(true);
+    match options.clone().open(path).await {
+        Ok((db, _)) => Ok(db),
+        Err(error) => match error.root() {
+            Error::PageSizeMismatch { actual, .. } => {
+                let (db, _) = options.with_page_size(*actual).open(path).await?;
+                Ok(db)
+            }
+            _ => Err(error),
+        },
+    }
+}
+
+/// Copies the database at the given source path into a new database file at
+/// the given destination path (which must not exist yet), whose page size is
+/// the given one. Returns the number of copied rows.
+///
+/// The catalog objects are recreated in the new file, and the rows of each
+/// table are streamed into it in batches. Indexes are rebuilt once all tables
+/// are copied. The source database must not be opened elsewhere; it is opened
+/// in read-only mode. If the migration fails, the new file is removed.
+#[instrument(level = "debug", skip_all)]
+pub async fn migrate_page_size(src: &Path, dst: &Path, new_size: u16) -> DbResult<u64> {
+    if fs::metadata(dst).await.is_ok() {
+        return Err(Error::ExecError(format!(
+            "`{}` already exists",
+            dst.display()
+        )));
+    }
+    let src_db = open_read_only(src).await?;
+    let options = DbOptions::new().with_page_size(new_size);
+    let (dst_db, _) = options.open(dst).await?;
+
+    match migrate(&src_db, &dst_db).await {
+        Ok(rows) => {
+            dst_db.close().await?;
+            info!(?src, ?dst, new_size, rows, "migrated database");
+            Ok(rows)
+        }
+        Err(error) => {
+            drop(dst_db);
+            // Best effort; the error of the migration is the relevant one.
+            let _ = fs::remove_file(dst).await;
+            Err(error)
+        }
+    }
+}
+
+/// Copies the catalog objects and the table rows of `src` into `dst`.
+async fn migrate(src: &Db, dst: &Db) -> DbResult<u64> {
+    let mut objects = Vec::new();
+    src.execute(query::object::Select::new(), |object| {
+        objects.push(object);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+
+    let mut rows = 0;
+    for object in &objects {
+        match &object.ty {
+            ObjectType::Table(schema) => {
+                let create = query::object::CreateTable::new(&object.name, schema.clone());
+                dst.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+                let src_table = object.clone().try_into_table()?;
+                let dst_table = Object::find(dst, &object.name).await?.try_into_table()?;
+                rows += copy_rows(src, &src_table, dst, &dst_table).await?;
+            }
+            ObjectType::External(schema) => {
+                let create = query::object::CreateExternalTable::new(&object.name, schema.clone());
+                dst.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+            }
+            // Indexes are rebuilt once their tables are copied, and the
+            // statistics are recreated along with their tables.
+            ObjectType::Index(_) | ObjectType::Statistics(_) => (),
+        }
+    }
+    for object in objects {
+        if let ObjectType::Index(schema) = object.ty {
+            let create = query::index::Create::new(object.name, schema.table, schema.column);
+            dst.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+        }
+    }
+    Ok(rows)
+}
+
+/// Streams the rows of the given `src` table into the given `dst` table, in
+/// batches of [`MIGRATION_BATCH_SIZE`]. Returns the number of copied rows.
+async fn copy_rows(
+    src: &Db,
+    src_table: &TableObject,
+    dst: &Db,
+    dst_table: &TableObject,
+) -> DbResult<u64> {
+    let schema = &src_table.schema;
+    let mut select = query::table::Select::new(src_table);
+    let mut batch: Vec<Values> = Vec::with_capacity(MIGRATION_BATCH_SIZE);
+    let mut rows = 0;
+    loop {
+        // The source database is read-only, hence not written concurrently.
+        let row = RecordSource::next(&mut select, src).await?;
+        let done = row.is_none();
+        if let Some(row) = row {
+            batch.push(row.into_values(schema));
+        }
+        if batch.len() == MIGRATION_BATCH_SIZE || (done && !batch.is_empty()) {
+            rows += batch.len() as u64;
+            let insert = query::table::BulkInsert::new(dst_table, batch.drain(..));
+            dst.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+        }
+        if done {
+            return Ok(rows);
+        }
+    }
+}
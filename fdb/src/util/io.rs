@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use buff::Buff;
+use buff::{Buff, BuffRead};
 
 use crate::error::{DbResult, Error};
 
@@ -22,9 +22,12 @@ pub trait Serialize: Size {
 }
 
 /// Deserializes without context.
+///
+/// Deserialization reads from a shared buffer, hence implementations may borrow
+/// from the underlying bytes for the buffer's lifetime, `'a`.
 pub trait Deserialize<'a> {
     /// Deserializes the bytes.
-    fn deserialize(buf: &mut Buff<'a>) -> DbResult<Self>
+    fn deserialize(buf: &mut BuffRead<'a>) -> DbResult<Self>
     where
         Self: Sized;
 }
@@ -38,7 +41,7 @@ pub trait SerializeCtx<C> {
 /// Deserializes with context. See [`Deserialize`]'s documentation.
 pub trait DeserializeCtx<'a, C> {
     /// Deserializes the bytes.
-    fn deserialize(buf: &mut Buff<'a>, ctx: &C) -> DbResult<Self>
+    fn deserialize(buf: &mut BuffRead<'a>, ctx: &C) -> DbResult<Self>
     where
         Self: Sized;
 }
@@ -46,7 +49,7 @@ pub trait DeserializeCtx<'a, C> {
 /// Asserts that the next `expected.len()` bytes are equal to `expected`.
 ///
 /// Returns `true` is the read string was correctly verified.
-pub fn read_verify_eq(buf: &mut Buff<'_>, expected: &[u8]) -> bool {
+pub fn read_verify_eq(buf: &mut BuffRead<'_>, expected: &[u8]) -> bool {
     expected.iter().all(|byte| *byte == buf.read::<1, u8>())
 }
 
@@ -74,7 +77,7 @@ where
 {
     fn serialize(&self, buf: &mut Buff<'_>) -> DbResult<()> {
         let len = u16::try_from(self.0.len()).expect("u16 length");
-        buf.write(len);
+        buf.try_write(len)?;
         for item in self.0.iter() {
             item.serialize(buf)?;
         }
@@ -88,7 +91,7 @@ where
     <[T] as ToOwned>::Owned: FromIterator<T>,
     T: for<'b> Deserialize<'b>,
 {
-    fn deserialize(buf: &mut Buff<'a>) -> DbResult<Self>
+    fn deserialize(buf: &mut BuffRead<'a>) -> DbResult<Self>
     where
         Self: Sized,
     {
@@ -137,32 +140,30 @@ where
 }
 
 /// Serialization/deserialization wrapper for variable-length serialization
-/// format for byte strings.
+/// format for byte strings, which are prefixed by their length as a varint
+/// (see [`buff::varint_size`]).
 pub struct VarBytes<'a>(pub Cow<'a, [u8]>);
 
 impl Size for VarBytes<'_> {
     fn size(&self) -> u32 {
-        2 + self.0.len() as u32
+        (buff::varint_size(self.0.len() as u64) + self.0.len()) as u32
     }
 }
 
 impl Serialize for VarBytes<'_> {
     fn serialize(&self, buf: &mut Buff<'_>) -> DbResult<()> {
-        buf.write::<u16>(self.0.len() as u16);
-        buf.write_slice(&self.0);
+        buf.try_write_prefixed(self.0.len(), |buf| buf.try_write_slice(&self.0))?;
         Ok(())
     }
 }
 
 impl<'a> Deserialize<'a> for VarBytes<'a> {
-    fn deserialize(buf: &mut Buff<'_>) -> DbResult<VarBytes<'a>>
+    fn deserialize(buf: &mut BuffRead<'a>) -> DbResult<VarBytes<'a>>
     where
         Self: Sized,
     {
-        let len: u16 = buf.read();
-        let mut bytes = vec![0; len as usize]; // TODO: Optimize using `MaybeUninit`.
-        buf.read_slice(&mut bytes);
-        Ok(VarBytes(Cow::Owned(bytes)))
+        let bytes = buf.read_prefixed().ok_or(Error::CorruptedVarint)?;
+        Ok(VarBytes(Cow::Borrowed(bytes.get())))
     }
 }
 
@@ -172,7 +173,7 @@ pub struct VarString<'a>(pub Cow<'a, str>);
 
 impl Size for VarString<'_> {
     fn size(&self) -> u32 {
-        2 + self.0.len() as u32
+        VarBytes(Cow::Borrowed(self.0.as_bytes())).size()
     }
 }
 
@@ -183,13 +184,19 @@ impl Serialize for VarString<'_> {
 }
 
 impl<'a> Deserialize<'a> for VarString<'a> {
-    fn deserialize(buf: &mut Buff<'a>) -> DbResult<Self>
+    fn deserialize(buf: &mut BuffRead<'a>) -> DbResult<Self>
     where
         Self: Sized,
     {
-        let bytes = VarBytes::deserialize(buf)?.0.into_owned();
-        let string = String::from_utf8(bytes).map_err(|_| Error::CorruptedUtf8)?;
-        Ok(VarString(Cow::Owned(string)))
+        let string = match VarBytes::deserialize(buf)?.0 {
+            Cow::Borrowed(bytes) => {
+                Cow::Borrowed(std::str::from_utf8(bytes).map_err(|_| Error::CorruptedUtf8)?)
+            }
+            Cow::Owned(bytes) => {
+                Cow::Owned(String::from_utf8(bytes).map_err(|_| Error::CorruptedUtf8)?)
+            }
+        };
+        Ok(VarString(string))
     }
 }
 
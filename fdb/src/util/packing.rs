@@ -0,0 +1,188 @@
+//! Delta and bit packing encoding for integer sequences.
+//!
+//! The first integer (the base) is stored in full. Each subsequent integer is
+//! stored as the (zigzag encoded) delta to its predecessor. Deltas are grouped
+//! in blocks of [`BLOCK_LEN`] elements; each block stores its bit width (i.e.,
+//! the width of its largest delta) in a single byte, followed by the deltas
+//! packed using that width (least significant bits first).
+//!
+//! Since blocks have a fixed length and the widths are found by OR-reducing the
+//! block, the encoding loops are straightforward to vectorize. Sorted or
+//! clustered sequences take a few bits per element, while constant sequences
+//! take no bits at all (besides the block headers).
+
+use buff::{Buff, BuffRead};
+
+use crate::error::{DbResult, Error};
+
+/// The number of deltas in each block.
+pub const BLOCK_LEN: usize = 128;
+
+/// Returns the size of the packed representation of the given integers. Notice
+/// that the sequence length is not included, so it must be stored elsewhere.
+pub fn packed_size(values: &[i64]) -> u32 {
+    let Some((base, rest)) = values.split_first() else {
+        return 0;
+    };
+    let mut prev = *base;
+    let mut size = 8;
+    let mut deltas = [0; BLOCK_LEN];
+    for block in rest.chunks(BLOCK_LEN) {
+        let deltas = &mut deltas[..block.len()];
+        prev = zigzag_deltas(prev, block, deltas);
+        size += 1 + packed_block_size(deltas.len(), width(deltas));
+    }
+    size
+}
+
+/// Packs the given integers. See [`packed_size`].
+pub fn pack(values: &[i64], buf: &mut Buff<'_>) -> DbResult<()> {
+    let Some((base, rest)) = values.split_first() else {
+        return Ok(());
+    };
+    buf.try_write(*base)?;
+    let mut prev = *base;
+    let mut deltas = [0; BLOCK_LEN];
+    for block in rest.chunks(BLOCK_LEN) {
+        let deltas = &mut deltas[..block.len()];
+        prev = zigzag_deltas(prev, block, deltas);
+        let width = width(deltas);
+        buf.try_write(width)?;
+
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        for &delta in deltas.iter() {
+            acc |= (delta as u128) << bits;
+            bits += width as u32;
+            while bits >= 8 {
+                buf.try_write(acc as u8)?;
+                acc >>= 8;
+                bits -= 8;
+            }
+        }
+        if bits > 0 {
+            buf.try_write(acc as u8)?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpacks `len` integers, pushing them into `out`. See [`pack`].
+pub fn unpack(buf: &mut BuffRead<'_>, len: usize, out: &mut Vec<i64>) -> DbResult<()> {
+    if len == 0 {
+        return Ok(());
+    }
+    out.reserve(len);
+    let mut prev: i64 = buf.read();
+    out.push(prev);
+
+    let mut rem = len - 1;
+    while rem > 0 {
+        let block_len = rem.min(BLOCK_LEN);
+        let width: u8 = buf.read();
+        if width > 64 {
+            return Err(Error::CorruptedPacking);
+        }
+        let mask = if width == 64 {
+            u64::MAX
+        } else {
+            (1 << width) - 1
+        };
+
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        for _ in 0..block_len {
+            while bits < width as u32 {
+                acc |= (buf.read::<1, u8>() as u128) << bits;
+                bits += 8;
+            }
+            let delta = acc as u64 & mask;
+            acc >>= width;
+            bits -= width as u32;
+
+            prev = prev.wrapping_add(unzigzag(delta));
+            out.push(prev);
+        }
+        rem -= block_len;
+    }
+    Ok(())
+}
+
+/// Writes the zigzag encoded deltas of `block` (whose predecessor is `prev`)
+/// into `deltas`. Returns the last element of the block.
+#[inline]
+fn zigzag_deltas(prev: i64, block: &[i64], deltas: &mut [u64]) -> i64 {
+    deltas[0] = zigzag(block[0].wrapping_sub(prev));
+    for i in 1..block.len() {
+        deltas[i] = zigzag(block[i].wrapping_sub(block[i - 1]));
+    }
+    block[block.len() - 1]
+}
+
+/// Returns the bit width of the largest of the given values.
+#[inline]
+fn width(values: &[u64]) -> u8 {
+    let or = values.iter().fold(0, |acc, value| acc | value);
+    (64 - or.leading_zeros()) as u8
+}
+
+/// Returns the size of a block of `len` values packed using the given width.
+#[inline]
+fn packed_block_size(len: usize, width: u8) -> u32 {
+    (len as u32 * width as u32).div_ceil(8)
+}
+
+/// Maps signed integers to unsigned ones, so that small magnitudes (either
+/// positive or negative) have small representations.
+#[inline]
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[inline]
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(values: &[i64]) -> u32 {
+        let size = packed_size(values);
+        let mut bytes = vec![0; size as usize];
+        let mut buf = Buff::new(&mut bytes);
+        pack(values, &mut buf).unwrap();
+        assert_eq!(buf.offset(), size as usize);
+
+        let mut buf = BuffRead::new(&bytes);
+        let mut out = Vec::new();
+        unpack(&mut buf, values.len(), &mut out).unwrap();
+        assert_eq!(out, values);
+        size
+    }
+
+    #[test]
+    fn test_zigzag() {
+        for value in [0, 1, -1, 2, -2, i64::MAX, i64::MIN] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        assert_eq!(roundtrip(&[]), 0);
+        assert_eq!(roundtrip(&[42]), 8);
+        // Constant sequences only take the block headers.
+        assert_eq!(roundtrip(&[7; 300]), 8 + 3);
+        // Deltas of 1 take 2 bits each.
+        let sorted: Vec<_> = (0..1000).collect();
+        // 999 deltas: 7 full blocks of 32 bytes and a block of 26 bytes.
+        assert_eq!(roundtrip(&sorted), 8 + 8 + 7 * 32 + 26);
+        roundtrip(&[i64::MIN, i64::MAX, 0, -1, i64::MAX, i64::MIN]);
+        let mixed: Vec<_> = (0..500).map(|i| (i * 7919) % 263 - 131).collect();
+        roundtrip(&mixed);
+    }
+}
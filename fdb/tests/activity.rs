@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{
+        activity::{TableActivity, VacuumThreshold},
+        query,
+        value::Value,
+        values::Values,
+    },
+    sql::planner::SqlOutput,
+    Db,
+};
+
+mod test_utils;
+
+fn row(id: i32, text: &str) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(text.into())),
+        ("bool".into(), Value::Bool(true)),
+    ]))
+}
+
+async fn activity(db: &Db) -> DbResult<TableActivity> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    db.activity().table_activity(db.pager(), &table).await
+}
+
+#[tokio::test]
+async fn test_table_activity() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let rows = (0..20).map(|id| row(id, "text"));
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let insert = query::table::Insert::new(&table, row(20, "text"));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // Two in-place updates and one that relocates the record.
+    let pred = |values: &Values| matches!(values.get("id"), Some(Value::Int(0 | 1)));
+    let updater = |values: &mut Values| values.set("text".into(), Value::Text("t".into()));
+    let update = query::table::Update::new(&table, &pred, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+    let pred = |values: &Values| values.get("id") == Some(&Value::Int(2));
+    let updater = |values: &mut Values| {
+        values.set("text".into(), Value::Text("long text".repeat(10)));
+    };
+    let update = query::table::Update::new(&table, &pred, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let pred = |values: &Values| matches!(values.get("id"), Some(Value::Int(10..=14)));
+    let delete = query::table::Delete::new(&table, &pred);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let expected = TableActivity {
+        inserts: 21,
+        updates: 3,
+        deletes: 5,
+        live_rows: 16,
+        dead_rows: 6,
+    };
+    assert_eq!(activity(&db).await?, expected);
+
+    // The counters are persisted by checkpoints.
+    db.checkpoint().await?;
+    db.reopen().await?;
+    assert_eq!(activity(&db).await?, expected);
+
+    // Vacuum recommendations.
+    let threshold = VacuumThreshold {
+        min_dead_rows: 5,
+        scale_factor: 0.3,
+    };
+    let candidates = db.vacuum_candidates(&threshold).await?;
+    assert_eq!(candidates, [("test_table".to_owned(), expected)]);
+    assert!(db
+        .vacuum_candidates(&VacuumThreshold::default())
+        .await?
+        .is_empty());
+    assert!((expected.dead_ratio() - 6.0 / 22.0).abs() < f64::EPSILON);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_table_activity_system_table() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true), (2, 'two', false)")
+        .await?;
+    db.execute_sql("DELETE FROM test_table WHERE id = 1")
+        .await?;
+
+    let SqlOutput::Rows { columns, rows } = db
+        .execute_sql(
+            "SELECT table_name, live_rows, dead_rows FROM fdb_table_activity \
+             WHERE table_name = 'test_table'",
+        )
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, ["table_name", "live_rows", "dead_rows"]);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get("live_rows"), Some(&Value::BigInt(1)));
+    assert_eq!(rows[0].get("dead_rows"), Some(&Value::BigInt(1)));
+
+    // Dropped tables are no longer tracked.
+    let drop = query::object::DropTable::new("test_table");
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+    db.checkpoint().await?;
+    let SqlOutput::Rows { rows, .. } = db.execute_sql("SELECT * FROM fdb_table_activity").await?
+    else {
+        panic!("expected rows");
+    };
+    assert!(rows.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        page::{FirstPage, PageId},
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::DbResult,
+    exec::{
+        query::{
+            self,
+            table::{Aggregate, AggregateFn, Select},
+        },
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+async fn create_sales(db: &Db) -> DbResult<()> {
+    let column = |name: &str, ty| Column {
+        ty: TypeId::Primitive(ty),
+        name: name.into(),
+        max_len: None,
+        default: None,
+        not_null: false,
+    };
+    let schema = TableSchema {
+        columns: vec![
+            column("region", PrimitiveTypeId::Text),
+            column("product", PrimitiveTypeId::Text),
+            column("amount", PrimitiveTypeId::Int),
+        ],
+    };
+    let create = query::object::CreateTable::new("sales", schema);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    db.execute_sql(
+        "INSERT INTO sales VALUES \
+         ('north', 'a', 10), \
+         ('south', 'a', 5), \
+         ('north', 'b', 7), \
+         ('north', 'a', 3), \
+         ('south', 'b', 1)",
+    )
+    .await?;
+    Ok(())
+}
+
+fn aggregates() -> Vec<(String, AggregateFn)> {
+    vec![
+        ("count".into(), AggregateFn::Count),
+        ("total".into(), AggregateFn::Sum("amount".into())),
+        ("max".into(), AggregateFn::Max("amount".into())),
+    ]
+}
+
+type Summary = (
+    Option<String>,
+    Option<String>,
+    i64,
+    Option<i64>,
+    Option<i32>,
+);
+
+async fn collect<S: query::RecordSource>(db: &Db, query: Aggregate<S>) -> DbResult<Vec<Summary>> {
+    let text = |row: &Values, name| {
+        row.get(name)
+            .map(|v| v.try_cast_text_ref().unwrap().to_owned())
+    };
+    let mut rows = Vec::new();
+    db.execute(query, |row| {
+        rows.push((
+            text(&row, "region"),
+            text(&row, "product"),
+            *row.get("count").unwrap().try_cast_big_int_ref().unwrap(),
+            row.get("total").map(|v| *v.try_cast_big_int_ref().unwrap()),
+            row.get("max").map(|v| *v.try_cast_int_ref().unwrap()),
+        ));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(rows)
+}
+
+fn summary(
+    region: Option<&str>,
+    product: Option<&str>,
+    count: i64,
+    total: i64,
+    max: i32,
+) -> Summary {
+    (
+        region.map(Into::into),
+        product.map(Into::into),
+        count,
+        Some(total),
+        Some(max),
+    )
+}
+
+#[tokio::test]
+async fn test_group_by_many_columns() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_sales(&db).await?;
+    let table = Object::find(&db, "sales").await?.try_into_table()?;
+
+    let aggregate = Aggregate::new(Select::new(&table), &["region", "product"], aggregates())?;
+    assert_eq!(
+        collect(&db, aggregate).await?,
+        [
+            summary(Some("north"), Some("a"), 2, 13, 10),
+            summary(Some("south"), Some("a"), 1, 5, 5),
+            summary(Some("north"), Some("b"), 1, 7, 7),
+            summary(Some("south"), Some("b"), 1, 1, 1),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rollup() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_sales(&db).await?;
+    let table = Object::find(&db, "sales").await?.try_into_table()?;
+
+    let aggregate =
+        Aggregate::new(Select::new(&table), &["region", "product"], aggregates())?.with_rollup();
+    assert_eq!(
+        collect(&db, aggregate).await?,
+        [
+            summary(Some("north"), Some("a"), 2, 13, 10),
+            summary(Some("south"), Some("a"), 1, 5, 5),
+            summary(Some("north"), Some("b"), 1, 7, 7),
+            summary(Some("south"), Some("b"), 1, 1, 1),
+            summary(Some("north"), None, 3, 20, 10),
+            summary(Some("south"), None, 2, 6, 5),
+            summary(None, None, 5, 26, 10),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grouping_sets() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_sales(&db).await?;
+    let table = Object::find(&db, "sales").await?.try_into_table()?;
+
+    let aggregate = Aggregate::new(Select::new(&table), &["region", "product"], aggregates())?
+        .with_grouping_sets(&[&["product"], &[]])?;
+    assert_eq!(
+        collect(&db, aggregate).await?,
+        [
+            summary(None, Some("a"), 3, 18, 10),
+            summary(None, Some("b"), 2, 8, 7),
+            summary(None, None, 5, 26, 10),
+        ]
+    );
+
+    // The empty grouping set yields a row even without records.
+    db.execute_sql("DELETE FROM sales").await?;
+    let aggregate = Aggregate::new(Select::new(&table), &["region"], aggregates())?.with_rollup();
+    assert_eq!(
+        collect(&db, aggregate).await?,
+        [(None, None, 0, None, None)]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_sales(&db).await?;
+    let table = Object::find(&db, "sales").await?.try_into_table()?;
+
+    let new = |group_by: &[&str], aggregate: AggregateFn| {
+        Aggregate::new(Select::new(&table), group_by, vec![("x".into(), aggregate)])
+    };
+    let conflicting = vec![("region".into(), AggregateFn::Count)];
+    assert!(new(&["missing"], AggregateFn::Count).is_err());
+    assert!(new(&["region"], AggregateFn::Max("missing".into())).is_err());
+    assert!(new(&["region"], AggregateFn::Sum("product".into())).is_err());
+    assert!(Aggregate::new(Select::new(&table), &["region"], conflicting).is_err());
+    assert!(new(&["region"], AggregateFn::Count)?
+        .with_grouping_sets(&[&["product"]])
+        .is_err());
+
+    // Minimums and maximums aren't restricted to integers.
+    let aggregate = new(&[], AggregateFn::Min("product".into()))?;
+    let mut mins = Vec::new();
+    db.execute(aggregate, |row| {
+        mins.push(row.get("x").cloned());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(mins, [Some(Value::Text("a".into()))]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregate_spill() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_sales(&db).await?;
+    let table = Object::find(&db, "sales").await?.try_into_table()?;
+    let values: Vec<_> = (0..300)
+        .map(|i| format!("('r{}', 'p{}', {i})", i % 50, i % 7))
+        .collect();
+    db.execute_sql(&format!("INSERT INTO sales VALUES {}", values.join(", ")))
+        .await?;
+
+    let new = || {
+        Aggregate::new(Select::new(&table), &["region", "product"], aggregates())
+            .map(Aggregate::with_rollup)
+    };
+    let mut expected = collect(&db, new()?).await?;
+    assert_eq!(expected.len(), 300 + 4 + 50 + 2 + 1);
+
+    // The groups which don't fit are spilled, and yielded afterwards.
+    for max_groups in [1, 10, 100] {
+        let mut actual = collect(&db, new()?.with_max_groups(max_groups)).await?;
+        assert_eq!(actual.len(), expected.len(), "{max_groups}");
+        actual.sort();
+        expected.sort();
+        assert_eq!(actual, expected, "{max_groups}");
+    }
+
+    // All spill tapes are released.
+    let temp_seqs = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.temp_seq_page_ids.len()
+        })
+        .await?;
+    assert_eq!(temp_seqs, 0);
+
+    Ok(())
+}
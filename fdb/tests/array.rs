@@ -0,0 +1,242 @@
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{query, value::Value},
+    sql::planner::SqlOutput,
+    Db,
+};
+
+mod test_utils;
+
+async fn create_posts(db: &Db) -> DbResult<()> {
+    let schema = TableSchema {
+        columns: vec![
+            Column {
+                ty: TypeId::Primitive(PrimitiveTypeId::Int),
+                name: "id".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
+            },
+            Column {
+                ty: TypeId::Array(PrimitiveTypeId::Text),
+                name: "tags".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
+            },
+            Column {
+                ty: TypeId::Array(PrimitiveTypeId::Int),
+                name: "scores".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
+            },
+        ],
+    };
+    let create = query::object::CreateTable::new("posts", schema);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    db.execute_sql(
+        "INSERT INTO posts VALUES \
+         (1, ['db', 'rust'], [10, 20]), \
+         (2, ['rust'], [30]), \
+         (3, [], [])",
+    )
+    .await?;
+    Ok(())
+}
+
+async fn select_ids(db: &Db, filter: &str) -> DbResult<Vec<Value>> {
+    let sql = format!("SELECT id FROM posts WHERE {filter}");
+    let SqlOutput::Rows { rows, .. } = db.execute_sql(&sql).await? else {
+        panic!("expected rows");
+    };
+    Ok(rows
+        .iter()
+        .map(|row| row.get("id").unwrap().clone())
+        .collect())
+}
+
+#[tokio::test]
+async fn test_array_functions() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_posts(&db).await?;
+
+    let ids = |ids: &[i32]| ids.iter().map(|&id| Value::Int(id)).collect::<Vec<_>>();
+    assert_eq!(
+        select_ids(&db, "array_contains(tags, 'rust')").await?,
+        ids(&[1, 2])
+    );
+    assert_eq!(
+        select_ids(&db, "NOT ARRAY_CONTAINS(tags, 'db')").await?,
+        ids(&[2, 3])
+    );
+    assert_eq!(
+        select_ids(&db, "array_length(scores) = 0").await?,
+        ids(&[3])
+    );
+    assert_eq!(
+        select_ids(&db, "array_length(tags) >= 1").await?,
+        ids(&[1, 2])
+    );
+    // Indices start at 1, and out of bounds accesses never match.
+    assert_eq!(select_ids(&db, "tags[1] = 'rust'").await?, ids(&[2]));
+    assert_eq!(select_ids(&db, "scores[2] > 15").await?, ids(&[1]));
+    assert_eq!(select_ids(&db, "scores[0] = 10").await?, ids(&[]));
+
+    db.execute_sql("UPDATE posts SET tags = ['c'] WHERE id = 3")
+        .await?;
+    assert_eq!(
+        select_ids(&db, "array_contains(tags, 'c')").await?,
+        ids(&[3])
+    );
+
+    for filter in [
+        "array_contains(tags, 1)",
+        "array_contains(id, 1)",
+        "array_length(tags, 1) = 1",
+        "unknown(tags)",
+        "id[1] = 1",
+        "tags = tags",
+        "tags[1] = ['a']",
+    ] {
+        let error = select_ids(&db, filter).await.unwrap_err();
+        assert!(matches!(error, Error::ExecError(_)), "{filter}: {error}");
+    }
+    let error = db
+        .execute_sql("INSERT INTO posts VALUES (4, ['a', 1], [])")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::Cast(_)), "{error}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unnest() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_posts(&db).await?;
+    let table = Object::find(&db, "posts").await?.try_into_table()?;
+
+    let unnest = query::table::Unnest::new(query::table::Select::new(&table), "tags")?;
+    let mut rows = Vec::new();
+    db.execute(unnest, |row| {
+        rows.push((
+            row.get("id").unwrap().clone(),
+            row.get("tags").unwrap().clone(),
+        ));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    let text = |text: &str| Value::Text(text.into());
+    assert_eq!(
+        rows,
+        [
+            (Value::Int(1), text("db")),
+            (Value::Int(1), text("rust")),
+            (Value::Int(2), text("rust")),
+        ]
+    );
+
+    // Unnested rows may be sorted like any other record source.
+    let unnest = query::table::Unnest::new(query::table::Select::new(&table), "scores")?;
+    let sort = query::table::Sort::new(unnest, vec![query::table::SortKey::desc("scores")]);
+    let mut scores = Vec::new();
+    db.execute(sort, |row| {
+        scores.push(row.get("scores").unwrap().clone());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(scores, [Value::Int(30), Value::Int(20), Value::Int(10)]);
+
+    for column in ["id", "missing"] {
+        let select = query::table::Select::new(&table);
+        assert!(query::table::Unnest::new(select, column).is_err());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nested_arrays() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("CREATE TABLE grids (id int, grid int[][], names text[][])")
+        .await?;
+    db.execute_sql(
+        "INSERT INTO grids VALUES \
+         (1, [[1, 2], [3]], [['a', 'b']]), \
+         (2, [[], [4, 5, 6]], [])",
+    )
+    .await?;
+    db.reopen().await?;
+    assert!(db
+        .schema_script()
+        .await?
+        .contains("CREATE TABLE grids (id int, grid int[][], names text[][]);"));
+
+    let SqlOutput::Rows { columns, rows } = db
+        .execute_sql("SELECT id, grid[2], grid[1][2], names[1][1] FROM grids")
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, ["id", "grid[2]", "grid[1][2]", "names[1][1]"]);
+    let int = PrimitiveTypeId::Int;
+    assert_eq!(
+        rows[0].get("grid[2]"),
+        Some(&Value::Array(int, vec![Value::Int(3)]))
+    );
+    assert_eq!(rows[0].get("grid[1][2]"), Some(&Value::Int(2)));
+    assert_eq!(rows[0].get("names[1][1]"), Some(&Value::Text("a".into())));
+    // Out of bounds accesses yield no value.
+    assert_eq!(rows[1].get("grid[1][2]"), None);
+    assert_eq!(rows[1].get("names[1][1]"), None);
+
+    let SqlOutput::Rows { rows, .. } = db
+        .execute_sql("SELECT id FROM grids WHERE grid[2][1] = 4 OR array_length(grid[1]) = 2")
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows.len(), 2);
+
+    // Unnesting yields the inner arrays.
+    let table = Object::find(&db, "grids").await?.try_into_table()?;
+    let unnest = query::table::Unnest::new(query::table::Select::new(&table), "grid")?;
+    let mut inner = Vec::new();
+    db.execute(unnest, |row| {
+        inner.push(row.get("grid").unwrap().clone());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    let ints = |ints: &[i32]| Value::Array(int, ints.iter().map(|&i| Value::Int(i)).collect());
+    assert_eq!(
+        inner,
+        [ints(&[1, 2]), ints(&[3]), ints(&[]), ints(&[4, 5, 6])]
+    );
+
+    for sql in [
+        "SELECT id[1] FROM grids",
+        "SELECT grid[1][1][1] FROM grids",
+        "SELECT id FROM grids WHERE grid[1] = 1",
+        "SELECT id FROM grids WHERE array_contains(grid, 1)",
+    ] {
+        let error = db.execute_sql(sql).await.unwrap_err();
+        assert!(matches!(error, Error::ExecError(_)), "{sql}: {error}");
+    }
+    let error = db
+        .execute_sql("INSERT INTO grids (grid) VALUES ([1, 2])")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::Cast(_)), "{error}");
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+    Db,
+};
+
+mod test_utils;
+
+async fn insert(db: &Db, ids: std::ops::Range<i32>) -> DbResult<()> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    for id in ids {
+        let values = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text(format!("name-{id}"))),
+            ("bool".into(), Value::Bool(id % 2 == 0)),
+        ]));
+        let insert = query::table::Insert::new(&table, values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    Ok(())
+}
+
+async fn count(db: &Db) -> DbResult<usize> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut count = 0;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(count)
+}
+
+#[tokio::test]
+async fn test_backup() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    insert(&db, 0..200).await?;
+
+    let path = db.path().with_extension("backup");
+    let size = db.backup_to(&path).await?;
+    assert_eq!(size, db.stats().await?.size);
+
+    // The copy doesn't observe the writes which follow it.
+    insert(&db, 200..250).await?;
+    assert_eq!(count(&db).await?, 250);
+
+    {
+        let (backup, is_new) = Db::open_with_page_size(&path, db.page_size()).await?;
+        assert!(!is_new);
+        assert_eq!(count(&backup).await?, 200);
+        // The copy is a regular database.
+        insert(&backup, 200..210).await?;
+        assert_eq!(count(&backup).await?, 210);
+    }
+
+    // Existing files are never overwritten.
+    assert!(db.backup_to(&path).await.is_err());
+    std::fs::remove_file(&path).unwrap();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_backup_concurrent_writes() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    insert(&db, 0..100).await?;
+
+    let path = db.path().with_extension("backup");
+    let (size, ()) = tokio::join!(db.backup_to(&path), async {
+        insert(&db, 100..150).await.unwrap();
+    });
+    size?;
+
+    // The copy holds the statements that finished before it, but never part of
+    // a statement.
+    let (backup, _) = Db::open_with_page_size(&path, db.page_size()).await?;
+    let copied = count(&backup).await?;
+    assert!((100..=150).contains(&copied));
+    drop(backup);
+    std::fs::remove_file(&path).unwrap();
+
+    Ok(())
+}
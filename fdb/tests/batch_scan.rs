@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use buff::BuffRead;
+use fdb::{
+    catalog::{
+        object::{Object, TableObject},
+        record::simple_record::{SimpleRecord, TableRecordCtx},
+        table_schema::TableSchema,
+    },
+    error::DbResult,
+    exec::{
+        operations::{heap, PhysicalState},
+        query,
+        value::Value,
+        values::{SchematizedValues, Values},
+    },
+    util::io::DeserializeCtx,
+    Db,
+};
+
+mod test_utils;
+
+type Record = SimpleRecord<'static, SchematizedValues>;
+
+fn row(id: i32, len: usize) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text("t".repeat(len))),
+    ]))
+}
+
+fn deserializer(
+    schema: &TableSchema,
+) -> impl Fn(&mut BuffRead, PhysicalState) -> DbResult<Record> + Copy + '_ {
+    |buf, state| {
+        let ctx = TableRecordCtx::from_physical(state, schema);
+        Record::deserialize(buf, &ctx)
+    }
+}
+
+/// Returns the ID of the given record, and whether it was deleted.
+fn id(record: &Record) -> (i32, bool) {
+    let id = record.as_data().as_slice()[0].try_cast_int_ref().unwrap();
+    (*id, record.is_deleted())
+}
+
+async fn scan(
+    db: &Db,
+    table: &TableObject,
+    batch_size: Option<usize>,
+) -> DbResult<Vec<(i32, bool)>> {
+    let deserializer = deserializer(&table.schema);
+    let mut seq_scan = heap::SeqScan::<Record>::new(table.page_id);
+    let mut ids = Vec::new();
+    match batch_size {
+        Some(n) => loop {
+            let batch = seq_scan.next_batch(db, n, deserializer).await?;
+            if batch.is_empty() {
+                break;
+            }
+            assert!(batch.len() <= n);
+            ids.extend(batch.iter().map(id));
+        },
+        None => {
+            while let Some(record) = seq_scan.next(db, deserializer).await? {
+                ids.push(id(&record));
+            }
+        }
+    }
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_batch_scan() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(256)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    // Some records span many pages.
+    let rows = (0..100).map(|id| row(id, if id % 25 == 0 { 600 } else { 20 }));
+    let insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() % 3 == 0;
+    let delete = query::table::Delete::new(&table, &pred);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let expected = scan(&db, &table, None).await?;
+    assert_eq!(expected.len(), 100);
+    assert_eq!(expected.iter().filter(|(_, deleted)| *deleted).count(), 34);
+    for n in [1, 3, 64] {
+        assert_eq!(
+            scan(&db, &table, Some(n)).await?,
+            expected,
+            "batches of {n}"
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+#[tokio::test]
+async fn test_io_buffers_are_reused() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(Some(256)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    for id in 0..40 {
+        let row = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("t".repeat(50))),
+        ]));
+        let insert = query::table::Insert::new(&table, row);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    assert!(db.pager().buffer_pool().allocated_count() <= 2);
+
+    db.reopen().await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| Ok::<_, ()>(())).await?.unwrap();
+    let pager = db.pager();
+    let (_, reads) = pager.cache_stats();
+    assert!(reads > 10, "{reads} reads");
+    assert_eq!(pager.buffer_pool().allocated_count(), 1);
+    assert_eq!(pager.buffer_pool().idle_count(), 1);
+    Ok(())
+}
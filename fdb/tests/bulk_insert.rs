@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{FirstPage, HeapPage, PageId},
+    },
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+    Db,
+};
+
+mod test_utils;
+
+fn row(id: i32, text: String) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(text)),
+        ("bool".into(), Value::Bool(true)),
+    ]))
+}
+
+async fn select_ids(db: &Db) -> DbResult<Vec<i32>> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut ids = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(ids)
+}
+
+/// Returns the table's sequence `(page_count, record_count)` and the database
+/// page count.
+async fn counts(db: &Db) -> DbResult<(u32, u64, u32)> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let (page_count, record_count) = db
+        .pager()
+        .read_with(table.page_id, |page: &HeapPage| {
+            let seq_header = page.header.seq_header.as_ref().unwrap();
+            (seq_header.page_count, seq_header.record_count)
+        })
+        .await?;
+    let db_page_count = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| page.header.page_count)
+        .await?;
+    Ok((page_count, record_count, db_page_count))
+}
+
+#[tokio::test]
+async fn test_bulk_insert() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let insert = query::table::Insert::new(&table, row(0, "first".into()));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let (_, _, initial_db_page_count) = counts(&db).await?;
+
+    let rows = (1..=300).map(|id| row(id, format!("text-{id}")));
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let (page_count, record_count, db_page_count) = counts(&db).await?;
+    assert_eq!(record_count, 301);
+    assert!(page_count > 1);
+    assert_eq!(db_page_count - initial_db_page_count, page_count - 1);
+
+    // The sequence is still consistent for subsequent single inserts.
+    let insert = query::table::Insert::new(&table, row(301, "last".into()));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(select_ids(&db).await?, (0..=301).collect::<Vec<_>>());
+
+    // An empty bulk insert is a no-op.
+    let bulk_insert = query::table::BulkInsert::new(&table, []);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(counts(&db).await?.1, 302);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bulk_insert_is_validated_upfront() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let before = counts(&db).await?;
+
+    // Invalid type.
+    let mut invalid = row(3, "x".into());
+    invalid.set("bool".into(), Value::Int(1));
+    let rows = [row(1, "x".into()), row(2, "x".into()), invalid];
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    assert!(db.execute(bulk_insert, |_| Ok::<_, ()>(())).await.is_err());
+
+    // Record too large.
+    let rows = [row(1, "x".into()), row(2, "x".repeat(70_000))];
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    assert!(db.execute(bulk_insert, |_| Ok::<_, ()>(())).await.is_err());
+
+    assert_eq!(counts(&db).await?, before);
+    assert!(select_ids(&db).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bulk_insert_maintains_indexes() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let rows = (0..200).map(|id| row(id, "x".repeat(id as usize % 40)));
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    for id in [0, 77, 199] {
+        let mut found = Vec::new();
+        let select =
+            query::table::Select::with_filter(&table, "id", Value::Int(id)..=Value::Int(id));
+        db.execute(select, |row| {
+            found.push(row.get("text").unwrap().clone());
+            Ok::<_, ()>(())
+        })
+        .await?
+        .unwrap();
+        assert_eq!(found, [Value::Text("x".repeat(id as usize % 40))]);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::query,
+    sql, Db,
+};
+
+mod test_utils;
+
+async fn create_table(db: &Db, name: &str) -> DbResult<()> {
+    let schema = TableSchema {
+        columns: vec![Column {
+            ty: TypeId::Primitive(PrimitiveTypeId::Int),
+            name: "id".into(),
+            max_len: None,
+            default: None,
+            not_null: false,
+        }],
+    };
+    let create = query::object::CreateTable::new(name, schema);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+/// Returns the cache hits and misses since the given stats were taken.
+fn delta(db: &Db, (hits, misses): (u64, u64)) -> (u64, u64) {
+    let (new_hits, new_misses) = db.catalog_cache().stats();
+    (new_hits - hits, new_misses - misses)
+}
+
+#[tokio::test]
+async fn test_catalog_cache() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let stats = db.catalog_cache().stats();
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let cached = Object::find(&db, "test_table").await?.try_into_table()?;
+    assert_eq!(cached.page_id, table.page_id);
+    assert_eq!(delta(&db, stats), (1, 1));
+    assert_eq!(db.catalog_cache().len(), 1);
+
+    // Creating an object invalidates the cache.
+    let stats = db.catalog_cache().stats();
+    create_table(&db, "other").await?;
+    Object::find(&db, "other").await?.try_into_table()?;
+    Object::find(&db, "test_table").await?.try_into_table()?;
+    Object::find(&db, "test_table").await?.try_into_table()?;
+    assert_eq!(delta(&db, stats), (1, 2));
+    assert_eq!(db.catalog_cache().len(), 2);
+
+    // So does dropping it.
+    let drop = query::object::DropTable::new("other");
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+    let result = Object::find(&db, "other").await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refresh_catalog() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    Object::find(&db, "test_table").await?;
+    let statement = sql::parser::parse("SELECT * FROM test_table")?;
+    let prepared = sql::planner::prepare(&db, statement).await?;
+
+    let (version, stats) = (db.catalog_version(), db.catalog_cache().stats());
+    db.refresh_catalog();
+    assert_eq!(db.catalog_version(), version + 1);
+    assert!(prepared.is_stale(&db));
+    Object::find(&db, "test_table").await?;
+    assert_eq!(delta(&db, stats), (0, 1));
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use fdb::{
+    error::{DbResult, Error},
+    exec::{format::ValueFormat, value::Value, values::Values},
+    sql::planner::SqlOutput,
+    Db,
+};
+use serde_json::json;
+
+mod test_utils;
+
+async fn create_people(db: &Db) -> DbResult<()> {
+    db.execute_sql(
+        "CREATE TABLE people (id int, address struct(city text(20), zip int, \
+         geo struct(lat float, lon float)))",
+    )
+    .await?;
+    db.execute_sql(
+        "INSERT INTO people VALUES \
+         (1, {city: 'Lisbon', zip: 1000, geo: {lat: 38.7, lon: -9.1}}), \
+         (2, {zip: 4000, city: 'Porto'}), \
+         (3, {})",
+    )
+    .await?;
+    Ok(())
+}
+
+async fn select(db: &Db, sql: &str) -> DbResult<(Vec<String>, Vec<Values>)> {
+    let SqlOutput::Rows { columns, rows } = db.execute_sql(sql).await? else {
+        panic!("expected rows");
+    };
+    Ok((columns, rows))
+}
+
+async fn select_ids(db: &Db, filter: &str) -> DbResult<Vec<i32>> {
+    let sql = format!("SELECT id FROM people WHERE {filter}");
+    let (_, rows) = select(db, &sql).await?;
+    let mut ids: Vec<_> = (rows.iter())
+        .map(|row| *row.get("id").unwrap().try_cast_int_ref().unwrap())
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_composite_values() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    create_people(&db).await?;
+    db.reopen().await?;
+
+    let (columns, rows) = select(
+        &db,
+        "SELECT address.city, address.geo.lat, address FROM people WHERE id = 1",
+    )
+    .await?;
+    assert_eq!(columns, ["address.city", "address.geo.lat", "address"]);
+    assert_eq!(
+        rows[0].get("address.city"),
+        Some(&Value::Text("Lisbon".into()))
+    );
+    let address = rows[0].get("address").unwrap();
+    assert_eq!(address.field("zip"), Some(&Value::Int(1000)));
+    assert_eq!(
+        address.to_json(),
+        json!({ "city": "Lisbon", "zip": 1000, "geo": { "lat": 38.7, "lon": -9.1 } })
+    );
+    assert_eq!(
+        ValueFormat::default().format(address),
+        "{city: 'Lisbon', zip: 1000, geo: {lat: 38.7, lon: -9.1}}"
+    );
+
+    // Omitted fields take their default values.
+    let (_, rows) = select(
+        &db,
+        "SELECT address.city, address.geo.lat FROM people WHERE id = 3",
+    )
+    .await?;
+    assert_eq!(rows[0].get("address.city"), Some(&Value::Text("".into())));
+
+    assert_eq!(select_ids(&db, "address.zip >= 1000").await?, [1, 2]);
+    assert_eq!(select_ids(&db, "address.city = 'Porto'").await?, [2]);
+    assert_eq!(select_ids(&db, "address.geo.lon < 0").await?, [1]);
+
+    db.execute_sql("UPDATE people SET address = {city: 'Faro'} WHERE id = 3")
+        .await?;
+    assert_eq!(select_ids(&db, "address.city = 'Faro'").await?, [3]);
+
+    // Selected composite values may be inserted into columns of the same type.
+    db.execute_sql("INSERT INTO people (id, address) SELECT id, address FROM people WHERE id = 2")
+        .await?;
+    assert_eq!(select_ids(&db, "address.city = 'Porto'").await?, [2, 2]);
+
+    for sql in [
+        "SELECT address.country FROM people",
+        "SELECT id.zip FROM people",
+        "SELECT id FROM people WHERE address.zip = 'a'",
+        "SELECT id FROM people WHERE address = address",
+        "SELECT id FROM people WHERE address.geo = 1",
+        "SELECT id FROM people WHERE address.missing = 1",
+        "INSERT INTO people VALUES (4, {country: 'PT'})",
+        "INSERT INTO people VALUES (4, {zip: 1, zip: 2})",
+    ] {
+        let error = db.execute_sql(sql).await.unwrap_err();
+        assert!(matches!(error, Error::ExecError(_)), "{sql}: {error}");
+    }
+    for sql in [
+        "INSERT INTO people VALUES (4, {zip: 'a'})",
+        "INSERT INTO people VALUES (4, 1)",
+        "INSERT INTO people VALUES (4, {geo: 1})",
+    ] {
+        let error = db.execute_sql(sql).await.unwrap_err();
+        assert!(matches!(error, Error::Cast(_)), "{sql}: {error}");
+    }
+    let error = db
+        .execute_sql(
+            "INSERT INTO people VALUES (4, {city: 'Llanfairpwllgwyngyll-gogerychwyrndrobwll'})",
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::ValueTooLong { .. }), "{error}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_composite_schema_script() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    create_people(&db).await?;
+    db.reopen().await?;
+
+    assert!(db.schema_script().await?.contains(
+        "CREATE TABLE people (id int, address struct(city text(20), zip int, \
+         geo struct(lat float, lon float)));"
+    ));
+
+    for sql in [
+        "CREATE TABLE invalid (a struct(b int, b int))",
+        "CREATE TABLE invalid (a struct(b int(4)))",
+    ] {
+        assert!(db.execute_sql(sql).await.is_err(), "{sql}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+fn schema() -> TableSchema {
+    TableSchema {
+        columns: vec![
+            Column {
+                ty: TypeId::Primitive(PrimitiveTypeId::BigInt),
+                name: "id".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
+            },
+            Column {
+                ty: TypeId::Primitive(PrimitiveTypeId::Text),
+                name: "name".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
+            },
+        ],
+    }
+}
+
+#[tokio::test]
+async fn test_create_drop_table() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    let create = query::object::CreateTable::new("people", schema());
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let table = Object::find(&db, "people").await?.try_into_table()?;
+    let names: Vec<_> = table.schema.columns.iter().map(|c| &*c.name).collect();
+    assert_eq!(names, ["id", "name"]);
+
+    let values = Values::from(HashMap::from([
+        ("id".into(), Value::BigInt(1)),
+        ("name".into(), Value::Text("Alice".into())),
+    ]));
+    let insert = query::table::Insert::new(&table, values.clone());
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let mut rows = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        rows.push(row);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(rows, [values]);
+
+    let drop = query::object::DropTable::new("people");
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    assert!(Object::find(&db, "people").await.is_err());
+    // Other objects are left untouched.
+    Object::find(&db, "test_table").await?.try_into_table()?;
+
+    // Once dropped, the name may be reused.
+    let create = query::object::CreateTable::new("people", schema());
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let table = Object::find(&db, "people").await?.try_into_table()?;
+    let mut count = 0;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(count, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_drop_table_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    let create = query::object::CreateTable::new("test_table", schema());
+    assert!(db.execute(create, |_| Ok::<_, ()>(())).await.is_err());
+
+    let create = query::object::CreateTable::new("empty", TableSchema { columns: vec![] });
+    assert!(db.execute(create, |_| Ok::<_, ()>(())).await.is_err());
+
+    let mut duplicated = schema();
+    duplicated.columns.push(duplicated.columns[0].clone());
+    let create = query::object::CreateTable::new("dup", duplicated);
+    assert!(db.execute(create, |_| Ok::<_, ()>(())).await.is_err());
+
+    let drop = query::object::DropTable::new("missing");
+    assert!(db.execute(drop, |_| Ok::<_, ()>(())).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error, ErrorContext},
+    exec::{query, value::Value, values::Values},
+    Db,
+};
+
+mod test_utils;
+
+/// A CSV file path, whose file is removed on drop.
+struct CsvFile(String);
+
+impl CsvFile {
+    fn new(name: &str) -> CsvFile {
+        std::fs::create_dir_all("ignore").unwrap();
+        CsvFile(format!("ignore/{name}"))
+    }
+
+    fn with_contents(name: &str, contents: &str) -> CsvFile {
+        let file = CsvFile::new(name);
+        std::fs::write(&file.0, contents).unwrap();
+        file
+    }
+}
+
+impl Drop for CsvFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+async fn create_copy_table(db: &Db) -> DbResult<()> {
+    let column = |name: &str, primitive| Column {
+        ty: TypeId::Primitive(primitive),
+        name: name.into(),
+        max_len: None,
+        default: None,
+        not_null: false,
+    };
+    let schema = TableSchema {
+        columns: vec![
+            column("id", PrimitiveTypeId::Int),
+            column("text", PrimitiveTypeId::Text),
+            column("bool", PrimitiveTypeId::Bool),
+        ],
+    };
+    let create = query::object::CreateTable::new("copy", schema);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+/// Returns the `(id, text, bool)` rows of the given table, ordered by `id`.
+async fn rows(db: &Db, table: &str) -> DbResult<Vec<(i32, String, bool)>> {
+    let table = Object::find(db, table).await?.try_into_table()?;
+    let mut rows = Vec::new();
+    db.execute(query::table::Select::new(&table), |values| {
+        let id = *values.get("id").unwrap().try_cast_int_ref().unwrap();
+        let text = values.get("text").unwrap().try_cast_text_ref().unwrap();
+        let bool = *values.get("bool").unwrap().try_cast_bool_ref().unwrap();
+        rows.push((id, text.to_owned(), bool));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    rows.sort();
+    Ok(rows)
+}
+
+async fn import(db: &Db, table: &str, path: &str) -> DbResult<usize> {
+    let table = Object::find(db, table).await?.try_into_table()?;
+    let mut count = 0;
+    let import = query::table::ImportCsv::new(&table, path);
+    db.execute(import, |()| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(count)
+}
+
+#[tokio::test]
+async fn test_csv_round_trip() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    for (id, text) in [
+        (1, "plain"),
+        (2, "with, comma"),
+        (3, "with \"quotes\"\nand a line"),
+    ] {
+        let values = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text(text.into())),
+            ("bool".into(), Value::Bool(id % 2 == 0)),
+        ]));
+        let insert = query::table::Insert::new(&table, values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+
+    let file = CsvFile::new("csv-round-trip.csv");
+    let mut count = 0;
+    let export = query::table::ExportCsv::new(query::table::Select::new(&table), &file.0);
+    db.execute(export, |()| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(count, 3);
+    let contents = std::fs::read_to_string(&file.0).unwrap();
+    assert!(contents.starts_with("id,text,bool\n"));
+    assert!(contents.contains("2,\"with, comma\",true\n"));
+
+    create_copy_table(&db).await?;
+    assert_eq!(import(&db, "copy", &file.0).await?, 3);
+    assert_eq!(rows(&db, "copy").await?, rows(&db, "test_table").await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_csv_import_columns() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_copy_table(&db).await?;
+
+    // Columns may be reordered or missing.
+    let file = CsvFile::with_contents("csv-columns.csv", "bool,id\ntrue,1\n,2\n");
+    assert_eq!(import(&db, "copy", &file.0).await?, 2);
+    assert_eq!(
+        rows(&db, "copy").await?,
+        [(1, String::new(), true), (2, String::new(), false)]
+    );
+
+    let unknown = CsvFile::with_contents("csv-unknown.csv", "id,name\n1,one\n");
+    assert!(matches!(
+        import(&db, "copy", &unknown.0).await.unwrap_err().root(),
+        Error::ExecError(_)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_csv_import_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_copy_table(&db).await?;
+
+    let file = CsvFile::with_contents("csv-errors.csv", "id,text,bool\n1,one,true\nx,two,true\n");
+    let error = import(&db, "copy", &file.0).await.unwrap_err();
+    assert!(matches!(error.root(), Error::Cast(_)));
+    assert!(error
+        .contexts()
+        .any(|context| matches!(context, ErrorContext::Line { line: 3, .. })));
+
+    // No rows are imported from a malformed file.
+    assert!(rows(&db, "copy").await?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+#[tokio::test]
+async fn test_cte() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    for i in 0..10 {
+        let ins = query::table::Insert::new(
+            &table,
+            Values::from(HashMap::from([
+                ("id".into(), Value::Int(i)),
+                ("text".into(), Value::Text(format!("row {i}"))),
+                ("bool".into(), Value::Bool(i % 2 == 0)),
+            ])),
+        );
+        db.execute(ins, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+
+    let select = query::table::Select::new(&table);
+    let evens = query::table::Cte::materialize_where(&db, "evens", select, |row| {
+        *row.get("bool").unwrap().try_cast_bool_ref().unwrap()
+    })
+    .await?;
+    assert_eq!(evens.name(), "evens");
+    assert_eq!(evens.len(), 5);
+
+    // The CTE may be referenced many times without re-executing the query.
+    for _ in 0..2 {
+        let mut ids = Vec::new();
+        db.execute(evens.scan(), |row| {
+            ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+            Ok::<_, ()>(())
+        })
+        .await?
+        .unwrap();
+        ids.sort();
+        assert_eq!(ids, [0, 2, 4, 6, 8]);
+    }
+
+    Ok(())
+}
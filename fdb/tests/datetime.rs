@@ -0,0 +1,108 @@
+use fdb::{
+    error::{DbResult, Error},
+    exec::{
+        time::{self, Date, Time},
+        value::Value,
+    },
+    sql::planner::SqlOutput,
+    Db,
+};
+
+mod test_utils;
+
+async fn create_events(db: &Db) -> DbResult<()> {
+    db.execute_sql("CREATE TABLE events (id int, day date, at time, created timestamp)")
+        .await?;
+    db.execute_sql(
+        "INSERT INTO events VALUES \
+         (1, '2023-03-14', '12:30', '2023-03-14T12:30:00Z'), \
+         (2, DATE '2024-02-29', TIME '08:00:00.5', TIMESTAMP '2024-02-29 08:00:00.5'), \
+         (3, '1969-12-31', '23:59:59.999', 1678795200000)",
+    )
+    .await?;
+    Ok(())
+}
+
+async fn select_ids(db: &Db, filter: &str) -> DbResult<Vec<i32>> {
+    let sql = format!("SELECT id FROM events WHERE {filter}");
+    let SqlOutput::Rows { rows, .. } = db.execute_sql(&sql).await? else {
+        panic!("expected rows");
+    };
+    let mut ids: Vec<_> = (rows.iter())
+        .map(|row| *row.get("id").unwrap().try_cast_int_ref().unwrap())
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_datetime_values() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_events(&db).await?;
+
+    let SqlOutput::Rows { rows, .. } = db
+        .execute_sql("SELECT day, at, created FROM events WHERE id = 2")
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    let day = Date::from_ymd(2024, 2, 29).unwrap();
+    let at = Time::from_hms_milli(8, 0, 0, 500).unwrap();
+    assert_eq!(rows[0].get("day"), Some(&Value::Date(day)));
+    assert_eq!(rows[0].get("at"), Some(&Value::Time(at)));
+    assert_eq!(
+        rows[0].get("created"),
+        Some(&Value::Timestamp(time::timestamp(day, at)))
+    );
+    assert_eq!(rows[0].get("day").unwrap().to_string(), "2024-02-29");
+    assert_eq!(rows[0].get("at").unwrap().to_string(), "08:00:00.500");
+
+    assert_eq!(select_ids(&db, "day > DATE '2000-01-01'").await?, [1, 2]);
+    assert_eq!(select_ids(&db, "day = DATE '1969-12-31'").await?, [3]);
+    assert_eq!(select_ids(&db, "at < TIME '12:30'").await?, [2]);
+    assert_eq!(select_ids(&db, "at >= TIME '12:30:00'").await?, [1, 3]);
+    assert_eq!(
+        select_ids(&db, "created <= TIMESTAMP '2023-03-14T12:00:00Z'").await?,
+        [3]
+    );
+    // Timestamps are still comparable with integers (as milliseconds).
+    assert_eq!(select_ids(&db, "created > 1678795200000").await?, [1, 2]);
+
+    // Dates and times are only comparable with values of the same type.
+    for filter in [
+        "day > '2000-01-01'",
+        "day = at",
+        "at > 0",
+        "day < TIMESTAMP '2023-01-01'",
+    ] {
+        assert!(select_ids(&db, filter).await.is_err(), "{filter}");
+    }
+    for insert in [
+        "INSERT INTO events (day) VALUES ('2023-02-29')",
+        "INSERT INTO events (at) VALUES ('25:00')",
+        "INSERT INTO events (created) VALUES ('yesterday')",
+        "INSERT INTO events (day) VALUES (19430)",
+    ] {
+        let error = db.execute_sql(insert).await.unwrap_err();
+        assert!(matches!(error, Error::Cast(_)), "{insert}: {error}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_datetime_index() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    create_events(&db).await?;
+    db.execute_sql("CREATE INDEX events_day ON events (day)")
+        .await?;
+    db.reopen().await?;
+
+    assert_eq!(select_ids(&db, "day >= DATE '2023-03-14'").await?, [1, 2]);
+    assert_eq!(
+        db.schema_script().await?.lines().nth(1),
+        Some("CREATE TABLE events (id int, day date, at time, created timestamp);")
+    );
+
+    Ok(())
+}
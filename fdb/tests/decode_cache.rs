@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::{Object, TableObject},
+    error::DbResult,
+    exec::{
+        query::{self, table::RecordId},
+        value::Value,
+        values::Values,
+    },
+    Db, DbOptions,
+};
+
+mod test_utils;
+
+fn row(id: i32, text: &str) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(text.into())),
+        ("bool".into(), Value::Bool(true)),
+    ]))
+}
+
+fn id_of(values: &Values) -> i32 {
+    *values.get("id").unwrap().try_cast_int_ref().unwrap()
+}
+
+fn text_of(values: &Values) -> String {
+    values
+        .get("text")
+        .unwrap()
+        .try_cast_text_ref()
+        .unwrap()
+        .to_string()
+}
+
+async fn list_ids(db: &Db, table: &TableObject) -> DbResult<Vec<(RecordId, i32)>> {
+    let mut select = query::table::Select::new(table);
+    let mut ids = Vec::new();
+    while let Some((record_id, values)) = select.next_with_id(db).await? {
+        ids.push((record_id, id_of(&values)));
+    }
+    Ok(ids)
+}
+
+async fn get(db: &Db, table: &TableObject, record_id: RecordId) -> DbResult<Option<Values>> {
+    let mut values = None;
+    let get = query::table::GetById::new(table, record_id);
+    db.execute(get, |row| {
+        values = Some(row);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(values)
+}
+
+fn stats(db: &Db) -> (u64, u64) {
+    db.decode_cache().expect("enabled").stats()
+}
+
+#[tokio::test]
+async fn test_decode_cache() -> DbResult<()> {
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_decode_cache_capacity(16);
+    let db = test_utils::TestDb::new_temp_with_options(options).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let rows = (0..4).map(|id| row(id, "text"));
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let ids = list_ids(&db, &table).await?;
+
+    for _ in 0..3 {
+        let values = get(&db, &table, ids[1].0).await?.unwrap();
+        assert_eq!(id_of(&values), 1);
+    }
+    assert_eq!(stats(&db), (2, 1));
+
+    // A write to the page invalidates its cached records, even if the written
+    // record is another one. The update itself misses the cache.
+    let updater = |values: &mut Values| values.set("text".into(), Value::Text("TEXT".into()));
+    let update = query::table::UpdateById::new(&table, ids[2].0, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+    let values = get(&db, &table, ids[1].0).await?.unwrap();
+    assert_eq!(text_of(&values), "text");
+    assert_eq!(stats(&db), (2, 3));
+
+    let values = get(&db, &table, ids[2].0).await?.unwrap();
+    assert_eq!(text_of(&values), "TEXT");
+    let values = get(&db, &table, ids[2].0).await?.unwrap();
+    assert_eq!(text_of(&values), "TEXT");
+    assert_eq!(stats(&db), (3, 4));
+
+    // Deleted records are never served from the cache.
+    let delete = query::table::DeleteById::new(&table, ids[2].0);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert!(get(&db, &table, ids[2].0).await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decode_cache_disabled_by_default() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    assert!(db.decode_cache().is_none());
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let insert = query::table::Insert::new(&table, row(1, "text"));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let ids = list_ids(&db, &table).await?;
+    let values = get(&db, &table, ids[0].0).await?.unwrap();
+    assert_eq!(id_of(&values), 1);
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+fn column(name: &str, ty: PrimitiveTypeId, default: Option<Value>, not_null: bool) -> Column {
+    Column {
+        ty: TypeId::Primitive(ty),
+        name: name.into(),
+        max_len: None,
+        default,
+        not_null,
+    }
+}
+
+fn schema() -> TableSchema {
+    TableSchema {
+        columns: vec![
+            column("id", PrimitiveTypeId::Int, None, true),
+            column(
+                "name",
+                PrimitiveTypeId::Text,
+                Some(Value::Text("anon".into())),
+                true,
+            ),
+            column(
+                "score",
+                PrimitiveTypeId::BigInt,
+                Some(Value::BigInt(10)),
+                false,
+            ),
+            column("active", PrimitiveTypeId::Bool, None, false),
+        ],
+    }
+}
+
+fn values(values: &[(&str, Value)]) -> Values {
+    Values::from(
+        (values.iter())
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+#[tokio::test]
+async fn test_column_defaults() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    let create = query::object::CreateTable::new("people", schema());
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // The defaults are persisted in the catalog.
+    db.reopen().await?;
+    let table = Object::find(&db, "people").await?.try_into_table()?;
+    assert_eq!(table.schema, schema());
+
+    // Omitted columns take their defaults, or the zero value of their type.
+    let insert = query::table::Insert::new(&table, values(&[("id", Value::Int(1))]));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let row = values(&[
+        ("id", Value::Int(2)),
+        ("name", Value::Text("ana".into())),
+        ("score", Value::BigInt(5)),
+        ("active", Value::Bool(true)),
+    ]);
+    let insert = query::table::Insert::new(&table, row.clone());
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // `NOT NULL` columns without defaults can't be omitted.
+    let insert = query::table::Insert::new(&table, values(&[("score", Value::BigInt(1))]));
+    let error = db.execute(insert, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(
+        matches!(&error, Error::MissingValue(column) if column == "id"),
+        "{error}"
+    );
+
+    let mut rows = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        rows.push(row);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    let defaulted = values(&[
+        ("id", Value::Int(1)),
+        ("name", Value::Text("anon".into())),
+        ("score", Value::BigInt(10)),
+        ("active", Value::Bool(false)),
+    ]);
+    assert_eq!(rows, [defaulted, row]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_column_defaults_sql() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql(
+        "CREATE TABLE items (id int NOT NULL, label text(8) DEFAULT 'none', \
+         tags int[] DEFAULT [1, 2])",
+    )
+    .await?;
+    db.execute_sql("INSERT INTO items (id) VALUES (1)").await?;
+    let error = db
+        .execute_sql("INSERT INTO items (label) VALUES ('x')")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::MissingValue(_)), "{error}");
+
+    db.reopen().await?;
+    assert!(db.schema_script().await?.contains(
+        "CREATE TABLE items (id int NOT NULL, label text(8) DEFAULT 'none', \
+         tags int[] DEFAULT [1, 2]);"
+    ));
+    let table = Object::find(&db, "items").await?.try_into_table()?;
+    let mut rows = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        rows.push(row);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(
+        rows,
+        [values(&[
+            ("id", Value::Int(1)),
+            ("label", Value::Text("none".into())),
+            (
+                "tags",
+                Value::Array(PrimitiveTypeId::Int, vec![Value::Int(1), Value::Int(2)])
+            ),
+        ])]
+    );
+
+    // Defaults must be of the column type, and fit in it.
+    for sql in [
+        "CREATE TABLE invalid (a text(2) DEFAULT 'long')",
+        "CREATE TABLE invalid (a struct(b int NOT NULL))",
+    ] {
+        assert!(db.execute_sql(sql).await.is_err(), "{sql}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{FirstPage, PageId},
+    },
+    error::DbResult,
+    exec::{
+        query::{self, table::SortKey, RecordSource},
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+/// Returns the test table rows of the given `(id, text)` pairs.
+fn table_rows<'a>(rows: &'a [(i32, &str)]) -> impl Iterator<Item = Values> + 'a {
+    rows.iter()
+        .map(|&(id, text)| test_utils::row(id, text, true))
+}
+
+fn row(values: &Values) -> (i32, String) {
+    let id = values.get("id").unwrap().try_cast_int_ref().unwrap();
+    let text = values.get("text").unwrap().try_cast_text_ref().unwrap();
+    (*id, text.to_owned())
+}
+
+async fn distinct(db: &Db, work_mem: Option<usize>) -> DbResult<Vec<(i32, String)>> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let select = query::table::Select::new(&table);
+    let mut distinct = query::table::Distinct::new(select);
+    if let Some(work_mem) = work_mem {
+        distinct = distinct.with_work_mem(work_mem);
+    }
+    let mut rows = Vec::new();
+    db.execute(distinct, |values| {
+        rows.push(row(&values));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(rows)
+}
+
+fn rows(rows: &[(i32, &str)]) -> Vec<(i32, String)> {
+    rows.iter()
+        .map(|&(id, text)| (id, text.to_owned()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_distinct_hashed() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(
+        &db,
+        table_rows(&[(2, "b"), (1, "a"), (2, "b"), (1, "c"), (1, "a")]),
+    )
+    .await?;
+
+    // Records are yielded in the order they first appeared.
+    assert_eq!(
+        distinct(&db, None).await?,
+        rows(&[(2, "b"), (1, "a"), (1, "c")])
+    );
+    // Exactly `work_mem` distinct records still fit in memory.
+    assert_eq!(
+        distinct(&db, Some(3)).await?,
+        rows(&[(2, "b"), (1, "a"), (1, "c")])
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_distinct_sorted() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut input = Vec::new();
+    for i in 0..200 {
+        input.push((i % 37, ["x", "y"][(i % 3 == 0) as usize]));
+    }
+    test_utils::fill(&db, table_rows(&input)).await?;
+
+    let mut expected: Vec<_> = input.clone();
+    expected.sort();
+    expected.dedup();
+
+    // Records are yielded in ascending order once spilled, and the sort tapes
+    // are released once the distinct is exhausted.
+    assert_eq!(distinct(&db, Some(4)).await?, rows(&expected));
+    let temp_seqs = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.temp_seq_page_ids.len()
+        })
+        .await?;
+    assert_eq!(temp_seqs, 0);
+
+    let mut hashed = distinct(&db, None).await?;
+    hashed.sort();
+    assert_eq!(hashed, rows(&expected));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_distinct_as_record_source() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, table_rows(&[(3, "c"), (1, "a"), (3, "c"), (2, "b")])).await?;
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let select = query::table::Select::new(&table);
+    let mut distinct = query::table::Distinct::new(select);
+    let first = RecordSource::peek(&mut distinct, &db).await?.unwrap();
+    assert_eq!(row(&first.to_values(&table.schema)), (3, "c".into()));
+
+    let sort = query::table::Sort::new(distinct, vec![SortKey::desc("id")]);
+    let mut rows = Vec::new();
+    db.execute(sort, |values| {
+        rows.push(row(&values));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(rows, self::rows(&[(3, "c"), (2, "b"), (1, "a")]));
+    Ok(())
+}
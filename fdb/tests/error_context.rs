@@ -0,0 +1,145 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{HeapPage, PageId},
+    },
+    error::{DbResult, Error, ErrorContext},
+    exec::{operations::index::BTree, query, value::Value},
+    util::checksum::crc32,
+    Db,
+};
+
+mod test_utils;
+
+/// Applies the given change to the page contents on disk, optionally updating
+/// the page checksum.
+fn overwrite_page(
+    db: &test_utils::TestDb,
+    page_id: PageId,
+    update_checksum: bool,
+    f: impl FnOnce(&mut [u8]),
+) -> DbResult<()> {
+    let offset = page_id.offset(db.pager().page_size());
+    let mut buf = vec![0; db.pager().page_size() as usize];
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(db.path())?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+
+    let (payload, checksum) = buf.split_at_mut(db.pager().usable_size() as usize);
+    f(payload);
+    if update_checksum {
+        checksum.copy_from_slice(&crc32(payload).to_be_bytes());
+    }
+
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&buf)?;
+    Ok(())
+}
+
+async fn select_all(db: &Db) -> DbResult<()> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_corrupted_record() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, (0..3).map(|id| test_utils::row(id, "hello", true))).await?;
+
+    // Finds the second record's location using an index.
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let index = Object::find(&db, "test_table_by_id")
+        .await?
+        .try_into_index()?;
+    let cells = BTree::new(index.page_id)
+        .search(db.pager(), &Value::Int(1))
+        .await?;
+    let (page_id, offset) = (cells[0].page_id, cells[0].offset);
+
+    // Overwrites the text contents with invalid UTF-8. The record header has
+    // 3 bytes, the `id` column has 4 bytes and the text length has 2 bytes.
+    let guard = db.pager().get::<HeapPage>(page_id).await?;
+    let mut page = guard.write().await;
+    page.write_at(offset + 3 + 4 + 2, |buf| {
+        buf.write_bytes(5, 0xFF);
+        Ok(())
+    })?;
+    page.flush();
+    db.pager().flush_all().await?;
+
+    let error = select_all(&db).await.unwrap_err();
+    assert!(matches!(error.root(), Error::CorruptedUtf8));
+    assert_eq!(
+        error.contexts().cloned().collect::<Vec<_>>(),
+        [
+            ErrorContext::Object("test_table".into()),
+            ErrorContext::Record { page_id, offset },
+        ]
+    );
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "object `test_table`: record at page {}, offset {offset}: \
+             utf-8 error while decoding string",
+            page_id.get()
+        )
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_corrupted_page() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, (0..3).map(|id| test_utils::row(id, "hello", true))).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let page_id = table.page_id;
+
+    // Overwrites the page type tag on disk, updating the page checksum.
+    overwrite_page(&db, page_id, true, |payload| payload[0] = 0x7F)?;
+    db.reopen().await?;
+
+    let error = select_all(&db).await.unwrap_err();
+    assert!(matches!(error.root(), Error::CorruptedTypeTag));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "object `test_table`: page {}: corrupted type tag",
+            page_id.get()
+        )
+    );
+
+    // The database schema is still accessible.
+    assert!(Object::find(&db, "test_table").await.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_checksum_mismatch() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, (0..3).map(|id| test_utils::row(id, "hello", true))).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let page_id = table.page_id;
+
+    // Flips a single bit of the page contents on disk.
+    overwrite_page(&db, page_id, false, |payload| payload[100] ^= 0x01)?;
+    db.reopen().await?;
+
+    let error = select_all(&db).await.unwrap_err();
+    assert!(matches!(error.root(), Error::ChecksumMismatch(id) if *id == page_id));
+
+    // Other pages are still readable.
+    db.ping().await?;
+    assert!(Object::find(&db, "test_table").await.is_ok());
+
+    Ok(())
+}
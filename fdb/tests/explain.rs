@@ -0,0 +1,128 @@
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{
+        query::{
+            self,
+            table::{Sort, SortKey},
+            Explain, Plan, Query,
+        },
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+/// Returns the given number of rows, with 7 distinct texts.
+fn rows(count: i32) -> impl Iterator<Item = Values> {
+    (0..count).map(|id| test_utils::row(id, format!("name-{}", id % 7), id % 2 == 0))
+}
+
+async fn explain<Q>(db: &Db, query: Q) -> DbResult<Plan>
+where
+    Q: Query + Send,
+{
+    let mut plans = Vec::new();
+    db.execute(Explain::new(query), |plan| {
+        plans.push(plan);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(plans.len(), 1);
+    Ok(plans.pop().unwrap())
+}
+
+#[tokio::test]
+async fn test_explain_select_access_path() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(500)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let plan = explain(
+        &db,
+        query::table::Select::with_filter(&table, "id", Value::Int(10)..Value::Int(20)),
+    )
+    .await?;
+    assert_eq!(plan.name, "TableSelect");
+    assert_eq!(plan.get("access"), Some("seq scan"));
+    assert_eq!(plan.get("filter"), Some("id in [10, 20)"));
+    assert!(plan.get("pages").unwrap().parse::<u32>().unwrap() > 1);
+
+    let create = query::index::Create::new("test_table_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let plan = explain(
+        &db,
+        query::table::Select::with_filter(&table, "id", Value::Int(10)..Value::Int(20)),
+    )
+    .await?;
+    assert_eq!(plan.get("access"), Some("index scan"));
+    assert_eq!(plan.get("index"), Some("test_table_id"));
+    assert_eq!(plan.get("range"), Some("[10, 20)"));
+
+    // Hints are honored, as in the execution.
+    let select = query::table::Select::with_filter(&table, "id", Value::Int(10)..).no_index_scan();
+    let plan = explain(&db, select).await?;
+    assert_eq!(plan.get("access"), Some("seq scan"));
+    assert_eq!(plan.get("filter"), Some("id in [10, +inf)"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_explain_doesnt_execute() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(10)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() < 5;
+    let plan = explain(&db, query::table::Delete::new(&table, &pred)).await?;
+    assert_eq!(plan.name, "TableDelete");
+    assert_eq!(plan.get("pred"), Some("<closure>"));
+    assert_eq!(plan.children[0].name, "TableSeqScan");
+
+    let mut count = 0;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(count, 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_explain_tree() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(20)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let select = query::table::Select::new(&table).with_columns(&["id", "text"]);
+    let sort = Sort::new(select, vec![SortKey::asc("text"), SortKey::desc("id")])
+        .with_run_size(64)
+        .limit(5);
+    let plan = explain(&db, sort).await?;
+
+    assert_eq!(plan.name, "Limit");
+    assert_eq!(plan.get("count"), Some("5"));
+    let sort = plan.find("TableSort").unwrap();
+    assert_eq!(sort.get("keys"), Some("text asc, id desc"));
+    assert_eq!(sort.get("run_size"), Some("64"));
+    let select = sort.find("TableSelect").unwrap();
+    assert_eq!(select.get("columns"), Some("id, text"));
+
+    let text = plan.to_string();
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("Limit (count: 5"));
+    assert!(lines[1].starts_with("  -> TableSort (keys: text asc, id desc"));
+    assert!(lines[2].starts_with("    -> TableSelect (table: test_table"));
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::{DbResult, Error},
+    exec::{
+        expr::{col, lit},
+        query,
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+fn row(id: i32) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(format!("text-{id}"))),
+        ("bool".into(), Value::Bool(id % 2 == 0)),
+    ]))
+}
+
+async fn select_ids(db: &Db, select: query::table::Select<'_>) -> DbResult<Vec<i32>> {
+    let mut ids = Vec::new();
+    db.execute(select, |row| {
+        ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_expr_queries() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let bulk_insert = query::table::BulkInsert::new(&table, (0..50).map(row));
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // Disjunctions are linearly scanned.
+    let expr = col("id")
+        .gte(lit(Value::BigInt(40)))
+        .and(col("bool"))
+        .or(col("text").eq(lit(Value::Text("text-3".into()))));
+    let select = query::table::Select::with_expr(&table, &expr);
+    assert_eq!(select_ids(&db, select).await?, [3, 40, 42, 44, 46, 48]);
+    // Otherwise, the key range (over the indexed column) is combined with the
+    // rest of the expression. Integer literals of other widths are accepted.
+    let expr = lit(Value::Byte(40))
+        .lte(col("id"))
+        .and(col("bool"))
+        .and(!col("id").eq(lit(Value::Int(44))));
+    let select = query::table::Select::with_expr(&table, &expr);
+    assert_eq!(select_ids(&db, select).await?, [40, 42, 46, 48]);
+
+    let expr = col("id").lt(lit(Value::Int(10)));
+    let updater = |values: &mut Values| values.set("bool".into(), Value::Bool(true));
+    let update = query::table::Update::with_expr(&table, &expr, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+    let expr = col("bool").and(col("id").lt(lit(Value::Int(10))));
+    let select = query::table::Select::with_expr(&table, &expr);
+    assert_eq!(select_ids(&db, select).await?, (0..10).collect::<Vec<_>>());
+
+    let expr = col("id").gte(lit(Value::Int(5)));
+    let delete = query::table::Delete::with_expr(&table, &expr);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    let select = query::table::Select::new(&table);
+    assert_eq!(select_ids(&db, select).await?, [0, 1, 2, 3, 4]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expr_type_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let not_bool = col("id");
+    let select = query::table::Select::with_expr(&table, &not_bool);
+    let error = select_ids(&db, select).await.unwrap_err();
+    assert!(matches!(error, Error::ExecError(_)), "{error}");
+
+    let mismatch = col("id").eq(lit(Value::Text("1".into())));
+    let delete = query::table::Delete::with_expr(&table, &mismatch);
+    let error = db.execute(delete, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(matches!(error, Error::Cast(_)), "{error}");
+
+    let missing = col("missing").eq(lit(Value::Int(1)));
+    let updater = |_: &mut Values| {};
+    let update = query::table::Update::with_expr(&table, &missing, &updater);
+    let error = db.execute(update, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(matches!(error, Error::ExecError(_)), "{error}");
+
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use fdb::{
+    catalog::{
+        column::Column,
+        external_schema::{ExternalFormat, ExternalTableSchema},
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error, ErrorContext},
+    exec::query,
+    sql::planner::SqlOutput,
+    Db,
+};
+
+mod test_utils;
+
+/// An external file, removed on drop.
+struct ExternalFile(String);
+
+impl ExternalFile {
+    fn new(name: &str, contents: &str) -> ExternalFile {
+        std::fs::create_dir_all("ignore").unwrap();
+        let path = format!("ignore/{name}");
+        std::fs::write(&path, contents).unwrap();
+        ExternalFile(path)
+    }
+}
+
+impl Drop for ExternalFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.0).unwrap();
+    }
+}
+
+fn column(name: &str, ty: TypeId) -> Column {
+    Column {
+        ty,
+        name: name.into(),
+        max_len: None,
+        default: None,
+        not_null: false,
+    }
+}
+
+fn schema() -> TableSchema {
+    TableSchema {
+        columns: vec![
+            column("id", TypeId::Primitive(PrimitiveTypeId::Int)),
+            column("text", TypeId::Primitive(PrimitiveTypeId::Text)),
+            column("bool", TypeId::Primitive(PrimitiveTypeId::Bool)),
+        ],
+    }
+}
+
+async fn create(db: &Db, name: &str, file: &ExternalFile, format: ExternalFormat) -> DbResult<()> {
+    let schema = ExternalTableSchema {
+        path: file.0.clone(),
+        format,
+        schema: schema(),
+    };
+    let query = query::object::CreateExternalTable::new(name, schema);
+    db.execute(query, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+/// Executes the given select, returning the `(id, text, bool)` rows in order.
+async fn select(db: &Db, sql: &str) -> DbResult<Vec<(i32, String, bool)>> {
+    let SqlOutput::Rows { rows, .. } = db.execute_sql(sql).await? else {
+        panic!("expected rows");
+    };
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let id = *row.get("id").unwrap().try_cast_int_ref().unwrap();
+            let text = row.get("text").unwrap().try_cast_text_ref().unwrap();
+            let bool = *row.get("bool").unwrap().try_cast_bool_ref().unwrap();
+            (id, text.to_owned(), bool)
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn test_external_csv() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let file = ExternalFile::new(
+        "external-csv.csv",
+        "bool,id,text,ignored\n\
+         true,1,one,x\n\
+         \n\
+         false,2,\"two, \"\"quoted\"\"\nand multi-line\",y\n\
+         ,3,,z\n",
+    );
+    create(&db, "ext", &file, ExternalFormat::Csv).await?;
+
+    let table = Object::find(&db, "ext").await?.try_into_external_table()?;
+    let mut ids = Vec::new();
+    let scan = query::table::ExternalScan::new(&table);
+    db.execute(scan, |row| {
+        ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(ids, [1, 2, 3]);
+
+    assert_eq!(
+        select(&db, "SELECT * FROM ext WHERE id >= 2").await?,
+        [
+            (2, "two, \"quoted\"\nand multi-line".into(), false),
+            (3, "".into(), false),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_external_jsonl() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let file = ExternalFile::new(
+        "external-jsonl.jsonl",
+        "{\"id\": 1, \"text\": \"one\", \"bool\": true}\n\
+         {\"id\": 2, \"text\": null, \"other\": [1, 2]}\n",
+    );
+    create(&db, "ext", &file, ExternalFormat::Jsonl).await?;
+
+    assert_eq!(
+        select(&db, "SELECT * FROM ext").await?,
+        [(1, "one".into(), true), (2, "".into(), false)]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_external_insert_select() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let file = ExternalFile::new(
+        "external-insert-select.csv",
+        "id,text,bool\n1,one,true\n2,two,false\n3,three,true\n",
+    );
+    create(&db, "ext", &file, ExternalFormat::Csv).await?;
+
+    let SqlOutput::Affected(count) = db
+        .execute_sql("INSERT INTO test_table SELECT * FROM ext WHERE bool = true")
+        .await?
+    else {
+        panic!("expected affected count");
+    };
+    assert_eq!(count, 2);
+    assert_eq!(
+        select(&db, "SELECT * FROM test_table").await?,
+        [(1, "one".into(), true), (3, "three".into(), true)]
+    );
+
+    // Selected columns are assigned by position.
+    db.execute_sql("INSERT INTO test_table (id, text) SELECT id, text FROM ext WHERE id = 2")
+        .await?;
+    assert_eq!(
+        select(&db, "SELECT * FROM test_table WHERE id = 2").await?,
+        [(2, "two".into(), false)]
+    );
+
+    let result = db
+        .execute_sql("INSERT INTO test_table (id) SELECT text FROM ext")
+        .await;
+    assert!(matches!(result, Err(Error::Cast(_))));
+    let result = db
+        .execute_sql("INSERT INTO test_table (id) SELECT id, text FROM ext")
+        .await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+
+    let result = db
+        .execute_sql("INSERT INTO ext VALUES (4, 'four', true)")
+        .await;
+    assert!(matches!(result, Err(Error::Cast(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_external_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let file = ExternalFile::new(
+        "external-errors.csv",
+        "id,text,bool\n1,one,true\nx,two,true\n",
+    );
+    create(&db, "ext", &file, ExternalFormat::Csv).await?;
+
+    let error = db.execute_sql("SELECT * FROM ext").await.unwrap_err();
+    assert!(matches!(error.root(), Error::Cast(_)));
+    assert!(matches!(
+        error.contexts().next(),
+        Some(ErrorContext::Line { path, line: 3 }) if path.ends_with("external-errors.csv")
+    ));
+
+    let unterminated = ExternalFile::new("external-unterminated.csv", "id,text\n1,\"one\n");
+    create(&db, "unterminated", &unterminated, ExternalFormat::Csv).await?;
+    let error = db
+        .execute_sql("SELECT * FROM unterminated")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.contexts().next(),
+        Some(ErrorContext::Line { line: 2, .. })
+    ));
+
+    let missing = ExternalTableSchema {
+        path: "ignore/missing-external.csv".into(),
+        format: ExternalFormat::Csv,
+        schema: schema(),
+    };
+    let query = query::object::CreateExternalTable::new("missing", missing);
+    db.execute(query, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert!(db.execute_sql("SELECT * FROM missing").await.is_err());
+
+    // Names are shared with regular tables.
+    let result = create(&db, "test_table", &file, ExternalFormat::Csv).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_drop_external_table() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    let file = ExternalFile::new("external-drop.jsonl", "{\"id\": 1}\n");
+    create(&db, "ext", &file, ExternalFormat::Jsonl).await?;
+
+    db.reopen().await?;
+    assert_eq!(
+        select(&db, "SELECT * FROM ext").await?,
+        [(1, "".into(), false)]
+    );
+
+    let query = query::object::DropTable::new("ext");
+    db.execute(query, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert!(Object::find(&db, "ext").await.is_err());
+    assert!(std::path::Path::new(&file.0).exists());
+
+    create(&db, "ext", &file, ExternalFormat::Jsonl).await?;
+    Ok(())
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "testing")]
+
+use std::path::Path;
+
+use fdb::{
+    catalog::page::PageId,
+    error::DbResult,
+    io::storage::{FileBackend, MemoryBackend, StorageBackend},
+    sql::planner::SqlOutput,
+    testing::{Fault, FaultInjector, FaultyBackend, InjectedFault},
+    Db, DbOptions,
+};
+
+mod test_utils;
+
+async fn count(db: &Db) -> DbResult<usize> {
+    let SqlOutput::Rows { rows, .. } = db.execute_sql("SELECT id FROM test_table").await? else {
+        panic!("expected rows");
+    };
+    Ok(rows.len())
+}
+
+#[tokio::test]
+async fn test_faults() -> std::io::Result<()> {
+    let injector = FaultInjector::new();
+    let mut backend = FaultyBackend::new(MemoryBackend::new(), injector.clone());
+    let page = PageId::FIRST;
+    backend.write_page(page, &[0xAA; 8]).await?;
+
+    injector.fault_at(1, Fault::Truncate(3));
+    backend.write_page(page, &[0xBB; 8]).await?;
+    injector.fault_at(2, Fault::Drop);
+    backend.write_page(page, &[0xCC; 8]).await?;
+    injector.fault_at(3, Fault::Fail);
+    assert!(backend.write_page(page, &[0xDD; 8]).await.is_err());
+
+    assert_eq!(injector.writes(), 4);
+    assert_eq!(
+        injector.injected(),
+        [
+            InjectedFault {
+                write: 1,
+                page_id: page,
+                fault: Fault::Truncate(3)
+            },
+            InjectedFault {
+                write: 2,
+                page_id: page,
+                fault: Fault::Drop
+            },
+            InjectedFault {
+                write: 3,
+                page_id: page,
+                fault: Fault::Fail
+            },
+        ]
+    );
+    let contents = backend.into_inner();
+    assert_eq!(contents.contents(), b"\xBB\xBB\xBB\xAA\xAA\xAA\xAA\xAA");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seeded_faults() -> std::io::Result<()> {
+    async fn run(seed: u64) -> std::io::Result<Vec<InjectedFault>> {
+        let injector = FaultInjector::seeded(seed, 0.3);
+        let mut backend = FaultyBackend::new(MemoryBackend::new(), injector.clone());
+        for id in 1..=100 {
+            backend.write_page(PageId::new_u32(id), &[1; 64]).await?;
+        }
+        Ok(injector.injected())
+    }
+
+    let injected = run(42).await?;
+    assert!(!injected.is_empty() && injected.len() < 100);
+    assert_eq!(run(42).await?, injected);
+    assert_ne!(run(7).await?, injected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crash_after_sync() -> DbResult<()> {
+    let path = Path::new("ignore/fault-injection-test.db");
+    std::fs::create_dir_all("ignore").unwrap();
+    let _ = std::fs::remove_file(path);
+
+    let injector = FaultInjector::new();
+    let backend = FaultyBackend::new(FileBackend::create(path).await?, injector.clone());
+    let options = DbOptions::new().with_page_size(1024);
+    let (db, _) = Db::open_with_backend(backend, options).await?;
+    test_utils::define_test_catalog(&db).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true), (2, 'two', false)")
+        .await?;
+    db.sync().await?;
+
+    // Nothing is written after the crash.
+    injector.crash_at(injector.writes());
+    db.execute_sql("INSERT INTO test_table VALUES (3, 'three', true)")
+        .await?;
+    db.checkpoint().await?;
+    assert!(injector.crashed());
+    drop(db);
+
+    let (db, is_new) = Db::open_with_page_size(path, 1024).await?;
+    assert!(!is_new);
+    assert_eq!(count(&db).await?, 2);
+
+    std::fs::remove_file(path).unwrap();
+    Ok(())
+}
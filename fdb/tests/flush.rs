@@ -0,0 +1,249 @@
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{FirstPage, PageId},
+    },
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+    io::{disk_manager::SyncMode, pager::FlushPolicy},
+    Db, DbOptions,
+};
+use tokio::time::{self, Instant};
+
+mod test_utils;
+
+async fn insert(db: &Db, ids: std::ops::Range<i32>) -> DbResult<()> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    for id in ids {
+        let values = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("t".repeat(100))),
+            ("bool".into(), Value::Bool(false)),
+        ]));
+        let insert = query::table::Insert::new(&table, values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    Ok(())
+}
+
+async fn count(db: &Db) -> DbResult<usize> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut count = 0;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(count)
+}
+
+async fn new_db(flush_policy: FlushPolicy) -> DbResult<test_utils::TestDb> {
+    test_utils::TestDb::new_temp_with_options(DbOptions {
+        page_size: 1024,
+        flush_policy,
+        ..DbOptions::default()
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_flush_immediate() -> DbResult<()> {
+    let mut db = new_db(FlushPolicy::IMMEDIATE).await?;
+    insert(&db, 0..20).await?;
+    assert_eq!(db.health().await?.pending_writes, 0);
+
+    db.reopen().await?;
+    assert_eq!(count(&db).await?, 20);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_flush_batched() -> DbResult<()> {
+    let mut db = new_db(FlushPolicy {
+        max_dirty_pages: 1000,
+        max_delay: None,
+    })
+    .await?;
+
+    // The writes of all statements are coalesced.
+    insert(&db, 0..20).await?;
+    let pending_writes = db.health().await?.pending_writes;
+    assert!(pending_writes > 0);
+    assert!(pending_writes < 20);
+    assert_eq!(count(&db).await?, 20);
+
+    db.checkpoint().await?;
+    assert_eq!(db.health().await?.pending_writes, 0);
+
+    db.reopen().await?;
+    assert_eq!(count(&db).await?, 20);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_flush_threshold() -> DbResult<()> {
+    let db = new_db(FlushPolicy {
+        max_dirty_pages: 4,
+        max_delay: None,
+    })
+    .await?;
+
+    for start in (0..40).step_by(5) {
+        insert(&db, start..start + 5).await?;
+        assert!(db.health().await?.pending_writes < 4);
+    }
+
+    assert_eq!(count(&db).await?, 40);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_flush_delay() -> DbResult<()> {
+    let mut db = new_db(FlushPolicy {
+        max_dirty_pages: 1000,
+        max_delay: Some(Duration::ZERO),
+    })
+    .await?;
+    insert(&db, 0..10).await?;
+    assert_eq!(db.health().await?.pending_writes, 0);
+
+    db.reopen().await?;
+    assert_eq!(count(&db).await?, 10);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_flush_background() -> DbResult<()> {
+    let mut db = new_db(FlushPolicy {
+        max_dirty_pages: 1000,
+        max_delay: None,
+    })
+    .await?;
+    time::pause();
+    // The delay may be set once the database is open.
+    db.pager().set_flush_policy(FlushPolicy {
+        max_dirty_pages: 1000,
+        max_delay: Some(Duration::from_secs(10)),
+    });
+    insert(&db, 0..10).await?;
+    time::advance(Duration::from_secs(9)).await;
+    assert!(db.health().await?.pending_writes > 0);
+
+    // The pages are written once the delay elapses, although no statement
+    // finishes in the meantime. The writes themselves take real time.
+    time::advance(Duration::from_secs(1)).await;
+    let start = Instant::now();
+    while db.health().await?.pending_writes > 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "pages weren't written"
+        );
+        time::sleep(Duration::from_millis(10)).await;
+    }
+
+    db.reopen().await?;
+    assert_eq!(count(&db).await?, 10);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dirty_pages() -> DbResult<()> {
+    let db = new_db(FlushPolicy {
+        max_dirty_pages: 1000,
+        max_delay: None,
+    })
+    .await?;
+    insert(&db, 0..5).await?;
+    db.pager().flush_all().await?;
+    assert_eq!(db.pager().pending_write_count(), 0);
+
+    // Pages written many times are counted once.
+    let guard = db.pager().get::<FirstPage>(PageId::FIRST).await?;
+    for _ in 0..3 {
+        guard.write().await.flush();
+    }
+    assert_eq!(db.pager().pending_write_count(), 1);
+
+    // Reads need no bookkeeping.
+    assert_eq!(count(&db).await?, 5);
+    assert_eq!(db.pager().pending_write_count(), 1);
+
+    db.pager().flush_all().await?;
+    assert_eq!(db.pager().pending_write_count(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_group_commit() -> DbResult<()> {
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_sync_mode(SyncMode::Normal)
+        .with_group_commit(Duration::from_millis(20));
+    let mut db = test_utils::TestDb::new_temp_with_options(options).await?;
+    let stats = db.health().await?.group_commit;
+
+    // The later statements run while the first one waits to sync.
+    let (a, b, c, d) = tokio::join!(
+        insert(&db, 0..1),
+        insert(&db, 1..2),
+        insert(&db, 2..3),
+        insert(&db, 3..4)
+    );
+    for result in [a, b, c, d] {
+        result?;
+    }
+    let new_stats = db.health().await?.group_commit;
+    assert_eq!(new_stats.commits - stats.commits, 4);
+    assert!(new_stats.syncs - stats.syncs < 4);
+    assert!(new_stats.batching_factor().unwrap() > 1.0);
+
+    db.reopen().await?;
+    assert_eq!(count(&db).await?, 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_group_commit_disabled() -> DbResult<()> {
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_sync_mode(SyncMode::Normal);
+    let db = test_utils::TestDb::new_temp_with_options(options).await?;
+    insert(&db, 0..4).await?;
+    let stats = db.health().await?.group_commit;
+    assert_eq!((stats.commits, stats.syncs), (0, 0));
+    assert_eq!(stats.batching_factor(), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close() -> DbResult<()> {
+    let path = Path::new("ignore/close-test.db");
+    std::fs::create_dir_all("ignore").unwrap();
+    let _ = std::fs::remove_file(path);
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_flush_policy(FlushPolicy {
+            max_dirty_pages: 1000,
+            max_delay: None,
+        });
+
+    let (db, _) = options.clone().open(path).await?;
+    test_utils::define_test_catalog(&db).await?;
+    insert(&db, 0..20).await?;
+    assert!(db.pager().pending_write_count() > 0);
+    // The deferred writes are written once the database is closed.
+    db.close().await?;
+
+    let (db, is_new) = options.clone().with_read_only(true).open(path).await?;
+    assert!(!is_new);
+    assert!(db.environment().clean_shutdown());
+    assert_eq!(count(&db).await?, 20);
+    db.close().await?;
+
+    std::fs::remove_file(path).unwrap();
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{FirstPage, HeapPage, PageId, SpecificPage},
+    },
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+    Db,
+};
+
+mod test_utils;
+
+/// Returns the database's page count and free page count.
+async fn counts(db: &Db) -> DbResult<(u32, u32)> {
+    db.pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            (page.header.page_count, page.header.free_page_count)
+        })
+        .await
+}
+
+fn row(id: i32, text: String) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(text)),
+        ("bool".into(), Value::Bool(true)),
+    ]))
+}
+
+#[tokio::test]
+async fn test_drop_table_reuses_pages() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    let (initial_page_count, _) = counts(&db).await?;
+    test_utils::fill(&db, (0..20).map(|i| row(i, "x".repeat(200)))).await?;
+    let (page_count, free_page_count) = counts(&db).await?;
+    assert!(page_count > initial_page_count + 1);
+    assert_eq!(free_page_count, 0);
+
+    let drop = query::object::DropTable::new("test_table");
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // All table pages (including the first one) were moved to the free list.
+    let table_page_count = page_count - initial_page_count + 1;
+    assert_eq!(counts(&db).await?, (page_count, table_page_count));
+
+    // Recreating the same table reuses the pages instead of growing the file.
+    test_utils::define_test_catalog(&db).await?;
+    test_utils::fill(&db, (0..20).map(|i| row(i, "x".repeat(200)))).await?;
+    assert_eq!(counts(&db).await?, (page_count, 0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insert_too_large_record_allocates_no_page() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let insert = query::table::Insert::new(&table, row(1, "x".repeat(900)));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let (page_count, _) = counts(&db).await?;
+
+    // Records beyond the maximum record size are rejected before any page is
+    // allocated for them.
+    let insert = query::table::Insert::new(&table, row(2, "x".repeat(70_000)));
+    assert!(db.execute(insert, |_| Ok::<_, ()>(())).await.is_err());
+    db.pager().flush_all().await?;
+    assert_eq!(counts(&db).await?, (page_count, 0));
+
+    let insert = query::table::Insert::new(&table, row(3, "x".repeat(900)));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(counts(&db).await?, (page_count + 1, 0));
+
+    let mut ids = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(ids, [1, 3]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_alloc_many() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let (page_count, _) = counts(&db).await?;
+
+    let ids = alloc_many(&db, 4).await?;
+    let expected: Vec<_> = (1..=4).map(|i| page_count + i).collect();
+    assert_eq!(ids, expected);
+    assert_eq!(counts(&db).await?, (page_count + 4, 0));
+
+    for id in &ids[1..3] {
+        db.pager().dealloc(PageId::new_u32(*id)).await?;
+    }
+    db.pager().flush_all().await?;
+    assert_eq!(counts(&db).await?, (page_count + 4, 2));
+
+    // Free pages (most recently freed first) are reused before growing.
+    let ids = alloc_many(&db, 3).await?;
+    assert_eq!(ids, [page_count + 3, page_count + 2, page_count + 5]);
+    assert_eq!(counts(&db).await?, (page_count + 5, 0));
+
+    Ok(())
+}
+
+async fn alloc_many(db: &Db, n: u32) -> DbResult<Vec<u32>> {
+    let guards = db.pager().alloc_many(n, HeapPage::new_seq_first).await?;
+    let mut ids = Vec::new();
+    for guard in guards {
+        let page = guard.read().await;
+        ids.push(page.id().get());
+        page.release();
+    }
+    db.pager().flush_all().await?;
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_alloc_while_holding_first_page() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let (page_count, _) = counts(&db).await?;
+
+    // Allocations don't latch the first page, hence they don't deadlock.
+    let first_page_guard = db.pager().get::<FirstPage>(PageId::FIRST).await?;
+    let first_page = first_page_guard.write().await;
+    let (a, b) = tokio::join!(
+        db.pager().alloc_many(2, HeapPage::new_seq_first),
+        db.pager().alloc(HeapPage::new_seq_first),
+    );
+    let mut ids = Vec::new();
+    for guard in a?.into_iter().chain([b?]) {
+        let page = guard.read().await;
+        ids.push(page.id().get());
+        page.release();
+    }
+    ids.sort_unstable();
+    let expected: Vec<_> = (1..=3).map(|i| page_count + i).collect();
+    assert_eq!(ids, expected);
+
+    // The counters of the latched first page lag behind.
+    assert_eq!(first_page.header.page_count, page_count);
+    assert_eq!(db.pager().alloc_state().await?.page_count, page_count + 3);
+    first_page.flush();
+    drop(first_page_guard);
+
+    db.pager().flush_all().await?;
+    assert_eq!(counts(&db).await?, (page_count + 3, 0));
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+    RecoveryState, COMPILED_FEATURES,
+};
+
+mod test_utils;
+
+#[tokio::test]
+async fn test_health() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    db.ping().await?;
+
+    let health = db.health().await?;
+    assert_eq!(health.recovery, RecoveryState::Created);
+    assert_eq!(health.size, db.stats().await?.size);
+    assert_eq!(health.pending_writes, 0);
+    assert_eq!(health.wal_backlog, None);
+    assert_eq!(health.last_checkpoint_age, None);
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // The catalog and table pages are cached by now.
+    let after_select = db.health().await?;
+    assert!(after_select.cache_hits > health.cache_hits);
+    assert!(after_select.cache_hit_rate().unwrap() > 0.0);
+
+    db.checkpoint().await?;
+    let health = db.health().await?;
+    assert_eq!(health.pending_writes, 0);
+    assert!(health.last_checkpoint_age.is_some());
+
+    db.reopen().await?;
+    db.ping().await?;
+    let health = db.health().await?;
+    assert_eq!(health.recovery, RecoveryState::Clean);
+    assert_eq!(health.last_checkpoint_age, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_health_recovered() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+
+    // Simulates a crash, i.e., the temporary table is never destroyed.
+    let schema = Object::find(&db, "test_table")
+        .await?
+        .try_into_table()?
+        .schema;
+    let temp = query::table::TempTable::create(&db, "tmp", schema).await?;
+    let values = Values::from(HashMap::from([
+        ("id".into(), Value::Int(1)),
+        ("text".into(), Value::Text("t".into())),
+        ("bool".into(), Value::Bool(false)),
+    ]));
+    let insert = query::table::Insert::new(temp.table(), values);
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    drop(temp);
+
+    db.reopen().await?;
+    assert_eq!(
+        db.health().await?.recovery,
+        RecoveryState::Recovered {
+            purged_temp_sequences: 1
+        }
+    );
+    assert!(!db.environment().clean_shutdown());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_environment() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+
+    let environment = *db.environment();
+    assert_eq!(environment.page_size, 1024);
+    assert_eq!(environment.format_version, 4);
+    assert_eq!(environment.recovery, RecoveryState::Created);
+    assert!(environment.clean_shutdown());
+    assert_eq!(environment.wal_segments, None);
+    assert!(!environment.read_only);
+    assert_eq!(environment.features, COMPILED_FEATURES);
+
+    db.reopen().await?;
+    let environment = *db.environment();
+    assert_eq!(environment.recovery, RecoveryState::Clean);
+    assert!(environment.clean_shutdown());
+    assert_eq!(environment.file_size, db.stats().await?.size);
+
+    let report = environment.to_string();
+    assert!(report.contains("page size: 1024\n"));
+    assert!(report.contains("recovery: Clean\n"));
+
+    Ok(())
+}
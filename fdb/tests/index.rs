@@ -0,0 +1,595 @@
+use std::{
+    collections::HashMap,
+    ops::{Bound, RangeBounds},
+};
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{BTreeCell, FirstPage, PageId},
+    },
+    error::DbResult,
+    exec::{
+        activity::{self, IndexUsage},
+        operations::index::BTree,
+        query,
+        value::Value,
+        values::Values,
+    },
+    sql::planner::SqlOutput,
+    util::io::Size,
+    Db,
+};
+
+mod test_utils;
+
+const ROWS: i32 = 300;
+
+/// Returns the given number of rows, with 50 distinct texts.
+fn rows(count: i32) -> impl Iterator<Item = Values> {
+    (0..count).map(|id| test_utils::row(id, format!("name-{}", id % 50), id % 3 == 0))
+}
+
+async fn create_index(db: &Db, name: &str, column: &str) -> DbResult<BTree> {
+    let create = query::index::Create::new(name, "test_table", column);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let index = Object::find(db, name).await?.try_into_index()?;
+    assert_eq!(index.schema.table, "test_table");
+    assert_eq!(index.schema.column, column);
+    Ok(BTree::new(index.page_id))
+}
+
+/// Selects the IDs of the rows whose `column` is within the given range.
+async fn select_ids(db: &Db, column: &str, range: impl RangeBounds<Value>) -> DbResult<Vec<i32>> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut ids = Vec::new();
+    let select = query::table::Select::with_filter(&table, column, range);
+    db.execute(select, |row| {
+        ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(ids)
+}
+
+/// Checks that the index over `column` has exactly one entry per row.
+async fn check_index(db: &Db, index: &str, column: &str) -> DbResult<()> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut expected = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        expected.push(row.get(column).unwrap().clone());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let index = Object::find(db, index).await?.try_into_index()?;
+    let cells = BTree::new(index.page_id)
+        .range(db.pager(), Bound::Unbounded, Bound::Unbounded)
+        .await?;
+    assert_eq!(keys(&cells), expected);
+    Ok(())
+}
+
+fn keys(cells: &[BTreeCell]) -> Vec<Value> {
+    cells.iter().map(|cell| cell.key.clone()).collect()
+}
+
+#[tokio::test]
+async fn test_create_index() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(ROWS)).await?;
+
+    let by_id = create_index(&db, "test_table_by_id", "id").await?;
+    let by_text = create_index(&db, "test_table_by_text", "text").await?;
+
+    let all = by_id
+        .range(db.pager(), Bound::Unbounded, Bound::Unbounded)
+        .await?;
+    assert_eq!(keys(&all), (0..ROWS).map(Value::Int).collect::<Vec<_>>());
+    let mut locations: Vec<_> = all
+        .iter()
+        .map(|cell| (cell.page_id.get(), cell.offset))
+        .collect();
+    locations.sort_unstable();
+    locations.dedup();
+    assert_eq!(locations.len(), ROWS as usize);
+
+    let cells = by_id.search(db.pager(), &Value::Int(42)).await?;
+    assert_eq!(keys(&cells), [Value::Int(42)]);
+    assert!(by_id
+        .search(db.pager(), &Value::Int(ROWS))
+        .await?
+        .is_empty());
+
+    let cells = by_id
+        .range(
+            db.pager(),
+            Bound::Excluded(&Value::Int(10)),
+            Bound::Included(&Value::Int(20)),
+        )
+        .await?;
+    assert_eq!(keys(&cells), (11..=20).map(Value::Int).collect::<Vec<_>>());
+
+    // Duplicated keys.
+    let cells = by_text
+        .search(db.pager(), &Value::Text("name-7".into()))
+        .await?;
+    assert_eq!(cells.len(), (ROWS / 50) as usize);
+    assert!(cells.windows(2).all(|w| w[0] < w[1]));
+
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    assert!(db.execute(create, |_| Ok::<_, ()>(())).await.is_err());
+    let create = query::index::Create::new("other", "test_table", "missing");
+    assert!(db.execute(create, |_| Ok::<_, ()>(())).await.is_err());
+    let create = query::index::Create::new("other", "missing", "id");
+    assert!(db.execute(create, |_| Ok::<_, ()>(())).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_insert_delete() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let tree = BTree::create(db.pager()).await?;
+
+    let cell = |n: u32| BTreeCell {
+        key: Value::Text(format!("key-{:05}", n % 700)),
+        page_id: PageId::new_u32(n + 1),
+        offset: (n % 7) as u16,
+    };
+
+    // Pseudo-random insertion order.
+    let mut expected = std::collections::BTreeSet::new();
+    let mut n: u32 = 1;
+    for _ in 0..2000 {
+        n = n.wrapping_mul(1_103_515_245).wrapping_add(12_345) % 100_000;
+        if expected.insert(cell(n)) {
+            tree.insert(db.pager(), cell(n)).await?;
+        } else {
+            assert!(tree.insert(db.pager(), cell(n)).await.is_err());
+        }
+    }
+    let all = tree
+        .range(db.pager(), Bound::Unbounded, Bound::Unbounded)
+        .await?;
+    assert_eq!(all, expected.iter().cloned().collect::<Vec<_>>());
+
+    // Removes every other cell, and then the remaining ones.
+    let cells: Vec<_> = expected.iter().cloned().collect();
+    for pass in [0, 1] {
+        for (i, cell) in cells.iter().enumerate() {
+            if i % 2 != pass {
+                continue;
+            }
+            assert!(tree.delete(db.pager(), cell).await?);
+            assert!(!tree.delete(db.pager(), cell).await?);
+            expected.remove(cell);
+        }
+        let all = tree
+            .range(db.pager(), Bound::Unbounded, Bound::Unbounded)
+            .await?;
+        assert_eq!(all, expected.iter().cloned().collect::<Vec<_>>());
+    }
+    db.pager().flush_all().await?;
+
+    // Merged nodes were released.
+    let free_page_count = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.header.free_page_count
+        })
+        .await?;
+    assert!(free_page_count > 0);
+
+    // The tree is still usable.
+    tree.insert(db.pager(), cell(1)).await?;
+    assert_eq!(tree.search(db.pager(), &cell(1).key).await?, [cell(1)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_bulk_load() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let tree = BTree::create(db.pager()).await?;
+
+    let cells: Vec<_> = (0..1500_u32)
+        .map(|n| BTreeCell {
+            key: Value::BigInt(n as i64 / 3),
+            page_id: PageId::new_u32(n + 1),
+            offset: 0,
+        })
+        .collect();
+    tree.bulk_load(db.pager(), cells.clone()).await?;
+    assert!(tree.bulk_load(db.pager(), cells.clone()).await.is_err());
+
+    let all = tree
+        .range(db.pager(), Bound::Unbounded, Bound::Unbounded)
+        .await?;
+    assert_eq!(all, cells);
+    let found = tree.search(db.pager(), &Value::BigInt(100)).await?;
+    assert_eq!(found, cells[300..303]);
+
+    // The loaded tree may be modified as usual.
+    for cell in cells.iter().step_by(2) {
+        assert!(tree.delete(db.pager(), cell).await?);
+    }
+    let cell = BTreeCell {
+        key: Value::BigInt(-1),
+        page_id: PageId::new_u32(1),
+        offset: 1,
+    };
+    tree.insert(db.pager(), cell.clone()).await?;
+    let all = tree
+        .range(db.pager(), Bound::Unbounded, Bound::Unbounded)
+        .await?;
+    let expected: Vec<_> = std::iter::once(cell)
+        .chain(cells.iter().skip(1).step_by(2).cloned())
+        .collect();
+    assert_eq!(all, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_with_filter() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(ROWS)).await?;
+
+    // Linear scan.
+    let linear = select_ids(&db, "id", Value::Int(10)..Value::Int(20)).await?;
+    assert_eq!(linear, (10..20).collect::<Vec<_>>());
+    let linear_text = select_ids(
+        &db,
+        "text",
+        Value::Text("name-7".into())..=Value::Text("name-7".into()),
+    )
+    .await?;
+    assert_eq!(linear_text, [7, 57, 107, 157, 207, 257]);
+
+    create_index(&db, "test_table_by_id", "id").await?;
+    create_index(&db, "test_table_by_text", "text").await?;
+
+    // Index scan.
+    assert_eq!(
+        select_ids(&db, "id", Value::Int(10)..Value::Int(20)).await?,
+        linear
+    );
+    assert_eq!(
+        select_ids(
+            &db,
+            "text",
+            Value::Text("name-7".into())..=Value::Text("name-7".into())
+        )
+        .await?,
+        linear_text
+    );
+    assert_eq!(
+        select_ids(&db, "id", Value::Int(42)..=Value::Int(42)).await?,
+        [42]
+    );
+    assert_eq!(
+        select_ids(&db, "id", Value::Int(ROWS - 2)..).await?,
+        [ROWS - 2, ROWS - 1]
+    );
+    assert_eq!(select_ids(&db, "id", ..).await?.len(), ROWS as usize);
+    assert!(select_ids(&db, "id", Value::Int(ROWS)..).await?.is_empty());
+
+    // Invalid filters.
+    assert!(select_ids(&db, "missing", ..).await.is_err());
+    assert!(select_ids(&db, "id", Value::BigInt(1)..).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_maintenance() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(ROWS)).await?;
+    create_index(&db, "test_table_by_id", "id").await?;
+    create_index(&db, "test_table_by_text", "text").await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    // Insert.
+    let values = Values::from(HashMap::from([
+        ("id".into(), Value::Int(1000)),
+        ("text".into(), Value::Text("inserted".into())),
+        ("bool".into(), Value::Bool(true)),
+    ]));
+    let insert = query::table::Insert::new(&table, values);
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(select_ids(&db, "id", Value::Int(1000)..).await?, [1000]);
+
+    // Update in place (indexed key changes) and with relocation.
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() < 10;
+    let updater = |values: &mut Values| {
+        let id = *values.get("id").unwrap().try_cast_int_ref().unwrap();
+        values.set("id".into(), Value::Int(id + 2000));
+        if id % 2 == 0 {
+            values.set("text".into(), Value::Text("x".repeat(100)));
+        }
+    };
+    let update = query::table::Update::new(&table, &pred, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert!(select_ids(&db, "id", ..Value::Int(10)).await?.is_empty());
+    assert_eq!(
+        select_ids(&db, "id", Value::Int(2000)..Value::Int(3000)).await?,
+        (2000..2010).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        select_ids(
+            &db,
+            "text",
+            Value::Text("x".repeat(100))..=Value::Text("x".repeat(100))
+        )
+        .await?
+        .len(),
+        5
+    );
+
+    // Delete.
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() % 3 == 0;
+    let delete = query::table::Delete::new(&table, &pred);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    let ids = select_ids(&db, "id", Value::Int(100)..Value::Int(110)).await?;
+    assert_eq!(ids, [100, 101, 103, 104, 106, 107, 109]);
+
+    check_index(&db, "test_table_by_id", "id").await?;
+    check_index(&db, "test_table_by_text", "text").await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_drop_table_drops_indexes() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(ROWS)).await?;
+    create_index(&db, "test_table_by_id", "id").await?;
+
+    let drop = query::object::DropTable::new("test_table");
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert!(Object::find(&db, "test_table_by_id").await.is_err());
+
+    // A new table with the same name isn't affected by the old index.
+    test_utils::define_test_catalog(&db).await?;
+    assert!(select_ids(&db, "id", ..).await?.is_empty());
+    create_index(&db, "test_table_by_id", "id").await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_prefix_compression() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let cell = |n: u32| BTreeCell {
+        key: Value::Text(format!("https://example.com/users/{:06}/profile", n / 2)),
+        page_id: PageId::new_u32(n + 1),
+        offset: 0,
+    };
+    let cells: Vec<_> = (0..2000).map(cell).collect();
+    let uncompressed_size: u32 = cells.iter().map(Size::size).sum();
+    let min_uncompressed_pages = (uncompressed_size / db.pager().usable_size() as u32) as usize;
+
+    // Inserted in a pseudo-random order.
+    let inserted = BTree::create(db.pager()).await?;
+    for i in 0..cells.len() as u32 {
+        inserted.insert(db.pager(), cell(i * 7 % 2000)).await?;
+    }
+    let loaded = BTree::create(db.pager()).await?;
+    loaded.bulk_load(db.pager(), cells.clone()).await?;
+
+    for tree in [inserted, loaded] {
+        let all = tree
+            .range(db.pager(), Bound::Unbounded, Bound::Unbounded)
+            .await?;
+        assert_eq!(all, cells);
+        let key = Value::Text("https://example.com/users/000500/profile".into());
+        assert_eq!(tree.search(db.pager(), &key).await?, cells[1000..1002]);
+        let start = Value::Text("https://example.com/users/0007".into());
+        let end = Value::Text("https://example.com/users/0008".into());
+        let found = tree
+            .range(db.pager(), Bound::Included(&start), Bound::Excluded(&end))
+            .await?;
+        assert_eq!(found, cells[1400..1600]);
+
+        // Fewer pages than the uncompressed cells would take, even if the
+        // pages were completely full.
+        let page_count = tree.release(db.pager()).await? as usize;
+        assert!(page_count < min_uncompressed_pages);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_separator_truncation() {
+    let cell = |key: &str, page_id: u32| BTreeCell {
+        key: Value::Text(key.into()),
+        page_id: PageId::new_u32(page_id),
+        offset: 0,
+    };
+    let separator = |last, first| BTreeCell::separator(&last, &first).key;
+
+    assert_eq!(
+        separator(cell("apple", 1), cell("banana", 2)),
+        Value::Text("b".into())
+    );
+    assert_eq!(
+        separator(cell("user-0041", 1), cell("user-0042x", 2)),
+        Value::Text("user-0042".into())
+    );
+    assert_eq!(
+        separator(cell("user", 1), cell("user-1", 2)),
+        Value::Text("user-".into())
+    );
+    // Nothing to truncate.
+    assert_eq!(
+        separator(cell("ab", 1), cell("ac", 2)),
+        Value::Text("ac".into())
+    );
+    assert_eq!(
+        separator(cell("same", 1), cell("same", 2)),
+        Value::Text("same".into())
+    );
+    // Truncation respects character boundaries.
+    assert_eq!(
+        separator(cell("olá", 1), cell("olé!", 2)),
+        Value::Text("olé".into())
+    );
+    // Other keys aren't truncated.
+    let int = |n| BTreeCell {
+        key: Value::Int(n),
+        page_id: PageId::new_u32(1),
+        offset: 0,
+    };
+    assert_eq!(BTreeCell::separator(&int(1), &int(200)), int(200));
+}
+
+#[tokio::test]
+async fn test_index_stats() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(256)).await?;
+
+    // Built by insertions (hence, sparser and deeper) and bulk loaded.
+    create_index(&db, "by_text_inserted", "text").await?;
+    // As the text lengths are varints, it takes about 300 rows (with 256-byte
+    // pages) for the inserted index, whose pages are split in halves, to reach
+    // a height of 3.
+    test_utils::fill(&db, rows(ROWS)).await?;
+    create_index(&db, "by_text_loaded", "text").await?;
+
+    let stats = activity::indexes(&db).await?;
+    let summary: Vec<_> = stats
+        .iter()
+        .map(|stats| (stats.name.as_str(), stats.height, stats.entries))
+        .collect();
+    assert_eq!(
+        summary,
+        [("by_text_inserted", 3, 300), ("by_text_loaded", 2, 300)]
+    );
+    assert!(stats[0].page_count > stats[1].page_count);
+    assert!(stats
+        .iter()
+        .all(|stats| stats.usage == IndexUsage::default()));
+
+    // The shallowest index is used.
+    assert_eq!(
+        select_ids(
+            &db,
+            "text",
+            Value::Text("name-7".into())..=Value::Text("name-7".into())
+        )
+        .await?
+        .len(),
+        (ROWS / 50) as usize
+    );
+    let stats = activity::indexes(&db).await?;
+    assert_eq!(stats[0].usage.lookups, 0);
+    assert_eq!(stats[1].usage.lookups, 1);
+    assert!(stats[1].usage.last_used.is_some());
+
+    // Through the system table.
+    let output = db
+        .execute_sql(
+            "SELECT index_name, column_name, lookups FROM fdb_index_stats WHERE lookups > 0",
+        )
+        .await?;
+    let SqlOutput::Rows { rows, .. } = output else {
+        panic!("expected rows");
+    };
+    assert_eq!(
+        rows,
+        [Values::from(HashMap::from([
+            ("index_name".into(), Value::Text("by_text_loaded".into())),
+            ("column_name".into(), Value::Text("text".into())),
+            ("lookups".into(), Value::BigInt(1)),
+        ]))]
+    );
+    let output = db
+        .execute_sql("SELECT * FROM fdb_index_stats WHERE last_used = 0")
+        .await?;
+    let SqlOutput::Rows { rows, .. } = output else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get("height"), Some(&Value::BigInt(3)));
+
+    let drop = query::object::DropTable::new("test_table");
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert!(activity::indexes(&db).await?.is_empty());
+
+    Ok(())
+}
+
+async fn count(db: &Db, select: query::table::Select<'_>) -> DbResult<usize> {
+    let mut count = 0;
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(count)
+}
+
+#[tokio::test]
+async fn test_index_hints() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(256)).await?;
+    create_index(&db, "by_text_inserted", "text").await?;
+    test_utils::fill(&db, rows(250)).await?;
+    create_index(&db, "by_text_loaded", "text").await?;
+    create_index(&db, "by_id", "id").await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let lookups = |stats: Vec<activity::IndexStats>| -> Vec<u64> {
+        stats.iter().map(|stats| stats.usage.lookups).collect()
+    };
+    let key = Value::Text("name-7".into());
+
+    // The deeper index is forced, instead of the shallowest one.
+    let select = query::table::Select::with_filter(&table, "text", key.clone()..=key.clone())
+        .use_index("by_text_inserted");
+    assert_eq!(count(&db, select).await?, 5);
+    assert_eq!(lookups(activity::indexes(&db).await?), [1, 0, 0]);
+
+    // Index scans are forbidden.
+    let select = query::table::Select::with_filter(&table, "text", key.clone()..=key.clone())
+        .no_index_scan();
+    assert_eq!(count(&db, select).await?, 5);
+    assert_eq!(lookups(activity::indexes(&db).await?), [1, 0, 0]);
+
+    // Unusable indexes.
+    let select = query::table::Select::with_filter(&table, "text", key.clone()..=key.clone())
+        .use_index("by_id");
+    assert!(count(&db, select).await.is_err());
+    let select = query::table::Select::new(&table).use_index("by_text_loaded");
+    assert!(count(&db, select).await.is_err());
+    let select = query::table::Select::with_filter(&table, "text", key.clone()..=key.clone())
+        .use_index("missing");
+    assert!(count(&db, select).await.is_err());
+
+    // Through SQL.
+    let output = db
+        .execute_sql("SELECT id FROM test_table USE INDEX (by_text_inserted) WHERE text = 'name-7'")
+        .await?;
+    let SqlOutput::Rows { rows, .. } = output else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows.len(), 5);
+    assert_eq!(lookups(activity::indexes(&db).await?), [2, 0, 0]);
+    db.execute_sql("SELECT id FROM test_table IGNORE INDEX WHERE id = 7")
+        .await?;
+    assert_eq!(lookups(activity::indexes(&db).await?), [2, 0, 0]);
+    for sql in [
+        "SELECT * FROM test_table USE INDEX (by_id) WHERE text = 'name-7'",
+        "SELECT * FROM test_table USE INDEX (by_id)",
+        "SELECT * FROM test_table USE INDEX (test_table) WHERE id = 1",
+        "SELECT * FROM fdb_index_stats IGNORE INDEX",
+    ] {
+        assert!(db.execute_sql(sql).await.is_err(), "{sql}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{
+        expr::{col, lit},
+        query::{
+            self,
+            table::{Sort, SortKey},
+        },
+        value::Value,
+    },
+    sql::planner::SqlOutput,
+    Db,
+};
+
+mod test_utils;
+
+fn column(name: &str, ty: PrimitiveTypeId) -> Column {
+    Column {
+        ty: TypeId::Primitive(ty),
+        name: name.into(),
+        max_len: None,
+        default: None,
+        not_null: false,
+    }
+}
+
+async fn create_copy_table(db: &Db, id_type: PrimitiveTypeId) -> DbResult<()> {
+    let schema = TableSchema {
+        columns: vec![
+            column("key", id_type),
+            column("label", PrimitiveTypeId::Text),
+        ],
+    };
+    let create = query::object::CreateTable::new("copy", schema);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+/// Executes the given query, returning the number of yielded items.
+async fn count<Q>(db: &Db, query: Q) -> DbResult<u64>
+where
+    Q: for<'a> query::Query<Item<'a> = ()>,
+{
+    let mut count = 0;
+    db.execute(query, |()| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(count)
+}
+
+async fn rows(db: &Db, sql: &str) -> DbResult<Vec<Vec<Value>>> {
+    let SqlOutput::Rows { columns, rows } = db.execute_sql(sql).await? else {
+        panic!("expected rows");
+    };
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let values = columns
+                .iter()
+                .map(|column| row.get(column).unwrap().clone());
+            values.collect()
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn test_insert_from_mapping() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql(
+        "INSERT INTO test_table VALUES (1, 'one', true), (2, 'two', false), (3, 'three', true)",
+    )
+    .await?;
+    create_copy_table(&db, PrimitiveTypeId::BigInt).await?;
+
+    let source = Object::find(&db, "test_table").await?.try_into_table()?;
+    let target = Object::find(&db, "copy").await?.try_into_table()?;
+    let filter = col("bool").eq(lit(Value::Bool(true)));
+    let select = query::table::Select::with_expr(&source, &filter);
+    let sort = Sort::new(select, vec![SortKey::desc("id")]);
+    let insert = query::table::InsertFrom::new(&target, sort)
+        .with_mapping(&[("key", "id"), ("label", "text")]);
+    assert_eq!(count(&db, insert).await?, 2);
+
+    assert_eq!(
+        rows(&db, "SELECT * FROM copy").await?,
+        [
+            [Value::BigInt(3), Value::Text("three".into())],
+            [Value::BigInt(1), Value::Text("one".into())],
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insert_from_same_table() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true), (2, 'two', false)")
+        .await?;
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let select = query::table::Select::new(&table);
+    let insert = query::table::InsertFrom::new(&table, select);
+    assert_eq!(count(&db, insert).await?, 2);
+
+    assert_eq!(
+        rows(&db, "SELECT id FROM test_table").await?,
+        [
+            [Value::Int(1)],
+            [Value::Int(2)],
+            [Value::Int(1)],
+            [Value::Int(2)]
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insert_from_checks() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true), (300, 'many', true)")
+        .await?;
+    create_copy_table(&db, PrimitiveTypeId::Byte).await?;
+
+    let source = Object::find(&db, "test_table").await?.try_into_table()?;
+    let target = Object::find(&db, "copy").await?.try_into_table()?;
+    let insert = |mapping| {
+        let select = query::table::Select::new(&source);
+        query::table::InsertFrom::new(&target, select).with_mapping(mapping)
+    };
+
+    let result = count(&db, insert(&[("key", "text")])).await;
+    assert!(matches!(result, Err(Error::Cast(_))));
+    let result = count(&db, insert(&[("key", "missing")])).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    let result = count(&db, insert(&[("key", "id"), ("key", "id")])).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    // The default mapping matches the columns by name.
+    let select = query::table::Select::new(&source);
+    let result = count(&db, query::table::InsertFrom::new(&target, select)).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+
+    // The second row is out of the `Byte` range, so no row is inserted.
+    let result = count(&db, insert(&[("key", "id")])).await;
+    assert!(matches!(result, Err(Error::Cast(_))));
+    assert!(rows(&db, "SELECT * FROM copy").await?.is_empty());
+
+    db.execute_sql("DELETE FROM test_table WHERE id = 300")
+        .await?;
+    assert_eq!(count(&db, insert(&[("key", "id")])).await?, 1);
+    assert_eq!(
+        rows(&db, "SELECT * FROM copy").await?,
+        [[Value::Byte(1), Value::Text("".into())]]
+    );
+    Ok(())
+}
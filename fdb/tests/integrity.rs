@@ -0,0 +1,112 @@
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{HeapPage, PageId, SpecificPage},
+    },
+    error::DbResult,
+    exec::integrity::{IntegrityProblem, ProblemKind, Structure},
+    Db,
+};
+
+mod test_utils;
+
+async fn alloc(db: &Db) -> DbResult<PageId> {
+    let guard = db.pager().alloc(HeapPage::new_seq_first).await?;
+    let page = guard.read().await;
+    let page_id = page.id();
+    page.release();
+    Ok(page_id)
+}
+
+/// Flips a byte in the middle of the given page, without updating its
+/// checksum.
+fn corrupt(path: &Path, page_id: PageId, page_size: u16) {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .unwrap();
+    let offset = page_id.offset(page_size) + page_size as u64 / 2;
+    let mut byte = [0];
+    file.seek(SeekFrom::Start(offset)).unwrap();
+    file.read_exact(&mut byte).unwrap();
+    file.seek(SeekFrom::Start(offset)).unwrap();
+    file.write_all(&[!byte[0]]).unwrap();
+}
+
+#[tokio::test]
+async fn test_integrity_check() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(1024)).await?;
+    db.execute_sql("CREATE INDEX test_table_id ON test_table (id)")
+        .await?;
+    for id in 0..200 {
+        // Some records span many pages.
+        let len = if id % 50 == 0 { 3000 } else { 20 };
+        let text = "x".repeat(len);
+        db.execute_sql(&format!(
+            "INSERT INTO test_table VALUES ({id}, '{text}', true)"
+        ))
+        .await?;
+    }
+    let page_id = alloc(&db).await?;
+    db.pager().dealloc(page_id).await?;
+
+    let report = db.check_integrity().await?;
+    assert!(report.is_ok(), "{:?}", report.problems);
+    assert_eq!(report.page_count, db.stats().await?.page_count);
+    assert_eq!(report.pages_checked, report.page_count);
+
+    // A page which isn't part of any structure.
+    let page_id = alloc(&db).await?;
+    let report = db.check_integrity().await?;
+    assert_eq!(
+        report.problems,
+        [IntegrityProblem {
+            page_id,
+            structure: None,
+            kind: ProblemKind::Orphaned,
+        }]
+    );
+    assert_eq!(report.pages_checked, report.page_count - 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_integrity_check_corruption() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(Some(1024)).await?;
+    for id in 0..100 {
+        db.execute_sql(&format!(
+            "INSERT INTO test_table VALUES ({id}, 'text', true)"
+        ))
+        .await?;
+    }
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    db.checkpoint().await?;
+
+    corrupt(db.path(), table.page_id, 1024);
+    db.reopen().await?;
+    let report = db.check_integrity().await?;
+    assert!(!report.is_ok());
+    assert_eq!(
+        report.problems[0],
+        IntegrityProblem {
+            page_id: table.page_id,
+            structure: Some(Structure::Table("test_table".into())),
+            kind: ProblemKind::ChecksumMismatch,
+        }
+    );
+    // The rest of the table is no longer reachable.
+    assert!(report.problems[1..]
+        .iter()
+        .all(|problem| problem.kind == ProblemKind::Orphaned));
+    assert!(report.problems.len() > 1);
+
+    Ok(())
+}
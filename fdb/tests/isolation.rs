@@ -0,0 +1,167 @@
+use std::{collections::HashMap, sync::Arc};
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+const ROWS: i32 = 40;
+const ROUNDS: usize = 10;
+
+fn text(round: usize) -> String {
+    // Every other round grows the records, which forces the update to move
+    // them (across pages) to the end of the heap sequence.
+    let len = [10, 60][round % 2];
+    format!("{round}").repeat(len)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_scan_never_observes_partial_statement() -> DbResult<()> {
+    let db = Arc::new(test_utils::TestDb::new_temp(Some(256)).await?);
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    for id in 0..ROWS {
+        let values = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text(text(0))),
+            ("bool".into(), Value::Bool(true)),
+        ]));
+        let insert = query::table::Insert::new(&table, values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+
+    let writer = tokio::spawn({
+        let db = Arc::clone(&db);
+        async move {
+            let table = Object::find(&db, "test_table").await?.try_into_table()?;
+            for round in 1..ROUNDS {
+                let new_text = text(round);
+                let updater = move |values: &mut Values| {
+                    values.set("text".into(), Value::Text(new_text.clone()));
+                };
+                let update = query::table::Update::new(&table, &|_| true, &updater);
+                db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+            }
+            DbResult::Ok(())
+        }
+    });
+
+    let mut scans = 0;
+    loop {
+        let finished = writer.is_finished();
+
+        let mut ids = Vec::new();
+        let mut texts = Vec::new();
+        let select = query::table::Select::new(&table);
+        db.execute(select, |row| {
+            ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+            texts.push(
+                row.get("text")
+                    .unwrap()
+                    .try_cast_text_ref()
+                    .unwrap()
+                    .to_owned(),
+            );
+            Ok::<_, ()>(())
+        })
+        .await?
+        .unwrap();
+        scans += 1;
+
+        // Either none or all of the records were updated by the statement.
+        ids.sort_unstable();
+        assert_eq!(ids, (0..ROWS).collect::<Vec<_>>());
+        assert!(texts.iter().all(|t| *t == texts[0]), "partial update seen");
+
+        if finished {
+            assert_eq!(texts[0], text(ROUNDS - 1));
+            break;
+        }
+        tokio::task::yield_now().await;
+    }
+    assert!(scans > 1);
+
+    writer.await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_snapshot_read_doesnt_block_writes() -> DbResult<()> {
+    let db = Arc::new(test_utils::TestDb::new_temp(Some(256)).await?);
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    for id in 0..ROWS {
+        let values = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text(text(0))),
+            ("bool".into(), Value::Bool(true)),
+        ]));
+        let insert = query::table::Insert::new(&table, values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+
+    // The reader stops on its first row until the update is done.
+    let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+    let (resume_tx, resume_rx) = std::sync::mpsc::channel::<()>();
+    let reader = tokio::spawn({
+        let db = Arc::clone(&db);
+        let table = table.clone();
+        async move {
+            let mut started_tx = Some(started_tx);
+            let resume_rx = std::sync::Mutex::new(resume_rx);
+            let mut texts = Vec::new();
+            let select = query::table::Select::new(&table);
+            db.execute(select, |row| {
+                if let Some(started_tx) = started_tx.take() {
+                    started_tx.send(()).unwrap();
+                    resume_rx.lock().unwrap().recv().unwrap();
+                }
+                let text = row.get("text").unwrap().try_cast_text_ref().unwrap();
+                texts.push(text.to_owned());
+                Ok::<_, ()>(())
+            })
+            .await?
+            .unwrap();
+            DbResult::<_>::Ok(texts)
+        }
+    });
+    started_rx.await.unwrap();
+    assert_eq!(db.pager().txns().stats().snapshots, 1);
+
+    let updater = |values: &mut Values| {
+        values.set("text".into(), Value::Text(text(1)));
+    };
+    let update = query::table::Update::new(&table, &|_| true, &updater);
+    let update = db.execute(update, |_| Ok::<_, ()>(()));
+    let duration = std::time::Duration::from_secs(10);
+    tokio::time::timeout(duration, update)
+        .await
+        .expect("update blocked by the reader")?
+        .unwrap();
+    assert!(db.pager().txns().stats().versioned_pages > 0);
+
+    // The reader still sees its snapshot.
+    resume_tx.send(()).unwrap();
+    let texts = reader.await.unwrap()?;
+    assert_eq!(texts.len(), ROWS as usize);
+    assert!(texts.iter().all(|t| *t == text(0)), "snapshot not kept");
+
+    // Before-images are dropped along with the snapshot.
+    let stats = db.pager().txns().stats();
+    assert_eq!((stats.snapshots, stats.versioned_pages), (0, 0));
+    assert!(stats.captured > 0);
+
+    let mut texts = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        texts.push(row.get("text").unwrap().clone());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert!(texts.iter().all(|t| *t == Value::Text(text(1))));
+
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        column::Column,
+        object::{Object, TableObject},
+        page::{FirstPage, PageId},
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::DbResult,
+    exec::{
+        query::{self, Query},
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+fn column(name: &str, ty: PrimitiveTypeId) -> Column {
+    Column {
+        ty: TypeId::Primitive(ty),
+        name: name.into(),
+        max_len: None,
+        default: None,
+        not_null: false,
+    }
+}
+
+/// Creates the `users (id, name)` and `orders (id, user_id, item)` tables.
+async fn create_tables(db: &Db) -> DbResult<(TableObject, TableObject)> {
+    let users = TableSchema {
+        columns: vec![
+            column("id", PrimitiveTypeId::Int),
+            column("name", PrimitiveTypeId::Text),
+        ],
+    };
+    let orders = TableSchema {
+        columns: vec![
+            column("id", PrimitiveTypeId::Int),
+            column("user_id", PrimitiveTypeId::Int),
+            column("item", PrimitiveTypeId::Text),
+        ],
+    };
+    for (name, schema) in [("users", users), ("orders", orders)] {
+        let create = query::object::CreateTable::new(name, schema);
+        db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    let users = Object::find(db, "users").await?.try_into_table()?;
+    let orders = Object::find(db, "orders").await?.try_into_table()?;
+    Ok((users, orders))
+}
+
+async fn insert(db: &Db, table: &TableObject, rows: Vec<Vec<(&str, Value)>>) -> DbResult<()> {
+    let rows = rows.into_iter().map(|row| {
+        let row = row
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value));
+        Values::from(row.collect::<HashMap<_, _>>())
+    });
+    let bulk_insert = query::table::BulkInsert::new(table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+fn user(id: i32) -> Vec<(&'static str, Value)> {
+    vec![
+        ("id", Value::Int(id)),
+        ("name", Value::Text(format!("user-{id}"))),
+    ]
+}
+
+fn order(id: i32, user_id: i32) -> Vec<(&'static str, Value)> {
+    vec![
+        ("id", Value::Int(id)),
+        ("user_id", Value::Int(user_id)),
+        ("item", Value::Text(format!("item-{id}"))),
+    ]
+}
+
+async fn temp_seq_count(db: &Db) -> DbResult<usize> {
+    db.pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.temp_seq_page_ids.len()
+        })
+        .await
+}
+
+/// Runs the join, returning the sorted `(order id, user name)` pairs.
+async fn run<Q>(db: &Db, join: Q) -> DbResult<Vec<(i32, String)>>
+where
+    Q: for<'a> Query<Item<'a> = Values>,
+{
+    let mut rows = Vec::new();
+    db.execute(join, |row| {
+        let order_id = *row.get("orders.id").unwrap().try_cast_int_ref().unwrap();
+        let user_id = row.get("orders.user_id").unwrap();
+        assert_eq!(row.get("users.id"), Some(user_id));
+        let name = row.get("users.name").unwrap().try_cast_text_ref().unwrap();
+        rows.push((order_id, name.to_string()));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    rows.sort();
+    Ok(rows)
+}
+
+#[tokio::test]
+async fn test_hash_join() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let (users, orders) = create_tables(&db).await?;
+
+    insert(&db, &users, (1..=4).map(user).collect()).await?;
+    // User 4 has no orders and user 9 doesn't exist.
+    let rows = vec![
+        order(10, 1),
+        order(11, 2),
+        order(12, 1),
+        order(13, 9),
+        order(14, 3),
+    ];
+    insert(&db, &orders, rows).await?;
+    // Deleted records are skipped.
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() == 14;
+    let delete = query::table::Delete::new(&orders, &pred);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let expected = vec![
+        (10, "user-1".to_string()),
+        (11, "user-2".to_string()),
+        (12, "user-1".to_string()),
+    ];
+    // Both tables may be the build side.
+    let join = query::table::HashJoin::new(&orders, "user_id", &users, "id");
+    assert_eq!(run(&db, join).await?, expected);
+    let join = query::table::HashJoin::new(&users, "id", &orders, "user_id");
+    assert_eq!(run(&db, join).await?, expected);
+
+    // A join with an empty table yields nothing.
+    let create = query::object::CreateTable::new("no_users", users.schema.clone());
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let empty = Object::find(&db, "no_users").await?.try_into_table()?;
+    let join = query::table::HashJoin::new(&orders, "user_id", &empty, "id")
+        .with_prefixes("orders", "users");
+    assert_eq!(run(&db, join).await?, []);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hash_join_partitioned() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let (users, orders) = create_tables(&db).await?;
+
+    insert(&db, &users, (0..60).map(user).collect()).await?;
+    insert(
+        &db,
+        &orders,
+        (0..150).map(|id| order(id, id % 70)).collect(),
+    )
+    .await?;
+    let expected: Vec<_> = (0..150)
+        .filter(|id| id % 70 < 60)
+        .map(|id| (id, format!("user-{}", id % 70)))
+        .collect();
+
+    let join = query::table::HashJoin::new(&orders, "user_id", &users, "id").with_build_size(8);
+    assert_eq!(run(&db, join).await?, expected);
+
+    // The partitions are released once joined.
+    assert_eq!(temp_seq_count(&db).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_self_join() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let rows = (0..4).map(|id| {
+        Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text(format!("{}", id / 2))),
+            ("bool".into(), Value::Bool(true)),
+        ]))
+    });
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let join = query::table::HashJoin::new(&table, "text", &table, "text");
+    let error = db.execute(join, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(error.to_string().contains("ambiguous join prefix"));
+
+    let mut pairs = Vec::new();
+    let join = query::table::HashJoin::new(&table, "text", &table, "text").with_prefixes("a", "b");
+    db.execute(join, |row| {
+        let a = *row.get("a.id").unwrap().try_cast_int_ref().unwrap();
+        let b = *row.get("b.id").unwrap().try_cast_int_ref().unwrap();
+        pairs.push((a, b));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    pairs.sort();
+    assert_eq!(
+        pairs,
+        [
+            (0, 0),
+            (0, 1),
+            (1, 0),
+            (1, 1),
+            (2, 2),
+            (2, 3),
+            (3, 2),
+            (3, 3)
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hash_join_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let (users, orders) = create_tables(&db).await?;
+
+    let join = query::table::HashJoin::new(&orders, "item", &users, "id");
+    let error = db.execute(join, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("can't join column `item` of type `text`"));
+
+    let join = query::table::HashJoin::new(&orders, "nope", &users, "id");
+    let error = db.execute(join, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(error.to_string().contains("column `nope` does not exist"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_merge_join() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let (users, orders) = create_tables(&db).await?;
+
+    // Users 1 and 3 are duplicated, so that there are many-to-many matches.
+    let rows = [3, 1, 4, 1, 3, 5].into_iter().map(user).collect();
+    insert(&db, &users, rows).await?;
+    let rows = [(10, 3), (11, 1), (12, 9), (13, 3), (14, 2), (15, 1)];
+    insert(
+        &db,
+        &orders,
+        rows.map(|(id, user_id)| order(id, user_id)).into(),
+    )
+    .await?;
+
+    let mut keys = Vec::new();
+    let join = query::table::MergeJoin::new(&orders, "user_id", &users, "id");
+    db.execute(join, |row| {
+        keys.push(*row.get("users.id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    // Rows are yielded in the join key order.
+    assert_eq!(keys, [1, 1, 1, 1, 3, 3, 3, 3]);
+
+    let join = query::table::MergeJoin::new(&orders, "user_id", &users, "id");
+    let merged = run(&db, join).await?;
+    let join = query::table::HashJoin::new(&orders, "user_id", &users, "id");
+    assert_eq!(merged, run(&db, join).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_merge_join_external() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let (users, orders) = create_tables(&db).await?;
+
+    insert(&db, &users, (0..60).rev().map(user).collect()).await?;
+    insert(
+        &db,
+        &orders,
+        (0..150).map(|id| order(id, id % 70)).collect(),
+    )
+    .await?;
+    let expected: Vec<_> = (0..150)
+        .filter(|id| id % 70 < 60)
+        .map(|id| (id, format!("user-{}", id % 70)))
+        .collect();
+
+    let join = query::table::MergeJoin::new(&orders, "user_id", &users, "id").with_run_size(8);
+    assert_eq!(run(&db, join).await?, expected);
+    // The tapes of both sorts are released, even though the users are
+    // exhausted before the orders.
+    assert_eq!(temp_seq_count(&db).await?, 0);
+
+    let join = query::table::MergeJoin::new(&orders, "item", &users, "id");
+    let error = db.execute(join, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(error.to_string().contains("can't join column `item`"));
+
+    Ok(())
+}
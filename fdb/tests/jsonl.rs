@@ -0,0 +1,168 @@
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error, ErrorContext},
+    exec::{query, value::Value},
+    Db,
+};
+
+mod test_utils;
+
+const COLUMNS: [&str; 4] = ["id", "data", "at", "tags"];
+
+/// A JSONL file path, whose file is removed on drop.
+struct JsonlFile(String);
+
+impl JsonlFile {
+    fn new(name: &str) -> JsonlFile {
+        std::fs::create_dir_all("ignore").unwrap();
+        JsonlFile(format!("ignore/{name}"))
+    }
+
+    fn with_contents(name: &str, contents: &str) -> JsonlFile {
+        let file = JsonlFile::new(name);
+        std::fs::write(&file.0, contents).unwrap();
+        file
+    }
+}
+
+impl Drop for JsonlFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+async fn create_events(db: &Db, name: &str) -> DbResult<()> {
+    let column = |name: &str, ty| Column {
+        ty,
+        name: name.into(),
+        max_len: None,
+        default: None,
+        not_null: false,
+    };
+    let schema = TableSchema {
+        columns: vec![
+            column("id", TypeId::Primitive(PrimitiveTypeId::Int)),
+            column("data", TypeId::Primitive(PrimitiveTypeId::Blob)),
+            column("at", TypeId::Primitive(PrimitiveTypeId::Timestamp)),
+            column("tags", TypeId::Array(PrimitiveTypeId::Text)),
+        ],
+    };
+    let create = query::object::CreateTable::new(name, schema);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+/// Returns the rows of the given table (in the [`COLUMNS`] order), ordered by
+/// `id`.
+async fn rows(db: &Db, table: &str) -> DbResult<Vec<Vec<Value>>> {
+    let table = Object::find(db, table).await?.try_into_table()?;
+    let mut rows = Vec::new();
+    db.execute(query::table::Select::new(&table), |values| {
+        rows.push(
+            COLUMNS
+                .iter()
+                .map(|column| values.get(column).unwrap().clone())
+                .collect::<Vec<_>>(),
+        );
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    rows.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
+    Ok(rows)
+}
+
+async fn import(db: &Db, table: &str, path: &str) -> DbResult<usize> {
+    let table = Object::find(db, table).await?.try_into_table()?;
+    let mut count = 0;
+    let import = query::table::ImportJsonl::new(&table, path);
+    db.execute(import, |()| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(count)
+}
+
+#[tokio::test]
+async fn test_jsonl_round_trip() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_events(&db, "events").await?;
+    let input = JsonlFile::with_contents(
+        "jsonl-input.jsonl",
+        "{\"id\": 1, \"data\": \"yv4=\", \"at\": 1678795200000, \"tags\": [\"a\", \"b\"]}\n\
+         \n\
+         {\"id\": 2, \"tags\": null}\n",
+    );
+    assert_eq!(import(&db, "events", &input.0).await?, 2);
+    let imported = rows(&db, "events").await?;
+    assert_eq!(
+        imported[0],
+        [
+            Value::Int(1),
+            Value::Blob(b"\xCA\xFE".to_vec()),
+            Value::Timestamp(1_678_795_200_000),
+            Value::Array(
+                PrimitiveTypeId::Text,
+                vec![Value::Text("a".into()), Value::Text("b".into())]
+            ),
+        ]
+    );
+    // Missing and null fields take the default values.
+    assert_eq!(imported[1][1], Value::Blob(Vec::new()));
+    assert_eq!(
+        imported[1][3],
+        Value::Array(PrimitiveTypeId::Text, Vec::new())
+    );
+
+    let output = JsonlFile::new("jsonl-output.jsonl");
+    let table = Object::find(&db, "events").await?.try_into_table()?;
+    let export = query::table::ExportJsonl::new(query::table::Select::new(&table), &output.0);
+    let mut count = 0;
+    db.execute(export, |()| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(count, 2);
+    let contents = std::fs::read_to_string(&output.0).unwrap();
+    assert!(contents.contains("\"data\":\"yv4=\""));
+
+    create_events(&db, "copy").await?;
+    assert_eq!(import(&db, "copy", &output.0).await?, 2);
+    assert_eq!(rows(&db, "copy").await?, imported);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_jsonl_import_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_events(&db, "events").await?;
+
+    for (contents, line) in [
+        ("{\"id\": 1}\n{\"id\": \"two\"}\n", 2),
+        ("{\"id\": 1}\n\n{\"id\": 3, \"name\": \"x\"}\n", 3),
+        ("[1]\n", 1),
+        ("{\"id\": \n", 1),
+    ] {
+        let file = JsonlFile::with_contents("jsonl-errors.jsonl", contents);
+        let error = import(&db, "events", &file.0).await.unwrap_err();
+        assert!(matches!(error.root(), Error::Cast(_) | Error::ExecError(_)));
+        assert!(error
+            .contexts()
+            .any(|context| matches!(context, ErrorContext::Line { line: l, .. } if *l == line)));
+    }
+
+    // No rows are imported from a malformed file.
+    assert!(rows(&db, "events").await?.is_empty());
+
+    Ok(())
+}
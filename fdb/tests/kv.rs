@@ -0,0 +1,92 @@
+use fdb::{catalog::object::Object, error::DbResult};
+
+mod test_utils;
+
+#[tokio::test]
+async fn test_kv_put_get_delete() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let kv = db.kv("settings");
+
+    // The namespace doesn't exist until the first write.
+    assert_eq!(kv.get(b"theme").await?, None);
+    assert!(!kv.delete(b"theme").await?);
+    assert!(Object::find(&db, "__kv_settings").await.is_err());
+
+    kv.put(b"theme", b"dark").await?;
+    kv.put(b"lang", b"en").await?;
+    assert_eq!(kv.get(b"theme").await?.as_deref(), Some(&b"dark"[..]));
+    assert_eq!(kv.get(b"lang").await?.as_deref(), Some(&b"en"[..]));
+
+    // Puts replace the previous value, even if it doesn't fit in place.
+    kv.put(b"theme", &[7; 300]).await?;
+    assert_eq!(kv.get(b"theme").await?, Some(vec![7; 300]));
+    assert_eq!(kv.scan_prefix(b"").await?.len(), 2);
+
+    assert!(kv.delete(b"theme").await?);
+    assert!(!kv.delete(b"theme").await?);
+    assert_eq!(kv.get(b"theme").await?, None);
+    assert_eq!(kv.get(b"lang").await?.as_deref(), Some(&b"en"[..]));
+
+    // Namespaces are independent.
+    assert_eq!(db.kv("other").get(b"lang").await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_kv_scan_prefix() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let kv = db.kv("scan");
+
+    for key in [
+        &b"user:2"[..],
+        b"user:1",
+        b"users",
+        b"user",
+        b"use",
+        b"\xff\xff",
+    ] {
+        kv.put(key, &key[key.len() - 1..]).await?;
+    }
+
+    let keys = |pairs: Vec<(Vec<u8>, Vec<u8>)>| -> Vec<Vec<u8>> {
+        pairs.into_iter().map(|(key, _)| key).collect()
+    };
+    assert_eq!(
+        keys(kv.scan_prefix(b"user:").await?),
+        [b"user:1".to_vec(), b"user:2".to_vec()]
+    );
+    assert_eq!(
+        keys(kv.scan_prefix(b"user").await?),
+        [&b"user"[..], b"user:1", b"user:2", b"users"]
+    );
+    assert_eq!(keys(kv.scan_prefix(b"\xff").await?), [b"\xff\xff"]);
+    assert_eq!(kv.scan_prefix(b"").await?.len(), 6);
+    assert!(kv.scan_prefix(b"x").await?.is_empty());
+
+    let pairs = kv.scan_prefix(b"user:1").await?;
+    assert_eq!(pairs, [(b"user:1".to_vec(), b"1".to_vec())]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_kv_many_namespaces() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+
+    // Each namespace adds a table and an index to the database schema, which
+    // then spans many pages.
+    for i in 0..100 {
+        db.kv(format!("namespace_{i}"))
+            .put(b"id", i.to_string().as_bytes())
+            .await?;
+    }
+    db.reopen().await?;
+
+    for i in 0..100 {
+        let value = db.kv(format!("namespace_{i}")).get(b"id").await?;
+        assert_eq!(value, Some(i.to_string().into_bytes()));
+    }
+
+    Ok(())
+}
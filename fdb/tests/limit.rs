@@ -0,0 +1,113 @@
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{
+        query::{
+            self,
+            table::{Sort, SortKey},
+            Limit, Query, RecordSource,
+        },
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+fn row(id: i32) -> Values {
+    test_utils::row(id, format!("text-{id}"), true)
+}
+
+fn id_of(values: &Values) -> i32 {
+    *values.get("id").unwrap().try_cast_int_ref().unwrap()
+}
+
+async fn ids<Q>(db: &Db, query: Q) -> DbResult<Vec<i32>>
+where
+    Q: for<'a> Query<Item<'a> = Values>,
+{
+    let mut ids = Vec::new();
+    db.execute(query, |values| {
+        ids.push(id_of(&values));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(ids)
+}
+
+/// Returns the number of page loads since the database was opened.
+async fn page_loads(db: &Db) -> DbResult<u64> {
+    let health = db.health().await?;
+    Ok(health.cache_hits + health.cache_misses)
+}
+
+#[tokio::test]
+async fn test_limit() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    test_utils::fill(&db, (0..300).map(row)).await?;
+
+    let select = query::table::Select::new(&table).limit(5);
+    assert_eq!(ids(&db, select).await?, [0, 1, 2, 3, 4]);
+    let select = query::table::Select::new(&table).limit(3).with_offset(10);
+    assert_eq!(ids(&db, select).await?, [10, 11, 12]);
+    let select = query::table::Select::new(&table).limit(10).with_offset(295);
+    assert_eq!(ids(&db, select).await?, [295, 296, 297, 298, 299]);
+    let select = query::table::Select::new(&table).limit(10).with_offset(300);
+    assert!(ids(&db, select).await?.is_empty());
+    let select = query::table::Select::new(&table).limit(0);
+    assert!(ids(&db, select).await?.is_empty());
+
+    // The limit also applies to arbitrary queries, e.g., a filtered select.
+    let select = query::table::Select::with_filter(&table, "id", Value::Int(100)..);
+    assert_eq!(ids(&db, Limit::new(select, 2)).await?, [100, 101]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limit_stops_scan_early() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    test_utils::fill(&db, (0..300).map(row)).await?;
+
+    let before = page_loads(&db).await?;
+    let select = query::table::Select::new(&table).limit(5);
+    assert_eq!(ids(&db, select).await?.len(), 5);
+    let limited = page_loads(&db).await? - before;
+
+    let before = page_loads(&db).await?;
+    let select = query::table::Select::new(&table);
+    assert_eq!(ids(&db, select).await?.len(), 300);
+    let full = page_loads(&db).await? - before;
+
+    assert!(limited < full, "limited: {limited}, full: {full}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limit_sort() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    test_utils::fill(&db, (0..50).rev().map(row)).await?;
+
+    let keys = vec![SortKey::asc("id")];
+    let sort = Sort::new(query::table::Select::new(&table), keys.clone()).limit(3);
+    assert_eq!(ids(&db, sort).await?, [0, 1, 2]);
+
+    // A limit is also a record source.
+    let sort = Sort::new(query::table::Select::new(&table), keys);
+    let mut limit = sort.limit(2).with_offset(47);
+    let peeked = RecordSource::peek(&mut limit, &db).await?.unwrap();
+    assert_eq!(id_of(&peeked.to_values(&table.schema)), 47);
+    let mut yielded = Vec::new();
+    while let Some(record) = RecordSource::next(&mut limit, &db).await? {
+        yielded.push(id_of(&record.into_values(&table.schema)));
+    }
+    assert_eq!(yielded, [47, 48]);
+
+    Ok(())
+}
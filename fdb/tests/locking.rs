@@ -0,0 +1,183 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use fdb::{
+    catalog::{object::Object, page::PageId},
+    error::{DbResult, Error},
+    exec::{
+        locking::{LockManager, LockMode},
+        query::{self, table::RecordId},
+        value::Value,
+        values::Values,
+    },
+    DbOptions,
+};
+
+mod test_utils;
+
+fn id(offset: u16) -> RecordId {
+    RecordId::new(PageId::new_u32(10), offset, 0)
+}
+
+/// Waits until the given number of owners are waiting for a lock.
+async fn wait_for_waiters(locks: &LockManager, count: usize) {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while locks.waiting_count() != count {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    })
+    .await
+    .expect("timed out waiting for lock waiters");
+}
+
+#[tokio::test]
+async fn test_lock_modes() -> DbResult<()> {
+    let locks = Arc::new(LockManager::new());
+    let mut a = locks.owner();
+    let mut b = locks.owner();
+
+    // Shared locks are compatible.
+    assert!(!a.lock(id(1), LockMode::Shared).await?);
+    assert!(!b.lock(id(1), LockMode::Shared).await?);
+    assert_eq!(locks.locked_count(), 1);
+
+    // Exclusive locks wait for the other holders.
+    let waiter = tokio::spawn(async move {
+        let waited = a.lock(id(1), LockMode::Exclusive).await?;
+        DbResult::<_>::Ok((a, waited))
+    });
+    wait_for_waiters(&locks, 1).await;
+    drop(b);
+    let (mut a, waited) = waiter.await.unwrap()?;
+    assert!(waited);
+    assert_eq!(locks.waiting_count(), 0);
+
+    // Re-locking a held record doesn't wait.
+    assert!(!a.lock(id(1), LockMode::Shared).await?);
+    assert!(!a.lock(id(2), LockMode::Exclusive).await?);
+    assert_eq!(a.len(), 2);
+    drop(a);
+    assert_eq!(locks.locked_count(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deadlock_detection() -> DbResult<()> {
+    let locks = Arc::new(LockManager::new());
+    let mut a = locks.owner();
+    let mut b = locks.owner();
+    a.lock(id(1), LockMode::Exclusive).await?;
+    b.lock(id(2), LockMode::Exclusive).await?;
+
+    // `b` waits for `a`, hence `a` can't wait for `b`.
+    let waiter = tokio::spawn(async move {
+        b.lock(id(1), LockMode::Exclusive).await?;
+        DbResult::<_>::Ok(b)
+    });
+    wait_for_waiters(&locks, 1).await;
+    let error = a.lock(id(2), LockMode::Exclusive).await.unwrap_err();
+    assert!(matches!(error, Error::Deadlock));
+
+    // Once `a` is aborted, `b` proceeds.
+    drop(a);
+    let b = waiter.await.unwrap()?;
+    assert_eq!(b.len(), 2);
+    drop(b);
+    assert_eq!(locks.locked_count(), 0);
+    assert_eq!(locks.waiting_count(), 0);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_update_waits_for_record_lock() -> DbResult<()> {
+    let db = Arc::new(test_utils::TestDb::new_temp(None).await?);
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    for id in 0..3 {
+        let values = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("a".into())),
+            ("bool".into(), Value::Bool(true)),
+        ]));
+        let insert = query::table::Insert::new(&table, values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    let mut select = query::table::Select::new(&table);
+    let (record_id, _) = select.next_with_id(&db).await?.unwrap();
+    drop(select);
+
+    let mut holder = db.locks().owner();
+    holder.lock(record_id, LockMode::Exclusive).await?;
+
+    let update = tokio::spawn({
+        let db = Arc::clone(&db);
+        async move {
+            let table = Object::find(&db, "test_table").await?.try_into_table()?;
+            let updater = |values: &mut Values| {
+                values.set("text".into(), Value::Text("b".into()));
+            };
+            let update = query::table::Update::new(&table, &|_| true, &updater);
+            db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+            DbResult::<_>::Ok(())
+        }
+    });
+    wait_for_waiters(db.locks(), 1).await;
+    assert!(!update.is_finished());
+
+    drop(holder);
+    update.await.unwrap()?;
+    assert_eq!(db.locks().locked_count(), 0);
+
+    let mut texts = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        texts.push(row.get("text").unwrap().clone());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(texts, vec![Value::Text("b".into()); 3]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_lock_timeout() -> DbResult<()> {
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_lock_timeout(Duration::from_millis(100));
+    let db = Arc::new(test_utils::TestDb::new_temp_with_options(options).await?);
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    test_utils::fill(&db, (0..3).map(|id| test_utils::row(id, "a", true))).await?;
+    let mut select = query::table::Select::new(&table);
+    let (record_id, _) = select.next_with_id(&db).await?.unwrap();
+    drop(select);
+
+    let mut holder = db.locks().owner();
+    holder.lock(record_id, LockMode::Exclusive).await?;
+    let update = tokio::spawn({
+        let db = Arc::clone(&db);
+        async move {
+            let table = Object::find(&db, "test_table").await?.try_into_table()?;
+            let updater = |values: &mut Values| {
+                values.set("text".into(), Value::Text("b".into()));
+            };
+            let update = query::table::Update::new(&table, &|_| true, &updater);
+            db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+            DbResult::<_>::Ok(())
+        }
+    });
+    wait_for_waiters(db.locks(), 1).await;
+
+    // The holder waits for the statement latch, held by the waiting update,
+    // before releasing its lock. The update times out.
+    let delete = query::table::Delete::new(&table, &|_| false);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    let error = update.await.unwrap().unwrap_err();
+    assert!(matches!(error.root(), Error::Deadlock), "{error}");
+    drop(holder);
+    assert_eq!(db.locks().locked_count(), 0);
+    assert_eq!(db.locks().waiting_count(), 0);
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        column::Column,
+        object::Object,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+fn column(name: &str, ty: PrimitiveTypeId, max_len: Option<u16>) -> Column {
+    Column {
+        ty: TypeId::Primitive(ty),
+        name: name.into(),
+        max_len,
+        default: None,
+        not_null: false,
+    }
+}
+
+fn schema() -> TableSchema {
+    TableSchema {
+        columns: vec![
+            column("id", PrimitiveTypeId::Int, None),
+            column("name", PrimitiveTypeId::Text, Some(8)),
+            column("avatar", PrimitiveTypeId::Blob, Some(4)),
+        ],
+    }
+}
+
+fn row(id: i32, name: &str, avatar: &[u8]) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("name".into(), Value::Text(name.into())),
+        ("avatar".into(), Value::Blob(avatar.to_vec())),
+    ]))
+}
+
+fn assert_too_long(error: Error, expected_column: &str, expected_len: usize) {
+    match error {
+        Error::ValueTooLong { column, len, .. } => {
+            assert_eq!((column.as_str(), len), (expected_column, expected_len));
+        }
+        error => panic!("unexpected error: {error}"),
+    }
+}
+
+#[tokio::test]
+async fn test_column_max_len() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    let create = query::object::CreateTable::new("people", schema());
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // The maximum lengths are persisted in the catalog.
+    db.reopen().await?;
+    let table = Object::find(&db, "people").await?.try_into_table()?;
+    let max_lens: Vec<_> = table.schema.columns.iter().map(|c| c.max_len).collect();
+    assert_eq!(max_lens, [None, Some(8), Some(4)]);
+
+    // Lengths are in bytes, and the limit is inclusive.
+    let insert = query::table::Insert::new(&table, row(1, "joão-jo", b"\x01\x02\x03\x04"));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let insert = query::table::Insert::new(&table, row(2, "joão-joe", b""));
+    let error = db.execute(insert, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert_too_long(error, "name", 9);
+    let insert = query::table::Insert::new(&table, row(2, "", b"12345"));
+    let error = db.execute(insert, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert_too_long(error, "avatar", 5);
+
+    let error = db
+        .execute_sql("UPDATE people SET name = 'too long name'")
+        .await
+        .unwrap_err();
+    assert_too_long(error, "name", 13);
+    let error = db
+        .execute_sql("INSERT INTO people VALUES (3, 'ok', x'0102030405')")
+        .await
+        .unwrap_err();
+    assert_too_long(error, "avatar", 5);
+
+    // The failed statements left the table untouched.
+    let mut rows = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        rows.push(row);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(rows, [row(1, "joão-jo", b"\x01\x02\x03\x04")]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_column_max_len_type() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let schema = TableSchema {
+        columns: vec![column("id", PrimitiveTypeId::Int, Some(4))],
+    };
+    let create = query::object::CreateTable::new("invalid", schema);
+    let error = db.execute(create, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(matches!(error, Error::ExecError(_)), "{error}");
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{operations::merge::KWayMerge, query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+#[tokio::test]
+async fn test_k_way_merge() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    for i in 0..30 {
+        let ins = query::table::Insert::new(
+            &table,
+            Values::from(HashMap::from([
+                ("id".into(), Value::Int(i)),
+                ("text".into(), Value::Text(format!("row {i}"))),
+            ])),
+        );
+        db.execute(ins, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+
+    // Since the rows were inserted in order, each of the following sources is
+    // sorted by `id`.
+    let mut sources = Vec::new();
+    for k in 0..3 {
+        let select = query::table::Select::new(&table);
+        let cte = query::table::Cte::materialize_where(&db, format!("mod_{k}"), select, |row| {
+            row.get("id").unwrap().try_cast_int_ref().unwrap() % 3 == k
+        })
+        .await?;
+        sources.push(cte.scan());
+    }
+    // An empty source must not affect the merge.
+    let select = query::table::Select::new(&table);
+    let empty = query::table::Cte::materialize_where(&db, "empty", select, |_| false).await?;
+    sources.push(empty.scan());
+
+    let id = |row: &Values| *row.get("id").unwrap().try_cast_int_ref().unwrap();
+    let merge = KWayMerge::new(sources, |a: &Values, b: &Values| id(a).cmp(&id(b)));
+    assert_eq!(merge.k(), 4);
+
+    let mut ids = Vec::new();
+    db.execute(merge, |row| {
+        ids.push(id(&row));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(ids, (0..30).collect::<Vec<_>>());
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+    io::metrics::MetricsSnapshot,
+    Db,
+};
+
+mod test_utils;
+
+/// Returns the given number of rows, with distinct texts.
+fn rows(count: i32) -> impl Iterator<Item = Values> {
+    (0..count).map(|id| test_utils::row(id, format!("name-{id}"), id % 2 == 0))
+}
+
+/// Selects the rows whose `id` is within `[start, end)`, returning their count
+/// along with the metrics of the query.
+async fn select(db: &Db, start: i32, end: i32) -> DbResult<(usize, MetricsSnapshot)> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let select =
+        query::table::Select::with_filter(&table, "id", Value::Int(start)..Value::Int(end));
+    let mut count = 0;
+    let (result, metrics) = db
+        .execute_with_metrics(select, |_| {
+            count += 1;
+            Ok::<_, ()>(())
+        })
+        .await?;
+    result.unwrap();
+    Ok((count, metrics))
+}
+
+#[tokio::test]
+async fn test_query_metrics() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(100)).await?;
+
+    let (count, metrics) = select(&db, 10, 20).await?;
+    assert_eq!(count, 10);
+    assert_eq!(metrics.records_scanned, 100);
+    assert_eq!(metrics.records_matched, 10);
+    assert_eq!(metrics.pages_written, 0);
+    assert!(metrics.cache_hits > 0);
+
+    let create = query::index::Create::new("test_table_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // The index scan only reads the records within the range.
+    let (count, metrics) = select(&db, 10, 20).await?;
+    assert_eq!(count, 10);
+    assert_eq!(metrics.records_scanned, 10);
+    assert_eq!(metrics.records_matched, 10);
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() >= 90;
+    let delete = query::table::Delete::new(&table, &pred);
+    let (result, metrics) = db.execute_with_metrics(delete, |_| Ok::<_, ()>(())).await?;
+    result.unwrap();
+    assert_eq!(metrics.records_scanned, 100);
+    assert_eq!(metrics.records_matched, 10);
+    assert!(metrics.pages_written > 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stats_metrics() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(50)).await?;
+    let before = db.stats().await?.metrics;
+    assert!(before.pages_written > 0);
+
+    db.reopen().await?;
+    // The counters restart when the database is opened.
+    let reopened = db.stats().await?.metrics;
+    assert!(reopened.pages_written < before.pages_written);
+
+    // The table pages aren't cached yet.
+    let (count, _) = select(&db, 0, 50).await?;
+    assert_eq!(count, 50);
+    let after = db.stats().await?.metrics;
+    let delta = after.since(&reopened);
+    assert_eq!(delta.records_scanned, 50);
+    assert_eq!(delta.records_matched, 50);
+    assert!(delta.pages_read > 0);
+    assert_eq!(delta.pages_read, delta.cache_misses);
+    assert!(delta.cache_hit_rate().is_some());
+
+    Ok(())
+}
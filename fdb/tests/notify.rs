@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{
+        notify::{Change, ChangeKind},
+        query::{self, table::RecordId},
+        value::Value,
+        values::Values,
+    },
+};
+use tokio::sync::broadcast::{error::TryRecvError, Receiver};
+
+mod test_utils;
+
+fn row(id: i32, text: &str) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(text.into())),
+        ("bool".into(), Value::Bool(true)),
+    ]))
+}
+
+fn id_of(values: &Values) -> i32 {
+    *values.get("id").unwrap().try_cast_int_ref().unwrap()
+}
+
+/// Returns the kinds and record IDs of the received changes.
+fn drain(receiver: &mut Receiver<Change>) -> Vec<(ChangeKind, RecordId)> {
+    let mut changes = Vec::new();
+    loop {
+        match receiver.try_recv() {
+            Ok(change) => {
+                assert_eq!(change.table, "test_table");
+                changes.push((change.kind, change.record_id));
+            }
+            Err(TryRecvError::Empty) => return changes,
+            Err(error) => panic!("unexpected error: {error}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let mut receiver = db.subscribe("test_table");
+    let mut other = db.subscribe("other_table");
+
+    let insert = query::table::Insert::new(&table, row(1, "a"));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let bulk_insert = query::table::BulkInsert::new(&table, [row(2, "b"), row(3, "c")]);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let mut ids = Vec::new();
+    let mut select = query::table::Select::new(&table);
+    while let Some((record_id, values)) = select.next_with_id(&db).await? {
+        ids.push((record_id, id_of(&values)));
+    }
+    let inserted = drain(&mut receiver);
+    assert_eq!(
+        inserted,
+        ids.iter()
+            .map(|(id, _)| (ChangeKind::Insert, *id))
+            .collect::<Vec<_>>()
+    );
+    // Reads don't notify.
+    db.execute(query::table::Select::new(&table), |_| Ok::<_, ()>(()))
+        .await?
+        .unwrap();
+    assert_eq!(drain(&mut receiver), []);
+
+    let pred = |values: &Values| id_of(values) == 2;
+    let updater = |values: &mut Values| values.set("text".into(), Value::Text("B".into()));
+    let update = query::table::Update::new(&table, &pred, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(drain(&mut receiver), [(ChangeKind::Update, ids[1].0)]);
+
+    // A relocated record is notified with its new ID.
+    let updater = |values: &mut Values| values.set("text".into(), Value::Text("BBBBBB".into()));
+    let update = query::table::Update::new(&table, &pred, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+    let changes = drain(&mut receiver);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].0, ChangeKind::Update);
+    assert_ne!(changes[0].1, ids[1].0);
+
+    let pred = |values: &Values| id_of(values) != 2;
+    let delete = query::table::Delete::new(&table, &pred);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(
+        drain(&mut receiver),
+        [
+            (ChangeKind::Delete, ids[0].0),
+            (ChangeKind::Delete, ids[2].0)
+        ]
+    );
+
+    assert_eq!(other.try_recv(), Err(TryRecvError::Empty));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_statement_is_not_notified() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let bulk_insert = query::table::BulkInsert::new(&table, [row(1, "a"), row(2, "b")]);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let mut receiver = db.subscribe("test_table");
+
+    // The first record is updated before the second one fails the update.
+    let pred = |_: &Values| true;
+    let updater = |values: &mut Values| {
+        let value = match id_of(values) {
+            1 => Value::Bool(false),
+            _ => Value::Int(0),
+        };
+        values.set("bool".into(), value);
+    };
+    let update = query::table::Update::new(&table, &pred, &updater);
+    assert!(db.execute(update, |_| Ok::<_, ()>(())).await.is_err());
+    assert_eq!(drain(&mut receiver), []);
+
+    // Statements stopped by the callback are notified, since their changes
+    // were applied.
+    let pred = |_: &Values| true;
+    let delete = query::table::Delete::new(&table, &pred);
+    assert_eq!(db.execute(delete, |_| Err(())).await?, Err(()));
+    assert_eq!(drain(&mut receiver).len(), 1);
+
+    // Dropped receivers are no longer notified.
+    drop(receiver);
+    let insert = query::table::Insert::new(&table, row(3, "c"));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let mut receiver = db.subscribe("test_table");
+    assert_eq!(drain(&mut receiver), []);
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use fdb::{
+    catalog::object::Object,
+    error::{DbResult, Error},
+    exec::{
+        query::{
+            self,
+            table::{Sort, SortKey},
+        },
+        value::{Decimal, Float, Value},
+    },
+    sql::planner::SqlOutput,
+    Db,
+};
+
+mod test_utils;
+
+async fn create_products(db: &Db) -> DbResult<()> {
+    db.execute_sql("CREATE TABLE products (id int, price decimal(2), weight float)")
+        .await?;
+    db.execute_sql(
+        "INSERT INTO products VALUES \
+         (1, 10.5, 0.25), \
+         (2, 3, 1.5), \
+         (3, -0.75, 12), \
+         (4, 10.49, -2.5)",
+    )
+    .await?;
+    Ok(())
+}
+
+fn price(unscaled: i64) -> Value {
+    Value::Decimal(Decimal { unscaled, scale: 2 })
+}
+
+async fn select_ids(db: &Db, filter: &str) -> DbResult<Vec<Value>> {
+    let sql = format!("SELECT id FROM products WHERE {filter}");
+    let SqlOutput::Rows { rows, .. } = db.execute_sql(&sql).await? else {
+        panic!("expected rows");
+    };
+    Ok(rows
+        .iter()
+        .map(|row| row.get("id").unwrap().clone())
+        .collect())
+}
+
+#[tokio::test]
+async fn test_numeric_values() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_products(&db).await?;
+
+    let SqlOutput::Rows { rows, .. } = db
+        .execute_sql("SELECT price, weight FROM products WHERE id = 3")
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows[0].get("price"), Some(&price(-75)));
+    assert_eq!(rows[0].get("weight"), Some(&Value::Float(Float(12.0))));
+
+    let ids = |ids: &[i32]| ids.iter().map(|&id| Value::Int(id)).collect::<Vec<_>>();
+    assert_eq!(select_ids(&db, "price > 10.4").await?, ids(&[1, 4]));
+    assert_eq!(select_ids(&db, "price = 3").await?, ids(&[2]));
+    assert_eq!(select_ids(&db, "weight < 1").await?, ids(&[1, 4]));
+    assert_eq!(select_ids(&db, "weight >= 1.5").await?, ids(&[2, 3]));
+
+    // Values which don't fit the column type are rejected.
+    for insert in [
+        "INSERT INTO products VALUES (5, 1.234, 0)",
+        "INSERT INTO products VALUES (5, 'x', 0)",
+        "INSERT INTO products VALUES (5, 92233720368547759, 0)",
+    ] {
+        let error = db.execute_sql(insert).await.unwrap_err();
+        assert!(matches!(error, Error::Cast(_)), "{insert}: {error}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_numeric_order() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    create_products(&db).await?;
+    let table = Object::find(&db, "products").await?.try_into_table()?;
+
+    let sort = Sort::new(
+        query::table::Select::new(&table),
+        vec![SortKey::asc("price")],
+    );
+    let mut prices = Vec::new();
+    db.execute(sort, |row| {
+        prices.push(row.get("price").unwrap().clone());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(prices, [price(-75), price(300), price(1049), price(1050)]);
+
+    // Index keys keep their types (and scales) across pages.
+    db.execute_sql("CREATE INDEX products_weight ON products (weight)")
+        .await?;
+    db.execute_sql("CREATE INDEX products_price ON products (price)")
+        .await?;
+    db.reopen().await?;
+    assert_eq!(select_ids(&db, "price >= 3.00").await?.len(), 3);
+    assert_eq!(select_ids(&db, "weight > 0").await?.len(), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_numeric_schema_script() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("CREATE TABLE amounts (total decimal(4), history decimal(1)[], ratio float)")
+        .await?;
+    let script = db.schema_script().await?;
+    assert!(script
+        .contains("CREATE TABLE amounts (total decimal(4), history decimal(1)[], ratio float);"));
+
+    for ty in ["decimal", "decimal(19)", "decimal(-1)"] {
+        let sql = format!("CREATE TABLE invalid (a {ty})");
+        assert!(db.execute_sql(&sql).await.is_err(), "{ty}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::{DbResult, Error},
+    exec::{query, value::Value, values::Values},
+    io::disk_manager::SyncMode,
+    Db, DbOptions,
+};
+
+mod test_utils;
+
+async fn insert(db: &Db, id: i32) -> DbResult<()> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let values = Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text("hello".into())),
+        ("bool".into(), Value::Bool(true)),
+    ]));
+    let insert = query::table::Insert::new(&table, values);
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+async fn ids(db: &Db) -> DbResult<Vec<i32>> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut ids = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |row| {
+        ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_page_size_mismatch() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(1024)).await?;
+    insert(&db, 1).await?;
+    db.checkpoint().await?;
+
+    let result = DbOptions::new().with_page_size(2048).open(db.path()).await;
+    assert!(matches!(
+        result,
+        Err(Error::PageSizeMismatch {
+            expected: 2048,
+            actual: 1024
+        })
+    ));
+
+    let (reopened, is_new) = DbOptions::new()
+        .with_page_size(1024)
+        .open(db.path())
+        .await?;
+    assert!(!is_new);
+    assert_eq!(ids(&reopened).await?, [1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_only() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(1024)).await?;
+    insert(&db, 1).await?;
+    db.checkpoint().await?;
+
+    let (read_only, _) = DbOptions::new()
+        .with_page_size(1024)
+        .with_read_only(true)
+        .open(db.path())
+        .await?;
+    assert!(read_only.is_read_only());
+    assert_eq!(ids(&read_only).await?, [1]);
+    assert!(matches!(insert(&read_only, 2).await, Err(Error::ReadOnly)));
+    assert!(matches!(
+        read_only.execute_sql("DELETE FROM test_table").await,
+        Err(Error::ReadOnly)
+    ));
+    assert!(matches!(read_only.checkpoint().await, Err(Error::ReadOnly)));
+    assert!(matches!(read_only.sync().await, Err(Error::ReadOnly)));
+
+    assert_eq!(ids(&db).await?, [1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_only_missing_file() -> DbResult<()> {
+    let result = DbOptions::new()
+        .with_read_only(true)
+        .open("ignore/missing-test.db".as_ref())
+        .await;
+    assert!(result.is_err());
+    assert!(!std::path::Path::new("ignore/missing-test.db").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_options() -> DbResult<()> {
+    let path = "ignore/invalid-options-test.db".as_ref();
+    let result = DbOptions::new().with_page_size(16).open(path).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    let result = DbOptions::new().with_cache_capacity(0).open(path).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cache_capacity_and_sync_modes() -> DbResult<()> {
+    let sync_modes = [
+        SyncMode::Off,
+        SyncMode::Checkpoint,
+        SyncMode::Normal,
+        SyncMode::Full,
+    ];
+    for sync_mode in sync_modes {
+        let options = DbOptions::new()
+            .with_page_size(1024)
+            .with_cache_capacity(2)
+            .with_sync_mode(sync_mode);
+        let mut db = test_utils::TestDb::new_temp_with_options(options).await?;
+        for id in 0..30 {
+            insert(&db, id).await?;
+        }
+        assert_eq!(ids(&db).await?, (0..30).collect::<Vec<_>>());
+        db.sync().await?;
+
+        db.reopen().await?;
+        assert_eq!(ids(&db).await?, (0..30).collect::<Vec<_>>());
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_first_access() -> DbResult<()> {
+    let path = std::path::PathBuf::from("ignore/concurrent-first-access-test.db");
+    std::fs::create_dir_all("ignore").unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let opens = (0..8).map(|_| {
+        let path = path.clone();
+        tokio::spawn(async move {
+            let (db, is_new) = Db::open_with_page_size(&path, 1024).await?;
+            if is_new {
+                test_utils::define_test_catalog(&db).await?;
+            }
+            db.pager().flush_all().await?;
+            Ok::<_, Error>(is_new)
+        })
+    });
+    let mut created = 0;
+    for open in opens.collect::<Vec<_>>() {
+        created += open.await.unwrap()? as u32;
+    }
+    assert_eq!(created, 1);
+
+    // No temporary files are left behind.
+    let leftovers = std::fs::read_dir("ignore")
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy()
+                .starts_with("concurrent-first-access-test.db.")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+
+    let (db, is_new) = Db::open_with_page_size(&path, 1024).await?;
+    assert!(!is_new);
+    insert(&db, 1).await?;
+    assert_eq!(ids(&db).await?, [1]);
+
+    drop(db);
+    std::fs::remove_file(&path).unwrap();
+    Ok(())
+}
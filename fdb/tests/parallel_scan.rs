@@ -0,0 +1,65 @@
+use std::{path::Path, sync::Arc};
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, values::Values},
+    Db,
+};
+
+fn id(values: &Values) -> i32 {
+    *values.get("id").unwrap().try_cast_int_ref().unwrap()
+}
+
+async fn scan_ids(db: &Arc<Db>, tasks: usize) -> DbResult<Vec<i32>> {
+    let table = Object::find(db, "items").await?.try_into_table()?;
+    let mut ids = Vec::new();
+    let scan = query::table::ParallelScan::new(Arc::clone(db), &table, tasks);
+    db.execute(scan, |values| {
+        ids.push(id(&values));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    ids.sort();
+    Ok(ids)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_parallel_scan() -> DbResult<()> {
+    let path = Path::new("ignore/parallel-scan-test.db");
+    let _ = std::fs::remove_file(path);
+    std::fs::create_dir_all("ignore").unwrap();
+    let (db, _) = Db::open_with_page_size(path, 512).await?;
+    let db = Arc::new(db);
+
+    db.execute_sql("CREATE TABLE items (id int, name text)")
+        .await?;
+    for chunk in (0..600).collect::<Vec<_>>().chunks(100) {
+        let rows: Vec<_> = (chunk.iter())
+            .map(|id| format!("({id}, 'item {id}')"))
+            .collect();
+        db.execute_sql(&format!("INSERT INTO items VALUES {}", rows.join(", ")))
+            .await?;
+    }
+    // A record which spans many pages.
+    let long = "x".repeat(2000);
+    db.execute_sql(&format!("INSERT INTO items VALUES (600, '{long}')"))
+        .await?;
+    db.execute_sql("DELETE FROM items WHERE id < 50").await?;
+
+    let expected: Vec<_> = (50..=600).collect();
+    for tasks in [1, 4, 1000] {
+        assert_eq!(scan_ids(&db, tasks).await?, expected, "{tasks} tasks");
+    }
+
+    // Scans stopped early don't wait for their tasks.
+    let table = Object::find(&db, "items").await?.try_into_table()?;
+    let scan = query::table::ParallelScan::new(Arc::clone(&db), &table, 4);
+    let result = db.execute(scan, |_| Err(())).await?;
+    assert_eq!(result, Err(()));
+
+    drop(db);
+    std::fs::remove_file(path).unwrap();
+    Ok(())
+}
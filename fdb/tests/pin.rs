@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{FirstPage, PageId},
+    },
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+    DbOptions,
+};
+
+mod test_utils;
+
+#[tokio::test]
+async fn test_pinned_pages_not_evicted() -> DbResult<()> {
+    let options = DbOptions::new().with_page_size(256).with_cache_capacity(2);
+    let db = test_utils::TestDb::new_temp_with_options(options).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let rows = (0..50).map(|id| {
+        Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("t".repeat(50))),
+        ]))
+    });
+    let insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let pager = db.pager();
+    let guard = pager.get::<FirstPage>(PageId::FIRST).await?;
+    let other_guard = pager.get::<FirstPage>(PageId::FIRST).await?;
+    assert_eq!(pager.pin_count(PageId::FIRST), 2);
+    drop(other_guard);
+    assert_eq!(pager.pin_count(PageId::FIRST), 1);
+
+    // An in-memory only change, which would be lost if the page was evicted
+    // and read back from the disk.
+    let marker = vec![PageId::new_u32(42)];
+    {
+        let mut page = guard.write().await;
+        page.hot_page_ids = marker.clone();
+    }
+
+    // Scanning the table churns the (tiny) page cache.
+    let mut count = 0;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(count, 50);
+
+    let reloaded = pager.get::<FirstPage>(PageId::FIRST).await?;
+    assert_eq!(reloaded.read().await.hot_page_ids, marker);
+    drop((guard, reloaded));
+    assert_eq!(pager.pin_count(PageId::FIRST), 0);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use fdb::{
+    catalog::object::Object,
+    error::{DbResult, Error},
+    exec::{
+        expr::{col, lit},
+        query::{
+            table::{Select, SortKey},
+            Pipeline, Query, RecordSource,
+        },
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+fn row(id: i32) -> Values {
+    test_utils::row(id, format!("text-{}", id % 3), id % 2 == 0)
+}
+
+fn id_of(values: &Values) -> i32 {
+    *values.get("id").unwrap().try_cast_int_ref().unwrap()
+}
+
+async fn collect<Q>(db: &Db, query: Q) -> DbResult<Vec<Values>>
+where
+    Q: for<'a> Query<Item<'a> = Values>,
+{
+    let mut rows = Vec::new();
+    db.execute(query, |values| {
+        rows.push(values);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(rows)
+}
+
+#[tokio::test]
+async fn test_pipeline() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    test_utils::fill(&db, (0..100).map(row)).await?;
+
+    let expr = col("id").gte(lit(Value::Int(10)));
+    let even = |values: &Values| id_of(values) % 2 == 0;
+    let pipeline = Select::new(&table)
+        .filter_expr(&expr)
+        .filter(&even)
+        .sort(vec![SortKey::desc("id")])
+        .limit(3)
+        .with_offset(1);
+    let ids: Vec<_> = collect(&db, pipeline).await?.iter().map(id_of).collect();
+    assert_eq!(ids, [96, 94, 92]);
+
+    // Operators apply in the order in which they are chained.
+    let pipeline = Select::new(&table).limit(20).filter_expr(&expr);
+    assert_eq!(collect(&db, pipeline).await?.len(), 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filter_source() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    test_utils::fill(&db, (0..10).map(row)).await?;
+
+    let expr = col("bool").eq(lit(Value::Bool(false)));
+    let mut filter = Select::new(&table).filter_expr(&expr);
+    let peeked = RecordSource::peek(&mut filter, &db).await?.unwrap();
+    assert_eq!(id_of(&peeked.to_values(&table.schema)), 1);
+    let mut ids = Vec::new();
+    while let Some(record) = RecordSource::next(&mut filter, &db).await? {
+        ids.push(id_of(&record.into_values(&table.schema)));
+    }
+    assert_eq!(ids, [1, 3, 5, 7, 9]);
+
+    // Expressions are type-checked against the source schema.
+    let expr = col("text").eq(lit(Value::Int(1)));
+    let filter = Select::new(&table).filter_expr(&expr);
+    let error = collect(&db, filter).await.unwrap_err();
+    assert!(matches!(error, Error::Cast(_)), "{error}");
+
+    Ok(())
+}
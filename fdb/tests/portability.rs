@@ -0,0 +1,168 @@
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use buff::{Buff, BuffRead};
+use fdb::{
+    catalog::{
+        page::{FirstPage, HeapPage, PageId, BYTE_ORDER_MARK_OFFSET},
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::value::Value,
+    util::{
+        checksum,
+        io::{Deserialize, DeserializeCtx, Serialize, Size},
+    },
+    Db,
+};
+
+/// Serializes the given value, checking it against the golden bytes and
+/// checking that they deserialize back to the value.
+fn check_value(value: Value, ty: TypeId, golden: &[u8]) -> DbResult<()> {
+    let mut bytes = vec![0; value.size() as usize];
+    value.serialize(&mut Buff::new(&mut bytes))?;
+    assert_eq!(bytes, golden, "{value:?}");
+    let deserialized = Value::deserialize(&mut BuffRead::new(&bytes), &ty)?;
+    assert_eq!(deserialized, value);
+    Ok(())
+}
+
+#[test]
+fn test_value_golden_bytes() -> DbResult<()> {
+    use PrimitiveTypeId::*;
+    let p = TypeId::Primitive;
+
+    check_value(Value::Bool(true), p(Bool), b"\x01")?;
+    check_value(Value::Byte(0xAB), p(Byte), b"\xAB")?;
+    check_value(Value::ShortInt(-2), p(ShortInt), b"\xFF\xFE")?;
+    check_value(Value::Int(0x0102_0304), p(Int), b"\x01\x02\x03\x04")?;
+    check_value(
+        Value::BigInt(-0x0102),
+        p(BigInt),
+        b"\xFF\xFF\xFF\xFF\xFF\xFF\xFE\xFE",
+    )?;
+    check_value(
+        Value::Timestamp(1),
+        p(Timestamp),
+        b"\x00\x00\x00\x00\x00\x00\x00\x01",
+    )?;
+    check_value(Value::Text("hé".into()), p(Text), b"\x03h\xC3\xA9")?;
+    check_value(Value::Blob(vec![7, 8]), p(Blob), b"\x02\x07\x08")?;
+    check_value(
+        Value::Array(Text, vec![Value::Text("a".into())]),
+        TypeId::Array(Text),
+        b"\x00\x01\x01a",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_header_golden_bytes() -> DbResult<()> {
+    let mut bytes = vec![0; 1024];
+    FirstPage::new(1024).serialize(&mut Buff::new(&mut bytes))?;
+
+    let golden: &[u8] = b"fdb format\
+        \x04\
+        \x04\x00\
+        \x00\x00\x00\x01\
+        \x00\x00\x00\x00\
+        \x00\x00\x00\x02\
+        \x00\x00\x00\x00\
+        \x01\x02\x03\x04";
+    assert_eq!(&bytes[..golden.len()], golden);
+    assert_eq!(&bytes[98..100], br"\0");
+
+    let page = FirstPage::deserialize(&mut BuffRead::new(&bytes))?;
+    assert_eq!(page.header.page_size, 1024);
+    assert_eq!(page.header.page_count, 1);
+    Ok(())
+}
+
+#[test]
+fn test_serialize_overflow() {
+    let overflows = |result: DbResult<()>| matches!(result, Err(Error::BufferOverflow(_)));
+
+    let mut bytes = vec![0; 64];
+    assert!(overflows(
+        FirstPage::new(1024).serialize(&mut Buff::new(&mut bytes))
+    ));
+    let mut bytes = vec![0; 512];
+    let page = HeapPage::new_seq_first(1024, PageId::new_u32(2));
+    assert!(overflows(page.serialize(&mut Buff::new(&mut bytes))));
+    let mut bytes = vec![0; 4];
+    let value = Value::Text("overflow".into());
+    assert!(overflows(value.serialize(&mut Buff::new(&mut bytes))));
+}
+
+/// Overwrites the given bytes of the first page of the given database file,
+/// updating the page checksum.
+fn patch(path: &Path, offset: usize, bytes: &[u8]) {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .unwrap();
+    let mut page = [0; 1024];
+    file.read_exact(&mut page).unwrap();
+    page[offset..offset + bytes.len()].copy_from_slice(bytes);
+    let checksum = checksum::crc32(&page[..1020]);
+    page[1020..].copy_from_slice(&checksum.to_be_bytes());
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.write_all(&page).unwrap();
+}
+
+/// Opens the database at the given path, returning the (root) error.
+async fn open_error(path: &Path) -> Error {
+    match Db::open_with_page_size(path, 1024).await {
+        Ok(_) => panic!("expected error"),
+        Err(error) => error.root().clone(),
+    }
+}
+
+#[tokio::test]
+async fn test_header_compatibility_check() -> DbResult<()> {
+    let path = PathBuf::from("ignore/portability-test.db");
+    std::fs::create_dir_all("ignore").unwrap();
+    let _ = std::fs::remove_file(&path);
+    let (db, is_new) = Db::open_with_page_size(&path, 1024).await?;
+    assert!(is_new);
+    db.pager().flush_all().await?;
+    drop(db);
+
+    // A file written with the opposite byte order.
+    let offset = BYTE_ORDER_MARK_OFFSET;
+    patch(&path, offset, b"\x04\x03\x02\x01");
+    let error = open_error(&path).await;
+    assert!(matches!(error, Error::IncompatibleFile(_)), "{error}");
+    patch(&path, offset, b"\x04\x03\x02\x00");
+    let error = open_error(&path).await;
+    assert!(matches!(error, Error::CorruptedHeader(_)), "{error}");
+
+    // Files created before the byte order mark are still opened.
+    patch(&path, offset, b"\x00\x00\x00\x00");
+    let (db, is_new) = Db::open_with_page_size(&path, 1024).await?;
+    assert!(!is_new);
+    drop(db);
+
+    // A newer file format version.
+    patch(&path, 10, b"\x05");
+    let error = open_error(&path).await;
+    assert!(matches!(error, Error::IncompatibleFile(_)), "{error}");
+    // Older ones, whose heap pages had no layout version, whose column
+    // definitions had no attributes, or whose lengths weren't varints.
+    for version in [b"\x03", b"\x02", b"\x01"] {
+        patch(&path, 10, version);
+        let error = open_error(&path).await;
+        assert!(matches!(error, Error::IncompatibleFile(_)), "{error}");
+    }
+
+    patch(&path, 10, b"\x04");
+    patch(&path, offset, b"\x01\x02\x03\x04");
+    Db::open_with_page_size(&path, 1024).await?;
+
+    std::fs::remove_file(&path).unwrap();
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashMap, time::Duration};
+
+use fdb::{
+    catalog::{object::Object, page::PageId},
+    error::DbResult,
+    exec::{operations::heap, query, value::Value, values::Values},
+    Db,
+};
+
+mod test_utils;
+
+/// Returns the rows of the prefetched table, without the `bool` column.
+fn rows() -> impl Iterator<Item = Values> {
+    (0..60).map(|id| {
+        Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("t".repeat(50))),
+        ]))
+    })
+}
+
+/// Waits (for a while) until all of the given pages are cached.
+async fn all_cached(db: &Db, page_ids: &[PageId]) -> bool {
+    for _ in 0..1000 {
+        if page_ids
+            .iter()
+            .all(|page_id| db.pager().is_cached(*page_id))
+        {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_prefetch() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(Some(256)).await?;
+    test_utils::fill(&db, rows()).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let page_ids = heap::page_ids(db.pager(), table.page_id).await?;
+    assert!(page_ids.len() > 10);
+
+    db.reopen().await?;
+    assert!(!page_ids
+        .iter()
+        .any(|page_id| db.pager().is_cached(*page_id)));
+    db.pager().prefetch(page_ids.clone());
+    assert!(all_cached(&db, &page_ids).await);
+
+    // Pages out of bounds are ignored.
+    let out_of_bounds = PageId::new_u32(100_000);
+    db.pager().prefetch([out_of_bounds]);
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(!db.pager().is_cached(out_of_bounds));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scan_read_ahead() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(Some(256)).await?;
+    test_utils::fill(&db, rows()).await?;
+    db.reopen().await?;
+
+    let before = db.stats().await?.metrics;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let mut ids = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |values| {
+        ids.push(*values.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(ids, (0..60).collect::<Vec<_>>());
+
+    // The pages are read from the disk once, either by the scan itself or by
+    // its read-ahead.
+    let delta = db.stats().await?.metrics.since(&before);
+    assert!(delta.pages_read > 10);
+    assert_eq!(delta.pages_read, delta.cache_misses);
+
+    Ok(())
+}
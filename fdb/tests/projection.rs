@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::{DbResult, Error},
+    exec::{query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+fn row(id: i32) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(format!("text-{id}"))),
+        ("bool".into(), Value::Bool(id % 2 == 0)),
+    ]))
+}
+
+#[tokio::test]
+async fn test_select_projection() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let bulk_insert = query::table::BulkInsert::new(&table, (0..3).map(row));
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    // Rows follow the schema order by default.
+    let mut select = query::table::Select::new(&table);
+    let row = select.next_row(&db).await?.unwrap();
+    assert_eq!(row.columns(), ["id", "text", "bool"]);
+    assert_eq!(
+        row.values(),
+        [
+            Value::Int(0),
+            Value::Text("text-0".into()),
+            Value::Bool(true)
+        ]
+    );
+
+    // And the projection order otherwise.
+    let mut select = query::table::Select::new(&table).with_columns(&["bool", "id"]);
+    let mut rows = Vec::new();
+    while let Some(row) = select.next_row(&db).await? {
+        assert_eq!(row.columns(), ["bool", "id"]);
+        assert_eq!(row.get("text"), None);
+        rows.push(row.into_values());
+    }
+    assert_eq!(
+        rows,
+        (0..3)
+            .map(|id| vec![Value::Bool(id % 2 == 0), Value::Int(id)])
+            .collect::<Vec<_>>()
+    );
+
+    // Projections also apply to the values maps yielded by the query.
+    let select =
+        query::table::Select::with_filter(&table, "id", Value::Int(1)..).with_columns(&["text"]);
+    let mut texts = Vec::new();
+    db.execute(select, |values| {
+        assert_eq!(values.get("id"), None);
+        texts.push(values.get("text").unwrap().clone());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(
+        texts,
+        [Value::Text("text-1".into()), Value::Text("text-2".into())]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_select_projection_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    for columns in [&["id", "missing"][..], &["id", "text", "id"]] {
+        let mut select = query::table::Select::new(&table).with_columns(columns);
+        let error = select.next_row(&db).await.unwrap_err();
+        assert!(matches!(error, Error::ExecError(_)), "{error}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use fdb::{
+    error::{DbResult, Error},
+    exec::query,
+    Db,
+};
+
+mod test_utils;
+
+async fn insert(db: &Db, id: i32) -> DbResult<()> {
+    test_utils::fill(db, [test_utils::row(id, "x".repeat(300), true)]).await
+}
+
+/// Inserts rows until the database is full, returning the number of rows.
+async fn insert_until_full(db: &Db) -> DbResult<i32> {
+    for id in 0.. {
+        match insert(db, id).await {
+            Ok(()) => (),
+            Err(Error::DatabaseFull { .. }) => return Ok(id),
+            Err(error) => return Err(error),
+        }
+    }
+    unreachable!()
+}
+
+#[tokio::test]
+async fn test_quota() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    let stats = db.stats().await?;
+    assert_eq!(stats.max_size, None);
+    assert_eq!(stats.available(), None);
+    assert_eq!(stats.size, stats.page_count as u64 * 1024);
+
+    // Four more pages (the size is rounded down).
+    db.set_max_size(Some(stats.size + 4 * 1024 + 1000));
+    let stats = db.stats().await?;
+    assert_eq!(stats.max_size, Some(stats.size + 4 * 1024));
+    assert_eq!(stats.available(), Some(4 * 1024));
+
+    let count = insert_until_full(&db).await?;
+    assert!(count > 0);
+    let full = db.stats().await?;
+    assert_eq!(full.page_count, stats.page_count + 4);
+    assert_eq!(full.available(), Some(0));
+    let error = insert(&db, -1).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!("database is full (maximum size is {} bytes)", full.size)
+    );
+
+    // Freed pages count as available space.
+    let drop = query::object::DropTable::new("test_table");
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+    let stats = db.stats().await?;
+    assert_eq!(stats.page_count, full.page_count);
+    assert_eq!(stats.free_page_count, 5);
+    assert_eq!(stats.available(), Some(5 * 1024));
+
+    test_utils::define_test_catalog(&db).await?;
+    assert_eq!(insert_until_full(&db).await?, count);
+
+    // Lifting the limit.
+    db.set_max_size(None);
+    insert(&db, -1).await?;
+    assert_eq!(db.stats().await?.page_count, full.page_count + 1);
+
+    Ok(())
+}
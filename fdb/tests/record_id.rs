@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::{Object, TableObject},
+    error::DbResult,
+    exec::{
+        query::{self, table::RecordId},
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+fn row(id: i32, text: &str) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(text.into())),
+        ("bool".into(), Value::Bool(true)),
+    ]))
+}
+
+fn id_of(values: &Values) -> i32 {
+    *values.get("id").unwrap().try_cast_int_ref().unwrap()
+}
+
+async fn list_ids(db: &Db, table: &TableObject) -> DbResult<Vec<(RecordId, i32)>> {
+    let mut select = query::table::Select::new(table);
+    let mut ids = Vec::new();
+    while let Some((record_id, values)) = select.next_with_id(db).await? {
+        ids.push((record_id, id_of(&values)));
+    }
+    Ok(ids)
+}
+
+async fn get(db: &Db, table: &TableObject, record_id: RecordId) -> DbResult<Option<Values>> {
+    let mut values = None;
+    let get = query::table::GetById::new(table, record_id);
+    db.execute(get, |row| {
+        values = Some(row);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(values)
+}
+
+#[tokio::test]
+async fn test_record_id() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let rows = (0..30).map(|id| row(id, "text"));
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let ids = list_ids(&db, &table).await?;
+    assert_eq!(ids.len(), 30);
+    assert!(ids.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    for &(record_id, id) in &ids {
+        let values = get(&db, &table, record_id).await?.unwrap();
+        assert_eq!(id_of(&values), id);
+    }
+
+    // In-place updates keep the record ID.
+    let (record_id, _) = ids[3];
+    let updater = |values: &mut Values| {
+        values.set("text".into(), Value::Text("t".into()));
+    };
+    let mut new_ids = Vec::new();
+    let update = query::table::UpdateById::new(&table, record_id, &updater);
+    db.execute(update, |id| {
+        new_ids.push(id);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(new_ids, [record_id]);
+
+    // Updates that don't fit relocate the record.
+    let updater = |values: &mut Values| {
+        values.set("text".into(), Value::Text("long text".repeat(10)));
+    };
+    let mut new_ids = Vec::new();
+    let update = query::table::UpdateById::new(&table, record_id, &updater);
+    db.execute(update, |id| {
+        new_ids.push(id);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(new_ids.len(), 1);
+    assert_ne!(new_ids[0], record_id);
+    assert!(get(&db, &table, record_id).await?.is_none());
+    let values = get(&db, &table, new_ids[0]).await?.unwrap();
+    assert_eq!(id_of(&values), 3);
+    assert_eq!(
+        values.get("text"),
+        Some(&Value::Text("long text".repeat(10)))
+    );
+
+    // Deletes.
+    let (record_id, _) = ids[5];
+    let mut count = 0;
+    let delete = query::table::DeleteById::new(&table, record_id);
+    db.execute(delete, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(count, 1);
+    assert!(get(&db, &table, record_id).await?.is_none());
+
+    // Deleting a deleted record is a no-op.
+    let delete = query::table::DeleteById::new(&table, record_id);
+    db.execute(delete, |_| Err(())).await?.unwrap();
+
+    let remaining = list_ids(&db, &table).await?;
+    assert_eq!(remaining.len(), 29);
+    assert!(remaining.iter().all(|&(_, id)| id != 5));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_record_id_index_maintenance() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let rows = (0..10).map(|id| row(id, "text"));
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let ids = list_ids(&db, &table).await?;
+
+    let updater = |values: &mut Values| {
+        values.set("id".into(), Value::Int(100));
+        values.set("text".into(), Value::Text("long text".repeat(10)));
+    };
+    let update = query::table::UpdateById::new(&table, ids[2].0, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+    let delete = query::table::DeleteById::new(&table, ids[4].0);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let mut found = Vec::new();
+    let select = query::table::Select::with_filter(&table, "id", Value::Int(0)..);
+    db.execute(select, |row| {
+        found.push(id_of(&row));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(found, [0, 1, 3, 5, 6, 7, 8, 9, 100]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_record_id() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let insert = query::table::Insert::new(&table, row(1, "text"));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let record_id = RecordId::new(table.page_id, 4000, 0);
+    assert!(get(&db, &table, record_id).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_record_id() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let rows = (0..10).map(|id| row(id, "long text"));
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let ids = list_ids(&db, &table).await?;
+
+    // Reclaiming the padding of the first record shifts the next ones.
+    let pred = |values: &Values| id_of(values) == 0;
+    let updater = |values: &mut Values| values.set("text".into(), Value::Text("t".into()));
+    let update = query::table::Update::new(&table, &pred, &updater)
+        .with_pad_policy(query::table::PadPolicy::ReclaimAbove(0));
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let (record_id, _) = ids[5];
+    assert!(get(&db, &table, record_id).await.is_err());
+    let update = query::table::UpdateById::new(&table, record_id, &updater);
+    assert!(db.execute(update, |_| Ok::<_, ()>(())).await.is_err());
+    let delete = query::table::DeleteById::new(&table, record_id);
+    assert!(db.execute(delete, |_| Ok::<_, ()>(())).await.is_err());
+    assert_eq!(list_ids(&db, &table).await?.len(), 10);
+
+    // The IDs obtained since then are valid, until a vacuum moves the records.
+    let ids = list_ids(&db, &table).await?;
+    let delete = query::table::DeleteById::new(&table, ids[1].0);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    let values = get(&db, &table, ids[5].0).await?.unwrap();
+    assert_eq!(id_of(&values), ids[5].1);
+
+    let vacuum = query::table::Vacuum::new(&table);
+    db.execute(vacuum, |_| Ok::<_, ()>(())).await?.unwrap();
+    let delete = query::table::DeleteById::new(&table, ids[5].0);
+    assert!(db.execute(delete, |_| Ok::<_, ()>(())).await.is_err());
+    let ids = list_ids(&db, &table).await?;
+    assert_eq!(ids.len(), 9);
+    let values = get(&db, &table, ids[4].0).await?.unwrap();
+    assert_eq!(id_of(&values), 5);
+
+    Ok(())
+}
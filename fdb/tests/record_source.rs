@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{
+        query::{self, RecordSource},
+        value::Value,
+        values::Values,
+    },
+};
+
+mod test_utils;
+
+#[tokio::test]
+async fn test_select_as_record_source() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    for i in 1..=3 {
+        let ins = query::table::Insert::new(
+            &table,
+            Values::from(HashMap::from([("id".into(), Value::Int(i))])),
+        );
+        db.execute(ins, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    let del = query::table::Delete::new(&table, &|val| {
+        *val.get("id").unwrap().try_cast_int_ref().unwrap() == 1
+    });
+    db.execute(del, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let mut source = query::table::Select::new(&table);
+    assert_eq!(source.schema().columns.len(), 3);
+
+    let id = |record: Option<fdb::exec::values::SchematizedValues>| {
+        let record = record.expect("record");
+        *record
+            .get(&table.schema, "id")
+            .unwrap()
+            .try_cast_int_ref()
+            .unwrap()
+    };
+
+    // Peeking skips the deleted record and doesn't advance the source.
+    assert_eq!(id(source.peek(&db).await?), 2);
+    assert_eq!(id(source.peek(&db).await?), 2);
+    assert_eq!(id(RecordSource::next(&mut source, &db).await?), 2);
+    assert_eq!(id(source.peek(&db).await?), 3);
+    assert_eq!(id(RecordSource::next(&mut source, &db).await?), 3);
+    assert!(source.peek(&db).await?.is_none());
+    assert!(RecordSource::next(&mut source, &db).await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schematized_ordinals() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let schema = &table.schema;
+
+    let values = Values::from(HashMap::from([
+        ("bool".into(), Value::Bool(true)),
+        ("id".into(), Value::Int(7)),
+        ("text".into(), Value::Text("seven".into())),
+    ]));
+    let record = values.clone().try_into_schematized(schema)?;
+
+    // Values are stored in the schema order, regardless of the map order.
+    assert_eq!(schema.ordinal("text"), Some(1));
+    assert_eq!(schema.ordinal("missing"), None);
+    assert_eq!(
+        record.as_slice(),
+        [
+            Value::Int(7),
+            Value::Text("seven".into()),
+            Value::Bool(true)
+        ]
+    );
+    assert_eq!(record.get_ordinal(2), Some(&Value::Bool(true)));
+    assert_eq!(record.get_ordinal(3), None);
+    assert_eq!(record.get(schema, "text"), record.get_ordinal(1));
+    assert_eq!(record.project(&[2, 0]), [Value::Bool(true), Value::Int(7)]);
+    assert_eq!(record.into_values(schema), values);
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use fdb::{
+    error::DbResult,
+    exec::value::Value,
+    sql::planner::{self, SqlOutput},
+    Db, DbOptions,
+};
+
+mod test_utils;
+
+async fn texts(db: &Db, sql: &str) -> DbResult<Vec<Value>> {
+    let SqlOutput::Rows { rows, .. } = db.execute_sql(sql).await? else {
+        panic!("expected rows");
+    };
+    Ok(rows
+        .iter()
+        .map(|row| row.get("text").unwrap().clone())
+        .collect())
+}
+
+fn stats(db: &Db) -> (u64, u64) {
+    db.result_cache().expect("enabled").stats()
+}
+
+fn text(text: &str) -> Value {
+    Value::Text(text.into())
+}
+
+#[tokio::test]
+async fn test_result_cache() -> DbResult<()> {
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_result_cache_capacity(16);
+    let db = test_utils::TestDb::new_temp_with_options(options).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'a', true), (2, 'b', false)")
+        .await?;
+
+    let sql = "SELECT text FROM test_table WHERE id = 1";
+    assert_eq!(texts(&db, sql).await?, [text("a")]);
+    // Normalized, hence whitespace and keyword case don't matter.
+    let same = "select text  from test_table\nwhere id = 1";
+    assert_eq!(texts(&db, same).await?, [text("a")]);
+    assert_eq!(stats(&db), (1, 1));
+
+    // Writes to the table invalidate its results.
+    db.execute_sql("UPDATE test_table SET text = 'c' WHERE id = 1")
+        .await?;
+    assert_eq!(texts(&db, sql).await?, [text("c")]);
+    assert_eq!(texts(&db, sql).await?, [text("c")]);
+    assert_eq!(stats(&db), (2, 2));
+
+    db.execute_sql("DELETE FROM test_table WHERE id = 1")
+        .await?;
+    assert_eq!(texts(&db, sql).await?, []);
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'd', true)")
+        .await?;
+    assert_eq!(texts(&db, sql).await?, [text("d")]);
+    assert_eq!(stats(&db), (2, 4));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_result_cache_invalidation_is_per_table() -> DbResult<()> {
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_result_cache_capacity(16);
+    let db = test_utils::TestDb::new_temp_with_options(options).await?;
+    db.execute_sql("CREATE TABLE other (id int, text text)")
+        .await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'a', true)")
+        .await?;
+
+    let sql = "SELECT * FROM test_table";
+    assert_eq!(texts(&db, sql).await?, [text("a")]);
+    db.execute_sql("INSERT INTO other VALUES (1, 'b')").await?;
+    assert_eq!(texts(&db, sql).await?, [text("a")]);
+    assert_eq!(stats(&db), (1, 1));
+
+    // Catalog changes invalidate all results.
+    db.execute_sql("CREATE INDEX test_table_id ON test_table (id)")
+        .await?;
+    assert_eq!(texts(&db, sql).await?, [text("a")]);
+    assert_eq!(stats(&db), (1, 2));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_result_cache_skips_unseeded_random() -> DbResult<()> {
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_result_cache_capacity(16);
+    let db = test_utils::TestDb::new_temp_with_options(options).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'a', true)")
+        .await?;
+
+    let sql = "SELECT * FROM test_table WHERE random_sample(50)";
+    for _ in 0..2 {
+        texts(&db, sql).await?;
+    }
+    assert_eq!(stats(&db), (0, 0));
+
+    // Seeded statements are reproducible, hence cached.
+    for _ in 0..2 {
+        let statement = fdb::sql::parser::parse(sql)?;
+        let prepared = planner::prepare(&db, statement).await?.with_seed(7);
+        prepared.execute(&db).await?;
+    }
+    assert_eq!(stats(&db), (1, 1));
+
+    // System tables aren't cached.
+    db.execute_sql("SELECT * FROM fdb_table_activity").await?;
+    assert_eq!(stats(&db), (1, 1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_result_cache_disabled_by_default() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    assert!(db.result_cache().is_none());
+    assert_eq!(db.environment().result_cache_capacity, 0);
+    Ok(())
+}
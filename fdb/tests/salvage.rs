@@ -0,0 +1,148 @@
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{HeapPage, PageId},
+    },
+    error::DbResult,
+    exec::query,
+    Db,
+};
+
+mod test_utils;
+
+/// Creates a test database with an index and the given number of rows, whose
+/// table spans many pages. Returns it along with the ID of the table's second
+/// page.
+async fn setup(rows: i32) -> DbResult<(test_utils::TestDb, PageId)> {
+    let db = test_utils::TestDb::new_temp(Some(1024)).await?;
+    db.execute_sql("CREATE INDEX test_table_id ON test_table (id)")
+        .await?;
+    for id in 0..rows {
+        db.execute_sql(&format!(
+            "INSERT INTO test_table VALUES ({id}, 'text-{id}', true)"
+        ))
+        .await?;
+    }
+    db.checkpoint().await?;
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let page_id = db
+        .pager()
+        .read_with(table.page_id, |page: &HeapPage| page.header.next_page_id)
+        .await?
+        .unwrap();
+    Ok((db, page_id))
+}
+
+/// Applies the given function to the bytes of the given page, in the file.
+fn modify_page(path: &Path, page_id: PageId, page_size: u16, f: impl FnOnce(&mut [u8])) {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .unwrap();
+    let mut page = vec![0; page_size as usize];
+    file.seek(SeekFrom::Start(page_id.offset(page_size)))
+        .unwrap();
+    file.read_exact(&mut page).unwrap();
+    f(&mut page);
+    file.seek(SeekFrom::Start(page_id.offset(page_size)))
+        .unwrap();
+    file.write_all(&page).unwrap();
+}
+
+async fn count(db: &Db) -> DbResult<u64> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut count = 0;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(count)
+}
+
+fn salvage_path(db: &test_utils::TestDb) -> PathBuf {
+    db.path().with_extension("salvage")
+}
+
+#[tokio::test]
+async fn test_salvage() -> DbResult<()> {
+    let (db, _) = setup(200).await?;
+    let path = salvage_path(&db);
+    let report = db.salvage_to(&path).await?;
+    assert!(report.is_lossless(), "{report:?}");
+    assert_eq!(report.tables.len(), 1);
+    assert_eq!(report.tables[0].0, "test_table");
+    assert_eq!(report.tables[0].1.records, 200);
+    assert_eq!(report.indexes, 1);
+
+    // The path must not exist.
+    assert!(db.salvage_to(&path).await.is_err());
+
+    {
+        let (salvaged, _) = Db::open_with_page_size(&path, db.page_size()).await?;
+        assert_eq!(count(&salvaged).await?, 200);
+        Object::find(&salvaged, "test_table_id")
+            .await?
+            .try_into_index()?;
+        assert!(salvaged.check_integrity().await?.is_ok());
+    }
+    std::fs::remove_file(&path).unwrap();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_salvage_damaged_page() -> DbResult<()> {
+    let (mut db, page_id) = setup(200).await?;
+    // Flips a byte in the middle of the page, without updating its checksum.
+    modify_page(db.path(), page_id, 1024, |page| page[512] = !page[512]);
+    db.reopen().await?;
+
+    let path = salvage_path(&db);
+    let report = db.salvage_to(&path).await?;
+    let (_, salvage) = &report.tables[0];
+    assert_eq!(salvage.damaged_pages, 1);
+    assert!(salvage.complete);
+    // The flipped byte may or may not make records undecodable.
+    assert_eq!(salvage.records + salvage.lost_records, 200);
+
+    {
+        let (salvaged, _) = Db::open_with_page_size(&path, db.page_size()).await?;
+        assert_eq!(count(&salvaged).await?, salvage.records);
+    }
+    std::fs::remove_file(&path).unwrap();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_salvage_unreadable_page() -> DbResult<()> {
+    let (mut db, page_id) = setup(200).await?;
+    modify_page(db.path(), page_id, 1024, |page| page.fill(0));
+    db.reopen().await?;
+
+    let path = salvage_path(&db);
+    let report = db.salvage_to(&path).await?;
+    assert!(!report.is_lossless());
+    let (_, salvage) = &report.tables[0];
+    // The first page is salvaged, but the rest of the table is no longer
+    // reachable.
+    assert!(!salvage.complete);
+    assert!(salvage.records > 0 && salvage.records < 200);
+    assert_eq!(report.indexes, 1);
+
+    {
+        let (salvaged, _) = Db::open_with_page_size(&path, db.page_size()).await?;
+        assert_eq!(count(&salvaged).await?, salvage.records);
+    }
+    std::fs::remove_file(&path).unwrap();
+    Ok(())
+}
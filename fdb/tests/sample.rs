@@ -0,0 +1,121 @@
+use fdb::{
+    catalog::object::Object,
+    error::{DbResult, Error},
+    exec::{
+        query::{self, table::Select},
+        sample::{self, PageSample, RANDOM_MAX},
+        values::Values,
+    },
+    sql::{self, planner::SqlOutput},
+    Db,
+};
+
+mod test_utils;
+
+/// Returns the given number of rows, with distinct texts.
+fn rows(count: i32) -> impl Iterator<Item = Values> {
+    (0..count).map(|id| test_utils::row(id, format!("text-{id}"), id % 2 == 0))
+}
+
+/// Executes the given select with the given seed, returning the sorted IDs.
+async fn sampled_ids(db: &Db, sql: &str, seed: u64) -> DbResult<Vec<i32>> {
+    let prepared = sql::planner::prepare(db, sql::parser::parse(sql)?).await?;
+    let SqlOutput::Rows { rows, .. } = prepared.with_seed(seed).execute(db).await? else {
+        panic!("expected rows");
+    };
+    let mut ids: Vec<_> = rows
+        .iter()
+        .map(|row| *row.get("id").unwrap().try_cast_int_ref().unwrap())
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_random_sample() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(2000)).await?;
+
+    // The conjunction isn't a sampling filter, so rows are sampled one by one.
+    let sql = "SELECT id FROM test_table WHERE random_sample(10) AND id >= 0";
+    let sample = sampled_ids(&db, sql, 42).await?;
+    assert!((100..300).contains(&sample.len()), "{}", sample.len());
+    assert_eq!(sampled_ids(&db, sql, 42).await?, sample);
+    assert_ne!(sampled_ids(&db, sql, 7).await?, sample);
+
+    let sql = "SELECT id FROM test_table WHERE random_sample(0) OR id < 3";
+    assert_eq!(sampled_ids(&db, sql, 42).await?, [0, 1, 2]);
+    let sql = "SELECT id FROM test_table WHERE random_sample(100) AND id < 3";
+    assert_eq!(sampled_ids(&db, sql, 42).await?, [0, 1, 2]);
+
+    let half = RANDOM_MAX / 2;
+    let sql = format!("SELECT id FROM test_table WHERE id >= 0 AND random() < {half}");
+    let sample = sampled_ids(&db, &sql, 42).await?;
+    assert!((800..1200).contains(&sample.len()), "{}", sample.len());
+
+    // Sessions may also be seeded.
+    let mut session = db.session().with_seed(42);
+    let SqlOutput::Rows { rows, .. } = session.execute(&sql).await? else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows.len(), sample.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_page_sample() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(2000)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    // The expected sample holds the records of the sampled pages.
+    let page_sample = PageSample::new(42, sample::threshold(30));
+    let (mut all, mut expected, mut pages) = (Select::new(&table), Vec::new(), Vec::new());
+    while let Some((id, values)) = all.next_with_id(&db).await? {
+        if !pages.contains(&id.page_id) {
+            pages.push(id.page_id);
+        }
+        if page_sample.includes(id.page_id) {
+            expected.push(*values.get("id").unwrap().try_cast_int_ref().unwrap());
+        }
+    }
+    assert!(pages.len() > 10);
+    assert!(!expected.is_empty() && expected.len() < 2000);
+
+    let mut select = Select::new(&table).with_page_sample(page_sample);
+    let mut ids = Vec::new();
+    while let Some(values) = query::Query::next(&mut select, &db).await? {
+        ids.push(*values.get("id").unwrap().try_cast_int_ref().unwrap());
+    }
+    assert_eq!(ids, expected);
+
+    // The planner uses the page sample for the sampling filters.
+    let sql = "SELECT id FROM test_table WHERE random_sample(30)";
+    assert_eq!(sampled_ids(&db, sql, 42).await?, expected);
+    let threshold = sample::threshold(30);
+    let sql = format!("SELECT id FROM test_table WHERE random() < {threshold}");
+    assert_eq!(sampled_ids(&db, &sql, 42).await?, expected);
+    let sql = format!("SELECT id FROM test_table WHERE {threshold} > random()");
+    assert_eq!(sampled_ids(&db, &sql, 42).await?, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_random_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    for sql in [
+        "SELECT id FROM test_table WHERE random(1) < 10",
+        "SELECT id FROM test_table WHERE random_sample()",
+        "SELECT id FROM test_table WHERE random_sample('ten')",
+        "SELECT id FROM test_table WHERE random() < 'ten'",
+        "SELECT id FROM test_table WHERE random()",
+    ] {
+        let result = db.execute_sql(sql).await;
+        assert!(matches!(result, Err(Error::ExecError(_))), "{sql}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+    io::scheduler::{background, is_background, MAX_BACKGROUND_DEFERRAL},
+    Db,
+};
+
+mod test_utils;
+
+/// Creates a test database whose table spans many pages, and reopens it so
+/// that its pages must be read from the disk.
+async fn populated_db() -> DbResult<test_utils::TestDb> {
+    let mut db = test_utils::TestDb::new_temp(Some(256)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let rows = (0..40).map(|id| {
+        Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("t".repeat(50))),
+        ]))
+    });
+    let insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    db.reopen().await?;
+    Ok(db)
+}
+
+/// Scans the test table, returning the number of pages read from the disk.
+async fn scan(db: &Db) -> DbResult<u64> {
+    let (_, misses) = db.pager().cache_stats();
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut count = 0;
+    let select = query::table::Select::new(&table);
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(count, 40);
+    Ok(db.pager().cache_stats().1 - misses)
+}
+
+#[tokio::test]
+async fn test_background_rate() -> DbResult<()> {
+    let db = populated_db().await?;
+    let scheduler = db.pager().io_scheduler();
+    scheduler.set_background_rate(Some(200));
+
+    assert!(!is_background());
+    let start = Instant::now();
+    let reads = background(async {
+        assert!(is_background());
+        scan(&db).await
+    })
+    .await?;
+    assert!(reads > 5, "{reads} reads");
+    // Each read but the first one waits for its slot (of 5ms).
+    assert!(start.elapsed() >= Duration::from_millis(5) * (reads as u32 - 1));
+    assert_eq!(scheduler.deferred_count(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_background_deferral() -> DbResult<()> {
+    let db = populated_db().await?;
+    let scheduler = db.pager().io_scheduler();
+    assert!(background(async { scheduler.foreground().is_none() }).await);
+
+    let foreground = scheduler.foreground();
+    assert_eq!(scheduler.foreground_count(), 1);
+    let ((reads, finished_at), released_at) = tokio::join!(
+        background(async { (scan(&db).await, Instant::now()) }),
+        async {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            drop(foreground);
+            Instant::now()
+        },
+    );
+    assert!(reads? > 0);
+    assert!(finished_at >= released_at);
+    assert_eq!(scheduler.deferred_count(), 1);
+    assert_eq!(scheduler.foreground_count(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_background_deferral_is_bounded() -> DbResult<()> {
+    let mut db = populated_db().await?;
+    {
+        let scheduler = db.pager().io_scheduler();
+        let _foreground = scheduler.foreground();
+        let start = Instant::now();
+        let reads = background(scan(&db)).await?;
+        assert!(start.elapsed() >= MAX_BACKGROUND_DEFERRAL * reads as u32);
+        assert_eq!(scheduler.deferred_count(), reads);
+    }
+
+    // Foreground I/O is never delayed.
+    db.reopen().await?;
+    db.pager().io_scheduler().set_background_rate(Some(1));
+    let start = Instant::now();
+    assert!(scan(&db).await? > 5);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use fdb::{
+    error::{DbResult, Error},
+    exec::{query, value::Value},
+    sql::planner::SqlOutput,
+    Db,
+};
+
+mod test_utils;
+
+/// Drops the test table, so that the database has no objects.
+async fn clear(db: &Db) -> DbResult<()> {
+    let drop = query::object::DropTable::new("test_table");
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schema_script_round_trip() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let statements = [
+        "CREATE TABLE people (id bigint, name text(20), tags int[])",
+        "CREATE INDEX people_id ON people (id)",
+        "CREATE EXTERNAL TABLE \"from\" (id int) FROM 'ignore/order.csv' FORMAT csv",
+    ];
+    for statement in statements {
+        assert!(matches!(db.execute_sql(statement).await?, SqlOutput::Done));
+    }
+    db.execute_sql("INSERT INTO people VALUES (1, 'ann', [1, 2])")
+        .await?;
+
+    let script = db.schema_script().await?;
+    assert_eq!(
+        script,
+        "CREATE TABLE test_table (id int, text text, bool bool);\n\
+         CREATE TABLE people (id bigint, name text(20), tags int[]);\n\
+         CREATE EXTERNAL TABLE \"from\" (id int) FROM 'ignore/order.csv' FORMAT csv;\n\
+         CREATE INDEX people_id ON people (id);\n"
+    );
+
+    // Loaded into another database (with another page size), the script
+    // recreates the same objects, but no rows.
+    let other = test_utils::TestDb::new_temp(Some(512)).await?;
+    clear(&other).await?;
+    assert_eq!(other.load_schema_script(&script).await?, 4);
+    assert_eq!(other.schema_script().await?, script);
+    let SqlOutput::Rows { rows, .. } = other.execute_sql("SELECT * FROM people").await? else {
+        panic!("expected rows");
+    };
+    assert!(rows.is_empty());
+
+    other
+        .execute_sql("INSERT INTO people VALUES (2, 'bob', [])")
+        .await?;
+    let SqlOutput::Rows { rows, .. } = other
+        .execute_sql("SELECT name FROM people WHERE id = 2")
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows[0].get("name"), Some(&Value::Text("bob".into())));
+    let result = other
+        .execute_sql("INSERT INTO people VALUES (3, 'a very, very long name', [])")
+        .await;
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schema_script_load_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    // The database must be empty.
+    let result = db.load_schema_script("CREATE TABLE people (id int);").await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+
+    clear(&db).await?;
+    let result = db
+        .load_schema_script("CREATE TABLE people (id int); DELETE FROM people;")
+        .await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    let result = db.load_schema_script("CREATE TABLE people (id int").await;
+    assert!(matches!(result, Err(Error::Syntax(_))));
+    // No object was created by the rejected scripts.
+    assert_eq!(db.schema_script().await?, "");
+
+    assert_eq!(db.load_schema_script("-- empty\n").await?, 0);
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use fdb::{
+    catalog::object::{Object, ObjectType},
+    error::{DbResult, Error},
+    exec::{query, value::Value},
+    sql::planner::SqlOutput,
+    Db,
+};
+
+mod test_utils;
+
+/// A segment file path, whose file is removed on drop.
+struct SegmentPath(String);
+
+impl SegmentPath {
+    fn new(name: &str) -> SegmentPath {
+        std::fs::create_dir_all("ignore").unwrap();
+        let path = format!("ignore/{name}.fseg");
+        let _ = std::fs::remove_file(&path);
+        SegmentPath(path)
+    }
+}
+
+impl Drop for SegmentPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+async fn ids(db: &Db, table: &str) -> DbResult<Vec<Value>> {
+    let sql = format!("SELECT id FROM {table}");
+    let SqlOutput::Rows { rows, .. } = db.execute_sql(&sql).await? else {
+        panic!("expected rows");
+    };
+    Ok(rows
+        .iter()
+        .map(|row| row.get("id").unwrap().clone())
+        .collect())
+}
+
+async fn seal(db: &Db, table: &str, path: &str) -> DbResult<u64> {
+    let table = Object::find(db, table).await?.try_into_table()?;
+    let mut count = 0;
+    let seal = query::object::SealTable::new(&table, path);
+    db.execute(seal, |sealed| {
+        count = sealed;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(count)
+}
+
+async fn attach(db: &Db, name: &str, path: &str) -> DbResult<()> {
+    let attach = query::object::AttachSegment::new(name, path);
+    db.execute(attach, |()| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seal_table() -> DbResult<()> {
+    let path = SegmentPath::new("seal");
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'a', true), (2, 'b', false), (3, 'c', true)")
+        .await?;
+    db.execute_sql("DELETE FROM test_table WHERE id = 2")
+        .await?;
+
+    assert_eq!(seal(&db, "test_table", &path.0).await?, 2);
+    let object = Object::find(&db, "test_table").await?;
+    assert!(matches!(object.ty, ObjectType::External(_)));
+    assert_eq!(
+        ids(&db, "test_table").await?,
+        [Value::Int(1), Value::Int(3)]
+    );
+    let SqlOutput::Rows { rows, .. } = db
+        .execute_sql("SELECT text FROM test_table WHERE bool = true AND id > 1")
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows[0].get("text"), Some(&Value::Text("c".into())));
+
+    // Sealed tables are immutable.
+    let result = db
+        .execute_sql("INSERT INTO test_table VALUES (4, 'd', true)")
+        .await;
+    assert!(matches!(result, Err(Error::Cast(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_segments_are_write_once() -> DbResult<()> {
+    let path = SegmentPath::new("write_once");
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'a', true)")
+        .await?;
+    std::fs::write(&path.0, "taken").unwrap();
+
+    let result = seal(&db, "test_table", &path.0).await;
+    assert!(matches!(result, Err(Error::Io(_))));
+    // The table is left untouched.
+    Object::find(&db, "test_table").await?.try_into_table()?;
+    assert_eq!(ids(&db, "test_table").await?, [Value::Int(1)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_attach_segment() -> DbResult<()> {
+    let path = SegmentPath::new("attach");
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'a', true), (2, 'b', false)")
+        .await?;
+    seal(&db, "test_table", &path.0).await?;
+
+    // Attached to another database file.
+    let other = test_utils::TestDb::new_temp(None).await?;
+    attach(&other, "archive", &path.0).await?;
+    let object = Object::find(&other, "archive")
+        .await?
+        .try_into_external_table()?;
+    assert_eq!(object.schema.schema.columns.len(), 3);
+    assert_eq!(
+        ids(&other, "archive").await?,
+        [Value::Int(1), Value::Int(2)]
+    );
+    let result = attach(&other, "archive", &path.0).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+
+    // Corrupted segments are rejected.
+    let mut bytes = std::fs::read(&path.0).unwrap();
+    let last = bytes.len() - 5;
+    bytes[last] ^= 0xFF;
+    std::fs::write(&path.0, bytes).unwrap();
+    let result = ids(&other, "archive").await;
+    assert!(
+        matches!(result.unwrap_err().root(), Error::ExecError(message) if message.contains("checksum"))
+    );
+    let result = attach(&other, "corrupted", &path.0).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use fdb::{
+    catalog::{
+        column::Column,
+        table_schema::TableSchema,
+        ty::{PrimitiveTypeId, TypeId},
+    },
+    error::{DbResult, Error},
+    exec::{query, value::Value},
+    sql::{self, planner::SqlOutput, session::Session},
+    Db,
+};
+
+mod test_utils;
+
+async fn create_table(db: &Db, name: &str, columns: &[&str]) -> DbResult<()> {
+    let columns = columns.iter().map(|&name| Column {
+        ty: TypeId::Primitive(PrimitiveTypeId::Int),
+        name: name.into(),
+        max_len: None,
+        default: None,
+        not_null: false,
+    });
+    let schema = TableSchema {
+        columns: columns.collect(),
+    };
+    let create = query::object::CreateTable::new(name, schema);
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+async fn drop_table(db: &Db, name: &str) -> DbResult<()> {
+    let drop = query::object::DropTable::new(name);
+    db.execute(drop, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+async fn ids(session: &mut Session<'_>, sql: &str) -> DbResult<Vec<Value>> {
+    let SqlOutput::Rows { rows, .. } = session.execute(sql).await? else {
+        panic!("expected rows");
+    };
+    Ok(rows
+        .iter()
+        .map(|row| row.get("id").unwrap().clone())
+        .collect())
+}
+
+#[tokio::test]
+async fn test_session_cache_hit() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut session = db.session();
+
+    for id in 1..=3 {
+        let sql = format!("INSERT INTO test_table (id) VALUES ({id})");
+        session.execute(&sql).await?;
+    }
+    let select = "SELECT id FROM test_table WHERE id >= 2";
+    assert_eq!(
+        ids(&mut session, select).await?,
+        [Value::Int(2), Value::Int(3)]
+    );
+    session
+        .execute("INSERT INTO test_table (id) VALUES (3)")
+        .await?;
+    assert_eq!(
+        ids(&mut session, select).await?,
+        [Value::Int(2), Value::Int(3), Value::Int(3)]
+    );
+    assert_eq!(session.cache_stats(), (2, 4));
+    assert_eq!(session.cached_count(), 4);
+
+    // Failed statements aren't cached.
+    let result = session.execute("SELECT id FROM missing").await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    assert_eq!(session.cached_count(), 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_invalidation() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut session = db.session();
+    create_table(&db, "t", &["id"]).await?;
+
+    session.execute("INSERT INTO t VALUES (1)").await?;
+    let select = "SELECT * FROM t";
+    assert_eq!(ids(&mut session, select).await?, [Value::Int(1)]);
+    let prepared = sql::planner::prepare(&db, sql::parser::parse(select)?).await?;
+
+    // The table is recreated with another schema.
+    drop_table(&db, "t").await?;
+    create_table(&db, "t", &["id", "other"]).await?;
+    assert!(prepared.is_stale(&db));
+    let result = prepared.execute(&db).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+
+    session.execute("INSERT INTO t VALUES (2, 3)").await?;
+    let SqlOutput::Rows { columns, rows } = session.execute(select).await? else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, ["id", "other"]);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get("other"), Some(&Value::Int(3)));
+    assert_eq!(session.cache_stats(), (0, 4));
+
+    drop_table(&db, "t").await?;
+    let result = session.execute(select).await;
+    assert!(matches!(result, Err(Error::ExecError(_))));
+    assert_eq!(session.cached_count(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_capacity() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut session = db.session().with_capacity(2);
+
+    let (a, b, c) = (
+        "SELECT id FROM test_table",
+        "SELECT text FROM test_table",
+        "SELECT bool FROM test_table",
+    );
+    session.execute(a).await?;
+    session.execute(b).await?;
+    session.execute(a).await?;
+    // Evicts `b`, the least recently used statement.
+    session.execute(c).await?;
+    assert_eq!(session.cached_count(), 2);
+    session.execute(a).await?;
+    session.execute(b).await?;
+    assert_eq!(session.cache_stats(), (2, 4));
+
+    let mut session = db.session().with_capacity(0);
+    session.execute(a).await?;
+    session.execute(a).await?;
+    assert_eq!(session.cache_stats(), (0, 2));
+    assert_eq!(session.cached_count(), 0);
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{FirstPage, PageId, MAX_TEMP_SEQS},
+    },
+    error::DbResult,
+    exec::{
+        query::{self, table::SortKey, RecordSource},
+        values::Values,
+    },
+    io::temp,
+    Db,
+};
+
+mod test_utils;
+
+/// Returns rows with pseudo-random ids and texts with many duplicates.
+fn rows(count: i32) -> impl Iterator<Item = Values> {
+    let mut n: i32 = 7;
+    (0..count).map(move |i| {
+        n = n.wrapping_mul(1_103_515_245).wrapping_add(12_345) & 0x7FFF;
+        test_utils::row(n, format!("t{}", n % 5), i % 2 == 0)
+    })
+}
+
+fn row(values: &Values) -> (String, i32) {
+    let text = values.get("text").unwrap().try_cast_text_ref().unwrap();
+    let id = values.get("id").unwrap().try_cast_int_ref().unwrap();
+    (text.to_owned(), *id)
+}
+
+/// Returns the `(text, id)` of all rows, in insertion order.
+async fn select_all(db: &Db) -> DbResult<Vec<(String, i32)>> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut rows = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |values| {
+        rows.push(row(&values));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(rows)
+}
+
+async fn sort(
+    db: &Db,
+    keys: Vec<SortKey>,
+    run_size: usize,
+    fan_in: usize,
+) -> DbResult<Vec<(String, i32)>> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut rows = Vec::new();
+    let select = query::table::Select::new(&table);
+    let sort = query::table::Sort::new(select, keys)
+        .with_run_size(run_size)
+        .with_fan_in(fan_in);
+    db.execute(sort, |values| {
+        rows.push(row(&values));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(rows)
+}
+
+/// Describes and then runs the given sort, returning the described properties
+/// and the sorted rows.
+async fn run_sort(
+    db: &Db,
+    mut sort: query::table::Sort<query::table::Select<'_>>,
+) -> DbResult<(query::Plan, Vec<(String, i32)>)> {
+    let plan = query::Query::describe(&mut sort, db).await?;
+    let mut rows = Vec::new();
+    db.execute(sort, |values| {
+        rows.push(row(&values));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok((plan, rows))
+}
+
+async fn temp_seq_count(db: &Db) -> DbResult<usize> {
+    db.pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.temp_seq_page_ids.len()
+        })
+        .await
+}
+
+#[tokio::test]
+async fn test_sort_in_memory() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(50)).await?;
+    let all = select_all(&db).await?;
+
+    let rows = sort(&db, vec![SortKey::desc("text"), SortKey::asc("id")], 100, 2).await?;
+    let mut expected = all.clone();
+    expected.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    assert_eq!(rows, expected);
+    assert_eq!(temp_seq_count(&db).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sort_external() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(300)).await?;
+    let all = select_all(&db).await?;
+
+    // Stable: rows with the same text keep the insertion order.
+    let mut expected = all.clone();
+    expected.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (run_size, fan_in) in [(7, 3), (10, 2), (100, 8), (299, 2), (300, 2)] {
+        let rows = sort(&db, vec![SortKey::asc("text")], run_size, fan_in).await?;
+        assert_eq!(rows, expected, "run_size = {run_size}, fan_in = {fan_in}");
+        assert_eq!(temp_seq_count(&db).await?, 0);
+    }
+
+    // The tape pages are reused by subsequent sorts.
+    let page_count = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| page.header.page_count)
+        .await?;
+    sort(&db, vec![SortKey::desc("id")], 7, 3).await?;
+    let page_count_after = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| page.header.page_count)
+        .await?;
+    assert_eq!(page_count, page_count_after);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sort_external_failure() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(100)).await?;
+
+    // Only leaves room for a single tape, hence the sort fails once it writes
+    // the second one.
+    let mut seqs = Vec::new();
+    for _ in 0..MAX_TEMP_SEQS - 1 {
+        seqs.push(temp::alloc_seq(db.pager()).await?);
+    }
+    assert!(sort(&db, vec![SortKey::asc("id")], 10, 2).await.is_err());
+    // The tapes written before the failure are released.
+    assert_eq!(temp_seq_count(&db).await?, MAX_TEMP_SEQS - 1);
+
+    for page_id in seqs {
+        temp::release_seq(db.pager(), page_id).await?;
+    }
+    assert_eq!(temp_seq_count(&db).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sort_many_runs() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(128)).await?;
+    let mut expected = select_all(&db).await?;
+    expected.sort_by_key(|row| row.1);
+
+    // There are as many runs as temporary sequences, hence they are merged
+    // before all of them are written.
+    let rows = sort(&db, vec![SortKey::asc("id")], 2, 8).await?;
+    assert_eq!(rows, expected);
+    assert_eq!(temp_seq_count(&db).await?, 0);
+
+    // If few sequences are left, fewer tapes are merged at once.
+    let mut seqs = Vec::new();
+    for _ in 0..MAX_TEMP_SEQS - 4 {
+        seqs.push(temp::alloc_seq(db.pager()).await?);
+    }
+    let rows = sort(&db, vec![SortKey::asc("id")], 2, 8).await?;
+    assert_eq!(rows, expected);
+    for page_id in seqs {
+        temp::release_seq(db.pager(), page_id).await?;
+    }
+    assert_eq!(temp_seq_count(&db).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sort_external_multiple_keys() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(200)).await?;
+    let all = select_all(&db).await?;
+
+    let keys = vec![SortKey::asc("text"), SortKey::desc("id").nulls_last()];
+    let mut expected = all.clone();
+    expected.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    for (run_size, fan_in) in [(7, 3), (200, 2)] {
+        let rows = sort(&db, keys.clone(), run_size, fan_in).await?;
+        assert_eq!(rows, expected, "run_size = {run_size}, fan_in = {fan_in}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sort_as_record_source() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(30)).await?;
+    let mut ids: Vec<_> = select_all(&db).await?.into_iter().map(|r| r.1).collect();
+    ids.sort_unstable();
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let select = query::table::Select::new(&table);
+    let mut sort = query::table::Sort::new(select, vec![SortKey::asc("id")]).with_run_size(4);
+    assert_eq!(sort.schema().columns.len(), 3);
+
+    let id = |record: Option<fdb::exec::values::SchematizedValues>| {
+        row(&record.expect("record").into_values(&table.schema)).1
+    };
+    assert_eq!(id(sort.peek(&db).await?), ids[0]);
+    assert_eq!(id(sort.peek(&db).await?), ids[0]);
+    for expected in &ids {
+        assert_eq!(id(RecordSource::next(&mut sort, &db).await?), *expected);
+    }
+    assert!(sort.peek(&db).await?.is_none());
+    db.pager().flush_all().await?;
+    assert_eq!(temp_seq_count(&db).await?, 0);
+
+    // Unknown column.
+    let select = query::table::Select::new(&table);
+    let sort = query::table::Sort::new(select, vec![SortKey::asc("missing")]);
+    assert!(db.execute(sort, |_| Ok::<_, ()>(())).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sort_strategy() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    test_utils::fill(&db, rows(100)).await?;
+    let mut expected = select_all(&db).await?;
+    expected.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let mut select = query::table::Select::new(&table);
+    let estimate = select.estimate(&db).await?.expect("table statistics");
+    assert_eq!(estimate.records, 100);
+    assert!(estimate.avg_record_size > 0.0);
+
+    let new = || {
+        let select = query::table::Select::new(&table);
+        query::table::Sort::new(select, vec![SortKey::asc("id"), SortKey::asc("text")])
+    };
+
+    // A source which exactly fills a run is sorted in memory.
+    let (plan, rows) = run_sort(&db, new().with_run_size(100)).await?;
+    assert_eq!(rows, expected);
+    assert_eq!(plan.get("strategy"), Some("in memory"));
+    assert_eq!(plan.get("run_size"), Some("100"));
+    assert_eq!(plan.get("runs"), None);
+
+    // The run size is derived from the estimated record size.
+    let memory_limit = (estimate.avg_record_size * 10.0).ceil() as usize;
+    let (plan, rows) = run_sort(&db, new().with_memory_limit(memory_limit)).await?;
+    assert_eq!(rows, expected);
+    assert_eq!(plan.get("strategy"), Some("external"));
+    assert_eq!(plan.get("run_size"), Some("10"));
+    assert_eq!(plan.get("runs"), Some("10"));
+
+    // The runs are evened out (4 runs of 25 records, instead of 3 of 30 and
+    // one of 10), and the merges are deferred to the final phase, as there
+    // are few enough runs.
+    let (plan, rows) = run_sort(&db, new().with_run_size(30).with_fan_in(3)).await?;
+    assert_eq!(rows, expected);
+    assert_eq!(plan.get("strategy"), Some("external"));
+    assert_eq!(plan.get("run_size"), Some("25"));
+    assert_eq!(plan.get("runs"), Some("4"));
+    assert_eq!(plan.get("deferred_merges"), Some("true"));
+
+    // Many runs are still merged eagerly.
+    let (plan, rows) = run_sort(&db, new().with_run_size(5).with_fan_in(2)).await?;
+    assert_eq!(rows, expected);
+    assert_eq!(plan.get("runs"), Some("20"));
+    assert_eq!(plan.get("deferred_merges"), Some("false"));
+    db.pager().flush_all().await?;
+    assert_eq!(temp_seq_count(&db).await?, 0);
+
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        object::{Object, TableObject},
+        page::{FirstPage, HeapPage, PageId},
+    },
+    error::DbResult,
+    exec::{
+        query::{self, table::VacuumStats},
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+
+mod test_utils;
+
+fn row(id: i32, len: usize) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(text(id, len))),
+        ("bool".into(), Value::Bool(true)),
+    ]))
+}
+
+fn text(id: i32, len: usize) -> String {
+    (0..len)
+        .map(|i| (b'a' + ((i + id as usize) % 26) as u8) as char)
+        .collect()
+}
+
+async fn select(db: &Db, table: &TableObject) -> DbResult<Vec<(i32, String)>> {
+    let mut rows = Vec::new();
+    let select = query::table::Select::new(table);
+    db.execute(select, |row| {
+        let id = *row.get("id").unwrap().try_cast_int_ref().unwrap();
+        let text = row
+            .get("text")
+            .unwrap()
+            .try_cast_text_ref()
+            .unwrap()
+            .to_string();
+        rows.push((id, text));
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(rows)
+}
+
+async fn select_by_id(db: &Db, table: &TableObject, id: i32) -> DbResult<Vec<String>> {
+    let mut texts = Vec::new();
+    let select = query::table::Select::with_filter(table, "id", Value::Int(id)..=Value::Int(id));
+    db.execute(select, |row| {
+        texts.push(
+            row.get("text")
+                .unwrap()
+                .try_cast_text_ref()
+                .unwrap()
+                .to_string(),
+        );
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(texts)
+}
+
+async fn free_page_count(db: &Db) -> DbResult<u32> {
+    db.pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.header.free_page_count
+        })
+        .await
+}
+
+async fn page_count(db: &Db, table: &TableObject) -> DbResult<u32> {
+    db.pager()
+        .read_with(table.page_id, |page: &HeapPage| {
+            page.header.seq_header.as_ref().unwrap().page_count
+        })
+        .await
+}
+
+async fn vacuum(db: &Db, table: &TableObject) -> DbResult<VacuumStats> {
+    let mut stats = None;
+    db.execute(query::table::Vacuum::new(table), |s| {
+        stats = Some(s);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(stats.unwrap())
+}
+
+#[tokio::test]
+async fn test_spanned_insert_update_delete() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let lens = [10, 3000, 20, 5000, 30];
+    for (id, len) in lens.iter().enumerate() {
+        let insert = query::table::Insert::new(&table, row(id as i32, *len));
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+    let expected: Vec<_> = (lens.iter().enumerate())
+        .map(|(id, len)| (id as i32, text(id as i32, *len)))
+        .collect();
+    assert_eq!(select(&db, &table).await?, expected);
+    assert_eq!(select_by_id(&db, &table, 3).await?, [text(3, 5000)]);
+
+    // Spanned records are relocated on update, whatever their new size.
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() == 1;
+    let updater = |values: &mut Values| {
+        values.set("text".into(), Value::Text(text(100, 4000)));
+    };
+    let update = query::table::Update::new(&table, &pred, &updater);
+    db.execute(update, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(select_by_id(&db, &table, 1).await?, [text(100, 4000)]);
+
+    db.reopen().await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    assert_eq!(select(&db, &table).await?.len(), lens.len());
+
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() != 0;
+    let delete = query::table::Delete::new(&table, &pred);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(select(&db, &table).await?, [(0, text(0, 10))]);
+
+    let pages_before = page_count(&db, &table).await?;
+    let free_before = free_page_count(&db).await?;
+    let stats = vacuum(&db, &table).await?;
+    assert_eq!(stats.records_removed, 5);
+    assert!(stats.pages_released > 0);
+    assert_eq!(
+        page_count(&db, &table).await?,
+        pages_before - stats.pages_released
+    );
+    assert_eq!(
+        free_page_count(&db).await?,
+        free_before + stats.pages_released
+    );
+    assert_eq!(select(&db, &table).await?, [(0, text(0, 10))]);
+
+    // The table is still writable after its fragment pages were released.
+    let insert = query::table::Insert::new(&table, row(7, 2500));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(
+        select(&db, &table).await?,
+        [(0, text(0, 10)), (7, text(7, 2500))]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_spanned_bulk_insert_and_vacuum_moves() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let lens = |id: i32| if id % 4 == 3 { 1500 + id as usize } else { 20 };
+    let rows = (0..16).map(|id| row(id, lens(id)));
+    let bulk_insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(bulk_insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let expected: Vec<_> = (0..16).map(|id| (id, text(id, lens(id)))).collect();
+    assert_eq!(select(&db, &table).await?, expected);
+
+    // Removes the records before the spanned heads, so that vacuum moves them.
+    let pred = |values: &Values| *values.get("id").unwrap().try_cast_int_ref().unwrap() % 4 == 0;
+    let delete = query::table::Delete::new(&table, &pred);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    let stats = vacuum(&db, &table).await?;
+    assert_eq!(stats.records_removed, 4);
+
+    let expected: Vec<_> = expected.into_iter().filter(|(id, _)| id % 4 != 0).collect();
+    assert_eq!(select(&db, &table).await?, expected);
+    for id in [3, 7, 11, 15] {
+        assert_eq!(select_by_id(&db, &table, id).await?, [text(id, lens(id))]);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_record_too_large() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let insert = query::table::Insert::new(&table, row(1, 70_000));
+    let error = db.execute(insert, |_| Ok::<_, ()>(())).await.unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("exceeds the maximum record size"));
+
+    let bulk_insert = query::table::BulkInsert::new(&table, [row(1, 10), row(2, 70_000)]);
+    let error = db
+        .execute(bulk_insert, |_| Ok::<_, ()>(()))
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("exceeds the maximum record size"));
+    assert_eq!(select(&db, &table).await?, []);
+
+    Ok(())
+}
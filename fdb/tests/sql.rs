@@ -0,0 +1,133 @@
+use fdb::{error::DbResult, exec::value::Value, sql::planner::SqlOutput};
+
+mod test_utils;
+
+/// Executes the given select, returning the sorted `(id, text)` pairs.
+async fn select(db: &fdb::Db, sql: &str) -> DbResult<Vec<(i32, String)>> {
+    let SqlOutput::Rows { rows, .. } = db.execute_sql(sql).await? else {
+        panic!("expected rows");
+    };
+    let mut rows: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let id = *row.get("id").unwrap().try_cast_int_ref().unwrap();
+            let text = row.get("text").unwrap().try_cast_text_ref().unwrap();
+            (id, text.to_owned())
+        })
+        .collect();
+    rows.sort();
+    Ok(rows)
+}
+
+fn affected(output: SqlOutput) -> u64 {
+    let SqlOutput::Affected(count) = output else {
+        panic!("expected affected count");
+    };
+    count
+}
+
+#[tokio::test]
+async fn test_sql() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    let out = db
+        .execute_sql("INSERT INTO test_table VALUES (1, 'one', true), (2, 'two', false)")
+        .await?;
+    assert_eq!(affected(out), 2);
+    let out = db
+        .execute_sql("insert into test_table (text, id) values ('three', 3);")
+        .await?;
+    assert_eq!(affected(out), 1);
+
+    assert_eq!(
+        select(&db, "SELECT * FROM test_table").await?,
+        [(1, "one".into()), (2, "two".into()), (3, "three".into())]
+    );
+    assert_eq!(
+        select(
+            &db,
+            "SELECT * FROM test_table WHERE id >= 2 AND NOT text = 'two'"
+        )
+        .await?,
+        [(3, "three".into())]
+    );
+
+    let SqlOutput::Rows { columns, rows } = db
+        .execute_sql("SELECT bool, id FROM test_table WHERE bool = true OR id = 3")
+        .await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, ["bool", "id"]);
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row.get("text").is_none()));
+    assert!(rows
+        .iter()
+        .any(|row| row.get("bool") == Some(&Value::Bool(false))));
+
+    let out = db
+        .execute_sql("UPDATE test_table SET text = 'TWO' WHERE id = 2")
+        .await?;
+    assert_eq!(affected(out), 1);
+    let out = db
+        .execute_sql("DELETE FROM test_table WHERE id < 2")
+        .await?;
+    assert_eq!(affected(out), 1);
+    assert_eq!(
+        select(&db, "SELECT * FROM test_table").await?,
+        [(2, "TWO".into()), (3, "three".into())]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sql_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    for sql in [
+        "SELECT * FROM nope",
+        "SELECT nope FROM test_table",
+        "SELECT * FROM test_table WHERE id = 'one'",
+        "SELECT * FROM test_table WHERE id",
+        "INSERT INTO test_table VALUES (1)",
+        "INSERT INTO test_table (id) VALUES ('one')",
+        "INSERT INTO test_table (id, id) VALUES (1, 2)",
+        "INSERT INTO test_table (id) VALUES (9999999999)",
+        "UPDATE test_table SET nope = 1",
+        "DELETE test_table",
+    ] {
+        assert!(db.execute_sql(sql).await.is_err(), "`{sql}` should fail");
+    }
+
+    // Nothing may be inserted by a failing statement.
+    assert_eq!(select(&db, "SELECT * FROM test_table").await?, []);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sql_select_with_index() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    for id in 1..=20 {
+        db.execute_sql(&format!(
+            "INSERT INTO test_table VALUES ({id}, 't{id}', true)"
+        ))
+        .await?;
+    }
+    let create = fdb::exec::query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+
+    let ids = |rows: Vec<(i32, String)>| rows.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+    let rows = select(&db, "SELECT * FROM test_table WHERE id = 3").await?;
+    assert_eq!(rows, [(3, "t3".to_owned())]);
+    let rows = select(&db, "SELECT * FROM test_table WHERE 18 < id").await?;
+    assert_eq!(ids(rows), [19, 20]);
+    let rows = select(&db, "SELECT * FROM test_table WHERE id <= 2").await?;
+    assert_eq!(ids(rows), [1, 2]);
+    // Out of range keys fall back to a linear scan.
+    let rows = select(&db, "SELECT * FROM test_table WHERE id < 99999999999").await?;
+    assert_eq!(rows.len(), 20);
+
+    Ok(())
+}
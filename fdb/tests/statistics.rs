@@ -0,0 +1,106 @@
+use fdb::{
+    catalog::{object::Object, statistics::TableStatistics},
+    error::DbResult,
+    exec::{query, value::Value},
+    Db,
+};
+
+mod test_utils;
+
+async fn create_items(db: &Db) -> DbResult<()> {
+    db.execute_sql("CREATE TABLE items (id int, name text)")
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_statistics_tracking() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    create_items(&db).await?;
+    db.execute_sql("CREATE INDEX items_id ON items (id)")
+        .await?;
+
+    let stats = db.table_statistics("items").await?.unwrap();
+    assert_eq!(
+        (stats.row_count, stats.page_count, stats.total_size),
+        (0, 1, 0)
+    );
+    assert!(stats.columns.is_empty());
+
+    db.execute_sql("INSERT INTO items VALUES (3, 'c'), (1, 'a'), (2, 'b')")
+        .await?;
+    db.execute_sql("DELETE FROM items WHERE id = 2").await?;
+    db.execute_sql("UPDATE items SET id = 7 WHERE id = 3")
+        .await?;
+
+    let stats = db.table_statistics("items").await?.unwrap();
+    assert_eq!(stats.row_count, 2);
+    // Each record has an int (4 bytes) and a one-byte text (2 bytes).
+    assert_eq!(stats.total_size, 12);
+    assert_eq!(stats.avg_record_size(), 6.0);
+    let id = stats.column("id").unwrap();
+    assert_eq!((&id.min, &id.max), (&Value::Int(1), &Value::Int(7)));
+    // Only the indexed columns are tracked.
+    assert!(stats.column("name").is_none());
+
+    db.checkpoint().await?;
+    db.reopen().await?;
+    assert_eq!(db.table_statistics("items").await?, Some(stats));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_statistics_analyze() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    create_items(&db).await?;
+    db.execute_sql("INSERT INTO items VALUES (5, 'e'), (2, 'b'), (9, 'i')")
+        .await?;
+
+    // Indexes created over existing records get their ranges right away.
+    db.execute_sql("CREATE INDEX items_name ON items (name)")
+        .await?;
+    let range = |stats: &TableStatistics| {
+        let name = stats.column("name").unwrap();
+        (name.min.clone(), name.max.clone())
+    };
+    let stats = db.table_statistics("items").await?.unwrap();
+    assert_eq!(
+        range(&stats),
+        (Value::Text("b".into()), Value::Text("i".into()))
+    );
+
+    // Deletes don't shrink the ranges, until the table is analyzed.
+    db.execute_sql("DELETE FROM items WHERE id = 9").await?;
+    let stats = db.table_statistics("items").await?.unwrap();
+    assert_eq!(stats.row_count, 2);
+    assert_eq!(range(&stats).1, Value::Text("i".into()));
+
+    let table = Object::find(&db, "items").await?.try_into_table()?;
+    let mut analyzed = None;
+    db.execute(query::table::Analyze::new(&table), |stats| {
+        analyzed = Some(stats);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    let analyzed = analyzed.unwrap();
+    assert_eq!(analyzed.row_count, 2);
+    assert_eq!(
+        range(&analyzed),
+        (Value::Text("b".into()), Value::Text("e".into()))
+    );
+    assert_eq!(db.table_statistics("items").await?, Some(analyzed));
+
+    // The statistics are dropped along with the table.
+    db.execute(query::object::DropTable::new("items"), |()| Ok::<_, ()>(()))
+        .await?
+        .unwrap();
+    assert!(db.table_statistics("items").await.is_err());
+    create_items(&db).await?;
+    let stats = db.table_statistics("items").await?.unwrap();
+    assert_eq!(stats.row_count, 0);
+    assert!(stats.columns.is_empty());
+
+    Ok(())
+}
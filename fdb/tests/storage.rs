@@ -0,0 +1,295 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use fdb::{
+    catalog::page::PageId,
+    error::{DbResult, Error},
+    io::{
+        disk_manager::{SegmentLayout, SyncMode},
+        storage::{IoFuture, MemoryBackend, StorageBackend},
+    },
+    sql::planner::SqlOutput,
+    Db, DbOptions,
+};
+
+mod test_utils;
+
+async fn count(db: &Db) -> DbResult<usize> {
+    let SqlOutput::Rows { rows, .. } = db.execute_sql("SELECT id FROM test_table").await? else {
+        panic!("expected rows");
+    };
+    Ok(rows.len())
+}
+
+#[tokio::test]
+async fn test_memory_backend() -> DbResult<()> {
+    let options = DbOptions::new().with_page_size(1024);
+    let (db, is_new) = Db::open_with_backend(MemoryBackend::new(), options).await?;
+    assert!(is_new);
+    test_utils::define_test_catalog(&db).await?;
+
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true), (2, 'two', false)")
+        .await?;
+    assert_eq!(count(&db).await?, 2);
+    db.checkpoint().await?;
+    assert_eq!(db.pager().file_size().await?, db.stats().await?.size);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_in_memory() -> DbResult<()> {
+    let db = Db::open_in_memory().await?;
+    test_utils::define_test_catalog(&db).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true)")
+        .await?;
+    assert_eq!(count(&db).await?, 1);
+
+    // Each database is a new one.
+    let other = DbOptions::new()
+        .with_page_size(1024)
+        .open_in_memory()
+        .await?;
+    test_utils::define_test_catalog(&other).await?;
+    assert_eq!(count(&other).await?, 0);
+
+    let options = DbOptions::new().with_read_only(true);
+    let result = Db::open_in_memory_with_options(options).await;
+    assert!(matches!(result, Err(Error::ReadOnly)));
+
+    Ok(())
+}
+
+/// A backend defined outside of the crate, which counts the page writes and the
+/// syncs of the inner backend.
+#[derive(Default)]
+struct CountingBackend {
+    inner: MemoryBackend,
+    writes: Arc<AtomicUsize>,
+    syncs: Arc<AtomicUsize>,
+}
+
+impl StorageBackend for CountingBackend {
+    fn read_page<'a>(&'a mut self, page_id: PageId, buf: &'a mut [u8]) -> IoFuture<'a, ()> {
+        self.inner.read_page(page_id, buf)
+    }
+
+    fn write_page<'a>(&'a mut self, page_id: PageId, buf: &'a [u8]) -> IoFuture<'a, ()> {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        self.inner.write_page(page_id, buf)
+    }
+
+    fn sync(&mut self, metadata: bool) -> IoFuture<'_, ()> {
+        self.syncs.fetch_add(1, Ordering::Relaxed);
+        self.inner.sync(metadata)
+    }
+
+    fn len(&mut self) -> IoFuture<'_, u64> {
+        self.inner.len()
+    }
+}
+
+#[tokio::test]
+async fn test_custom_backend() -> DbResult<()> {
+    let backend = CountingBackend::default();
+    let (writes, syncs) = (Arc::clone(&backend.writes), Arc::clone(&backend.syncs));
+    let options = DbOptions::new()
+        .with_page_size(1024)
+        .with_sync_mode(SyncMode::Full);
+    let (db, is_new) = Db::open_with_backend(backend, options).await?;
+    assert!(is_new);
+    test_utils::define_test_catalog(&db).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true)")
+        .await?;
+    db.checkpoint().await?;
+    assert_eq!(count(&db).await?, 1);
+
+    // Each page write is synced.
+    let writes = writes.load(Ordering::Relaxed);
+    assert!(writes > 0);
+    assert!(syncs.load(Ordering::Relaxed) >= writes);
+
+    Ok(())
+}
+
+/// Removes the segments of the database at the given path.
+fn remove_segments(path: &Path) {
+    for index in 0.. {
+        if std::fs::remove_file(SegmentLayout::segment_path(path, index)).is_err() {
+            break;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_segmented_storage() -> DbResult<()> {
+    let path = Path::new("ignore/segmented-storage-test.db");
+    std::fs::create_dir_all("ignore").unwrap();
+    remove_segments(path);
+    let options = DbOptions::new()
+        .with_page_size(512)
+        .with_max_segment_size(8 * 512 + 100);
+
+    let (db, is_new) = options.clone().open(path).await?;
+    assert!(is_new);
+    test_utils::define_test_catalog(&db).await?;
+    let values: Vec<_> = (0..100)
+        .map(|id| format!("({id}, '{}', true)", "t".repeat(100)))
+        .collect();
+    db.execute_sql(&format!(
+        "INSERT INTO test_table VALUES {}",
+        values.join(", ")
+    ))
+    .await?;
+    db.checkpoint().await?;
+    let size = db.stats().await?.size;
+    drop(db);
+
+    // All segments but the last one are full, holding 8 pages each.
+    let segment_size = 8 * 512;
+    let segments = size.div_ceil(segment_size) as usize;
+    assert!(segments > 2);
+    for index in 0..segments {
+        let len = std::fs::metadata(SegmentLayout::segment_path(path, index))
+            .unwrap()
+            .len();
+        if index + 1 < segments {
+            assert_eq!(len, segment_size);
+        } else {
+            assert_eq!(len, size - (segments as u64 - 1) * segment_size);
+        }
+    }
+    assert!(!SegmentLayout::segment_path(path, segments).exists());
+    assert!(!path.exists());
+
+    let (db, is_new) = options.clone().open(path).await?;
+    assert!(!is_new);
+    assert_eq!(count(&db).await?, 100);
+    drop(db);
+
+    // The segment size must match.
+    let other = options.clone().with_max_segment_size(16 * 512);
+    let result = other.open(path).await;
+    assert!(matches!(result, Err(Error::IncompatibleFile(_))));
+    // As well as the page size.
+    let other = options.with_page_size(1024);
+    let result = other.open(path).await;
+    assert!(matches!(result, Err(Error::PageSizeMismatch { .. })));
+
+    remove_segments(path);
+    Ok(())
+}
+
+#[test]
+fn test_segment_layout() -> DbResult<()> {
+    let layout = SegmentLayout::new(1024, 4 * 1024 + 1)?;
+    assert_eq!(layout.segment_size(), 4 * 1024);
+    assert_eq!(layout.locate(PageId::FIRST), (0, 0));
+    assert_eq!(layout.locate(PageId::new_u32(4)), (0, 3 * 1024));
+    assert_eq!(layout.locate(PageId::new_u32(5)), (1, 0));
+    assert_eq!(
+        SegmentLayout::segment_path(Path::new("dir/my-db"), 1),
+        Path::new("dir/my-db.0002")
+    );
+    assert!(SegmentLayout::new(1024, 1023).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_backend_contents() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true), (2, 'two', false)")
+        .await?;
+    let contents = std::fs::read(db.path()).unwrap();
+
+    // A copy of the database file may be opened in memory.
+    let backend = MemoryBackend::with_contents(contents);
+    let options = DbOptions::new().with_page_size(1024);
+    let (copy, is_new) = Db::open_with_backend(backend, options).await?;
+    assert!(!is_new);
+    assert_eq!(count(&copy).await?, 2);
+
+    // The page size is checked against the header.
+    let backend = MemoryBackend::with_contents(std::fs::read(db.path()).unwrap());
+    let options = DbOptions::new().with_page_size(2048);
+    let result = Db::open_with_backend(backend, options).await;
+    assert!(matches!(result, Err(Error::PageSizeMismatch { .. })));
+
+    Ok(())
+}
+
+#[cfg(feature = "http")]
+#[tokio::test]
+async fn test_http_backend() -> DbResult<()> {
+    use std::sync::Arc;
+
+    use fdb::io::storage::HttpBackend;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    let db = test_utils::TestDb::new_temp(None).await?;
+    db.execute_sql("INSERT INTO test_table VALUES (1, 'one', true), (2, 'two', false)")
+        .await?;
+    let contents = Arc::new(std::fs::read(db.path()).unwrap());
+
+    // A minimal server, which supports `HEAD` and ranged `GET` requests.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let contents = Arc::clone(&contents);
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stream);
+                let (mut request, mut range) = (String::new(), None);
+                reader.read_line(&mut request).await.unwrap();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    if line.trim_end().is_empty() {
+                        break;
+                    }
+                    if let Some(bytes) = line.trim_end().strip_prefix("Range: bytes=") {
+                        let (start, end) = bytes.split_once('-').unwrap();
+                        range = Some((start.parse().unwrap(), end.parse::<usize>().unwrap()));
+                    }
+                }
+                let response = match range {
+                    None => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                        contents.len()
+                    )
+                    .into_bytes(),
+                    Some((start, end)) => {
+                        let body = &contents[start..=end.min(contents.len() - 1)];
+                        let mut response = format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(body);
+                        response
+                    }
+                };
+                reader.get_mut().write_all(&response).await.unwrap();
+            });
+        }
+    });
+
+    let backend = HttpBackend::new(&format!("http://{addr}/test.db")).unwrap();
+    let options = DbOptions::new().with_page_size(1024).with_read_only(true);
+    let (remote, is_new) = Db::open_with_backend(backend, options).await?;
+    assert!(!is_new);
+    assert_eq!(count(&remote).await?, 2);
+    let insert = remote.execute_sql("INSERT INTO test_table VALUES (3, 'three', true)");
+    assert!(matches!(insert.await, Err(Error::ReadOnly)));
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{FirstPage, PageId},
+    },
+    error::DbResult,
+    exec::{
+        operations::tape::Tape,
+        query::Query,
+        value::Value,
+        values::{SchematizedValues, Values},
+    },
+    Db,
+};
+
+mod test_utils;
+
+async fn temp_seq_count(db: &Db) -> DbResult<usize> {
+    db.pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.temp_seq_page_ids.len()
+        })
+        .await
+}
+
+#[tokio::test]
+async fn test_tape() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(256)).await?;
+    let schema = Object::find(&db, "test_table")
+        .await?
+        .try_into_table()?
+        .schema;
+    let row = |id: i32| -> DbResult<SchematizedValues> {
+        Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("t".repeat(id as usize % 50))),
+        ]))
+        .try_into_schematized(&schema)
+    };
+
+    let tape = Tape::create(&db, "test_tape", schema.clone()).await?;
+    assert_eq!(temp_seq_count(&db).await?, 1);
+    let mut writer = tape.writer(7);
+    for id in 0..100 {
+        writer.push(&db, row(id)?).await?;
+    }
+    assert_eq!(writer.len(), 100);
+    writer.finish(&db).await?;
+    tape.write_all(&db, vec![row(100)?, row(101)?]).await?;
+
+    // Many readers may read the tape at once, in the order it was written.
+    let mut first = tape.reader();
+    let mut second = tape.reader();
+    for id in 0..102 {
+        let expected = row(id)?;
+        for reader in [&mut first, &mut second] {
+            assert_eq!(reader.next(&db).await?, Some(expected.clone()));
+        }
+    }
+    assert!(first.next(&db).await?.is_none());
+
+    tape.destroy(&db).await?;
+    assert_eq!(temp_seq_count(&db).await?, 0);
+    Ok(())
+}
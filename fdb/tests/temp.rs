@@ -0,0 +1,169 @@
+use std::{collections::HashMap, path::Path};
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{BTreeCell, FirstPage, PageId},
+    },
+    error::DbResult,
+    exec::{operations::index::BTree, query, value::Value, values::Values},
+    io::temp,
+    Db, DbOptions,
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+mod test_utils;
+
+/// Returns the temporary sequences and the free page count.
+async fn temp_state(db: &Db) -> DbResult<(Vec<PageId>, u32)> {
+    db.pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            (page.temp_seq_page_ids.clone(), page.header.free_page_count)
+        })
+        .await
+}
+
+async fn create_filled(db: &Db) -> DbResult<query::table::TempTable> {
+    let schema = Object::find(db, "test_table")
+        .await?
+        .try_into_table()?
+        .schema;
+    let temp = query::table::TempTable::create(db, "tmp", schema).await?;
+
+    for id in 0..20 {
+        let values = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("t".repeat(100))),
+            ("bool".into(), Value::Bool(false)),
+        ]));
+        let insert = query::table::Insert::new(temp.table(), values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+
+    let mut count = 0;
+    let select = query::table::Select::new(temp.table());
+    db.execute(select, |_| {
+        count += 1;
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(count, 20);
+
+    Ok(temp)
+}
+
+#[tokio::test]
+async fn test_temp_table_destroy() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+
+    let temp = create_filled(&db).await?;
+    let page_id = temp.table().page_id;
+    assert_eq!(temp_state(&db).await?, (vec![page_id], 0));
+
+    temp.destroy(&db).await?;
+    let (temp_seqs, free_page_count) = temp_state(&db).await?;
+    assert!(temp_seqs.is_empty());
+    assert!(free_page_count > 1);
+
+    // Temporary tables are not part of the schema.
+    assert!(Object::find(&db, "tmp").await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_temp_table_purged_on_open() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+
+    // Simulates a crash, i.e., the temporary table is never destroyed.
+    let temp = create_filled(&db).await?;
+    let page_id = temp.table().page_id;
+    drop(temp);
+    assert_eq!(temp_state(&db).await?, (vec![page_id], 0));
+
+    db.reopen().await?;
+    let (temp_seqs, free_page_count) = temp_state(&db).await?;
+    assert!(temp_seqs.is_empty());
+    assert!(free_page_count > 1);
+
+    // The purged pages are reused.
+    let temp = create_filled(&db).await?;
+    let (_, new_free_page_count) = temp_state(&db).await?;
+    assert!(new_free_page_count < free_page_count);
+    temp.destroy(&db).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_build_purged_on_open() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    assert_eq!(temp_state(&db).await?, (vec![], 0));
+
+    // Simulates a crash while an index is built (see `index::Create`).
+    let tree = BTree::create(db.pager()).await?;
+    temp::register(db.pager(), tree.root()).await?;
+    let cells = (0..500)
+        .map(|id| BTreeCell {
+            key: Value::Int(id),
+            page_id: PageId::new_u32(2),
+            offset: id as u16,
+        })
+        .collect();
+    tree.bulk_load(db.pager(), cells).await?;
+    db.pager().flush_all().await?;
+    assert_eq!(temp_state(&db).await?, (vec![tree.root()], 0));
+
+    db.reopen().await?;
+    let (temp_seqs, free_page_count) = temp_state(&db).await?;
+    assert!(temp_seqs.is_empty());
+    assert!(free_page_count > 1);
+    assert!(db.check_integrity().await?.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_temp_files() -> DbResult<()> {
+    let path = Path::new("ignore/temp-files-test.db");
+    let dir = Path::new("ignore/temp-files-test");
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    // A leftover of a crash, and an unrelated file.
+    std::fs::write(dir.join("temp-files-test.db-7.tmp"), b"leftover").unwrap();
+    std::fs::write(dir.join("other.db-1.tmp"), b"other").unwrap();
+
+    let options = DbOptions::new().with_page_size(1024).with_temp_dir(dir);
+    let (db, _) = options.open(path).await?;
+    assert_eq!(db.temp_files().dir(), dir);
+    assert!(!dir.join("temp-files-test.db-7.tmp").exists());
+    assert!(dir.join("other.db-1.tmp").exists());
+
+    let mut temp = db.temp_files().create().await?;
+    let temp_path = temp.path().to_owned();
+    assert!(temp_path.starts_with(dir));
+    temp.file().write_all(b"spill").await?;
+    temp.file().rewind().await?;
+    let mut contents = Vec::new();
+    temp.file().read_to_end(&mut contents).await?;
+    assert_eq!(contents, b"spill");
+    assert_eq!(db.temp_files().live_count(), 1);
+    drop(temp);
+    assert!(!temp_path.exists());
+    assert_eq!(db.temp_files().live_count(), 0);
+
+    // The files still alive are removed along with the database.
+    let temp = db.temp_files().create().await?;
+    let temp_path = temp.path().to_owned();
+    drop(db);
+    assert!(!temp_path.exists());
+    drop(temp);
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    Ok(())
+}
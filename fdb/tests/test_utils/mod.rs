@@ -1,20 +1,23 @@
 use std::{
+    collections::HashMap,
     ops::{Deref, DerefMut},
     path::PathBuf,
-    sync::atomic::{AtomicU32, Ordering},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
 };
 
 use fdb::{
     catalog::{
         column::Column,
-        object::{Object, ObjectType},
-        page::{HeapPage, SpecificPage},
+        object::Object,
         table_schema::TableSchema,
         ty::{PrimitiveTypeId, TypeId},
     },
     error::DbResult,
-    exec::query,
-    Db,
+    exec::{query, value::Value, values::Values},
+    Db, DbOptions,
 };
 use tokio::fs;
 
@@ -39,19 +42,51 @@ pub fn setup_tracing(level: Option<&str>) {
         .init();
 }
 
-pub struct TestDb(Db, PathBuf);
+pub struct TestDb(Arc<Db>, PathBuf);
 
 impl TestDb {
     /// Creates a new test database in a temporary file.
+    #[allow(dead_code)]
     pub async fn new_temp(page_size: Option<u16>) -> DbResult<Self> {
+        Self::new_temp_with_options(DbOptions {
+            page_size: page_size.unwrap_or(1024),
+            ..DbOptions::default()
+        })
+        .await
+    }
+
+    /// Creates a new test database in a temporary file, using the given
+    /// options.
+    pub async fn new_temp_with_options(options: DbOptions) -> DbResult<Self> {
         let path = test_path().await;
-        let page_size = page_size.unwrap_or(1024);
 
-        let (db, is_new) = Db::open_with_page_size(&path, page_size).await?;
+        let (db, is_new) = Db::open_with_options(&path, options).await?;
         assert!(is_new, "db file must be new");
         define_test_catalog(&db).await?;
 
-        Ok(Self(db, path))
+        Ok(Self(Arc::new(db), path))
+    }
+
+    /// Returns the path of the underlying database file.
+    #[allow(dead_code)]
+    pub fn path(&self) -> &PathBuf {
+        &self.1
+    }
+
+    /// Returns a shared handle to the database (e.g., for background jobs),
+    /// which must be dropped before the database is mutably borrowed.
+    #[allow(dead_code)]
+    pub fn shared(&self) -> Arc<Db> {
+        Arc::clone(&self.0)
+    }
+
+    /// Closes and reopens the database, keeping the underlying file.
+    #[allow(dead_code)]
+    pub async fn reopen(&mut self) -> DbResult<()> {
+        let (db, is_new) = Db::open_with_page_size(&self.1, self.0.page_size()).await?;
+        assert!(!is_new, "db file must exist");
+        self.0 = Arc::new(db);
+        Ok(())
     }
 }
 
@@ -65,7 +100,7 @@ impl Deref for TestDb {
 
 impl DerefMut for TestDb {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        Arc::get_mut(&mut self.0).expect("database is shared")
     }
 }
 
@@ -86,21 +121,27 @@ async fn test_path() -> PathBuf {
 
 // TODO: Remove me.
 pub async fn define_test_catalog(db: &Db) -> DbResult<()> {
-    let test_page_guard = db.pager().alloc(HeapPage::new_seq_first).await?;
-    let test_page = test_page_guard.write().await;
-
-    let object = Object {
-        ty: ObjectType::Table(get_test_schema()),
-        page_id: test_page.id(),
-        name: "test_table".into(),
-    };
-
-    let query = query::object::Create::new(&object);
+    let query = query::object::CreateTable::new("test_table", get_test_schema());
     db.execute(query, |_| Ok::<(), ()>(())).await?.unwrap();
+    Ok(())
+}
 
-    test_page.flush();
-    db.pager().flush_all().await?;
+/// Returns a row of the test table.
+#[allow(dead_code)]
+pub fn row(id: i32, text: impl Into<String>, bool: bool) -> Values {
+    Values::from(HashMap::from([
+        ("id".into(), Value::Int(id)),
+        ("text".into(), Value::Text(text.into())),
+        ("bool".into(), Value::Bool(bool)),
+    ]))
+}
 
+/// Inserts the given rows into the test table, in a single statement.
+#[allow(dead_code)]
+pub async fn fill(db: &Db, rows: impl IntoIterator<Item = Values>) -> DbResult<()> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let insert = query::table::BulkInsert::new(&table, rows);
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
     Ok(())
 }
 
@@ -110,14 +151,23 @@ fn get_test_schema() -> TableSchema {
             Column {
                 ty: TypeId::Primitive(PrimitiveTypeId::Int),
                 name: "id".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
             },
             Column {
                 ty: TypeId::Primitive(PrimitiveTypeId::Text),
                 name: "text".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
             },
             Column {
                 ty: TypeId::Primitive(PrimitiveTypeId::Bool),
                 name: "bool".into(),
+                max_len: None,
+                default: None,
+                not_null: false,
             },
         ],
     }
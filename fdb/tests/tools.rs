@@ -0,0 +1,65 @@
+use fdb::{
+    catalog::object::Object,
+    error::DbResult,
+    exec::{query, value::Value},
+    tools, Db,
+};
+
+mod test_utils;
+
+async fn rows(db: &Db) -> DbResult<Vec<(i32, String)>> {
+    let table = Object::find(db, "test_table").await?.try_into_table()?;
+    let mut rows = Vec::new();
+    let select = query::table::Select::new(&table);
+    db.execute(select, |values| {
+        let (Some(Value::Int(id)), Some(Value::Text(text))) =
+            (values.get("id"), values.get("text"))
+        else {
+            return Err(());
+        };
+        rows.push((*id, text.clone()));
+        Ok(())
+    })
+    .await?
+    .unwrap();
+    rows.sort();
+    Ok(rows)
+}
+
+#[tokio::test]
+async fn test_migrate_page_size() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(1024)).await?;
+    db.execute_sql("CREATE INDEX test_table_id ON test_table (id)")
+        .await?;
+    for id in 0..300 {
+        // Some records span many pages.
+        let len = if id % 50 == 0 { 3000 } else { 20 };
+        let text = "x".repeat(len);
+        db.execute_sql(&format!(
+            "INSERT INTO test_table VALUES ({id}, '{text}', true)"
+        ))
+        .await?;
+    }
+    db.checkpoint().await?;
+    let expected = rows(&db).await?;
+
+    let path = db.path().with_extension("migrated");
+    let count = tools::migrate_page_size(db.path(), &path, 4096).await?;
+    assert_eq!(count, 300);
+    // The destination must not exist.
+    assert!(tools::migrate_page_size(db.path(), &path, 4096)
+        .await
+        .is_err());
+
+    {
+        let migrated = tools::open_read_only(&path).await?;
+        assert_eq!(migrated.page_size(), 4096);
+        assert_eq!(rows(&migrated).await?, expected);
+        Object::find(&migrated, "test_table_id")
+            .await?
+            .try_into_index()?;
+        assert!(migrated.check_integrity().await?.is_ok());
+    }
+    std::fs::remove_file(&path).unwrap();
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use fdb::{
+    catalog::object::Object,
+    error::{DbResult, Error},
+    exec::{activity::PERSIST_INTERVAL, query, value::Value, values::Values},
+    io::temp,
+    sql::{planner::SqlOutput, session::Session},
+};
+use tokio::sync::broadcast::error::TryRecvError;
+
+mod test_utils;
+
+/// Returns an insert of the rows with the given IDs, whose texts are long
+/// enough for the rows to span many pages.
+fn insert(ids: impl IntoIterator<Item = i32>) -> String {
+    let rows: Vec<_> = (ids.into_iter())
+        .map(|id| format!("({id}, '{}', true)", "x".repeat(100)))
+        .collect();
+    format!(
+        "INSERT INTO test_table (id, text, bool) VALUES {}",
+        rows.join(", ")
+    )
+}
+
+async fn ids(session: &mut Session<'_>) -> DbResult<Vec<i32>> {
+    let SqlOutput::Rows { rows, .. } = session.execute("SELECT id FROM test_table").await? else {
+        panic!("expected rows");
+    };
+    let mut ids: Vec<_> = (rows.iter())
+        .map(|row| *row.get("id").unwrap().try_cast_int_ref().unwrap())
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_commit() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    {
+        let mut session = db.session();
+        session.execute("BEGIN").await?;
+        assert!(session.in_transaction());
+        session.execute(&insert(0..50)).await?;
+        // The transaction sees its own changes.
+        assert_eq!(ids(&mut session).await?, Vec::from_iter(0..50));
+        session
+            .execute("DELETE FROM test_table WHERE id >= 40")
+            .await?;
+        session.execute("COMMIT").await?;
+        assert!(!session.in_transaction());
+    }
+
+    db.checkpoint().await?;
+    db.reopen().await?;
+    assert_eq!(ids(&mut db.session()).await?, Vec::from_iter(0..40));
+    assert!(db.check_integrity().await?.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rollback() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    let mut session = db.session();
+    session.execute(&insert(0..50)).await?;
+    let page_count = db.pager().alloc_state().await?.page_count;
+
+    // The file grows, and the existing pages are modified.
+    session.execute("BEGIN TRANSACTION").await?;
+    session.execute(&insert(50..150)).await?;
+    session
+        .execute("DELETE FROM test_table WHERE id < 10")
+        .await?;
+    session
+        .execute("UPDATE test_table SET text = 'y' WHERE id < 30")
+        .await?;
+    assert_eq!(ids(&mut session).await?, Vec::from_iter(10..150));
+    assert!(db.pager().alloc_state().await?.page_count > page_count);
+    session.execute("ROLLBACK").await?;
+
+    assert_eq!(ids(&mut session).await?, Vec::from_iter(0..50));
+    assert_eq!(db.pager().alloc_state().await?.page_count, page_count);
+    assert!(db.check_integrity().await?.is_ok());
+
+    // The released pages are allocated again.
+    session.execute(&insert(50..60)).await?;
+    drop(session);
+    db.checkpoint().await?;
+    db.reopen().await?;
+    assert_eq!(ids(&mut db.session()).await?, Vec::from_iter(0..60));
+    assert!(db.check_integrity().await?.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_statement() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let mut transaction = db.begin().await?;
+    transaction.execute_sql(&insert(0..20)).await?;
+
+    // The first records are updated before the last one fails the update.
+    let pred = |_: &Values| true;
+    let updater = |values: &mut Values| {
+        let value = match *values.get("id").unwrap().try_cast_int_ref().unwrap() {
+            19 => Value::Int(0),
+            _ => Value::Bool(false),
+        };
+        values.set("bool".into(), value);
+    };
+    let update = query::table::Update::new(&table, &pred, &updater);
+    assert!(transaction
+        .execute(update, |_| Ok::<_, ()>(()))
+        .await
+        .is_err());
+
+    // The transaction is still running, without the failed statement's changes.
+    let SqlOutput::Rows { rows, .. } =
+        (transaction.execute_sql("SELECT * FROM test_table")).await?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows.len(), 20);
+    assert!((rows.iter()).all(|row| row.get("bool") == Some(&Value::Bool(true))));
+    transaction.commit().await?;
+
+    assert_eq!(ids(&mut db.session()).await?, Vec::from_iter(0..20));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_savepoints() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut session = db.session();
+    session.execute("BEGIN").await?;
+    session.execute(&insert([1])).await?;
+    session.execute("SAVEPOINT a").await?;
+    session.execute(&insert([2])).await?;
+    session.execute("SAVEPOINT b").await?;
+    session.execute(&insert([3])).await?;
+
+    session.execute("ROLLBACK TO SAVEPOINT a").await?;
+    assert_eq!(ids(&mut session).await?, [1]);
+    // The later savepoints are removed, but the rolled back one is kept.
+    assert!(matches!(
+        session.execute("ROLLBACK TO b").await,
+        Err(Error::ExecError(_))
+    ));
+    session.execute(&insert([4])).await?;
+    session.execute("ROLLBACK TO a").await?;
+    assert_eq!(ids(&mut session).await?, [1]);
+
+    // Released savepoints keep their changes.
+    session.execute(&insert([5])).await?;
+    session.execute("SAVEPOINT c").await?;
+    session.execute(&insert([6])).await?;
+    session.execute("RELEASE a").await?;
+    assert!(session.execute("ROLLBACK TO c").await.is_err());
+    session.execute("COMMIT").await?;
+    assert_eq!(ids(&mut session).await?, [1, 5, 6]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_temp_seqs() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut session = db.session();
+    session.execute("BEGIN").await?;
+    session.execute(&insert(0..10)).await?;
+    let pending_writes = db.pager().pending_write_count();
+    assert!(pending_writes > 0);
+
+    // Temporary sequences (e.g., sort tapes) don't write the transaction's
+    // pages before it ends.
+    let page_id = temp::alloc_seq(db.pager()).await?;
+    temp::release_seq(db.pager(), page_id).await?;
+    assert!(db.pager().pending_write_count() >= pending_writes);
+    session.execute("ROLLBACK").await?;
+
+    assert!(ids(&mut session).await?.is_empty());
+    assert_eq!(temp::seq_count(db.pager()).await?, 0);
+    assert!(db.check_integrity().await?.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rollback_activity() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let mut session = db.session();
+    session.execute(&insert(0..100)).await?;
+
+    // Enough changes for the activity deltas to be persisted, were the
+    // statements not in a transaction.
+    session.execute("BEGIN").await?;
+    let rounds = PERSIST_INTERVAL / 100 + 1;
+    for _ in 0..rounds {
+        session
+            .execute("UPDATE test_table SET bool = false")
+            .await?;
+    }
+    session.execute("ROLLBACK").await?;
+
+    // The activity counters aren't rolled back.
+    let activity = db.activity().table_activity(db.pager(), &table).await?;
+    assert_eq!(activity.updates, rounds * 100);
+    assert!(db.check_integrity().await?.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rollback_create_table() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut session = db.session();
+    session.execute("BEGIN").await?;
+    session.execute("CREATE TABLE other (id int)").await?;
+    session.execute("INSERT INTO other (id) VALUES (1)").await?;
+    session.execute("ROLLBACK").await?;
+
+    assert!(session.execute("SELECT id FROM other").await.is_err());
+    session.execute("CREATE TABLE other (id int)").await?;
+    let SqlOutput::Rows { rows, .. } = session.execute("SELECT id FROM other").await? else {
+        panic!("expected rows");
+    };
+    assert!(rows.is_empty());
+    assert!(db.check_integrity().await?.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_notifications() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut receiver = db.subscribe("test_table");
+    let mut session = db.session();
+
+    // The changes are only published once the transaction commits.
+    session.execute("BEGIN").await?;
+    session.execute(&insert([1])).await?;
+    session.execute("SAVEPOINT a").await?;
+    session.execute(&insert([2])).await?;
+    session.execute("ROLLBACK TO a").await?;
+    assert_eq!(receiver.try_recv().unwrap_err(), TryRecvError::Empty);
+    session.execute("COMMIT").await?;
+    assert!(receiver.try_recv().is_ok());
+    assert_eq!(receiver.try_recv().unwrap_err(), TryRecvError::Empty);
+
+    session.execute("BEGIN").await?;
+    session.execute(&insert([3])).await?;
+    session.execute("ROLLBACK").await?;
+    assert_eq!(receiver.try_recv().unwrap_err(), TryRecvError::Empty);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dropped_transaction() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    {
+        let mut session = db.session();
+        session.execute("BEGIN").await?;
+        session.execute(&insert(0..10)).await?;
+    }
+    // Waits until the transaction is rolled back in the background.
+    assert!(ids(&mut db.session()).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transaction_errors() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let mut session = db.session();
+    for sql in [
+        "COMMIT",
+        "ROLLBACK",
+        "SAVEPOINT a",
+        "ROLLBACK TO a",
+        "RELEASE a",
+    ] {
+        let result = session.execute(sql).await;
+        assert!(matches!(result, Err(Error::ExecError(_))), "{sql}");
+    }
+    // Outside of sessions, transaction statements aren't supported.
+    assert!(matches!(
+        db.execute_sql("BEGIN").await,
+        Err(Error::ExecError(_))
+    ));
+
+    session.execute("BEGIN").await?;
+    assert!(matches!(
+        session.execute("BEGIN").await,
+        Err(Error::ExecError(_))
+    ));
+    assert!(matches!(
+        session.execute("RELEASE a").await,
+        Err(Error::ExecError(_))
+    ));
+    session.execute("COMMIT").await?;
+
+    Ok(())
+}
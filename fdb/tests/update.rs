@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use fdb::{
-    catalog::object::Object,
+    catalog::{object::Object, page::HeapPage},
     error::DbResult,
     exec::{query, value::Value, values::Values},
 };
@@ -127,3 +127,75 @@ async fn test_update_bigger() -> DbResult<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_update_reclaim_padding() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    let values = &mut [
+        Values::from(HashMap::from([
+            ("id".into(), Value::Int(1)),
+            ("text".into(), Value::Text("hello, world!".into())),
+            ("bool".into(), Value::Bool(true)),
+        ])),
+        Values::from(HashMap::from([
+            ("id".into(), Value::Int(2)),
+            ("text".into(), Value::Text("olá, mundo!".into())),
+            ("bool".into(), Value::Bool(false)),
+        ])),
+        Values::from(HashMap::from([
+            ("id".into(), Value::Int(3)),
+            ("text".into(), Value::Text("woo!".into())),
+            ("bool".into(), Value::Bool(true)),
+        ])),
+    ];
+
+    {
+        for value in values.iter() {
+            let ins = query::table::Insert::new(&table, value.clone());
+            db.execute(ins, |_| Ok::<_, ()>(())).await?.unwrap();
+        }
+    }
+
+    let free_offset = || async {
+        db.pager()
+            .read_with(table.page_id, |page: &HeapPage| page.offset())
+            .await
+    };
+    let offset_before = free_offset().await?;
+
+    {
+        let pred = |val: &Values| *val.get("id").unwrap().try_cast_int_ref().unwrap() != 3;
+        let updater = |val: &mut Values| val.set("text".into(), Value::Text("olá!".into()));
+        let upd = query::table::Update::new(&table, &pred, &updater)
+            .with_pad_policy(query::table::PadPolicy::ReclaimAbove(0));
+        db.execute(upd, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+
+    // "hello, world!" (13 bytes) and "olá, mundo!" (12 bytes) became "olá!" (5
+    // bytes).
+    assert_eq!(free_offset().await?, offset_before - (13 - 5) - (12 - 5));
+
+    {
+        values[0].set("text".into(), Value::Text("olá!".into()));
+        values[1].set("text".into(), Value::Text("olá!".into()));
+        let mut expected_rows: HashMap<_, _> = values
+            .iter_mut()
+            .map(|value| (*value.get("id").unwrap().try_cast_int_ref().unwrap(), value))
+            .collect();
+        let second_select = query::table::Select::new(&table);
+        db.execute(second_select, |row| {
+            let expected = expected_rows
+                .remove(row.get("id").unwrap().try_cast_int_ref().unwrap())
+                .unwrap();
+            assert_eq!(&row, expected);
+            Ok::<_, ()>(())
+        })
+        .await?
+        .unwrap();
+        assert_eq!(expected_rows.len(), 0);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use fdb::{
+    catalog::{
+        object::{Object, TableObject},
+        page::{FirstPage, HeapPage, PageId},
+    },
+    error::DbResult,
+    exec::{
+        activity::VacuumThreshold,
+        auto_vacuum::{AutoVacuum, AutoVacuumConfig},
+        query::{self, table::VacuumStats},
+        value::Value,
+        values::Values,
+    },
+    Db,
+};
+use tokio::time;
+
+mod test_utils;
+
+fn row(id: i32) -> Values {
+    test_utils::row(id, format!("text-{id}"), true)
+}
+
+async fn delete_ids(db: &Db, table: &TableObject, pred: fn(i32) -> bool) -> DbResult<()> {
+    let pred = move |values: &Values| pred(*values.get("id").unwrap().try_cast_int_ref().unwrap());
+    let delete = query::table::Delete::new(table, &pred);
+    db.execute(delete, |_| Ok::<_, ()>(())).await?.unwrap();
+    Ok(())
+}
+
+async fn select_ids(db: &Db, table: &TableObject) -> DbResult<Vec<i32>> {
+    let mut ids = Vec::new();
+    let select = query::table::Select::new(table);
+    db.execute(select, |row| {
+        ids.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(ids)
+}
+
+/// Returns the table's sequence `page_count` and the database free page count.
+async fn page_counts(db: &Db, table: &TableObject) -> DbResult<(u32, u32)> {
+    let page_count = db
+        .pager()
+        .read_with(table.page_id, |page: &HeapPage| {
+            page.header.seq_header.as_ref().unwrap().page_count
+        })
+        .await?;
+    let free_page_count = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| {
+            page.header.free_page_count
+        })
+        .await?;
+    Ok((page_count, free_page_count))
+}
+
+async fn vacuum(db: &Db, vacuum: query::table::Vacuum<'_>) -> DbResult<VacuumStats> {
+    let mut stats = None;
+    db.execute(vacuum, |s| {
+        stats = Some(s);
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    Ok(stats.unwrap())
+}
+
+#[tokio::test]
+async fn test_vacuum() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let create = query::index::Create::new("test_table_by_id", "test_table", "id");
+    db.execute(create, |_| Ok::<_, ()>(())).await?.unwrap();
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    test_utils::fill(&db, (0..200).map(row)).await?;
+    // Empties the middle pages and leaves holes in the others.
+    delete_ids(&db, &table, |id| (40..160).contains(&id) || id % 3 == 0).await?;
+    let expected: Vec<_> = (0..200)
+        .filter(|id| !(40..160).contains(id) && id % 3 != 0)
+        .collect();
+    let (page_count, free_page_count) = page_counts(&db, &table).await?;
+
+    let stats = vacuum(&db, query::table::Vacuum::new(&table)).await?;
+    assert_eq!(stats.records_removed, 200 - expected.len() as u64);
+    assert_eq!(stats.pages_scanned, page_count);
+    assert!(stats.pages_released > 0);
+    assert!(stats.bytes_reclaimed > 0);
+    assert_eq!(stats.resume_at, None);
+    assert_eq!(
+        page_counts(&db, &table).await?,
+        (
+            page_count - stats.pages_released,
+            free_page_count + stats.pages_released
+        )
+    );
+
+    assert_eq!(select_ids(&db, &table).await?, expected);
+    let activity = db.activity().table_activity(db.pager(), &table).await?;
+    assert_eq!(activity.dead_rows, 0);
+    assert_eq!(activity.live_rows, expected.len() as u64);
+
+    // The index is kept in sync with the moved records.
+    let mut found = Vec::new();
+    let select = query::table::Select::with_filter(&table, "id", Value::Int(100)..);
+    db.execute(select, |row| {
+        found.push(*row.get("id").unwrap().try_cast_int_ref().unwrap());
+        Ok::<_, ()>(())
+    })
+    .await?
+    .unwrap();
+    assert_eq!(
+        found,
+        expected
+            .iter()
+            .copied()
+            .filter(|id| *id >= 100)
+            .collect::<Vec<_>>()
+    );
+
+    // A second vacuum has nothing to do.
+    let stats = vacuum(&db, query::table::Vacuum::new(&table)).await?;
+    assert_eq!((stats.records_removed, stats.bytes_reclaimed), (0, 0));
+
+    db.reopen().await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+    let insert = query::table::Insert::new(&table, row(1000));
+    db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    let mut expected = expected;
+    expected.push(1000);
+    assert_eq!(select_ids(&db, &table).await?, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_incremental_vacuum() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    test_utils::fill(&db, (0..200).map(row)).await?;
+    delete_ids(&db, &table, |id| id % 2 == 0).await?;
+    let (page_count, _) = page_counts(&db, &table).await?;
+
+    let mut rounds = 0;
+    let mut removed = 0;
+    let mut start = 0;
+    loop {
+        let round = query::table::Vacuum::new(&table)
+            .with_start(start)
+            .with_page_budget(2);
+        let stats = vacuum(&db, round).await?;
+        assert!(stats.pages_scanned <= 2);
+        rounds += 1;
+        removed += stats.records_removed;
+        match stats.resume_at {
+            Some(position) => start = position,
+            None => break,
+        }
+    }
+    assert_eq!(rounds, page_count.div_ceil(2));
+    assert_eq!(removed, 100);
+    assert_eq!(
+        select_ids(&db, &table).await?,
+        (0..200).filter(|id| id % 2 != 0).collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auto_vacuum() -> DbResult<()> {
+    let db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    test_utils::fill(&db, (0..200).map(row)).await?;
+    delete_ids(&db, &table, |id| id < 150).await?;
+    let mut select = query::table::Select::new(&table);
+    let (record_id, _) = select.next_with_id(&db).await?.unwrap();
+    drop(select);
+
+    time::pause();
+    let config = AutoVacuumConfig {
+        interval: Duration::from_secs(1),
+        page_budget: 1,
+        threshold: VacuumThreshold {
+            min_dead_rows: 10,
+            scale_factor: 0.5,
+        },
+    };
+    let auto_vacuum = AutoVacuum::spawn(db.shared(), config);
+    // The rounds are run as the clock advances, but their writes take real
+    // time.
+    let start = std::time::Instant::now();
+    while (db.activity().table_activity(db.pager(), &table).await?).dead_rows > 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "table wasn't vacuumed"
+        );
+        time::sleep(Duration::from_secs(1)).await;
+    }
+    auto_vacuum.stop().await;
+    assert_eq!(
+        select_ids(&db, &table).await?,
+        (150..200).collect::<Vec<_>>()
+    );
+
+    // The records were moved, hence the previous IDs are rejected.
+    let delete = query::table::DeleteById::new(&table, record_id);
+    assert!(db.execute(delete, |_| Ok::<_, ()>(())).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use fdb::{
+    catalog::{
+        object::Object,
+        page::{FirstPage, PageId, MAX_HOT_PAGES},
+    },
+    error::DbResult,
+    exec::{query, value::Value, values::Values},
+};
+
+mod test_utils;
+
+#[tokio::test]
+async fn test_warmup_prefetches_hot_pages() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(None).await?;
+    let table = Object::find(&db, "test_table").await?.try_into_table()?;
+
+    for id in 0..10 {
+        let values = Values::from(HashMap::from([
+            ("id".into(), Value::Int(id)),
+            ("text".into(), Value::Text("hot".into())),
+            ("bool".into(), Value::Bool(true)),
+        ]));
+        let insert = query::table::Insert::new(&table, values);
+        db.execute(insert, |_| Ok::<_, ()>(())).await?.unwrap();
+    }
+
+    db.checkpoint().await?;
+    let hot_page_ids = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| page.hot_page_ids.clone())
+        .await?;
+    // The schema page and the table's first page are the most accessed ones.
+    assert!(hot_page_ids.contains(&PageId::new_u32(2)));
+    assert!(hot_page_ids.contains(&table.page_id));
+    assert!(!hot_page_ids.contains(&PageId::FIRST));
+
+    db.reopen().await?;
+    for page_id in &hot_page_ids {
+        assert!(db.pager().is_cached(*page_id), "{page_id:?} not prefetched");
+    }
+
+    // The list survives reopening.
+    let reopened_hot_page_ids = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| page.hot_page_ids.clone())
+        .await?;
+    assert_eq!(reopened_hot_page_ids, hot_page_ids);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_warmup_many_hot_pages() -> DbResult<()> {
+    let mut db = test_utils::TestDb::new_temp(Some(512)).await?;
+    let rows = (0..400).map(|id| test_utils::row(id, "t".repeat(50), true));
+    test_utils::fill(&db, rows).await?;
+
+    // The list is full, and survives reopening. Files written by previous
+    // versions may hold as many hot pages.
+    db.checkpoint().await?;
+    let hot_page_ids = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| page.hot_page_ids.clone())
+        .await?;
+    assert_eq!(hot_page_ids.len(), MAX_HOT_PAGES);
+    assert_eq!(hot_page_ids.len(), 32);
+    db.reopen().await?;
+    let reopened_hot_page_ids = db
+        .pager()
+        .read_with(PageId::FIRST, |page: &FirstPage| page.hot_page_ids.clone())
+        .await?;
+    assert_eq!(reopened_hot_page_ids, hot_page_ids);
+
+    Ok(())
+}